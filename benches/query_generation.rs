@@ -0,0 +1,63 @@
+//! `generate-queries` throughput on one worker thread against every core: the chunks are
+//! generated and serialized in parallel and written in order, here to a sink so the disk isn't
+//! timed.
+//!
+//! Needs no server: `cargo bench --bench query_generation`.
+
+use benchmark::queries_repository::{
+    AccessPattern, AlgorithmQuerySelection, Flavour, QueryCoverageProfile, UsersQueriesRepository,
+};
+use benchmark::query_generation::{write_queries, CHUNK_SIZE};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+const QUERIES: usize = 100_000;
+
+fn generation(c: &mut Criterion) {
+    let repository = Arc::new(UsersQueriesRepository::new(
+        10_000,
+        121_716,
+        Flavour::FalkorDB,
+        AlgorithmQuerySelection::default(),
+        QueryCoverageProfile::default(),
+        AccessPattern::default(),
+        false,
+    ));
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    let rt = Runtime::new().expect("tokio runtime");
+
+    let mut group = c.benchmark_group("write_queries_100k");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(QUERIES as u64));
+    let mut workers = vec![1, cores];
+    workers.dedup();
+    for workers in workers {
+        group.bench_with_input(
+            BenchmarkId::new("workers", workers),
+            &workers,
+            |b, &workers| {
+                b.to_async(&rt).iter(|| {
+                    let repository = Arc::clone(&repository);
+                    async move {
+                        write_queries(
+                            repository,
+                            QUERIES,
+                            0.2,
+                            0,
+                            workers,
+                            CHUNK_SIZE,
+                            &mut tokio::io::sink(),
+                        )
+                        .await
+                        .unwrap()
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, generation);
+criterion_main!(benches);
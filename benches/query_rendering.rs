@@ -0,0 +1,80 @@
+//! Client-side cost of turning one queued query into what the driver sends: the `PreparedQuery`
+//! path `run` used to take (the pre-rendered `CYPHER ...` text for FalkorDB, a clone of the Bolt
+//! struct for Neo4j and Memgraph) against a `CompactQuery`'s, with and without its pre-rendered
+//! text.
+//!
+//! Needs no server: `cargo bench --bench query_rendering`.
+
+use benchmark::compact_query::{compact_queries, QueryParams};
+use benchmark::queries_repository::{
+    AccessPattern, AlgorithmQuerySelection, Flavour, PreparedQuery, QueryCoverageProfile,
+    UsersQueriesRepository,
+};
+use benchmark::scenario::Vendor;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const QUERIES: usize = 10_000;
+
+fn queries() -> Vec<PreparedQuery> {
+    UsersQueriesRepository::new(
+        10_000,
+        121_716,
+        Flavour::FalkorDB,
+        AlgorithmQuerySelection::default(),
+        QueryCoverageProfile::default(),
+        AccessPattern::default(),
+        false,
+    )
+    .random_queries(QUERIES, 0.2)
+    .collect()
+}
+
+fn rendering(c: &mut Criterion) {
+    let prepared = queries();
+    let compact = compact_queries(queries(), Vendor::Neo4j, QueryParams::Parameterized).unwrap();
+    let compact_falkor =
+        compact_queries(queries(), Vendor::Falkor, QueryParams::Parameterized).unwrap();
+
+    let mut group = c.benchmark_group("render_10k");
+    group.bench_function("prepared_bolt", |b| {
+        b.iter(|| {
+            for query in &prepared {
+                black_box(
+                    neo4rs::query(query.bolt.query.as_str()).params(query.bolt.clone().params),
+                );
+            }
+        })
+    });
+    group.bench_function("compact_bolt", |b| {
+        b.iter(|| {
+            for query in &compact {
+                black_box(query.statement().bolt());
+            }
+        })
+    });
+    group.bench_function("prepared_cypher", |b| {
+        b.iter(|| {
+            for query in &prepared {
+                black_box(query.cypher.as_str());
+            }
+        })
+    });
+    group.bench_function("compact_cypher_rendered", |b| {
+        b.iter(|| {
+            for query in &compact_falkor {
+                black_box(query.statement().cypher().unwrap());
+            }
+        })
+    });
+    group.bench_function("compact_cypher_from_template", |b| {
+        b.iter(|| {
+            for query in &compact {
+                black_box(query.statement().cypher().unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, rendering);
+criterion_main!(benches);
@@ -1,22 +1,52 @@
-use benchmark::error::BenchmarkError::OtherError;
-use benchmark::error::BenchmarkResult;
-use benchmark::scenario::{Name, Size, Spec, Vendor};
+use crate::distributed::{self, CLIENT_DIR_PREFIX};
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::inspect::GraphSize;
+use crate::metrics_collector::{
+    percentile_us, LatencyHistograms, RunHistograms, HISTOGRAMS_FILE, QUERY_HIST_PCTS,
+};
+use crate::process_monitor::ServerRestarts;
+use crate::queries_repository::QueryGroup;
+use crate::resource_limits::ServerMemoryReport;
+use crate::resource_series::{ResourceSeries, ResourceSummary};
+use crate::result_size::ResultSize;
+use crate::scenario::{Name, Size, Spec, Vendor};
+use crate::significance::{self, Comparison, BOOTSTRAP_RESAMPLES};
+use histogram::Histogram;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use tracing::warn;
 
 #[derive(Debug, Deserialize, Clone)]
 #[allow(dead_code)]
 struct RunResultsMeta {
     vendor: String,
     dataset: String,
+    // Missing in results of queries files generated before the ratio was recorded.
+    #[serde(default)]
+    write_ratio: Option<f64>,
     queries_file: String,
     queries_count: usize,
     parallel: usize,
     mps: usize,
     simulate_ms: Option<usize>,
     endpoint: Option<String>,
+    // Missing in results written before restarts were tracked.
+    #[serde(default)]
+    server_restarts: Option<ServerRestarts>,
+    #[serde(default)]
+    server_memory: Option<ServerMemoryReport>,
+    // Missing in results written before the graph size was recorded.
+    #[serde(default)]
+    graph_size: Option<GraphSize>,
+    // Missing in results written before `--dispatch` existed, which all dispatched shared.
+    #[serde(default)]
+    dispatch: Option<String>,
+    // The machine `run` ran on; missing in results written before it was recorded.
+    #[serde(default)]
+    platform: Option<String>,
     started_at_epoch_secs: u64,
     finished_at_epoch_secs: u64,
     elapsed_ms: u128,
@@ -33,6 +63,25 @@ struct UiLatency {
     p99: String,
 }
 
+#[derive(Debug, Serialize)]
+struct UiResultSizeLatency {
+    #[serde(flatten)]
+    latency: UiLatency,
+    // The queries in the bin.
+    queries: Vec<String>,
+}
+
+// Beyond P99, where the Prometheus buckets can't resolve anything; only from the in-process
+// HDR histograms.
+#[derive(Debug, Serialize)]
+struct UiTailLatency {
+    #[serde(rename = "p99.9")]
+    p99_9: String,
+    #[serde(rename = "p99.99")]
+    p99_99: String,
+    max: String,
+}
+
 #[derive(Debug, Serialize)]
 struct UiLatencyHistogram {
     // Bucket upper bounds (in milliseconds) and cumulative counts.
@@ -49,6 +98,9 @@ struct UiOpsBreakdown {
     by_query: BTreeMap<String, u64>,
     #[serde(rename = "by-spawn")]
     by_spawn: BTreeMap<String, u64>,
+    // oltp / analytics / algorithm, summed over `by-query`.
+    #[serde(rename = "by-group")]
+    by_group: BTreeMap<String, u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -63,6 +115,9 @@ struct UiSpawnStats {
     max_min_ratio: f64,
     // Coefficient of variation (stddev / mean) for per-spawn totals.
     cv: f64,
+    // The run's `--dispatch`, which decides how even the totals can be.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dispatch: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -75,6 +130,26 @@ struct UiTelemetryBreakdown {
     report_ms: f64,
 }
 
+// JVM garbage collection during the run (Neo4j only). `pause-share` is the part of the run's
+// wall time spent collecting, to tell GC-driven latency from query cost.
+#[derive(Debug, Serialize)]
+struct UiGcPauses {
+    collections: u64,
+    #[serde(rename = "pause-ms")]
+    pause_ms: f64,
+    #[serde(rename = "pause-share")]
+    pause_share: f64,
+    #[serde(rename = "by-collector")]
+    by_collector: BTreeMap<String, UiGcCollector>,
+}
+
+#[derive(Debug, Serialize)]
+struct UiGcCollector {
+    collections: u64,
+    #[serde(rename = "pause-ms")]
+    pause_ms: f64,
+}
+
 #[derive(Debug, Serialize)]
 struct UiResult {
     #[serde(rename = "deadline-offset")]
@@ -82,6 +157,35 @@ struct UiResult {
     #[serde(rename = "actual-messages-per-second")]
     actual_messages_per_second: f64,
     latency: UiLatency,
+    // The same percentiles as numbers (ms), for the spread across repetitions.
+    #[serde(skip)]
+    latency_ms: [f64; 3],
+    // Missing in runs before the tail gauges were exported.
+    #[serde(rename = "tail-latency", skip_serializing_if = "Option::is_none")]
+    tail_latency: Option<UiTailLatency>,
+    // P99.9/P99.99/max as numbers (ms), likewise.
+    #[serde(skip)]
+    tail_ms: Option<[f64; 3]>,
+    // Execution time reported by the server itself (FalkorDB only); the gap to `latency` is
+    // network and client overhead.
+    #[serde(rename = "server-latency", skip_serializing_if = "Option::is_none")]
+    server_latency: Option<UiLatency>,
+    // Missing in runs before per-group latencies were exported.
+    #[serde(
+        rename = "latency-by-group",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    latency_by_group: BTreeMap<String, UiLatency>,
+    // `read` and `write`; missing in runs before per-type latencies were exported.
+    #[serde(rename = "latency-by-type", skip_serializing_if = "BTreeMap::is_empty")]
+    latency_by_type: BTreeMap<String, UiLatency>,
+    // By the mean rows of each query (see `result_size`); only from the HDR histograms, so
+    // missing in runs before result rows were counted.
+    #[serde(
+        rename = "latency-by-result-size",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    latency_by_result_size: BTreeMap<String, UiResultSizeLatency>,
     #[serde(rename = "avg-latency-ms")]
     avg_latency_ms: f64,
     #[serde(rename = "latency-histogram")]
@@ -92,8 +196,9 @@ struct UiResult {
     cpu_usage: f64,
     #[serde(rename = "ram-usage")]
     ram_usage: String,
-    // Memgraph-only today: base dataset memory estimate from formula
-    // StorageRAMUsage = NumberOfVertices×212B + NumberOfEdges×162B
+    // Dataset footprint: Memgraph's formula estimate
+    // (StorageRAMUsage = NumberOfVertices×212B + NumberOfEdges×162B), Neo4j's store size or
+    // estimate, FalkorDB's GRAPH.MEMORY right after load.
     #[serde(rename = "base-dataset-bytes", skip_serializing_if = "Option::is_none")]
     base_dataset_bytes: Option<u64>,
     errors: u64,
@@ -114,6 +219,13 @@ struct UiResult {
         skip_serializing_if = "BTreeMap::is_empty"
     )]
     telemetry_for_type: BTreeMap<String, UiTelemetryBreakdown>,
+    // Missing for other vendors and in runs before GC was tracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gc: Option<UiGcPauses>,
+    // Average and max CPU/memory over the run from resources.csv; missing in runs before it
+    // was written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<ResourceSummary>,
 }
 
 #[derive(Debug, Serialize)]
@@ -129,7 +241,113 @@ struct UiRun {
     relationships: u64,
     #[serde(rename = "started-at-epoch-secs")]
     started_at_epoch_secs: u64,
+    // Set when the numbers can't be trusted as-is, e.g. the server restarted mid-run.
+    #[serde(
+        rename = "potentially-invalid",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    potentially_invalid: bool,
+    #[serde(rename = "invalid-reasons", skip_serializing_if = "Vec::is_empty")]
+    invalid_reasons: Vec<String>,
     result: UiResult,
+    // Spread across `<vendor>/run-<n>/` repetitions; `result` is the representative one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repetitions: Option<UiRepetitions>,
+}
+
+#[derive(Debug, Serialize)]
+struct UiSpread {
+    median: f64,
+    min: f64,
+    max: f64,
+    stddev: f64,
+}
+
+impl UiSpread {
+    fn new(values: &[f64]) -> Self {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let n = sorted.len();
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        } else {
+            sorted[n / 2]
+        };
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n as f64;
+        UiSpread {
+            median,
+            min: sorted[0],
+            max: sorted[n - 1],
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct UiRepetitions {
+    count: usize,
+    // The repetition with the median P99, whose numbers are shown in `result`.
+    representative: String,
+    #[serde(rename = "p50-ms")]
+    p50_ms: UiSpread,
+    #[serde(rename = "p95-ms")]
+    p95_ms: UiSpread,
+    #[serde(rename = "p99-ms")]
+    p99_ms: UiSpread,
+    // Only when every repetition has its tail latencies.
+    #[serde(rename = "p99.9-ms", skip_serializing_if = "Option::is_none")]
+    p99_9_ms: Option<UiSpread>,
+    #[serde(rename = "p99.99-ms", skip_serializing_if = "Option::is_none")]
+    p99_99_ms: Option<UiSpread>,
+    #[serde(rename = "max-ms", skip_serializing_if = "Option::is_none")]
+    max_ms: Option<UiSpread>,
+    #[serde(rename = "avg-latency-ms")]
+    avg_latency_ms: UiSpread,
+    #[serde(rename = "actual-messages-per-second")]
+    actual_messages_per_second: UiSpread,
+    // P50/P95/P99 of every repetition, for the significance comparison.
+    #[serde(skip)]
+    latency_ms: Vec<[f64; 3]>,
+}
+
+// Bootstrap 95% intervals of the baseline's and the other vendor's median percentiles across
+// repetitions; a verdict is only given when the two intervals don't overlap.
+#[derive(Debug, Serialize)]
+struct UiSignificance {
+    baseline: String,
+    other: String,
+    resamples: usize,
+    #[serde(rename = "p50-ms")]
+    p50_ms: Comparison,
+    #[serde(rename = "p95-ms")]
+    p95_ms: Comparison,
+    #[serde(rename = "p99-ms")]
+    p99_ms: Comparison,
+}
+
+impl UiSignificance {
+    /// `None` unless both runs were repeated at least twice.
+    fn new(
+        baseline: &UiRun,
+        other: &UiRun,
+    ) -> Option<Self> {
+        let samples = |run: &UiRun, pct: usize| -> Vec<f64> {
+            run.repetitions
+                .as_ref()
+                .map(|reps| reps.latency_ms.iter().map(|l| l[pct]).collect())
+                .unwrap_or_default()
+        };
+        let compare = |pct| significance::compare(&samples(baseline, pct), &samples(other, pct));
+        Some(UiSignificance {
+            baseline: baseline.vendor.clone(),
+            other: other.vendor.clone(),
+            resamples: BOOTSTRAP_RESAMPLES,
+            p50_ms: compare(0)?,
+            p95_ms: compare(1)?,
+            p99_ms: compare(2)?,
+        })
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -140,6 +358,8 @@ struct UiSummary {
     unrealstic: Vec<serde_json::Value>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     platforms: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    significance: Option<UiSignificance>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -156,10 +376,15 @@ struct HistogramData {
     sum: f64,
 }
 
+/// One `<vendor>_vs_<baseline>.json` per vendor compared with `baseline`, e.g.
+/// `neo4j_vs_falkordb.json`. Without results for the baseline, or with nothing to compare it to,
+/// each vendor gets a `<vendor>.json` summary of its own. Returns the summaries written.
 pub fn aggregate_results(
     results_dir: &str,
     out_dir: &str,
-) -> BenchmarkResult<()> {
+    graph_size_tolerance: f64,
+    baseline: Vendor,
+) -> BenchmarkResult<Vec<PathBuf>> {
     let results_dir = PathBuf::from(results_dir);
     if !results_dir.exists() {
         return Err(OtherError(format!(
@@ -177,41 +402,223 @@ pub fn aggregate_results(
         ))
     })?;
 
-    // Required baseline vendor
-    let falkor = load_vendor(&results_dir, Vendor::Falkor)?;
-
-    // neo4j vs falkor
-    if let Ok(neo4j) = load_vendor(&results_dir, Vendor::Neo4j) {
-        let summary = make_summary(&[falkor.clone(), neo4j])?;
-        let out_path = out_dir.join("neo4j_vs_falkordb.json");
-        write_summary(&out_path, &summary)?;
+    let mut loaded = Vec::new();
+    for (vendor, vendor_dir) in vendor_dirs(&results_dir)? {
+        match load_vendor(&vendor_dir, vendor) {
+            Ok(runs) => loaded.push((vendor, runs)),
+            Err(e) => warn!("Skipping the {} results: {}", vendor, e),
+        }
+    }
+    if loaded.is_empty() {
+        return Err(OtherError(format!(
+            "No falkor, neo4j or memgraph results in {}",
+            results_dir.display()
+        )));
+    }
+    let present: Vec<Vendor> = loaded.iter().map(|(vendor, _)| *vendor).collect();
+    if present.len() > 1 && !present.contains(&baseline) {
+        warn!(
+            "No {} results in {} to compare against; writing a summary per vendor (see --baseline)",
+            baseline,
+            results_dir.display()
+        );
     }
 
-    // memgraph vs falkor
-    if let Ok(memgraph) = load_vendor(&results_dir, Vendor::Memgraph) {
-        let summary = make_summary(&[falkor, memgraph])?;
-        let out_path = out_dir.join("memgraph_vs_falkordb.json");
-        write_summary(&out_path, &summary)?;
+    let mut written = Vec::new();
+    for (file_name, vendors) in summary_plan(&present, baseline) {
+        let runs: Vec<Vec<VendorArtifacts>> = vendors
+            .iter()
+            .filter_map(|vendor| {
+                loaded
+                    .iter()
+                    .find(|(loaded, _)| loaded == vendor)
+                    .map(|(_, runs)| runs.clone())
+            })
+            .collect();
+        let summary = make_summary(&runs, graph_size_tolerance)?;
+        let path = out_dir.join(file_name);
+        write_summary(&path, &summary)?;
+        written.push(path);
     }
 
-    Ok(())
+    Ok(written)
+}
+
+/// The summaries to write for the vendors with results, as file name and vendors with the
+/// baseline first.
+fn summary_plan(
+    present: &[Vendor],
+    baseline: Vendor,
+) -> Vec<(String, Vec<Vendor>)> {
+    let others: Vec<Vendor> = present
+        .iter()
+        .copied()
+        .filter(|vendor| *vendor != baseline)
+        .collect();
+    if present.contains(&baseline) && !others.is_empty() {
+        others
+            .into_iter()
+            .map(|other| {
+                (
+                    format!("{}_vs_{}.json", vendor_id(other), vendor_id(baseline)),
+                    vec![baseline, other],
+                )
+            })
+            .collect()
+    } else {
+        present
+            .iter()
+            .map(|vendor| (format!("{}.json", vendor_id(*vendor)), vec![*vendor]))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
 struct VendorArtifacts {
     vendor: Vendor,
+    // `run-<n>` for one of several repetitions; `None` for a single run.
+    repetition: Option<String>,
     meta: RunResultsMeta,
     metrics_text: String,
+    // From histograms.json; missing in results written before the histograms were kept.
+    histograms: Option<LatencyHistograms>,
+    // From resources.csv, likewise.
+    resources: Option<ResourceSeries>,
 }
 
+/// How deep below the results directory runs are looked for.
+const MAX_LAYOUT_DEPTH: usize = 4;
+
+/// The directory of each vendor's runs, in report order: `<results-dir>/<vendor>` in the default
+/// layout, or wherever `run --results-template` put them. Runs are found by their meta.json,
+/// which names the vendor; a vendor's repetitions and clients share one directory. `slo/` and
+/// `sweep/` probes are left out.
+fn vendor_dirs(results_dir: &Path) -> BenchmarkResult<Vec<(Vendor, PathBuf)>> {
+    const ORDER: [Vendor; 3] = [Vendor::Falkor, Vendor::Neo4j, Vendor::Memgraph];
+
+    #[derive(Deserialize)]
+    struct MetaVendor {
+        vendor: String,
+    }
+    let mut found: BTreeMap<Vendor, BTreeSet<PathBuf>> = BTreeMap::new();
+    let mut pending = vec![(results_dir.to_path_buf(), 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        let meta_path = dir.join("meta.json");
+        if meta_path.exists() {
+            let vendor = fs::read_to_string(&meta_path)
+                .ok()
+                .and_then(|raw| serde_json::from_str::<MetaVendor>(&raw).ok())
+                .and_then(|meta| {
+                    ORDER
+                        .into_iter()
+                        .find(|vendor| vendor.to_string() == meta.vendor)
+                });
+            match vendor {
+                Some(vendor) => {
+                    found.entry(vendor).or_default().insert(run_group_dir(&dir));
+                }
+                None => warn!("Skipping {}: no known vendor", meta_path.display()),
+            }
+            continue;
+        }
+        if depth == MAX_LAYOUT_DEPTH {
+            continue;
+        }
+        if let Ok(entries) = fs::read_dir(&dir) {
+            pending.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .filter(|entry| !matches!(entry.file_name().to_str(), Some("slo" | "sweep")))
+                    .map(|entry| (entry.path(), depth + 1)),
+            );
+        }
+    }
+
+    let mut dirs = Vec::new();
+    for vendor in ORDER {
+        let Some(vendor_dirs) = found.remove(&vendor) else {
+            continue;
+        };
+        if vendor_dirs.len() > 1 {
+            return Err(OtherError(format!(
+                "{} has runs in {} directories of {}: {}; point --results-dir at the one to aggregate",
+                vendor,
+                vendor_dirs.len(),
+                results_dir.display(),
+                vendor_dirs
+                    .iter()
+                    .map(|dir| dir.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        dirs.extend(vendor_dirs.into_iter().map(|dir| (vendor, dir)));
+    }
+    Ok(dirs)
+}
+
+/// The directory `run_dir` belongs to, without its `client-<i>/` and `run-<n>/` levels.
+fn run_group_dir(run_dir: &Path) -> PathBuf {
+    let mut dir = run_dir;
+    for prefix in [CLIENT_DIR_PREFIX, "run-"] {
+        let numbered = dir
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_prefix(prefix)?.parse::<usize>().ok())
+            .is_some();
+        if let (true, Some(parent)) = (numbered, dir.parent()) {
+            dir = parent;
+        }
+    }
+    dir.to_path_buf()
+}
+
+/// Every run of `vendor` in `vendor_dir`: `{meta.json,metrics.prom}` for a single run, or one
+/// per `run-<n>/` repetition, in repetition order.
 fn load_vendor(
-    results_dir: &Path,
+    vendor_dir: &Path,
     vendor: Vendor,
+) -> BenchmarkResult<Vec<VendorArtifacts>> {
+    if vendor_dir.join("meta.json").exists() {
+        return Ok(vec![load_run(vendor_dir, vendor, None)?]);
+    }
+
+    let mut repetitions: Vec<(u32, String)> = fs::read_dir(vendor_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let n = name.strip_prefix("run-")?.parse().ok()?;
+                    Some((n, name))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if repetitions.is_empty() {
+        // Reports the missing meta.json.
+        return Ok(vec![load_run(vendor_dir, vendor, None)?]);
+    }
+    repetitions.sort();
+    repetitions
+        .into_iter()
+        .map(|(_, name)| load_run(&vendor_dir.join(&name), vendor, Some(name)))
+        .collect()
+}
+
+fn load_run(
+    run_dir: &Path,
+    vendor: Vendor,
+    repetition: Option<String>,
 ) -> BenchmarkResult<VendorArtifacts> {
-    let vendor_dir = results_dir.join(vendor.to_string());
-    let meta_path = vendor_dir.join("meta.json");
-    let metrics_path = vendor_dir.join("metrics.prom");
+    let meta_path = run_dir.join("meta.json");
+    let metrics_path = run_dir.join("metrics.prom");
 
+    let clients = client_dirs(run_dir);
+    if !meta_path.exists() && !clients.is_empty() {
+        return load_clients(&clients, vendor, repetition);
+    }
     if !meta_path.exists() {
         return Err(OtherError(format!(
             "Missing meta.json for vendor {} at {}",
@@ -235,10 +642,106 @@ fn load_vendor(
     let metrics_text = fs::read_to_string(&metrics_path)
         .map_err(|e| OtherError(format!("Failed reading {}: {}", metrics_path.display(), e)))?;
 
+    let histograms = read_run_histograms(run_dir)?
+        .map(|histograms| LatencyHistograms::from_runs(&[histograms]))
+        .transpose()?;
+
     Ok(VendorArtifacts {
         vendor,
+        repetition,
         meta,
         metrics_text,
+        histograms,
+        resources: ResourceSeries::read(run_dir)?,
+    })
+}
+
+/// The run's histograms.json; `None` when the run predates it.
+fn read_run_histograms(run_dir: &Path) -> BenchmarkResult<Option<RunHistograms>> {
+    let path = run_dir.join(HISTOGRAMS_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path)
+        .map_err(|e| OtherError(format!("Failed reading {}: {}", path.display(), e)))?;
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| OtherError(format!("Failed parsing {}: {}", path.display(), e)))
+}
+
+/// The `client-<i>/` directories of a distributed run (`run --slice`), in client order.
+fn client_dirs(run_dir: &Path) -> Vec<PathBuf> {
+    let mut clients: Vec<(usize, PathBuf)> = fs::read_dir(run_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let n = name.strip_prefix(CLIENT_DIR_PREFIX)?.parse().ok()?;
+                    Some((n, entry.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    clients.sort();
+    clients.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Merge the clients of a distributed run into one logical run: query counts, workers and
+/// rates add up, the run spans the earliest start to the latest finish, and the metrics are
+/// merged by [`distributed::merge_metrics_text`] (exact percentiles when every client wrote
+/// its histograms).
+fn load_clients(
+    client_dirs: &[PathBuf],
+    vendor: Vendor,
+    repetition: Option<String>,
+) -> BenchmarkResult<VendorArtifacts> {
+    let clients = client_dirs
+        .iter()
+        .map(|dir| load_run(dir, vendor, None))
+        .collect::<BenchmarkResult<Vec<_>>>()?;
+    let histograms = client_dirs
+        .iter()
+        .map(|dir| read_run_histograms(dir))
+        .collect::<BenchmarkResult<Option<Vec<_>>>>()?;
+    // Without every client's histograms the merged percentiles are the slowest client's.
+    let histograms = histograms
+        .map(|histograms| LatencyHistograms::from_runs(&histograms))
+        .transpose()?;
+
+    let mut meta = clients[0].meta.clone();
+    for client in &clients[1..] {
+        meta.queries_count += client.meta.queries_count;
+        meta.parallel += client.meta.parallel;
+        meta.mps += client.meta.mps;
+        meta.started_at_epoch_secs = meta
+            .started_at_epoch_secs
+            .min(client.meta.started_at_epoch_secs);
+        meta.finished_at_epoch_secs = meta
+            .finished_at_epoch_secs
+            .max(client.meta.finished_at_epoch_secs);
+        meta.elapsed_ms = meta.elapsed_ms.max(client.meta.elapsed_ms);
+        meta.graph_size = meta.graph_size.or(client.meta.graph_size);
+        meta.server_memory = meta
+            .server_memory
+            .take()
+            .or(client.meta.server_memory.clone());
+        meta.server_restarts = meta
+            .server_restarts
+            .take()
+            .or(client.meta.server_restarts.clone());
+    }
+    // The clients share the server; the first one's machine stands for the rest.
+    let resources = clients[0].resources.clone();
+    let metrics_texts: Vec<String> = clients.into_iter().map(|c| c.metrics_text).collect();
+    Ok(VendorArtifacts {
+        vendor,
+        repetition,
+        meta,
+        metrics_text: distributed::merge_metrics_text(&metrics_texts, histograms.as_ref()),
+        histograms,
+        resources,
     })
 }
 
@@ -337,17 +840,45 @@ fn write_summary(
     Ok(())
 }
 
-fn make_summary(vendors: &[VendorArtifacts]) -> BenchmarkResult<UiSummary> {
+/// The first vendor is the baseline; a run whose graph size differs from the baseline's by
+/// more than `graph_size_tolerance` is flagged potentially invalid.
+fn make_summary(
+    vendors: &[Vec<VendorArtifacts>],
+    graph_size_tolerance: f64,
+) -> BenchmarkResult<UiSummary> {
     let mut runs = Vec::new();
 
     for v in vendors {
-        runs.push(build_ui_run(v)?);
+        runs.push(build_repeated_ui_run(v)?);
+    }
+    let graph_size =
+        |v: &[VendorArtifacts]| v.iter().find_map(|artifacts| artifacts.meta.graph_size);
+    if let Some(baseline) = vendors.first().and_then(|v| graph_size(v)) {
+        for (run, v) in runs.iter_mut().zip(vendors).skip(1) {
+            let Some(mismatch) =
+                graph_size(v).and_then(|size| size.mismatch(&baseline, graph_size_tolerance))
+            else {
+                continue;
+            };
+            run.potentially_invalid = true;
+            run.invalid_reasons.push(format!(
+                "{} graph differs from {}: {}",
+                run.vendor,
+                vendor_id(vendors[0][0].vendor),
+                mismatch
+            ));
+        }
     }
+    let significance = match runs.as_slice() {
+        [baseline, other] => UiSignificance::new(baseline, other),
+        _ => None,
+    };
 
     Ok(UiSummary {
         runs,
         unrealstic: vec![],
         platforms: vec![],
+        significance,
     })
 }
 
@@ -358,6 +889,8 @@ struct CustomRunArtifacts {
     ui_platform: String,
     meta: RunResultsMeta,
     metrics_text: String,
+    histograms: Option<LatencyHistograms>,
+    resources: Option<ResourceSeries>,
 }
 
 /// Aggregate `aws-tests/` style folders into a single UI summary JSON.
@@ -500,7 +1033,7 @@ pub fn aggregate_aws_tests(
         {
             "intel".to_string()
         } else if lower.contains("falkordb-c") || lower.contains("falkordb-rs") {
-            detected_platform()
+            meta.platform.clone().unwrap_or_else(host_platform)
         } else {
             // Fallback: use the raw directory name as the platform/hardware identifier
             dir_name.clone()
@@ -514,6 +1047,10 @@ pub fn aggregate_aws_tests(
             ui_platform,
             meta,
             metrics_text,
+            histograms: read_run_histograms(&path)?
+                .map(|histograms| LatencyHistograms::from_runs(&[histograms]))
+                .transpose()?,
+            resources: ResourceSeries::read(&path)?,
         });
     }
 
@@ -554,6 +1091,7 @@ pub fn aggregate_aws_tests(
         runs,
         unrealstic: vec![],
         platforms: vec![],
+        significance: None,
     };
 
     let out_path = PathBuf::from(out_path);
@@ -580,7 +1118,8 @@ fn parse_size(s: &str) -> BenchmarkResult<Size> {
     }
 }
 
-fn detected_platform() -> String {
+/// This machine's platform as the results UI tags it: `arm`, `intel` or the architecture.
+pub fn host_platform() -> String {
     match std::env::consts::ARCH {
         "aarch64" | "arm64" => "arm".to_string(),
         "x86_64" => "intel".to_string(),
@@ -588,13 +1127,89 @@ fn detected_platform() -> String {
     }
 }
 
+/// One UI run for all repetitions of a vendor: the repetition with the median P99, plus the
+/// spread of the headline numbers across repetitions.
+fn build_repeated_ui_run(runs: &[VendorArtifacts]) -> BenchmarkResult<UiRun> {
+    let mut ui_runs = runs
+        .iter()
+        .map(build_ui_run)
+        .collect::<BenchmarkResult<Vec<_>>>()?;
+    if ui_runs.len() <= 1 {
+        return ui_runs
+            .pop()
+            .ok_or_else(|| OtherError("no runs to aggregate".to_string()));
+    }
+
+    let spread = |value: fn(&UiResult) -> f64| {
+        UiSpread::new(
+            &ui_runs
+                .iter()
+                .map(|run| value(&run.result))
+                .collect::<Vec<_>>(),
+        )
+    };
+    let p50_ms = spread(|result| result.latency_ms[0]);
+    let p95_ms = spread(|result| result.latency_ms[1]);
+    let p99_ms = spread(|result| result.latency_ms[2]);
+    let tails: Option<Vec<[f64; 3]>> = ui_runs.iter().map(|run| run.result.tail_ms).collect();
+    let tail_spread = |i: usize| {
+        tails
+            .as_ref()
+            .map(|tails| UiSpread::new(&tails.iter().map(|tail| tail[i]).collect::<Vec<_>>()))
+    };
+    let (p99_9_ms, p99_99_ms, max_ms) = (tail_spread(0), tail_spread(1), tail_spread(2));
+    let avg_latency_ms = spread(|result| result.avg_latency_ms);
+    let actual_messages_per_second = spread(|result| result.actual_messages_per_second);
+    let latency_ms = ui_runs.iter().map(|run| run.result.latency_ms).collect();
+
+    // Any repetition's problem taints the comparison.
+    let invalid_reasons: Vec<String> = runs
+        .iter()
+        .zip(&ui_runs)
+        .flat_map(|(run, ui_run)| {
+            let repetition = run.repetition.clone().unwrap_or_default();
+            ui_run
+                .invalid_reasons
+                .iter()
+                .map(move |reason| format!("{}: {}", repetition, reason))
+        })
+        .collect();
+
+    let mut by_p99: Vec<usize> = (0..ui_runs.len()).collect();
+    by_p99.sort_by(|&a, &b| {
+        ui_runs[a].result.latency_ms[2].total_cmp(&ui_runs[b].result.latency_ms[2])
+    });
+    let representative = by_p99[(by_p99.len() - 1) / 2];
+    let count = ui_runs.len();
+    let mut ui_run = ui_runs.swap_remove(representative);
+    ui_run.potentially_invalid = !invalid_reasons.is_empty();
+    ui_run.invalid_reasons = invalid_reasons;
+    ui_run.repetitions = Some(UiRepetitions {
+        count,
+        representative: runs[representative].repetition.clone().unwrap_or_default(),
+        p50_ms,
+        p95_ms,
+        p99_ms,
+        p99_9_ms,
+        p99_99_ms,
+        max_ms,
+        avg_latency_ms,
+        actual_messages_per_second,
+        latency_ms,
+    });
+    Ok(ui_run)
+}
+
 fn build_ui_run(v: &VendorArtifacts) -> BenchmarkResult<UiRun> {
     let custom = CustomRunArtifacts {
         vendor: v.vendor,
         ui_vendor: vendor_id(v.vendor),
-        ui_platform: detected_platform(),
+        // Results from before the platform was recorded get this machine's.
+        ui_platform: v.meta.platform.clone().unwrap_or_else(host_platform),
         meta: v.meta.clone(),
         metrics_text: v.metrics_text.clone(),
+        histograms: v.histograms.clone(),
+        resources: v.resources.clone(),
     };
 
     build_ui_run_custom(&custom)
@@ -603,27 +1218,38 @@ fn build_ui_run(v: &VendorArtifacts) -> BenchmarkResult<UiRun> {
 fn build_ui_run_custom(v: &CustomRunArtifacts) -> BenchmarkResult<UiRun> {
     let dataset = parse_size(&v.meta.dataset)?;
     let spec = Spec::new(Name::Users, dataset, v.vendor);
+    // The graph the run measured; the dataset's counts for runs that didn't record it.
+    let graph_size = v.meta.graph_size.unwrap_or(GraphSize {
+        nodes: spec.vertices,
+        relationships: spec.edges,
+    });
 
     let metrics = MetricsIndex::from_prometheus_text(&v.metrics_text)?;
 
     let success_hist = metrics.histogram(v.vendor, HistogramKind::Success)?;
     let error_hist = metrics.histogram(v.vendor, HistogramKind::Error)?;
 
-    // Prefer in-process computed percentiles (microseconds gauges) when present.
-    let (p50_s, p95_s, p99_s) =
-        if let Some((p50_us, p95_us, p99_us)) = metrics.latency_percentiles_us(v.vendor) {
-            (
-                (p50_us / 1_000_000.0),
-                (p95_us / 1_000_000.0),
-                (p99_us / 1_000_000.0),
-            )
-        } else {
-            (
-                histogram_quantile_seconds(&success_hist, 0.50),
-                histogram_quantile_seconds(&success_hist, 0.95),
-                histogram_quantile_seconds(&success_hist, 0.99),
-            )
-        };
+    let [p50_s, p95_s, p99_s] =
+        overall_percentiles_s(v.histograms.as_ref(), &metrics, &success_hist, v.vendor);
+
+    let tail_ms = tail_latency_ms(v.histograms.as_ref(), &metrics, v.vendor);
+    let tail_latency = tail_ms.map(|[p99_9, p99_99, max]| UiTailLatency {
+        p99_9: format_ms(p99_9),
+        p99_99: format_ms(p99_99),
+        max: format_ms(max),
+    });
+
+    let server_latency = v
+        .histograms
+        .as_ref()
+        .and_then(|histograms| histograms.server.as_ref())
+        .map(exact_percentiles_us)
+        .or_else(|| metrics.server_execution_percentiles_us(v.vendor))
+        .map(|(p50_us, p95_us, p99_us)| UiLatency {
+            p50: format_ms(p50_us / 1000.0),
+            p95: format_ms(p95_us / 1000.0),
+            p99: format_ms(p99_us / 1000.0),
+        });
 
     let avg_latency_ms = if success_hist.count > 0.0 {
         (success_hist.sum / success_hist.count) * 1000.0
@@ -661,10 +1287,11 @@ fn build_ui_run_custom(v: &CustomRunArtifacts) -> BenchmarkResult<UiRun> {
                 .map(|v| v.round().max(0.0) as u64)
                 .filter(|v| *v > 0);
 
-            // Back-compat for older runs: compute from dataset constants.
+            // Back-compat for older runs: compute from the graph size.
             // StorageRAMUsage = NumberOfVertices×212B + NumberOfEdges×162B
             let computed = {
-                let bytes: i128 = (spec.vertices as i128) * 212 + (spec.edges as i128) * 162;
+                let bytes: i128 =
+                    (graph_size.nodes as i128) * 212 + (graph_size.relationships as i128) * 162;
                 if bytes > 0 {
                     Some(bytes.min(u64::MAX as i128) as u64)
                 } else {
@@ -687,23 +1314,48 @@ fn build_ui_run_custom(v: &CustomRunArtifacts) -> BenchmarkResult<UiRun> {
 
             store.or(estimate)
         }
-        _ => None,
+        Vendor::Falkor => metrics
+            .get_single_value("falkordb_base_dataset_bytes")
+            .map(|v| v.round().max(0.0) as u64)
+            .filter(|v| *v > 0),
     };
 
     let operations = metrics.operations_breakdown(v.vendor);
-    let spawn_stats = compute_spawn_stats(&operations.by_spawn);
+    let spawn_stats = UiSpawnStats {
+        dispatch: v.meta.dispatch.clone(),
+        ..compute_spawn_stats(&operations.by_spawn)
+    };
 
-    let histogram_for_type = metrics.query_latency_histogram_ms(v.vendor);
+    let mut histogram_for_type = metrics.query_latency_histogram_ms(v.vendor);
+    let mut latency_by_group = metrics.latency_by_group(v.vendor);
+    let mut latency_by_type = metrics.latency_by_type(v.vendor);
+    if let Some(histograms) = &v.histograms {
+        exact_query_latencies(
+            histograms,
+            &mut histogram_for_type,
+            &mut latency_by_group,
+            &mut latency_by_type,
+        );
+    }
+    let latency_by_result_size = v
+        .histograms
+        .as_ref()
+        .map(|histograms| latency_by_result_size(histograms, &metrics.result_sizes(v.vendor)))
+        .unwrap_or_default();
     let telemetry_for_type = metrics.telemetry_for_type(v.vendor);
+    let gc = metrics.gc_pauses(v.meta.elapsed_ms as f64);
+    let invalid_reasons = invalid_reasons(v, &metrics);
     Ok(UiRun {
         vendor: v.ui_vendor.clone(),
-        read_write_ratio: 0.0,
+        read_write_ratio: v.meta.write_ratio.unwrap_or(0.0),
         clients: v.meta.parallel as u64,
         platform: v.ui_platform.clone(),
         target_messages_per_second: v.meta.mps as u64,
-        edges: spec.vertices,
-        relationships: spec.edges,
+        edges: graph_size.nodes,
+        relationships: graph_size.relationships,
         started_at_epoch_secs: v.meta.started_at_epoch_secs,
+        potentially_invalid: !invalid_reasons.is_empty(),
+        invalid_reasons,
         result: UiResult {
             deadline_offset: "0ms".to_string(),
             actual_messages_per_second: actual_mps,
@@ -712,6 +1364,13 @@ fn build_ui_run_custom(v: &CustomRunArtifacts) -> BenchmarkResult<UiRun> {
                 p95: format_ms(p95_s * 1000.0),
                 p99: format_ms(p99_s * 1000.0),
             },
+            latency_ms: [p50_s * 1000.0, p95_s * 1000.0, p99_s * 1000.0],
+            tail_latency,
+            tail_ms,
+            server_latency,
+            latency_by_group,
+            latency_by_type,
+            latency_by_result_size,
             avg_latency_ms,
             latency_histogram,
             elapsed_ms: v.meta.elapsed_ms as u64,
@@ -724,10 +1383,158 @@ fn build_ui_run_custom(v: &CustomRunArtifacts) -> BenchmarkResult<UiRun> {
             spawn_stats,
             histogram_for_type,
             telemetry_for_type,
+            gc,
+            resources: v.resources.as_ref().and_then(ResourceSeries::summary),
         },
+        repetitions: None,
     })
 }
 
+/// Overall P50/P95/P99 in seconds from the most precise source the run has: its HDR
+/// histograms, then the in-process percentile gauges, then the Prometheus buckets, which only
+/// resolve to a bucket boundary (legacy results).
+fn overall_percentiles_s(
+    histograms: Option<&LatencyHistograms>,
+    metrics: &MetricsIndex,
+    success_hist: &HistogramData,
+    vendor: Vendor,
+) -> [f64; 3] {
+    let from_us = |(p50_us, p95_us, p99_us): (f64, f64, f64)| {
+        [p50_us, p95_us, p99_us].map(|us| us / 1_000_000.0)
+    };
+    if let Some(histograms) = histograms {
+        return from_us(exact_percentiles_us(&histograms.overall));
+    }
+    if let Some(percentiles) = metrics.latency_percentiles_us(vendor) {
+        return from_us(percentiles);
+    }
+    [0.50, 0.95, 0.99].map(|q| histogram_quantile_seconds(success_hist, q))
+}
+
+/// Overall P99.9/P99.99/max in milliseconds from the run's HDR histograms or, without them, the
+/// in-process gauges; the Prometheus buckets are too coarse to say anything this far out.
+fn tail_latency_ms(
+    histograms: Option<&LatencyHistograms>,
+    metrics: &MetricsIndex,
+    vendor: Vendor,
+) -> Option<[f64; 3]> {
+    let tail_us = match histograms {
+        Some(histograms) => {
+            Some([99.9, 99.99, 100.0].map(|pct| percentile_us(&histograms.overall, pct) as f64))
+        }
+        None => metrics.tail_latency_us(vendor),
+    };
+    tail_us.map(|tail| tail.map(|us| us / 1000.0))
+}
+
+fn exact_percentiles_us(hist: &Histogram) -> (f64, f64, f64) {
+    (
+        percentile_us(hist, 50.0) as f64,
+        percentile_us(hist, 95.0) as f64,
+        percentile_us(hist, 99.0) as f64,
+    )
+}
+
+/// Recompute the per-query, per-group and per-type percentiles from the run's histograms; the
+/// timeout rate in the last slot of each query's row stays as exported.
+fn exact_query_latencies(
+    histograms: &LatencyHistograms,
+    histogram_for_type: &mut BTreeMap<String, Vec<f64>>,
+    latency_by_group: &mut BTreeMap<String, UiLatency>,
+    latency_by_type: &mut BTreeMap<String, UiLatency>,
+) {
+    for (query, hist) in &histograms.queries {
+        let row = histogram_for_type
+            .entry(query.clone())
+            .or_insert_with(|| vec![0.0; QUERY_HIST_PCTS.len() + 1]);
+        for (slot, pct) in row.iter_mut().zip(QUERY_HIST_PCTS) {
+            *slot = percentile_us(hist, pct) as f64 / 1000.0;
+        }
+    }
+    for group in latency_by_group.keys().cloned().collect::<Vec<_>>() {
+        let Some(hist) = histograms.group(&group) else {
+            continue;
+        };
+        latency_by_group.insert(group, exact_latency(&hist));
+    }
+    for (q_type, hist) in &histograms.types {
+        latency_by_type.insert(q_type.clone(), exact_latency(hist));
+    }
+}
+
+fn exact_latency(hist: &Histogram) -> UiLatency {
+    let ms = |pct: f64| format_ms(percentile_us(hist, pct) as f64 / 1000.0);
+    UiLatency {
+        p50: ms(50.0),
+        p95: ms(95.0),
+        p99: ms(99.0),
+    }
+}
+
+/// P50/P95/P99 of the queries of each result-size bin merged, the bins keyed by their label.
+fn latency_by_result_size(
+    histograms: &LatencyHistograms,
+    sizes: &BTreeMap<String, ResultSize>,
+) -> BTreeMap<String, UiResultSizeLatency> {
+    let mut bins: BTreeMap<ResultSize, Vec<String>> = BTreeMap::new();
+    for query in histograms.queries.keys() {
+        if let Some(&size) = sizes.get(query) {
+            bins.entry(size).or_default().push(query.clone());
+        }
+    }
+    bins.into_iter()
+        .filter_map(|(size, queries)| {
+            let hist = histograms.merged(queries.iter().map(String::as_str))?;
+            let latency = exact_latency(&hist);
+            Some((size.to_string(), UiResultSizeLatency { latency, queries }))
+        })
+        .collect()
+}
+
+fn invalid_reasons(
+    v: &CustomRunArtifacts,
+    metrics: &MetricsIndex,
+) -> Vec<String> {
+    let mut reasons = Vec::new();
+    match &v.meta.server_restarts {
+        Some(restarts) if restarts.count > 0 => {
+            let at = restarts
+                .at_epoch_ms
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            reasons.push(format!(
+                "{} server restarted {} time(s) during the measured phase (epoch ms: {})",
+                v.ui_vendor, restarts.count, at
+            ));
+        }
+        Some(_) => {}
+        None => {
+            // Older results: fall back to the monitor's counter, which covers the whole `run`.
+            if v.vendor == Vendor::Falkor {
+                let restarts = metrics
+                    .get_single_value("falkordb_restarts_total")
+                    .unwrap_or(0.0);
+                if restarts > 0.0 {
+                    reasons.push(format!(
+                        "{} server restarted {} time(s) during the run",
+                        v.ui_vendor, restarts
+                    ));
+                }
+            }
+        }
+    }
+    if let Some(memory) = v.meta.server_memory.as_ref().filter(|m| m.oom_kills > 0) {
+        reasons.push(format!(
+            "{} server was OOM-killed {} time(s) under a {} memory limit",
+            v.ui_vendor, memory.oom_kills, memory.limit
+        ));
+    }
+    reasons
+}
+
+/// The results UI's id for a vendor; metric dumps are labelled with [`Vendor::metric_label`].
 fn vendor_id(vendor: Vendor) -> String {
     match vendor {
         Vendor::Falkor => "falkordb".to_string(),
@@ -843,6 +1650,124 @@ impl MetricsIndex {
         Some((p50v, p95v, p99v))
     }
 
+    /// P99.9/P99.99/max from the in-process gauges; `None` for runs before they were exported.
+    fn tail_latency_us(
+        &self,
+        vendor: Vendor,
+    ) -> Option<[f64; 3]> {
+        let prefix = match vendor {
+            Vendor::Falkor => "falkordb",
+            Vendor::Neo4j => "neo4j",
+            Vendor::Memgraph => "memgraph",
+        };
+        let tail = [
+            self.get_single_value(&format!("{}_latency_p99_9_us", prefix))?,
+            self.get_single_value(&format!("{}_latency_p99_99_us", prefix))?,
+            self.get_single_value(&format!("{}_latency_max_us", prefix))?,
+        ];
+        Some(tail).filter(|tail| tail[2] > 0.0)
+    }
+
+    /// P50/P95/P99 of the server-reported execution time; only FalkorDB reports one.
+    fn server_execution_percentiles_us(
+        &self,
+        vendor: Vendor,
+    ) -> Option<(f64, f64, f64)> {
+        if vendor != Vendor::Falkor {
+            return None;
+        }
+        let p50v = self.get_single_value("falkordb_server_execution_p50_us")?;
+        let p95v = self.get_single_value("falkordb_server_execution_p95_us")?;
+        let p99v = self.get_single_value("falkordb_server_execution_p99_us")?;
+
+        // Simulated runs get no server time; all zero means nothing was recorded.
+        if p99v <= 0.0 {
+            return None;
+        }
+
+        Some((p50v, p95v, p99v))
+    }
+
+    /// P50/P95/P99 of each query group, from `query_group_latency_us`.
+    fn latency_by_group(
+        &self,
+        vendor: Vendor,
+    ) -> BTreeMap<String, UiLatency> {
+        self.latency_by_label("query_group_latency_us", "group", vendor)
+    }
+
+    /// P50/P95/P99 of reads and of writes, from `query_type_latency_us`.
+    fn latency_by_type(
+        &self,
+        vendor: Vendor,
+    ) -> BTreeMap<String, UiLatency> {
+        self.latency_by_label("query_type_latency_us", "type", vendor)
+    }
+
+    /// The result-size bin of each query, from its mean rows in `query_result_rows`.
+    fn result_sizes(
+        &self,
+        vendor: Vendor,
+    ) -> BTreeMap<String, ResultSize> {
+        let want_vendor = vendor.metric_label().to_string();
+        let by_name = |metric: &str| -> BTreeMap<String, f64> {
+            self.samples
+                .get(metric)
+                .into_iter()
+                .flatten()
+                .filter(|(labels, _)| labels.get("vendor") == Some(&want_vendor))
+                .filter_map(|(labels, value)| Some((labels.get("name")?.clone(), *value)))
+                .collect()
+        };
+        let sums = by_name("query_result_rows_sum");
+        by_name("query_result_rows_count")
+            .into_iter()
+            .filter(|(_, count)| *count > 0.0)
+            .filter_map(|(name, count)| {
+                let size = ResultSize::of_mean(sums.get(&name)? / count);
+                Some((name, size))
+            })
+            .collect()
+    }
+
+    /// P50/P95/P99 per value of `label`, from a `{vendor, <label>, pct}` gauge in microseconds.
+    fn latency_by_label(
+        &self,
+        metric: &str,
+        label: &str,
+        vendor: Vendor,
+    ) -> BTreeMap<String, UiLatency> {
+        let want_vendor = vendor.metric_label().to_string();
+        // label value -> pct -> us
+        let mut by_value: BTreeMap<String, BTreeMap<String, f64>> = BTreeMap::new();
+        for (labels, value) in self.samples.get(metric).into_iter().flatten() {
+            if labels.get("vendor") != Some(&want_vendor) {
+                continue;
+            }
+            let (Some(key), Some(pct)) = (labels.get(label), labels.get("pct")) else {
+                continue;
+            };
+            by_value
+                .entry(key.clone())
+                .or_default()
+                .insert(pct.clone(), *value);
+        }
+        by_value
+            .into_iter()
+            .map(|(key, by_pct)| {
+                let ms = |pct: &str| format_ms(by_pct.get(pct).copied().unwrap_or(0.0) / 1000.0);
+                (
+                    key,
+                    UiLatency {
+                        p50: ms("50"),
+                        p95: ms("95"),
+                        p99: ms("99"),
+                    },
+                )
+            })
+            .collect()
+    }
+
     fn query_latency_histogram_ms(
         &self,
         vendor: Vendor,
@@ -853,8 +1778,9 @@ impl MetricsIndex {
             Vendor::Memgraph => "memgraph_query_latency_pct_us",
         };
         let timeout_metric = match vendor {
+            Vendor::Falkor => Some("falkordb_query_timeout_rate_pct"),
+            Vendor::Neo4j => Some("neo4j_query_timeout_rate_pct"),
             Vendor::Memgraph => Some("memgraph_query_timeout_rate_pct"),
-            _ => None,
         };
 
         let samples = self.samples.get(metric).cloned().unwrap_or_default();
@@ -1036,7 +1962,7 @@ impl MetricsIndex {
         let mut by_query: BTreeMap<String, u64> = BTreeMap::new();
         let mut by_spawn: BTreeMap<String, u64> = BTreeMap::new();
 
-        let want_vendor = vendor.to_string();
+        let want_vendor = vendor.metric_label().to_string();
         if let Some(samples) = self.samples.get("operations_total") {
             for (labels, value) in samples {
                 if labels
@@ -1046,6 +1972,10 @@ impl MetricsIndex {
                 {
                     continue;
                 }
+                // Failures are counted again under their failure type; count each query once.
+                if labels.get("type").is_some_and(|t| !t.is_empty()) {
+                    continue;
+                }
 
                 let name = labels
                     .get("name")
@@ -1062,7 +1992,62 @@ impl MetricsIndex {
             }
         }
 
-        UiOpsBreakdown { by_query, by_spawn }
+        let mut by_group: BTreeMap<String, u64> = BTreeMap::new();
+        for (name, count) in &by_query {
+            *by_group
+                .entry(QueryGroup::of(name).as_str().to_string())
+                .or_insert(0) += count;
+        }
+
+        UiOpsBreakdown {
+            by_query,
+            by_spawn,
+            by_group,
+        }
+    }
+
+    /// From `neo4j_gc_collections_total` and `neo4j_gc_pause_seconds_total`, which count the
+    /// garbage collection during the run per collector; `None` when they weren't exported.
+    fn gc_pauses(
+        &self,
+        elapsed_ms: f64,
+    ) -> Option<UiGcPauses> {
+        let mut by_collector: BTreeMap<String, UiGcCollector> = BTreeMap::new();
+        for (name, is_pause) in [
+            ("neo4j_gc_collections_total", false),
+            ("neo4j_gc_pause_seconds_total", true),
+        ] {
+            for (labels, value) in self.samples.get(name).into_iter().flatten() {
+                let Some(collector) = labels.get("collector") else {
+                    continue;
+                };
+                let entry = by_collector
+                    .entry(collector.clone())
+                    .or_insert(UiGcCollector {
+                        collections: 0,
+                        pause_ms: 0.0,
+                    });
+                if is_pause {
+                    entry.pause_ms += value.max(0.0) * 1000.0;
+                } else {
+                    entry.collections += value.round().max(0.0) as u64;
+                }
+            }
+        }
+        if by_collector.is_empty() {
+            return None;
+        }
+        let pause_ms: f64 = by_collector.values().map(|c| c.pause_ms).sum();
+        Some(UiGcPauses {
+            collections: by_collector.values().map(|c| c.collections).sum(),
+            pause_ms,
+            pause_share: if elapsed_ms > 0.0 {
+                pause_ms / elapsed_ms
+            } else {
+                0.0
+            },
+            by_collector,
+        })
     }
 
     fn vendor_cpu_mem(
@@ -1075,47 +2060,14 @@ impl MetricsIndex {
             Vendor::Memgraph => self.get_single_value("memgraph_cpu_usage").unwrap_or(0.0),
         };
 
-        // Prefer query-interface memory metrics when present.
-        let mem_str = match vendor {
-            Vendor::Falkor => {
-                // `GRAPH.MEMORY USAGE` reports MB.
-                if let Some(mb) = self.get_single_value("falkordb_graph_memory_usage_mb") {
-                    if mb > 0.0 {
-                        format_mem_from_mb(mb)
-                    } else {
-                        // Fallback: process RSS (sysinfo, KiB)
-                        let mem_kib = self.get_single_value("falkor_memory_usage").unwrap_or(0.0);
-                        format_mem_from_kib(mem_kib)
-                    }
-                } else {
-                    let mem_kib = self.get_single_value("falkor_memory_usage").unwrap_or(0.0);
-                    format_mem_from_kib(mem_kib)
-                }
-            }
-            Vendor::Memgraph => {
-                // `SHOW STORAGE INFO` reports bytes.
-                let bytes = self
-                    .get_single_value("memgraph_storage_memory_tracked_bytes")
-                    .or_else(|| self.get_single_value("memgraph_storage_memory_res_bytes"))
-                    .or_else(|| self.get_single_value("memgraph_storage_peak_memory_res_bytes"))
-                    .unwrap_or(0.0);
-
-                if bytes > 0.0 {
-                    format_mem_from_bytes(bytes)
-                } else {
-                    // Fallback: process RSS (sysinfo, KiB)
-                    let mem_kib = self
-                        .get_single_value("memgraph_memory_usage")
-                        .unwrap_or(0.0);
-                    format_mem_from_kib(mem_kib)
-                }
-            }
-            Vendor::Neo4j => {
-                // No query-interface metric wired yet; use process RSS (sysinfo, KiB)
-                let mem_kib = self.get_single_value("neo4j_memory_usage").unwrap_or(0.0);
-                format_mem_from_kib(mem_kib)
-            }
-        };
+        // Normalized bytes gauges only: prefer what the server reports through its query
+        // interface, else the process RSS.
+        let bytes = self
+            .get_single_value(&format!("{}_reported_memory_bytes", vendor))
+            .filter(|bytes| *bytes > 0.0)
+            .or_else(|| self.get_single_value(&format!("{}_process_memory_bytes", vendor)))
+            .unwrap_or(0.0);
+        let mem_str = format_mem_from_bytes(bytes);
 
         (cpu, mem_str)
     }
@@ -1161,15 +2113,6 @@ fn parse_labels(s: &str) -> BTreeMap<String, String> {
     out
 }
 
-fn format_mem_from_kib(kib: f64) -> String {
-    if !kib.is_finite() || kib <= 0.0 {
-        return "0MB".to_string();
-    }
-
-    let mib = kib / 1024.0;
-    format_mem_from_mib(mib)
-}
-
 fn format_mem_from_bytes(bytes: f64) -> String {
     if !bytes.is_finite() || bytes <= 0.0 {
         return "0MB".to_string();
@@ -1179,14 +2122,6 @@ fn format_mem_from_bytes(bytes: f64) -> String {
     format_mem_from_mib(mib)
 }
 
-fn format_mem_from_mb(mb: f64) -> String {
-    if !mb.is_finite() || mb <= 0.0 {
-        return "0MB".to_string();
-    }
-
-    format_mem_from_mib(mb)
-}
-
 fn format_mem_from_mib(mib: f64) -> String {
     if !mib.is_finite() || mib <= 0.0 {
         return "0MB".to_string();
@@ -1209,6 +2144,7 @@ fn compute_spawn_stats(by_spawn: &BTreeMap<String, u64>) -> UiSpawnStats {
             p95: 0,
             max_min_ratio: 0.0,
             cv: 0.0,
+            dispatch: None,
         };
     }
 
@@ -1250,6 +2186,7 @@ fn compute_spawn_stats(by_spawn: &BTreeMap<String, u64>) -> UiSpawnStats {
         p95,
         max_min_ratio,
         cv,
+        dispatch: None,
     }
 }
 
@@ -1271,3 +2208,332 @@ fn quantile_u64(
     let idx = ((sorted.len() as f64 - 1.0) * q).round() as usize;
     sorted[idx.min(sorted.len() - 1)]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1000 successful calls of 0.1ms, 0.2ms, .. 100ms, as the run writes them to
+    /// histograms.json and as Prometheus buckets in metrics.prom.
+    fn synthetic_run() -> (LatencyHistograms, String) {
+        let latencies_us: Vec<u64> = (1..=1000).map(|i| i * 100).collect();
+        let mut hist = Histogram::new(7, 64).unwrap();
+        for us in &latencies_us {
+            hist.increment(*us).unwrap();
+        }
+        let run = RunHistograms {
+            overall: (&hist).into(),
+            server: None,
+            queries: BTreeMap::from([("single_vertex_read".to_string(), (&hist).into())]),
+            types: BTreeMap::from([("read".to_string(), (&hist).into())]),
+        };
+
+        let mut text = String::new();
+        for le in [0.005, 0.01, 0.025, 0.05, 0.1, 0.25] {
+            let count = latencies_us
+                .iter()
+                .filter(|us| **us as f64 <= le * 1_000_000.0)
+                .count();
+            text.push_str(&format!(
+                "falkordb_response_time_success_histogram_bucket{{le=\"{}\"}} {}\n",
+                le, count
+            ));
+        }
+        text.push_str("falkordb_response_time_success_histogram_bucket{le=\"+Inf\"} 1000\n");
+        text.push_str("falkordb_response_time_success_histogram_sum 50.05\n");
+        text.push_str("falkordb_response_time_success_histogram_count 1000\n");
+        (LatencyHistograms::from_runs(&[run]).unwrap(), text)
+    }
+
+    #[test]
+    fn exact_percentiles_refine_the_bucketed_fallback() {
+        let (histograms, text) = synthetic_run();
+        let metrics = MetricsIndex::from_prometheus_text(&text).unwrap();
+        let success_hist = metrics
+            .histogram(Vendor::Falkor, HistogramKind::Success)
+            .unwrap();
+
+        let bucketed = overall_percentiles_s(None, &metrics, &success_hist, Vendor::Falkor);
+        let exact =
+            overall_percentiles_s(Some(&histograms), &metrics, &success_hist, Vendor::Falkor);
+
+        // The buckets only resolve to their boundaries: P95 and P99 both read as 100ms.
+        assert_eq!(bucketed, [0.05, 0.1, 0.1]);
+        for (exact, truth) in exact.iter().zip([0.05, 0.095, 0.099]) {
+            assert!(
+                (exact - truth).abs() / truth < 0.01,
+                "{} vs {}",
+                exact,
+                truth
+            );
+        }
+        // Both paths agree to within a bucket.
+        for (exact, bucketed) in exact.iter().zip(bucketed) {
+            assert!(*exact <= bucketed * 1.01);
+        }
+
+        // Gauges from a run without histograms.json still win over the buckets.
+        let with_gauges = format!(
+            "{}falkordb_latency_p50_us 50000\nfalkordb_latency_p95_us 95000\nfalkordb_latency_p99_us 99000\n",
+            text
+        );
+        let metrics = MetricsIndex::from_prometheus_text(&with_gauges).unwrap();
+        assert_eq!(
+            overall_percentiles_s(None, &metrics, &success_hist, Vendor::Falkor),
+            [0.05, 0.095, 0.099]
+        );
+
+        // Beyond P99 only the histograms or the tail gauges say anything.
+        assert_eq!(tail_latency_ms(None, &metrics, Vendor::Falkor), None);
+        let tail = tail_latency_ms(Some(&histograms), &metrics, Vendor::Falkor).unwrap();
+        for (tail, truth) in tail.iter().zip([99.9, 100.0, 100.0]) {
+            assert!((tail - truth).abs() / truth < 0.01, "{} vs {}", tail, truth);
+        }
+        let with_tail = format!(
+            "{}falkordb_latency_p99_9_us 99900\nfalkordb_latency_p99_99_us 99990\nfalkordb_latency_max_us 100000\n",
+            with_gauges
+        );
+        let metrics = MetricsIndex::from_prometheus_text(&with_tail).unwrap();
+        assert_eq!(
+            tail_latency_ms(None, &metrics, Vendor::Falkor),
+            Some([99.9, 99.99, 100.0])
+        );
+    }
+
+    #[test]
+    fn latencies_are_split_by_the_mean_rows_of_each_query() {
+        let constant = |us: u64| {
+            let mut hist = Histogram::new(7, 64).unwrap();
+            for _ in 0..100 {
+                hist.increment(us).unwrap();
+            }
+            hist
+        };
+        let run = RunHistograms {
+            overall: (&constant(1000)).into(),
+            server: None,
+            queries: BTreeMap::from([
+                ("shortest_path".to_string(), (&constant(1000)).into()),
+                ("single_vertex_read".to_string(), (&constant(2000)).into()),
+                (
+                    "aggregate_expansion_2".to_string(),
+                    (&constant(40_000)).into(),
+                ),
+                (
+                    "aggregate_expansion_3".to_string(),
+                    (&constant(80_000)).into(),
+                ),
+            ]),
+            types: BTreeMap::new(),
+        };
+        let histograms = LatencyHistograms::from_runs(&[run]).unwrap();
+        let mut text = String::new();
+        for (name, sum) in [
+            ("shortest_path", 0),
+            ("single_vertex_read", 100),
+            ("aggregate_expansion_2", 30_000),
+            ("aggregate_expansion_3", 300_000),
+        ] {
+            text.push_str(&format!(
+                "query_result_rows_sum{{name=\"{0}\",vendor=\"falkor\"}} {1}\n\
+                 query_result_rows_count{{name=\"{0}\",vendor=\"falkor\"}} 100\n",
+                name, sum
+            ));
+        }
+        text.push_str("query_result_rows_sum{name=\"shortest_path\",vendor=\"neo4j\"} 500\n");
+        text.push_str("query_result_rows_count{name=\"shortest_path\",vendor=\"neo4j\"} 1\n");
+        let metrics = MetricsIndex::from_prometheus_text(&text).unwrap();
+
+        let by_size = latency_by_result_size(&histograms, &metrics.result_sizes(Vendor::Falkor));
+        assert_eq!(
+            by_size.keys().collect::<Vec<_>>(),
+            ["0", "1-10", "11-1000", ">1000"]
+        );
+        assert_eq!(by_size["0"].queries, ["shortest_path"]);
+        assert_eq!(by_size[">1000"].queries, ["aggregate_expansion_3"]);
+        assert_eq!(
+            by_size["0"].latency.p99,
+            exact_latency(&histograms.queries["shortest_path"]).p99
+        );
+        assert_ne!(by_size["11-1000"].latency.p50, by_size["0"].latency.p50);
+
+        let neo4j = metrics.result_sizes(Vendor::Neo4j);
+        assert_eq!(neo4j["shortest_path"], ResultSize::Many);
+    }
+
+    #[test]
+    fn exact_query_latencies_replace_the_exported_percentiles() {
+        let (histograms, _) = synthetic_run();
+        let mut histogram_for_type = BTreeMap::from([(
+            "single_vertex_read".to_string(),
+            vec![0.0; 11].into_iter().chain([2.5]).collect(),
+        )]);
+        let mut latency_by_group = BTreeMap::from([(
+            "oltp".to_string(),
+            UiLatency {
+                p50: "0ms".to_string(),
+                p95: "0ms".to_string(),
+                p99: "0ms".to_string(),
+            },
+        )]);
+        let mut latency_by_type = BTreeMap::new();
+        exact_query_latencies(
+            &histograms,
+            &mut histogram_for_type,
+            &mut latency_by_group,
+            &mut latency_by_type,
+        );
+
+        let row = &histogram_for_type["single_vertex_read"];
+        assert_eq!(row.len(), 12);
+        assert!((row[4] - 50.0).abs() < 0.5, "p50 {}", row[4]);
+        // The timeout rate is kept.
+        assert_eq!(row[11], 2.5);
+        assert_eq!(latency_by_group["oltp"].p50, format_ms(row[4]));
+        assert_eq!(latency_by_type["read"].p99, format_ms(row[10]));
+        assert!(!latency_by_type.contains_key("write"));
+    }
+
+    #[test]
+    fn summaries_compare_against_the_baseline_or_stand_alone() {
+        let names = |present: &[Vendor], baseline| -> Vec<String> {
+            summary_plan(present, baseline)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect()
+        };
+        let all = [Vendor::Falkor, Vendor::Neo4j, Vendor::Memgraph];
+        assert_eq!(
+            names(&all, Vendor::Falkor),
+            ["neo4j_vs_falkordb.json", "memgraph_vs_falkordb.json"]
+        );
+        assert_eq!(
+            summary_plan(&[Vendor::Neo4j, Vendor::Memgraph], Vendor::Neo4j),
+            [(
+                "memgraph_vs_neo4j.json".to_string(),
+                vec![Vendor::Neo4j, Vendor::Memgraph]
+            )]
+        );
+        // Nothing to compare with.
+        assert_eq!(names(&[Vendor::Neo4j], Vendor::Falkor), ["neo4j.json"]);
+        assert_eq!(names(&[Vendor::Falkor], Vendor::Falkor), ["falkordb.json"]);
+        assert_eq!(
+            names(&[Vendor::Neo4j, Vendor::Memgraph], Vendor::Falkor),
+            ["neo4j.json", "memgraph.json"]
+        );
+    }
+
+    #[test]
+    fn memory_comes_from_the_normalized_bytes_gauges() {
+        let metrics = MetricsIndex::from_prometheus_text(
+            "falkor_memory_usage 5\n\
+             falkor_process_memory_bytes 1073741824\n\
+             falkor_reported_memory_bytes 0\n\
+             memgraph_process_memory_bytes 1073741824\n\
+             memgraph_reported_memory_bytes 52428800\n",
+        )
+        .unwrap();
+        assert_eq!(metrics.vendor_cpu_mem(Vendor::Falkor).1, "1.00GB");
+        assert_eq!(metrics.vendor_cpu_mem(Vendor::Memgraph).1, "50.0MB");
+        assert_eq!(metrics.vendor_cpu_mem(Vendor::Neo4j).1, "0MB");
+    }
+
+    #[test]
+    fn gc_pauses_sum_the_collectors_and_share_the_run_time() {
+        let metrics = MetricsIndex::from_prometheus_text(
+            "neo4j_gc_collections_total{collector=\"G1 Young Generation\"} 12\n\
+             neo4j_gc_collections_total{collector=\"G1 Old Generation\"} 1\n\
+             neo4j_gc_pause_seconds_total{collector=\"G1 Young Generation\"} 0.3\n\
+             neo4j_gc_pause_seconds_total{collector=\"G1 Old Generation\"} 0.7\n",
+        )
+        .unwrap();
+        let gc = metrics.gc_pauses(20_000.0).unwrap();
+        assert_eq!(gc.collections, 13);
+        assert!((gc.pause_ms - 1000.0).abs() < 1e-9);
+        assert!((gc.pause_share - 0.05).abs() < 1e-9);
+        assert_eq!(gc.by_collector["G1 Old Generation"].collections, 1);
+        assert!(MetricsIndex::from_prometheus_text("neo4j_cpu_usage 1\n")
+            .unwrap()
+            .gc_pauses(20_000.0)
+            .is_none());
+    }
+
+    #[test]
+    fn templated_layouts_are_found_by_their_meta_json() {
+        let root = std::env::temp_dir().join(format!("benchmark-layout-{}", std::process::id()));
+        for (dir, vendor) in [
+            ("small/falkor-4000mps", "falkor"),
+            ("small/neo4j-4000mps/run-1", "neo4j"),
+            ("small/neo4j-4000mps/run-2", "neo4j"),
+            ("small/neo4j-4000mps/sweep/mps-400", "neo4j"),
+            ("small/memgraph-4000mps/client-0", "memgraph"),
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(
+                root.join(dir).join("meta.json"),
+                format!("{{\"vendor\":\"{}\"}}", vendor),
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            vendor_dirs(&root).unwrap(),
+            vec![
+                (Vendor::Falkor, root.join("small/falkor-4000mps")),
+                (Vendor::Neo4j, root.join("small/neo4j-4000mps")),
+                (Vendor::Memgraph, root.join("small/memgraph-4000mps")),
+            ]
+        );
+
+        fs::create_dir_all(root.join("large/falkor")).unwrap();
+        fs::write(
+            root.join("large/falkor/meta.json"),
+            r#"{"vendor":"falkor"}"#,
+        )
+        .unwrap();
+        let err = vendor_dirs(&root).unwrap_err().to_string();
+        assert!(err.contains("falkor has runs in 2 directories"), "{}", err);
+        assert_eq!(
+            vendor_dirs(&root.join("large")).unwrap(),
+            vec![(Vendor::Falkor, root.join("large/falkor"))]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn ui_runs_take_the_graph_size_and_platform_the_run_recorded() {
+        let (histograms, metrics_text) = synthetic_run();
+        let meta = |extra: &str| -> RunResultsMeta {
+            serde_json::from_str(&format!(
+                r#"{{"vendor":"falkor","dataset":"small","queries_file":"q.json","queries_count":1000,
+                    "parallel":8,"mps":100,"simulate_ms":null,"endpoint":null,
+                    "started_at_epoch_secs":0,"finished_at_epoch_secs":10,"elapsed_ms":10000{}}}"#,
+                extra
+            ))
+            .unwrap()
+        };
+        let run = |meta| VendorArtifacts {
+            vendor: Vendor::Falkor,
+            repetition: None,
+            meta,
+            metrics_text: metrics_text.clone(),
+            histograms: Some(histograms.clone()),
+            resources: None,
+        };
+
+        let recorded = build_ui_run(&run(meta(
+            r#","graph_size":{"nodes":1200,"relationships":34000},"platform":"arm""#,
+        )))
+        .unwrap();
+        assert_eq!(
+            (recorded.edges, recorded.relationships, recorded.platform),
+            (1200, 34000, "arm".to_string())
+        );
+
+        let spec = Spec::new(Name::Users, Size::Small, Vendor::Falkor);
+        let older = build_ui_run(&run(meta(""))).unwrap();
+        assert_eq!(
+            (older.edges, older.relationships, older.platform),
+            (spec.vertices, spec.edges, host_platform())
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! `--artifact-store s3://bucket/prefix` (or `gs://bucket/prefix`): share prepared dataset dumps
+//! and run results through a bucket instead of re-running multi-hour loads on every machine.
+//!
+//! `load` uploads the dump and its manifest to `<prefix>/dumps/<vendor>/<dataset>/<size>/`;
+//! `run` downloads a missing local dump from there before restoring it and uploads its run
+//! directory to `<prefix>/results/<results-dir>/...` when it's done. Transfers go through the
+//! `aws` and `gcloud storage` CLIs, so their usual credentials and configuration apply.
+
+use crate::backup_manifest::MANIFEST_FILE;
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::memgraph::SNAPSHOT_FILE;
+use crate::scenario::{Spec, Vendor};
+use crate::REDIS_DATA_DIR;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreKind {
+    S3,
+    Gcs,
+}
+
+impl StoreKind {
+    fn scheme(self) -> &'static str {
+        match self {
+            StoreKind::S3 => "s3",
+            StoreKind::Gcs => "gs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactStore {
+    pub kind: StoreKind,
+    pub bucket: String,
+    /// Without leading or trailing slashes; empty for the bucket root.
+    pub prefix: String,
+}
+
+impl FromStr for ArtifactStore {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = if let Some(rest) = s.strip_prefix("s3://") {
+            (StoreKind::S3, rest)
+        } else if let Some(rest) = s.strip_prefix("gs://") {
+            (StoreKind::Gcs, rest)
+        } else {
+            return Err(format!(
+                "unsupported artifact store '{}', expected s3://bucket/prefix or gs://bucket/prefix",
+                s
+            ));
+        };
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!("no bucket in artifact store '{}'", s));
+        }
+        Ok(ArtifactStore {
+            kind,
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+}
+
+impl Display for ArtifactStore {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}", self.url(""))
+    }
+}
+
+impl ArtifactStore {
+    /// The URL of `key` below the prefix.
+    pub fn url(
+        &self,
+        key: &str,
+    ) -> String {
+        let path: Vec<&str> = [self.prefix.as_str(), key.trim_matches('/')]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect();
+        format!(
+            "{}://{}/{}",
+            self.kind.scheme(),
+            self.bucket,
+            path.join("/")
+        )
+    }
+
+    /// The CLI invocation copying `from` to `to`, one of them a bucket URL.
+    fn copy_command(
+        &self,
+        from: &str,
+        to: &str,
+        recursive: bool,
+    ) -> (&'static str, Vec<String>) {
+        let mut args: Vec<String> = match self.kind {
+            StoreKind::S3 => vec!["s3".to_string(), "cp".to_string()],
+            StoreKind::Gcs => vec!["storage".to_string(), "cp".to_string()],
+        };
+        if recursive {
+            args.push("--recursive".to_string());
+        }
+        args.push(from.to_string());
+        args.push(to.to_string());
+        let program = match self.kind {
+            StoreKind::S3 => "aws",
+            StoreKind::Gcs => "gcloud",
+        };
+        (program, args)
+    }
+
+    async fn copy(
+        &self,
+        from: &str,
+        to: &str,
+        recursive: bool,
+    ) -> BenchmarkResult<()> {
+        let (program, args) = self.copy_command(from, to, recursive);
+        info!("Copying {} to {}", from, to);
+        let output = Command::new(program)
+            .args(&args)
+            .output()
+            .await
+            .map_err(|e| OtherError(format!("Failed to run {}: {}", program, e)))?;
+        if !output.status.success() {
+            return Err(OtherError(format!(
+                "{} {} failed: {}",
+                program,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Upload the file or directory `local` to `key`.
+    pub async fn upload(
+        &self,
+        local: &Path,
+        key: &str,
+    ) -> BenchmarkResult<()> {
+        let recursive = tokio::fs::metadata(local)
+            .await
+            .map_err(|e| OtherError(format!("Failed reading {}: {}", local.display(), e)))?
+            .is_dir();
+        self.copy(&local.to_string_lossy(), &self.url(key), recursive)
+            .await
+    }
+
+    /// Download the file at `key` to `local`.
+    pub async fn download(
+        &self,
+        key: &str,
+        local: &Path,
+    ) -> BenchmarkResult<()> {
+        if let Some(parent) = local.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        self.copy(&self.url(key), &local.to_string_lossy(), false)
+            .await
+    }
+
+    /// Upload the dump of `spec` and its manifest; a missing local file is skipped.
+    pub async fn upload_dump(
+        &self,
+        spec: &Spec<'_>,
+    ) -> BenchmarkResult<()> {
+        let mut uploaded = 0;
+        for (local, key) in dump_files(spec) {
+            if tokio::fs::metadata(&local).await.is_ok() {
+                self.upload(&local, &key).await?;
+                uploaded += 1;
+            }
+        }
+        if uploaded == 0 {
+            warn!(
+                "No local {} {} dump to upload to {}",
+                spec.vendor, spec.size, self
+            );
+        }
+        Ok(())
+    }
+
+    /// Download the dump of `spec` and its manifest unless a local dump is already present.
+    /// Failing downloads are only logged, so the caller falls back to loading the dataset.
+    pub async fn download_dump(
+        &self,
+        spec: &Spec<'_>,
+    ) {
+        let files = dump_files(spec);
+        if tokio::fs::metadata(&files[0].0).await.is_ok() {
+            return;
+        }
+        for (local, key) in files {
+            if let Err(e) = self.download(&key, &local).await {
+                warn!("Couldn't download {}: {}", self.url(&key), e);
+                return;
+            }
+        }
+    }
+
+    /// Upload a finished run's directory below `results/<results-dir>/`.
+    pub async fn upload_results(
+        &self,
+        results_dir: &Path,
+        run_dir: &Path,
+    ) -> BenchmarkResult<()> {
+        self.upload(run_dir, &results_key(results_dir, run_dir))
+            .await
+    }
+}
+
+/// The local dump files of `spec`, the dump itself first, with their keys in the store.
+pub fn dump_files(spec: &Spec<'_>) -> Vec<(PathBuf, String)> {
+    let dir = format!("dumps/{}/{}/{}", spec.vendor, spec.name, spec.size);
+    let (dump, manifest, dump_name) = match spec.vendor {
+        Vendor::Falkor => (
+            PathBuf::from(format!("{}/{}_dump.rdb", REDIS_DATA_DIR, spec.size)),
+            PathBuf::from(format!("{}/{}_manifest.json", REDIS_DATA_DIR, spec.size)),
+            "dump.rdb",
+        ),
+        Vendor::Neo4j => (
+            Path::new(&spec.backup_path()).join("neo4j.dump"),
+            Path::new(&spec.backup_path()).join(MANIFEST_FILE),
+            "neo4j.dump",
+        ),
+        Vendor::Memgraph => (
+            Path::new(&spec.backup_path()).join(SNAPSHOT_FILE),
+            Path::new(&spec.backup_path()).join(MANIFEST_FILE),
+            SNAPSHOT_FILE,
+        ),
+    };
+    vec![
+        (dump, format!("{}/{}", dir, dump_name)),
+        (manifest, format!("{}/{}", dir, MANIFEST_FILE)),
+    ]
+}
+
+/// `results/<results-dir name>/<run dir below it>`.
+fn results_key(
+    results_dir: &Path,
+    run_dir: &Path,
+) -> String {
+    let name = results_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let below = run_dir.strip_prefix(results_dir).unwrap_or(run_dir);
+    format!("results/{}/{}", name, below.to_string_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::{Name, Size};
+
+    #[test]
+    fn store_urls_and_commands() {
+        let store: ArtifactStore = "s3://bench-artifacts/ci/".parse().unwrap();
+        assert_eq!(store.prefix, "ci");
+        assert_eq!(
+            store.url("dumps/falkor/users/small/dump.rdb"),
+            "s3://bench-artifacts/ci/dumps/falkor/users/small/dump.rdb"
+        );
+        let (program, args) = store.copy_command("out", &store.url("results/x"), true);
+        assert_eq!(program, "aws");
+        assert_eq!(
+            args,
+            vec![
+                "s3",
+                "cp",
+                "--recursive",
+                "out",
+                "s3://bench-artifacts/ci/results/x"
+            ]
+        );
+
+        let gcs: ArtifactStore = "gs://shared".parse().unwrap();
+        assert_eq!(gcs.url("a/b"), "gs://shared/a/b");
+        assert_eq!(gcs.copy_command("x", "y", false).0, "gcloud");
+        assert!("file:///tmp".parse::<ArtifactStore>().is_err());
+        assert!("s3:///prefix".parse::<ArtifactStore>().is_err());
+
+        let files = dump_files(&Spec::new(Name::Users, Size::Small, Vendor::Falkor));
+        assert_eq!(files[0].1, "dumps/falkor/users/small/dump.rdb");
+        assert_eq!(
+            results_key(
+                Path::new("Results-261016-15:45"),
+                Path::new("Results-261016-15:45/neo4j/run-2")
+            ),
+            "results/Results-261016-15:45/neo4j/run-2"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! `manifest.json` next to every backup: what was loaded, by which server version and loader
+//! options, so a restore can refuse a dump made for another dataset, size or vendor instead of
+//! silently benchmarking the wrong graph.
+//!
+//! FalkorDB dumps all live in `redis-data/`, so theirs is `<size>_manifest.json` beside
+//! `<size>_dump.rdb`; Neo4j and Memgraph keep `manifest.json` in the spec's backup directory.
+//! Dumps saved before manifests existed are restored with a warning.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::queries_repository::QueryCoverageProfile;
+use crate::scenario::Spec;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tracing::{info, warn};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// How `load` built the graph that was dumped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoaderOptions {
+    pub batch_size: usize,
+    pub query_profile: QueryCoverageProfile,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub vendor: String,
+    pub dataset: String,
+    pub size: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor_version: Option<String>,
+    pub nodes: u64,
+    pub edges: u64,
+    pub created_at_epoch_secs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader: Option<LoaderOptions>,
+    /// FalkorDB: `GRAPH.MEMORY USAGE` right after the load, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_dataset_bytes: Option<u64>,
+}
+
+impl BackupManifest {
+    /// A manifest for a dump of `spec` taken now.
+    pub fn new(
+        spec: &Spec<'_>,
+        nodes: u64,
+        edges: u64,
+    ) -> Self {
+        BackupManifest {
+            vendor: spec.vendor.to_string(),
+            dataset: spec.name.to_string(),
+            size: spec.size.to_string(),
+            vendor_version: None,
+            nodes,
+            edges,
+            created_at_epoch_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            loader: None,
+            base_dataset_bytes: None,
+        }
+    }
+
+    pub async fn write(
+        &self,
+        path: &Path,
+    ) -> BenchmarkResult<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .await
+            .map_err(|e| OtherError(format!("Failed writing {}: {}", path.display(), e)))?;
+        info!("Wrote backup manifest {}", path.display());
+        Ok(())
+    }
+
+    /// Refuse a dump made for another vendor, dataset or size than `spec`.
+    pub fn validate(
+        &self,
+        spec: &Spec<'_>,
+    ) -> BenchmarkResult<()> {
+        let expected = [
+            ("vendor", spec.vendor.to_string(), &self.vendor),
+            ("dataset", spec.name.to_string(), &self.dataset),
+            ("size", spec.size.to_string(), &self.size),
+        ];
+        let mismatches: Vec<String> = expected
+            .iter()
+            .filter(|(_, expected, actual)| expected != *actual)
+            .map(|(field, expected, actual)| {
+                format!("{} is {} but the run expects {}", field, actual, expected)
+            })
+            .collect();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(OtherError(format!(
+                "Refusing to restore a mismatched backup: {}",
+                mismatches.join(", ")
+            )))
+        }
+    }
+}
+
+/// Read the manifest at `path` and validate it against `spec` before a restore. `None` when the
+/// dump has no manifest.
+pub async fn check_before_restore(
+    path: &Path,
+    spec: &Spec<'_>,
+) -> BenchmarkResult<Option<BackupManifest>> {
+    let raw = match fs::read_to_string(path).await {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            warn!(
+                "No backup manifest at {}; restoring without checking the dump matches the run",
+                path.display()
+            );
+            return Ok(None);
+        }
+        Err(e) => {
+            return Err(OtherError(format!(
+                "Failed reading {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    let manifest: BackupManifest = serde_json::from_str(&raw)
+        .map_err(|e| OtherError(format!("Invalid backup manifest {}: {}", path.display(), e)))?;
+    manifest.validate(spec)?;
+    info!(
+        "Restoring {} {} {} backup of {} nodes and {} edges",
+        manifest.vendor, manifest.dataset, manifest.size, manifest.nodes, manifest.edges
+    );
+    Ok(Some(manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::{Name, Size, Vendor};
+
+    #[test]
+    fn a_dump_of_another_size_is_refused() {
+        let small = Spec::new(Name::Users, Size::Small, Vendor::Falkor);
+        let large = Spec::new(Name::Users, Size::Large, Vendor::Falkor);
+        let mut manifest = BackupManifest::new(&small, 10_000, 121_716);
+        manifest.loader = Some(LoaderOptions {
+            batch_size: 1000,
+            query_profile: QueryCoverageProfile::default(),
+        });
+        assert!(manifest.validate(&small).is_ok());
+        let err = manifest.validate(&large).unwrap_err().to_string();
+        assert!(
+            err.contains("size is small but the run expects large"),
+            "{}",
+            err
+        );
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert!(!json.contains("base_dataset_bytes"));
+        let parsed: BackupManifest = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, manifest);
+    }
+}
@@ -0,0 +1,139 @@
+//! Waiting for a freshly started Neo4j or Memgraph: connect over Bolt and run `RETURN 1` until
+//! the server answers, backing off between attempts, instead of sleeping a fixed time and hoping.
+//! The server process dying during startup ends the wait at once.
+
+use crate::error::BenchmarkError::{Neo4rsError, OtherError};
+use crate::error::BenchmarkResult;
+use neo4rs::{query, ConfigBuilder, Graph};
+use std::process::Child;
+use std::time::Duration;
+use tokio::time::{sleep, timeout, Instant};
+use tracing::{info, trace};
+
+/// How long a server gets to answer unless `BOLT_READY_TIMEOUT_SECS` says otherwise.
+pub const DEFAULT_READY_BUDGET: Duration = Duration::from_secs(120);
+/// Longest a single connection attempt may take.
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The delays between attempts: 100ms, doubling up to 2s.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    next: Duration,
+    max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            next: Duration::from_millis(100),
+            max: Duration::from_secs(2),
+        }
+    }
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        Some(delay)
+    }
+}
+
+/// Where and as whom to reach the server's Bolt port.
+pub struct BoltReadiness {
+    pub vendor: &'static str,
+    pub uri: String,
+    pub user: String,
+    pub password: String,
+    pub db: Option<String>,
+    pub budget: Duration,
+}
+
+impl BoltReadiness {
+    pub fn new(
+        vendor: &'static str,
+        uri: &str,
+        user: &str,
+        password: &str,
+        db: Option<&str>,
+    ) -> Self {
+        let budget = std::env::var("BOLT_READY_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map_or(DEFAULT_READY_BUDGET, Duration::from_secs);
+        BoltReadiness {
+            vendor,
+            uri: uri.to_string(),
+            user: user.to_string(),
+            password: password.to_string(),
+            db: db.map(str::to_string),
+            budget,
+        }
+    }
+
+    async fn probe(&self) -> BenchmarkResult<()> {
+        let mut config = ConfigBuilder::default()
+            .uri(&self.uri)
+            .user(&self.user)
+            .password(&self.password)
+            .max_connections(1);
+        if let Some(db) = &self.db {
+            config = config.db(db.as_str());
+        }
+        let graph = Graph::connect(config.build().map_err(Neo4rsError)?)
+            .await
+            .map_err(Neo4rsError)?;
+        graph.run(query("RETURN 1")).await.map_err(Neo4rsError)?;
+        Ok(())
+    }
+
+    /// Wait until the server answers `RETURN 1`; returns how long that took.
+    pub async fn wait(
+        &self,
+        server: &mut Child,
+    ) -> BenchmarkResult<Duration> {
+        let start = Instant::now();
+        let mut backoff = Backoff::default();
+        loop {
+            if let Some(status) = server.try_wait()? {
+                return Err(OtherError(format!(
+                    "{} exited with {} while starting up",
+                    self.vendor, status
+                )));
+            }
+            let error = match timeout(ATTEMPT_TIMEOUT, self.probe()).await {
+                Ok(Ok(())) => {
+                    let waited = start.elapsed();
+                    info!("{} answered over Bolt after {:?}", self.vendor, waited);
+                    return Ok(waited);
+                }
+                Ok(Err(e)) => e.to_string(),
+                Err(_) => format!("no answer within {:?}", ATTEMPT_TIMEOUT),
+            };
+            if start.elapsed() >= self.budget {
+                return Err(OtherError(format!(
+                    "{} didn't answer over Bolt at {} within {:?}: {}",
+                    self.vendor, self.uri, self.budget, error
+                )));
+            }
+            trace!("{} not ready yet: {}", self.vendor, error);
+            sleep(backoff.next().unwrap_or_default()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_its_cap() {
+        let delays: Vec<u64> = Backoff::default()
+            .take(7)
+            .map(|d| d.as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1600, 2000, 2000]);
+    }
+}
@@ -0,0 +1,221 @@
+//! `generate-queries --bounded-writes <n>` keeps a long mixed run on the dataset that was loaded.
+//! The writes overwrite users' `age` and `rpc_social_credit` with ids, remove properties, delete
+//! users and create new ones, so over a multi-hour run each vendor is measured on a graph that has
+//! drifted further from the dataset with every write.
+//!
+//! While a chunk of the file is generated, the users each write changes or creates are tracked.
+//! After `n` of them, and at the end of the chunk, compensating writes follow in the file:
+//! [`DROP_CREATED_USER_QUERY_NAME`] for every user the workload created, then
+//! [`RESTORE_USER_QUERY_NAME`] for every user it changed. A restore copies the properties of a
+//! user the pending writes didn't touch, which still holds the loaded values, so restored ages
+//! and credit scores are drawn from the dataset's own distributions. A deleted user is recreated
+//! the same way, without its friendships; edges are not compensated.
+
+use crate::queries_repository::PreparedQuery;
+use crate::query::{Query, QueryBuilder, QueryParam};
+use rand::{Rng, RngExt};
+use std::collections::BTreeSet;
+
+pub const RESTORE_USER_QUERY_NAME: &str = "bounded_restore_user";
+pub const DROP_CREATED_USER_QUERY_NAME: &str = "bounded_drop_created_user";
+
+/// Gives `$id` the properties of `$source`, creating it again when it was deleted.
+pub const RESTORE_USER_TEXT: &str = "MATCH (s:User {id: $source}) MERGE (u:User {id: $id}) SET u = properties(s), u.id = $id RETURN u.id";
+/// Deletes the users the workload created under `$id`: those a `MERGE` stamped with `created_at`
+/// and those a `CREATE` left with nothing but their id.
+pub const DROP_CREATED_USER_TEXT: &str =
+    "MATCH (u:User {id: $id}) WHERE u.created_at IS NOT NULL OR size(keys(u)) = 1 DETACH DELETE u";
+
+/// Attempts at drawing a restore's source among the users with no pending compensation.
+const SOURCE_ATTEMPTS: usize = 64;
+
+/// Whether `name` is one of the compensating writes, which are emitted and never drawn.
+pub fn is_compensation_query_name(name: &str) -> bool {
+    name == RESTORE_USER_QUERY_NAME || name == DROP_CREATED_USER_QUERY_NAME
+}
+
+/// What a write does to the users its parameters name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mutation {
+    /// Overwrites or removes their properties, or deletes them.
+    Changed,
+    /// Creates a user that wasn't loaded.
+    Created,
+}
+
+/// The mutation of write `q_name` and the parameters holding the ids of the users it mutates.
+fn mutation(q_name: &str) -> Option<(Mutation, &'static [&'static str])> {
+    match q_name {
+        "single_vertex_update"
+        | "merge_user_upsert_existing"
+        | "detach_delete_user"
+        | "remove_user_property_and_label"
+        | "foreach_loop_mutation"
+        | "tx_transfer_social_credit" => Some((Mutation::Changed, &["id"])),
+        "tx_befriend_and_touch" => Some((Mutation::Changed, &["from", "to"])),
+        "single_vertex_write" | "merge_user_insert_path" => Some((Mutation::Created, &["id"])),
+        _ => None,
+    }
+}
+
+/// The users mutated since the last compensation, in one chunk of the generated file.
+#[derive(Debug)]
+pub struct MutationTracker {
+    every: usize,
+    vertices: i32,
+    changed: BTreeSet<i32>,
+    created: BTreeSet<i32>,
+    /// Mutations recorded since the last compensation, a user counted once per write.
+    pending: usize,
+}
+
+impl MutationTracker {
+    /// Compensates after every `every` mutations of the users `1..=vertices`.
+    pub fn new(
+        every: u32,
+        vertices: i32,
+    ) -> Self {
+        MutationTracker {
+            every: every.max(1) as usize,
+            vertices,
+            changed: BTreeSet::new(),
+            created: BTreeSet::new(),
+            pending: 0,
+        }
+    }
+
+    /// Records the users `query` mutates; `true` once `every` mutations are waiting.
+    pub fn record(
+        &mut self,
+        query: &PreparedQuery,
+    ) -> bool {
+        let Some((kind, names)) = mutation(&query.q_name) else {
+            return false;
+        };
+        let statements: Vec<_> = if query.is_transaction() {
+            query
+                .statements
+                .iter()
+                .map(|statement| &statement.bolt)
+                .collect()
+        } else {
+            vec![&query.bolt]
+        };
+        for (name, value) in statements.iter().flat_map(|bolt| bolt.params.iter()) {
+            if let (true, QueryParam::Integer(id)) = (names.contains(&name.as_str()), value) {
+                match kind {
+                    Mutation::Changed => self.changed.insert(*id),
+                    Mutation::Created => self.created.insert(*id),
+                };
+                self.pending += 1;
+            }
+        }
+        self.pending >= self.every
+    }
+
+    /// The compensating writes for the mutations recorded so far, as `(catalog name, query)`,
+    /// drops before restores; a restore's source is drawn from `rng`.
+    pub fn drain(
+        &mut self,
+        rng: &mut dyn Rng,
+    ) -> Vec<(&'static str, Query)> {
+        let mut compensations = Vec::with_capacity(self.created.len() + self.changed.len());
+        for id in &self.created {
+            compensations.push((
+                DROP_CREATED_USER_QUERY_NAME,
+                QueryBuilder::new()
+                    .text(DROP_CREATED_USER_TEXT)
+                    .param("id", *id)
+                    .build(),
+            ));
+        }
+        for id in &self.changed {
+            let source = self.draw_source(rng);
+            compensations.push((
+                RESTORE_USER_QUERY_NAME,
+                QueryBuilder::new()
+                    .text(RESTORE_USER_TEXT)
+                    .param("id", *id)
+                    .param("source", source)
+                    .build(),
+            ));
+        }
+        self.changed.clear();
+        self.created.clear();
+        self.pending = 0;
+        compensations
+    }
+
+    /// A loaded user with no pending compensation, or the last one drawn when the pending ones
+    /// crowd the dataset.
+    fn draw_source(
+        &self,
+        rng: &mut dyn Rng,
+    ) -> i32 {
+        let vertices = self.vertices.max(1);
+        let mut source = rng.random_range(1..=vertices);
+        for _ in 1..SOURCE_ATTEMPTS {
+            if !self.changed.contains(&source) && !self.created.contains(&source) {
+                break;
+            }
+            source = rng.random_range(1..=vertices);
+        }
+        source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queries_repository::QueryType;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn write(
+        name: &str,
+        id: i32,
+    ) -> PreparedQuery {
+        PreparedQuery::new(
+            0,
+            name.to_string(),
+            QueryType::Write,
+            QueryBuilder::new()
+                .text("RETURN $id")
+                .param("id", id)
+                .build(),
+        )
+    }
+
+    #[test]
+    fn mutated_users_are_dropped_or_restored_from_untouched_ones() {
+        let mut tracker = MutationTracker::new(3, 10);
+        assert!(!tracker.record(&write("single_vertex_read", 1)));
+        assert!(!tracker.record(&write("single_vertex_update", 4)));
+        assert!(!tracker.record(&write("merge_user_insert_path", 17)));
+        assert!(tracker.record(&write("single_vertex_write", 2)));
+
+        let compensations = tracker.drain(&mut StdRng::seed_from_u64(7));
+        let ids = |name: &str| -> Vec<(i32, Option<i32>)> {
+            compensations
+                .iter()
+                .filter(|(q_name, _)| *q_name == name)
+                .map(|(_, query)| {
+                    let param = |key: &str| match query.params.get(key) {
+                        Some(QueryParam::Integer(i)) => Some(*i),
+                        _ => None,
+                    };
+                    (param("id").unwrap(), param("source"))
+                })
+                .collect()
+        };
+        assert_eq!(ids(DROP_CREATED_USER_QUERY_NAME), [(2, None), (17, None)]);
+        let restores = ids(RESTORE_USER_QUERY_NAME);
+        assert_eq!(restores.iter().map(|(id, _)| *id).collect::<Vec<_>>(), [4]);
+        for (_, source) in restores {
+            let source = source.unwrap();
+            assert!((1..=10).contains(&source) && ![2, 4, 17].contains(&source));
+        }
+        assert_eq!(compensations[0].0, DROP_CREATED_USER_QUERY_NAME);
+        assert!(tracker.drain(&mut StdRng::seed_from_u64(7)).is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::utils::spawn_command;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// Full scans run by `--warm` before the measured phase: every user's properties, then every
+/// friendship with both endpoints, so node, property and relationship storage are all touched.
+pub const WARMUP_QUERIES: [&str; 2] = [
+    "MATCH (n:User) RETURN count(n.id) AS users, sum(n.age) AS ages",
+    "MATCH (n:User)-[:Friend]->(m:User) RETURN count(m.id) AS friendships",
+];
+
+/// How the server's caches are prepared before the measured phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheMode {
+    /// Whatever state the restore and setup steps left behind.
+    #[default]
+    Uncontrolled,
+    /// Restart the locally managed server and drop OS page caches.
+    Cold,
+    /// Run [`WARMUP_QUERIES`] first.
+    Warm,
+}
+
+impl CacheMode {
+    pub fn from_flags(
+        cold_start: bool,
+        warm: bool,
+    ) -> Self {
+        match (cold_start, warm) {
+            (true, _) => CacheMode::Cold,
+            (false, true) => CacheMode::Warm,
+            (false, false) => CacheMode::Uncontrolled,
+        }
+    }
+}
+
+/// What was actually done for the requested [`CacheMode`]; recorded in meta.json.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CachePreparation {
+    pub mode: CacheMode,
+    pub server_restarted: bool,
+    pub os_page_cache_dropped: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warmup_ms: Option<u128>,
+}
+
+impl CachePreparation {
+    pub fn new(mode: CacheMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+}
+
+/// Flush dirty pages and drop the OS page caches: the page, dentry and inode caches on Linux,
+/// the unified buffer cache via `purge` on macOS. Best-effort: both need root and other systems
+/// have no equivalent, so failures are logged and reported as `false`.
+pub async fn drop_os_page_caches() -> bool {
+    if !cfg!(any(target_os = "linux", target_os = "macos")) {
+        warn!("Dropping OS page caches is only supported on Linux and macOS");
+        return false;
+    }
+    if let Err(e) = spawn_command("sync", &[]).await {
+        warn!("Failed to sync before dropping OS page caches: {}", e);
+    }
+    if cfg!(target_os = "macos") {
+        return match spawn_command("purge", &[]).await {
+            Ok(_) => {
+                info!("Dropped OS page caches");
+                true
+            }
+            Err(e) => {
+                warn!("Failed to drop OS page caches (purge needs root): {}", e);
+                false
+            }
+        };
+    }
+    match tokio::fs::write("/proc/sys/vm/drop_caches", "3\n").await {
+        Ok(()) => {
+            info!("Dropped OS page caches");
+            true
+        }
+        Err(e) => {
+            warn!(
+                "Failed to drop OS page caches (writing /proc/sys/vm/drop_caches needs root): {}",
+                e
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_mode_from_flags_and_meta_shape() {
+        assert_eq!(CacheMode::from_flags(false, false), CacheMode::Uncontrolled);
+        assert_eq!(CacheMode::from_flags(true, false), CacheMode::Cold);
+        assert_eq!(CacheMode::from_flags(false, true), CacheMode::Warm);
+
+        let mut prepared = CachePreparation::new(CacheMode::Warm);
+        prepared.warmup_ms = Some(1200);
+        assert_eq!(
+            serde_json::to_value(&prepared).unwrap(),
+            serde_json::json!({
+                "mode": "warm",
+                "server_restarted": false,
+                "os_page_cache_dropped": false,
+                "warmup_ms": 1200
+            })
+        );
+        let cold = serde_json::to_value(CachePreparation::new(CacheMode::Cold)).unwrap();
+        assert!(cold.get("warmup_ms").is_none());
+    }
+}
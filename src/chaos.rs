@@ -0,0 +1,290 @@
+use crate::error::BenchmarkResult;
+use crate::throughput::ThroughputTracker;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// What `--chaos` does to the locally managed server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChaosAction {
+    /// SIGKILL the server process, then bring it back (FalkorDB's process monitor restarts it
+    /// on its own; Neo4j and Memgraph are restarted by the benchmark).
+    KillServer,
+}
+
+impl fmt::Display for ChaosAction {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            ChaosAction::KillServer => write!(f, "kill-server"),
+        }
+    }
+}
+
+/// `--chaos <action>:<interval>`, e.g. `kill-server:300s`: perform `action` every `interval`
+/// of the measured phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChaosSpec {
+    pub action: ChaosAction,
+    pub interval: Duration,
+}
+
+impl FromStr for ChaosSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (action, interval) = s
+            .split_once(':')
+            .ok_or_else(|| format!("invalid chaos spec '{}', expected e.g. kill-server:300s", s))?;
+        let action = match action.trim() {
+            "kill-server" => ChaosAction::KillServer,
+            other => {
+                return Err(format!(
+                    "unknown chaos action '{}', expected kill-server",
+                    other
+                ))
+            }
+        };
+        let interval = parse_chaos_interval(interval)?;
+        Ok(ChaosSpec { action, interval })
+    }
+}
+
+impl fmt::Display for ChaosSpec {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        if self.interval.subsec_millis() == 0 {
+            write!(f, "{}:{}s", self.action, self.interval.as_secs())
+        } else {
+            write!(f, "{}:{}ms", self.action, self.interval.as_millis())
+        }
+    }
+}
+
+fn parse_chaos_interval(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(idx) => s.split_at(idx),
+        None => (s, "s"),
+    };
+    let value: u64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid chaos interval '{}', expected e.g. 300s or 5m", s))?;
+    let interval = match unit {
+        "ms" => Duration::from_millis(value),
+        "s" => Duration::from_secs(value),
+        "m" => Duration::from_secs(value * 60),
+        other => {
+            return Err(format!(
+                "unknown chaos interval unit '{}', expected ms, s or m",
+                other
+            ))
+        }
+    };
+    if interval.is_zero() {
+        return Err("chaos interval must be positive".to_string());
+    }
+    Ok(interval)
+}
+
+/// One injected failure and how the workload came back from it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChaosEvent {
+    /// Offset of the kill from the start of the measured phase.
+    pub at_ms: u64,
+    /// Time from the kill until the first successful query; `None` if the run ended first.
+    pub recovery_ms: Option<u64>,
+    /// Failed queries between the kill and that first success.
+    pub error_burst: u64,
+}
+
+/// The `--chaos` spec and its events; recorded in meta.json.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChaosReport {
+    pub spec: String,
+    pub events: Vec<ChaosEvent>,
+}
+
+/// Matches query completions against injected failures. Workers feed it every completion;
+/// only failures still waiting for their first success are touched, so it stays cheap.
+#[derive(Debug, Default)]
+pub struct RecoveryTracker {
+    events: Mutex<Vec<ChaosEvent>>,
+}
+
+impl RecoveryTracker {
+    pub fn mark_failure_injected(
+        &self,
+        at: Duration,
+    ) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push(ChaosEvent {
+                at_ms: at.as_millis() as u64,
+                ..Default::default()
+            });
+        }
+    }
+
+    pub fn record(
+        &self,
+        at: Duration,
+        success: bool,
+    ) {
+        let Ok(mut events) = self.events.lock() else {
+            return;
+        };
+        let at_ms = at.as_millis() as u64;
+        for event in events
+            .iter_mut()
+            .rev()
+            .take_while(|event| event.recovery_ms.is_none())
+        {
+            if at_ms < event.at_ms {
+                continue;
+            }
+            if success {
+                event.recovery_ms = Some(at_ms - event.at_ms);
+            } else {
+                event.error_burst += 1;
+            }
+        }
+    }
+
+    pub fn events(&self) -> Vec<ChaosEvent> {
+        self.events
+            .lock()
+            .map(|events| events.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Background task injecting `spec` every interval. It owns `target` (e.g. the local server
+/// handle needed to restart it) and hands it back from [`ChaosHandle::stop`].
+pub struct ChaosHandle<T> {
+    spec: ChaosSpec,
+    throughput: Arc<ThroughputTracker>,
+    handle: JoinHandle<T>,
+    shutdown_tx: tokio::sync::oneshot::Sender<()>,
+}
+
+impl<T> ChaosHandle<T> {
+    pub async fn stop(self) -> (Option<T>, ChaosReport) {
+        drop(self.shutdown_tx);
+        let target = self.handle.await.ok();
+        let report = ChaosReport {
+            spec: self.spec.to_string(),
+            events: self.throughput.chaos_events(),
+        };
+        (target, report)
+    }
+}
+
+/// `inject` performs the action and returns the target back alongside the outcome; failures are
+/// logged and the schedule keeps going. Recovery is measured from the completions `throughput`
+/// sees, so it must have been built with recovery tracking.
+pub fn spawn_chaos<T, F, FUTURE>(
+    spec: ChaosSpec,
+    throughput: Arc<ThroughputTracker>,
+    target: T,
+    inject: F,
+) -> ChaosHandle<T>
+where
+    T: Send + 'static,
+    F: Fn(T) -> FUTURE + Send + 'static,
+    FUTURE: Future<Output = (T, BenchmarkResult<()>)> + Send + 'static,
+{
+    let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel();
+    let tracker = Arc::clone(&throughput);
+    let handle = tokio::spawn(async move {
+        let mut target = target;
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(spec.interval) => {
+                    warn!("chaos: injecting {}", spec.action);
+                    tracker.mark_failure_injected();
+                    let (returned, result) = inject(target).await;
+                    target = returned;
+                    match result {
+                        Ok(()) => info!("chaos: {} done", spec.action),
+                        Err(e) => error!("chaos: {} failed: {}", spec.action, e),
+                    }
+                }
+                _ = &mut shutdown_rx => {
+                    return target;
+                }
+            }
+        }
+    });
+    ChaosHandle {
+        spec,
+        throughput,
+        handle,
+        shutdown_tx,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_chaos_spec() {
+        let spec: ChaosSpec = "kill-server:300s".parse().unwrap();
+        assert_eq!(spec.action, ChaosAction::KillServer);
+        assert_eq!(spec.interval, Duration::from_secs(300));
+        assert_eq!(
+            "kill-server:5m".parse::<ChaosSpec>().unwrap().interval,
+            Duration::from_secs(300)
+        );
+        assert_eq!(
+            "kill-server:90".parse::<ChaosSpec>().unwrap().interval,
+            Duration::from_secs(90)
+        );
+        assert!("kill-server".parse::<ChaosSpec>().is_err());
+        assert!("pause-server:10s".parse::<ChaosSpec>().is_err());
+        assert!("kill-server:0s".parse::<ChaosSpec>().is_err());
+    }
+
+    #[test]
+    fn recovery_counts_errors_until_first_success() {
+        let tracker = RecoveryTracker::default();
+        let ms = Duration::from_millis;
+
+        tracker.record(ms(50), true);
+        tracker.mark_failure_injected(ms(100));
+        tracker.record(ms(120), false);
+        tracker.record(ms(130), false);
+        tracker.record(ms(900), true);
+        // Errors after recovery don't grow the burst.
+        tracker.record(ms(950), false);
+        tracker.mark_failure_injected(ms(2_000));
+        tracker.record(ms(2_100), false);
+
+        assert_eq!(
+            tracker.events(),
+            vec![
+                ChaosEvent {
+                    at_ms: 100,
+                    recovery_ms: Some(800),
+                    error_burst: 2,
+                },
+                ChaosEvent {
+                    at_ms: 2_000,
+                    recovery_ms: None,
+                    error_burst: 1,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,394 @@
+//! What the workers do when queries fail. Each worker counts its consecutive failures and waits
+//! before its next query, `--error-backoff 50ms..3s` doubling the wait from 50ms with every
+//! failure in a row up to 3s, so a server that's down isn't flooded by workers spinning on
+//! errors. The schedule keeps going meanwhile: the queries due during a backoff run late and
+//! their latency shows it.
+//!
+//! `--circuit-breaker 50%/5s` pauses dispatch for the whole run: once more than 50% of the
+//! queries completed over the last 5 full seconds failed, the breaker opens for 5 seconds and the
+//! workers shed the queries due meanwhile instead of sending them. Another trip then takes a
+//! fresh full window. meta.json records every opening.
+
+use crate::error_budget::ErrorBudget;
+use crate::query_timeout::parse_duration;
+use crate::run_control::RunControl;
+use crate::scheduler::Msg;
+use crate::throughput::ThroughputTracker;
+use crate::{
+    CIRCUIT_BREAKER_OPEN, CIRCUIT_BREAKER_OPEN_SECONDS, CIRCUIT_BREAKER_SHED_QUERIES,
+    WORKERS_FAILING, WORKER_ERROR_BACKOFF_SECONDS,
+};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// `--error-backoff <initial>..<max>`, or `off`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Default for ErrorBackoff {
+    fn default() -> Self {
+        ErrorBackoff {
+            initial: Duration::from_millis(50),
+            max: Duration::from_secs(3),
+        }
+    }
+}
+
+impl ErrorBackoff {
+    pub fn is_off(&self) -> bool {
+        self.initial.is_zero()
+    }
+
+    /// The wait after the `failures`-th failure in a row.
+    fn after(
+        &self,
+        failures: u32,
+    ) -> Duration {
+        let doublings = failures.saturating_sub(1).min(31);
+        self.initial
+            .saturating_mul(1 << doublings)
+            .min(self.max.max(self.initial))
+    }
+}
+
+impl FromStr for ErrorBackoff {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "off" {
+            return Ok(ErrorBackoff {
+                initial: Duration::ZERO,
+                max: Duration::ZERO,
+            });
+        }
+        let (initial, max) = s.split_once("..").ok_or_else(|| {
+            format!(
+                "expected <initial>..<max>, e.g. 50ms..3s, or off, got '{}'",
+                s
+            )
+        })?;
+        let (initial, max) = (parse_duration(initial)?, parse_duration(max)?);
+        if initial.is_zero() || max < initial {
+            return Err(format!(
+                "the initial backoff must be positive and at most the maximum, got '{}'",
+                s
+            ));
+        }
+        Ok(ErrorBackoff { initial, max })
+    }
+}
+
+impl fmt::Display for ErrorBackoff {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        if self.is_off() {
+            return write!(f, "off");
+        }
+        write!(
+            f,
+            "{}ms..{}ms",
+            self.initial.as_millis(),
+            self.max.as_millis()
+        )
+    }
+}
+
+/// `--circuit-breaker <rate>%/<window>`: trips like `--abort-on-error-rate`, then stays open for
+/// the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircuitBreaker(pub ErrorBudget);
+
+impl FromStr for CircuitBreaker {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(CircuitBreaker)
+    }
+}
+
+/// One time the breaker paused dispatch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CircuitOpening {
+    /// Seconds into the measured phase.
+    pub at_second: u64,
+    pub open_ms: u64,
+    pub reason: String,
+}
+
+/// Recorded in meta.json as `error_backoff`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorBackoffReport {
+    /// `off`, or the initial and maximum wait.
+    pub backoff: String,
+    pub backoff_ms: u64,
+    pub max_consecutive_failures: u32,
+}
+
+/// Recorded in meta.json as `circuit_breaker`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CircuitBreakerReport {
+    pub max_rate: f64,
+    pub window_secs: u64,
+    pub openings: Vec<CircuitOpening>,
+    pub open_ms: u64,
+    pub shed: u64,
+}
+
+/// What the workers and the breaker share through [`RunControl::circuit`].
+#[derive(Debug, Default)]
+pub struct CircuitState {
+    open: AtomicBool,
+    openings: Mutex<Vec<CircuitOpening>>,
+    shed: AtomicU64,
+    backoff_us: AtomicU64,
+    max_consecutive_failures: AtomicU32,
+}
+
+impl CircuitState {
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::Relaxed)
+    }
+
+    fn open(
+        &self,
+        at_second: u64,
+        reason: String,
+    ) {
+        warn!("Circuit breaker open, shedding queries: {}", reason);
+        CIRCUIT_BREAKER_OPEN.set(1);
+        if let Ok(mut openings) = self.openings.lock() {
+            openings.push(CircuitOpening {
+                at_second,
+                open_ms: 0,
+                reason,
+            });
+        }
+        self.open.store(true, Ordering::Relaxed);
+    }
+
+    fn close(
+        &self,
+        open: Duration,
+    ) {
+        self.open.store(false, Ordering::Relaxed);
+        CIRCUIT_BREAKER_OPEN.set(0);
+        if let Ok(mut openings) = self.openings.lock() {
+            if let Some(opening) = openings.last_mut() {
+                opening.open_ms = open.as_millis() as u64;
+            }
+        }
+        info!("Circuit breaker closed after {:?}, dispatch resumes", open);
+    }
+
+    fn record_backoff(
+        &self,
+        pause: Duration,
+        failures: u32,
+    ) {
+        self.backoff_us
+            .fetch_add(pause.as_micros() as u64, Ordering::Relaxed);
+        self.max_consecutive_failures
+            .fetch_max(failures, Ordering::Relaxed);
+    }
+
+    pub fn backoff_report(
+        &self,
+        backoff: ErrorBackoff,
+    ) -> ErrorBackoffReport {
+        ErrorBackoffReport {
+            backoff: backoff.to_string(),
+            backoff_ms: self.backoff_us.load(Ordering::Relaxed) / 1000,
+            max_consecutive_failures: self.max_consecutive_failures.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn breaker_report(
+        &self,
+        breaker: CircuitBreaker,
+    ) -> CircuitBreakerReport {
+        let openings = self
+            .openings
+            .lock()
+            .map(|openings| openings.clone())
+            .unwrap_or_default();
+        CircuitBreakerReport {
+            max_rate: breaker.0.max_rate,
+            window_secs: breaker.0.window_secs,
+            open_ms: openings.iter().map(|opening| opening.open_ms).sum(),
+            openings,
+            shed: self.shed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Hold `msg` until it is due while the breaker is open; `true` when it is to be shed because
+/// the breaker still is.
+pub async fn shed<Payload: Send + Sync>(
+    control: &RunControl,
+    msg: &Msg<Payload>,
+) -> bool {
+    let circuit = control.circuit();
+    if !circuit.is_open() {
+        return false;
+    }
+    tokio::time::sleep_until(msg.intended_start()).await;
+    if !circuit.is_open() {
+        return false;
+    }
+    circuit.shed.fetch_add(1, Ordering::Relaxed);
+    CIRCUIT_BREAKER_SHED_QUERIES.inc();
+    true
+}
+
+/// One worker's run of failed queries.
+pub struct ConsecutiveFailures {
+    backoff: ErrorBackoff,
+    vendor: &'static str,
+    count: u32,
+}
+
+impl ConsecutiveFailures {
+    pub fn new(
+        backoff: ErrorBackoff,
+        vendor: &'static str,
+    ) -> Self {
+        ConsecutiveFailures {
+            backoff,
+            vendor,
+            count: 0,
+        }
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn succeeded(&mut self) {
+        if self.count > 0 {
+            WORKERS_FAILING.with_label_values(&[self.vendor]).dec();
+        }
+        self.count = 0;
+    }
+
+    /// Count a failure; the wait before the next query, `None` when backing off is off.
+    pub fn failed(
+        &mut self,
+        control: &RunControl,
+    ) -> Option<Duration> {
+        if self.count == 0 {
+            WORKERS_FAILING.with_label_values(&[self.vendor]).inc();
+        }
+        self.count = self.count.saturating_add(1);
+        if self.backoff.is_off() {
+            return None;
+        }
+        let pause = self.backoff.after(self.count);
+        control.circuit().record_backoff(pause, self.count);
+        WORKER_ERROR_BACKOFF_SECONDS
+            .with_label_values(&[self.vendor])
+            .inc_by(pause.as_secs_f64());
+        Some(pause)
+    }
+}
+
+impl Drop for ConsecutiveFailures {
+    fn drop(&mut self) {
+        self.succeeded();
+    }
+}
+
+/// Checks `control`'s circuit breaker against `throughput` every second.
+pub struct CircuitBreakerWatch {
+    handle: JoinHandle<()>,
+}
+
+impl CircuitBreakerWatch {
+    /// `None` when the run has no `--circuit-breaker`.
+    pub fn spawn(
+        control: &Arc<RunControl>,
+        throughput: &Arc<ThroughputTracker>,
+    ) -> Option<Self> {
+        let CircuitBreaker(trip) = control.circuit_breaker()?;
+        let control = control.clone();
+        let throughput = throughput.clone();
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(1));
+            // The seconds before `since` were judged already.
+            let mut since = 0u64;
+            let mut opened: Option<(u64, Instant)> = None;
+            while !control.is_stopped() {
+                ticker.tick().await;
+                let now = throughput.elapsed().as_secs();
+                match opened {
+                    Some((at, open_since)) => {
+                        CIRCUIT_BREAKER_OPEN_SECONDS.inc();
+                        if now >= at + trip.window_secs {
+                            control.circuit().close(open_since.elapsed());
+                            since = now;
+                            opened = None;
+                        }
+                    }
+                    None => {
+                        let samples = throughput.samples();
+                        let judged = samples.get(since as usize..).unwrap_or_default();
+                        if let Some(trip) = trip.check(judged, now.saturating_sub(since)) {
+                            control.circuit().open(now, trip.reason);
+                            opened = Some((now, Instant::now()));
+                        }
+                    }
+                }
+            }
+            if let Some((_, open_since)) = opened {
+                control.circuit().close(open_since.elapsed());
+            }
+        });
+        Some(CircuitBreakerWatch { handle })
+    }
+
+    pub fn stop(self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_its_maximum_and_resets_on_success() {
+        let backoff: ErrorBackoff = "50ms..300ms".parse().unwrap();
+        assert_eq!(backoff.to_string(), "50ms..300ms");
+        assert!("off".parse::<ErrorBackoff>().unwrap().is_off());
+        assert!("3s..50ms".parse::<ErrorBackoff>().is_err());
+        assert!("50ms".parse::<ErrorBackoff>().is_err());
+
+        let control = RunControl::new(100).with_error_backoff(backoff);
+        let mut failures = ConsecutiveFailures::new(backoff, "test");
+        let waits: Vec<_> = (0..5)
+            .map(|_| failures.failed(&control).unwrap().as_millis())
+            .collect();
+        assert_eq!(waits, [50, 100, 200, 300, 300]);
+        failures.succeeded();
+        assert_eq!(failures.failed(&control), Some(Duration::from_millis(50)));
+        let report = control.circuit().backoff_report(backoff);
+        assert_eq!(
+            (report.backoff_ms, report.max_consecutive_failures),
+            (1000, 5)
+        );
+
+        let mut off = ConsecutiveFailures::new("off".parse().unwrap(), "test");
+        assert_eq!(off.failed(&control), None);
+        assert_eq!(off.count(), 1);
+    }
+}
@@ -0,0 +1,217 @@
+//! `benchmark clean`: remove what `load`, `run` and `suite` leave in the working directory, so
+//! nobody has to remember where each vendor keeps its dumps.
+//!
+//! Only paths the benchmark itself writes are candidates: the dumps and manifests of each vendor
+//! and size, the dataset files `load` downloads to `cache/`, and the default `Results-*` and
+//! `Suite-*` result directories. A Neo4j or Memgraph installation under `downloads/` is never
+//! touched.
+
+use crate::artifact_store::dump_files;
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::scenario::{Name, Size, Spec, Vendor};
+use clap::ValueEnum;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What `clean --what` removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CleanTarget {
+    /// Dumps and their manifests: `redis-data/<size>_dump.rdb` and `backups/<vendor>/...`.
+    Dumps,
+    /// `Results-*` and `Suite-*` directories and `output.txt`.
+    Results,
+    /// Dataset files cached under `cache/<vendor>/...`.
+    Downloads,
+    All,
+}
+
+/// The artifacts of `what` below `root` that exist; `dataset` narrows dumps and downloads to one
+/// size. Results mix sizes, so they are only removed without `dataset`.
+pub fn artifacts(
+    root: &Path,
+    what: CleanTarget,
+    dataset: Option<Size>,
+) -> BenchmarkResult<Vec<PathBuf>> {
+    let sizes = dataset.map_or_else(|| Size::value_variants().to_vec(), |size| vec![size]);
+    let specs = || {
+        Vendor::ALL.into_iter().flat_map(|vendor| {
+            sizes
+                .iter()
+                .map(move |size| Spec::new(Name::Users, *size, vendor))
+        })
+    };
+    let mut paths = Vec::new();
+    if matches!(what, CleanTarget::Dumps | CleanTarget::All) {
+        for spec in specs() {
+            match spec.vendor {
+                Vendor::Falkor => paths.extend(dump_files(&spec).into_iter().map(|(path, _)| path)),
+                // The dump is one of several files in the spec's backup directory.
+                Vendor::Neo4j | Vendor::Memgraph => paths.push(PathBuf::from(spec.backup_path())),
+            }
+        }
+    }
+    if matches!(what, CleanTarget::Downloads | CleanTarget::All) {
+        paths.extend(specs().map(|spec| {
+            PathBuf::from(format!("cache/{}/{}/{}", spec.vendor, spec.name, spec.size))
+        }));
+    }
+    let mut paths: Vec<PathBuf> = paths
+        .into_iter()
+        .map(|path| root.join(path.strip_prefix("./").unwrap_or(&path)))
+        .collect();
+    if matches!(what, CleanTarget::Results | CleanTarget::All) && dataset.is_none() {
+        paths.push(root.join("output.txt"));
+        let entries = fs::read_dir(root)
+            .map_err(|e| OtherError(format!("Failed reading {}: {}", root.display(), e)))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            if is_dir && (name.starts_with("Results-") || name.starts_with("Suite-")) {
+                paths.push(entry.path());
+            }
+        }
+    }
+    paths.retain(|path| path.symlink_metadata().is_ok());
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Remove the artifacts of `what` from the working directory, or only list them with `dry_run`.
+pub fn clean(
+    what: CleanTarget,
+    dataset: Option<Size>,
+    dry_run: bool,
+) -> BenchmarkResult<()> {
+    if dataset.is_some() && what == CleanTarget::Results {
+        return Err(OtherError(
+            "--dataset narrows dumps and downloads; results directories mix datasets".to_string(),
+        ));
+    }
+    let paths = artifacts(Path::new("."), what, dataset)?;
+    if paths.is_empty() {
+        println!("nothing to clean");
+        return Ok(());
+    }
+    let mut total = 0;
+    for path in &paths {
+        let size = path_size(path);
+        total += size;
+        let shown = path.strip_prefix(".").unwrap_or(path).display();
+        if dry_run {
+            println!("would remove {} ({})", shown, format_bytes(size));
+            continue;
+        }
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        removed.map_err(|e| OtherError(format!("Failed removing {}: {}", shown, e)))?;
+        println!("removed {} ({})", shown, format_bytes(size));
+    }
+    println!(
+        "{} {} in {} paths",
+        if dry_run { "would free" } else { "freed" },
+        format_bytes(total),
+        paths.len()
+    );
+    Ok(())
+}
+
+/// Bytes below `path`, without following symlinks.
+fn path_size(path: &Path) -> u64 {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| path_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_only_what_the_benchmark_wrote() {
+        let root = std::env::temp_dir().join(format!("benchmark-clean-{}", std::process::id()));
+        let files = [
+            "redis-data/small_dump.rdb",
+            "redis-data/small_manifest.json",
+            "redis-data/medium_dump.rdb",
+            "backups/neo4j/users/small/neo4j.dump",
+            "cache/falkor/users/small/pokec_small_import.cypher",
+            "Results-261016-15:45/neo4j/meta.json",
+            "Suite-261016-16:00/suite.json",
+            "downloads/neo4j_local/bin/neo4j",
+            "results-kept/falkor/meta.json",
+            "falkordb.so",
+        ];
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+        let found = |what, dataset| {
+            artifacts(&root, what, dataset)
+                .unwrap()
+                .into_iter()
+                .map(|path| {
+                    path.strip_prefix(&root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            found(CleanTarget::Dumps, Some(Size::Small)),
+            [
+                "backups/neo4j/users/small",
+                "redis-data/small_dump.rdb",
+                "redis-data/small_manifest.json"
+            ]
+        );
+        assert_eq!(found(CleanTarget::Dumps, None).len(), 4);
+        assert_eq!(
+            found(CleanTarget::Downloads, Some(Size::Medium)),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            found(CleanTarget::Results, None),
+            ["Results-261016-15:45", "Suite-261016-16:00"]
+        );
+        // The Neo4j installation, custom results dirs and the module are never candidates.
+        assert_eq!(found(CleanTarget::All, None).len(), 7);
+
+        assert_eq!(path_size(&root.join("redis-data")), 3);
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
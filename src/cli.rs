@@ -1,8 +1,34 @@
-use crate::queries_repository::{QueryCoverageProfile, QueryType};
+use crate::artifact_store::ArtifactStore;
+use crate::chaos::ChaosSpec;
+use crate::circuit_breaker::{CircuitBreaker, ErrorBackoff};
+use crate::compact_query::QueryParams;
+use crate::database_config::DatabaseConfig;
+use crate::distributed::QuerySlice;
+use crate::error_budget::ErrorBudget;
+use crate::falkor::falkor_process::FalkorModuleConfig;
+use crate::memgraph_client::MemgraphStorageMode;
+use crate::phases::Phases;
+use crate::prometheus_endpoint::MetricsEndpointConfig;
+use crate::queries_repository::{
+    AccessDistribution, QueryCoverageProfile, QueryMix, QueryType, DEFAULT_HOT_ACCESS_FRACTION,
+    DEFAULT_HOT_SET_FRACTION, DEFAULT_VECTOR_K, DEFAULT_VECTOR_SHARE, DEFAULT_ZIPF_EXPONENT,
+};
+use crate::query_plans::PlanMode;
+use crate::query_timeout::{QueryTimeout, QueryTimeoutOverride};
+use crate::resource_limits::{CpuSet, MemoryLimit};
+use crate::results_layout::{parse_run_id, ResultsTemplate, DEFAULT_RESULTS_TEMPLATE};
+use crate::run_summary::OutputFormat;
+use crate::sanitizer::Sanitizer;
 use crate::scenario::Vendor;
+use crate::scheduler::{Dispatch, OverloadPolicy, ThinkTime};
+use crate::simulate::SimulatedLatency;
+use crate::slo_search::SloP99;
+use crate::slow_queries::{SlowQueryThreshold, DEFAULT_SLOW_QUERY_LIMIT};
+use crate::sweep::Probe;
 use crate::synthetic::{CacheSelection, OpName, Tier};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use clap_complete::Shell;
+use std::time::Duration;
 
 /// A `--op` value: either a single operation, or the magic `all` / `*` meaning **every** read op.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -151,6 +177,18 @@ impl clap::builder::TypedValueParser for OpSelectorValueParser {
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    #[arg(
+        long,
+        help = "TOML file with per-subcommand option sections, e.g. [run] mps = 4000; command-line flags override it. Must come before the subcommand"
+    )]
+    pub config: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        env = "BENCHMARK_CREDENTIALS_FILE",
+        help = "TOML file with [credentials.<vendor>] user/password for endpoints given without inline secrets (default: ./benchmark.toml if present)"
+    )]
+    pub credentials_file: Option<String>,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -160,53 +198,7 @@ pub enum Commands {
     GenerateAutoComplete { shell: Shell },
     #[command(arg_required_else_help = true)]
     #[command(about = "load data into the database")]
-    Load {
-        #[arg(short, long, value_enum)]
-        vendor: Vendor,
-        #[arg(short, long, value_enum)]
-        size: crate::scenario::Size,
-        #[arg(
-            short,
-            long,
-            required = false,
-            default_value_t = false,
-            default_missing_value = "true",
-            help = "execute clear -f before"
-        )]
-        force: bool,
-        #[arg(
-            short,
-            long,
-            required = false,
-            default_value_t = false,
-            default_missing_value = "true",
-            help = "only load the data from the cache and iterate over it, show how much time it takes, do not send it to the server"
-        )]
-        dry_run: bool,
-        #[arg(
-            short,
-            long,
-            required = false,
-            default_value_t = 1000,
-            help = "number of cypher commands to execute in a single batch"
-        )]
-        batch_size: usize,
-        #[arg(
-            short,
-            long,
-            required = false,
-            help = "endpoint for external database connection (e.g., falkor://127.0.0.1:6379)"
-        )]
-        endpoint: Option<String>,
-        #[arg(
-            long,
-            value_enum,
-            required = false,
-            default_value_t = QueryCoverageProfile::Baseline,
-            help = "query coverage profile used to decide if post-phase fixture/index setup should run"
-        )]
-        query_profile: QueryCoverageProfile,
-    },
+    Load(LoadArgs),
     #[command(
         about = "generate a set of queries and store them in a file to be used with the run command"
     )]
@@ -222,7 +214,7 @@ pub enum Commands {
             long,
             required = false,
             default_missing_value = "queries.json",
-            help = "name of json file to save the queries"
+            help = "name of json file to save the queries, gzip or zstd compressed when it ends in .gz or .zst"
         )]
         name: String,
         #[arg(
@@ -261,6 +253,27 @@ pub enum Commands {
             help = "enable the algo_harmonic_summary query in generated workloads"
         )]
         enable_algo_harmonic: bool,
+        #[arg(
+            long,
+            default_value_t = false,
+            action = clap::ArgAction::Set,
+            help = "include multi-statement transaction workloads (tx_*) in the write mix"
+        )]
+        enable_transactions: bool,
+        #[arg(
+            long,
+            default_value_t = false,
+            action = clap::ArgAction::Set,
+            help = "include reads looking users up by string, float, list and datetime parameters"
+        )]
+        enable_typed_params: bool,
+        #[arg(
+            long,
+            default_value_t = false,
+            action = clap::ArgAction::Set,
+            help = "read the dataset's edges so path queries draw connected ids and age filters thresholds a friend meets"
+        )]
+        enable_correlated_params: bool,
         #[arg(
             long,
             value_enum,
@@ -269,58 +282,149 @@ pub enum Commands {
             help = "query coverage profile to generate (baseline, extended-core, fixture-dependent)"
         )]
         query_profile: QueryCoverageProfile,
+        #[arg(
+            long,
+            value_enum,
+            required = false,
+            default_value_t = QueryMix::Oltp,
+            help = "reads to draw from: oltp, analytics (whole-graph aggregations) or mixed; writes follow --write-ratio"
+        )]
+        query_mix: QueryMix,
+        #[arg(
+            long,
+            value_enum,
+            required = false,
+            default_value_t = AccessDistribution::Uniform,
+            help = "distribution used to pick node ids (uniform, zipfian, latest, hotspot)"
+        )]
+        access_pattern: AccessDistribution,
+        #[arg(
+            long,
+            required = false,
+            value_parser = parse_zipf_exponent,
+            default_value_t = DEFAULT_ZIPF_EXPONENT,
+            help = "skew of the zipfian/latest access patterns (> 0, higher is more skewed)"
+        )]
+        zipf_exponent: f64,
+        #[arg(
+            long,
+            required = false,
+            value_parser = parse_fraction,
+            default_value_t = DEFAULT_HOT_SET_FRACTION,
+            help = "fraction of the node ids that form the hot set for the hotspot access pattern (0.0 - 1.0)"
+        )]
+        hot_set_fraction: f64,
+        #[arg(
+            long,
+            required = false,
+            value_parser = parse_fraction,
+            default_value_t = DEFAULT_HOT_ACCESS_FRACTION,
+            help = "fraction of accesses that hit the hot set for the hotspot access pattern (0.0 - 1.0)"
+        )]
+        hot_access_fraction: f64,
+        #[arg(
+            long,
+            required = false,
+            help = "timeout for one query in the catalog instead of the run's --query-timeout, e.g. algo_pagerank_summary=5m; repeatable"
+        )]
+        query_timeout_override: Vec<QueryTimeoutOverride>,
+        #[arg(
+            long,
+            required = false,
+            value_parser = clap::value_parser!(u16).range(1..=4096),
+            help = "add vector_knn reads over a vector index of this many dimensions on :User (FalkorDB and Neo4j)"
+        )]
+        vector_dimensions: Option<u16>,
+        #[arg(
+            long,
+            required = false,
+            value_parser = clap::value_parser!(u16).range(1..),
+            default_value_t = DEFAULT_VECTOR_K,
+            requires = "vector_dimensions",
+            help = "neighbours returned by each vector_knn read"
+        )]
+        vector_k: u16,
+        #[arg(
+            long,
+            required = false,
+            value_parser = parse_fraction,
+            default_value_t = DEFAULT_VECTOR_SHARE,
+            requires = "vector_dimensions",
+            help = "share of the read queries that are vector_knn reads (0.0 - 1.0)"
+        )]
+        vector_share: f64,
+        #[arg(
+            long,
+            required = false,
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "keep the dataset stable: after every N user mutations, add writes that drop the users the workload created and restore the ones it changed from untouched users"
+        )]
+        bounded_writes: Option<u32>,
+        #[arg(
+            long,
+            required = false,
+            help = "seed for the generated queries (same seed ⇒ identical file); random by default"
+        )]
+        seed: Option<u64>,
+        #[arg(
+            long,
+            required = false,
+            help = "threads generating queries; default: the number of cores"
+        )]
+        workers: Option<usize>,
     },
 
     #[command(
         about = "run the queries generated by the GenerateQueries command against the chosen vendor"
     )]
-    Run {
-        #[arg(short, long, value_enum)]
-        vendor: Vendor,
+    Run(RunArgs),
+    #[command(
+        about = "run a matrix of vendors x datasets x write ratios x parallel x mps from a suite file, then aggregate each combination"
+    )]
+    Suite {
         #[arg(
-            short,
             long,
-            required = false,
-            default_value_t = 1,
-            default_missing_value = "1",
-            help = "parallelism level"
+            help = "suite file describing the matrix: YAML (.yaml, .yml) or TOML"
         )]
-        parallel: usize,
+        file: String,
         #[arg(
-            short,
             long,
             required = false,
-            default_missing_value = "queries.json",
-            help = "name of json file to load the queries from"
+            help = "root of the suite results tree (overrides results_dir in the file). Defaults to Suite-YYMMDD-HHMMSS"
         )]
-        name: String,
+        results_dir: Option<String>,
         #[arg(
-            short,
             long,
-            required = true,
-            help = "the rate of messages that sent to the server (messages per second)"
+            default_value_t = false,
+            help = "print the planned steps without running them"
         )]
-        mps: usize,
+        dry_run: bool,
+    },
+    #[command(
+        about = "start several `run --control-api --wait-for-start --slice i/n` clients at once and follow them until they finish"
+    )]
+    Coordinate {
         #[arg(
-            short,
             long,
-            required = false,
-            help = "simulate the benchmark without sending the messages to the server, the value the process time in milliseconds"
+            required = true,
+            value_delimiter = ',',
+            help = "metrics endpoints of the clients, comma-separated (e.g. http://load-1:8080,http://load-2:8080)"
         )]
-        simulate: Option<usize>,
+        workers: Vec<String>,
         #[arg(
-            short,
             long,
             required = false,
-            help = "endpoint for external database connection (e.g., falkor://127.0.0.1:6379)"
+            default_value_t = 2,
+            help = "seconds between status polls"
         )]
-        endpoint: Option<String>,
+        poll_interval_secs: u64,
         #[arg(
             long,
             required = false,
-            help = "base directory to write detailed per-vendor run results (will create <results-dir>/<vendor>/...). Defaults to Results-YYMMDD-HH:MM"
+            default_value_t = 600,
+            help = "seconds to wait for every client to be ready before giving up"
         )]
-        results_dir: Option<String>,
+        ready_timeout_secs: u64,
     },
     #[command(about = "aggregate per-vendor run results into UI summary JSON files")]
     Aggregate {
@@ -337,6 +441,21 @@ pub enum Commands {
             help = "directory to write UI summary JSON files"
         )]
         out_dir: String,
+        #[arg(
+            long,
+            required = false,
+            default_value_t = crate::inspect::DEFAULT_GRAPH_SIZE_TOLERANCE,
+            help = "fraction by which a vendor's node or relationship count may differ from the baseline's before its run is flagged potentially invalid"
+        )]
+        graph_size_tolerance: f64,
+        #[arg(
+            long,
+            required = false,
+            value_enum,
+            default_value_t = Vendor::Falkor,
+            help = "vendor the others are compared against; without its results, or with no other vendor, each vendor gets a summary of its own"
+        )]
+        baseline: Vendor,
     },
 
     #[command(
@@ -359,6 +478,25 @@ pub enum Commands {
         out_path: String,
     },
 
+    #[command(
+        about = "write a Grafana dashboard JSON for the metrics of a finished run (run writes one as grafana-dashboard.json)"
+    )]
+    GrafanaDashboard {
+        #[arg(
+            long,
+            required = true,
+            help = "vendor results directory of the run (contains meta.json and metrics.prom)"
+        )]
+        run_dir: String,
+        #[arg(
+            short,
+            long,
+            required = false,
+            help = "path of the dashboard JSON; <run-dir>/grafana-dashboard.json when omitted"
+        )]
+        out: Option<String>,
+    },
+
     #[command(
         about = "Run each generated Memgraph query type once against a Memgraph endpoint to detect failing queries"
     )]
@@ -381,6 +519,72 @@ pub enum Commands {
         name: String,
     },
 
+    #[command(
+        about = "check the binaries, ports, dumps, disk space and limits a managed run of a vendor needs"
+    )]
+    Doctor {
+        #[arg(short, long, value_enum)]
+        vendor: Vendor,
+        #[arg(
+            short,
+            long,
+            value_enum,
+            required = false,
+            help = "dataset size to check the dump of; any size when omitted"
+        )]
+        size: Option<crate::scenario::Size>,
+    },
+
+    #[command(
+        about = "remove dumps, result directories or downloaded datasets from the working directory"
+    )]
+    Clean {
+        #[arg(long, value_enum, help = "which artifacts to remove")]
+        what: crate::clean::CleanTarget,
+        #[arg(
+            long,
+            value_enum,
+            required = false,
+            help = "only the dumps and downloads of this dataset size"
+        )]
+        dataset: Option<crate::scenario::Size>,
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "list what would be removed without removing it"
+        )]
+        dry_run: bool,
+    },
+
+    #[command(
+        about = "report node/edge counts, out-degree, property cardinalities and indexes of a loaded vendor"
+    )]
+    Inspect {
+        #[arg(short, long, value_enum)]
+        vendor: Vendor,
+        #[arg(
+            short,
+            long,
+            required = false,
+            help = "endpoint of the loaded vendor; the local default when omitted"
+        )]
+        endpoint: Option<String>,
+        #[arg(
+            short,
+            long,
+            required = false,
+            default_value = "inspect.json",
+            help = "path of the JSON report"
+        )]
+        out: String,
+        #[arg(
+            long,
+            required = false,
+            help = "inspect report of another vendor; fail when the graphs differ"
+        )]
+        compare_with: Option<String>,
+    },
+
     #[command(
         about = "synthetic per-operation latency probe (measures server + total time in isolation)"
     )]
@@ -390,6 +594,510 @@ pub enum Commands {
     },
 }
 
+impl Commands {
+    /// `--output` of `run` and `load`; the other commands only log.
+    pub fn output(&self) -> OutputFormat {
+        match self {
+            Commands::Load(args) => args.output,
+            Commands::Run(args) => args.output,
+            _ => OutputFormat::Text,
+        }
+    }
+}
+
+/// The options of `benchmark load`; what [`crate::runner::load_dataset`] takes.
+#[derive(Args, Debug)]
+pub struct LoadArgs {
+    #[arg(short, long, value_enum)]
+    pub vendor: Vendor,
+    #[arg(short, long, value_enum)]
+    pub size: crate::scenario::Size,
+    #[arg(
+        short,
+        long,
+        required = false,
+        default_value_t = false,
+        default_missing_value = "true",
+        help = "execute clear -f before"
+    )]
+    pub force: bool,
+    #[arg(
+        short,
+        long,
+        required = false,
+        default_value_t = false,
+        default_missing_value = "true",
+        help = "only load the data from the cache and iterate over it, show how much time it takes, do not send it to the server"
+    )]
+    pub dry_run: bool,
+    #[arg(
+        short,
+        long,
+        required = false,
+        default_value_t = 1000,
+        help = "number of cypher commands to execute in a single batch"
+    )]
+    pub batch_size: usize,
+    #[arg(
+        short,
+        long,
+        required = false,
+        help = "endpoint for external database connection (e.g., falkor://127.0.0.1:6379)"
+    )]
+    pub endpoint: Option<String>,
+    #[command(flatten)]
+    pub database: DatabaseConfig,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = false,
+        env = "BENCHMARK_READ_ONLY",
+        help = "refuse to load: this shell or profile only targets shared environments that must not be written to"
+    )]
+    pub read_only: bool,
+    #[arg(
+        long,
+        value_enum,
+        required = false,
+        default_value_t = QueryCoverageProfile::Baseline,
+        help = "query coverage profile used to decide if post-phase fixture/index setup should run"
+    )]
+    pub query_profile: QueryCoverageProfile,
+    #[arg(
+        long,
+        required = false,
+        help = "upload the saved dump and its manifest to this bucket (s3://bucket/prefix or gs://bucket/prefix) for other machines to reuse"
+    )]
+    pub artifact_store: Option<ArtifactStore>,
+    #[command(flatten)]
+    pub metrics_endpoint: MetricsEndpointConfig,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = false,
+        help = "don't draw the progress bar on stderr (it's only drawn on a terminal anyway)"
+    )]
+    pub no_progress: bool,
+    #[arg(
+        long,
+        required = false,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "json logs to stderr and ends with a one-line JSON summary on stdout (status, vendor, message); exits 1 when loading failed"
+    )]
+    pub output: OutputFormat,
+    #[arg(
+        long,
+        required = false,
+        value_enum,
+        help = "switch Memgraph to this storage mode before loading"
+    )]
+    pub memgraph_storage_mode: Option<MemgraphStorageMode>,
+    #[command(flatten)]
+    pub falkor_module: FalkorModuleConfig,
+}
+
+/// The options of `benchmark run`; what [`crate::runner::run_benchmark`] takes.
+#[derive(Args, Debug, Clone)]
+pub struct RunArgs {
+    #[arg(short, long, value_enum)]
+    pub vendor: Vendor,
+    #[arg(
+        short,
+        long,
+        required = false,
+        default_value_t = 1,
+        default_missing_value = "1",
+        help = "parallelism level"
+    )]
+    pub parallel: usize,
+    #[arg(
+        short,
+        long,
+        required = false,
+        default_value = "queries.json",
+        help = "name of json file to load the queries from"
+    )]
+    pub name: String,
+    #[arg(
+        long,
+        required = false,
+        conflicts_with = "name",
+        requires = "dataset",
+        help = "generate this many queries at the start of the run instead of reading --name, with the generate-queries defaults; for quick exploratory runs"
+    )]
+    pub inline_queries: Option<usize>,
+    #[arg(
+        long,
+        value_enum,
+        required = false,
+        requires = "inline_queries",
+        help = "the dataset size the --inline-queries draw their node ids from"
+    )]
+    pub dataset: Option<crate::scenario::Size>,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = 0.0,
+        value_parser = parse_write_ratio,
+        requires = "inline_queries",
+        help = "the write ratio of the --inline-queries (0.0 - 1.0)"
+    )]
+    pub write_ratio: f32,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = 0,
+        requires = "inline_queries",
+        help = "seed for the --inline-queries (same seed ⇒ identical queries); fixed by default so repetitions and sweep points run the same queries"
+    )]
+    pub seed: u64,
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = "the rate of messages that sent to the server (messages per second)"
+    )]
+    pub mps: usize,
+    #[arg(
+        short,
+        long,
+        required = false,
+        help = "simulate the benchmark without sending the messages to the server, the value the process time of each query: a fixed delay (milliseconds unless suffixed with us, ms or s), uniform:<min>-<max> (e.g. uniform:2-10ms) or lognormal:<median>,<sigma> (e.g. lognormal:5ms,1.5)"
+    )]
+    pub simulate: Option<SimulatedLatency>,
+    #[arg(
+        short,
+        long,
+        required = false,
+        help = "endpoint for external database connection (e.g., falkor://127.0.0.1:6379)"
+    )]
+    pub endpoint: Option<String>,
+    #[command(flatten)]
+    pub database: DatabaseConfig,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = false,
+        env = "BENCHMARK_READ_ONLY",
+        help = "refuse a queries file with any write query, refuse writes again when dispatching them, and skip the setup that writes to the graph"
+    )]
+    pub read_only: bool,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = false,
+        help = "before the workload, send a succeeding and a failing query and fail the run unless exactly this vendor's operation counters, result rows and deadline-offset gauge moved"
+    )]
+    pub verify_metrics: bool,
+    #[arg(
+        long,
+        required = false,
+        requires = "endpoint",
+        conflicts_with = "attach_port",
+        help = "PID of the locally running server behind --endpoint; sample its CPU and memory and record its downtime like a managed server's"
+    )]
+    pub attach_pid: Option<u32>,
+    #[arg(
+        long,
+        required = false,
+        requires = "endpoint",
+        help = "like --attach-pid, but follow whichever local process listens on this TCP port, so restarts are picked up"
+    )]
+    pub attach_port: Option<u16>,
+    #[arg(
+        long,
+        required = false,
+        help = "base directory to write detailed per-vendor run results (will create <results-dir>/<vendor>/...). Defaults to Results-<run-id>"
+    )]
+    pub results_dir: Option<String>,
+    #[arg(
+        long,
+        required = false,
+        value_parser = parse_run_id,
+        help = "name of the run, for the default --results-dir and the {run_id} token of --results-template. Defaults to the UTC time as YYMMDD-HHMMSS"
+    )]
+    pub run_id: Option<String>,
+    #[arg(
+        long,
+        required = false,
+        default_value = DEFAULT_RESULTS_TEMPLATE,
+        help = "directory of the run inside --results-dir, with the tokens {vendor}, {dataset}, {mps}, {parallel}, {timestamp} and {run_id}, e.g. {dataset}/{vendor}-{mps}mps"
+    )]
+    pub results_template: ResultsTemplate,
+    #[arg(
+        long,
+        required = false,
+        help = "bucket shared between machines (s3://bucket/prefix or gs://bucket/prefix): download a missing dump from it before restoring, and upload the run's results to it afterwards"
+    )]
+    pub artifact_store: Option<ArtifactStore>,
+    #[arg(
+        long,
+        required = false,
+        help = "per-query client think time added by each worker between queries, outside the measured latency (e.g. 5ms, 5ms±2ms, 500us+-100us)"
+    )]
+    pub think_time: Option<ThinkTime>,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "run even when the target's property indexes differ from the dataset spec (the mismatch is recorded in meta.json)"
+    )]
+    pub allow_index_mismatch: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "warm",
+        help = "restart the locally managed server and drop OS page caches (Linux, needs root) before the measured phase"
+    )]
+    pub cold_start: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "run a full warmup scan of users and friendships before the measured phase"
+    )]
+    pub warm: bool,
+    #[arg(
+        long,
+        required = false,
+        help = "inject failures into the locally managed server during the run and report recovery, e.g. kill-server:300s (SIGKILL every 300s)"
+    )]
+    pub chaos: Option<ChaosSpec>,
+    #[arg(
+        long,
+        required = false,
+        help = "run the locally managed server in a cgroup v2 group with this memory limit (e.g. 8G) and record OOM kills"
+    )]
+    pub server_mem_limit: Option<MemoryLimit>,
+    #[arg(
+        long,
+        required = false,
+        help = "set the locally managed Neo4j's server.memory.heap.max_size (e.g. 4G) in neo4j.conf before it starts"
+    )]
+    pub neo4j_heap: Option<MemoryLimit>,
+    #[arg(
+        long,
+        required = false,
+        help = "set the locally managed Neo4j's server.memory.pagecache.size (e.g. 8G) in neo4j.conf before it starts"
+    )]
+    pub neo4j_pagecache: Option<MemoryLimit>,
+    #[command(flatten)]
+    pub falkor_module: FalkorModuleConfig,
+    #[arg(
+        long,
+        required = false,
+        value_enum,
+        help = "run FalkorDB's asan or msan build (FALKOR_SANITIZER_REDIS_SERVER, FALKOR_PATH) at a lowered --mps; the sanitizer's reports are recorded in meta.json and fail the run"
+    )]
+    pub sanitizer: Option<Sanitizer>,
+    #[arg(
+        long,
+        required = false,
+        help = "pin the locally managed server to these cores, e.g. 0-7 (taskset -c syntax)"
+    )]
+    pub server_cpus: Option<CpuSet>,
+    #[arg(
+        long,
+        required = false,
+        help = "pin the benchmark client and its workers to these cores, e.g. 8-15; must not overlap --server-cpus"
+    )]
+    pub client_cpus: Option<CpuSet>,
+    #[arg(
+        long,
+        required = false,
+        help = "per-query timeout, e.g. 60s or 500ms (defaults: FalkorDB 180s or FALKOR_QUERY_TIMEOUT_MS, Neo4j 60s, Memgraph 900s or MEMGRAPH_QUERY_TIMEOUT_MS); queries with a --query-timeout-override in the queries file keep theirs"
+    )]
+    pub query_timeout: Option<QueryTimeout>,
+    #[arg(
+        long,
+        required = false,
+        help = "write queries that take at least this long (e.g. 500ms) with their parameters to slow_queries.jsonl in the results dir"
+    )]
+    pub slow_query_threshold: Option<SlowQueryThreshold>,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = DEFAULT_SLOW_QUERY_LIMIT,
+        help = "most entries kept in slow_queries.jsonl; past it only the slowest are kept"
+    )]
+    pub slow_query_limit: usize,
+    #[arg(
+        long,
+        required = false,
+        help = "stop the run early once more than this share of the queries completed over the window failed, e.g. 5%/30s; the collected metrics are still written and meta.json records the abort"
+    )]
+    pub abort_on_error_rate: Option<ErrorBudget>,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = ErrorBackoff::default(),
+        help = "wait after a failed query before a worker's next one, doubling from the first value with every failure in a row up to the second, e.g. 50ms..3s; off sends the next query right away"
+    )]
+    pub error_backoff: ErrorBackoff,
+    #[arg(
+        long,
+        required = false,
+        help = "pause dispatch once more than this share of the queries completed over the window failed, e.g. 50%/5s: the queries due over the next window are shed instead of sent, then dispatch resumes"
+    )]
+    pub circuit_breaker: Option<CircuitBreaker>,
+    #[arg(
+        long,
+        required = false,
+        value_enum,
+        default_value_t = OverloadPolicy::Block,
+        help = "what the scheduler does when the workers fall behind --mps: block waits for a free slot, drop discards the message and counts it, queue-unbounded queues the whole schedule up front"
+    )]
+    pub overload_policy: OverloadPolicy,
+    #[arg(
+        long,
+        required = false,
+        value_enum,
+        default_value_t = Dispatch::Shared,
+        help = "how queries reach the workers: shared has them all take from one queue, round-robin deals the queries out to per-worker queues in turn so every worker runs the same share"
+    )]
+    pub dispatch: Dispatch,
+    #[arg(
+        long,
+        required = false,
+        value_enum,
+        help = "switch Memgraph to this storage mode before the run (and again after a --cache cold restart); the mode SHOW STORAGE INFO reports is recorded in meta.json"
+    )]
+    pub memgraph_storage_mode: Option<MemgraphStorageMode>,
+    #[arg(
+        long,
+        required = false,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "open a new connection per worker every K queries, to measure connection-churn workloads"
+    )]
+    pub reconnect_every: Option<u32>,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = false,
+        help = "FalkorDB only: drive the query queue past MAX_QUEUED_QUERIES (needs --parallel above it) and record the rejections and how long FalkorDB takes to accept queries again in <vendor>/saturation.json"
+    )]
+    pub saturate_queue: bool,
+    #[arg(
+        long,
+        required = false,
+        help = "repetition number of this run; results go to <results-dir>/<vendor>/run-<N>/ and aggregate reports the median and spread across repetitions"
+    )]
+    pub repetition: Option<u32>,
+    #[arg(
+        long,
+        required = false,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "run the workload N times, one after the other, into <results-dir>/<vendor>/run-1/ .. run-N/ (ignored with --repetition)"
+    )]
+    pub repeat: Option<u32>,
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "repeat",
+        conflicts_with = "warm",
+        help = "with --repeat, restart the locally managed server and drop OS page caches (as --cold-start) before every repetition after the first"
+    )]
+    pub restart_between_repetitions: bool,
+    #[arg(
+        long,
+        required = false,
+        conflicts_with_all = ["repeat", "repetition", "slice", "control_api"],
+        help = "search for the highest rate whose P99 stays within this latency (e.g. 50ms), starting at --mps; each probe runs the queries file into <results-dir>/<vendor>/slo/mps-<N>/ and the outcome goes to <results-dir>/<vendor>/slo.json"
+    )]
+    pub slo_p99: Option<SloP99>,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = crate::slo_search::DEFAULT_SLO_RESOLUTION,
+        help = "with --slo-p99, stop once the fastest passing and slowest failing rates are within this fraction of each other"
+    )]
+    pub slo_resolution: f64,
+    #[arg(
+        long,
+        required = false,
+        conflicts_with_all = ["slo_p99", "repeat", "repetition", "slice", "control_api"],
+        requires = "duration",
+        help = "run a latency-vs-throughput sweep up to this rate: each of --sweep-points runs for --duration into <results-dir>/<vendor>/sweep/mps-<N>/, and the curve goes to <results-dir>/<vendor>/sweep.csv and sweep.json"
+    )]
+    pub sweep: Option<usize>,
+    #[arg(
+        long,
+        required = false,
+        value_delimiter = ',',
+        default_values_t = crate::sweep::DEFAULT_SWEEP_POINTS,
+        help = "with --sweep, the rates to run in percent of the --sweep rate"
+    )]
+    pub sweep_points: Vec<f64>,
+    #[arg(
+        long,
+        required = false,
+        value_parser = crate::query_timeout::parse_duration,
+        help = "stop scheduling queries after this long (e.g. 60s or 5m) even if the queries file has more"
+    )]
+    pub duration: Option<Duration>,
+    #[arg(
+        long,
+        required = false,
+        conflicts_with_all = ["duration", "sweep", "slo_p99"],
+        help = "shift the read/write mix during the run, e.g. read-only:5m,mixed:10m,write-heavy:5m (read-only, read-heavy, mixed, write-heavy, write-only or a write ratio); the run lasts as long as the phases and the queries file needs both reads and writes"
+    )]
+    pub phases: Option<Phases>,
+    #[arg(long, required = false, value_enum, hide = true)]
+    pub probe: Option<Probe>,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = false,
+        help = "serve /control/start, /control/stop and /control/mps on the metrics endpoint so a coordinator can drive the run"
+    )]
+    pub control_api: bool,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = false,
+        requires = "control_api",
+        help = "with --control-api, prepare the run and wait for POST /control/start before the measured phase"
+    )]
+    pub wait_for_start: bool,
+    #[arg(
+        long,
+        required = false,
+        help = "run every n-th query of the file as client <i> of <n> (e.g. 2/4) into <vendor>/client-<i>/, for distributed load generation; aggregate merges the clients"
+    )]
+    pub slice: Option<QuerySlice>,
+    #[arg(
+        long,
+        required = false,
+        value_enum,
+        help = "before the measured phase, save the EXPLAIN (or PROFILE, for reads) plan of one instance of every catalog query to <vendor>/plans.json"
+    )]
+    pub capture_plans: Option<PlanMode>,
+    #[arg(
+        long,
+        value_enum,
+        required = false,
+        default_value_t = QueryParams::Parameterized,
+        help = "send query values as parameters (Bolt parameters, FalkorDB's CYPHER preamble) or interpolated into the query text as literals, for every vendor"
+    )]
+    pub query_params: QueryParams,
+    #[command(flatten)]
+    pub metrics_endpoint: MetricsEndpointConfig,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = false,
+        help = "don't draw the progress bar on stderr (it's only drawn on a terminal anyway)"
+    )]
+    pub no_progress: bool,
+    #[arg(
+        long,
+        required = false,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "json logs to stderr and ends with a one-line JSON summary on stdout (status, errors, p50/p95/p99, achieved qps, results path); exits 1 when the run failed, 2 when --slo-p99 found no passing rate and 3 when --abort-on-error-rate stopped it"
+    )]
+    pub output: OutputFormat,
+}
+
 /// Subcommands of `benchmark synthetic`.
 // The `Run` variant carries many optional CLI knobs; this subcommand enum is parsed once at
 // startup, so the size gap versus the unit `ListOps` variant doesn't matter.
@@ -613,6 +1321,22 @@ fn parse_write_ratio(val: &str) -> Result<f32, String> {
     }
 }
 
+fn parse_fraction(val: &str) -> Result<f64, String> {
+    match val.parse::<f64>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+        Ok(_) => Err(String::from("Value must be between 0.0 and 1.0")),
+        Err(_) => Err(String::from("Invalid float value")),
+    }
+}
+
+fn parse_zipf_exponent(val: &str) -> Result<f64, String> {
+    match val.parse::<f64>() {
+        Ok(value) if value.is_finite() && value > 0.0 => Ok(value),
+        Ok(_) => Err(String::from("Value must be a finite number greater than 0")),
+        Err(_) => Err(String::from("Invalid float value")),
+    }
+}
+
 /// Parse `--elapsed-secs`: a finite, non-negative number of seconds (rejects `-1`, `inf`, `NaN`).
 fn parse_elapsed_secs(val: &str) -> Result<f64, String> {
     match val.parse::<f64>() {
@@ -766,6 +1490,44 @@ mod tests {
         .is_err());
     }
 
+    #[test]
+    fn generate_queries_access_pattern_flags_parse_and_validate() {
+        use clap::Parser;
+        let base = [
+            "benchmark",
+            "generate-queries",
+            "--vendor",
+            "falkor",
+            "-s",
+            "10",
+            "--dataset",
+            "small",
+            "--name",
+            "q.json",
+            "--write-ratio",
+            "0.0",
+        ];
+        let parse = |extra: &[&str]| Cli::try_parse_from(base.iter().chain(extra.iter()));
+
+        match parse(&[]).unwrap().command {
+            Commands::GenerateQueries {
+                access_pattern,
+                zipf_exponent,
+                ..
+            } => {
+                assert_eq!(access_pattern, AccessDistribution::Uniform);
+                assert_eq!(zipf_exponent, DEFAULT_ZIPF_EXPONENT);
+            }
+            other => panic!("unexpected command {other:?}"),
+        }
+        assert!(parse(&["--access-pattern", "zipfian", "--zipf-exponent", "1.2"]).is_ok());
+        assert!(parse(&["--access-pattern", "latest"]).is_ok());
+        assert!(parse(&["--access-pattern", "hotspot", "--hot-set-fraction", "0.1"]).is_ok());
+        assert!(parse(&["--access-pattern", "pareto"]).is_err());
+        assert!(parse(&["--zipf-exponent", "0"]).is_err());
+        assert!(parse(&["--hot-access-fraction", "1.5"]).is_err());
+    }
+
     #[test]
     fn cli_tier_flag_parses_and_conflicts_with_op_selection() {
         use clap::Parser;
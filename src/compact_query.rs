@@ -0,0 +1,377 @@
+//! The runner's in-memory form of a queries file. A `PreparedQuery` carries its text three times
+//! (the query, the `CYPHER ...` rendering and the Bolt struct). Here each distinct text and
+//! parameter-name list is kept once as a [`Template`], and a query is its template plus the
+//! parameter values.
+//!
+//! Neo4j and Memgraph build their Bolt query from the template when it's sent. FalkorDB's
+//! `CYPHER ...` text is rendered once here and borrowed for every send; rendering it per send
+//! took about 100ns a query (`cargo bench --bench query_rendering`).
+//!
+//! `run --query-params` picks how values reach the server, the same way for every vendor:
+//! `parameterized` (the default) sends them as parameters, Bolt parameters for Neo4j and Memgraph
+//! and the falkordb client's `CYPHER name=value` preamble for FalkorDB; `interpolated` writes them
+//! into the text as literals, so each query is a distinct text to the server's plan cache.
+
+use crate::error::BenchmarkResult;
+use crate::queries_repository::{PreparedQuery, QueryType};
+use crate::query::{Bolt, QueryParam};
+use crate::scenario::Vendor;
+use clap::ValueEnum;
+use falkordb::to_cypher_param;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::info;
+
+/// How `run` sends a query's parameter values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryParams {
+    /// As parameters of the query.
+    #[default]
+    Parameterized,
+    /// As literals in the query's text.
+    Interpolated,
+}
+
+/// A statement's text and its parameter names, sorted.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Template {
+    pub text: String,
+    pub names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct CompactStatement {
+    pub template: Arc<Template>,
+    /// Values of `template.names`, in the same order.
+    pub params: Box<[QueryParam]>,
+    /// [`Self::cypher`], rendered when the statement was compacted for FalkorDB.
+    rendered: Option<Box<str>>,
+}
+
+impl CompactStatement {
+    pub fn params(&self) -> impl Iterator<Item = (&str, &QueryParam)> {
+        self.template
+            .names
+            .iter()
+            .map(String::as_str)
+            .zip(self.params.iter())
+    }
+
+    /// The text FalkorDB is sent: `CYPHER name=value ... text`, with the preamble the falkordb
+    /// client's `with_params` would build, or just the text when there are no parameters.
+    pub fn cypher(&self) -> BenchmarkResult<Cow<'_, str>> {
+        match &self.rendered {
+            Some(rendered) => Ok(Cow::Borrowed(rendered)),
+            None => self.render_cypher().map(Cow::Owned),
+        }
+    }
+
+    fn render_cypher(&self) -> BenchmarkResult<String> {
+        let mut cypher =
+            String::with_capacity(self.template.text.len() + 16 * self.params.len() + 8);
+        if !self.params.is_empty() {
+            cypher.push_str("CYPHER ");
+            for (name, value) in self.params() {
+                cypher.push_str(name);
+                cypher.push('=');
+                write_literal(value, &mut cypher)?;
+                cypher.push(' ');
+            }
+        }
+        cypher.push_str(&self.template.text);
+        Ok(cypher)
+    }
+
+    /// The statement for the Bolt drivers.
+    pub fn bolt(&self) -> neo4rs::Query {
+        neo4rs::query(&self.template.text)
+            .params(self.params().map(|(name, value)| (name, value.clone())))
+    }
+}
+
+#[derive(Debug)]
+pub struct CompactQuery {
+    pub q_id: u16,
+    pub q_name: Arc<str>,
+    pub q_type: QueryType,
+    /// The query, or every statement of a transaction.
+    pub statements: Box<[CompactStatement]>,
+    transaction: bool,
+}
+
+impl CompactQuery {
+    pub fn is_transaction(&self) -> bool {
+        self.transaction
+    }
+
+    pub fn is_write(&self) -> bool {
+        self.q_type == QueryType::Write
+    }
+
+    /// The query, or a transaction's first statement.
+    pub fn statement(&self) -> &CompactStatement {
+        &self.statements[0]
+    }
+}
+
+/// Interns the templates and query names of the queries it compacts.
+#[derive(Debug, Default)]
+pub struct QueryTemplates {
+    templates: HashSet<Arc<Template>>,
+    names: HashMap<String, Arc<str>>,
+    render_cypher: bool,
+    params: QueryParams,
+}
+
+impl QueryTemplates {
+    /// Templates for queries sent to `vendor`, with their values sent as `params`.
+    pub fn new(
+        vendor: Vendor,
+        params: QueryParams,
+    ) -> Self {
+        QueryTemplates {
+            render_cypher: vendor == Vendor::Falkor,
+            params,
+            ..Self::default()
+        }
+    }
+
+    /// The number of distinct templates seen.
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    fn statement(
+        &mut self,
+        bolt: Bolt,
+    ) -> BenchmarkResult<CompactStatement> {
+        let mut params = bolt.params;
+        // Older files list a statement's parameters in hash order.
+        params.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let (names, params): (Vec<String>, Vec<QueryParam>) = params.into_iter().unzip();
+        let (template, params) = match self.params {
+            QueryParams::Parameterized => (
+                Template {
+                    text: bolt.query,
+                    names,
+                },
+                params,
+            ),
+            QueryParams::Interpolated => (
+                Template {
+                    text: interpolate(&bolt.query, &names, &params)?,
+                    names: Vec::new(),
+                },
+                Vec::new(),
+            ),
+        };
+        let template = match self.templates.get(&template) {
+            Some(interned) => interned.clone(),
+            None => {
+                let template = Arc::new(template);
+                self.templates.insert(template.clone());
+                template
+            }
+        };
+        let mut statement = CompactStatement {
+            template,
+            params: params.into_boxed_slice(),
+            rendered: None,
+        };
+        if self.render_cypher {
+            statement.rendered = Some(statement.render_cypher()?.into_boxed_str());
+        }
+        Ok(statement)
+    }
+
+    pub fn compact(
+        &mut self,
+        query: PreparedQuery,
+    ) -> BenchmarkResult<CompactQuery> {
+        let transaction = query.is_transaction();
+        let statements: Vec<CompactStatement> = if transaction {
+            query
+                .statements
+                .into_iter()
+                .map(|statement| self.statement(statement.bolt))
+                .collect::<BenchmarkResult<_>>()?
+        } else {
+            vec![self.statement(query.bolt)?]
+        };
+        let q_name = match self.names.get(&query.q_name) {
+            Some(name) => name.clone(),
+            None => {
+                let name: Arc<str> = Arc::from(query.q_name.as_str());
+                self.names.insert(query.q_name, name.clone());
+                name
+            }
+        };
+        Ok(CompactQuery {
+            q_id: query.q_id,
+            q_name,
+            q_type: query.q_type,
+            statements: statements.into_boxed_slice(),
+            transaction,
+        })
+    }
+}
+
+/// `value` as a Cypher literal, encoded by the falkordb client.
+fn write_literal(
+    value: &QueryParam,
+    out: &mut String,
+) -> BenchmarkResult<()> {
+    let literal = match value {
+        QueryParam::String(s) => to_cypher_param(s),
+        QueryParam::Integer(i) => to_cypher_param(i),
+        QueryParam::Float(f) => to_cypher_param(f),
+        QueryParam::Boolean(b) => to_cypher_param(b),
+        QueryParam::DateTime(millis) => to_cypher_param(millis),
+        QueryParam::List(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_literal(item, out)?;
+            }
+            out.push(']');
+            return Ok(());
+        }
+    }?;
+    out.push_str(&literal);
+    Ok(())
+}
+
+/// `text` with each `$name` of `names` replaced by its value's literal.
+fn interpolate(
+    text: &str,
+    names: &[String],
+    params: &[QueryParam],
+) -> BenchmarkResult<String> {
+    let mut interpolated = String::with_capacity(text.len() + 16 * params.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('$') {
+        interpolated.push_str(&rest[..at]);
+        let after = &rest[at + 1..];
+        let len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        match names.iter().position(|name| *name == after[..len]) {
+            Some(i) => write_literal(&params[i], &mut interpolated)?,
+            None => {
+                interpolated.push('$');
+                interpolated.push_str(&after[..len]);
+            }
+        }
+        rest = &after[len..];
+    }
+    interpolated.push_str(rest);
+    Ok(interpolated)
+}
+
+/// Compact the queries `run` is about to send to `vendor`.
+pub fn compact_queries(
+    queries: Vec<PreparedQuery>,
+    vendor: Vendor,
+    params: QueryParams,
+) -> BenchmarkResult<Vec<CompactQuery>> {
+    let mut templates = QueryTemplates::new(vendor, params);
+    let compacted = queries
+        .into_iter()
+        .map(|query| templates.compact(query))
+        .collect::<BenchmarkResult<Vec<CompactQuery>>>()?;
+    info!(
+        "Compacted {} queries into {} templates",
+        compacted.len(),
+        templates.len()
+    );
+    Ok(compacted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::QueryBuilder;
+
+    #[test]
+    fn compact_queries_share_templates_and_render_like_prepared_ones() {
+        let query = |id: i32| {
+            PreparedQuery::new(
+                3,
+                "single_vertex_read".to_string(),
+                QueryType::Read,
+                QueryBuilder::new()
+                    .text("MATCH (n:User {id: $id}) WHERE n.age > $age RETURN n")
+                    .param("id", id)
+                    .param("age", "x\"y")
+                    .build(),
+            )
+        };
+        let mut templates = QueryTemplates::new(Vendor::Neo4j, QueryParams::Parameterized);
+        let first = templates.compact(query(1)).unwrap();
+        let second = templates.compact(query(2)).unwrap();
+        assert_eq!(templates.len(), 1);
+        assert!(Arc::ptr_eq(
+            &first.statement().template,
+            &second.statement().template
+        ));
+        assert!(Arc::ptr_eq(&first.q_name, &second.q_name));
+        assert_eq!(
+            first.statement().cypher().unwrap(),
+            "CYPHER age='x\"y' id=1 MATCH (n:User {id: $id}) WHERE n.age > $age RETURN n"
+        );
+
+        let transaction = templates
+            .compact(PreparedQuery::new_transaction(
+                9,
+                "tx".to_string(),
+                QueryType::Write,
+                vec![
+                    QueryBuilder::new().text("RETURN 1").build(),
+                    QueryBuilder::new().text("RETURN $v").param("v", 2).build(),
+                ],
+            ))
+            .unwrap();
+        assert!(transaction.is_transaction());
+        assert_eq!(transaction.statements.len(), 2);
+        assert_eq!(transaction.statements[0].cypher().unwrap(), "RETURN 1");
+        assert_eq!(templates.len(), 3);
+
+        let falkor = QueryTemplates::new(Vendor::Falkor, QueryParams::Parameterized)
+            .compact(query(1))
+            .unwrap();
+        let cypher = falkor.statement().cypher().unwrap();
+        assert!(matches!(cypher, Cow::Borrowed(_)));
+        assert_eq!(cypher, first.statement().cypher().unwrap());
+    }
+
+    #[test]
+    fn interpolated_queries_carry_their_values_as_literals() {
+        let query = QueryBuilder::new()
+            .text("MATCH (n:User {id: $id}) SET n.name = $name, n.score = $score RETURN $ids")
+            .param("id", 7)
+            .param("name", "O'Neil")
+            .param("score", 1.0f32)
+            .build();
+        let interpolated = QueryTemplates::new(Vendor::Falkor, QueryParams::Interpolated)
+            .compact(PreparedQuery::new(
+                1,
+                "update".to_string(),
+                QueryType::Write,
+                query,
+            ))
+            .unwrap();
+        let text = "MATCH (n:User {id: 7}) SET n.name = 'O\\'Neil', n.score = 1.0 RETURN $ids";
+        assert_eq!(interpolated.statement().template.text, text);
+        assert_eq!(interpolated.statement().params().count(), 0);
+        assert_eq!(interpolated.statement().cypher().unwrap(), text);
+    }
+}
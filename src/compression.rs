@@ -0,0 +1,123 @@
+//! Transparent `.gz`/`.zst` support for queries files and dataset files. The compression is
+//! picked from the file name, so `generate-queries --name queries.jsonl.zst` writes a zstd
+//! file that `run --name queries.jsonl.zst` reads back, and a cached `.gz` dataset is
+//! streamed through the decoder instead of being inflated to disk first.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
+use async_compression::tokio::write::{GzipEncoder, ZstdEncoder};
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncBufRead, AsyncWrite, BufReader, BufWriter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The compression of `path`, from its extension.
+    pub fn of(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// `reader` decoded with `compression`.
+pub fn decoder<R>(
+    compression: Compression,
+    reader: R,
+) -> Box<dyn AsyncBufRead + Unpin + Send>
+where
+    R: AsyncBufRead + Unpin + Send + 'static,
+{
+    match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => {
+            let mut decoder = GzipDecoder::new(reader);
+            decoder.multiple_members(true);
+            Box::new(BufReader::new(decoder))
+        }
+        Compression::Zstd => {
+            let mut decoder = ZstdDecoder::new(reader);
+            decoder.multiple_members(true);
+            Box::new(BufReader::new(decoder))
+        }
+    }
+}
+
+/// `writer` encoding with `compression`. Shut it down to write the compressed stream's end.
+pub fn encoder<'a, W>(
+    compression: Compression,
+    writer: W,
+) -> Box<dyn AsyncWrite + Unpin + Send + 'a>
+where
+    W: AsyncWrite + Unpin + Send + 'a,
+{
+    match compression {
+        Compression::None => Box::new(writer),
+        Compression::Gzip => Box::new(GzipEncoder::new(writer)),
+        Compression::Zstd => Box::new(ZstdEncoder::new(writer)),
+    }
+}
+
+/// Open `path` for reading, decompressing `.gz` and `.zst` files.
+pub async fn open(path: impl AsRef<Path>) -> BenchmarkResult<Box<dyn AsyncBufRead + Unpin + Send>> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .await
+        .map_err(|e| OtherError(format!("Failed opening {}: {}", path.display(), e)))?;
+    Ok(decoder(Compression::of(path), BufReader::new(file)))
+}
+
+/// Create `path` for writing, compressing `.gz` and `.zst` files.
+pub async fn create(path: impl AsRef<Path>) -> BenchmarkResult<Box<dyn AsyncWrite + Unpin + Send>> {
+    let path = path.as_ref();
+    let file = File::create(path)
+        .await
+        .map_err(|e| OtherError(format!("Failed creating {}: {}", path.display(), e)))?;
+    Ok(encoder(Compression::of(path), BufWriter::new(file)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn compressed_lines_round_trip() {
+        assert_eq!(Compression::of("queries.jsonl.zst"), Compression::Zstd);
+        assert_eq!(Compression::of("cache/pokec.cypher.gz"), Compression::Gzip);
+        assert_eq!(Compression::of("queries.json"), Compression::None);
+
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let mut encoded = Vec::new();
+            {
+                let mut writer = encoder(compression, &mut encoded);
+                writer
+                    .write_all(b"{\"size\":2}\nfirst\nsecond\n")
+                    .await
+                    .unwrap();
+                writer.shutdown().await.unwrap();
+            }
+            let mut lines = decoder(compression, Cursor::new(encoded)).lines();
+            let mut read = Vec::new();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                read.push(line);
+            }
+            assert_eq!(
+                read,
+                vec!["{\"size\":2}", "first", "second"],
+                "{:?}",
+                compression
+            );
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! `--config benchmark.toml`: checked-in defaults for any subcommand's CLI options.
+//!
+//! Each `[<subcommand>]` section (`[run]`, `[load]`, `[generate-queries]`, ...) holds that
+//! subcommand's options keyed by their long flag name, e.g. `mps = 4000` or `cold-start = true`.
+//! The section is turned into CLI arguments placed before the ones actually typed, and every
+//! option overrides itself, so precedence is **CLI flag > file value > built-in default**. The
+//! same file may also hold the `[credentials]` section (see [`crate::credentials`]).
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::utils::redact_endpoint;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command};
+use std::ffi::OsString;
+use std::fmt::Write;
+use toml::{Table, Value};
+
+/// Top-level sections that aren't subcommands.
+const NON_SUBCOMMAND_SECTIONS: [&str; 1] = ["credentials"];
+
+/// Parsed `--config` file.
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    path: String,
+    sections: Table,
+}
+
+impl ConfigFile {
+    pub fn from_toml(
+        path: &str,
+        text: &str,
+    ) -> BenchmarkResult<ConfigFile> {
+        let sections: Table = toml::from_str(text)
+            .map_err(|e| OtherError(format!("invalid config file '{}': {}", path, e)))?;
+        Ok(ConfigFile {
+            path: path.to_string(),
+            sections,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn load(path: &str) -> BenchmarkResult<ConfigFile> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| OtherError(format!("could not read config '{}': {}", path, e)))?;
+        ConfigFile::from_toml(path, &text)
+    }
+
+    /// Reject sections that don't name a subcommand of `cli`, so a typo fails loudly.
+    fn validate_sections(
+        &self,
+        cli: &Command,
+    ) -> BenchmarkResult<()> {
+        for (name, value) in &self.sections {
+            if NON_SUBCOMMAND_SECTIONS.contains(&name.as_str()) {
+                continue;
+            }
+            if cli.find_subcommand(name).is_none() || !value.is_table() {
+                return Err(OtherError(format!(
+                    "unknown section [{}] in config '{}'",
+                    name, self.path
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// CLI arguments for `subcommand` from its section; empty if there is none.
+    fn args_for(
+        &self,
+        subcommand: &Command,
+    ) -> BenchmarkResult<Vec<OsString>> {
+        let Some(Value::Table(section)) = self.sections.get(subcommand.get_name()) else {
+            return Ok(Vec::new());
+        };
+        let mut args = Vec::new();
+        for (key, value) in section {
+            let long = key.replace('_', "-");
+            let arg = subcommand
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(long.as_str()))
+                .ok_or_else(|| {
+                    OtherError(format!(
+                        "unknown option '{}' in [{}] of config '{}'",
+                        key,
+                        subcommand.get_name(),
+                        self.path
+                    ))
+                })?;
+            let flag = format!("--{}", long);
+            if !arg.get_action().takes_values() {
+                match value {
+                    Value::Boolean(true) => args.push(flag.into()),
+                    Value::Boolean(false) => {}
+                    _ => {
+                        return Err(OtherError(format!(
+                            "option '{}' in config '{}' must be true or false",
+                            key, self.path
+                        )))
+                    }
+                }
+                continue;
+            }
+            let values = match value {
+                Value::Array(values) => values.iter().map(scalar_arg).collect(),
+                value => vec![scalar_arg(value)],
+            };
+            let values = values
+                .into_iter()
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| {
+                    OtherError(format!(
+                        "option '{}' in config '{}' must be a string, number or boolean",
+                        key, self.path
+                    ))
+                })?;
+            args.push(flag.into());
+            args.extend(values.into_iter().map(OsString::from));
+        }
+        Ok(args)
+    }
+}
+
+fn scalar_arg(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Integer(i) => Some(i.to_string()),
+        Value::Float(f) => Some(f.to_string()),
+        Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// `cli` with every subcommand's options overriding themselves, so a value typed on the command
+/// line replaces the one spliced in from the config file instead of being rejected as a repeat.
+pub fn with_overridable_args(mut cli: Command) -> Command {
+    let names: Vec<String> = cli
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+    for name in names {
+        cli = cli.mut_subcommand(name, |subcommand| subcommand.args_override_self(true));
+    }
+    cli
+}
+
+/// If the top-level `--config <file>` is present in `args`, splice its section for the chosen
+/// subcommand in right after the subcommand name. Returns the (possibly unchanged) arguments and
+/// the loaded file. `cli` must have `args_override_self` set on its subcommands.
+pub fn apply_config_file(
+    cli: &Command,
+    args: Vec<OsString>,
+) -> BenchmarkResult<(Vec<OsString>, Option<ConfigFile>)> {
+    // Only top-level options can precede the subcommand name.
+    let mut config_path = None;
+    let mut idx = 1;
+    while let Some(arg) = args.get(idx).and_then(|arg| arg.to_str()) {
+        if !arg.starts_with('-') {
+            break;
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            config_path = Some(path.to_string());
+        } else if arg == "--config" {
+            config_path = args
+                .get(idx + 1)
+                .map(|path| path.to_string_lossy().to_string());
+            idx += 1;
+        } else if arg == "--credentials-file" {
+            idx += 1;
+        }
+        idx += 1;
+    }
+    let Some(config_path) = config_path else {
+        return Ok((args, None));
+    };
+    let config = ConfigFile::load(&config_path)?;
+    config.validate_sections(cli)?;
+
+    let Some(subcommand) = args.get(idx).and_then(|name| cli.find_subcommand(name)) else {
+        return Ok((args, Some(config)));
+    };
+    let file_args = config.args_for(subcommand)?;
+    let mut merged = args;
+    merged.splice(idx + 1..idx + 1, file_args);
+    Ok((merged, Some(config)))
+}
+
+/// The fully resolved options of `subcommand` (file, CLI and defaults alike) as a config file
+/// section that `--config` accepts back. Endpoint passwords are redacted and
+/// `--metrics-basic-auth` is left out.
+pub fn resolved_config(
+    subcommand: &Command,
+    matches: &ArgMatches,
+) -> String {
+    let mut toml = format!("[{}]\n", subcommand.get_name());
+    for arg in subcommand.get_arguments() {
+        let (Some(long), id) = (arg.get_long(), arg.get_id().as_str()) else {
+            continue;
+        };
+        if long == "metrics-basic-auth"
+            || matches!(
+                matches.value_source(id),
+                None | Some(ValueSource::EnvVariable)
+            )
+        {
+            continue;
+        }
+        let Ok(Some(raw)) = matches.try_get_raw(id) else {
+            continue;
+        };
+        let mut values: Vec<Value> = raw
+            .map(|raw| {
+                let raw = raw.to_string_lossy();
+                if !arg.get_action().takes_values() {
+                    Value::Boolean(raw == "true")
+                } else if let Ok(i) = raw.parse::<i64>() {
+                    Value::Integer(i)
+                } else if long == "endpoint" {
+                    Value::String(redact_endpoint(&raw))
+                } else {
+                    Value::String(raw.to_string())
+                }
+            })
+            .collect();
+        let value = if values.len() == 1 {
+            values.remove(0)
+        } else {
+            Value::Array(values)
+        };
+        let _ = writeln!(toml, "{} = {}", long, toml_value(&value));
+    }
+    toml
+}
+
+/// [`resolved_config`] of the `run` subcommand, if that's the one `matches` chose.
+pub fn resolved_run_config(
+    cli: &Command,
+    matches: &ArgMatches,
+) -> Option<String> {
+    matches
+        .subcommand()
+        .filter(|(name, _)| *name == "run")
+        .and_then(|(name, run_matches)| {
+            cli.find_subcommand(name)
+                .map(|run_cmd| resolved_config(run_cmd, run_matches))
+        })
+}
+
+/// `config`, a section [`resolved_config`] produced, with each of `overrides` (long flag name
+/// and value) replacing that option's line or added after the others.
+pub fn with_overrides(
+    config: &str,
+    overrides: &[(&str, Value)],
+) -> String {
+    let mut toml = String::new();
+    for line in config.lines() {
+        let long = line.split(" = ").next().unwrap_or_default();
+        if !overrides.iter().any(|(name, _)| *name == long) {
+            let _ = writeln!(toml, "{}", line);
+        }
+    }
+    for (long, value) in overrides {
+        let _ = writeln!(toml, "{} = {}", long, toml_value(value));
+    }
+    toml
+}
+
+/// Inline TOML for the scalars and arrays [`resolved_config`] produces. JSON string escapes are
+/// all valid in TOML basic strings.
+fn toml_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => serde_json::to_string(s).unwrap_or_default(),
+        Value::Array(values) => format!(
+            "[{}]",
+            values.iter().map(toml_value).collect::<Vec<_>>().join(", ")
+        ),
+        Value::Integer(i) => i.to_string(),
+        Value::Boolean(b) => b.to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{Arg, ArgAction};
+
+    fn cli() -> Command {
+        Command::new("benchmark")
+            .arg(Arg::new("config").long("config"))
+            .subcommand(
+                Command::new("run")
+                    .args_override_self(true)
+                    .arg(Arg::new("vendor").long("vendor").required(true))
+                    .arg(
+                        Arg::new("mps")
+                            .long("mps")
+                            .value_parser(clap::value_parser!(usize))
+                            .default_value("1000"),
+                    )
+                    .arg(Arg::new("endpoint").long("endpoint"))
+                    .arg(Arg::new("warm").long("warm").action(ArgAction::SetTrue)),
+            )
+    }
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn cli_flags_override_config_file_values() {
+        let config = ConfigFile::from_toml(
+            "benchmark.toml",
+            "[run]\nvendor = \"neo4j\"\nmps = 4000\nwarm = true\nendpoint = \"neo4j://u:pw@db:7687\"\n",
+        )
+        .unwrap();
+        let cli = cli();
+        let run = cli.find_subcommand("run").unwrap();
+        let mut args = os_args(&["benchmark", "run"]);
+        args.extend(config.args_for(run).unwrap());
+        args.extend(os_args(&["--mps", "10"]));
+
+        let matches = cli.clone().try_get_matches_from(args).unwrap();
+        let (_, run_matches) = matches.subcommand().unwrap();
+        assert_eq!(run_matches.get_one::<usize>("mps"), Some(&10));
+        assert_eq!(
+            run_matches.get_one::<String>("vendor").map(String::as_str),
+            Some("neo4j")
+        );
+        assert!(run_matches.get_flag("warm"));
+
+        let resolved = resolved_config(run, run_matches);
+        assert!(resolved.contains("mps = 10"));
+        assert!(resolved.contains("warm = true"));
+        assert!(!resolved.contains("pw"));
+        let child = with_overrides(&resolved, &[("mps", Value::Integer(20))]);
+        assert!(child.contains("mps = 20") && !child.contains("mps = 10"));
+        assert!(child.starts_with("[run]\n") && child.contains("warm = true"));
+
+        assert!(ConfigFile::from_toml("x", "[run]\nmsp = 1\n")
+            .unwrap()
+            .args_for(run)
+            .is_err());
+        assert!(ConfigFile::from_toml("x", "[rnu]\nmps = 1\n")
+            .unwrap()
+            .validate_sections(&cli)
+            .is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Re-loadable Cypher for `MemgraphClient::export_to_file`, the dump path of external endpoints
+//! where the server's snapshot directory isn't reachable.
+//!
+//! The file has one statement per line. Nodes are created in `UNWIND` batches of the same label
+//! set, tagged with a temporary label and their exported id; relationships then match their
+//! endpoints through that id, in batches of the same type. The last statements drop the tag and
+//! its index again. Strings, labels, types and property keys are escaped, so any value the
+//! server returned reads back the same.
+
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+pub const EXPORT_BATCH_SIZE: usize = 1000;
+
+/// Marks exported nodes while relationships are matched to them.
+const EXPORT_LABEL: &str = "__benchmark_export__";
+const EXPORT_ID: &str = "__benchmark_export_id__";
+
+/// A Cypher string literal.
+pub fn quote_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('\'');
+    for c in s.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\'' => quoted.push_str("\\'"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// A label, relationship type or property key, backtick-quoted.
+pub fn quote_name(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// `value` as a Cypher literal.
+pub fn literal(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => quote_string(s),
+        Value::Array(items) => format!(
+            "[{}]",
+            items.iter().map(literal).collect::<Vec<_>>().join(", ")
+        ),
+        Value::Object(map) => map_literal(map),
+    }
+}
+
+/// A map literal; null entries are left out, as stored properties can't be null.
+fn map_literal(map: &Map<String, Value>) -> String {
+    let entries: Vec<String> = map
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| format!("{}: {}", quote_name(key), literal(value)))
+        .collect();
+    format!("{{{}}}", entries.join(", "))
+}
+
+/// Statements before the nodes.
+pub fn preamble() -> Vec<String> {
+    vec![format!(
+        "CREATE INDEX ON :{}({});",
+        quote_name(EXPORT_LABEL),
+        quote_name(EXPORT_ID)
+    )]
+}
+
+/// Statements after the relationships.
+pub fn epilogue() -> Vec<String> {
+    vec![
+        format!(
+            "MATCH (n:{}) REMOVE n:{}, n.{};",
+            quote_name(EXPORT_LABEL),
+            quote_name(EXPORT_LABEL),
+            quote_name(EXPORT_ID)
+        ),
+        format!(
+            "DROP INDEX ON :{}({});",
+            quote_name(EXPORT_LABEL),
+            quote_name(EXPORT_ID)
+        ),
+    ]
+}
+
+/// Creates nodes with `labels`, each `(id, properties)`.
+pub fn nodes_statement(
+    labels: &[String],
+    nodes: &[(i64, Map<String, Value>)],
+) -> String {
+    let rows: Vec<String> = nodes
+        .iter()
+        .map(|(id, props)| format!("{{id: {}, props: {}}}", id, map_literal(props)))
+        .collect();
+    let labels: String = std::iter::once(EXPORT_LABEL)
+        .chain(labels.iter().map(String::as_str))
+        .map(|label| format!(":{}", quote_name(label)))
+        .collect();
+    format!(
+        "UNWIND [{}] AS row CREATE (n{} {{{}: row.id}}) SET n += row.props;",
+        rows.join(", "),
+        labels,
+        quote_name(EXPORT_ID)
+    )
+}
+
+/// Creates relationships of `rel_type`, each `(from id, to id, properties)`.
+pub fn relationships_statement(
+    rel_type: &str,
+    relationships: &[(i64, i64, Map<String, Value>)],
+) -> String {
+    let rows: Vec<String> = relationships
+        .iter()
+        .map(|(from, to, props)| {
+            format!(
+                "{{from: {}, to: {}, props: {}}}",
+                from,
+                to,
+                map_literal(props)
+            )
+        })
+        .collect();
+    let label = quote_name(EXPORT_LABEL);
+    let id = quote_name(EXPORT_ID);
+    format!(
+        "UNWIND [{}] AS row MATCH (a:{} {{{}: row.from}}), (b:{} {{{}: row.to}}) CREATE (a)-[r:{}]->(b) SET r += row.props;",
+        rows.join(", "),
+        label,
+        id,
+        label,
+        id,
+        quote_name(rel_type)
+    )
+}
+
+/// Items grouped by `K` (a label set, a relationship type) into batches of `batch_size`.
+pub struct Batches<K, T> {
+    batch_size: usize,
+    pending: BTreeMap<K, Vec<T>>,
+}
+
+impl<K: Ord + Clone, T> Batches<K, T> {
+    pub fn new(batch_size: usize) -> Self {
+        Batches {
+            batch_size: batch_size.max(1),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Add `item`; returns its group's batch once that is full.
+    pub fn push(
+        &mut self,
+        key: K,
+        item: T,
+    ) -> Option<(K, Vec<T>)> {
+        let batch = self.pending.entry(key.clone()).or_default();
+        batch.push(item);
+        if batch.len() >= self.batch_size {
+            self.pending.remove(&key).map(|batch| (key, batch))
+        } else {
+            None
+        }
+    }
+
+    /// The partial batches left.
+    pub fn drain(&mut self) -> Vec<(K, Vec<T>)> {
+        std::mem::take(&mut self.pending).into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn statements_escape_values_and_names() {
+        assert_eq!(
+            quote_string("O'Brien \\ \"x\"\n"),
+            "'O\\'Brien \\\\ \"x\"\\n'"
+        );
+        assert_eq!(quote_name("we`ird"), "`we``ird`");
+        assert_eq!(
+            literal(&json!({"a": [1, 2.5, true], "b": null, "c": "x"})),
+            "{`a`: [1, 2.5, true], `c`: 'x'}"
+        );
+
+        let props = json!({"name": "Ann", "age": 31})
+            .as_object()
+            .unwrap()
+            .clone();
+        assert_eq!(
+            nodes_statement(&["User".to_string()], &[(7, props)]),
+            "UNWIND [{id: 7, props: {`age`: 31, `name`: 'Ann'}}] AS row CREATE \
+             (n:`__benchmark_export__`:`User` {`__benchmark_export_id__`: row.id}) SET n += row.props;"
+        );
+        assert!(
+            relationships_statement("FRIEND", &[(7, 8, Map::new())]).contains(
+                "[{from: 7, to: 8, props: {}}] AS row MATCH (a:`__benchmark_export__` \
+             {`__benchmark_export_id__`: row.from})"
+            )
+        );
+
+        let mut batches = Batches::new(2);
+        assert!(batches.push("User", 1).is_none());
+        assert!(batches.push("Post", 2).is_none());
+        assert_eq!(batches.push("User", 3), Some(("User", vec![1, 3])));
+        assert_eq!(batches.drain(), vec![("Post", vec![2])]);
+        assert!(batches.drain().is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+//! `--graph-name` and `--database`: which graph on the server `load` and `run` use. FalkorDB keeps
+//! the benchmark graph under the key `falkor`, Neo4j and Memgraph in their default Bolt databases.
+//! A shared or cloud instance may hold it elsewhere, so the name can be given with `--endpoint`; a
+//! locally managed server always restores its dump under the default name.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::scenario::Vendor;
+use clap::Args;
+
+pub const DEFAULT_FALKOR_GRAPH: &str = "falkor";
+pub const DEFAULT_NEO4J_DATABASE: &str = "neo4j";
+pub const DEFAULT_MEMGRAPH_DATABASE: &str = "memgraph";
+
+#[derive(Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatabaseConfig {
+    #[arg(
+        long,
+        required = false,
+        requires = "endpoint",
+        help = "FalkorDB graph key to load or query instead of `falkor`"
+    )]
+    pub graph_name: Option<String>,
+    #[arg(
+        long,
+        required = false,
+        requires = "endpoint",
+        help = "Neo4j or Memgraph database to load or query instead of `neo4j` or `memgraph`"
+    )]
+    pub database: Option<String>,
+}
+
+impl DatabaseConfig {
+    /// The graph key or database `vendor` is used through. The option of the other vendors is
+    /// refused rather than ignored.
+    pub fn resolve(
+        &self,
+        vendor: Vendor,
+    ) -> BenchmarkResult<String> {
+        let (name, option, other, other_option) = match vendor {
+            Vendor::Falkor => (
+                &self.graph_name,
+                "--graph-name",
+                &self.database,
+                "--database",
+            ),
+            Vendor::Neo4j | Vendor::Memgraph => (
+                &self.database,
+                "--database",
+                &self.graph_name,
+                "--graph-name",
+            ),
+        };
+        if other.is_some() {
+            return Err(OtherError(format!(
+                "{} doesn't apply to --vendor {}; use {}",
+                other_option, vendor, option
+            )));
+        }
+        match name {
+            Some(name) if name.trim().is_empty() => {
+                Err(OtherError(format!("{} can't be empty", option)))
+            }
+            Some(name) => Ok(name.clone()),
+            None => Ok(default_database(vendor).to_string()),
+        }
+    }
+}
+
+/// The graph key or database the benchmark uses on `vendor` unless told otherwise.
+pub fn default_database(vendor: Vendor) -> &'static str {
+    match vendor {
+        Vendor::Falkor => DEFAULT_FALKOR_GRAPH,
+        Vendor::Neo4j => DEFAULT_NEO4J_DATABASE,
+        Vendor::Memgraph => DEFAULT_MEMGRAPH_DATABASE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_vendor_takes_its_own_option_or_its_default() {
+        let config = |graph_name: Option<&str>, database: Option<&str>| DatabaseConfig {
+            graph_name: graph_name.map(str::to_string),
+            database: database.map(str::to_string),
+        };
+        assert_eq!(
+            DatabaseConfig::default().resolve(Vendor::Falkor).unwrap(),
+            "falkor"
+        );
+        assert_eq!(
+            DatabaseConfig::default().resolve(Vendor::Memgraph).unwrap(),
+            "memgraph"
+        );
+        assert_eq!(
+            config(Some("bench"), None).resolve(Vendor::Falkor).unwrap(),
+            "bench"
+        );
+        assert_eq!(
+            config(None, Some("bench")).resolve(Vendor::Neo4j).unwrap(),
+            "bench"
+        );
+        let err = config(Some("bench"), None)
+            .resolve(Vendor::Neo4j)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--graph-name doesn't apply"), "{}", err);
+        assert!(config(None, Some("bench")).resolve(Vendor::Falkor).is_err());
+        assert!(config(Some(" "), None).resolve(Vendor::Falkor).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! The Pokec users graph as its dataset file holds it, for `generate-queries
+//! --enable-correlated-params`. Two uniformly random ids are rarely within a few hops of each
+//! other, so most path-finding queries between them find nothing and only measure how quickly an
+//! engine gives up. Knowing the graph, the generator draws the target of a path query by walking
+//! from its source, and an age threshold from the ages of the source's friends, so the queries
+//! return rows the way a real application's would.
+//!
+//! The graph is the one loaded: writes during a run don't change which pairs were drawn.
+
+use crate::error::BenchmarkResult;
+use crate::falkor::{users_edge_ids, users_node_map};
+use crate::scenario::Spec;
+use futures::StreamExt;
+use rand::prelude::IndexedRandom;
+use rand::{Rng, RngExt};
+use tracing::info;
+
+/// Out-edges and ages by node id.
+#[derive(Debug, Default)]
+pub struct DatasetGraph {
+    /// `targets[offsets[id]..offsets[id + 1]]` are the nodes `id` has a `Friend` edge to.
+    offsets: Vec<usize>,
+    targets: Vec<i32>,
+    ages: Vec<Option<i32>>,
+}
+
+impl DatasetGraph {
+    /// Read the dataset file of `spec`, downloading it to the cache first if needed.
+    pub async fn load(spec: &Spec<'_>) -> BenchmarkResult<Self> {
+        let mut lines = spec.init_data_iterator().await?;
+        let mut builder = DatasetGraphBuilder::default();
+        while let Some(line) = lines.next().await {
+            builder.push(&line?);
+        }
+        let graph = builder.build();
+        info!(
+            "Read {} nodes and {} edges to correlate query parameters",
+            graph.ages.iter().flatten().count(),
+            graph.targets.len()
+        );
+        Ok(graph)
+    }
+
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut builder = DatasetGraphBuilder::default();
+        for line in lines {
+            builder.push(line);
+        }
+        builder.build()
+    }
+
+    fn neighbours(
+        &self,
+        id: i32,
+    ) -> &[i32] {
+        let id = id as usize;
+        match (self.offsets.get(id), self.offsets.get(id + 1)) {
+            (Some(&start), Some(&end)) => &self.targets[start..end],
+            _ => &[],
+        }
+    }
+
+    pub fn degree(
+        &self,
+        id: i32,
+    ) -> usize {
+        self.neighbours(id).len()
+    }
+
+    pub fn age(
+        &self,
+        id: i32,
+    ) -> Option<i32> {
+        self.ages.get(id as usize).copied().flatten()
+    }
+
+    /// The node a walk of 1 to `max_hops` random edges from `from` ends on; `None` when it ends
+    /// back on `from`, or `from` has no edges.
+    pub fn walk(
+        &self,
+        rng: &mut dyn Rng,
+        from: i32,
+        max_hops: u8,
+    ) -> Option<i32> {
+        let hops = rng.random_range(1..=max_hops.max(1));
+        let mut at = from;
+        for _ in 0..hops {
+            match self.neighbours(at).choose(rng) {
+                Some(&next) => at = next,
+                None => break,
+            }
+        }
+        (at != from).then_some(at)
+    }
+
+    /// The age of a random friend of `id`, so a `>=` threshold on it keeps at least that friend.
+    pub fn friend_age(
+        &self,
+        rng: &mut dyn Rng,
+        id: i32,
+    ) -> Option<i32> {
+        self.neighbours(id)
+            .choose(rng)
+            .and_then(|&friend| self.age(friend))
+    }
+}
+
+#[derive(Default)]
+struct DatasetGraphBuilder {
+    edges: Vec<(i32, i32)>,
+    ages: Vec<Option<i32>>,
+}
+
+impl DatasetGraphBuilder {
+    fn push(
+        &mut self,
+        line: &str,
+    ) {
+        let trimmed = line.trim();
+        if trimmed.starts_with("MATCH") {
+            if let Some((from, to)) = users_edge_ids(trimmed) {
+                if let (Ok(from), Ok(to)) = (i32::try_from(from), i32::try_from(to)) {
+                    self.edges.push((from, to));
+                }
+            }
+        } else if let Some(map) = users_node_map(trimmed) {
+            let (Some(id), age) = (property(map, "id"), property(map, "age")) else {
+                return;
+            };
+            let Ok(id) = usize::try_from(id) else {
+                return;
+            };
+            if self.ages.len() <= id {
+                self.ages.resize(id + 1, None);
+            }
+            self.ages[id] = age.and_then(|age| i32::try_from(age).ok());
+        }
+    }
+
+    fn build(mut self) -> DatasetGraph {
+        self.edges.sort_unstable();
+        let nodes = self
+            .edges
+            .last()
+            .map_or(0, |&(from, _)| from as usize + 1)
+            .max(self.ages.len());
+        let mut offsets = vec![0usize; nodes + 1];
+        for &(from, _) in &self.edges {
+            offsets[from as usize + 1] += 1;
+        }
+        for id in 1..offsets.len() {
+            offsets[id] += offsets[id - 1];
+        }
+        DatasetGraph {
+            offsets,
+            targets: self.edges.into_iter().map(|(_, to)| to).collect(),
+            ages: self.ages,
+        }
+    }
+}
+
+/// The integer `key` of a property map like `{id: 1, age: 20, gender: "male"}`.
+fn property(
+    map: &str,
+    key: &str,
+) -> Option<i64> {
+    map.trim_start_matches('{')
+        .trim_end_matches('}')
+        .split(',')
+        .filter_map(|pair| pair.split_once(':'))
+        .find(|(name, _)| name.trim() == key)
+        .and_then(|(_, value)| value.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn walks_follow_the_dataset_edges() {
+        let graph = DatasetGraph::from_lines([
+            "CREATE (:User {id: 1, completion_percentage: 14, gender: \"male\", age: 18});",
+            "CREATE (:User {id: 2, completion_percentage: 62, gender: \"female\", age: 31});",
+            "CREATE (:User {id: 3, completion_percentage: 0, gender: \"male\", age: 44});",
+            "CREATE (:User {id: 4, completion_percentage: 0, gender: \"male\"});",
+            "MATCH (n:User {id: 1}), (m:User {id: 2}) CREATE (n)-[e: Friend]->(m);",
+            "MATCH (n:User {id: 2}), (m:User {id: 3}) CREATE (n)-[e: Friend]->(m);",
+        ]);
+        assert_eq!(
+            (graph.degree(1), graph.degree(3), graph.degree(99)),
+            (1, 0, 0)
+        );
+        assert_eq!((graph.age(2), graph.age(4)), (Some(31), None));
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let to = graph.walk(&mut rng, 1, 2).unwrap();
+            assert!(to == 2 || to == 3, "{}", to);
+            assert_eq!(graph.walk(&mut rng, 2, 1), Some(3));
+        }
+        assert_eq!(graph.walk(&mut rng, 3, 4), None);
+        assert_eq!(graph.friend_age(&mut rng, 1), Some(31));
+        assert_eq!(graph.friend_age(&mut rng, 3), None);
+    }
+}
@@ -0,0 +1,411 @@
+//! Distributed load generation: several `run --slice` clients, usually on different hosts, share
+//! one queries file and one server. Each runs every n-th query, `coordinate` starts them together
+//! through their control APIs, and `aggregate` merges their results into one logical run.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::metrics_collector::{percentile_us, LatencyHistograms};
+use crate::run_status::{Phase, RunStatus};
+use futures::future::join_all;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Results of client `<i>` go to `<vendor>/client-<i>/` (or `<vendor>/run-<n>/client-<i>/`).
+pub const CLIENT_DIR_PREFIX: &str = "client-";
+
+/// Gauges whose values add up across clients; other gauges keep the largest client value.
+const SUMMED_GAUGES: [&str; 2] = ["throughput_qps", "query_timeouts"];
+
+/// `--slice <index>/<count>`, 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QuerySlice {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl QuerySlice {
+    /// Every `count`-th query starting at `index`, so each client keeps the file's query mix.
+    pub fn apply<T>(
+        &self,
+        queries: Vec<T>,
+    ) -> Vec<T> {
+        queries
+            .into_iter()
+            .enumerate()
+            .filter(|(position, _)| position % self.count == self.index - 1)
+            .map(|(_, query)| query)
+            .collect()
+    }
+
+    pub fn dir_name(&self) -> String {
+        format!("{}{}", CLIENT_DIR_PREFIX, self.index)
+    }
+}
+
+impl FromStr for QuerySlice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid slice '{}', expected <index>/<count> like 2/4", s);
+        let (index, count) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let index: usize = index.trim().parse().map_err(|_| invalid())?;
+        let count: usize = count.trim().parse().map_err(|_| invalid())?;
+        if index == 0 || index > count {
+            return Err(format!(
+                "slice index must be between 1 and the count, got '{}'",
+                s
+            ));
+        }
+        Ok(QuerySlice { index, count })
+    }
+}
+
+impl fmt::Display for QuerySlice {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// The value of an in-process percentile gauge (see `MetricsCollector::export_to_prometheus`)
+/// over the merged histograms; `None` for any other sample.
+fn percentile_gauge(
+    histograms: &LatencyHistograms,
+    name: &str,
+    labels: &BTreeMap<String, String>,
+) -> Option<f64> {
+    let pct_label = || labels.get("pct")?.parse::<f64>().ok();
+    let hist = if name == "query_group_latency_us" {
+        return Some(percentile_us(&histograms.group(labels.get("group")?)?, pct_label()?) as f64);
+    } else if name == "query_type_latency_us" {
+        return Some(
+            percentile_us(histograms.types.get(labels.get("type")?)?, pct_label()?) as f64,
+        );
+    } else if name.ends_with("_query_latency_pct_us") {
+        return Some(
+            percentile_us(histograms.queries.get(labels.get("query")?)?, pct_label()?) as f64,
+        );
+    } else if name.starts_with("falkordb_server_execution_p") {
+        histograms.server.as_ref()?
+    } else if [
+        "falkordb_latency_",
+        "neo4j_latency_",
+        "memgraph_latency_",
+    ]
+    .iter()
+    .any(|prefix| name.starts_with(prefix))
+    {
+        &histograms.overall
+    } else {
+        return None;
+    };
+    // `_p99_us`, `_p99_9_us` (P99.9) or `_max_us`.
+    let stem = name.strip_suffix("_us")?;
+    let pct: f64 = if stem.ends_with("_max") {
+        100.0
+    } else {
+        stem.rsplit_once("_p")?.1.replace('_', ".").parse().ok()?
+    };
+    Some(percentile_us(hist, pct) as f64)
+}
+
+struct Family {
+    comments: Vec<String>,
+    kind: String,
+    samples: Vec<(String, f64)>,
+    index: HashMap<String, usize>,
+}
+
+/// Merge the clients' `metrics.prom` snapshots: counters and histograms are summed, the
+/// percentile gauges are recomputed from `histograms` when given, [`SUMMED_GAUGES`] are summed,
+/// and every other gauge keeps its largest value.
+pub fn merge_metrics_text(
+    texts: &[String],
+    histograms: Option<&LatencyHistograms>,
+) -> String {
+    let mut families: Vec<(String, Family)> = Vec::new();
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    for text in texts {
+        let mut current: Option<usize> = None;
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(comment) = line.strip_prefix("# ") {
+                let mut parts = comment.splitn(3, ' ');
+                let (Some(keyword), Some(name)) = (parts.next(), parts.next()) else {
+                    continue;
+                };
+                let family = *by_name.entry(name.to_string()).or_insert_with(|| {
+                    families.push((
+                        name.to_string(),
+                        Family {
+                            comments: Vec::new(),
+                            kind: "untyped".to_string(),
+                            samples: Vec::new(),
+                            index: HashMap::new(),
+                        },
+                    ));
+                    families.len() - 1
+                });
+                let entry = &mut families[family].1;
+                if keyword == "TYPE" {
+                    entry.kind = parts.next().unwrap_or("untyped").to_string();
+                }
+                if !entry.comments.iter().any(|c| c == line) {
+                    entry.comments.push(line.to_string());
+                }
+                current = Some(family);
+                continue;
+            }
+            let Some((key, value)) = line.rsplit_once(' ') else {
+                continue;
+            };
+            let Ok(value) = value.parse::<f64>() else {
+                continue;
+            };
+            let family = match current {
+                Some(family) => family,
+                None => {
+                    let name = key.split('{').next().unwrap_or(key).to_string();
+                    let family = *by_name.entry(name.clone()).or_insert_with(|| {
+                        families.push((
+                            name,
+                            Family {
+                                comments: Vec::new(),
+                                kind: "untyped".to_string(),
+                                samples: Vec::new(),
+                                index: HashMap::new(),
+                            },
+                        ));
+                        families.len() - 1
+                    });
+                    current = Some(family);
+                    family
+                }
+            };
+            let (name, entry) = &mut families[family];
+            let summed = entry.kind != "gauge" || SUMMED_GAUGES.contains(&name.as_str());
+            match entry.index.get(key) {
+                Some(&i) if summed => entry.samples[i].1 += value,
+                Some(&i) => entry.samples[i].1 = entry.samples[i].1.max(value),
+                None => {
+                    entry.index.insert(key.to_string(), entry.samples.len());
+                    entry.samples.push((key.to_string(), value));
+                }
+            }
+        }
+    }
+
+    let mut merged = String::new();
+    for (name, family) in families {
+        for comment in &family.comments {
+            merged.push_str(comment);
+            merged.push('\n');
+        }
+        for (key, value) in family.samples {
+            let value = histograms
+                .filter(|_| family.kind == "gauge")
+                .and_then(|histograms| percentile_gauge(histograms, &name, &sample_labels(&key)))
+                .unwrap_or(value);
+            merged.push_str(&format!("{} {}\n", key, value));
+        }
+    }
+    merged
+}
+
+fn sample_labels(key: &str) -> BTreeMap<String, String> {
+    let Some((_, labels)) = key.split_once('{') else {
+        return BTreeMap::new();
+    };
+    labels
+        .trim_end_matches('}')
+        .split(',')
+        .filter_map(|part| {
+            let (k, v) = part.split_once('=')?;
+            Some((k.trim().to_string(), v.trim().trim_matches('"').to_string()))
+        })
+        .collect()
+}
+
+async fn worker_status(
+    client: &reqwest::Client,
+    worker: &str,
+) -> Option<RunStatus> {
+    let body = client
+        .get(format!("{}/status", worker))
+        .send()
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    serde_json::from_str(&body).ok()
+}
+
+/// Wait until every worker (`run --control-api --wait-for-start`, given by its metrics endpoint
+/// URL) is ready, start them all at once, then follow them until each has finished.
+pub async fn coordinate(
+    workers: &[String],
+    poll_interval: Duration,
+    ready_timeout: Duration,
+) -> BenchmarkResult<()> {
+    let workers: Vec<String> = workers
+        .iter()
+        .map(|worker| worker.trim_end_matches('/').to_string())
+        .collect();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+
+    let waiting_since = Instant::now();
+    loop {
+        let statuses = join_all(workers.iter().map(|w| worker_status(&client, w))).await;
+        let not_ready: Vec<&String> = workers
+            .iter()
+            .zip(&statuses)
+            .filter(|(_, status)| status.as_ref().is_none_or(|s| s.phase != Phase::Ready))
+            .map(|(worker, _)| worker)
+            .collect();
+        if not_ready.is_empty() {
+            break;
+        }
+        if waiting_since.elapsed() > ready_timeout {
+            return Err(OtherError(format!(
+                "workers not ready after {:?}: {:?}",
+                ready_timeout, not_ready
+            )));
+        }
+        info!("Waiting for {} worker(s) to be ready", not_ready.len());
+        tokio::time::sleep(poll_interval).await;
+    }
+
+    info!("Starting {} workers", workers.len());
+    let started = join_all(
+        workers
+            .iter()
+            .map(|w| client.post(format!("{}/control/start", w)).send()),
+    )
+    .await;
+    for (worker, response) in workers.iter().zip(started) {
+        if let Err(e) = response.and_then(|r| r.error_for_status()) {
+            return Err(OtherError(format!("failed to start {}: {}", worker, e)));
+        }
+    }
+
+    // A worker exits once it has written its results, so one that stops answering after it
+    // started counts as finished.
+    let mut finished = vec![false; workers.len()];
+    while finished.iter().any(|done| !done) {
+        tokio::time::sleep(poll_interval).await;
+        let statuses = join_all(workers.iter().map(|w| worker_status(&client, w))).await;
+        let (mut completed, mut total, mut qps) = (0, 0, 0.0);
+        for ((worker, status), done) in workers.iter().zip(statuses).zip(finished.iter_mut()) {
+            match status {
+                Some(status) => {
+                    *done = status.phase == Phase::Done;
+                    completed += status.completed;
+                    total += status.total;
+                    qps += status.qps;
+                }
+                None if !*done => {
+                    warn!("{} stopped answering, counting it as finished", worker);
+                    *done = true;
+                }
+                None => {}
+            }
+        }
+        info!(
+            "{}/{} workers finished, {} of {} queries, {:.0} qps",
+            finished.iter().filter(|done| **done).count(),
+            workers.len(),
+            completed,
+            total,
+            qps
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics_collector::RunHistograms;
+    use histogram::{Histogram, SparseHistogram};
+
+    #[test]
+    fn slices_interleave_the_queries() {
+        let slice: QuerySlice = "2/3".parse().unwrap();
+        assert_eq!(slice.apply((0..8).collect()), vec![1, 4, 7]);
+        assert_eq!(slice.to_string(), "2/3");
+        assert_eq!(slice.dir_name(), "client-2");
+        assert!("0/3".parse::<QuerySlice>().is_err());
+        assert!("4/3".parse::<QuerySlice>().is_err());
+        assert!("2".parse::<QuerySlice>().is_err());
+    }
+
+    #[test]
+    fn merged_metrics_sum_counters_and_recompute_percentiles() {
+        let client = |latencies_us: &[u64], cpu: f64| {
+            let mut hist = Histogram::new(7, 64).unwrap();
+            for us in latencies_us {
+                hist.increment(*us).unwrap();
+            }
+            let text = format!(
+                "# HELP operations_total ops\n# TYPE operations_total counter\n\
+                 operations_total{{vendor=\"falkor\"}} {}\n\
+                 # HELP cpu_usage cpu\n# TYPE cpu_usage gauge\ncpu_usage {}\n\
+                 # HELP falkordb_latency_p99_us p99\n# TYPE falkordb_latency_p99_us gauge\n\
+                 falkordb_latency_p99_us {}\n\
+                 # HELP falkordb_latency_max_us max\n# TYPE falkordb_latency_max_us gauge\n\
+                 falkordb_latency_max_us {}\n\
+                 # HELP falkordb_query_latency_pct_us pct\n\
+                 # TYPE falkordb_query_latency_pct_us gauge\n\
+                 falkordb_query_latency_pct_us{{query=\"single_vertex_read\",pct=\"50\"}} {}\n",
+                latencies_us.len(),
+                cpu,
+                percentile_us(&hist, 99.0),
+                percentile_us(&hist, 100.0),
+                percentile_us(&hist, 50.0)
+            );
+            let histograms = RunHistograms {
+                overall: SparseHistogram::from(&hist),
+                server: None,
+                queries: BTreeMap::from([(
+                    "single_vertex_read".to_string(),
+                    SparseHistogram::from(&hist),
+                )]),
+                types: BTreeMap::new(),
+            };
+            (text, histograms)
+        };
+        let (fast_text, fast) = client(&[100; 99], 10.0);
+        let (slow_text, slow) = client(&[100_000], 30.0);
+
+        let histograms = LatencyHistograms::from_runs(&[fast, slow]).unwrap();
+        let merged = merge_metrics_text(&[fast_text, slow_text], Some(&histograms));
+        let samples: BTreeMap<&str, f64> = merged
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let (key, value) = line.rsplit_once(' ')?;
+                Some((key, value.parse().ok()?))
+            })
+            .collect();
+
+        assert_eq!(samples["operations_total{vendor=\"falkor\"}"], 100.0);
+        assert_eq!(samples["cpu_usage"], 30.0);
+        // 1 of the 100 merged calls is slow, so the merged p99 is still a fast call, while a
+        // max over the clients' p99s would report the slow one.
+        assert!(samples["falkordb_latency_p99_us"] < 1_000.0);
+        assert!(samples["falkordb_latency_max_us"] >= 100_000.0);
+        assert!(
+            samples["falkordb_query_latency_pct_us{query=\"single_vertex_read\",pct=\"50\"}"]
+                < 1_000.0
+        );
+        assert_eq!(merged.matches("# TYPE operations_total counter").count(), 1);
+    }
+}
@@ -0,0 +1,438 @@
+//! `benchmark doctor`: check what a managed `load` or `run` of a vendor needs before starting
+//! one, and say how to fix what's missing. Otherwise a missing binary or a busy port shows up
+//! as a spawn failure halfway into the command.
+
+use crate::artifact_store::dump_files;
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::memgraph::memgraph_home;
+use crate::neo4j::neo4j_home;
+use crate::scenario::{Name, Size, Spec, Vendor};
+use crate::utils::falkor_module_path;
+use clap::ValueEnum;
+use std::env;
+use std::fmt;
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+const GIB: u64 = 1 << 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    /// Worth fixing, but a run may still work.
+    Warn,
+    /// A managed run of the vendor fails without a fix.
+    Fail,
+}
+
+/// One line of the report, with what to do about it unless it passed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+    ) -> Self {
+        Check {
+            name: name.into(),
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn problem(
+        status: CheckStatus,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Check {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let status = match self.status {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "FAIL",
+        };
+        write!(f, "[{:<4}] {}: {}", status, self.name, self.detail)?;
+        if let Some(fix) = &self.fix {
+            write!(f, "\n       fix: {}", fix)?;
+        }
+        Ok(())
+    }
+}
+
+/// Print every check for `vendor`; an error when any of them failed. `size` narrows the dump
+/// check to the dataset about to be run.
+pub fn run_doctor(
+    vendor: Vendor,
+    size: Option<Size>,
+) -> BenchmarkResult<()> {
+    let checks = checks(vendor, size);
+    for check in &checks {
+        println!("{}", check);
+    }
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        return Err(OtherError(format!(
+            "{} of {} checks failed for {}",
+            failed,
+            checks.len(),
+            vendor
+        )));
+    }
+    Ok(())
+}
+
+pub fn checks(
+    vendor: Vendor,
+    size: Option<Size>,
+) -> Vec<Check> {
+    let mut checks = binaries(vendor);
+    checks.extend(ports(vendor).into_iter().map(port_check));
+    checks.push(dumps(vendor, size));
+    checks.push(disk_space());
+    checks.extend(open_files(vendor));
+    checks
+}
+
+fn binaries(vendor: Vendor) -> Vec<Check> {
+    match vendor {
+        Vendor::Falkor => {
+            let path_var = env::var("PATH").unwrap_or_default();
+            let redis_server = match find_in_path("redis-server", &path_var) {
+                Some(path) => Check::ok("redis-server", path.display().to_string()),
+                None => Check::problem(
+                    CheckStatus::Fail,
+                    "redis-server",
+                    "not found on PATH",
+                    "install Redis (e.g. `sudo apt install redis-server`) or add its bin directory to PATH",
+                ),
+            };
+            let module = match falkor_module_path() {
+                Ok(path) if Path::new(&path).is_file() => Check::ok("falkordb.so", path),
+                Ok(path) => Check::problem(
+                    CheckStatus::Fail,
+                    "falkordb.so",
+                    format!("{} does not exist", path),
+                    "build FalkorDB and copy falkordb.so into the working directory, or set FALKOR_PATH to it",
+                ),
+                Err(e) => Check::problem(
+                    CheckStatus::Fail,
+                    "falkordb.so",
+                    e.to_string(),
+                    "set FALKOR_PATH to the module",
+                ),
+            };
+            vec![redis_server, module]
+        }
+        Vendor::Neo4j => {
+            let home = neo4j_home();
+            vec![home_binary(
+                "neo4j",
+                Path::new(&home).join("bin/neo4j"),
+                "run `./scripts/download-neo4j.sh`, or set NEO4J_HOME to a Neo4j installation",
+            )]
+        }
+        Vendor::Memgraph => {
+            let home = memgraph_home();
+            vec![home_binary(
+                "memgraph",
+                Path::new(&home).join("memgraph"),
+                "set MEMGRAPH_HOME to the directory holding the memgraph binary",
+            )]
+        }
+    }
+}
+
+fn home_binary(
+    name: &str,
+    binary: PathBuf,
+    fix: &str,
+) -> Check {
+    if is_executable(&binary) {
+        Check::ok(name, binary.display().to_string())
+    } else {
+        Check::problem(
+            CheckStatus::Fail,
+            name,
+            format!("{} is not an executable", binary.display()),
+            fix,
+        )
+    }
+}
+
+/// The first executable `name` in the directories of `path_var`, like a shell would run.
+fn find_in_path(
+    name: &str,
+    path_var: &str,
+) -> Option<PathBuf> {
+    env::split_paths(path_var)
+        .map(|dir| dir.join(name))
+        .find(|candidate| is_executable(candidate))
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// The local ports the managed server listens on.
+fn ports(vendor: Vendor) -> Vec<(&'static str, u16)> {
+    let bolt_port = |var: &str| {
+        env::var(var)
+            .ok()
+            .and_then(|uri| uri.rsplit(':').next()?.parse().ok())
+            .unwrap_or(7687)
+    };
+    match vendor {
+        Vendor::Falkor => vec![("redis port", 6379)],
+        Vendor::Neo4j => vec![("bolt port", bolt_port("NEO4J_URI")), ("http port", 7474)],
+        Vendor::Memgraph => vec![("bolt port", bolt_port("MEMGRAPH_URI"))],
+    }
+}
+
+fn port_check((name, port): (&'static str, u16)) -> Check {
+    match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(_) => Check::ok(name, format!("{} is free", port)),
+        Err(e) if e.kind() == ErrorKind::AddrInUse => Check::problem(
+            CheckStatus::Warn,
+            name,
+            format!("{} is in use", port),
+            format!(
+                "stop what listens on it (`lsof -i :{}`), or pass --endpoint to benchmark it \
+                 as an external server",
+                port
+            ),
+        ),
+        Err(e) => Check::problem(
+            CheckStatus::Warn,
+            name,
+            format!("can't bind {}: {}", port, e),
+            "check the local firewall and permissions",
+        ),
+    }
+}
+
+/// A dump for `size`, or for any size when none is given; `run` restores from it instead of
+/// importing the dataset.
+fn dumps(
+    vendor: Vendor,
+    size: Option<Size>,
+) -> Check {
+    let sizes = size.map_or_else(|| Size::value_variants().to_vec(), |size| vec![size]);
+    let present: Vec<String> = sizes
+        .iter()
+        .filter(|size| {
+            dump_files(&Spec::new(Name::Users, **size, vendor))
+                .first()
+                .is_some_and(|(dump, _)| dump.exists())
+        })
+        .map(Size::to_string)
+        .collect();
+    if !present.is_empty() {
+        return Check::ok("dumps", present.join(", "));
+    }
+    let size = size.unwrap_or(Size::Small);
+    let cache_dir = format!("./cache/{}/{}/{}", vendor, Name::Users, size);
+    let dataset = if Path::new(&cache_dir).is_dir() {
+        format!("the dataset is cached in {}", cache_dir)
+    } else {
+        "load downloads the dataset first".to_string()
+    };
+    Check::problem(
+        CheckStatus::Warn,
+        "dumps",
+        format!("no {} dump; {}", size, dataset),
+        format!("benchmark load --vendor {} --size {}", vendor, size),
+    )
+}
+
+/// Free space on the filesystem of the working directory, where dumps, server data and results
+/// go.
+fn disk_space() -> Check {
+    let Ok(cwd) = env::current_dir().and_then(|dir| dir.canonicalize()) else {
+        return Check::problem(
+            CheckStatus::Warn,
+            "disk space",
+            "can't resolve the working directory",
+            "run doctor from the benchmark's directory",
+        );
+    };
+    let disks = Disks::new_with_refreshed_list();
+    match disks
+        .list()
+        .iter()
+        .filter(|disk| cwd.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+    {
+        Some(disk) => disk_space_check(disk.mount_point(), disk.available_space()),
+        None => Check::problem(
+            CheckStatus::Warn,
+            "disk space",
+            format!("no mounted disk found for {}", cwd.display()),
+            "check the free space by hand with `df -h .`",
+        ),
+    }
+}
+
+fn disk_space_check(
+    mount_point: &Path,
+    available: u64,
+) -> Check {
+    let detail = format!(
+        "{:.1} GiB free on {}",
+        available as f64 / GIB as f64,
+        mount_point.display()
+    );
+    let fix = "free up space, or run from a larger filesystem";
+    if available < GIB {
+        Check::problem(CheckStatus::Fail, "disk space", detail, fix)
+    } else if available < 10 * GIB {
+        Check::problem(
+            CheckStatus::Warn,
+            "disk space",
+            format!("{}; the large dataset and its dump need more", detail),
+            fix,
+        )
+    } else {
+        Check::ok("disk space", detail)
+    }
+}
+
+/// Neo4j asks for 40000 open files; redis-server wants room for its 10000 clients plus 32 of
+/// its own, and Memgraph about the same.
+fn min_open_files(vendor: Vendor) -> u64 {
+    match vendor {
+        Vendor::Neo4j => 40_000,
+        Vendor::Falkor | Vendor::Memgraph => 10_032,
+    }
+}
+
+#[cfg(unix)]
+fn open_files(vendor: Vendor) -> Option<Check> {
+    use nix::sys::resource::{getrlimit, Resource};
+    let (soft, hard) = getrlimit(Resource::RLIMIT_NOFILE).ok()?;
+    Some(open_files_check(vendor, soft, hard))
+}
+
+#[cfg(not(unix))]
+fn open_files(_vendor: Vendor) -> Option<Check> {
+    None
+}
+
+fn open_files_check(
+    vendor: Vendor,
+    soft: u64,
+    hard: u64,
+) -> Check {
+    let min = min_open_files(vendor);
+    let detail = format!("open files limit {} (hard {})", soft, hard);
+    if soft >= min {
+        Check::ok("ulimit -n", detail)
+    } else if hard >= min {
+        Check::problem(
+            CheckStatus::Warn,
+            "ulimit -n",
+            format!("{}, {} needs {}", detail, vendor, min),
+            format!("`ulimit -n {}` in the shell that runs the benchmark", min),
+        )
+    } else {
+        Check::problem(
+            CheckStatus::Warn,
+            "ulimit -n",
+            format!("{}, {} needs {}", detail, vendor, min),
+            format!(
+                "raise the nofile hard limit to {} in /etc/security/limits.conf, then `ulimit -n {}`",
+                min, min
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_grade_what_they_find_and_say_how_to_fix_it() {
+        let mount = Path::new("/data");
+        assert_eq!(disk_space_check(mount, GIB / 2).status, CheckStatus::Fail);
+        assert_eq!(disk_space_check(mount, 5 * GIB).status, CheckStatus::Warn);
+        let plenty = disk_space_check(mount, 50 * GIB);
+        assert_eq!(
+            plenty.to_string(),
+            "[ok  ] disk space: 50.0 GiB free on /data"
+        );
+
+        assert_eq!(
+            open_files_check(Vendor::Falkor, 65_536, 65_536).status,
+            CheckStatus::Ok
+        );
+        let low = open_files_check(Vendor::Neo4j, 1024, 1_048_576);
+        assert_eq!(low.status, CheckStatus::Warn);
+        assert_eq!(
+            low.fix.as_deref(),
+            Some("`ulimit -n 40000` in the shell that runs the benchmark")
+        );
+        assert!(open_files_check(Vendor::Neo4j, 1024, 4096)
+            .fix
+            .unwrap()
+            .contains("limits.conf"));
+
+        let dir = env::temp_dir().join(format!("benchmark-doctor-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path_var = env::join_paths([Path::new("/nonexistent"), &dir]).unwrap();
+        let path_var = path_var.to_str().unwrap();
+        assert_eq!(find_in_path("redis-server", path_var), None);
+        std::fs::write(dir.join("redis-server"), "").unwrap();
+        #[cfg(unix)]
+        {
+            // Present but not executable doesn't count.
+            assert_eq!(find_in_path("redis-server", path_var), None);
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(
+                dir.join("redis-server"),
+                std::fs::Permissions::from_mode(0o755),
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            find_in_path("redis-server", path_var),
+            Some(dir.join("redis-server"))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -30,6 +30,12 @@ pub enum BenchmarkError {
     TokioSendError(#[from] tokio::sync::mpsc::error::SendError<PreparedQuery>),
     #[error("Tokio elapsed error: {0}")]
     TokioElapsed(#[from] tokio::time::error::Elapsed),
+    #[error("Timeout after {}ms", .0.as_millis())]
+    QueryTimeout(std::time::Duration),
+    /// The server turned the query away instead of queueing it, e.g. FalkorDB at
+    /// MAX_QUEUED_QUERIES.
+    #[error("Query rejected: {0}")]
+    QueryRejected(String),
     #[error("Other error: {0}")]
     OtherError(String),
 }
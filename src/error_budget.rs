@@ -0,0 +1,172 @@
+//! `run --abort-on-error-rate 5%/30s`: stop scheduling once more than 5% of the queries completed
+//! in the last 30 full seconds failed. The queries already queued still run, the collected
+//! metrics are written as usual and meta.json records why the run was aborted.
+
+use crate::query_timeout::parse_duration;
+use crate::run_control::RunControl;
+use crate::throughput::{SecondSample, ThroughputTracker};
+use serde::Serialize;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorBudget {
+    /// Highest tolerated share of failed queries, between 0 and 1.
+    pub max_rate: f64,
+    /// Whole seconds the rate is measured over.
+    pub window_secs: u64,
+}
+
+impl FromStr for ErrorBudget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rate, window) = s
+            .split_once('/')
+            .ok_or_else(|| format!("expected <rate>%/<window>, e.g. 5%/30s, got '{}'", s))?;
+        let percent: f64 = rate
+            .trim()
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|e| format!("invalid error rate '{}': {}", rate, e))?;
+        if !(0.0..100.0).contains(&percent) {
+            return Err(format!(
+                "error rate must be at least 0% and below 100%, got '{}'",
+                rate
+            ));
+        }
+        let window = parse_duration(window)?;
+        if window < Duration::from_secs(1) {
+            return Err(format!("window must be at least 1s, got '{}'", s));
+        }
+        Ok(ErrorBudget {
+            max_rate: percent / 100.0,
+            window_secs: window.as_secs() + u64::from(window.subsec_nanos() > 0),
+        })
+    }
+}
+
+/// Why a run stopped early; recorded in meta.json as `aborted`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunAbort {
+    pub reason: String,
+    /// Seconds into the measured phase.
+    pub at_second: u64,
+    pub errors: u64,
+    pub completed: u64,
+}
+
+impl ErrorBudget {
+    /// The abort the per-second `samples` call for, `now` whole seconds into the measured phase.
+    /// Nothing is decided before a full window has elapsed.
+    pub fn check(
+        &self,
+        samples: &[SecondSample],
+        now: u64,
+    ) -> Option<RunAbort> {
+        if now < self.window_secs {
+            return None;
+        }
+        let (completed, errors) = samples
+            .iter()
+            .take(now as usize)
+            .skip((now - self.window_secs) as usize)
+            .fold((0, 0), |(completed, errors), sample| {
+                (completed + sample.completed, errors + sample.errors)
+            });
+        if completed == 0 {
+            return None;
+        }
+        let rate = errors as f64 / completed as f64;
+        (rate > self.max_rate).then(|| RunAbort {
+            reason: format!(
+                "error rate {:.1}% ({} of {} queries) over the last {}s exceeded {:.1}%",
+                rate * 100.0,
+                errors,
+                completed,
+                self.window_secs,
+                self.max_rate * 100.0
+            ),
+            at_second: now,
+            errors,
+            completed,
+        })
+    }
+}
+
+/// Checks `control`'s error budget against `throughput` every second.
+pub struct ErrorBudgetWatch {
+    handle: JoinHandle<()>,
+}
+
+impl ErrorBudgetWatch {
+    /// `None` when the run has no `--abort-on-error-rate`.
+    pub fn spawn(
+        control: &Arc<RunControl>,
+        throughput: &Arc<ThroughputTracker>,
+    ) -> Option<Self> {
+        let budget = control.error_budget()?;
+        let control = control.clone();
+        let throughput = throughput.clone();
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(1));
+            while !control.is_stopped() {
+                ticker.tick().await;
+                let now = throughput.elapsed().as_secs();
+                if let Some(abort) = budget.check(&throughput.samples(), now) {
+                    control.abort(abort);
+                }
+            }
+        });
+        Some(ErrorBudgetWatch { handle })
+    }
+
+    pub fn stop(self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aborts_once_a_full_window_fails_too_often() {
+        let budget: ErrorBudget = "5%/3s".parse().unwrap();
+        assert_eq!(
+            budget,
+            ErrorBudget {
+                max_rate: 0.05,
+                window_secs: 3
+            }
+        );
+        assert!("5%".parse::<ErrorBudget>().is_err());
+        assert!("100%/30s".parse::<ErrorBudget>().is_err());
+        assert!("5%/500ms".parse::<ErrorBudget>().is_err());
+
+        let sample = |completed, errors| SecondSample { completed, errors };
+        let samples = [
+            sample(100, 50),
+            sample(100, 0),
+            sample(100, 0),
+            sample(100, 20),
+            sample(100, 0),
+        ];
+        // Too early to judge, however bad the first second was.
+        assert_eq!(budget.check(&samples, 2), None);
+        // Seconds 1-3: 20 of 300 failed, 6.7%. The in-progress second 4 is left out.
+        let abort = budget.check(&samples, 4).unwrap();
+        assert_eq!(
+            (abort.errors, abort.completed, abort.at_second),
+            (20, 300, 4)
+        );
+        assert_eq!(
+            abort.reason,
+            "error rate 6.7% (20 of 300 queries) over the last 3s exceeded 5.0%"
+        );
+        let lenient: ErrorBudget = "20%/3s".parse().unwrap();
+        assert_eq!(lenient.check(&samples, 3), None);
+    }
+}
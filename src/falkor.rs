@@ -1,5 +1,7 @@
 mod falkor_driver;
+pub mod falkor_log;
 pub mod falkor_process;
+pub mod queue_saturation;
 pub mod telemetry_collector;
 
 // Re-export the falkor module as if it in crate::falkor
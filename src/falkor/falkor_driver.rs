@@ -1,26 +1,46 @@
+use crate::backup_manifest::{check_before_restore, BackupManifest};
+use crate::cache_control::WARMUP_QUERIES;
+use crate::compact_query::CompactQuery;
 use crate::data_prep::bench_capacity;
-use crate::error::BenchmarkError::OtherError;
+use crate::database_config::DEFAULT_FALKOR_GRAPH;
+use crate::error::BenchmarkError::{self, OtherError, QueryRejected, QueryTimeout};
 use crate::error::BenchmarkResult;
-use crate::falkor::falkor_process::FalkorProcess;
-use crate::queries_repository::{PreparedQuery, QueryType};
-use crate::scenario::Size;
+use crate::falkor::falkor_process::{
+    collect_query_info_metrics, collect_redis_info_metrics, FalkorModuleConfig, FalkorProcess,
+};
+use crate::index_parity::IndexDescriptor;
+use crate::index_wait::{self, IndexPopulation, IndexState, IndexWaitReport, INDEX_ONLINE_TIMEOUT};
+use crate::process_monitor::ServerRestarts;
+use crate::prometheus_metrics::EndpointMetricsPoller;
+use crate::queries_repository::{PreparedQuery, QueryType, VectorWorkload};
+use crate::query_plans::PlanMode;
+use crate::query_timeout::QueryTimeouts;
+use crate::resource_limits::ServerLimits;
+use crate::result_size;
+use crate::sanitizer::Sanitizer;
+use crate::scenario::{Name, Size, Spec, Vendor};
 use crate::scheduler::Msg;
+use crate::simulate::SimulatedLatency;
+use crate::synthetic::provenance::{self, decode_module_version};
 use crate::utils::{
-    delete_file, falkor_shared_lib_path, file_exists, get_command_pid, redis_save, redis_shutdown,
-    wait_for_redis_ready,
+    delete_file, falkor_module_path, file_exists, get_command_pid, kill_process,
+    redact_endpoint, redis_save, redis_shutdown, wait_for_redis_ready,
 };
 use crate::{
-    FALKOR_GRAPH_MEMORY_USAGE_MB, FALKOR_MSG_DEADLINE_OFFSET_GAUGE, OPERATION_COUNTER,
-    OPERATION_ERROR_COUNTER, REDIS_DATA_DIR,
+    FALKOR_GRAPH_MEMORY_USAGE_MB, FALKOR_MSG_DEADLINE_OFFSET_GAUGE, FALKOR_REJECTED_QUERIES_TOTAL,
+    FALKOR_REPORTED_MEMORY_BYTES, OPERATION_COUNTER, OPERATION_ERROR_COUNTER, REDIS_DATA_DIR,
 };
 use falkordb::{
-    AsyncGraph, ConnectionStrategy, FalkorClientBuilder, FalkorResult, QueryResult, RowStream,
+    AsyncGraph, ConnectionStrategy, FalkorClientBuilder, FalkorResult, FalkorValue, QueryResult,
+    RowStream,
 };
+use redis::aio::MultiplexedConnection;
 use std::env;
 use std::hint::black_box;
 use std::io;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 use futures::StreamExt;
 use tokio::fs;
@@ -55,6 +75,102 @@ fn resolve_falkor_benchmark_query_timeout_guard(timeout_ms: i64) -> Duration {
     Duration::from_millis(timeout_ms.saturating_add(FALKOR_BENCHMARK_QUERY_TIMEOUT_GUARD_EXTRA_MS))
 }
 
+/// The FalkorDB query `timeout` (ms) for `timeout` and the Tokio guard slightly above it.
+fn falkor_query_timeout(timeout: Duration) -> (i64, Duration) {
+    let timeout_ms = timeout.as_millis().min(i64::MAX as u128) as i64;
+    (
+        timeout_ms,
+        resolve_falkor_benchmark_query_timeout_guard(timeout_ms),
+    )
+}
+
+/// A transaction's `MULTI`/`EXEC` pipeline: one `GRAPH.QUERY <graph> <cypher> --compact timeout
+/// <ms>` per statement, the arguments the falkordb client sends for a query with a timeout.
+fn transaction_pipeline<'a>(
+    graph_name: &str,
+    cyphers: impl IntoIterator<Item = &'a str>,
+    timeout_ms: i64,
+) -> redis::Pipeline {
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    for cypher in cyphers {
+        pipe.cmd("GRAPH.QUERY")
+            .arg(graph_name)
+            .arg(cypher)
+            .arg("--compact")
+            .arg("timeout")
+            .arg(timeout_ms);
+    }
+    pipe
+}
+
+/// FalkorDB's `Query internal execution time: <ms> milliseconds` from the statistics of one raw
+/// `GRAPH.QUERY` reply, whose last element is the statistics array, in the compact reply format
+/// as in the verbose one.
+fn raw_reply_execution_time(reply: &redis::Value) -> Option<Duration> {
+    let redis::Value::Array(parts) = reply else {
+        return None;
+    };
+    let redis::Value::Array(stats) = parts.last()? else {
+        return None;
+    };
+    stats.iter().find_map(|stat| {
+        let stat = match stat {
+            redis::Value::BulkString(bytes) => String::from_utf8_lossy(bytes).to_string(),
+            redis::Value::SimpleString(s) => s.clone(),
+            redis::Value::VerbatimString { text, .. } => text.clone(),
+            _ => return None,
+        };
+        if !stat.contains("internal execution time") {
+            return None;
+        }
+        execution_time_from_ms(stat.split(": ").nth(1)?.split(' ').next()?.parse().ok()?)
+    })
+}
+
+fn execution_time_from_ms(ms: f64) -> Option<Duration> {
+    (ms.is_finite() && ms >= 0.0).then(|| Duration::from_secs_f64(ms / 1000.0))
+}
+
+/// FalkorDB reports its own query timeout expiring as a "Query timed out" error.
+fn is_falkor_timeout_message(message: &str) -> bool {
+    message.contains("timed out")
+}
+
+/// FalkorDB's reply to a query that arrives with MAX_QUEUED_QUERIES already waiting.
+fn is_falkor_rejection_message(message: &str) -> bool {
+    message.contains("Max pending queries exceeded")
+}
+
+/// A rejection, counted in `falkordb_rejected_queries_total`. Not logged: under saturation
+/// there is one per query over the limit.
+fn rejected(message: String) -> BenchmarkError {
+    FALKOR_REJECTED_QUERIES_TOTAL.inc();
+    QueryRejected(message)
+}
+
+/// Count a failed query in `operations_total` under its failure type, as the Bolt drivers do.
+fn count_failure(
+    spawn_id: &str,
+    query_name: &str,
+    e: &BenchmarkError,
+) {
+    let failure = match e {
+        QueryTimeout(_) => "timeout",
+        _ => "error",
+    };
+    OPERATION_COUNTER
+        .with_label_values(&[
+            Vendor::Falkor.metric_label(),
+            spawn_id,
+            failure,
+            query_name,
+            "",
+            "",
+        ])
+        .inc();
+}
+
 #[allow(dead_code)]
 pub struct Started(FalkorProcess);
 pub struct Stopped;
@@ -62,6 +178,11 @@ pub struct Stopped;
 pub struct Falkor<U> {
     path: String,
     endpoint: Option<String>,
+    /// The key of the benchmark graph, `--graph-name`.
+    graph_name: String,
+    server_limits: ServerLimits,
+    module: FalkorModuleConfig,
+    sanitizer: Option<Sanitizer>,
     #[allow(dead_code)]
     state: U,
 }
@@ -97,19 +218,59 @@ impl Falkor<Stopped> {
     }
 
     fn with_endpoint(endpoint: Option<String>) -> Falkor<Stopped> {
-        let default = falkor_shared_lib_path().unwrap();
-        let path = env::var("FALKOR_PATH").unwrap_or(default);
+        let path = falkor_module_path().unwrap();
         if let Some(ref ep) = endpoint {
-            info!("using external falkor endpoint: {}", ep);
+            info!("using external falkor endpoint: {}", redact_endpoint(ep));
         } else {
             info!("falkor shared lib path: {}", path);
         }
         Falkor {
             path,
             endpoint,
+            graph_name: DEFAULT_FALKOR_GRAPH.to_string(),
+            server_limits: ServerLimits::default(),
+            module: FalkorModuleConfig::default(),
+            sanitizer: None,
             state: Stopped,
         }
     }
+
+    /// Launch the locally managed redis-server under `server_limits` (`--server-mem-limit`,
+    /// `--server-cpus`).
+    pub fn with_server_limits(
+        mut self,
+        server_limits: ServerLimits,
+    ) -> Self {
+        self.server_limits = server_limits;
+        self
+    }
+
+    /// Load the FalkorDB module into the locally managed redis-server with `module`'s options.
+    pub fn with_module_config(
+        mut self,
+        module: FalkorModuleConfig,
+    ) -> Self {
+        self.module = module;
+        self
+    }
+
+    /// Use the graph under `graph_name` instead of `falkor` (`--graph-name`).
+    pub fn with_graph_name(
+        mut self,
+        graph_name: String,
+    ) -> Self {
+        self.graph_name = graph_name;
+        self
+    }
+
+    /// Run the sanitizer build of redis-server (`--sanitizer`).
+    pub fn with_sanitizer(
+        mut self,
+        sanitizer: Option<Sanitizer>,
+    ) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
     pub async fn start(self) -> BenchmarkResult<Falkor<Started>> {
         if self.endpoint.is_some() {
             // For external endpoints, we don't manage a process
@@ -118,14 +279,23 @@ impl Falkor<Stopped> {
             Ok(Falkor {
                 path: self.path.clone(),
                 endpoint: self.endpoint.clone(),
+                graph_name: self.graph_name.clone(),
+                server_limits: self.server_limits.clone(),
+                module: self.module.clone(),
+                sanitizer: self.sanitizer,
                 state: Started(FalkorProcess::external()),
             })
         } else {
-            let falkor_process: FalkorProcess = FalkorProcess::new().await?;
+            let falkor_process: FalkorProcess =
+                FalkorProcess::new(&self.server_limits, &self.module, self.sanitizer).await?;
             Self::wait_for_ready().await?;
             Ok(Falkor {
                 path: self.path.clone(),
                 endpoint: self.endpoint.clone(),
+                graph_name: self.graph_name.clone(),
+                server_limits: self.server_limits.clone(),
+                module: self.module.clone(),
+                sanitizer: self.sanitizer,
                 state: Started(falkor_process),
             })
         }
@@ -139,6 +309,7 @@ impl Falkor<Stopped> {
     pub async fn save_db(
         &self,
         size: Size,
+        manifest: &BackupManifest,
     ) -> BenchmarkResult<()> {
         if self.get_redis_pid().await.is_ok() {
             redis_shutdown().await?;
@@ -155,6 +326,7 @@ impl Falkor<Stopped> {
             target.as_str()
         );
         fs::copy(REDIS_DUMP_FILE, target.as_str()).await?;
+        manifest.write(&backup_manifest_path(size)).await?;
         Ok(())
     }
 }
@@ -167,44 +339,73 @@ impl Falkor<Started> {
         Ok(Falkor {
             path: self.path.clone(),
             endpoint: self.endpoint.clone(),
+            graph_name: self.graph_name.clone(),
+            server_limits: self.server_limits.clone(),
+            module: self.module.clone(),
+            sanitizer: self.sanitizer,
             state: Stopped,
         })
     }
 
+    /// Restarts of the locally managed server within `[from, to]`; `None` for external endpoints.
+    pub fn server_restarts(
+        &self,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Option<ServerRestarts> {
+        self.state
+            .0
+            .restart_log()
+            .map(|restart_log| restart_log.between(from, to))
+    }
+
+    /// SIGKILL the locally managed redis-server; the process monitor brings it back up.
+    /// Used by `--chaos kill-server`.
+    pub async fn kill_server() -> BenchmarkResult<()> {
+        kill_process(get_command_pid("redis-server").await?).await
+    }
+
     /// Best-effort collection of graph memory usage via `GRAPH.MEMORY USAGE <graph>`.
     ///
     /// Sets the Prometheus gauge `falkordb_graph_memory_usage_mb` if the command succeeds.
     pub async fn collect_graph_memory_usage_metrics(&self) {
-        // Avoid stale values when multiple runs happen in a single process.
-        FALKOR_GRAPH_MEMORY_USAGE_MB.set(0);
+        let redis_url = falkor_endpoint_to_redis_url(self.endpoint.as_ref());
+        collect_graph_memory_usage_metrics(&redis_url, &self.graph_name).await;
+    }
 
-        let graph_name = "falkor";
-        match self.graph_memory_usage_mb(graph_name).await {
-            Ok(Some(mb)) => {
-                FALKOR_GRAPH_MEMORY_USAGE_MB.set(mb.round().max(0.0) as i64);
-            }
-            Ok(None) => {
-                // Keep the reset-to-0 value.
-            }
+    /// `GRAPH.MEMORY USAGE` of the benchmark graph in bytes; `None` when the command fails.
+    pub async fn graph_memory_usage_bytes(&self) -> Option<u64> {
+        let redis_url = falkor_endpoint_to_redis_url(self.endpoint.as_ref());
+        match graph_memory_usage_mb(&redis_url, &self.graph_name).await {
+            Ok(mb) => mb.map(|mb| (mb * 1024.0 * 1024.0).round().max(0.0) as u64),
             Err(e) => {
-                tracing::debug!("Failed collecting falkor graph memory: {}", e);
+                tracing::debug!("Failed reading falkor graph memory: {}", e);
+                None
             }
         }
     }
 
-    async fn graph_memory_usage_mb(
-        &self,
-        graph_name: &str,
-    ) -> BenchmarkResult<Option<f64>> {
+    /// The graph module version from `MODULE LIST`, e.g. `4.20.1`; `None` when it can't be read.
+    pub async fn vendor_version(&self) -> Option<String> {
         let redis_url = falkor_endpoint_to_redis_url(self.endpoint.as_ref());
-        let client = redis::Client::open(redis_url.as_str())?;
-        let mut con = client.get_multiplexed_async_connection().await?;
-
-        let mut command = redis::cmd("GRAPH.MEMORY");
-        command.arg("USAGE").arg(graph_name);
-        let redis_value = con.send_packed_command(&command).await?;
+        let info = provenance::collect(&redis_url, None).await.ok()?;
+        info.module_graph_ver.map(decode_module_version)
+    }
 
-        Ok(parse_graph_memory_total_mb(redis_value))
+    /// For external endpoints: keep `GRAPH.MEMORY`, `INFO` and `GRAPH.INFO` gauges current for the
+    /// rest of the run. Local runs get these from the process monitor instead.
+    pub fn spawn_endpoint_metrics_poller(&self) -> EndpointMetricsPoller {
+        let redis_url = falkor_endpoint_to_redis_url(self.endpoint.as_ref());
+        let graph_name = self.graph_name.clone();
+        EndpointMetricsPoller::spawn(move || {
+            let redis_url = redis_url.clone();
+            let graph_name = graph_name.clone();
+            async move {
+                collect_graph_memory_usage_metrics(&redis_url, &graph_name).await;
+                collect_redis_info_metrics(&redis_url).await?;
+                collect_query_info_metrics(&redis_url).await
+            }
+        })
     }
 
     pub async fn graph_size(&self) -> BenchmarkResult<(u64, u64)> {
@@ -303,6 +504,26 @@ impl Falkor<Started> {
         ))
     }
 
+    /// Wait until every index is populated, after [`Self::wait_for_pokec_indexes_ready`] saw
+    /// them listed.
+    pub async fn wait_for_indexes_online(&self) -> BenchmarkResult<IndexWaitReport> {
+        let mut client = self.client().await?;
+        index_wait::wait_online(Vendor::Falkor, INDEX_ONLINE_TIMEOUT, async || {
+            client.index_states().await
+        })
+        .await
+    }
+
+    pub async fn list_indexes(&self) -> BenchmarkResult<Vec<IndexDescriptor>> {
+        let mut client = self.client().await?;
+        client.list_indexes().await
+    }
+
+    pub async fn warm_up(&self) -> BenchmarkResult<()> {
+        let mut client = self.client().await?;
+        client.warm_up().await
+    }
+
     pub async fn ensure_friend_capacity_ready(&self) -> BenchmarkResult<()> {
         let mut client = self.client().await?;
         client.ensure_friend_capacity_ready().await
@@ -369,10 +590,16 @@ impl<U> Falkor<U> {
             .await?;
         info!("Initialized Falkor async client with pooled strategy (size=8)");
         let query_timeout_ms = resolve_falkor_benchmark_query_timeout_ms();
+        let redis_client =
+            redis::Client::open(falkor_endpoint_to_redis_url(self.endpoint.as_ref()).as_str())?;
         Ok(FalkorBenchmarkClient {
-            graph: client.select_graph("falkor"),
-            query_timeout_ms,
-            query_timeout_guard: resolve_falkor_benchmark_query_timeout_guard(query_timeout_ms),
+            graph: client.select_graph(&self.graph_name),
+            connection_string: connection_string.to_string(),
+            query_timeouts: Arc::new(QueryTimeouts::uniform(Duration::from_millis(
+                query_timeout_ms as u64,
+            ))),
+            redis_client,
+            transaction_connection: None,
         })
     }
 
@@ -381,26 +608,44 @@ impl<U> Falkor<U> {
     }
 
     pub async fn get_redis_pid(&self) -> BenchmarkResult<u32> {
-        get_command_pid("redis-server").await
+        // Only the exact executable name matches, so a sanitizer build is looked up by its own.
+        let redis_server = self
+            .sanitizer
+            .map_or_else(|| "redis-server".to_string(), Sanitizer::redis_server);
+        let executable = std::path::Path::new(&redis_server)
+            .file_name()
+            .map_or(redis_server.clone(), |name| {
+                name.to_string_lossy().to_string()
+            });
+        get_command_pid(executable).await
     }
 
+    /// Restore the dump of `size` after checking its manifest; returns the manifest, `None` for
+    /// dumps saved without one.
     pub async fn restore_db(
         &self,
         size: Size,
-    ) -> BenchmarkResult<()> {
+    ) -> BenchmarkResult<Option<BackupManifest>> {
         let source = format!(
             "{}/{}_dump.rdb",
             REDIS_DATA_DIR,
             size.to_string().to_lowercase()
         );
+        let exists = file_exists(source.as_str()).await;
+        let manifest = if exists {
+            let spec = Spec::new(Name::Users, size, Vendor::Falkor);
+            check_before_restore(&backup_manifest_path(size), &spec).await?
+        } else {
+            None
+        };
         if self.get_redis_pid().await.is_ok() {
             redis_shutdown().await?;
         }
         info!("copy {} to {}", source, REDIS_DUMP_FILE);
-        if file_exists(source.as_str()).await {
+        if exists {
             fs::copy(source.as_str(), REDIS_DUMP_FILE).await?;
         }
-        Ok(())
+        Ok(manifest)
     }
 
     pub async fn dump_exists_or_error(
@@ -423,6 +668,14 @@ impl<U> Falkor<U> {
     }
 }
 
+fn backup_manifest_path(size: Size) -> PathBuf {
+    PathBuf::from(format!(
+        "{}/{}_manifest.json",
+        REDIS_DATA_DIR,
+        size.to_string().to_lowercase()
+    ))
+}
+
 pub fn falkor_endpoint_to_redis_url(endpoint: Option<&String>) -> String {
     let ep = endpoint
         .map(|s| s.as_str())
@@ -435,6 +688,57 @@ pub fn falkor_endpoint_to_redis_url(endpoint: Option<&String>) -> String {
     }
 }
 
+/// Whether `db.indexes()` lists a RANGE index for `property` in the row's `types` map.
+fn falkor_index_has_range(
+    types: &FalkorValue,
+    property: &str,
+) -> bool {
+    let FalkorValue::Map(types) = types else {
+        return false;
+    };
+    matches!(
+        types.get(property),
+        Some(FalkorValue::Array(kinds))
+            if kinds.iter().any(|kind| matches!(kind, FalkorValue::String(k) if k == "RANGE"))
+    )
+}
+
+async fn collect_graph_memory_usage_metrics(
+    redis_url: &str,
+    graph_name: &str,
+) {
+    // Avoid stale values when multiple runs happen in a single process.
+    FALKOR_GRAPH_MEMORY_USAGE_MB.set(0);
+    FALKOR_REPORTED_MEMORY_BYTES.set(0);
+
+    match graph_memory_usage_mb(redis_url, graph_name).await {
+        Ok(Some(mb)) => {
+            FALKOR_GRAPH_MEMORY_USAGE_MB.set(mb.round().max(0.0) as i64);
+            FALKOR_REPORTED_MEMORY_BYTES.set((mb * 1024.0 * 1024.0).round().max(0.0) as i64);
+        }
+        Ok(None) => {
+            // Keep the reset-to-0 value.
+        }
+        Err(e) => {
+            tracing::debug!("Failed collecting falkor graph memory: {}", e);
+        }
+    }
+}
+
+async fn graph_memory_usage_mb(
+    redis_url: &str,
+    graph_name: &str,
+) -> BenchmarkResult<Option<f64>> {
+    let client = redis::Client::open(redis_url)?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+
+    let mut command = redis::cmd("GRAPH.MEMORY");
+    command.arg("USAGE").arg(graph_name);
+    let redis_value = con.send_packed_command(&command).await?;
+
+    Ok(parse_graph_memory_total_mb(redis_value))
+}
+
 fn parse_graph_memory_total_mb(value: redis::Value) -> Option<f64> {
     // Expected to be an array of key/value pairs.
     let redis::Value::Array(items) = value else {
@@ -480,11 +784,49 @@ fn redis_value_to_f64(v: &redis::Value) -> Option<f64> {
 #[derive(Clone)]
 pub struct FalkorBenchmarkClient {
     graph: AsyncGraph,
-    query_timeout_ms: i64,
-    query_timeout_guard: Duration,
+    // For `reconnect`.
+    connection_string: String,
+    query_timeouts: Arc<QueryTimeouts>,
+    // Raw redis access for MULTI/EXEC transactions, which the falkordb client doesn't expose.
+    redis_client: redis::Client,
+    transaction_connection: Option<MultiplexedConnection>,
 }
 
 impl FalkorBenchmarkClient {
+    /// The per-query timeout from `FALKOR_QUERY_TIMEOUT_MS`, used unless `run --query-timeout`
+    /// replaces it.
+    pub fn default_query_timeout(&self) -> Duration {
+        self.query_timeouts.default_timeout()
+    }
+
+    pub fn set_query_timeouts(
+        &mut self,
+        query_timeouts: QueryTimeouts,
+    ) {
+        self.query_timeouts = Arc::new(query_timeouts);
+    }
+
+    /// Replace this client's connection pool with a fresh single connection, completing a round
+    /// trip on it, for `run --reconnect-every`. The transaction connection is reopened on the
+    /// next transaction. Clones keep the old pool.
+    pub async fn reconnect(&mut self) -> BenchmarkResult<()> {
+        let client = FalkorClientBuilder::new_async()
+            .with_connection_info(self.connection_string.as_str().try_into()?)
+            .with_connection_strategy(ConnectionStrategy::Pooled {
+                size: nonzero::nonzero!(1u8),
+            })
+            .build()
+            .await?;
+        let mut graph = client.select_graph(self.graph.graph_name());
+        let mut result = graph.query("RETURN 1").execute().await?;
+        while let Some(row) = result.data.next().await {
+            let _ = black_box(row);
+        }
+        self.graph = graph;
+        self.transaction_connection = None;
+        Ok(())
+    }
+
     async fn run_query_no_results(
         &mut self,
         q: &str,
@@ -517,6 +859,98 @@ impl FalkorBenchmarkClient {
         }
     }
 
+    /// Run the `--warm` scans before the measured phase, draining every row.
+    pub async fn warm_up(&mut self) -> BenchmarkResult<()> {
+        for q in WARMUP_QUERIES {
+            let mut result = self.graph.query(q).execute().await?;
+            while let Some(row) = result.data.next().await {
+                row?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Range-indexed properties present on the graph, for the pre-run parity check. FalkorDB
+    /// reports one row per label with per-property index types; fulltext and vector fields
+    /// are skipped.
+    /// Every index with its `status`, `OPERATIONAL` once populated; FalkorDB populates an index
+    /// created on a populated graph in the background.
+    pub async fn index_states(&mut self) -> BenchmarkResult<Vec<IndexState>> {
+        let mut result = self
+            .graph
+            .query("CALL db.indexes() YIELD label, properties, status RETURN label, properties, status")
+            .with_timeout(30_000)
+            .execute()
+            .await?;
+        let mut states = Vec::new();
+        while let Some(row) = result.data.next().await {
+            let row = row?;
+            let label: String = row.try_get_at(0)?;
+            let properties: Vec<String> = row.try_get_at(1)?;
+            let status: String = row.try_get_at(2)?;
+            states.push(IndexState {
+                name: format!(":{}({})", label, properties.join(",")),
+                state: match status.as_str() {
+                    "OPERATIONAL" => IndexPopulation::Online,
+                    _ => IndexPopulation::Populating(None),
+                },
+            });
+        }
+        Ok(states)
+    }
+
+    pub async fn list_indexes(&mut self) -> BenchmarkResult<Vec<IndexDescriptor>> {
+        let mut result = self
+            .graph
+            .query(
+                "CALL db.indexes() YIELD label, properties, types RETURN label, properties, types",
+            )
+            .with_timeout(30_000)
+            .execute()
+            .await?;
+        let mut indexes = Vec::new();
+        while let Some(row) = result.data.next().await {
+            let row = row?;
+            let label: String = row.try_get_at(0)?;
+            let properties: Vec<String> = row.try_get_at(1)?;
+            let types: FalkorValue = row.try_get_at(2)?;
+            for property in properties {
+                if falkor_index_has_range(&types, &property) {
+                    indexes.push(IndexDescriptor::new(label.clone(), property));
+                }
+            }
+        }
+        Ok(indexes)
+    }
+
+    /// Rows of a query returning `key` and `value` columns, for `inspect`.
+    pub async fn key_counts(
+        &mut self,
+        q: &str,
+    ) -> BenchmarkResult<Vec<(String, i64)>> {
+        let mut result = self.graph.query(q).execute().await?;
+        let mut rows = Vec::new();
+        while let Some(row) = result.data.next().await {
+            let row = row?;
+            rows.push((row.try_get_at(0)?, row.try_get_at(1)?));
+        }
+        Ok(rows)
+    }
+
+    /// `GRAPH.EXPLAIN` or `GRAPH.PROFILE` of `query`, for `run --capture-plans`.
+    pub async fn query_plan(
+        &mut self,
+        query: &PreparedQuery,
+        mode: PlanMode,
+    ) -> BenchmarkResult<Vec<String>> {
+        let cypher = query.cypher.as_str();
+        let plan = match mode {
+            PlanMode::Explain => self.graph.explain(cypher).execute().await?,
+            PlanMode::Profile => self.graph.profile(cypher).execute().await?,
+        };
+        Ok(plan.plan().to_vec())
+    }
+
     pub async fn detect_algorithm_capabilities(
         &mut self
     ) -> BenchmarkResult<FalkorAlgorithmCapabilities> {
@@ -648,6 +1082,85 @@ RETURN
         Ok(())
     }
 
+    /// Seed the embeddings of `vector` on every `User` still missing them, create its vector
+    /// index, and wait until a KNN probe finds neighbours. Embeddings already seeded by an
+    /// earlier run are kept.
+    pub async fn ensure_vector_workload_ready(
+        &mut self,
+        vector: VectorWorkload,
+    ) -> BenchmarkResult<()> {
+        const SEED_BATCH: usize = 10_000;
+        const MAX_ATTEMPTS: u32 = 18;
+        const DELAY_SECS: u64 = 5;
+
+        let property = vector.property();
+        let seed_start = Instant::now();
+        let mut seeded = 0;
+        loop {
+            let batch = self
+                .query_single_i64(&format!(
+                    "MATCH (u:User) WHERE u.{property} IS NULL \
+                     WITH u LIMIT {SEED_BATCH} \
+                     SET u.{property} = vecf32({}) \
+                     RETURN count(u)",
+                    vector.embedding_expression("u")
+                ))
+                .await?;
+            if batch == 0 {
+                break;
+            }
+            seeded += batch;
+        }
+        info!(
+            "Falkor seeded {} embeddings of {} dimensions in {:?}",
+            seeded,
+            vector.dimensions,
+            seed_start.elapsed()
+        );
+
+        self.create_index_if_not_exists(
+            "main",
+            "create_vector_index_user_knn_embedding",
+            &format!(
+                "CREATE VECTOR INDEX FOR (u:User) ON (u.{}) OPTIONS {{ dimension: {}, similarityFunction: 'cosine' }}",
+                property, vector.dimensions
+            ),
+        )
+        .await?;
+
+        let probe = format!(
+            "CALL db.idx.vector.queryNodes('User', '{}', 1, vecf32({})) \
+             YIELD node \
+             RETURN count(node)",
+            property,
+            vector.probe_embedding()
+        );
+        for attempt in 1..=MAX_ATTEMPTS {
+            match self.query_single_i64(&probe).await {
+                Ok(hits) if hits > 0 => {
+                    info!(
+                        "Falkor vector index on :User({}) ready after {} attempt(s)",
+                        property, attempt
+                    );
+                    return Ok(());
+                }
+                Ok(_) => info!(
+                    "Falkor vector index on :User({}) not ready yet (attempt {}/{})",
+                    property, attempt, MAX_ATTEMPTS
+                ),
+                Err(e) => info!(
+                    "Error while probing Falkor vector index readiness (attempt {}/{}): {}",
+                    attempt, MAX_ATTEMPTS, e
+                ),
+            }
+            tokio::time::sleep(Duration::from_secs(DELAY_SECS)).await;
+        }
+        Err(OtherError(format!(
+            "Falkor vector index on :User({}) was not ready after {} attempts",
+            property, MAX_ATTEMPTS
+        )))
+    }
+
     pub async fn ensure_post_phase1_fixtures_ready(&mut self) -> BenchmarkResult<()> {
         // Required fixture indexes (idempotent creation).
         self.create_index_if_not_exists(
@@ -950,41 +1463,17 @@ RETURN
 
             match phase {
                 Phase::Nodes => {
-                    if let (Some(l), Some(r)) = (trimmed.find('{'), trimmed.rfind('}')) {
-                        if r > l {
-                            node_maps.push(trimmed[l..=r].to_string());
-                            total_processed += 1;
-                        }
+                    if let Some(map) = users_node_map(trimmed) {
+                        node_maps.push(map.to_string());
+                        total_processed += 1;
                     }
                     if node_maps.len() >= batch_size {
                         flush_nodes(self, &mut node_maps, &mut batch_count).await?;
                     }
                 }
                 Phase::Edges => {
-                    let mut ids: [u64; 2] = [0, 0];
-                    let mut found = 0usize;
-                    let mut rest = trimmed;
-                    while found < 2 {
-                        let Some(pos) = rest.find("id:") else { break };
-                        rest = &rest[pos + 3..];
-                        let s = rest.trim_start();
-                        let mut end = 0usize;
-                        for (i, ch) in s.char_indices() {
-                            if !ch.is_ascii_digit() {
-                                end = i;
-                                break;
-                            }
-                        }
-                        let end = if end == 0 { s.len() } else { end };
-                        if let Ok(v) = s[..end].parse::<u64>() {
-                            ids[found] = v;
-                            found += 1;
-                        }
-                        rest = &s[end..];
-                    }
-
-                    if found == 2 {
-                        edge_pairs.push((ids[0], ids[1]));
+                    if let Some(pair) = users_edge_ids(trimmed) {
+                        edge_pairs.push(pair);
                         total_processed += 1;
                     }
 
@@ -993,6 +1482,7 @@ RETURN
                     }
                 }
             }
+            crate::run_status::loaded(total_processed as u64);
 
             // Report progress every 5 seconds
             let now = tokio::time::Instant::now();
@@ -1047,44 +1537,47 @@ RETURN
         }
     }
 
+    /// Run one measured query. Returns the server-side execution time FalkorDB reports with the
+    /// reply (summed over the statements of a transaction), if any.
     pub async fn execute_prepared_query<S: AsRef<str>>(
         &mut self,
         worker_id: S,
-        msg: &Msg<PreparedQuery>,
-        simulate: &Option<usize>,
-    ) -> BenchmarkResult<()> {
-        let Msg {
-            payload:
-                PreparedQuery {
-                    q_name,
-                    cypher,
-                    q_type,
-                    ..
-                },
+        msg: &Msg<CompactQuery>,
+        simulate: &Option<SimulatedLatency>,
+    ) -> BenchmarkResult<Option<Duration>> {
+        if msg.payload.is_transaction() {
+            return self
+                .execute_prepared_transaction(worker_id.as_ref(), msg, simulate)
+                .await;
+        }
+
+        let CompactQuery {
+            q_id,
+            q_name,
+            q_type,
             ..
-        } = msg;
+        } = &msg.payload;
 
         let worker_id = worker_id.as_ref();
-        let query = cypher.as_str();
+        let q_name = &**q_name;
+        let cypher = msg.payload.statement().cypher()?;
+        let query: &str = &cypher;
 
-        // Use longer FalkorDB per-query timeouts for large datasets.
-        // This mirrors the extended timeouts used in other Falkor paths
-        // (e.g. index creation, batch execution, graph_size).
+        // FalkorDB enforces the query's timeout server-side; the Tokio guard sits slightly above it.
+        let (timeout_ms, timeout) = falkor_query_timeout(self.query_timeouts.for_query(*q_id));
         let falkor_result = match q_type {
             QueryType::Read => self
                 .graph
                 .ro_query(query)
-                .with_timeout(self.query_timeout_ms)
+                .with_timeout(timeout_ms)
                 .execute(),
             QueryType::Write => self
                 .graph
                 .query(query)
-                .with_timeout(self.query_timeout_ms)
+                .with_timeout(timeout_ms)
                 .execute(),
         };
 
-        // Tokio-level guard: slightly above the FalkorDB per-query timeout.
-        let timeout = self.query_timeout_guard;
         let offset = msg.compute_offset_ms();
 
         FALKOR_MSG_DEADLINE_OFFSET_GAUGE.set(offset);
@@ -1093,19 +1586,143 @@ RETURN
             tokio::time::sleep(Duration::from_millis(offset as u64)).await;
         }
 
-        if let Some(delay) = simulate {
-            if *delay > 0 {
-                let delay: u64 = *delay as u64;
-                tokio::time::sleep(Duration::from_millis(delay)).await;
+        if let Some(latency) = simulate {
+            let delay = latency.sample(&mut rand::rng());
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
             }
-            return Ok(());
+            return Ok(None);
         }
 
         let falkor_result = tokio::time::timeout(timeout, falkor_result).await;
         OPERATION_COUNTER
-            .with_label_values(&["falkor", worker_id, "", q_name, "", ""])
+            .with_label_values(&[Vendor::Falkor.metric_label(), worker_id, "", q_name, "", ""])
             .inc();
-        Self::read_reply(worker_id, q_name, query, falkor_result).await
+        let (execution_time, rows) =
+            Self::read_reply(worker_id, q_name, query, timeout_ms, falkor_result).await?;
+        result_size::observe(Vendor::Falkor.metric_label(), q_name, rows);
+        Ok(execution_time)
+    }
+
+    /// Execute a transaction entry as a single `MULTI`/`EXEC` block of `GRAPH.QUERY` commands.
+    ///
+    /// FalkorDB has no explicit Cypher transactions; MULTI/EXEC applies the statements atomically
+    /// and in order, which is the closest equivalent to the explicit transactions used for Bolt.
+    async fn execute_prepared_transaction(
+        &mut self,
+        worker_id: &str,
+        msg: &Msg<CompactQuery>,
+        simulate: &Option<SimulatedLatency>,
+    ) -> BenchmarkResult<Option<Duration>> {
+        let CompactQuery {
+            q_id,
+            q_name,
+            statements,
+            ..
+        } = &msg.payload;
+        let q_name = &**q_name;
+        let (timeout_ms, timeout) = falkor_query_timeout(self.query_timeouts.for_query(*q_id));
+
+        let offset = msg.compute_offset_ms();
+        FALKOR_MSG_DEADLINE_OFFSET_GAUGE.set(offset);
+        if offset > 0 {
+            // sleep offset millis
+            tokio::time::sleep(Duration::from_millis(offset as u64)).await;
+        }
+
+        if let Some(latency) = simulate {
+            let delay = latency.sample(&mut rand::rng());
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            return Ok(None);
+        }
+
+        let cyphers = statements
+            .iter()
+            .map(|statement| statement.cypher())
+            .collect::<BenchmarkResult<Vec<_>>>()?;
+        let pipe = transaction_pipeline(
+            self.graph.graph_name(),
+            cyphers.iter().map(|cypher| cypher.as_ref()),
+            timeout_ms,
+        );
+
+        if self.transaction_connection.is_none() {
+            self.transaction_connection =
+                Some(self.redis_client.get_multiplexed_async_connection().await?);
+        }
+        let Some(connection) = self.transaction_connection.as_mut() else {
+            return Err(OtherError(
+                "Falkor transaction connection unavailable".to_string(),
+            ));
+        };
+
+        let reply =
+            tokio::time::timeout(timeout, pipe.query_async::<Vec<redis::Value>>(connection)).await;
+        OPERATION_COUNTER
+            .with_label_values(&[Vendor::Falkor.metric_label(), worker_id, "", q_name, "", ""])
+            .inc();
+        let result = match reply {
+            Ok(Ok(replies)) => {
+                // EXEC reports per-statement failures inline rather than failing the whole call.
+                match replies.iter().find_map(|reply| match reply {
+                    redis::Value::ServerError(e) => Some(e),
+                    _ => None,
+                }) {
+                    Some(e) if is_falkor_timeout_message(&format!("{:?}", e)) => {
+                        Err(QueryTimeout(Duration::from_millis(timeout_ms as u64)))
+                    }
+                    Some(e) if is_falkor_rejection_message(&format!("{:?}", e)) => {
+                        Err(rejected(format!("{:?}", e)))
+                    }
+                    Some(e) => {
+                        error!(
+                            "Error executing transaction: {}, the error is: {:?}",
+                            q_name, e
+                        );
+                        Err(OtherError(format!(
+                            "Error executing transaction: {}, the error is: {:?}",
+                            q_name, e
+                        )))
+                    }
+                    None => Ok(replies
+                        .iter()
+                        .map(raw_reply_execution_time)
+                        .sum::<Option<Duration>>()),
+                }
+            }
+            Ok(Err(e)) => {
+                error!(
+                    "Error executing transaction: {}, the error is: {:?}",
+                    q_name, e
+                );
+                // Drop the connection so the next transaction reconnects.
+                self.transaction_connection = None;
+                Err(e.into())
+            }
+            Err(_) => {
+                OPERATION_ERROR_COUNTER
+                    .with_label_values(&[
+                        Vendor::Falkor.metric_label(),
+                        worker_id,
+                        "",
+                        q_name,
+                        "",
+                        "",
+                    ])
+                    .inc();
+                error!(
+                    "Timeout executing transaction: {} after {:?}",
+                    q_name, timeout
+                );
+                Err(QueryTimeout(timeout))
+            }
+        };
+        if let Err(e) = &result {
+            count_failure(worker_id, q_name, e);
+        }
+        result
     }
 
     // #[instrument(skip(self), fields(query = %query, query_name = %query_name))]
@@ -1117,17 +1734,26 @@ RETURN
     ) -> BenchmarkResult<()> {
         // "vendor", "type", "name", "dataset", "dataset_size"
         OPERATION_COUNTER
-            .with_label_values(&["falkor", spawn_id, "", query_name, "", ""])
+            .with_label_values(&[
+                Vendor::Falkor.metric_label(),
+                spawn_id,
+                "",
+                query_name,
+                "",
+                "",
+            ])
             .inc();
 
+        let (timeout_ms, timeout) = falkor_query_timeout(self.query_timeouts.default_timeout());
         let falkor_result = self
             .graph
             .query(query)
-            .with_timeout(self.query_timeout_ms)
+            .with_timeout(timeout_ms)
             .execute();
-        let timeout = self.query_timeout_guard;
         let falkor_result = tokio::time::timeout(timeout, falkor_result).await;
-        Self::read_reply(spawn_id, query_name, query, falkor_result).await
+        Self::read_reply(spawn_id, query_name, query, timeout_ms, falkor_result)
+            .await
+            .map(|_| ())
     }
 
     /// Execute a batch of cypher commands individually (FalkorDB doesn't support multi-statement queries)
@@ -1143,18 +1769,33 @@ RETURN
         // Execute each query individually since FalkorDB doesn't support multi-statement queries
         for (i, query) in batch_queries.iter().enumerate() {
             OPERATION_COUNTER
-                .with_label_values(&["falkor", spawn_id, "", &format!("batch_{}", i), "", ""])
+                .with_label_values(&[
+                    Vendor::Falkor.metric_label(),
+                    spawn_id,
+                    "",
+                    &format!("batch_{}", i),
+                    "",
+                    "",
+                ])
                 .inc();
 
+            let (timeout_ms, timeout) =
+                falkor_query_timeout(self.query_timeouts.default_timeout());
             let falkor_result = self
                 .graph
                 .query(query)
-                .with_timeout(self.query_timeout_ms)
+                .with_timeout(timeout_ms)
                 .execute();
-            let timeout = self.query_timeout_guard;
             let falkor_result = tokio::time::timeout(timeout, falkor_result).await;
 
-            Self::read_reply(spawn_id, &format!("batch_{}", i), query, falkor_result).await?;
+            Self::read_reply(
+                spawn_id,
+                &format!("batch_{}", i),
+                query,
+                timeout_ms,
+                falkor_result,
+            )
+            .await?;
         }
 
         Ok(())
@@ -1168,15 +1809,22 @@ RETURN
         query: &'a str,
     ) -> BenchmarkResult<()> {
         OPERATION_COUNTER
-            .with_label_values(&["falkor", spawn_id, "", query_name, "", ""])
+            .with_label_values(&[
+                Vendor::Falkor.metric_label(),
+                spawn_id,
+                "",
+                query_name,
+                "",
+                "",
+            ])
             .inc();
 
+        let (timeout_ms, timeout) = falkor_query_timeout(self.query_timeouts.default_timeout());
         let falkor_result = self
             .graph
             .query(query)
-            .with_timeout(self.query_timeout_ms)
+            .with_timeout(timeout_ms)
             .execute();
-        let timeout = self.query_timeout_guard;
         let falkor_result = tokio::time::timeout(timeout, falkor_result).await;
 
         match falkor_result {
@@ -1205,7 +1853,14 @@ RETURN
             },
             Err(e) => {
                 OPERATION_ERROR_COUNTER
-                    .with_label_values(&["falkor", spawn_id, "", query_name, "", ""])
+                    .with_label_values(&[
+                        Vendor::Falkor.metric_label(),
+                        spawn_id,
+                        "",
+                        query_name,
+                        "",
+                        "",
+                    ])
                     .inc();
                 let error_type = std::any::type_name_of_val(&e);
                 error!("Error executing query: {}, the error is: {:?}", query, e);
@@ -1217,20 +1872,33 @@ RETURN
         }
     }
 
+    /// Drain a reply, returning the server-side execution time FalkorDB reported with it and the
+    /// number of rows.
     async fn read_reply(
         spawn_id: &str,
         query_name: &str,
         query: &str,
+        timeout_ms: i64,
         reply: Result<FalkorResult<QueryResult<RowStream>>, Elapsed>,
-    ) -> BenchmarkResult<()> {
-        match reply {
+    ) -> BenchmarkResult<(Option<Duration>, u64)> {
+        let timed_out = || QueryTimeout(Duration::from_millis(timeout_ms as u64));
+        let result = match reply {
             Ok(falkor_result) => match falkor_result {
                 Ok(query_result) => {
+                    let execution_time = query_result
+                        .get_internal_execution_time()
+                        .and_then(execution_time_from_ms);
                     let mut data = query_result.data;
+                    let mut rows = 0;
                     while let Some(row) = data.next().await {
                         let _ = black_box(row);
+                        rows += 1;
                     }
-                    Ok(())
+                    Ok((execution_time, rows))
+                }
+                Err(e) if is_falkor_timeout_message(&e.to_string()) => Err(timed_out()),
+                Err(e) if is_falkor_rejection_message(&e.to_string()) => {
+                    Err(rejected(e.to_string()))
                 }
                 Err(e) => {
                     let error_type = std::any::type_name_of_val(&e);
@@ -1242,17 +1910,212 @@ RETURN
                 }
             },
 
-            Err(e) => {
+            Err(_) => {
                 OPERATION_ERROR_COUNTER
-                    .with_label_values(&["falkor", spawn_id, "", query_name, "", ""])
+                    .with_label_values(&[
+                        Vendor::Falkor.metric_label(),
+                        spawn_id,
+                        "",
+                        query_name,
+                        "",
+                        "",
+                    ])
                     .inc();
-                let error_type = std::any::type_name_of_val(&e);
-                error!("Error executing query: {}, the error is: {:?}", query, e);
-                Err(OtherError(format!(
-                    "Error (type {}) executing query: {}, the error is: {:?}",
-                    error_type, query, e
-                )))
+                error!("Timeout executing query: {}", query);
+                Err(timed_out())
+            }
+        };
+        if let Err(e) = &result {
+            count_failure(spawn_id, query_name, e);
+        }
+        result
+    }
+}
+
+/// The property map of a Pokec Users node statement, `CREATE (:User {...});`.
+pub(crate) fn users_node_map(statement: &str) -> Option<&str> {
+    let (l, r) = (statement.find('{')?, statement.rfind('}')?);
+    (r > l).then(|| &statement[l..=r])
+}
+
+/// The two ids of a Pokec Users edge statement, `MATCH (n:User {id: X}), (m:User {id: Y}) ...`.
+pub(crate) fn users_edge_ids(statement: &str) -> Option<(u64, u64)> {
+    let mut ids: [u64; 2] = [0, 0];
+    let mut found = 0usize;
+    let mut rest = statement;
+    while found < 2 {
+        let Some(pos) = rest.find("id:") else { break };
+        rest = &rest[pos + 3..];
+        let s = rest.trim_start();
+        let mut end = 0usize;
+        for (i, ch) in s.char_indices() {
+            if !ch.is_ascii_digit() {
+                end = i;
+                break;
             }
         }
+        let end = if end == 0 { s.len() } else { end };
+        if let Ok(v) = s[..end].parse::<u64>() {
+            ids[found] = v;
+            found += 1;
+        }
+        rest = &s[end..];
+    }
+    (found == 2).then_some((ids[0], ids[1]))
+}
+
+/// What `execute_pokec_users_import_unwind` would send for a dataset, worked out without a
+/// server for `load --dry-run`: the same parsing, phase switch and batch flushes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UnwindBatchPlan {
+    pub batch_size: usize,
+    pub nodes: usize,
+    pub edges: usize,
+    /// Statements the loader would drop because they don't parse.
+    pub unparsed: usize,
+    pub node_batches: usize,
+    pub edge_batches: usize,
+    in_edges: bool,
+    pending_nodes: usize,
+    pending_edges: usize,
+}
+
+impl UnwindBatchPlan {
+    pub fn new(batch_size: usize) -> Self {
+        UnwindBatchPlan {
+            batch_size,
+            ..UnwindBatchPlan::default()
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        line: &str,
+    ) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed == ";" || trimmed.starts_with("//") {
+            return;
+        }
+        if !self.in_edges && trimmed.starts_with("MATCH") {
+            self.flush_nodes();
+            self.in_edges = true;
+        }
+        if self.in_edges {
+            match users_edge_ids(trimmed) {
+                Some(_) => {
+                    self.edges += 1;
+                    self.pending_edges += 1;
+                }
+                None => self.unparsed += 1,
+            }
+            if self.pending_edges >= self.batch_size {
+                self.flush_edges();
+            }
+        } else {
+            match users_node_map(trimmed) {
+                Some(_) => {
+                    self.nodes += 1;
+                    self.pending_nodes += 1;
+                }
+                None => self.unparsed += 1,
+            }
+            if self.pending_nodes >= self.batch_size {
+                self.flush_nodes();
+            }
+        }
+    }
+
+    /// The final flushes, once the stream is over.
+    pub fn finish(&mut self) {
+        self.flush_nodes();
+        self.flush_edges();
+    }
+
+    fn flush_nodes(&mut self) {
+        if self.pending_nodes > 0 {
+            self.node_batches += 1;
+            self.pending_nodes = 0;
+        }
+    }
+
+    fn flush_edges(&mut self) {
+        if self.pending_edges > 0 {
+            self.edge_batches += 1;
+            self.pending_edges = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_batch_plan_follows_the_unwind_loader() {
+        let mut plan = UnwindBatchPlan::new(2);
+        for line in [
+            "// Pokec users",
+            "CREATE (:User {id: 1, age: 20});",
+            "CREATE (:User {id: 2, age: 31});",
+            "CREATE (:User {id: 3, age: 44});",
+            "CREATE (:User);",
+            "",
+            "MATCH (n:User {id: 1}), (m:User {id: 2}) CREATE (n)-[e: Friend]->(m);",
+            "MATCH (n:User {id: 2}), (m:User {id: 3}) CREATE (n)-[e: Friend]->(m);",
+            "MATCH (n:User {id: 3}), (m:User {id: 1}) CREATE (n)-[e: Friend]->(m);",
+        ] {
+            plan.push(line);
+        }
+        plan.finish();
+        assert_eq!(
+            (
+                plan.nodes,
+                plan.edges,
+                plan.unparsed,
+                plan.node_batches,
+                plan.edge_batches
+            ),
+            (3, 3, 1, 2, 2)
+        );
+        assert_eq!(
+            users_edge_ids(
+                "MATCH (n:User {id: 17}), (m:User {id: 4}) CREATE (n)-[e: Friend]->(m);"
+            ),
+            Some((17, 4))
+        );
+        assert_eq!(users_node_map("CREATE (:User {id: 1});"), Some("{id: 1}"));
+    }
+
+    #[test]
+    fn transaction_statements_carry_a_compact_timeout() {
+        let pipe = transaction_pipeline("g", ["CREATE (:A)", "RETURN 1"], 250);
+        let packed = String::from_utf8(pipe.get_packed_pipeline()).unwrap();
+        let query = |cypher: &str| {
+            format!(
+                "*6\r\n$11\r\nGRAPH.QUERY\r\n$1\r\ng\r\n${}\r\n{}\r\n$9\r\n--compact\r\n$7\r\ntimeout\r\n$3\r\n250\r\n",
+                cypher.len(),
+                cypher
+            )
+        };
+        assert_eq!(
+            packed,
+            format!(
+                "*1\r\n$5\r\nMULTI\r\n{}{}*1\r\n$4\r\nEXEC\r\n",
+                query("CREATE (:A)"),
+                query("RETURN 1")
+            )
+        );
+
+        let compact_reply = redis::Value::Array(vec![
+            redis::Value::Array(vec![]),
+            redis::Value::Array(vec![]),
+            redis::Value::Array(vec![redis::Value::BulkString(
+                b"Query internal execution time: 1.5 milliseconds".to_vec(),
+            )]),
+        ]);
+        assert_eq!(
+            raw_reply_execution_time(&compact_reply),
+            Some(Duration::from_micros(1500))
+        );
     }
 }
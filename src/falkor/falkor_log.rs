@@ -0,0 +1,263 @@
+//! Tails falkordb.log during a run's measured phase. Warning-level and error lines, and the
+//! FalkorDB module's slow-query lines, are counted in `falkordb_log_lines_total` and written to
+//! `<results-dir>/falkor/server_errors.jsonl`, each with the number of queries the client saw fail
+//! in the same second so server-side complaints can be lined up with client-side failures.
+
+use crate::error::BenchmarkResult;
+use crate::throughput::ThroughputTracker;
+use crate::FALKOR_LOG_LINES;
+use serde::Serialize;
+use std::io::SeekFrom;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerLogKind {
+    Error,
+    SlowQuery,
+}
+
+impl ServerLogKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ServerLogKind::Error => "error",
+            ServerLogKind::SlowQuery => "slow_query",
+        }
+    }
+}
+
+/// One line of `server_errors.jsonl`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ServerLogEntry {
+    pub kind: ServerLogKind,
+    /// The server's own timestamp, e.g. `16 Oct 2026 12:00:00.123`.
+    pub logged_at: String,
+    pub message: String,
+    /// Seconds into the measured phase when the line was read.
+    pub at_second: u64,
+    /// Queries the client saw fail during that second.
+    pub client_errors: u64,
+}
+
+/// What the log had to say about the run; recorded in meta.json as `server_log`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ServerLogReport {
+    pub errors: u64,
+    pub slow_queries: u64,
+    /// Entries logged in a second in which the client also saw failures.
+    pub during_client_errors: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ServerLog {
+    pub entries: Vec<ServerLogEntry>,
+}
+
+impl ServerLog {
+    pub fn report(&self) -> ServerLogReport {
+        let mut report = ServerLogReport::default();
+        for entry in &self.entries {
+            match entry.kind {
+                ServerLogKind::Error => report.errors += 1,
+                ServerLogKind::SlowQuery => report.slow_queries += 1,
+            }
+            if entry.client_errors > 0 {
+                report.during_client_errors += 1;
+            }
+        }
+        report
+    }
+
+    pub fn to_jsonl(&self) -> BenchmarkResult<String> {
+        let mut jsonl = String::new();
+        for entry in &self.entries {
+            jsonl.push_str(&serde_json::to_string(entry)?);
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+}
+
+/// The kind, timestamp and message of a falkordb.log line worth keeping, e.g.
+/// `1234:M 16 Oct 2026 12:00:00.123 # <graph> ...`. Redis marks warnings with `#`; anything else
+/// is kept only when it reports an error or a slow query.
+pub fn classify_line(line: &str) -> Option<(ServerLogKind, String, String)> {
+    let (_, rest) = line.split_once(':')?;
+    let (_role, rest) = rest.split_once(' ')?;
+    // `16 Oct 2026 12:00:00.123 <level> <message>`
+    let mut fields = rest.splitn(6, ' ');
+    let logged_at = [
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+    ]
+    .join(" ");
+    let level = fields.next()?;
+    let message = fields.next()?.trim().to_string();
+    let lower = message.to_lowercase();
+    let kind = if lower.contains("slow") && lower.contains("quer") {
+        ServerLogKind::SlowQuery
+    } else if level == "#" || lower.contains("error") {
+        ServerLogKind::Error
+    } else {
+        return None;
+    };
+    Some((kind, logged_at, message))
+}
+
+/// Reads what the server appends to its log while the run is measured.
+pub struct ServerLogTail {
+    handle: JoinHandle<ServerLog>,
+    shutdown_tx: oneshot::Sender<()>,
+}
+
+impl ServerLogTail {
+    /// Starts from the log's current end, so only lines written from now on are read.
+    pub async fn spawn(
+        path: String,
+        throughput: Arc<ThroughputTracker>,
+    ) -> Self {
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+        let mut offset = tokio::fs::metadata(&path)
+            .await
+            .map(|meta| meta.len())
+            .unwrap_or(0);
+        let handle = tokio::spawn(async move {
+            let mut log = ServerLog::default();
+            let mut pending = String::new();
+            let mut ticker = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                let done = tokio::select! {
+                    _ = ticker.tick() => false,
+                    _ = &mut shutdown_rx => true,
+                };
+                let at_second = throughput.elapsed().as_secs();
+                for line in read_new_lines(&path, &mut offset, &mut pending).await {
+                    if let Some((kind, logged_at, message)) = classify_line(&line) {
+                        FALKOR_LOG_LINES.with_label_values(&[kind.as_str()]).inc();
+                        log.entries.push(ServerLogEntry {
+                            kind,
+                            logged_at,
+                            message,
+                            at_second,
+                            client_errors: 0,
+                        });
+                    }
+                }
+                if done {
+                    break;
+                }
+            }
+            let samples = throughput.samples();
+            for entry in &mut log.entries {
+                entry.client_errors = samples
+                    .get(entry.at_second as usize)
+                    .map_or(0, |sample| sample.errors);
+            }
+            log
+        });
+        ServerLogTail {
+            handle,
+            shutdown_tx,
+        }
+    }
+
+    /// Reads the rest of the log and returns what was found.
+    pub async fn stop(self) -> ServerLog {
+        drop(self.shutdown_tx);
+        self.handle.await.unwrap_or_default()
+    }
+}
+
+/// The complete lines appended to `path` since `offset`. A log that shrank was recreated by a
+/// server restart and is read again from the start.
+pub(crate) async fn read_new_lines(
+    path: &str,
+    offset: &mut u64,
+    pending: &mut String,
+) -> Vec<String> {
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return Vec::new();
+    };
+    let len = file.metadata().await.map(|meta| meta.len()).unwrap_or(0);
+    if len < *offset {
+        *offset = 0;
+        pending.clear();
+    }
+    if file.seek(SeekFrom::Start(*offset)).await.is_err() {
+        return Vec::new();
+    }
+    let mut bytes = Vec::new();
+    let Ok(read) = file.read_to_end(&mut bytes).await else {
+        return Vec::new();
+    };
+    *offset += read as u64;
+    pending.push_str(&String::from_utf8_lossy(&bytes));
+    let Some(end) = pending.rfind('\n') else {
+        return Vec::new();
+    };
+    let complete: String = pending.drain(..=end).collect();
+    complete.lines().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_warnings_errors_and_slow_queries() {
+        let line = classify_line;
+        assert_eq!(
+            line("4711:M 16 Oct 2026 12:00:00.123 # <graph> Failed to allocate query memory"),
+            Some((
+                ServerLogKind::Error,
+                "16 Oct 2026 12:00:00.123".to_string(),
+                "<graph> Failed to allocate query memory".to_string()
+            ))
+        );
+        assert_eq!(
+            line("4711:M 16 Oct 2026 12:00:01.000 * <graph> slow query took 512ms: MATCH (n) RETURN n")
+                .map(|(kind, _, _)| kind),
+            Some(ServerLogKind::SlowQuery)
+        );
+        assert_eq!(
+            line("4711:M 16 Oct 2026 12:00:02.000 * Error reading RDB preamble")
+                .map(|(kind, _, _)| kind),
+            Some(ServerLogKind::Error)
+        );
+        assert_eq!(
+            line("4711:M 16 Oct 2026 12:00:03.000 * Ready to accept connections tcp"),
+            None
+        );
+        assert_eq!(line("garbage"), None);
+
+        let entry = |kind, client_errors| ServerLogEntry {
+            kind,
+            logged_at: String::new(),
+            message: String::new(),
+            at_second: 0,
+            client_errors,
+        };
+        let log = ServerLog {
+            entries: vec![
+                entry(ServerLogKind::Error, 3),
+                entry(ServerLogKind::Error, 0),
+                entry(ServerLogKind::SlowQuery, 0),
+            ],
+        };
+        assert_eq!(
+            log.report(),
+            ServerLogReport {
+                errors: 2,
+                slow_queries: 1,
+                during_client_errors: 1
+            }
+        );
+        assert_eq!(log.to_jsonl().unwrap().lines().count(), 3);
+    }
+}
@@ -1,24 +1,118 @@
 use crate::error::BenchmarkError::OtherError;
 use crate::error::BenchmarkResult;
-use crate::process_monitor::ProcessMonitor;
+use crate::process_monitor::{ProcessMonitor, RestartLog};
+use crate::resource_limits::ServerLimits;
+use crate::sanitizer::{self, Sanitizer};
+use crate::scenario::Vendor;
 use crate::utils::{
-    create_directory_if_not_exists, delete_file, falkor_shared_lib_path, get_falkor_log_path,
+    create_directory_if_not_exists, delete_file, falkor_module_path, get_falkor_log_path,
     ping_redis, redis_shutdown,
 };
 use crate::{
     prometheus_metrics, CPU_USAGE_GAUGE, FALKOR_CPU_USAGE_GAUGE, FALKOR_MEM_USAGE_GAUGE,
-    FALKOR_NODES_GAUGE, FALKOR_RELATIONSHIPS_GAUGE, FALKOR_RESTART_COUNTER,
-    FALKOR_RUNNING_REQUESTS_GAUGE, FALKOR_WAITING_REQUESTS_GAUGE, MEM_USAGE_GAUGE, REDIS_DATA_DIR,
+    FALKOR_PROCESS_MEMORY_BYTES,
+    FALKOR_NODES_GAUGE, FALKOR_REDIS_CONNECTED_CLIENTS, FALKOR_REDIS_EVICTED_KEYS,
+    FALKOR_REDIS_INSTANTANEOUS_OPS_PER_SEC, FALKOR_REDIS_REJECTED_CONNECTIONS,
+    FALKOR_REDIS_USED_MEMORY_BYTES, FALKOR_REDIS_USED_MEMORY_RSS_BYTES, FALKOR_RELATIONSHIPS_GAUGE,
+    FALKOR_RESTART_COUNTER, FALKOR_RUNNING_REQUESTS_GAUGE, FALKOR_WAITING_REQUESTS_GAUGE,
+    MEM_USAGE_GAUGE, REDIS_DATA_DIR,
 };
+use clap::Args;
 use falkordb::{AsyncGraph, FalkorClientBuilder, FalkorConnectionInfo};
 use futures::StreamExt;
 use prometheus::core::{AtomicU64, GenericCounter};
-use std::env;
+use prometheus::IntGauge;
+use serde::Serialize;
 use std::sync::{Arc, Mutex};
 use sysinfo::{Pid, System};
 use tokio::task::JoinHandle;
 use tracing::{error, info};
 
+/// The FalkorDB module's load-time options for the locally managed redis-server; unset ones
+/// keep FalkorDB's own defaults. Recorded in meta.json as `falkor_module`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Args)]
+pub struct FalkorModuleConfig {
+    #[arg(
+        long = "falkor-thread-count",
+        env = "FALKOR_THREAD_COUNT",
+        required = false,
+        help = "FalkorDB's THREAD_COUNT, the size of its query thread pool (default: one per core)"
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_count: Option<u32>,
+    #[arg(
+        long = "falkor-cache-size",
+        env = "FALKOR_CACHE_SIZE",
+        required = false,
+        default_value_t = DEFAULT_CACHE_SIZE,
+        help = "FalkorDB's CACHE_SIZE, the query plans cached per thread"
+    )]
+    pub cache_size: u32,
+    #[arg(
+        long = "falkor-omp-thread-count",
+        env = "FALKOR_OMP_THREAD_COUNT",
+        required = false,
+        help = "FalkorDB's OMP_THREAD_COUNT, the threads a single GraphBLAS operation may use"
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub omp_thread_count: Option<u32>,
+    #[arg(
+        long = "falkor-max-queued-queries",
+        env = "FALKOR_MAX_QUEUED_QUERIES",
+        required = false,
+        default_value_t = DEFAULT_MAX_QUEUED_QUERIES,
+        help = "FalkorDB's MAX_QUEUED_QUERIES; queries past it are rejected"
+    )]
+    pub max_queued_queries: u32,
+    #[arg(
+        long = "falkor-query-mem-capacity",
+        env = "FALKOR_QUERY_MEM_CAPACITY",
+        required = false,
+        help = "FalkorDB's QUERY_MEM_CAPACITY, the bytes a single query may allocate (default: unlimited)"
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_mem_capacity: Option<u64>,
+}
+
+const DEFAULT_CACHE_SIZE: u32 = 40;
+const DEFAULT_MAX_QUEUED_QUERIES: u32 = 400;
+
+impl Default for FalkorModuleConfig {
+    fn default() -> Self {
+        FalkorModuleConfig {
+            thread_count: None,
+            cache_size: DEFAULT_CACHE_SIZE,
+            omp_thread_count: None,
+            max_queued_queries: DEFAULT_MAX_QUEUED_QUERIES,
+            query_mem_capacity: None,
+        }
+    }
+}
+
+impl FalkorModuleConfig {
+    /// The `--loadmodule` arguments after the module's path.
+    pub fn module_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "CACHE_SIZE".to_string(),
+            self.cache_size.to_string(),
+            "MAX_QUEUED_QUERIES".to_string(),
+            self.max_queued_queries.to_string(),
+        ];
+        let optional = [
+            ("THREAD_COUNT", self.thread_count.map(u64::from)),
+            ("OMP_THREAD_COUNT", self.omp_thread_count.map(u64::from)),
+            ("QUERY_MEM_CAPACITY", self.query_mem_capacity),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                args.push(name.to_string());
+                args.push(value.to_string());
+            }
+        }
+        args
+    }
+}
+
 #[derive(Default)]
 pub struct FalkorProcess {
     shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
@@ -27,6 +121,7 @@ pub struct FalkorProcess {
     prom_process_handle: Option<JoinHandle<()>>,
     ping_server_shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
     ping_server_handle: Option<JoinHandle<()>>,
+    restart_log: Option<RestartLog>,
     dropped: bool,
 }
 
@@ -41,49 +136,68 @@ impl FalkorProcess {
             prom_process_handle: None,
             ping_server_shutdown_tx: None,
             ping_server_handle: None,
+            restart_log: None,
             dropped: true, // Mark as dropped so Drop doesn't try to terminate
         }
     }
 
-    pub async fn new() -> BenchmarkResult<Self> {
+    pub async fn new(
+        server_limits: &ServerLimits,
+        module: &FalkorModuleConfig,
+        sanitizer: Option<Sanitizer>,
+    ) -> BenchmarkResult<Self> {
         redis_shutdown().await?; // if redis run on this machine, use redis-cli to shut it down
 
         create_directory_if_not_exists(REDIS_DATA_DIR).await?;
         let falkor_log_path = get_falkor_log_path()?;
         delete_file(falkor_log_path.as_str()).await?;
 
-        let default_so_path = falkor_shared_lib_path()?;
-        let default_so_path = env::var("FALKOR_PATH").unwrap_or_else(|_| default_so_path.clone());
+        let default_so_path = falkor_module_path()?;
         let falkor_log_path = get_falkor_log_path()?;
-        let command = "redis-server".to_string();
-
-        let args: Vec<String> = vec![
-            "--dir",
-            REDIS_DATA_DIR,
-            "--logfile",
-            falkor_log_path.as_str(),
-            "--protected-mode",
-            "no",
-            "--loadmodule",
-            default_so_path.as_str(),
-            "CACHE_SIZE",
-            "40",
-            "MAX_QUEUED_QUERIES",
-            "400",
-        ]
-        .into_iter()
-        .map(|s| s.to_string())
-        .collect();
-
-        let (mut process_monitor, shutdown_tx) = ProcessMonitor::new(
-            command,
-            args,
-            Default::default(),
-            std::time::Duration::from_secs(5),
+        let redis_server =
+            sanitizer.map_or_else(|| "redis-server".to_string(), Sanitizer::redis_server);
+        let (command, mut args) = server_limits.command(&redis_server);
+
+        args.extend(
+            [
+                "--dir",
+                REDIS_DATA_DIR,
+                "--logfile",
+                falkor_log_path.as_str(),
+                "--protected-mode",
+                "no",
+                "--loadmodule",
+                default_so_path.as_str(),
+            ]
+            .map(String::from),
         );
+        args.extend(module.module_args());
+
+        let (mut process_monitor, shutdown_tx) = match sanitizer {
+            Some(sanitizer) => {
+                let log_prefix = sanitizer::log_prefix()?;
+                sanitizer::clear_reports(&log_prefix).await;
+                // Time for the leak check the server runs as it exits.
+                let (monitor, shutdown_tx) = ProcessMonitor::new(
+                    command,
+                    args,
+                    sanitizer.env_vars(&log_prefix),
+                    std::time::Duration::from_secs(60),
+                );
+                (monitor.with_graceful_stop(), shutdown_tx)
+            }
+            None => ProcessMonitor::new(
+                command,
+                args,
+                Default::default(),
+                std::time::Duration::from_secs(5),
+            ),
+        };
         let counter: GenericCounter<AtomicU64> = FALKOR_RESTART_COUNTER.clone();
+        let restart_log = RestartLog::default();
+        let monitor_restart_log = restart_log.clone();
         let falkor_process_monitor = tokio::spawn(async move {
-            let _ = process_monitor.run(counter).await;
+            let _ = process_monitor.run(counter, monitor_restart_log).await;
         });
         let process_handle = Some(falkor_process_monitor);
 
@@ -99,9 +213,15 @@ impl FalkorProcess {
             prom_process_handle: Some(prom_process_handle),
             ping_server_shutdown_tx: Some(ping_server_shutdown_tx),
             ping_server_handle: Some(ping_server_handle),
+            restart_log: Some(restart_log),
             dropped: false,
         })
     }
+
+    /// Restarts of the monitored redis-server; `None` for external endpoints, which have no monitor.
+    pub fn restart_log(&self) -> Option<&RestartLog> {
+        self.restart_log.as_ref()
+    }
     async fn terminate(&mut self) {
         if let Some(ping_server_shutdown_tx) = self.ping_server_shutdown_tx.take() {
             drop(ping_server_shutdown_tx);
@@ -169,24 +289,26 @@ fn ping_server() -> (JoinHandle<()>, tokio::sync::oneshot::Sender<()>) {
 }
 
 async fn report_metrics(system: Arc<Mutex<System>>) -> BenchmarkResult<()> {
-    let client = redis::Client::open("redis://127.0.0.1:6379/")?;
-    let mut con = client.get_multiplexed_async_connection().await?;
-
-    let command = redis::cmd("GRAPH.INFO");
-    let redis_value = con.send_packed_command(&command).await?;
-    let (running_queries, waiting_queries) = redis_to_query_info(redis_value)?;
-
-    FALKOR_RUNNING_REQUESTS_GAUGE.set(running_queries as i64);
-    FALKOR_WAITING_REQUESTS_GAUGE.set(waiting_queries as i64);
+    // Each collector runs whatever the others did, so a failed GRAPH.INFO doesn't leave the
+    // INFO gauges and the CPU and memory samples stale.
+    if let Err(e) = collect_query_info_metrics("redis://127.0.0.1:6379/").await {
+        error!("Error collecting GRAPH.INFO metrics: {:?}", e);
+    }
+    if let Err(e) = collect_redis_info_metrics("redis://127.0.0.1:6379/").await {
+        error!("Error collecting INFO metrics: {:?}", e);
+    }
+    if let Err(e) = collect_graph_size_metrics().await {
+        error!("Error collecting graph size metrics: {:?}", e);
+    }
+    fill_memory_and_cpu_metrics(system).await
+}
 
-    let connection_info: FalkorConnectionInfo = "falkor://127.0.0.1:6379"
-        .try_into()
-        .expect("Invalid connection info");
+async fn collect_graph_size_metrics() -> BenchmarkResult<()> {
+    let connection_info: FalkorConnectionInfo = "falkor://127.0.0.1:6379".try_into()?;
     let client = FalkorClientBuilder::new_async()
         .with_connection_info(connection_info)
         .build()
-        .await
-        .expect("Failed to build client");
+        .await?;
     let mut graph = client.select_graph("falkor");
     if let Ok(relationships_number) =
         execute_i64_query(&mut graph, "MATCH ()-[r]->() RETURN count(r)").await
@@ -196,12 +318,51 @@ async fn report_metrics(system: Arc<Mutex<System>>) -> BenchmarkResult<()> {
     if let Ok(nodes_number) = execute_i64_query(&mut graph, "MATCH (n) RETURN count(n)").await {
         FALKOR_NODES_GAUGE.set(nodes_number);
     }
+    Ok(())
+}
+
+/// Refresh the running/waiting query gauges from `GRAPH.INFO`.
+pub(crate) async fn collect_query_info_metrics(redis_url: &str) -> BenchmarkResult<()> {
+    let client = redis::Client::open(redis_url)?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+
+    let command = redis::cmd("GRAPH.INFO");
+    let redis_value = con.send_packed_command(&command).await?;
+    let (running_queries, waiting_queries) = redis_to_query_info(redis_value)?;
 
-    fill_memory_and_cpu_metrics(system).await?;
+    FALKOR_RUNNING_REQUESTS_GAUGE.set(running_queries as i64);
+    FALKOR_WAITING_REQUESTS_GAUGE.set(waiting_queries as i64);
+    Ok(())
+}
 
+/// Refresh the Redis server gauges (memory, clients, throughput, rejections, evictions) from `INFO`.
+pub(crate) async fn collect_redis_info_metrics(redis_url: &str) -> BenchmarkResult<()> {
+    let client = redis::Client::open(redis_url)?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+    let info: redis::InfoDict = redis::cmd("INFO").query_async(&mut con).await?;
+    set_redis_info_gauges(&info);
     Ok(())
 }
 
+fn set_redis_info_gauges(info: &redis::InfoDict) {
+    let gauges: [(&IntGauge, &str); 6] = [
+        (&FALKOR_REDIS_USED_MEMORY_BYTES, "used_memory"),
+        (&FALKOR_REDIS_USED_MEMORY_RSS_BYTES, "used_memory_rss"),
+        (&FALKOR_REDIS_CONNECTED_CLIENTS, "connected_clients"),
+        (
+            &FALKOR_REDIS_INSTANTANEOUS_OPS_PER_SEC,
+            "instantaneous_ops_per_sec",
+        ),
+        (&FALKOR_REDIS_REJECTED_CONNECTIONS, "rejected_connections"),
+        (&FALKOR_REDIS_EVICTED_KEYS, "evicted_keys"),
+    ];
+    for (gauge, field) in gauges {
+        if let Some(value) = info.get::<i64>(field) {
+            gauge.set(value);
+        }
+    }
+}
+
 async fn fill_memory_and_cpu_metrics(sys: Arc<Mutex<System>>) -> BenchmarkResult<()> {
     let mut system = sys.lock().unwrap();
     // Refresh CPU usage
@@ -221,6 +382,8 @@ async fn fill_memory_and_cpu_metrics(sys: Arc<Mutex<System>>) -> BenchmarkResult
             FALKOR_CPU_USAGE_GAUGE.set(cpu_usage);
             let mem_used = process.memory() as i64;
             FALKOR_MEM_USAGE_GAUGE.set(mem_used);
+            FALKOR_PROCESS_MEMORY_BYTES.set(mem_used);
+            prometheus_metrics::report_process_disk_io(Vendor::Falkor, process);
         }
     }
 
@@ -292,3 +455,59 @@ async fn execute_i64_query(
         Err(OtherError(format!("No response for query: {}", query)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redis_info_fields_map_to_gauges() {
+        let info = redis::InfoDict::new(
+            "# Clients\r\nconnected_clients:41\r\n\r\n# Memory\r\nused_memory:1048576\r\n\
+             used_memory_rss:2097152\r\nused_memory_human:1.00M\r\n\r\n# Stats\r\n\
+             instantaneous_ops_per_sec:3800\r\nrejected_connections:2\r\nevicted_keys:0\r\n",
+        );
+        set_redis_info_gauges(&info);
+
+        assert_eq!(FALKOR_REDIS_CONNECTED_CLIENTS.get(), 41);
+        assert_eq!(FALKOR_REDIS_USED_MEMORY_BYTES.get(), 1_048_576);
+        assert_eq!(FALKOR_REDIS_USED_MEMORY_RSS_BYTES.get(), 2_097_152);
+        assert_eq!(FALKOR_REDIS_INSTANTANEOUS_OPS_PER_SEC.get(), 3800);
+        assert_eq!(FALKOR_REDIS_REJECTED_CONNECTIONS.get(), 2);
+        assert_eq!(FALKOR_REDIS_EVICTED_KEYS.get(), 0);
+    }
+
+    #[test]
+    fn module_args_pass_only_the_options_given() {
+        assert_eq!(
+            FalkorModuleConfig::default().module_args(),
+            ["CACHE_SIZE", "40", "MAX_QUEUED_QUERIES", "400"]
+        );
+        let module = FalkorModuleConfig {
+            thread_count: Some(8),
+            cache_size: 100,
+            omp_thread_count: Some(1),
+            max_queued_queries: 400,
+            query_mem_capacity: Some(1 << 30),
+        };
+        assert_eq!(
+            module.module_args(),
+            [
+                "CACHE_SIZE",
+                "100",
+                "MAX_QUEUED_QUERIES",
+                "400",
+                "THREAD_COUNT",
+                "8",
+                "OMP_THREAD_COUNT",
+                "1",
+                "QUERY_MEM_CAPACITY",
+                "1073741824"
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(FalkorModuleConfig::default()).unwrap(),
+            serde_json::json!({"cache_size": 40, "max_queued_queries": 400})
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! `run --saturate-queue`: push FalkorDB past MAX_QUEUED_QUERIES on purpose and record how it
+//! turns the overflow away and how long it takes to accept queries again, instead of measuring
+//! steady-state latency only.
+//!
+//! The experiment starts once `GRAPH.INFO` shows an idle queue, watches the running and waiting
+//! queries every [`QUEUE_WATCH_INTERVAL`] while the workers outnumber the queue, and ends with
+//! `saturation.json` next to `meta.json`.
+
+use crate::error::BenchmarkError::{self, OtherError, QueryRejected, QueryTimeout};
+use crate::error::BenchmarkResult;
+use crate::falkor::falkor_process::collect_query_info_metrics;
+use crate::{FALKOR_RUNNING_REQUESTS_GAUGE, FALKOR_WAITING_REQUESTS_GAUGE};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{info, warn};
+
+pub const SATURATION_FILE: &str = "saturation.json";
+
+/// How often `GRAPH.INFO` is read during the experiment; the 5s of the metrics reporters would
+/// miss the queue filling up.
+pub const QUEUE_WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long to wait for the queue to empty, before the experiment and after it.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What the experiment found, written to `saturation.json`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SaturationReport {
+    /// `GRAPH.CONFIG GET MAX_QUEUED_QUERIES`.
+    pub max_queued_queries: u64,
+    pub parallel: usize,
+    /// At least one query was rejected, i.e. the queue was full.
+    pub saturated: bool,
+    pub succeeded: u64,
+    pub rejected: u64,
+    /// Errors by kind: `rejected`, `timeout` or `other`.
+    pub errors: BTreeMap<String, u64>,
+    /// FalkorDB's reply to the first rejected query.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejection_message: Option<String>,
+    /// Into the measured phase.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_rejection_ms: Option<u64>,
+    pub rejections_per_second: Vec<u64>,
+    pub peak_running: u64,
+    pub peak_waiting: u64,
+    /// From the last scheduled query until a probe query was accepted again; `None` when none
+    /// was within a minute.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_ms: Option<u64>,
+}
+
+impl SaturationReport {
+    pub async fn write(
+        &self,
+        run_dir: &Path,
+    ) -> BenchmarkResult<()> {
+        let path = run_dir.join(SATURATION_FILE);
+        tokio::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .await
+            .map_err(|e| OtherError(format!("Failed writing {}: {}", path.display(), e)))
+    }
+}
+
+#[derive(Debug)]
+struct Tally {
+    start: Instant,
+    succeeded: u64,
+    errors: BTreeMap<String, u64>,
+    rejection_message: Option<String>,
+    first_rejection_ms: Option<u64>,
+    rejections_per_second: Vec<u64>,
+    peak_running: u64,
+    peak_waiting: u64,
+}
+
+/// The outcomes and queue depths of the measured phase, shared by the workers and the queue
+/// watch.
+#[derive(Debug)]
+pub struct QueueSaturation {
+    max_queued_queries: u64,
+    parallel: usize,
+    tally: Mutex<Tally>,
+}
+
+impl QueueSaturation {
+    /// Fails unless `parallel` workers can keep more queries in flight than the queue holds.
+    pub fn new(
+        max_queued_queries: u64,
+        parallel: usize,
+    ) -> BenchmarkResult<Self> {
+        if parallel as u64 <= max_queued_queries {
+            return Err(OtherError(format!(
+                "--saturate-queue needs more queries in flight than MAX_QUEUED_QUERIES ({}); raise --parallel above it or lower --falkor-max-queued-queries",
+                max_queued_queries
+            )));
+        }
+        Ok(QueueSaturation {
+            max_queued_queries,
+            parallel,
+            tally: Mutex::new(Tally {
+                start: Instant::now(),
+                succeeded: 0,
+                errors: BTreeMap::new(),
+                rejection_message: None,
+                first_rejection_ms: None,
+                rejections_per_second: Vec::new(),
+                peak_running: 0,
+                peak_waiting: 0,
+            }),
+        })
+    }
+
+    /// Start the measured phase: offsets count from here.
+    pub fn begin(&self) {
+        self.tally.lock().unwrap().start = Instant::now();
+    }
+
+    /// Count a query's outcome; `None` for a success.
+    pub fn record(
+        &self,
+        error: Option<&BenchmarkError>,
+    ) {
+        let offset = self.tally.lock().unwrap().start.elapsed();
+        self.record_at(offset, error);
+    }
+
+    fn record_at(
+        &self,
+        offset: Duration,
+        error: Option<&BenchmarkError>,
+    ) {
+        let mut tally = self.tally.lock().unwrap();
+        let Some(error) = error else {
+            tally.succeeded += 1;
+            return;
+        };
+        let kind = match error {
+            QueryRejected(message) => {
+                let second = offset.as_secs() as usize;
+                if tally.rejections_per_second.len() <= second {
+                    tally.rejections_per_second.resize(second + 1, 0);
+                }
+                tally.rejections_per_second[second] += 1;
+                if tally.first_rejection_ms.is_none() {
+                    tally.first_rejection_ms = Some(offset.as_millis() as u64);
+                    tally.rejection_message = Some(message.clone());
+                }
+                "rejected"
+            }
+            QueryTimeout(_) => "timeout",
+            _ => "other",
+        };
+        *tally.errors.entry(kind.to_string()).or_default() += 1;
+    }
+
+    fn record_queue(
+        &self,
+        running: u64,
+        waiting: u64,
+    ) {
+        let mut tally = self.tally.lock().unwrap();
+        tally.peak_running = tally.peak_running.max(running);
+        tally.peak_waiting = tally.peak_waiting.max(waiting);
+    }
+
+    pub fn report(
+        &self,
+        recovery: Option<Duration>,
+    ) -> SaturationReport {
+        let tally = self.tally.lock().unwrap();
+        let rejected = tally.errors.get("rejected").copied().unwrap_or(0);
+        SaturationReport {
+            max_queued_queries: self.max_queued_queries,
+            parallel: self.parallel,
+            saturated: rejected > 0,
+            succeeded: tally.succeeded,
+            rejected,
+            errors: tally.errors.clone(),
+            rejection_message: tally.rejection_message.clone(),
+            first_rejection_ms: tally.first_rejection_ms,
+            rejections_per_second: tally.rejections_per_second.clone(),
+            peak_running: tally.peak_running,
+            peak_waiting: tally.peak_waiting,
+            recovery_ms: recovery.map(|recovery| recovery.as_millis() as u64),
+        }
+    }
+}
+
+/// `GRAPH.CONFIG GET MAX_QUEUED_QUERIES`.
+pub async fn max_queued_queries(redis_url: &str) -> BenchmarkResult<u64> {
+    let client = redis::Client::open(redis_url)?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+    let reply: redis::Value = redis::cmd("GRAPH.CONFIG")
+        .arg("GET")
+        .arg("MAX_QUEUED_QUERIES")
+        .query_async(&mut con)
+        .await?;
+    let value = match &reply {
+        redis::Value::Array(items) => items.get(1),
+        redis::Value::Map(pairs) => pairs.first().map(|(_, value)| value),
+        _ => None,
+    };
+    match value {
+        Some(redis::Value::Int(n)) if *n >= 0 => Ok(*n as u64),
+        Some(redis::Value::BulkString(bytes)) => String::from_utf8_lossy(bytes)
+            .parse()
+            .map_err(|_| OtherError(format!("Invalid MAX_QUEUED_QUERIES {:?}", reply))),
+        _ => Err(OtherError(format!(
+            "Invalid MAX_QUEUED_QUERIES {:?}",
+            reply
+        ))),
+    }
+}
+
+/// The running and waiting queries from `GRAPH.INFO`, through their gauges.
+async fn queue_depth(redis_url: &str) -> BenchmarkResult<(u64, u64)> {
+    collect_query_info_metrics(redis_url).await?;
+    Ok((
+        FALKOR_RUNNING_REQUESTS_GAUGE.get().max(0) as u64,
+        FALKOR_WAITING_REQUESTS_GAUGE.get().max(0) as u64,
+    ))
+}
+
+/// Wait until nothing runs or waits, so the experiment starts from an empty queue.
+pub async fn wait_for_idle_queue(redis_url: &str) -> BenchmarkResult<()> {
+    let deadline = Instant::now() + IDLE_TIMEOUT;
+    loop {
+        let (running, waiting) = queue_depth(redis_url).await?;
+        if running == 0 && waiting == 0 {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(OtherError(format!(
+                "FalkorDB's queue didn't empty within {:?} ({} running, {} waiting); is another client loading it?",
+                IDLE_TIMEOUT, running, waiting
+            )));
+        }
+        sleep(QUEUE_WATCH_INTERVAL).await;
+    }
+}
+
+/// Reads the queue depth into `saturation` until [`QueueWatch::stop`].
+pub struct QueueWatch {
+    handle: JoinHandle<()>,
+    shutdown_tx: oneshot::Sender<()>,
+}
+
+impl QueueWatch {
+    pub fn spawn(
+        redis_url: String,
+        saturation: Arc<QueueSaturation>,
+    ) -> Self {
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(QUEUE_WATCH_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        match queue_depth(&redis_url).await {
+                            Ok((running, waiting)) => saturation.record_queue(running, waiting),
+                            Err(e) => warn!("Failed reading FalkorDB's queue: {}", e),
+                        }
+                    }
+                    _ = &mut shutdown_rx => return,
+                }
+            }
+        });
+        QueueWatch {
+            handle,
+            shutdown_tx,
+        }
+    }
+
+    pub async fn stop(self) {
+        drop(self.shutdown_tx);
+        let _ = self.handle.await;
+    }
+}
+
+/// Once no more queries are scheduled, probe `graph_name` with `RETURN 1` until FalkorDB accepts
+/// one; how long that took is how long the overload outlasted its cause.
+pub fn spawn_recovery_probe(
+    redis_url: String,
+    graph_name: String,
+) -> JoinHandle<Option<Duration>> {
+    tokio::spawn(async move {
+        let start = Instant::now();
+        let client = redis::Client::open(redis_url.as_str()).ok()?;
+        let mut con = client.get_multiplexed_async_connection().await.ok()?;
+        while start.elapsed() < IDLE_TIMEOUT {
+            let reply: redis::RedisResult<redis::Value> = redis::cmd("GRAPH.QUERY")
+                .arg(&graph_name)
+                .arg("RETURN 1")
+                .query_async(&mut con)
+                .await;
+            match reply {
+                Ok(_) => {
+                    info!(
+                        "FalkorDB accepted queries again after {:?}",
+                        start.elapsed()
+                    );
+                    return Some(start.elapsed());
+                }
+                Err(e) if !e.to_string().contains("Max pending queries exceeded") => {
+                    warn!("Recovery probe failed: {}", e);
+                    return None;
+                }
+                Err(_) => sleep(Duration::from_millis(10)).await,
+            }
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tallies_rejections_by_second_and_kind() {
+        assert!(QueueSaturation::new(400, 64).is_err());
+        let saturation = QueueSaturation::new(8, 64).unwrap();
+        let rejected = QueryRejected("Max pending queries exceeded".to_string());
+        let ms = Duration::from_millis;
+        saturation.record_at(ms(100), None);
+        saturation.record_at(ms(1_200), Some(&rejected));
+        saturation.record_at(ms(1_300), Some(&rejected));
+        saturation.record_at(ms(3_050), Some(&rejected));
+        saturation.record_at(ms(3_100), Some(&QueryTimeout(ms(500))));
+        saturation.record_queue(4, 8);
+        saturation.record_queue(4, 3);
+
+        let report = saturation.report(Some(ms(420)));
+        assert!(report.saturated);
+        assert_eq!((report.succeeded, report.rejected), (1, 3));
+        assert_eq!(report.first_rejection_ms, Some(1_200));
+        assert_eq!(report.rejections_per_second, [0, 2, 0, 1]);
+        assert_eq!(report.errors["timeout"], 1);
+        assert_eq!((report.peak_running, report.peak_waiting), (4, 8));
+        assert_eq!(report.recovery_ms, Some(420));
+        assert_eq!(
+            report.rejection_message.as_deref(),
+            Some("Max pending queries exceeded")
+        );
+    }
+}
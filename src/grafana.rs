@@ -0,0 +1,352 @@
+//! A Grafana dashboard for the metrics a run exported, so a Prometheus scraping the metrics
+//! endpoint can be browsed without building panels by hand.
+//!
+//! `run` writes `grafana-dashboard.json` next to `metrics.prom`; `grafana-dashboard --run-dir`
+//! rebuilds it for older results. There is one panel per metric family of `metrics.prom`:
+//! counters as rates, histograms as P50/P95/P99 and gauges as they are. Metrics with a `vendor`
+//! label are filtered on the run's vendor and other vendors' metrics are left out; the dataset
+//! names the dashboard. The Prometheus data source is a dashboard variable.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+pub const DASHBOARD_FILE: &str = "grafana-dashboard.json";
+
+const PANEL_WIDTH: u64 = 12;
+const PANEL_HEIGHT: u64 = 8;
+
+/// Name prefixes of each vendor's own metrics.
+const VENDOR_PREFIXES: [(&str, &[&str]); 3] = [
+    ("falkor", &["falkordb_", "falkor_"]),
+    ("neo4j", &["neo4j_"]),
+    ("memgraph", &["memgraph_"]),
+];
+
+/// Labels a panel doesn't break its series down by.
+const AGGREGATED_LABELS: [&str; 4] = ["vendor", "spawn_id", "dataset", "dataset_size"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MetricKind {
+    Counter,
+    Histogram,
+    Gauge,
+    Untyped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricInfo {
+    pub name: String,
+    pub help: String,
+    pub kind: MetricKind,
+    /// Label names of its samples, without a histogram's `le`.
+    pub labels: BTreeSet<String>,
+}
+
+/// The metric families of a Prometheus text exposition, by name.
+pub fn metrics_from_text(text: &str) -> Vec<MetricInfo> {
+    let mut families: BTreeMap<String, MetricInfo> = BTreeMap::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(comment) = line.strip_prefix("# ") {
+            let mut parts = comment.splitn(3, ' ');
+            let (Some(keyword), Some(name)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let rest = parts.next().unwrap_or_default();
+            let metric = family(&mut families, name);
+            match keyword {
+                "HELP" => metric.help = rest.to_string(),
+                "TYPE" => {
+                    metric.kind = match rest {
+                        "counter" => MetricKind::Counter,
+                        "histogram" => MetricKind::Histogram,
+                        "gauge" => MetricKind::Gauge,
+                        _ => MetricKind::Untyped,
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let key = line.split([' ', '\t']).next().unwrap_or(line);
+        let (sample, labels) = key.split_once('{').unwrap_or((key, ""));
+        let name = ["_bucket", "_sum", "_count"]
+            .iter()
+            .filter_map(|suffix| sample.strip_suffix(suffix))
+            .find(|base| {
+                families
+                    .get(*base)
+                    .is_some_and(|metric| metric.kind == MetricKind::Histogram)
+            })
+            .unwrap_or(sample);
+        let metric = family(&mut families, name);
+        for label in labels.trim_end_matches('}').split(',') {
+            if let Some((label, _)) = label.split_once('=') {
+                if label.trim() != "le" {
+                    metric.labels.insert(label.trim().to_string());
+                }
+            }
+        }
+    }
+    families.into_values().collect()
+}
+
+fn family<'a>(
+    families: &'a mut BTreeMap<String, MetricInfo>,
+    name: &str,
+) -> &'a mut MetricInfo {
+    families
+        .entry(name.to_string())
+        .or_insert_with(|| MetricInfo {
+            name: name.to_string(),
+            help: String::new(),
+            kind: MetricKind::Untyped,
+            labels: BTreeSet::new(),
+        })
+}
+
+/// Whether `name` belongs to a vendor other than `vendor`.
+fn other_vendor_metric(
+    vendor: &str,
+    name: &str,
+) -> bool {
+    VENDOR_PREFIXES
+        .iter()
+        .filter(|(owner, _)| *owner != vendor)
+        .flat_map(|(_, prefixes)| prefixes.iter())
+        .any(|prefix| name.starts_with(prefix))
+}
+
+fn unit(metric: &MetricInfo) -> &'static str {
+    let name = metric.name.as_str();
+    if metric.kind == MetricKind::Histogram {
+        "s"
+    } else if metric.kind == MetricKind::Counter {
+        "ops"
+    } else if name.ends_with("_us") {
+        "µs"
+    } else if name.ends_with("_bytes") || name == "memory_usage" || name.ends_with("_memory_usage")
+    {
+        "bytes"
+    } else if name.ends_with("_mb") {
+        "decmbytes"
+    } else if name.ends_with("_pct") || name.ends_with("cpu_usage") {
+        "percent"
+    } else if name.ends_with("_qps") {
+        "reqps"
+    } else {
+        "short"
+    }
+}
+
+fn panel(
+    id: u64,
+    metric: &MetricInfo,
+    vendor: &str,
+) -> Value {
+    let selector = if metric.labels.contains("vendor") {
+        format!("{{vendor=\"{}\"}}", vendor)
+    } else {
+        String::new()
+    };
+    let by: Vec<&str> = metric
+        .labels
+        .iter()
+        .map(String::as_str)
+        .filter(|label| !AGGREGATED_LABELS.contains(label))
+        .collect();
+    let legend = |prefix: &str| {
+        let parts: Vec<String> = Some(prefix.to_string())
+            .filter(|prefix| !prefix.is_empty())
+            .into_iter()
+            .chain(by.iter().map(|label| format!("{{{{{}}}}}", label)))
+            .collect();
+        if parts.is_empty() {
+            metric.name.clone()
+        } else {
+            parts.join(" ")
+        }
+    };
+    let name = &metric.name;
+    let targets: Vec<Value> = match metric.kind {
+        MetricKind::Counter => vec![(
+            format!(
+                "sum by ({}) (rate({}{}[$__rate_interval]))",
+                by.join(","),
+                name,
+                selector
+            ),
+            legend(""),
+        )],
+        MetricKind::Histogram => [("p50", 0.5), ("p95", 0.95), ("p99", 0.99)]
+            .iter()
+            .map(|(label, quantile)| {
+                let le: Vec<&str> = std::iter::once("le").chain(by.iter().copied()).collect();
+                (
+                    format!(
+                        "histogram_quantile({}, sum by ({}) (rate({}_bucket{}[$__rate_interval])))",
+                        quantile,
+                        le.join(","),
+                        name,
+                        selector
+                    ),
+                    legend(label),
+                )
+            })
+            .collect(),
+        MetricKind::Gauge | MetricKind::Untyped => {
+            vec![(format!("{}{}", name, selector), legend(""))]
+        }
+    }
+    .into_iter()
+    .enumerate()
+    .map(|(i, (expr, legend))| {
+        json!({
+            "datasource": {"type": "prometheus", "uid": "${datasource}"},
+            "expr": expr,
+            "legendFormat": legend,
+            "refId": ((b'A' + i as u8) as char).to_string(),
+        })
+    })
+    .collect();
+    let index = id - 1;
+    json!({
+        "id": id,
+        "type": "timeseries",
+        "title": name,
+        "description": metric.help,
+        "datasource": {"type": "prometheus", "uid": "${datasource}"},
+        "gridPos": {
+            "h": PANEL_HEIGHT,
+            "w": PANEL_WIDTH,
+            "x": (index % 2) * PANEL_WIDTH,
+            "y": (index / 2) * PANEL_HEIGHT,
+        },
+        "fieldConfig": {"defaults": {"unit": unit(metric)}, "overrides": []},
+        "options": {"legend": {"displayMode": "list", "placement": "bottom", "showLegend": true}},
+        "targets": targets,
+    })
+}
+
+/// A dashboard with one panel per metric of `metrics` that belongs to `vendor`, counters first,
+/// then histograms and gauges.
+pub fn dashboard(
+    vendor: &str,
+    dataset: Option<&str>,
+    metrics: &[MetricInfo],
+) -> Value {
+    let mut metrics: Vec<&MetricInfo> = metrics
+        .iter()
+        .filter(|metric| !other_vendor_metric(vendor, &metric.name))
+        .collect();
+    metrics.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+    let panels: Vec<Value> = metrics
+        .iter()
+        .enumerate()
+        .map(|(i, metric)| panel(i as u64 + 1, metric, vendor))
+        .collect();
+    let title = match dataset {
+        Some(dataset) => format!("benchmark {} {}", vendor, dataset),
+        None => format!("benchmark {}", vendor),
+    };
+    json!({
+        "title": title,
+        "uid": format!("benchmark-{}-{}", vendor, dataset.unwrap_or("any")),
+        "tags": ["benchmark", vendor],
+        "editable": true,
+        "schemaVersion": 39,
+        "time": {"from": "now-1h", "to": "now"},
+        "refresh": "10s",
+        "templating": {"list": [{
+            "name": "datasource",
+            "label": "Prometheus",
+            "type": "datasource",
+            "query": "prometheus",
+        }]},
+        "panels": panels,
+    })
+}
+
+/// `grafana-dashboard --run-dir`: the dashboard of a finished run from its meta.json and
+/// metrics.prom, written to `out` or into the run directory. Returns the path written.
+pub fn write_for_run(
+    run_dir: &Path,
+    out: Option<&Path>,
+) -> BenchmarkResult<PathBuf> {
+    let read = |name: &str| {
+        let path = run_dir.join(name);
+        std::fs::read_to_string(&path)
+            .map_err(|e| OtherError(format!("Failed reading {}: {}", path.display(), e)))
+    };
+    let meta: Value = serde_json::from_str(&read("meta.json")?)?;
+    let vendor = meta["vendor"]
+        .as_str()
+        .ok_or_else(|| OtherError(format!("No vendor in {}/meta.json", run_dir.display())))?;
+    let dashboard = dashboard(
+        vendor,
+        meta["dataset"].as_str(),
+        &metrics_from_text(&read("metrics.prom")?),
+    );
+    let out = out.map_or_else(|| run_dir.join(DASHBOARD_FILE), Path::to_path_buf);
+    std::fs::write(&out, serde_json::to_string_pretty(&dashboard)?)
+        .map_err(|e| OtherError(format!("Failed writing {}: {}", out.display(), e)))?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METRICS: &str = "\
+# HELP operations_total ops
+# TYPE operations_total counter
+operations_total{dataset=\"\",dataset_size=\"\",name=\"q1\",spawn_id=\"0\",type=\"\",vendor=\"falkor\"} 10
+# HELP falkordb_response_time_success_histogram latency
+# TYPE falkordb_response_time_success_histogram histogram
+falkordb_response_time_success_histogram_bucket{le=\"0.001\"} 3
+falkordb_response_time_success_histogram_sum 0.1
+falkordb_response_time_success_histogram_count 10
+# HELP neo4j_latency_p99_us p99
+# TYPE neo4j_latency_p99_us gauge
+neo4j_latency_p99_us 0
+# HELP throughput_qps qps
+# TYPE throughput_qps gauge
+throughput_qps{vendor=\"falkor\",window=\"1s\"} 100
+";
+
+    #[test]
+    fn panels_follow_the_exported_metrics() {
+        let metrics = metrics_from_text(METRICS);
+        let histogram = metrics
+            .iter()
+            .find(|m| m.name == "falkordb_response_time_success_histogram")
+            .unwrap();
+        assert_eq!(histogram.kind, MetricKind::Histogram);
+        assert!(histogram.labels.is_empty());
+        assert_eq!(metrics.len(), 4);
+
+        let dashboard = dashboard("falkor", Some("small"), &metrics);
+        let panels = dashboard["panels"].as_array().unwrap();
+        let titles: Vec<&str> = panels.iter().filter_map(|p| p["title"].as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "operations_total",
+                "falkordb_response_time_success_histogram",
+                "throughput_qps"
+            ]
+        );
+        assert_eq!(
+            panels[0]["targets"][0]["expr"],
+            "sum by (name,type) (rate(operations_total{vendor=\"falkor\"}[$__rate_interval]))"
+        );
+        assert_eq!(panels[1]["targets"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            panels[2]["targets"][0]["expr"],
+            "throughput_qps{vendor=\"falkor\"}"
+        );
+        assert_eq!(panels[2]["gridPos"]["y"], 8);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use tracing::warn;
+
+/// A single-label property index, e.g. `:User(id)`. Composite indexes join their properties
+/// with `,` so they never match a single-property expectation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct IndexDescriptor {
+    pub label: String,
+    pub property: String,
+}
+
+impl IndexDescriptor {
+    pub fn new(
+        label: impl Into<String>,
+        property: impl Into<String>,
+    ) -> Self {
+        Self {
+            label: label.into(),
+            property: property.into(),
+        }
+    }
+}
+
+impl fmt::Display for IndexDescriptor {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, ":{}({})", self.label, self.property)
+    }
+}
+
+/// Outcome of comparing the indexes present on the target with the dataset spec's expectation.
+/// Written to meta.json so a run on mismatched indexes is visible in the results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexParityReport {
+    pub expected: Vec<IndexDescriptor>,
+    pub present: Vec<IndexDescriptor>,
+    pub missing: Vec<IndexDescriptor>,
+    pub unexpected: Vec<IndexDescriptor>,
+    pub matched: bool,
+}
+
+impl IndexParityReport {
+    pub fn compare(
+        expected: impl IntoIterator<Item = IndexDescriptor>,
+        present: impl IntoIterator<Item = IndexDescriptor>,
+    ) -> Self {
+        let expected: BTreeSet<_> = expected.into_iter().collect();
+        let present: BTreeSet<_> = present.into_iter().collect();
+        let missing: Vec<_> = expected.difference(&present).cloned().collect();
+        let unexpected: Vec<_> = present.difference(&expected).cloned().collect();
+        Self {
+            matched: missing.is_empty() && unexpected.is_empty(),
+            expected: expected.into_iter().collect(),
+            present: present.into_iter().collect(),
+            missing,
+            unexpected,
+        }
+    }
+
+    /// Fail on a mismatch unless `allow_mismatch` is set, in which case only warn.
+    pub fn enforce(
+        &self,
+        vendor: &str,
+        allow_mismatch: bool,
+    ) -> BenchmarkResult<()> {
+        if self.matched {
+            return Ok(());
+        }
+        let message = format!(
+            "{} indexes differ from the dataset spec: missing [{}], unexpected [{}]",
+            vendor,
+            join(&self.missing),
+            join(&self.unexpected)
+        );
+        if allow_mismatch {
+            warn!(
+                "{}; continuing because --allow-index-mismatch is set",
+                message
+            );
+            Ok(())
+        } else {
+            Err(OtherError(format!(
+                "{}; refusing to run (pass --allow-index-mismatch to override)",
+                message
+            )))
+        }
+    }
+}
+
+fn join(indexes: &[IndexDescriptor]) -> String {
+    indexes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_reports_missing_and_unexpected_indexes() {
+        let expected = [
+            IndexDescriptor::new("User", "id"),
+            IndexDescriptor::new("User", "age"),
+        ];
+
+        let same = IndexParityReport::compare(expected.clone(), expected.clone());
+        assert!(same.matched);
+        assert!(same.enforce("falkor", false).is_ok());
+
+        let report = IndexParityReport::compare(
+            expected.clone(),
+            [
+                IndexDescriptor::new("User", "id"),
+                IndexDescriptor::new("Friend", "bench_capacity"),
+            ],
+        );
+        assert!(!report.matched);
+        assert_eq!(report.missing, vec![IndexDescriptor::new("User", "age")]);
+        assert_eq!(
+            report.unexpected,
+            vec![IndexDescriptor::new("Friend", "bench_capacity")]
+        );
+        let err = report.enforce("neo4j", false).unwrap_err().to_string();
+        assert!(err.contains("missing [:User(age)]"), "{}", err);
+        assert!(report.enforce("neo4j", true).is_ok());
+    }
+}
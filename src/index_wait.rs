@@ -0,0 +1,170 @@
+//! Waiting for indexes to come online. Neo4j builds an index in the background after `CREATE
+//! INDEX` returns, and FalkorDB does the same for an index created on a populated graph, so the
+//! first queries of a run or the edges of a load would otherwise race the population and measure
+//! label scans. `load` waits after creating its indexes and before loading data, and `run` waits
+//! before its queries. Every index the server lists is polled at once, and the time spent is
+//! logged and recorded in meta.json as `index_wait`.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::scenario::Vendor;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::info;
+
+/// How often the index states are polled while some aren't online.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long the indexes get to come online before the wait fails.
+pub const INDEX_ONLINE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// One index as the server reports it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexState {
+    pub name: String,
+    pub state: IndexPopulation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexPopulation {
+    Online,
+    /// Percent populated, when the server says.
+    Populating(Option<f64>),
+    Failed,
+}
+
+/// Recorded in meta.json as `index_wait`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexWaitReport {
+    pub indexes: usize,
+    pub waited_ms: u64,
+    pub polls: u32,
+}
+
+/// Poll `states` until every index is online; fail on a failed index, or once `timeout` passed
+/// with indexes still populating.
+pub async fn wait_online(
+    vendor: Vendor,
+    timeout: Duration,
+    mut states: impl AsyncFnMut() -> BenchmarkResult<Vec<IndexState>>,
+) -> BenchmarkResult<IndexWaitReport> {
+    let start = Instant::now();
+    let mut polls = 0;
+    loop {
+        let indexes = states().await?;
+        polls += 1;
+        if let Some(failed) = indexes
+            .iter()
+            .find(|index| index.state == IndexPopulation::Failed)
+        {
+            return Err(OtherError(format!(
+                "{} index {} failed to populate",
+                vendor, failed.name
+            )));
+        }
+        let populating = populating(&indexes);
+        if populating.is_empty() {
+            let report = IndexWaitReport {
+                indexes: indexes.len(),
+                waited_ms: start.elapsed().as_millis() as u64,
+                polls,
+            };
+            info!(
+                "{} {} indexes are online after {}ms",
+                vendor, report.indexes, report.waited_ms
+            );
+            return Ok(report);
+        }
+        if start.elapsed() >= timeout {
+            return Err(OtherError(format!(
+                "{} indexes still populating after {:?}: {}",
+                vendor,
+                timeout,
+                populating.join(", ")
+            )));
+        }
+        if polls == 1 {
+            info!(
+                "Waiting for {} indexes to come online: {}",
+                vendor,
+                populating.join(", ")
+            );
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// The indexes not online yet, with their progress when known.
+fn populating(indexes: &[IndexState]) -> Vec<String> {
+    indexes
+        .iter()
+        .filter_map(|index| match index.state {
+            IndexPopulation::Online | IndexPopulation::Failed => None,
+            IndexPopulation::Populating(Some(percent)) => {
+                Some(format!("{} ({:.0}%)", index.name, percent))
+            }
+            IndexPopulation::Populating(None) => Some(index.name.clone()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(
+        name: &str,
+        state: IndexPopulation,
+    ) -> IndexState {
+        IndexState {
+            name: name.to_string(),
+            state,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_until_every_index_is_online() {
+        let mut polls = vec![
+            vec![
+                index("pokec_user_id", IndexPopulation::Online),
+                index("pokec_age", IndexPopulation::Populating(Some(40.0))),
+            ],
+            vec![
+                index("pokec_user_id", IndexPopulation::Online),
+                index("pokec_age", IndexPopulation::Online),
+            ],
+        ]
+        .into_iter();
+        let report = wait_online(Vendor::Neo4j, INDEX_ONLINE_TIMEOUT, async || {
+            Ok(polls.next().unwrap())
+        })
+        .await
+        .unwrap();
+        assert_eq!((report.indexes, report.polls), (2, 2));
+        assert_eq!(report.waited_ms, POLL_INTERVAL.as_millis() as u64);
+
+        let err = wait_online(Vendor::Neo4j, Duration::from_secs(1), async || {
+            Ok(vec![index(
+                "pokec_age",
+                IndexPopulation::Populating(Some(40.0)),
+            )])
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("still populating after 1s: pokec_age (40%)"),
+            "{}",
+            err
+        );
+
+        let err = wait_online(Vendor::Falkor, INDEX_ONLINE_TIMEOUT, async || {
+            Ok(vec![index(":User(age)", IndexPopulation::Failed)])
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("index :User(age) failed"), "{}", err);
+    }
+}
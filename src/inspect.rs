@@ -0,0 +1,402 @@
+//! `benchmark inspect`: the graph a vendor actually holds, so a comparison only runs on vendors
+//! loaded with the same data.
+//!
+//! Every statistic comes from plain Cypher returning `key`/`value` rows, so all vendors are
+//! measured by the same queries; only the index listing is vendor-specific (see
+//! [`crate::index_parity`]). The queries scan the whole graph and are meant for a quiet server.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::index_parity::IndexDescriptor;
+use crate::scenario::Vendor;
+use crate::utils::format_number;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+const LABEL_COUNTS: &str = "MATCH (n) UNWIND labels(n) AS key RETURN key, count(*) AS value";
+const EDGE_TYPE_COUNTS: &str = "MATCH ()-[r]->() RETURN type(r) AS key, count(*) AS value";
+const OUT_DEGREES: &str = "MATCH (n) OPTIONAL MATCH (n)-[r]->() WITH n, count(r) AS degree \
+                           RETURN toString(degree) AS key, count(*) AS value";
+
+/// Default for `aggregate --graph-size-tolerance`.
+pub const DEFAULT_GRAPH_SIZE_TOLERANCE: f64 = 0.001;
+
+/// How far the graph `run` finds may be from the one its queries file was generated for; loading
+/// the fixtures and a dataset's id gaps move the counts a little.
+pub const QUERIES_GRAPH_TOLERANCE: f64 = 0.05;
+
+/// Node and relationship counts `run` takes before the measured phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphSize {
+    pub nodes: u64,
+    pub relationships: u64,
+}
+
+impl GraphSize {
+    /// `None` when both counts are within `tolerance` (a fraction of the larger one) of
+    /// `other`'s, otherwise both pairs of counts.
+    pub fn mismatch(
+        &self,
+        other: &GraphSize,
+        tolerance: f64,
+    ) -> Option<String> {
+        let differs = |a: u64, b: u64| a.abs_diff(b) as f64 > a.max(b) as f64 * tolerance;
+        if !differs(self.nodes, other.nodes) && !differs(self.relationships, other.relationships) {
+            return None;
+        }
+        Some(format!(
+            "{} vs {} nodes, {} vs {} relationships",
+            format_number(self.nodes),
+            format_number(other.nodes),
+            format_number(self.relationships),
+            format_number(other.relationships)
+        ))
+    }
+
+    /// Check the graph a queries file was generated for (`self`) against the `loaded` one. A
+    /// smaller loaded graph is an error, as the queries would look up ids it doesn't have; a
+    /// larger one only a warning, as they then cover part of it.
+    pub fn check_loaded(
+        &self,
+        loaded: &GraphSize,
+    ) -> Result<Option<String>, String> {
+        let Some(mismatch) = self.mismatch(loaded, QUERIES_GRAPH_TOLERANCE) else {
+            return Ok(None);
+        };
+        if (loaded.nodes as f64) < self.nodes as f64 * (1.0 - QUERIES_GRAPH_TOLERANCE) {
+            Err(format!(
+                "the queries file was generated for a larger graph than the loaded one ({}); generate queries for the loaded dataset",
+                mismatch
+            ))
+        } else {
+            Ok(Some(format!(
+                "the loaded graph is larger than the one the queries file was generated for ({}); the queries cover part of it",
+                mismatch
+            )))
+        }
+    }
+}
+
+/// Out-degree over every node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DegreeSummary {
+    pub nodes: u64,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p99: u64,
+}
+
+impl DegreeSummary {
+    /// From `(degree, nodes with that degree)` pairs, in any order.
+    pub fn from_histogram(counts: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        let mut counts: Vec<(u64, u64)> = counts.into_iter().filter(|(_, n)| *n > 0).collect();
+        counts.sort();
+        let nodes: u64 = counts.iter().map(|(_, n)| n).sum();
+        if nodes == 0 {
+            return DegreeSummary::default();
+        }
+        let edges: u64 = counts.iter().map(|(degree, n)| degree * n).sum();
+        let percentile = |p: f64| {
+            let rank = ((nodes as f64 * p).ceil() as u64).max(1);
+            let mut seen = 0;
+            for (degree, n) in &counts {
+                seen += n;
+                if seen >= rank {
+                    return *degree;
+                }
+            }
+            counts[counts.len() - 1].0
+        };
+        DegreeSummary {
+            nodes,
+            min: counts[0].0,
+            max: counts[counts.len() - 1].0,
+            mean: edges as f64 / nodes as f64,
+            p50: percentile(0.50),
+            p99: percentile(0.99),
+        }
+    }
+}
+
+/// How many entities carry a property, and how many distinct values it takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropertyStats {
+    pub present: u64,
+    pub distinct: u64,
+}
+
+/// Written by `inspect` as JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InspectReport {
+    pub vendor: String,
+    pub nodes_by_label: BTreeMap<String, u64>,
+    pub edges_by_type: BTreeMap<String, u64>,
+    pub out_degree: DegreeSummary,
+    /// Keyed `Label.property` for nodes and `TYPE.property` for relationships.
+    pub properties: BTreeMap<String, PropertyStats>,
+    pub indexes: Vec<IndexDescriptor>,
+}
+
+impl InspectReport {
+    /// Gather the report; `key_counts` runs one query and returns its `key`/`value` rows.
+    pub async fn collect(
+        vendor: Vendor,
+        mut key_counts: impl AsyncFnMut(&str) -> BenchmarkResult<Vec<(String, i64)>>,
+        mut indexes: Vec<IndexDescriptor>,
+    ) -> BenchmarkResult<Self> {
+        let nodes_by_label = counts(key_counts(LABEL_COUNTS).await?);
+        let edges_by_type = counts(key_counts(EDGE_TYPE_COUNTS).await?);
+
+        let mut degrees = Vec::new();
+        for (degree, nodes) in key_counts(OUT_DEGREES).await? {
+            let degree = degree
+                .parse()
+                .map_err(|_| OtherError(format!("out-degree '{}' is not a number", degree)))?;
+            degrees.push((degree, nodes.max(0) as u64));
+        }
+
+        let patterns = nodes_by_label
+            .keys()
+            .map(|label| (label, format!("(e:{})", escape(label))))
+            .chain(
+                edges_by_type
+                    .keys()
+                    .map(|edge_type| (edge_type, format!("()-[e:{}]->()", escape(edge_type)))),
+            );
+        let mut properties = BTreeMap::new();
+        for (owner, pattern) in patterns {
+            let keys = key_counts(&format!(
+                "MATCH {} UNWIND keys(e) AS key RETURN key, count(*) AS value",
+                pattern
+            ))
+            .await?;
+            for (property, present) in keys {
+                let distinct = key_counts(&format!(
+                    "MATCH {} WHERE e.{} IS NOT NULL \
+                     RETURN 'distinct' AS key, count(DISTINCT e.{}) AS value",
+                    pattern,
+                    escape(&property),
+                    escape(&property)
+                ))
+                .await?
+                .first()
+                .map_or(0, |(_, value)| (*value).max(0) as u64);
+                properties.insert(
+                    format!("{}.{}", owner, property),
+                    PropertyStats {
+                        present: present.max(0) as u64,
+                        distinct,
+                    },
+                );
+            }
+        }
+
+        indexes.sort();
+        Ok(InspectReport {
+            vendor: vendor.to_string(),
+            nodes_by_label,
+            edges_by_type,
+            out_degree: DegreeSummary::from_histogram(degrees),
+            properties,
+            indexes,
+        })
+    }
+
+    /// Where this graph differs from `other`'s, one line each; empty when they hold the same
+    /// graph.
+    pub fn differences(
+        &self,
+        other: &InspectReport,
+    ) -> Vec<String> {
+        let mut differences = Vec::new();
+        diff_maps(
+            "nodes",
+            &self.nodes_by_label,
+            &other.nodes_by_label,
+            &mut differences,
+        );
+        diff_maps(
+            "edges",
+            &self.edges_by_type,
+            &other.edges_by_type,
+            &mut differences,
+        );
+        if self.out_degree != other.out_degree {
+            differences.push(format!(
+                "out-degree: {:?} vs {:?}",
+                self.out_degree, other.out_degree
+            ));
+        }
+        let properties = |report: &InspectReport| {
+            report
+                .properties
+                .iter()
+                .map(|(name, stats)| {
+                    (
+                        name.clone(),
+                        format!("{} present, {} distinct", stats.present, stats.distinct),
+                    )
+                })
+                .collect::<BTreeMap<_, _>>()
+        };
+        diff_maps(
+            "property",
+            &properties(self),
+            &properties(other),
+            &mut differences,
+        );
+        if self.indexes != other.indexes {
+            let list = |indexes: &[IndexDescriptor]| {
+                indexes
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            differences.push(format!(
+                "indexes: [{}] vs [{}]",
+                list(&self.indexes),
+                list(&other.indexes)
+            ));
+        }
+        differences
+    }
+}
+
+fn counts(rows: Vec<(String, i64)>) -> BTreeMap<String, u64> {
+    let mut counts = BTreeMap::new();
+    for (key, value) in rows {
+        *counts.entry(key).or_insert(0) += value.max(0) as u64;
+    }
+    counts
+}
+
+fn escape(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+fn diff_maps<V: PartialEq + Display>(
+    what: &str,
+    ours: &BTreeMap<String, V>,
+    theirs: &BTreeMap<String, V>,
+    differences: &mut Vec<String>,
+) {
+    let missing = "missing".to_string();
+    for key in ours
+        .keys()
+        .chain(theirs.keys().filter(|key| !ours.contains_key(*key)))
+    {
+        let (a, b) = (ours.get(key), theirs.get(key));
+        if a != b {
+            differences.push(format!(
+                "{} {}: {} vs {}",
+                what,
+                key,
+                a.map_or(missing.clone(), ToString::to_string),
+                b.map_or(missing.clone(), ToString::to_string)
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_sizes_match_within_the_tolerance() {
+        let size = |nodes, relationships| GraphSize {
+            nodes,
+            relationships,
+        };
+        assert_eq!(size(1000, 5000).mismatch(&size(1000, 5000), 0.0), None);
+        assert_eq!(size(1000, 5000).mismatch(&size(999, 5004), 0.001), None);
+        assert_eq!(
+            size(1000, 5000)
+                .mismatch(&size(1000, 5006), 0.001)
+                .as_deref(),
+            Some("1,000 vs 1,000 nodes, 5,000 vs 5,006 relationships")
+        );
+
+        let small = size(10_000, 121_716);
+        let large = size(1_632_803, 30_622_564);
+        assert_eq!(small.check_loaded(&size(9_998, 121_716)), Ok(None));
+        assert!(large.check_loaded(&small).is_err());
+        assert!(small.check_loaded(&large).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn collects_the_same_report_from_the_same_rows() {
+        let rows = |query: &str| -> Vec<(String, i64)> {
+            let row = |key: &str, value| (key.to_string(), value);
+            if query == LABEL_COUNTS {
+                vec![row("User", 4)]
+            } else if query == EDGE_TYPE_COUNTS {
+                vec![row("Friend", 5)]
+            } else if query == OUT_DEGREES {
+                vec![row("2", 2), row("0", 1), row("1", 1)]
+            } else if query.contains("keys(e)") && query.starts_with("MATCH (e:`User`)") {
+                vec![row("id", 4), row("age", 3)]
+            } else if query.contains("keys(e)") {
+                vec![]
+            } else if query.contains("e.`age`") {
+                vec![row("distinct", 2)]
+            } else {
+                vec![row("distinct", 4)]
+            }
+        };
+        let collect = async |indexes: Vec<IndexDescriptor>| {
+            InspectReport::collect(Vendor::Falkor, async |query: &str| Ok(rows(query)), indexes)
+                .await
+                .unwrap()
+        };
+
+        let falkor = collect(vec![
+            IndexDescriptor::new("User", "id"),
+            IndexDescriptor::new("User", "age"),
+        ])
+        .await;
+        assert_eq!(falkor.nodes_by_label["User"], 4);
+        assert_eq!(
+            falkor.out_degree,
+            DegreeSummary {
+                nodes: 4,
+                min: 0,
+                max: 2,
+                mean: 1.25,
+                p50: 1,
+                p99: 2,
+            }
+        );
+        assert_eq!(
+            falkor.properties["User.age"],
+            PropertyStats {
+                present: 3,
+                distinct: 2
+            }
+        );
+        // Index order from the vendor doesn't matter.
+        let same = collect(vec![
+            IndexDescriptor::new("User", "age"),
+            IndexDescriptor::new("User", "id"),
+        ])
+        .await;
+        assert!(falkor.differences(&same).is_empty());
+
+        let mut other = same.clone();
+        other.nodes_by_label.insert("User".to_string(), 3);
+        other.properties.remove("User.age");
+        other.indexes.pop();
+        assert_eq!(
+            falkor.differences(&other),
+            vec![
+                "nodes User: 4 vs 3".to_string(),
+                "property User.age: 3 present, 2 distinct vs missing".to_string(),
+                "indexes: [:User(age), :User(id)] vs [:User(age)]".to_string(),
+            ]
+        );
+    }
+}
@@ -1,33 +1,93 @@
+// The `lazy_static!` block of metrics below outgrows the default macro recursion limit.
+#![recursion_limit = "256"]
+
 use lazy_static::lazy_static;
 use prometheus::register_counter_vec;
 use prometheus::register_gauge_vec;
 use prometheus::register_histogram;
+use prometheus::register_histogram_vec;
 use prometheus::register_int_counter;
+use prometheus::register_int_counter_vec;
 use prometheus::register_int_gauge;
 use prometheus::register_int_gauge_vec;
 use prometheus::CounterVec;
 use prometheus::GaugeVec;
 use prometheus::Histogram;
+use prometheus::HistogramVec;
 use prometheus::IntCounter;
+use prometheus::IntCounterVec;
 use prometheus::IntGauge;
 use prometheus::IntGaugeVec;
 
+pub mod aggregator;
+pub mod artifact_store;
+pub mod backup_manifest;
+pub mod bolt_ready;
+pub mod bounded_writes;
+pub mod cache_control;
+pub mod chaos;
+pub mod circuit_breaker;
+pub mod clean;
 pub mod cli;
+pub mod compact_query;
+pub mod compression;
+pub mod config_file;
+pub mod credentials;
+pub mod cypher_export;
 pub mod data_prep;
+pub mod dataset_graph;
+pub mod database_config;
+pub mod distributed;
+pub mod doctor;
 pub mod error;
+pub mod error_budget;
 pub mod falkor;
+pub mod grafana;
+pub mod index_parity;
+pub mod index_wait;
+pub mod inspect;
 pub mod memgraph;
 pub mod memgraph_client;
+pub mod metrics_collector;
+pub mod metrics_self_test;
 pub mod neo4j;
 pub mod neo4j_client;
+pub mod neo4j_gc;
+pub mod neo4j_memory;
+pub mod os_process;
+pub mod persistence_events;
+pub mod phases;
 pub mod process_monitor;
+pub mod progress;
 pub mod prometheus_endpoint;
 pub mod prometheus_metrics;
 pub mod queries_repository;
 pub mod query;
+pub mod query_generation;
+pub mod query_plans;
+pub mod query_timeout;
+pub mod read_only;
+pub mod resource_limits;
+pub mod resource_series;
+pub mod result_size;
+pub mod results_layout;
+pub mod run_control;
+pub mod run_metrics;
+pub mod run_status;
+pub mod run_summary;
+pub mod runner;
+pub mod sanitizer;
 pub mod scenario;
 pub mod scheduler;
+pub mod server_output;
+pub mod significance;
+pub mod simulate;
+pub mod slo_search;
+pub mod slow_queries;
+pub mod suite;
+pub mod sweep;
 pub mod synthetic;
+pub mod throughput;
 pub mod utils;
 
 // Compile-check the Rust code examples in the Markdown docs as doctests (`cargo test`). Only
@@ -79,6 +139,11 @@ lazy_static! {
         "The number of request that waiting to run by the falkordb server",
     )
     .unwrap();
+    pub static ref FALKOR_REJECTED_QUERIES_TOTAL: IntCounter = register_int_counter!(
+        "falkordb_rejected_queries_total",
+        "Queries FalkorDB rejected because MAX_QUEUED_QUERIES were already waiting"
+    )
+    .unwrap();
     pub static ref FALKOR_NODES_GAUGE: IntGauge = register_int_gauge!(
         "falkordb_nodes_total",
         "Total number of nodes in falkordb graph",
@@ -123,10 +188,72 @@ lazy_static! {
         "offset of the message from the deadline",
     )
     .unwrap();
+    // End-to-end latency of successful multi-statement transactions (all statements + commit),
+    // measured from the intended schedule time like the per-vendor response time histograms.
+    pub static ref TRANSACTION_DURATION_HISTOGRAM: HistogramVec = register_histogram_vec!(
+        "transaction_response_time_success_histogram",
+        "Response time histogram of the successful transactions",
+        &["vendor", "name"],
+        vec![0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,]
+    )
+    .unwrap();
+    // Rows each successful query returned, by query; the buckets are the result-size bins of
+    // `result_size`.
+    pub static ref QUERY_RESULT_ROWS_HISTOGRAM: HistogramVec = register_histogram_vec!(
+        "query_result_rows",
+        "Rows returned by the successful queries",
+        &["vendor", "name"],
+        crate::result_size::ROW_BUCKETS.to_vec()
+    )
+    .unwrap();
+    // The per-vendor success histograms split by query type (read or write).
+    pub static ref SUCCESS_REQUESTS_DURATION_BY_TYPE_HISTOGRAM: HistogramVec = register_histogram_vec!(
+        "response_time_success_by_type_histogram",
+        "Response time histogram of the successful requests per query type",
+        &["vendor", "type"],
+        vec![0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,]
+    )
+    .unwrap();
     pub static ref CPU_USAGE_GAUGE: IntGauge =
         register_int_gauge!("cpu_usage", "CPU usage percentage").unwrap();
     pub static ref MEM_USAGE_GAUGE: IntGauge =
         register_int_gauge!("memory_usage", "Memory usage in bytes").unwrap();
+    // Disk I/O of the managed server process and network traffic of the whole host, sampled by
+    // the process metrics reporters. Counters, so `rate()` gives the throughput.
+    pub static ref PROCESS_DISK_READ_BYTES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "process_disk_read_bytes_total",
+        "Bytes the server process read from disk",
+        &["vendor"]
+    )
+    .unwrap();
+    pub static ref PROCESS_DISK_WRITTEN_BYTES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "process_disk_written_bytes_total",
+        "Bytes the server process wrote to disk",
+        &["vendor"]
+    )
+    .unwrap();
+    pub static ref PERSISTENCE_EVENTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "persistence_events_total",
+        "Snapshots (Redis BGSAVE, AOF rewrites, Memgraph snapshots) the server started during the measured phase",
+        &["vendor", "kind"]
+    )
+    .unwrap();
+    pub static ref PERSISTENCE_IN_PROGRESS: IntGaugeVec = register_int_gauge_vec!(
+        "persistence_in_progress",
+        "1 while the server writes a snapshot of this kind",
+        &["vendor", "kind"]
+    )
+    .unwrap();
+    pub static ref NETWORK_RECEIVED_BYTES_TOTAL: IntCounter = register_int_counter!(
+        "network_received_bytes_total",
+        "Bytes received on all network interfaces of the host"
+    )
+    .unwrap();
+    pub static ref NETWORK_TRANSMITTED_BYTES_TOTAL: IntCounter = register_int_counter!(
+        "network_transmitted_bytes_total",
+        "Bytes transmitted on all network interfaces of the host"
+    )
+    .unwrap();
     pub static ref FALKOR_CPU_USAGE_GAUGE: IntGauge = register_int_gauge!(
         "falkor_cpu_usage",
         "CPU usage percentage for the falkordb process"
@@ -159,6 +286,19 @@ lazy_static! {
         "JVM non-heap used bytes reported by java.lang:type=Memory"
     )
     .unwrap();
+    // Neo4j garbage collection during the run, per collector (via JMX, see neo4j_gc).
+    pub static ref NEO4J_GC_COLLECTIONS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "neo4j_gc_collections_total",
+        "JVM garbage collections during the run reported by java.lang:type=GarbageCollector",
+        &["collector"]
+    )
+    .unwrap();
+    pub static ref NEO4J_GC_PAUSE_SECONDS_TOTAL: CounterVec = register_counter_vec!(
+        "neo4j_gc_pause_seconds_total",
+        "JVM garbage collection time during the run reported by java.lang:type=GarbageCollector",
+        &["collector"]
+    )
+    .unwrap();
 
     // Neo4j dataset footprint estimate (bytes) based on Neo4j sizing guidelines.
     // This is intended as a fallback when store sizing and JMX are unavailable (e.g. external endpoints).
@@ -228,6 +368,38 @@ lazy_static! {
     )
     .unwrap();
 
+    // FalkorDB: Redis-level fields from `INFO`, since GRAPH.MEMORY misses the server's own overhead.
+    pub static ref FALKOR_REDIS_USED_MEMORY_BYTES: IntGauge = register_int_gauge!(
+        "falkordb_redis_used_memory_bytes",
+        "Bytes allocated by Redis (INFO used_memory)"
+    )
+    .unwrap();
+    pub static ref FALKOR_REDIS_USED_MEMORY_RSS_BYTES: IntGauge = register_int_gauge!(
+        "falkordb_redis_used_memory_rss_bytes",
+        "Resident set size of the Redis process (INFO used_memory_rss)"
+    )
+    .unwrap();
+    pub static ref FALKOR_REDIS_CONNECTED_CLIENTS: IntGauge = register_int_gauge!(
+        "falkordb_redis_connected_clients",
+        "Client connections open on the Redis server (INFO connected_clients)"
+    )
+    .unwrap();
+    pub static ref FALKOR_REDIS_INSTANTANEOUS_OPS_PER_SEC: IntGauge = register_int_gauge!(
+        "falkordb_redis_instantaneous_ops_per_sec",
+        "Commands processed per second (INFO instantaneous_ops_per_sec)"
+    )
+    .unwrap();
+    pub static ref FALKOR_REDIS_REJECTED_CONNECTIONS: IntGauge = register_int_gauge!(
+        "falkordb_redis_rejected_connections",
+        "Connections rejected because of maxclients since server start (INFO rejected_connections)"
+    )
+    .unwrap();
+    pub static ref FALKOR_REDIS_EVICTED_KEYS: IntGauge = register_int_gauge!(
+        "falkordb_redis_evicted_keys",
+        "Keys evicted because of maxmemory since server start (INFO evicted_keys)"
+    )
+    .unwrap();
+
     // Memgraph: derived from `SHOW STORAGE INFO`.
     pub static ref MEMGRAPH_STORAGE_MEMORY_RES_BYTES: IntGauge = register_int_gauge!(
         "memgraph_storage_memory_res_bytes",
@@ -352,4 +524,241 @@ lazy_static! {
         &["query"]
     )
     .unwrap();
+
+    // Achieved client-side throughput over the last 1s / 10s (see `throughput`).
+    pub static ref THROUGHPUT_QPS: GaugeVec = register_gauge_vec!(
+        "throughput_qps",
+        "Completed queries per second over a sliding window (computed in-process)",
+        &["vendor", "window"]
+    )
+    .unwrap();
+}
+
+// Metrics added after the block above hit the macro's recursion limit.
+lazy_static! {
+    pub static ref FALKOR_QUERY_TIMEOUT_RATE_PCT: GaugeVec = register_gauge_vec!(
+        "falkordb_query_timeout_rate_pct",
+        "Timeout rate per query in percent (computed in-process)",
+        &["query"]
+    )
+    .unwrap();
+    pub static ref NEO4J_QUERY_TIMEOUT_RATE_PCT: GaugeVec = register_gauge_vec!(
+        "neo4j_query_timeout_rate_pct",
+        "Timeout rate per query in percent (computed in-process)",
+        &["query"]
+    )
+    .unwrap();
+    // Queries that hit their `--query-timeout` (or catalog override), per vendor and query.
+    pub static ref QUERY_TIMEOUTS: IntGaugeVec = register_int_gauge_vec!(
+        "query_timeouts",
+        "Timed-out executions per query (computed in-process)",
+        &["vendor", "query"]
+    )
+    .unwrap();
+    // Execution time FalkorDB reports with each reply, excluding network and client overhead.
+    pub static ref FALKOR_SERVER_EXECUTION_TIME_HISTOGRAM: Histogram = register_histogram!(
+        "falkordb_server_execution_time_histogram",
+        "Server-side execution time histogram of the successful requests",
+        vec![0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,]
+    )
+    .unwrap();
+    pub static ref FALKOR_SERVER_EXECUTION_P50_US: IntGauge = register_int_gauge!(
+        "falkordb_server_execution_p50_us",
+        "P50 server-side execution time in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref FALKOR_SERVER_EXECUTION_P95_US: IntGauge = register_int_gauge!(
+        "falkordb_server_execution_p95_us",
+        "P95 server-side execution time in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref FALKOR_SERVER_EXECUTION_P99_US: IntGauge = register_int_gauge!(
+        "falkordb_server_execution_p99_us",
+        "P99 server-side execution time in microseconds (computed in-process)"
+    )
+    .unwrap();
+    // `run --reconnect-every`: opening a connection up to its first answered round trip.
+    pub static ref CONNECTION_ESTABLISH_DURATION_HISTOGRAM: HistogramVec = register_histogram_vec!(
+        "connection_establish_duration_histogram",
+        "Time to open a new connection and complete a first round trip on it",
+        &["vendor"],
+        vec![0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,]
+    )
+    .unwrap();
+    // Client latency of all queries of a group (oltp, analytics, algorithm) together.
+    pub static ref QUERY_GROUP_LATENCY_US: IntGaugeVec = register_int_gauge_vec!(
+        "query_group_latency_us",
+        "Latency percentiles per query group in microseconds (computed in-process)",
+        &["vendor", "group", "pct"]
+    )
+    .unwrap();
+    pub static ref QUERY_TYPE_LATENCY_US: IntGaugeVec = register_int_gauge_vec!(
+        "query_type_latency_us",
+        "Latency percentiles per query type (read or write) in microseconds (computed in-process)",
+        &["vendor", "type", "pct"]
+    )
+    .unwrap();
+    // Normalized memory, always in bytes whatever unit the source reports in. The aggregator
+    // reads only these; the older `*_memory_usage`, `_mb` and storage gauges stay for existing
+    // dashboards.
+    // `<vendor>_process_memory_bytes`: RSS of the server process, local or `--attach-*`ed.
+    // `<vendor>_reported_memory_bytes`: what the server reports through its query interface.
+    pub static ref FALKOR_PROCESS_MEMORY_BYTES: IntGauge = register_int_gauge!(
+        "falkor_process_memory_bytes",
+        "Resident memory in bytes of the falkordb process"
+    )
+    .unwrap();
+    pub static ref FALKOR_REPORTED_MEMORY_BYTES: IntGauge = register_int_gauge!(
+        "falkor_reported_memory_bytes",
+        "Graph memory in bytes reported by GRAPH.MEMORY USAGE"
+    )
+    .unwrap();
+    pub static ref NEO4J_PROCESS_MEMORY_BYTES: IntGauge = register_int_gauge!(
+        "neo4j_process_memory_bytes",
+        "Resident memory in bytes of the neo4j process"
+    )
+    .unwrap();
+    pub static ref NEO4J_REPORTED_MEMORY_BYTES: IntGauge = register_int_gauge!(
+        "neo4j_reported_memory_bytes",
+        "JVM heap plus non-heap used bytes reported through dbms.queryJmx"
+    )
+    .unwrap();
+    pub static ref MEMGRAPH_PROCESS_MEMORY_BYTES: IntGauge = register_int_gauge!(
+        "memgraph_process_memory_bytes",
+        "Resident memory in bytes of the memgraph process"
+    )
+    .unwrap();
+    pub static ref MEMGRAPH_REPORTED_MEMORY_BYTES: IntGauge = register_int_gauge!(
+        "memgraph_reported_memory_bytes",
+        "Tracked memory in bytes reported by SHOW STORAGE INFO, else its resident memory"
+    )
+    .unwrap();
+    // FalkorDB dataset footprint: GRAPH.MEMORY USAGE right after `load`, kept with the dump, the
+    // counterpart of the Neo4j store size and the Memgraph base dataset estimate.
+    pub static ref FALKOR_BASE_DATASET_BYTES: IntGauge = register_int_gauge!(
+        "falkordb_base_dataset_bytes",
+        "Graph memory in bytes reported by GRAPH.MEMORY USAGE right after the dataset was loaded"
+    )
+    .unwrap();
+    // Whether a throughput shortfall is the client's: messages queued for a free worker, and how
+    // the workers' time splits between running queries and waiting for the next one.
+    pub static ref SCHEDULER_QUEUE_DEPTH: IntGaugeVec = register_int_gauge_vec!(
+        "scheduler_queue_depth",
+        "Messages waiting in the scheduler channel for a worker",
+        &["vendor"]
+    )
+    .unwrap();
+    pub static ref WORKER_BUSY_SECONDS: CounterVec = register_counter_vec!(
+        "worker_busy_seconds_total",
+        "Time workers spent running queries, from their scheduled start to the reply",
+        &["vendor"]
+    )
+    .unwrap();
+    pub static ref WORKER_IDLE_SECONDS: CounterVec = register_counter_vec!(
+        "worker_idle_seconds_total",
+        "Time workers spent waiting for a message, its scheduled start or think time",
+        &["vendor"]
+    )
+    .unwrap();
+    pub static ref MEMGRAPH_STORAGE_MODE: IntGaugeVec = register_int_gauge_vec!(
+        "memgraph_storage_mode",
+        "1 for the storage mode Memgraph reports in SHOW STORAGE INFO",
+        &["mode"]
+    )
+    .unwrap();
+    pub static ref SCHEDULER_DROPPED_MESSAGES: IntCounter = register_int_counter!(
+        "scheduler_dropped_messages_total",
+        "Messages dropped by --overload-policy drop because the channel was full"
+    )
+    .unwrap();
+    pub static ref WORKER_ERROR_BACKOFF_SECONDS: CounterVec = register_counter_vec!(
+        "worker_error_backoff_seconds_total",
+        "Time workers spent backing off after failed queries (--error-backoff)",
+        &["vendor"]
+    )
+    .unwrap();
+    pub static ref WORKERS_FAILING: IntGaugeVec = register_int_gauge_vec!(
+        "workers_failing",
+        "Workers whose most recent query failed",
+        &["vendor"]
+    )
+    .unwrap();
+    pub static ref CIRCUIT_BREAKER_OPEN: IntGauge = register_int_gauge!(
+        "circuit_breaker_open",
+        "1 while --circuit-breaker has paused dispatch"
+    )
+    .unwrap();
+    pub static ref CIRCUIT_BREAKER_OPEN_SECONDS: IntCounter = register_int_counter!(
+        "circuit_breaker_open_seconds_total",
+        "Whole seconds --circuit-breaker kept dispatch paused"
+    )
+    .unwrap();
+    pub static ref CIRCUIT_BREAKER_SHED_QUERIES: IntCounter = register_int_counter!(
+        "circuit_breaker_shed_queries_total",
+        "Queries due while --circuit-breaker was open, shed instead of sent"
+    )
+    .unwrap();
+    pub static ref FALKOR_LOG_LINES: IntCounterVec = register_int_counter_vec!(
+        "falkordb_log_lines_total",
+        "Error and slow-query lines FalkorDB wrote to its log during the measured phase",
+        &["kind"]
+    )
+    .unwrap();
+    pub static ref PHASE_LATENCY_US: IntGaugeVec = register_int_gauge_vec!(
+        "phase_latency_us",
+        "Latency percentiles per phase of run --phases in microseconds (computed in-process)",
+        &["vendor", "phase", "pct"]
+    )
+    .unwrap();
+    pub static ref RUN_PHASE: IntGaugeVec = register_int_gauge_vec!(
+        "run_phase",
+        "1 for the phase of run --phases the scheduler is in, 0 for the others",
+        &["phase"]
+    )
+    .unwrap();
+    pub static ref FALKOR_LATENCY_P99_9_US: IntGauge = register_int_gauge!(
+        "falkordb_latency_p99_9_us",
+        "P99.9 latency in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref FALKOR_LATENCY_P99_99_US: IntGauge = register_int_gauge!(
+        "falkordb_latency_p99_99_us",
+        "P99.99 latency in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref FALKOR_LATENCY_MAX_US: IntGauge = register_int_gauge!(
+        "falkordb_latency_max_us",
+        "Maximum latency in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref NEO4J_LATENCY_P99_9_US: IntGauge = register_int_gauge!(
+        "neo4j_latency_p99_9_us",
+        "P99.9 latency in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref NEO4J_LATENCY_P99_99_US: IntGauge = register_int_gauge!(
+        "neo4j_latency_p99_99_us",
+        "P99.99 latency in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref NEO4J_LATENCY_MAX_US: IntGauge = register_int_gauge!(
+        "neo4j_latency_max_us",
+        "Maximum latency in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref MEMGRAPH_LATENCY_P99_9_US: IntGauge = register_int_gauge!(
+        "memgraph_latency_p99_9_us",
+        "P99.9 latency in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref MEMGRAPH_LATENCY_P99_99_US: IntGauge = register_int_gauge!(
+        "memgraph_latency_p99_99_us",
+        "P99.99 latency in microseconds (computed in-process)"
+    )
+    .unwrap();
+    pub static ref MEMGRAPH_LATENCY_MAX_US: IntGauge = register_int_gauge!(
+        "memgraph_latency_max_us",
+        "Maximum latency in microseconds (computed in-process)"
+    )
+    .unwrap();
 }
@@ -1,21 +1,30 @@
+use crate::backup_manifest::{check_before_restore, BackupManifest, MANIFEST_FILE};
+use crate::bolt_ready::BoltReadiness;
+use crate::database_config::DEFAULT_MEMGRAPH_DATABASE;
 use crate::error::BenchmarkError::{FailedToSpawnProcessError, OtherError};
 use crate::error::BenchmarkResult;
 use crate::memgraph_client::MemgraphClient;
-use crate::scenario::Spec;
-use crate::utils::{create_directory_if_not_exists, spawn_command};
+use crate::resource_limits::ServerLimits;
+use crate::scenario::{Spec, Vendor};
+use crate::server_output::ServerOutput;
+use crate::utils::{create_directory_if_not_exists, file_exists, kill_process, spawn_command};
 use crate::{
     prometheus_metrics, CPU_USAGE_GAUGE, MEMGRAPH_CPU_USAGE_GAUGE, MEMGRAPH_MEM_USAGE_GAUGE,
-    MEM_USAGE_GAUGE,
+    MEMGRAPH_PROCESS_MEMORY_BYTES, MEM_USAGE_GAUGE,
 };
 use std::env;
 use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::process::Output;
 use std::process::{Child, Command};
-use std::time::Duration;
-use sysinfo::{Pid, System};
+use sysinfo::{Pid, ProcessStatus, System};
+use tokio::fs;
 use tokio::task::JoinHandle;
 use tracing::{info, trace};
 
+/// The snapshot a dump keeps in the spec's backup directory.
+pub const SNAPSHOT_FILE: &str = "memgraph.snapshot";
+
 pub struct Memgraph {
     uri: String,
     user: String,
@@ -23,6 +32,8 @@ pub struct Memgraph {
     memgraph_home: String,
     prom_shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
     prom_process_handle: Option<JoinHandle<()>>,
+    server_limits: ServerLimits,
+    server_output: Option<ServerOutput>,
 }
 
 impl Default for Memgraph {
@@ -31,10 +42,14 @@ impl Default for Memgraph {
     }
 }
 
+/// `MEMGRAPH_HOME`, the directory holding the `memgraph` binary.
+pub(crate) fn memgraph_home() -> String {
+    env::var("MEMGRAPH_HOME").unwrap_or_else(|_| String::from("./downloads/memgraph_local"))
+}
+
 impl Memgraph {
     fn new() -> Memgraph {
-        let memgraph_home = env::var("MEMGRAPH_HOME")
-            .unwrap_or_else(|_| String::from("./downloads/memgraph_local"));
+        let memgraph_home = memgraph_home();
         let uri = env::var("MEMGRAPH_URI").unwrap_or_else(|_| String::from("127.0.0.1:7687"));
         let user = env::var("MEMGRAPH_USER").unwrap_or_default();
         let password = env::var("MEMGRAPH_PASSWORD").unwrap_or_default();
@@ -45,9 +60,27 @@ impl Memgraph {
             memgraph_home,
             prom_shutdown_tx: None,
             prom_process_handle: None,
+            server_limits: ServerLimits::default(),
+            server_output: None,
         }
     }
 
+    /// Launch the server under `server_limits` from now on (`--server-mem-limit`, `--server-cpus`).
+    pub fn set_server_limits(
+        &mut self,
+        server_limits: ServerLimits,
+    ) {
+        self.server_limits = server_limits;
+    }
+
+    /// Capture the server's stdout and stderr, its log included, under `output`.
+    pub fn set_server_output(
+        &mut self,
+        output: ServerOutput,
+    ) {
+        self.server_output = Some(output);
+    }
+
     pub async fn restore_db(
         &self,
         spec: Spec<'_>,
@@ -60,6 +93,7 @@ impl Memgraph {
             self.uri.to_string(),
             self.user.to_string(),
             self.password.to_string(),
+            Some(DEFAULT_MEMGRAPH_DATABASE.to_string()),
         )
         .await
     }
@@ -73,64 +107,45 @@ impl Memgraph {
         format!("{}/memgraph.pid", self.memgraph_home.clone())
     }
 
+    fn data_dir(&self) -> String {
+        format!("{}/data", self.memgraph_home)
+    }
+
+    /// Snapshot the running server's graph into the spec's backup directory, with `manifest`
+    /// next to it.
     pub async fn dump(
         &self,
         spec: Spec<'_>,
-    ) -> BenchmarkResult<Output> {
-        if self.is_running().await? {
+        manifest: &BackupManifest,
+    ) -> BenchmarkResult<()> {
+        if !self.is_running().await? {
             return Err(OtherError(
-                "Cannot dump DB because it is running.".to_string(),
+                "Cannot dump DB because it is not running.".to_string(),
             ));
         }
         let backup_path = spec.backup_path();
-
         info!("Dumping DB to {}", backup_path);
         create_directory_if_not_exists(&backup_path).await?;
 
-        // Memgraph doesn't have a built-in dump command like Neo4j
-        // We'll use a cypher script to export the data
-        let dump_file = format!("{}/memgraph.cypher", backup_path);
-
-        // Start memgraph temporarily for dump
-        let mut temp_process = self.start_temp_for_dump().await?;
-
-        let client = self.client().await?;
-        client.export_to_file(&dump_file).await?;
-
-        // Stop the temporary process
-        temp_process.kill()?;
-        temp_process.wait()?;
-
-        Ok(Output {
-            status: std::process::ExitStatus::default(),
-            stdout: Vec::new(),
-            stderr: Vec::new(),
-        })
-    }
-
-    async fn start_temp_for_dump(&self) -> BenchmarkResult<Child> {
-        trace!("starting temporary Memgraph process for dump");
-        let child = Command::new(self.memgraph_binary())
-            .arg("--data-directory")
-            .arg(format!("{}/data", self.memgraph_home))
-            .arg("--storage-wal-enabled=false")
-            .arg("--storage-snapshot-interval-sec=0")
-            .spawn()
-            .map_err(|e| {
-                FailedToSpawnProcessError(
-                    e,
-                    format!(
-                        "Failed to spawn temporary Memgraph process, cmd: {}",
-                        self.memgraph_binary()
-                    ),
-                )
-            })?;
-
-        // Wait for Memgraph to be ready
-        tokio::time::sleep(Duration::from_secs(3)).await;
-        Ok(child)
+        self.client().await?.create_snapshot().await?;
+        let snapshot = latest_snapshot(&format!("{}/snapshots", self.data_dir())).await?;
+        let target = Path::new(&backup_path).join(SNAPSHOT_FILE);
+        fs::copy(&snapshot, &target).await.map_err(|e| {
+            OtherError(format!(
+                "Failed copying {} to {}: {}",
+                snapshot.display(),
+                target.display(),
+                e
+            ))
+        })?;
+        manifest
+            .write(&Path::new(&backup_path).join(MANIFEST_FILE))
+            .await?;
+        Ok(())
     }
 
+    /// Replace the data directory with the spec's snapshot, which the server recovers from on its
+    /// next start.
     pub async fn restore(
         &self,
         spec: Spec<'_>,
@@ -143,23 +158,22 @@ impl Memgraph {
         }
 
         let backup_path = spec.backup_path();
-        let dump_file = format!("{}/memgraph.cypher", backup_path);
-
-        // Start memgraph temporarily for restore
-        let mut temp_process = self.start_temp_for_dump().await?;
-
-        let client = self.client().await?;
-        client.import_from_file(&dump_file).await?;
-
-        // Stop the temporary process
-        temp_process.kill()?;
-        temp_process.wait()?;
-
-        Ok(Output {
-            status: std::process::ExitStatus::default(),
-            stdout: Vec::new(),
-            stderr: Vec::new(),
-        })
+        let snapshot = Path::new(&backup_path).join(SNAPSHOT_FILE);
+        if !file_exists(&snapshot.to_string_lossy()).await {
+            return Err(OtherError(format!(
+                "No Memgraph snapshot at {}, run load first",
+                snapshot.display()
+            )));
+        }
+        check_before_restore(&Path::new(&backup_path).join(MANIFEST_FILE), &spec).await?;
+
+        let data_dir = self.data_dir();
+        spawn_command("rm", &["-rf", &data_dir]).await?;
+        let snapshots_dir = format!("{}/snapshots", data_dir);
+        create_directory_if_not_exists(&snapshots_dir).await?;
+        let from = snapshot.to_string_lossy();
+        let to = format!("{}/{}", snapshots_dir, SNAPSHOT_FILE);
+        spawn_command("cp", &[&from, &to]).await
     }
 
     pub async fn clean_db(&mut self) -> BenchmarkResult<Output> {
@@ -170,9 +184,8 @@ impl Memgraph {
             self.stop(false).await?;
         }
 
-        let home = self.memgraph_home.clone();
-        let data_dir = format!("{}/data", home);
-        let log_dir = format!("{}/log", home);
+        let data_dir = self.data_dir();
+        let log_dir = format!("{}/log", self.memgraph_home);
 
         let args = ["-rf", &data_dir, &log_dir];
         spawn_command("rm", &args).await
@@ -188,40 +201,49 @@ impl Memgraph {
         info!("starting Memgraph process");
 
         // Create data directory if it doesn't exist
-        let data_dir = format!("{}/data", self.memgraph_home);
+        let data_dir = self.data_dir();
         create_directory_if_not_exists(&data_dir).await?;
 
-        let child = Command::new(self.memgraph_binary())
+        let (program, prefix) =
+            self.server_limits.command(&self.memgraph_binary());
+        let mut command = Command::new(program);
+        command
+            .args(prefix)
             .arg("--data-directory")
             .arg(&data_dir)
-            .arg("--log-level=WARNING")
-            .arg("--also-log-to-stderr=false")
-            .spawn()
-            .map_err(|e| {
-                FailedToSpawnProcessError(
-                    e,
-                    format!(
-                        "Failed to spawn Memgraph process, cmd: {}",
-                        self.memgraph_binary()
-                    ),
-                )
-            })?;
-
-        // Wait for Memgraph to be ready
-        let mut retries = 0;
-        let max_retries = 30;
-        while !self.is_running().await? && retries < max_retries {
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            retries += 1;
-        }
-
-        if retries >= max_retries {
-            return Err(OtherError(
-                "Memgraph failed to start within timeout".to_string(),
-            ));
+            .arg("--data-recovery-on-startup=true");
+        let mut child = match &self.server_output {
+            // Snapshot creation is only logged at info; persistence_events reads it from there.
+            Some(output) => output.spawn(
+                "memgraph",
+                command
+                    .arg("--log-level=INFO")
+                    .arg("--also-log-to-stderr=true"),
+            ),
+            None => command
+                .arg("--log-level=WARNING")
+                .arg("--also-log-to-stderr=false")
+                .spawn(),
         }
-
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        .map_err(|e| {
+            FailedToSpawnProcessError(
+                e,
+                format!(
+                    "Failed to spawn Memgraph process, cmd: {}",
+                    self.memgraph_binary()
+                ),
+            )
+        })?;
+
+        BoltReadiness::new(
+            "Memgraph",
+            &self.uri,
+            &self.user,
+            &self.password,
+            Some("memgraph"),
+        )
+        .wait(&mut child)
+        .await?;
         let pid: u32 = child.id();
 
         info!("Memgraph is running: {}", pid);
@@ -261,11 +283,41 @@ impl Memgraph {
         }
     }
 
+    /// SIGKILL the server (no clean shutdown) and start it again; used by `--chaos kill-server`.
+    pub async fn kill_and_restart(&mut self) -> BenchmarkResult<()> {
+        let pid = get_memgraph_server_pid()
+            .ok_or_else(|| OtherError("Memgraph server process not found".to_string()))?;
+        kill_process(pid).await?;
+        self.start().await?;
+        Ok(())
+    }
+
     pub async fn is_running(&self) -> BenchmarkResult<bool> {
         Ok(get_memgraph_server_pid().is_some())
     }
 }
 
+/// The most recently written file of Memgraph's `snapshots` directory.
+async fn latest_snapshot(snapshots_dir: &str) -> BenchmarkResult<PathBuf> {
+    let mut entries = fs::read_dir(snapshots_dir)
+        .await
+        .map_err(|e| OtherError(format!("Failed reading {}: {}", snapshots_dir, e)))?;
+    let mut latest: Option<(std::time::SystemTime, PathBuf)> = None;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata.modified()?;
+        if latest.as_ref().is_none_or(|(newest, _)| modified > *newest) {
+            latest = Some((modified, entry.path()));
+        }
+    }
+    latest
+        .map(|(_, path)| path)
+        .ok_or_else(|| OtherError(format!("No Memgraph snapshot in {}", snapshots_dir)))
+}
+
 async fn report_metrics(sys: std::sync::Arc<std::sync::Mutex<System>>) -> BenchmarkResult<()> {
     let mut system = sys.lock().unwrap();
     // Refresh CPU usage
@@ -285,6 +337,8 @@ async fn report_metrics(sys: std::sync::Arc<std::sync::Mutex<System>>) -> Benchm
             MEMGRAPH_CPU_USAGE_GAUGE.set(cpu_usage);
             let mem_used = process.memory() as i64;
             MEMGRAPH_MEM_USAGE_GAUGE.set(mem_used);
+            MEMGRAPH_PROCESS_MEMORY_BYTES.set(mem_used);
+            prometheus_metrics::report_process_disk_io(Vendor::Memgraph, process);
         }
     }
     Ok(())
@@ -293,7 +347,9 @@ async fn report_metrics(sys: std::sync::Arc<std::sync::Mutex<System>>) -> Benchm
 fn get_memgraph_server_pid() -> Option<u32> {
     let system = System::new_all();
     let res = system.processes().iter().find(|(_, process)| {
-        search_in_os_strings(process.cmd(), "memgraph") || process.name() == "memgraph"
+        // The server is our child and nobody reaps it, so a killed one lingers as a zombie.
+        process.status() != ProcessStatus::Zombie
+            && (search_in_os_strings(process.cmd(), "memgraph") || process.name() == "memgraph")
     });
     res.map(|(pid, _)| pid.as_u32())
 }
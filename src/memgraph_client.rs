@@ -1,22 +1,36 @@
+use crate::cache_control::WARMUP_QUERIES;
+use crate::compact_query::CompactQuery;
+use crate::cypher_export;
 use crate::data_prep::bench_capacity;
-use crate::error::BenchmarkError::{Neo4rsError, OtherError};
+use crate::error::BenchmarkError::{Neo4rsError, OtherError, QueryTimeout};
 use crate::error::BenchmarkResult;
+use crate::index_parity::IndexDescriptor;
+use crate::index_wait::{IndexPopulation, IndexState};
+use crate::prometheus_metrics::EndpointMetricsPoller;
 use crate::queries_repository::PreparedQuery;
+use crate::query_plans::PlanMode;
+use crate::query_timeout::QueryTimeouts;
+use crate::result_size;
+use crate::scenario::Vendor;
 use crate::scheduler::Msg;
+use crate::simulate::SimulatedLatency;
 use crate::{
-    MEMGRAPH_MSG_DEADLINE_OFFSET_GAUGE, MEMGRAPH_STORAGE_MEMORY_RES_BYTES,
-    MEMGRAPH_STORAGE_MEMORY_TRACKED_BYTES, MEMGRAPH_STORAGE_PEAK_MEMORY_RES_BYTES,
-    OPERATION_COUNTER,
+    MEMGRAPH_MSG_DEADLINE_OFFSET_GAUGE, MEMGRAPH_REPORTED_MEMORY_BYTES,
+    MEMGRAPH_STORAGE_MEMORY_RES_BYTES, MEMGRAPH_STORAGE_MEMORY_TRACKED_BYTES,
+    MEMGRAPH_STORAGE_MODE, MEMGRAPH_STORAGE_PEAK_MEMORY_RES_BYTES, OPERATION_COUNTER,
 };
+use clap::ValueEnum;
 use futures::stream::TryStreamExt;
 use futures::{Stream, StreamExt};
 use histogram::Histogram;
-use neo4rs::{query, ConfigBuilder, Graph, Row};
+use neo4rs::{query, Config, ConfigBuilder, Graph, Row};
+use serde::Serialize;
 use std::hint::black_box;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::fs::File;
-use tokio::io::{self, AsyncWriteExt};
+use tokio::io::{self, AsyncWriteExt, BufWriter};
 use tokio::time::Instant;
 use tracing::{error, info, trace, warn};
 
@@ -25,6 +39,25 @@ struct MemgraphStorageInfo {
     memory_res_bytes: Option<i64>,
     peak_memory_res_bytes: Option<i64>,
     memory_tracked_bytes: Option<i64>,
+    storage_mode: Option<String>,
+}
+
+/// Memgraph's `STORAGE MODE`; analytical drops transactional guarantees (and their overhead).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MemgraphStorageMode {
+    InMemoryTransactional,
+    InMemoryAnalytical,
+}
+
+impl MemgraphStorageMode {
+    /// The mode as Memgraph names it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MemgraphStorageMode::InMemoryTransactional => "IN_MEMORY_TRANSACTIONAL",
+            MemgraphStorageMode::InMemoryAnalytical => "IN_MEMORY_ANALYTICAL",
+        }
+    }
 }
 
 fn memgraph_query_timeout_from_env() -> Duration {
@@ -105,7 +138,9 @@ fn get_row_i64(
 #[derive(Clone)]
 pub struct MemgraphClient {
     graph: Graph,
-    query_timeout: Duration,
+    query_timeouts: Arc<QueryTimeouts>,
+    // The same target over a single connection, for `reconnect`.
+    single_connection: Config,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -128,18 +163,23 @@ impl MemgraphClient {
         uri: String,
         user: String,
         password: String,
+        database: Option<String>,
     ) -> BenchmarkResult<MemgraphClient> {
-        // Try using ConfigBuilder with "memgraph" as database name
-        // Some versions of Memgraph might expect a specific database name
-        let config = ConfigBuilder::default()
-            .uri(&uri)
-            .user(&user)
-            .password(&password)
-            .db("memgraph") // Try "memgraph" as database name
-            .build()
-            .map_err(Neo4rsError)?;
+        let config = || {
+            let config = ConfigBuilder::default()
+                .uri(&uri)
+                .user(&user)
+                .password(&password);
+            if let Some(db) = database.clone() {
+                config.db(db)
+            } else {
+                config
+            }
+        };
 
-        let graph = Graph::connect(config).await.map_err(Neo4rsError)?;
+        let graph = Graph::connect(config().build().map_err(Neo4rsError)?)
+            .await
+            .map_err(Neo4rsError)?;
         let query_timeout = memgraph_query_timeout_from_env();
 
         info!(
@@ -149,25 +189,54 @@ impl MemgraphClient {
 
         Ok(MemgraphClient {
             graph,
-            query_timeout,
+            query_timeouts: Arc::new(QueryTimeouts::uniform(query_timeout)),
+            single_connection: config().max_connections(1).build().map_err(Neo4rsError)?,
         })
     }
 
+    /// Replace this client's connection pool with a fresh single connection, completing a round
+    /// trip on it so the Bolt handshake is done, for `run --reconnect-every`. Clones keep the old
+    /// pool.
+    pub async fn reconnect(&mut self) -> BenchmarkResult<()> {
+        let graph = Graph::connect(self.single_connection.clone())
+            .await
+            .map_err(Neo4rsError)?;
+        graph.run(query("RETURN 1")).await.map_err(Neo4rsError)?;
+        self.graph = graph;
+        Ok(())
+    }
+
+    /// The per-query timeout from `MEMGRAPH_QUERY_TIMEOUT_MS`, used unless `run --query-timeout`
+    /// replaces it.
+    pub fn default_query_timeout(&self) -> Duration {
+        self.query_timeouts.default_timeout()
+    }
+
+    pub fn set_query_timeouts(
+        &mut self,
+        query_timeouts: QueryTimeouts,
+    ) {
+        self.query_timeouts = Arc::new(query_timeouts);
+    }
+
     pub async fn execute_prepared_query<S: AsRef<str>>(
         &mut self,
         worker_id: S,
-        msg: &Msg<PreparedQuery>,
-        simulate: &Option<usize>,
+        msg: &Msg<CompactQuery>,
+        simulate: &Option<SimulatedLatency>,
     ) -> BenchmarkResult<()> {
-        let Msg {
-            payload: PreparedQuery { bolt, q_name, .. },
-            ..
-        } = msg;
+        if msg.payload.is_transaction() {
+            return self
+                .execute_prepared_transaction(worker_id.as_ref(), msg, simulate)
+                .await;
+        }
+
+        let CompactQuery { q_name, q_id, .. } = &msg.payload;
 
         let worker_id = worker_id.as_ref();
-        let q_name = q_name.as_str();
+        let q_name = &**q_name;
         // Timeout for the full query lifecycle (execute + stream consumption).
-        let timeout = self.query_timeout;
+        let timeout = self.query_timeouts.for_query(*q_id);
         let offset = msg.compute_offset_ms();
 
         MEMGRAPH_MSG_DEADLINE_OFFSET_GAUGE.set(offset);
@@ -176,56 +245,163 @@ impl MemgraphClient {
             tokio::time::sleep(Duration::from_millis(offset as u64)).await;
         }
 
-        let bolt_query = bolt.query.as_str();
-        let bolt_params = bolt.clone().params;
         let memgraph_query = async {
             let mut stream = self
                 .graph
-                .execute(neo4rs::query(bolt_query).params(bolt_params))
+                .execute(msg.payload.statement().bolt())
                 .await
                 .map_err(Neo4rsError)?;
 
+            let mut rows = 0;
             while let Ok(Some(row)) = stream.next().await {
                 trace!("Row: {:?}", row);
                 black_box(row);
+                rows += 1;
             }
 
-            Ok(())
+            Ok(rows)
         };
 
-        if let Some(delay) = simulate {
-            if *delay > 0 {
-                let delay: u64 = *delay as u64;
-                tokio::time::sleep(Duration::from_millis(delay)).await;
+        if let Some(latency) = simulate {
+            let delay = latency.sample(&mut rand::rng());
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
             }
             return Ok(());
         }
 
         let memgraph_result = tokio::time::timeout(timeout, memgraph_query).await;
         OPERATION_COUNTER
-            .with_label_values(&["memgraph", worker_id, "", q_name, "", ""])
+            .with_label_values(&[
+                Vendor::Memgraph.metric_label(),
+                worker_id,
+                "",
+                q_name,
+                "",
+                "",
+            ])
             .inc();
         match memgraph_result {
-            Ok(Ok(())) => {}
+            Ok(Ok(rows)) => result_size::observe(Vendor::Memgraph.metric_label(), q_name, rows),
             Ok(Err(e)) => {
                 OPERATION_COUNTER
-                    .with_label_values(&["memgraph", worker_id, "error", q_name, "", ""])
+                    .with_label_values(&[
+                        Vendor::Memgraph.metric_label(),
+                        worker_id,
+                        "error",
+                        q_name,
+                        "",
+                        "",
+                    ])
                     .inc();
                 return Err(e);
             }
             Err(_) => {
                 OPERATION_COUNTER
-                    .with_label_values(&["memgraph", worker_id, "timeout", q_name, "", ""])
+                    .with_label_values(&[
+                        Vendor::Memgraph.metric_label(),
+                        worker_id,
+                        "timeout",
+                        q_name,
+                        "",
+                        "",
+                    ])
                     .inc();
-                return Err(OtherError(format!(
-                    "Timeout after {}ms",
-                    timeout.as_millis()
-                )));
+                return Err(QueryTimeout(timeout));
             }
         }
         Ok(())
     }
 
+    /// Execute a transaction entry: every statement runs inside one explicit Bolt transaction,
+    /// consuming its rows, and the transaction is committed at the end.
+    async fn execute_prepared_transaction(
+        &mut self,
+        worker_id: &str,
+        msg: &Msg<CompactQuery>,
+        simulate: &Option<SimulatedLatency>,
+    ) -> BenchmarkResult<()> {
+        let CompactQuery {
+            q_name,
+            q_id,
+            statements,
+            ..
+        } = &msg.payload;
+        let q_name = &**q_name;
+        let timeout = self.query_timeouts.for_query(*q_id);
+        let offset = msg.compute_offset_ms();
+
+        MEMGRAPH_MSG_DEADLINE_OFFSET_GAUGE.set(offset);
+        if offset > 0 {
+            // sleep offset millis
+            tokio::time::sleep(Duration::from_millis(offset as u64)).await;
+        }
+
+        if let Some(latency) = simulate {
+            let delay = latency.sample(&mut rand::rng());
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            return Ok(());
+        }
+
+        let transaction = async {
+            let mut txn = self.graph.start_txn().await.map_err(Neo4rsError)?;
+            for statement in statements {
+                let mut stream = txn
+                    .execute(statement.bolt())
+                    .await
+                    .map_err(Neo4rsError)?;
+                while let Ok(Some(row)) = stream.next(txn.handle()).await {
+                    trace!("Row: {:?}", row);
+                    black_box(row);
+                }
+            }
+            txn.commit().await.map_err(Neo4rsError)
+        };
+
+        let result = tokio::time::timeout(timeout, transaction).await;
+        OPERATION_COUNTER
+            .with_label_values(&[
+                Vendor::Memgraph.metric_label(),
+                worker_id,
+                "",
+                q_name,
+                "",
+                "",
+            ])
+            .inc();
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => {
+                OPERATION_COUNTER
+                    .with_label_values(&[
+                        Vendor::Memgraph.metric_label(),
+                        worker_id,
+                        "error",
+                        q_name,
+                        "",
+                        "",
+                    ])
+                    .inc();
+                Err(e)
+            }
+            Err(_) => {
+                OPERATION_COUNTER
+                    .with_label_values(&[
+                        Vendor::Memgraph.metric_label(),
+                        worker_id,
+                        "timeout",
+                        q_name,
+                        "",
+                        "",
+                    ])
+                    .inc();
+                Err(QueryTimeout(timeout))
+            }
+        }
+    }
+
     pub async fn detect_algorithm_capabilities(
         &self
     ) -> BenchmarkResult<MemgraphAlgorithmCapabilities> {
@@ -292,6 +468,107 @@ RETURN
         })
     }
 
+    /// Label/edge-type property indexes present on the server, for the pre-run parity check.
+    ///
+    /// Every index `SHOW INDEX INFO` lists, all online: Memgraph populates an index before
+    /// `CREATE INDEX` returns.
+    pub async fn index_states(&self) -> BenchmarkResult<Vec<IndexState>> {
+        let mut result = self
+            .graph
+            .execute(query("SHOW INDEX INFO;"))
+            .await
+            .map_err(Neo4rsError)?;
+        let mut states = Vec::new();
+        while let Some(row) = result.next().await.map_err(Neo4rsError)? {
+            let index_type: String = row.get("index type")?;
+            let label = row.get::<String>("label").unwrap_or_default();
+            states.push(IndexState {
+                name: format!("{} :{}", index_type, label),
+                state: IndexPopulation::Online,
+            });
+        }
+        Ok(states)
+    }
+
+    /// `SHOW INDEX INFO` can't be filtered server-side, so text, vector and label-only indexes
+    /// are skipped here. Newer Memgraph versions report composite properties as a list.
+    pub async fn list_indexes(&self) -> BenchmarkResult<Vec<IndexDescriptor>> {
+        let mut result = self
+            .graph
+            .execute(query("SHOW INDEX INFO;"))
+            .await
+            .map_err(Neo4rsError)?;
+        let mut indexes = Vec::new();
+        while let Some(row) = result.next().await.map_err(Neo4rsError)? {
+            let index_type: String = row.get("index type")?;
+            if !index_type.contains("+propert") {
+                continue;
+            }
+            let label: String = row.get("label")?;
+            let property = match row.get::<String>("property") {
+                Ok(property) => property,
+                Err(_) => row.get::<Vec<String>>("property")?.join(","),
+            };
+            indexes.push(IndexDescriptor::new(label, property));
+        }
+        Ok(indexes)
+    }
+
+    /// `EXPLAIN` or `PROFILE` of `query`, for `run --capture-plans`. `EXPLAIN` returns one
+    /// `QUERY PLAN` row per operator; `PROFILE` one row per operator with its hits and times.
+    pub async fn query_plan(
+        &self,
+        query: &PreparedQuery,
+        mode: PlanMode,
+    ) -> BenchmarkResult<Vec<String>> {
+        let q = format!("{} {}", mode.keyword(), query.bolt.query);
+        let mut result = self
+            .graph
+            .execute(neo4rs::query(&q).params(query.bolt.params.clone()))
+            .await
+            .map_err(Neo4rsError)?;
+        let cell = |row: &Row, column: &str| match row.get::<serde_json::Value>(column) {
+            Ok(serde_json::Value::String(s)) => s,
+            Ok(value) => value.to_string(),
+            Err(_) => String::new(),
+        };
+        let mut plan = Vec::new();
+        while let Some(row) = result.next().await.map_err(Neo4rsError)? {
+            plan.push(match mode {
+                PlanMode::Explain => cell(&row, "QUERY PLAN"),
+                PlanMode::Profile => format!(
+                    "{} | hits {} | {}",
+                    cell(&row, "OPERATOR"),
+                    cell(&row, "ACTUAL HITS"),
+                    cell(&row, "ABSOLUTE TIME")
+                ),
+            });
+        }
+        Ok(plan)
+    }
+
+    /// Rows of a query returning `key` and `value` columns, for `inspect`.
+    pub async fn key_counts(
+        &self,
+        q: &str,
+    ) -> BenchmarkResult<Vec<(String, i64)>> {
+        let mut result = self.graph.execute(query(q)).await.map_err(Neo4rsError)?;
+        let mut rows = Vec::new();
+        while let Some(row) = result.next().await.map_err(Neo4rsError)? {
+            rows.push((row.get("key")?, row.get("value")?));
+        }
+        Ok(rows)
+    }
+
+    /// Run the `--warm` scans before the measured phase, draining every row.
+    pub async fn warm_up(&self) -> BenchmarkResult<()> {
+        for q in WARMUP_QUERIES {
+            let mut result = self.graph.execute(query(q)).await.map_err(Neo4rsError)?;
+            while result.next().await.map_err(Neo4rsError)?.is_some() {}
+        }
+        Ok(())
+    }
+
     pub async fn graph_size(&self) -> BenchmarkResult<(u64, u64)> {
         let mut result = self
             .graph
@@ -585,14 +862,20 @@ RETURN
     /// - memory_res
     /// - peak_memory_res
     /// - memory_tracked
+    /// - storage_mode
     pub async fn collect_storage_info_metrics(&self) {
         // Avoid stale values when multiple runs happen in a single process.
         MEMGRAPH_STORAGE_MEMORY_RES_BYTES.set(0);
         MEMGRAPH_STORAGE_PEAK_MEMORY_RES_BYTES.set(0);
         MEMGRAPH_STORAGE_MEMORY_TRACKED_BYTES.set(0);
+        MEMGRAPH_REPORTED_MEMORY_BYTES.set(0);
+        MEMGRAPH_STORAGE_MODE.reset();
 
         match self.storage_info().await {
             Ok(info) => {
+                if let Some(mode) = &info.storage_mode {
+                    MEMGRAPH_STORAGE_MODE.with_label_values(&[mode]).set(1);
+                }
                 if let Some(v) = info.memory_res_bytes {
                     MEMGRAPH_STORAGE_MEMORY_RES_BYTES.set(v);
                 }
@@ -602,6 +885,13 @@ RETURN
                 if let Some(v) = info.memory_tracked_bytes {
                     MEMGRAPH_STORAGE_MEMORY_TRACKED_BYTES.set(v);
                 }
+                if let Some(v) = info
+                    .memory_tracked_bytes
+                    .filter(|v| *v > 0)
+                    .or(info.memory_res_bytes)
+                {
+                    MEMGRAPH_REPORTED_MEMORY_BYTES.set(v);
+                }
             }
             Err(e) => {
                 tracing::debug!("Failed collecting Memgraph storage info: {}", e);
@@ -609,6 +899,18 @@ RETURN
         }
     }
 
+    /// For external endpoints: re-sample `SHOW STORAGE INFO` for the rest of the run.
+    pub fn spawn_endpoint_metrics_poller(&self) -> EndpointMetricsPoller {
+        let client = self.clone();
+        EndpointMetricsPoller::spawn(move || {
+            let client = client.clone();
+            async move {
+                client.collect_storage_info_metrics().await;
+                Ok(())
+            }
+        })
+    }
+
     async fn storage_info(&self) -> BenchmarkResult<MemgraphStorageInfo> {
         let mut result = self
             .graph
@@ -644,6 +946,9 @@ RETURN
                     "memory_tracked" => {
                         info.memory_tracked_bytes = parse_human_bytes_to_i64(&v);
                     }
+                    "storage_mode" => {
+                        info.storage_mode = Some(v.trim().trim_matches('"').to_string());
+                    }
                     _ => {}
                 }
                 continue;
@@ -664,6 +969,30 @@ RETURN
         Ok(info)
     }
 
+    /// Switch the server to `mode`; it holds until the server restarts.
+    pub async fn set_storage_mode(
+        &self,
+        mode: MemgraphStorageMode,
+    ) -> BenchmarkResult<()> {
+        self.graph
+            .run(query(&format!("STORAGE MODE {}", mode.as_str())))
+            .await
+            .map_err(|e| {
+                OtherError(format!(
+                    "Failed switching Memgraph to {}: {}",
+                    mode.as_str(),
+                    e
+                ))
+            })?;
+        info!("Memgraph storage mode set to {}", mode.as_str());
+        Ok(())
+    }
+
+    /// The storage mode `SHOW STORAGE INFO` reports, if it reports one.
+    pub async fn storage_mode(&self) -> BenchmarkResult<Option<String>> {
+        Ok(self.storage_info().await?.storage_mode)
+    }
+
     /// Clear all user data in an external Memgraph instance.
     ///
     /// We intentionally avoid Neo4j's `cypher-shell` for Memgraph because recent versions
@@ -992,6 +1321,7 @@ RETURN
                     }
                 }
             }
+            crate::run_status::loaded(total_processed as u64);
         }
 
         flush_nodes(self, &mut node_maps, histogram, &mut batch_count).await?;
@@ -1095,107 +1425,125 @@ RETURN
         Ok(total_processed)
     }
 
-    /// Export database to a cypher file.
-    ///
-    /// NOTE: This is intended for benchmark backups and is best-effort. We generate Cypher
-    /// statements directly in the database (as strings) so we don't have to rely on Rust-side
-    /// serialization of Bolt types.
+    /// Export the graph to `file_path` as re-loadable Cypher, one statement per line, which
+    /// `import_from_file` reads back (see [`crate::cypher_export`]).
     pub async fn export_to_file(
         &self,
         file_path: &str,
     ) -> BenchmarkResult<()> {
         info!("Exporting database to {}", file_path);
-
-        let mut file = File::create(file_path).await?;
+        let mut file = BufWriter::new(File::create(file_path).await?);
         file.write_all(b"// Memgraph export generated by benchmark\n")
             .await?;
-
-        // 1) Export nodes that have an `id` property (preferred: allows stable relationship export).
-        // We MERGE on (labels, id) and SET += all properties.
-        let nodes_with_id = r#"
-MATCH (n)
-WHERE n.id IS NOT NULL
-RETURN
-  'MERGE (n' + reduce(s='', l IN labels(n) | s + ':' + l) +
-  ' {id: ' + toString(n.id) + '}) SET n += ' + toString(properties(n)) + ';'
-  AS cypher
-"#;
-
-        let mut result = self.graph.execute(query(nodes_with_id)).await?;
-        while let Ok(Some(row)) = result.next().await {
-            if let Ok(line) = row.get::<String>("cypher") {
-                file.write_all(line.as_bytes()).await?;
-                file.write_all(b"\n").await?;
-            }
+        for statement in cypher_export::preamble() {
+            write_line(&mut file, &statement).await?;
         }
 
-        // 2) Export nodes without `id` as plain CREATE.
-        let nodes_without_id = r#"
-MATCH (n)
-WHERE n.id IS NULL
-RETURN
-  'CREATE (' + reduce(s='', l IN labels(n) | s + ':' + l) +
-  ' ' + toString(properties(n)) + ');'
-  AS cypher
-"#;
-
-        let mut result = self.graph.execute(query(nodes_without_id)).await?;
-        while let Ok(Some(row)) = result.next().await {
-            if let Ok(line) = row.get::<String>("cypher") {
-                file.write_all(line.as_bytes()).await?;
-                file.write_all(b"\n").await?;
+        let mut result = self
+            .graph
+            .execute(query(
+                "MATCH (n) RETURN id(n) AS id, labels(n) AS labels, properties(n) AS props",
+            ))
+            .await
+            .map_err(Neo4rsError)?;
+        let mut nodes = cypher_export::Batches::new(cypher_export::EXPORT_BATCH_SIZE);
+        let mut node_count = 0u64;
+        while let Some(row) = result.next().await.map_err(Neo4rsError)? {
+            let id = row.get::<i64>("id")?;
+            let mut labels = row.get::<Vec<String>>("labels")?;
+            labels.sort();
+            let props = row.get::<serde_json::Map<String, serde_json::Value>>("props")?;
+            node_count += 1;
+            if let Some((labels, batch)) = nodes.push(labels, (id, props)) {
+                write_line(&mut file, &cypher_export::nodes_statement(&labels, &batch)).await?;
             }
         }
+        for (labels, batch) in nodes.drain() {
+            write_line(&mut file, &cypher_export::nodes_statement(&labels, &batch)).await?;
+        }
 
-        // 3) Export relationships (only when both endpoints have `id`).
-        // This assumes nodes can be matched back by {id: ...}.
-        let rels = r#"
-MATCH (a)-[r]->(b)
-WHERE a.id IS NOT NULL AND b.id IS NOT NULL
-RETURN
-  'MATCH (a {id: ' + toString(a.id) + '}), (b {id: ' + toString(b.id) + '}) '
-  + 'CREATE (a)-[:' + type(r) + ' ' + toString(properties(r)) + ']->(b);'
-  AS cypher
-"#;
-
-        let mut result = self.graph.execute(query(rels)).await?;
-        while let Ok(Some(row)) = result.next().await {
-            if let Ok(line) = row.get::<String>("cypher") {
-                file.write_all(line.as_bytes()).await?;
-                file.write_all(b"\n").await?;
+        let mut result = self
+            .graph
+            .execute(query(
+                "MATCH (a)-[r]->(b) RETURN id(a) AS from, id(b) AS to, type(r) AS type, properties(r) AS props",
+            ))
+            .await
+            .map_err(Neo4rsError)?;
+        let mut relationships = cypher_export::Batches::new(cypher_export::EXPORT_BATCH_SIZE);
+        let mut relationship_count = 0u64;
+        while let Some(row) = result.next().await.map_err(Neo4rsError)? {
+            let from = row.get::<i64>("from")?;
+            let to = row.get::<i64>("to")?;
+            let rel_type = row.get::<String>("type")?;
+            let props = row.get::<serde_json::Map<String, serde_json::Value>>("props")?;
+            relationship_count += 1;
+            if let Some((rel_type, batch)) = relationships.push(rel_type, (from, to, props)) {
+                write_line(
+                    &mut file,
+                    &cypher_export::relationships_statement(&rel_type, &batch),
+                )
+                .await?;
             }
         }
+        for (rel_type, batch) in relationships.drain() {
+            write_line(
+                &mut file,
+                &cypher_export::relationships_statement(&rel_type, &batch),
+            )
+            .await?;
+        }
 
+        for statement in cypher_export::epilogue() {
+            write_line(&mut file, &statement).await?;
+        }
         file.flush().await?;
-        info!("Database exported successfully");
+        info!(
+            "Exported {} nodes and {} relationships",
+            node_count, relationship_count
+        );
         Ok(())
     }
 
-    /// Import database from a cypher file.
+    /// Import a file written by `export_to_file`, one statement per line.
     pub async fn import_from_file(
         &self,
         file_path: &str,
     ) -> BenchmarkResult<()> {
         info!("Importing database from {}", file_path);
 
-        // Read and execute each line from the file.
         let content = tokio::fs::read_to_string(file_path).await?;
         for line in content.lines() {
             let trimmed = line.trim();
             if trimmed.is_empty() || trimmed.starts_with("//") {
                 continue;
             }
-
-            // Be tolerant of trailing semicolons.
-            let q = trimmed.trim_end_matches(';');
-
-            let mut results = self.execute_query(q).await?;
-            while results.next().await.is_some() {
-                // Drain results
-            }
+            self.graph
+                .run(query(trimmed.trim_end_matches(';')))
+                .await
+                .map_err(Neo4rsError)?;
         }
 
         info!("Database imported successfully");
         Ok(())
     }
+
+    /// `CREATE SNAPSHOT`: write the whole graph into a new file of the server's
+    /// `<data-directory>/snapshots`. Returns once the snapshot is on disk.
+    pub async fn create_snapshot(&self) -> BenchmarkResult<()> {
+        info!("Creating Memgraph snapshot");
+        self.graph
+            .run(query("CREATE SNAPSHOT;"))
+            .await
+            .map_err(Neo4rsError)?;
+        Ok(())
+    }
+}
+
+async fn write_line(
+    file: &mut BufWriter<File>,
+    statement: &str,
+) -> BenchmarkResult<()> {
+    file.write_all(statement.as_bytes()).await?;
+    file.write_all(b"\n").await?;
+    Ok(())
 }
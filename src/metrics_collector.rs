@@ -0,0 +1,1255 @@
+//! Latency bookkeeping for the measured phase of `run`, shared by the workers.
+//!
+//! The collector keeps an HDR histogram over every query plus one per query (indexed by query id
+//! like the catalog), outcome counts, and the slowest call of each query with the worker that made
+//! it. Groups and query types (read, write) are reported from the per-query histograms merged. After the run it sets the Prometheus gauges and renders `report.md`/`report.json`, which
+//! are written next to metrics.prom.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::phases::Phases;
+use crate::queries_repository::{QueryCatalogEntry, QueryGroup, QueryType};
+use crate::query_timeout::{QueryTimeoutReport, QueryTimeouts};
+use crate::scenario::Vendor;
+use crate::{
+    FALKOR_LATENCY_MAX_US, FALKOR_LATENCY_P50_US, FALKOR_LATENCY_P95_US, FALKOR_LATENCY_P99_9_US,
+    FALKOR_LATENCY_P99_99_US, FALKOR_LATENCY_P99_US, MEMGRAPH_LATENCY_MAX_US,
+    MEMGRAPH_LATENCY_P99_99_US, MEMGRAPH_LATENCY_P99_9_US, NEO4J_LATENCY_MAX_US,
+    NEO4J_LATENCY_P99_99_US, NEO4J_LATENCY_P99_9_US,
+    FALKOR_QUERY_LATENCY_PCT_US, FALKOR_QUERY_TIMEOUT_RATE_PCT, FALKOR_SERVER_EXECUTION_P50_US,
+    FALKOR_SERVER_EXECUTION_P95_US, FALKOR_SERVER_EXECUTION_P99_US, MEMGRAPH_LATENCY_P50_US,
+    MEMGRAPH_LATENCY_P95_US, MEMGRAPH_LATENCY_P99_US, MEMGRAPH_QUERY_LATENCY_PCT_US,
+    MEMGRAPH_QUERY_TIMEOUT_RATE_PCT, NEO4J_LATENCY_P50_US, NEO4J_LATENCY_P95_US,
+    NEO4J_LATENCY_P99_US, NEO4J_QUERY_LATENCY_PCT_US, NEO4J_QUERY_TIMEOUT_RATE_PCT,
+    PHASE_LATENCY_US, QUERY_GROUP_LATENCY_US, QUERY_TIMEOUTS, QUERY_TYPE_LATENCY_US,
+};
+use histogram::{Histogram, SampleQuantiles, SparseHistogram};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+/// Percentiles exported per query ("single"-workload style, P10..P99).
+pub const QUERY_HIST_PCTS: [f64; 11] = [
+    10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 95.0, 99.0,
+];
+
+/// Upper bound of the bucket holding percentile `p` (0-1 or 0-100); 0 for an empty histogram.
+pub fn percentile_us(
+    hist: &Histogram,
+    p: f64,
+) -> u64 {
+    let quantile = if p > 1.0 { p / 100.0 } else { p };
+    SampleQuantiles::quantile(hist, quantile)
+        .ok()
+        .flatten()
+        .and_then(|result| result.entries().values().next().map(|b| b.end()))
+        .unwrap_or(0)
+}
+
+/// Written next to metrics.prom by every run.
+pub const HISTOGRAMS_FILE: &str = "histograms.json";
+
+/// Written next to metrics.prom by every run: latency percentiles per [`HEATMAP_WINDOW_MS`] of
+/// the schedule, for plotting latency over time.
+pub const LATENCY_HEATMAP_FILE: &str = "latency_heatmap.csv";
+
+pub const HEATMAP_WINDOW_MS: u64 = 10_000;
+
+/// The percentile columns of [`LATENCY_HEATMAP_FILE`], before `max_us`.
+pub const HEATMAP_PCTS: [f64; 8] = [10.0, 25.0, 50.0, 75.0, 90.0, 95.0, 99.0, 99.9];
+
+/// A run's HDR latency histograms in microseconds, as written to [`HISTOGRAMS_FILE`], so the
+/// aggregator can compute any percentile exactly and add up the clients of a distributed run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunHistograms {
+    pub overall: SparseHistogram,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<SparseHistogram>,
+    /// Keyed by query name; only queries that succeeded at least once.
+    pub queries: BTreeMap<String, SparseHistogram>,
+    /// Keyed by query type (`read`, `write`); missing in runs before it was recorded.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub types: BTreeMap<String, SparseHistogram>,
+}
+
+/// One or more runs' [`RunHistograms`] added up, ready for percentiles.
+#[derive(Debug, Clone)]
+pub struct LatencyHistograms {
+    pub overall: Histogram,
+    pub server: Option<Histogram>,
+    pub queries: BTreeMap<String, Histogram>,
+    pub types: BTreeMap<String, Histogram>,
+}
+
+impl LatencyHistograms {
+    pub fn from_runs(runs: &[RunHistograms]) -> BenchmarkResult<Self> {
+        fn add(
+            sum: Option<Histogram>,
+            next: &SparseHistogram,
+        ) -> BenchmarkResult<Histogram> {
+            let next = Histogram::from(next);
+            Ok(match sum {
+                Some(sum) => sum.checked_add(&next)?,
+                None => next,
+            })
+        }
+        let mut overall = None;
+        let mut server = None;
+        let mut queries: BTreeMap<String, Histogram> = BTreeMap::new();
+        let mut types: BTreeMap<String, Histogram> = BTreeMap::new();
+        for run in runs {
+            overall = Some(add(overall, &run.overall)?);
+            if let Some(run_server) = &run.server {
+                server = Some(add(server, run_server)?);
+            }
+            for (name, hist) in &run.queries {
+                let sum = add(queries.remove(name), hist)?;
+                queries.insert(name.clone(), sum);
+            }
+            for (q_type, hist) in &run.types {
+                let sum = add(types.remove(q_type), hist)?;
+                types.insert(q_type.clone(), sum);
+            }
+        }
+        Ok(LatencyHistograms {
+            overall: overall.ok_or_else(|| OtherError("no run histograms".to_string()))?,
+            server,
+            queries,
+            types,
+        })
+    }
+
+    /// The queries of `group` (see [`QueryGroup::as_str`]) merged; `None` when none ran.
+    pub fn group(
+        &self,
+        group: &str,
+    ) -> Option<Histogram> {
+        self.merged(
+            self.queries
+                .keys()
+                .map(String::as_str)
+                .filter(|query| QueryGroup::of(query).as_str() == group),
+        )
+    }
+
+    /// The histograms of `queries` merged; `None` when none of them ran.
+    pub fn merged<'a>(
+        &self,
+        queries: impl IntoIterator<Item = &'a str>,
+    ) -> Option<Histogram> {
+        let mut hists = queries
+            .into_iter()
+            .filter_map(|query| self.queries.get(query));
+        let mut merged = hists.next()?.clone();
+        for hist in hists {
+            merged = merged.checked_add(hist).ok()?;
+        }
+        Some(merged)
+    }
+}
+
+/// The slowest successful call of a query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorstCall {
+    pub duration_us: u64,
+    pub worker: String,
+}
+
+/// Count and percentiles of one histogram.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub p99_us: u64,
+    pub p99_9_us: u64,
+    pub p99_99_us: u64,
+    pub max_us: u64,
+}
+
+impl LatencySummary {
+    fn new(
+        hist: &Histogram,
+        count: u64,
+    ) -> Self {
+        LatencySummary {
+            count,
+            p50_us: percentile_us(hist, 50.0),
+            p95_us: percentile_us(hist, 95.0),
+            p99_us: percentile_us(hist, 99.0),
+            p99_9_us: percentile_us(hist, 99.9),
+            p99_99_us: percentile_us(hist, 99.99),
+            max_us: percentile_us(hist, 100.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryReport {
+    pub name: String,
+    pub executions: u64,
+    pub errors: u64,
+    pub timeouts: u64,
+    /// Over the successful executions.
+    pub latency: LatencySummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worst: Option<WorstCall>,
+}
+
+/// Every query of one [`QueryGroup`] together.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupReport {
+    pub group: QueryGroup,
+    pub executions: u64,
+    pub errors: u64,
+    /// Over the successful executions.
+    pub latency: LatencySummary,
+}
+
+/// Every read or every write query together.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryTypeReport {
+    pub q_type: QueryType,
+    pub executions: u64,
+    pub errors: u64,
+    /// Over the successful executions.
+    pub latency: LatencySummary,
+}
+
+/// Every query scheduled in one phase of `run --phases`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseReport {
+    pub phase: String,
+    pub write_ratio: f32,
+    pub executions: u64,
+    pub errors: u64,
+    /// Over the successful executions.
+    pub latency: LatencySummary,
+}
+
+/// New connections opened by `run --reconnect-every`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionReport {
+    /// From opening the connection to the answer of its first round trip.
+    pub established: LatencySummary,
+    pub failures: u64,
+}
+
+/// `report.json`; [`MetricsReport::to_markdown`] renders `report.md`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsReport {
+    pub vendor: String,
+    pub latency: LatencySummary,
+    /// Execution time reported by the server itself (FalkorDB only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_execution: Option<LatencySummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connections: Option<ConnectionReport>,
+    /// Groups with at least one execution.
+    pub groups: Vec<GroupReport>,
+    /// Query types with at least one execution.
+    pub types: Vec<QueryTypeReport>,
+    /// Queries that ran at least once, in catalog order.
+    pub queries: Vec<QueryReport>,
+    /// The phases of `run --phases`, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<PhaseReport>,
+}
+
+impl MetricsReport {
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# {} run report\n\n", self.vendor);
+        let _ = writeln!(md, "| | count | p50 | p95 | p99 | p99.9 | p99.99 | max |");
+        let _ = writeln!(md, "|---|---:|---:|---:|---:|---:|---:|---:|");
+        let mut summary_row = |label: &str, latency: &LatencySummary| {
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {} | {} | {} | {} |",
+                label,
+                latency.count,
+                format_us(latency.p50_us),
+                format_us(latency.p95_us),
+                format_us(latency.p99_us),
+                format_us(latency.p99_9_us),
+                format_us(latency.p99_99_us),
+                format_us(latency.max_us)
+            );
+        };
+        summary_row("client latency", &self.latency);
+        if let Some(server) = &self.server_execution {
+            summary_row("server execution", server);
+        }
+        if let Some(connections) = &self.connections {
+            summary_row("connection setup", &connections.established);
+            if connections.failures > 0 {
+                let _ = writeln!(
+                    md,
+                    "\n{} reconnects failed; those workers kept their previous connection.",
+                    connections.failures
+                );
+            }
+        }
+
+        // A single group would repeat the client latency row.
+        if self.groups.len() > 1 {
+            let _ = writeln!(md, "\n## Per group\n");
+            let _ = writeln!(md, "| group | executions | errors | p50 | p95 | p99 |");
+            let _ = writeln!(md, "|---|---:|---:|---:|---:|---:|");
+            for group in &self.groups {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} | {} |",
+                    group.group.as_str(),
+                    group.executions,
+                    group.errors,
+                    format_us(group.latency.p50_us),
+                    format_us(group.latency.p95_us),
+                    format_us(group.latency.p99_us)
+                );
+            }
+        }
+
+        // Likewise a read-only or write-only run.
+        if self.types.len() > 1 {
+            let _ = writeln!(md, "\n## Per query type\n");
+            let _ = writeln!(md, "| type | executions | errors | p50 | p95 | p99 |");
+            let _ = writeln!(md, "|---|---:|---:|---:|---:|---:|");
+            for q_type in &self.types {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} | {} |",
+                    q_type.q_type.as_str(),
+                    q_type.executions,
+                    q_type.errors,
+                    format_us(q_type.latency.p50_us),
+                    format_us(q_type.latency.p95_us),
+                    format_us(q_type.latency.p99_us)
+                );
+            }
+        }
+
+        if !self.phases.is_empty() {
+            let _ = writeln!(md, "\n## Per phase\n");
+            let _ = writeln!(
+                md,
+                "| phase | write ratio | executions | errors | p50 | p95 | p99 |"
+            );
+            let _ = writeln!(md, "|---|---:|---:|---:|---:|---:|---:|");
+            for phase in &self.phases {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} | {} | {} |",
+                    phase.phase,
+                    phase.write_ratio,
+                    phase.executions,
+                    phase.errors,
+                    format_us(phase.latency.p50_us),
+                    format_us(phase.latency.p95_us),
+                    format_us(phase.latency.p99_us)
+                );
+            }
+        }
+
+        let _ = writeln!(md, "\n## Per query\n");
+        let _ = writeln!(
+            md,
+            "| query | executions | errors | timeouts | p50 | p95 | p99 | worst call |"
+        );
+        let _ = writeln!(md, "|---|---:|---:|---:|---:|---:|---:|---|");
+        for query in &self.queries {
+            let worst = query
+                .worst
+                .as_ref()
+                .map(|worst| format!("{} (worker {})", format_us(worst.duration_us), worst.worker))
+                .unwrap_or_else(|| "-".to_string());
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {} | {} | {} | {} |",
+                query.name,
+                query.executions,
+                query.errors,
+                query.timeouts,
+                format_us(query.latency.p50_us),
+                format_us(query.latency.p95_us),
+                format_us(query.latency.p99_us),
+                worst
+            );
+        }
+        md
+    }
+}
+
+fn format_us(us: u64) -> String {
+    format!("{:.3}ms", us as f64 / 1000.0)
+}
+
+#[derive(Debug)]
+struct QueryStats {
+    hist: Mutex<Histogram>,
+    total: AtomicU64,
+    successes: AtomicU64,
+    timeouts: AtomicU64,
+    max_us: AtomicU64,
+    worst: Mutex<Option<WorstCall>>,
+}
+
+impl QueryStats {
+    fn new() -> BenchmarkResult<Self> {
+        Ok(QueryStats {
+            hist: Mutex::new(Histogram::new(7, 64)?),
+            total: AtomicU64::new(0),
+            successes: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+            max_us: AtomicU64::new(0),
+            worst: Mutex::new(None),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct PhaseStats {
+    hist: Mutex<Histogram>,
+    executions: AtomicU64,
+    successes: AtomicU64,
+}
+
+/// One [`HEATMAP_WINDOW_MS`] of the schedule.
+#[derive(Debug)]
+struct WindowStats {
+    hist: Histogram,
+    executions: u64,
+    errors: u64,
+}
+
+#[derive(Debug)]
+pub struct MetricsCollector {
+    catalog: Vec<QueryCatalogEntry>,
+    overall: Mutex<Histogram>,
+    successes: AtomicU64,
+    server: Mutex<Histogram>,
+    server_samples: AtomicU64,
+    connects: Mutex<Histogram>,
+    connect_count: AtomicU64,
+    connect_failures: AtomicU64,
+    // Indexed by q_id.
+    queries: Vec<QueryStats>,
+    phase_plan: Option<Phases>,
+    // Indexed like the plan's phases.
+    phases: Vec<PhaseStats>,
+    // Indexed by offset / HEATMAP_WINDOW_MS.
+    windows: Mutex<Vec<WindowStats>>,
+}
+
+impl MetricsCollector {
+    pub fn catalog(&self) -> &[QueryCatalogEntry] {
+        &self.catalog
+    }
+
+    pub fn new(catalog: Vec<QueryCatalogEntry>) -> BenchmarkResult<Self> {
+        let queries = catalog
+            .iter()
+            .map(|_| QueryStats::new())
+            .collect::<BenchmarkResult<Vec<_>>>()?;
+        Ok(MetricsCollector {
+            catalog,
+            overall: Mutex::new(Histogram::new(7, 64)?),
+            successes: AtomicU64::new(0),
+            server: Mutex::new(Histogram::new(7, 64)?),
+            server_samples: AtomicU64::new(0),
+            connects: Mutex::new(Histogram::new(7, 64)?),
+            connect_count: AtomicU64::new(0),
+            connect_failures: AtomicU64::new(0),
+            queries,
+            phase_plan: None,
+            phases: Vec::new(),
+            windows: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Also keep the latency of every phase of `run --phases`.
+    pub fn with_phases(
+        mut self,
+        phases: Option<&Phases>,
+    ) -> BenchmarkResult<Self> {
+        let Some(phases) = phases else {
+            return Ok(self);
+        };
+        self.phases = phases
+            .phases()
+            .iter()
+            .map(|_| {
+                Ok(PhaseStats {
+                    hist: Mutex::new(Histogram::new(7, 64)?),
+                    executions: AtomicU64::new(0),
+                    successes: AtomicU64::new(0),
+                })
+            })
+            .collect::<BenchmarkResult<Vec<_>>>()?;
+        self.phase_plan = Some(phases.clone());
+        Ok(self)
+    }
+
+    /// A query scheduled `offset_ms` into the run finished after `duration`; a no-op without
+    /// phases.
+    pub fn record_phase(
+        &self,
+        offset_ms: u64,
+        duration: Duration,
+        success: bool,
+    ) {
+        let Some(stats) = self
+            .phase_plan
+            .as_ref()
+            .and_then(|plan| plan.index_at(Duration::from_millis(offset_ms)))
+            .and_then(|index| self.phases.get(index))
+        else {
+            return;
+        };
+        stats.executions.fetch_add(1, Ordering::Relaxed);
+        if success {
+            stats.successes.fetch_add(1, Ordering::Relaxed);
+            if let Ok(mut h) = stats.hist.lock() {
+                let _ = h.increment(duration.as_micros() as u64);
+            }
+        }
+    }
+
+    /// A query scheduled `offset_ms` into the run finished after `duration`, for
+    /// [`MetricsCollector::latency_heatmap_csv`].
+    pub fn record_window(
+        &self,
+        offset_ms: u64,
+        duration: Duration,
+        success: bool,
+    ) {
+        let Ok(mut windows) = self.windows.lock() else {
+            return;
+        };
+        let index = (offset_ms / HEATMAP_WINDOW_MS) as usize;
+        while windows.len() <= index {
+            // A coarser grouping than the run's histograms (3% instead of 1% error), since a long
+            // run keeps hundreds of them.
+            let Ok(hist) = Histogram::new(5, 64) else {
+                return;
+            };
+            windows.push(WindowStats {
+                hist,
+                executions: 0,
+                errors: 0,
+            });
+        }
+        let window = &mut windows[index];
+        window.executions += 1;
+        if success {
+            let _ = window.hist.increment(duration.as_micros() as u64);
+        } else {
+            window.errors += 1;
+        }
+    }
+
+    /// [`LATENCY_HEATMAP_FILE`]: a row per window with its executions, errors and the latency
+    /// percentiles of its successes in microseconds; 0 when nothing succeeded.
+    pub fn latency_heatmap_csv(&self) -> String {
+        let mut csv = String::from("window_start_s,executions,errors");
+        for pct in HEATMAP_PCTS {
+            let _ = write!(csv, ",p{}_us", pct.to_string().replace('.', "_"));
+        }
+        csv.push_str(",max_us\n");
+        let Ok(windows) = self.windows.lock() else {
+            return csv;
+        };
+        for (index, window) in windows.iter().enumerate() {
+            let _ = write!(
+                csv,
+                "{},{},{}",
+                index as u64 * HEATMAP_WINDOW_MS / 1000,
+                window.executions,
+                window.errors
+            );
+            for pct in HEATMAP_PCTS.into_iter().chain([100.0]) {
+                let _ = write!(csv, ",{}", percentile_us(&window.hist, pct));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    fn phase_reports(&self) -> Vec<PhaseReport> {
+        let Some(plan) = &self.phase_plan else {
+            return Vec::new();
+        };
+        plan.phases()
+            .iter()
+            .zip(&self.phases)
+            .map(|(phase, stats)| {
+                let executions = stats.executions.load(Ordering::Relaxed);
+                let successes = stats.successes.load(Ordering::Relaxed);
+                PhaseReport {
+                    phase: phase.name.clone(),
+                    write_ratio: phase.write_ratio,
+                    executions,
+                    errors: executions - successes,
+                    latency: stats
+                        .hist
+                        .lock()
+                        .map(|h| LatencySummary::new(&h, successes))
+                        .unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn record_success(
+        &self,
+        q_id: u16,
+        duration: Duration,
+        worker: &str,
+    ) {
+        let us = duration.as_micros() as u64;
+        self.successes.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut h) = self.overall.lock() {
+            let _ = h.increment(us);
+        }
+        let Some(stats) = self.queries.get(q_id as usize) else {
+            return;
+        };
+        stats.total.fetch_add(1, Ordering::Relaxed);
+        stats.successes.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut h) = stats.hist.lock() {
+            let _ = h.increment(us);
+        }
+        // Only take the lock when this call is (so far) the slowest.
+        if stats.max_us.fetch_max(us, Ordering::Relaxed) < us {
+            if let Ok(mut worst) = stats.worst.lock() {
+                if worst.as_ref().is_none_or(|worst| worst.duration_us < us) {
+                    *worst = Some(WorstCall {
+                        duration_us: us,
+                        worker: worker.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Execution time the server reported for a successful query.
+    pub fn record_server_time(
+        &self,
+        duration: Duration,
+    ) {
+        self.server_samples.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut h) = self.server.lock() {
+            let _ = h.increment(duration.as_micros() as u64);
+        }
+    }
+
+    /// Time a worker took to open a new connection.
+    pub fn record_connect(
+        &self,
+        duration: Duration,
+    ) {
+        self.connect_count.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut h) = self.connects.lock() {
+            let _ = h.increment(duration.as_micros() as u64);
+        }
+    }
+
+    pub fn record_connect_failure(&self) {
+        self.connect_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_failure(
+        &self,
+        q_id: u16,
+    ) {
+        if let Some(stats) = self.queries.get(q_id as usize) {
+            stats.total.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_timeout(
+        &self,
+        q_id: u16,
+    ) {
+        if let Some(stats) = self.queries.get(q_id as usize) {
+            stats.total.fetch_add(1, Ordering::Relaxed);
+            stats.timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn timeout_count(
+        &self,
+        q_id: u16,
+    ) -> u64 {
+        self.queries
+            .get(q_id as usize)
+            .map(|stats| stats.timeouts.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// The run's timeouts and per-query timeout counts for meta.json; warns when any query
+    /// timed out.
+    pub fn timeout_report(
+        &self,
+        query_timeouts: &QueryTimeouts,
+    ) -> QueryTimeoutReport {
+        let report = QueryTimeoutReport::new(
+            query_timeouts,
+            &self.catalog,
+            self.catalog
+                .iter()
+                .map(|entry| (entry.id, self.timeout_count(entry.id))),
+        );
+        if report.total() > 0 {
+            warn!(
+                "{} queries hit their timeout: {:?}",
+                report.total(),
+                report.timeouts
+            );
+        }
+        report
+    }
+
+    /// The merged latency histogram, executions and successes of every group that ran.
+    fn group_stats(&self) -> BTreeMap<QueryGroup, (Histogram, u64, u64)> {
+        self.merged_stats(|entry| entry.group)
+    }
+
+    /// Likewise for every query type that ran.
+    fn type_stats(&self) -> BTreeMap<QueryType, (Histogram, u64, u64)> {
+        self.merged_stats(|entry| entry.q_type)
+    }
+
+    fn merged_stats<K: Ord>(
+        &self,
+        key: impl Fn(&QueryCatalogEntry) -> K,
+    ) -> BTreeMap<K, (Histogram, u64, u64)> {
+        let mut merged: BTreeMap<K, (Histogram, u64, u64)> = BTreeMap::new();
+        for entry in &self.catalog {
+            let Some(stats) = self.queries.get(entry.id as usize) else {
+                continue;
+            };
+            let executions = stats.total.load(Ordering::Relaxed);
+            if executions == 0 {
+                continue;
+            }
+            let Ok(hist) = stats.hist.lock() else {
+                continue;
+            };
+            let successes = stats.successes.load(Ordering::Relaxed);
+            match merged.get_mut(&key(entry)) {
+                Some((merged_hist, merged_executions, merged_successes)) => {
+                    if let Ok(sum) = merged_hist.checked_add(&hist) {
+                        *merged_hist = sum;
+                    }
+                    *merged_executions += executions;
+                    *merged_successes += successes;
+                }
+                None => {
+                    merged.insert(key(entry), (hist.clone(), executions, successes));
+                }
+            }
+        }
+        merged
+    }
+
+    pub fn report(
+        &self,
+        vendor: Vendor,
+    ) -> MetricsReport {
+        let summary = |hist: &Mutex<Histogram>, count: &AtomicU64| {
+            hist.lock()
+                .map(|h| LatencySummary::new(&h, count.load(Ordering::Relaxed)))
+                .unwrap_or_default()
+        };
+        let server_execution =
+            Some(summary(&self.server, &self.server_samples)).filter(|server| server.count > 0);
+        let connections = Some(ConnectionReport {
+            established: summary(&self.connects, &self.connect_count),
+            failures: self.connect_failures.load(Ordering::Relaxed),
+        })
+        .filter(|connections| connections.established.count + connections.failures > 0);
+        let queries = self
+            .catalog
+            .iter()
+            .filter_map(|entry| {
+                let stats = self.queries.get(entry.id as usize)?;
+                let executions = stats.total.load(Ordering::Relaxed);
+                if executions == 0 {
+                    return None;
+                }
+                let latency = summary(&stats.hist, &stats.successes);
+                Some(QueryReport {
+                    name: entry.name.clone(),
+                    executions,
+                    errors: executions - latency.count,
+                    timeouts: stats.timeouts.load(Ordering::Relaxed),
+                    latency,
+                    worst: stats.worst.lock().ok().and_then(|worst| worst.clone()),
+                })
+            })
+            .collect();
+        let groups = self
+            .group_stats()
+            .into_iter()
+            .map(|(group, (hist, executions, successes))| GroupReport {
+                group,
+                executions,
+                errors: executions - successes,
+                latency: LatencySummary::new(&hist, successes),
+            })
+            .collect();
+        let types = self
+            .type_stats()
+            .into_iter()
+            .map(|(q_type, (hist, executions, successes))| QueryTypeReport {
+                q_type,
+                executions,
+                errors: executions - successes,
+                latency: LatencySummary::new(&hist, successes),
+            })
+            .collect();
+        MetricsReport {
+            vendor: vendor.metric_label().to_string(),
+            latency: summary(&self.overall, &self.successes),
+            server_execution,
+            connections,
+            groups,
+            types,
+            queries,
+            phases: self.phase_reports(),
+        }
+    }
+
+    /// The full histograms, written to [`HISTOGRAMS_FILE`] after the run.
+    pub fn histograms(&self) -> BenchmarkResult<RunHistograms> {
+        let sparse = |hist: &Mutex<Histogram>| hist.lock().ok().map(|h| SparseHistogram::from(&*h));
+        Ok(RunHistograms {
+            overall: sparse(&self.overall)
+                .ok_or_else(|| OtherError("latency histogram is poisoned".to_string()))?,
+            server: Some(&self.server)
+                .filter(|_| self.server_samples.load(Ordering::Relaxed) > 0)
+                .and_then(sparse),
+            queries: self
+                .catalog
+                .iter()
+                .filter_map(|entry| {
+                    let stats = self.queries.get(entry.id as usize)?;
+                    if stats.successes.load(Ordering::Relaxed) == 0 {
+                        return None;
+                    }
+                    Some((entry.name.clone(), sparse(&stats.hist)?))
+                })
+                .collect(),
+            types: self
+                .type_stats()
+                .into_iter()
+                .filter(|(_, (_, _, successes))| *successes > 0)
+                .map(|(q_type, (hist, _, _))| {
+                    (q_type.as_str().to_string(), SparseHistogram::from(&hist))
+                })
+                .collect(),
+        })
+    }
+
+    /// Set the run's latency gauges, overall and per query.
+    pub fn export_to_prometheus(
+        &self,
+        vendor: Vendor,
+    ) {
+        // Accurate pXX latency gauges (microseconds)
+        if let Ok(hist) = self.overall.lock() {
+            let gauges = match vendor {
+                Vendor::Falkor => [
+                    (&*FALKOR_LATENCY_P50_US, 50.0),
+                    (&*FALKOR_LATENCY_P95_US, 95.0),
+                    (&*FALKOR_LATENCY_P99_US, 99.0),
+                    (&*FALKOR_LATENCY_P99_9_US, 99.9),
+                    (&*FALKOR_LATENCY_P99_99_US, 99.99),
+                    (&*FALKOR_LATENCY_MAX_US, 100.0),
+                ],
+                Vendor::Neo4j => [
+                    (&*NEO4J_LATENCY_P50_US, 50.0),
+                    (&*NEO4J_LATENCY_P95_US, 95.0),
+                    (&*NEO4J_LATENCY_P99_US, 99.0),
+                    (&*NEO4J_LATENCY_P99_9_US, 99.9),
+                    (&*NEO4J_LATENCY_P99_99_US, 99.99),
+                    (&*NEO4J_LATENCY_MAX_US, 100.0),
+                ],
+                Vendor::Memgraph => [
+                    (&*MEMGRAPH_LATENCY_P50_US, 50.0),
+                    (&*MEMGRAPH_LATENCY_P95_US, 95.0),
+                    (&*MEMGRAPH_LATENCY_P99_US, 99.0),
+                    (&*MEMGRAPH_LATENCY_P99_9_US, 99.9),
+                    (&*MEMGRAPH_LATENCY_P99_99_US, 99.99),
+                    (&*MEMGRAPH_LATENCY_MAX_US, 100.0),
+                ],
+            };
+            for (gauge, pct) in gauges {
+                gauge.set(percentile_us(&hist, pct) as i64);
+            }
+        }
+        if vendor == Vendor::Falkor {
+            if let Ok(hist) = self.server.lock() {
+                FALKOR_SERVER_EXECUTION_P50_US.set(percentile_us(&hist, 50.0) as i64);
+                FALKOR_SERVER_EXECUTION_P95_US.set(percentile_us(&hist, 95.0) as i64);
+                FALKOR_SERVER_EXECUTION_P99_US.set(percentile_us(&hist, 99.0) as i64);
+            }
+        }
+
+        // Clear old label values in case multiple runs happen in a single process.
+        let query_latency = match vendor {
+            Vendor::Falkor => &*FALKOR_QUERY_LATENCY_PCT_US,
+            Vendor::Neo4j => &*NEO4J_QUERY_LATENCY_PCT_US,
+            Vendor::Memgraph => &*MEMGRAPH_QUERY_LATENCY_PCT_US,
+        };
+        query_latency.reset();
+        let timeout_rate = match vendor {
+            Vendor::Falkor => &*FALKOR_QUERY_TIMEOUT_RATE_PCT,
+            Vendor::Neo4j => &*NEO4J_QUERY_TIMEOUT_RATE_PCT,
+            Vendor::Memgraph => &*MEMGRAPH_QUERY_TIMEOUT_RATE_PCT,
+        };
+        timeout_rate.reset();
+        QUERY_TIMEOUTS.reset();
+        QUERY_GROUP_LATENCY_US.reset();
+        QUERY_TYPE_LATENCY_US.reset();
+        PHASE_LATENCY_US.reset();
+
+        for (group, (hist, _, _)) in self.group_stats() {
+            for pct in [50.0, 95.0, 99.0] {
+                QUERY_GROUP_LATENCY_US
+                    .with_label_values(&[
+                        vendor.metric_label(),
+                        group.as_str(),
+                        format!("{}", pct as i64).as_str(),
+                    ])
+                    .set(percentile_us(&hist, pct) as i64);
+            }
+        }
+
+        for (q_type, (hist, _, _)) in self.type_stats() {
+            for pct in [50.0, 95.0, 99.0] {
+                QUERY_TYPE_LATENCY_US
+                    .with_label_values(&[
+                        vendor.metric_label(),
+                        q_type.as_str(),
+                        format!("{}", pct as i64).as_str(),
+                    ])
+                    .set(percentile_us(&hist, pct) as i64);
+            }
+        }
+
+        if let Some(plan) = &self.phase_plan {
+            for (phase, stats) in plan.phases().iter().zip(&self.phases) {
+                let Ok(hist) = stats.hist.lock() else {
+                    continue;
+                };
+                for pct in [50.0, 95.0, 99.0] {
+                    PHASE_LATENCY_US
+                        .with_label_values(&[
+                            vendor.metric_label(),
+                            phase.name.as_str(),
+                            format!("{}", pct as i64).as_str(),
+                        ])
+                        .set(percentile_us(&hist, pct) as i64);
+                }
+            }
+        }
+
+        for entry in &self.catalog {
+            let Some(stats) = self.queries.get(entry.id as usize) else {
+                continue;
+            };
+            let total = stats.total.load(Ordering::Relaxed);
+            let timeout = stats.timeouts.load(Ordering::Relaxed);
+            if total > 0 {
+                let rate_pct = (timeout as f64 / total as f64) * 100.0;
+                timeout_rate
+                    .with_label_values(&[entry.name.as_str()])
+                    .set(rate_pct);
+                QUERY_TIMEOUTS
+                    .with_label_values(&[vendor.metric_label(), entry.name.as_str()])
+                    .set(timeout as i64);
+            }
+            let Ok(h) = stats.hist.lock() else {
+                continue;
+            };
+
+            // Skip empty hists.
+            if percentile_us(&h, 50.0) == 0 {
+                continue;
+            }
+
+            for pct in QUERY_HIST_PCTS {
+                let v = percentile_us(&h, pct) as i64;
+                let pct_label = if (pct - pct.round()).abs() < f64::EPSILON {
+                    format!("{}", pct as i64)
+                } else {
+                    format!("{}", pct)
+                };
+                query_latency
+                    .with_label_values(&[entry.name.as_str(), pct_label.as_str()])
+                    .set(v);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queries_repository::{QueryGroup, QueryType};
+
+    fn entry(
+        id: u16,
+        name: &str,
+    ) -> QueryCatalogEntry {
+        QueryCatalogEntry {
+            id,
+            name: name.to_string(),
+            q_type: QueryType::Read,
+            group: QueryGroup::of(name),
+            timeout_ms: None,
+            shape: None,
+        }
+    }
+
+    #[test]
+    fn tracks_per_query_outcomes_and_worst_call() {
+        let collector =
+            MetricsCollector::new(vec![entry(0, "single_vertex_read"), entry(1, "pagerank")])
+                .unwrap();
+        let ms = Duration::from_millis;
+        collector.record_success(0, ms(2), "0");
+        collector.record_success(0, ms(40), "3");
+        collector.record_success(0, ms(5), "1");
+        collector.record_timeout(0);
+        collector.record_failure(0);
+        // Out-of-catalog ids are ignored per query but still count overall.
+        collector.record_success(9, ms(1), "0");
+
+        let report = collector.report(Vendor::Falkor);
+        assert_eq!(report.latency.count, 4);
+        assert!(report.server_execution.is_none());
+        assert!(report.connections.is_none());
+        assert_eq!(report.queries.len(), 1);
+        let query = &report.queries[0];
+        assert_eq!((query.executions, query.errors, query.timeouts), (5, 2, 1));
+        assert_eq!(
+            query.worst,
+            Some(WorstCall {
+                duration_us: 40_000,
+                worker: "3".to_string()
+            })
+        );
+        assert!(query.latency.p99_us >= 40_000);
+
+        let md = report.to_markdown();
+        assert!(md.contains("| single_vertex_read | 5 | 2 | 1 |"));
+        assert!(md.contains("(worker 3)"));
+        assert!(!md.contains("pagerank"));
+
+        collector.record_connect(ms(3));
+        collector.record_connect_failure();
+        let connections = collector.report(Vendor::Neo4j).connections.unwrap();
+        assert_eq!(
+            (connections.established.count, connections.failures),
+            (1, 1)
+        );
+        assert!(connections.established.p50_us >= 3_000);
+
+        // One group repeats the overall latency, so the markdown only breaks down several.
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(
+            (
+                report.groups[0].group,
+                report.groups[0].executions,
+                report.groups[0].errors
+            ),
+            (QueryGroup::Oltp, 5, 2)
+        );
+        assert!(!md.contains("Per group"));
+        let collector = MetricsCollector::new(vec![
+            entry(0, "single_vertex_read"),
+            entry(1, "analytics_degree_distribution"),
+            entry(2, "analytics_top_connected_users"),
+        ])
+        .unwrap();
+        collector.record_success(0, ms(1), "0");
+        collector.record_success(1, ms(80), "0");
+        collector.record_success(2, ms(120), "1");
+        let report = collector.report(Vendor::Falkor);
+        let analytics = report
+            .groups
+            .iter()
+            .find(|group| group.group == QueryGroup::Analytics)
+            .unwrap();
+        assert_eq!(analytics.latency.count, 2);
+        assert!(analytics.latency.p99_us >= 120_000 && analytics.latency.p50_us >= 80_000);
+        assert!(report.to_markdown().contains("| analytics | 2 | 0 |"));
+    }
+
+    #[test]
+    fn exported_samples_carry_the_canonical_vendor_label() {
+        let ms = Duration::from_millis;
+        for vendor in Vendor::ALL {
+            let collector = MetricsCollector::new(vec![entry(0, "single_vertex_read")]).unwrap();
+            collector.record_success(0, ms(2), "0");
+            collector.record_timeout(0);
+            collector.export_to_prometheus(vendor);
+
+            let mut labels = Vec::new();
+            for family in prometheus::gather() {
+                if ![
+                    "query_timeouts",
+                    "query_group_latency_us",
+                    "query_type_latency_us",
+                ]
+                .contains(&family.name())
+                {
+                    continue;
+                }
+                for metric in family.get_metric() {
+                    for label in metric.get_label() {
+                        if label.name() == "vendor" {
+                            labels.push(label.value().to_string());
+                        }
+                    }
+                }
+            }
+            assert!(!labels.is_empty());
+            assert!(labels.iter().all(|label| label == vendor.metric_label()));
+            // Results directories are named by the same word.
+            assert_eq!(vendor.to_string(), vendor.metric_label());
+        }
+    }
+
+    #[test]
+    fn reports_each_phase_apart() {
+        let phases: Phases = "read-only:1s,write-heavy:1s".parse().unwrap();
+        let collector = MetricsCollector::new(vec![entry(0, "single_vertex_read")])
+            .unwrap()
+            .with_phases(Some(&phases))
+            .unwrap();
+        let ms = Duration::from_millis;
+        collector.record_phase(0, ms(2), true);
+        collector.record_phase(999, ms(3), true);
+        collector.record_phase(1000, ms(50), true);
+        collector.record_phase(1500, ms(60), false);
+        // Past the last phase, e.g. a query queued just before the end.
+        collector.record_phase(2000, ms(70), true);
+
+        let report = collector.report(Vendor::Falkor);
+        let phases: Vec<_> = report
+            .phases
+            .iter()
+            .map(|phase| (phase.phase.as_str(), phase.executions, phase.errors))
+            .collect();
+        assert_eq!(phases, [("read-only", 2, 0), ("write-heavy", 2, 1)]);
+        assert!(report.phases[0].latency.p99_us < 50_000);
+        assert!(report.phases[1].latency.p50_us >= 50_000);
+        assert!(report
+            .to_markdown()
+            .contains("| write-heavy | 0.9 | 2 | 1 |"));
+        assert!(MetricsCollector::new(Vec::new())
+            .unwrap()
+            .report(Vendor::Falkor)
+            .phases
+            .is_empty());
+    }
+
+    #[test]
+    fn buckets_latency_per_window_of_the_schedule() {
+        let collector = MetricsCollector::new(vec![entry(0, "single_vertex_read")]).unwrap();
+        let ms = Duration::from_millis;
+        for _ in 0..99 {
+            collector.record_window(1_000, ms(2), true);
+        }
+        collector.record_window(9_999, ms(200), true);
+        collector.record_window(25_000, ms(40), false);
+
+        let csv = collector.latency_heatmap_csv();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|l| l.split(',').collect()).collect();
+        assert_eq!(
+            rows[0][..4],
+            ["window_start_s", "executions", "errors", "p10_us"]
+        );
+        assert_eq!(rows[0][10..], ["p99_9_us", "max_us"]);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[1][..3], ["0", "100", "0"]);
+        let p50: u64 = rows[1][5].parse().unwrap();
+        let max: u64 = rows[1][11].parse().unwrap();
+        assert!((2_000..2_100).contains(&p50));
+        assert!(max >= 200_000);
+        // An empty window stays in the series, a failure-only one has no latency.
+        assert_eq!(rows[2][..4], ["10", "0", "0", "0"]);
+        assert_eq!(rows[3][..5], ["20", "1", "1", "0", "0"]);
+    }
+
+    #[test]
+    fn reports_reads_and_writes_apart() {
+        let write = QueryCatalogEntry {
+            q_type: QueryType::Write,
+            ..entry(1, "single_edge_write")
+        };
+        let collector = MetricsCollector::new(vec![entry(0, "single_vertex_read"), write]).unwrap();
+        let ms = Duration::from_millis;
+        collector.record_success(0, ms(1), "0");
+        collector.record_success(0, ms(2), "1");
+        collector.record_success(1, ms(30), "0");
+        collector.record_failure(1);
+
+        let report = collector.report(Vendor::Memgraph);
+        let types: Vec<(QueryType, u64, u64)> = report
+            .types
+            .iter()
+            .map(|t| (t.q_type, t.executions, t.errors))
+            .collect();
+        assert_eq!(
+            types,
+            vec![(QueryType::Read, 2, 0), (QueryType::Write, 2, 1)]
+        );
+        assert!(report.types[0].latency.p99_us < 30_000);
+        assert!(report.types[1].latency.p50_us >= 30_000);
+        assert!(report.to_markdown().contains("| write | 2 | 1 |"));
+
+        let histograms = LatencyHistograms::from_runs(&[collector.histograms().unwrap()]).unwrap();
+        assert!(percentile_us(&histograms.types["write"], 50.0) >= 30_000);
+        assert!(percentile_us(&histograms.types["read"], 99.0) < 30_000);
+    }
+
+    #[test]
+    fn histograms_round_trip_and_add_up() {
+        let collector = MetricsCollector::new(vec![
+            entry(0, "single_vertex_read"),
+            entry(1, "analytics_degree_distribution"),
+            entry(2, "pagerank"),
+        ])
+        .unwrap();
+        let ms = Duration::from_millis;
+        collector.record_success(0, ms(1), "0");
+        collector.record_success(1, ms(80), "0");
+        collector.record_failure(2);
+
+        let written = serde_json::to_string(&collector.histograms().unwrap()).unwrap();
+        let run: RunHistograms = serde_json::from_str(&written).unwrap();
+        assert!(run.server.is_none());
+        assert_eq!(
+            run.queries.keys().collect::<Vec<_>>(),
+            vec!["analytics_degree_distribution", "single_vertex_read"]
+        );
+
+        let one = LatencyHistograms::from_runs(std::slice::from_ref(&run)).unwrap();
+        let two = LatencyHistograms::from_runs(&[run.clone(), run]).unwrap();
+        assert_eq!(
+            percentile_us(&one.overall, 99.0),
+            percentile_us(&two.overall, 99.0)
+        );
+        assert!(percentile_us(&one.group("analytics").unwrap(), 50.0) >= 80_000);
+        assert!(percentile_us(&two.group("oltp").unwrap(), 99.0) < 80_000);
+        assert!(two.group("algorithm").is_none());
+    }
+}
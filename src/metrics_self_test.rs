@@ -0,0 +1,257 @@
+//! `run --verify-metrics`: check the vendor's metric wiring before the workload starts. Each
+//! driver writes its own statics and labels, and a copy-paste slip, like one vendor's failures
+//! counted under another's label, corrupts every report of the run without failing anything.
+//! The self-test sends one query due a second ago that returns a row and one that fails, through
+//! the same driver call the workers use. It then checks that only this vendor's attempt and error
+//! counters, result rows and deadline-offset gauge moved. `metrics.prom` counts from after the
+//! self-test.
+
+use crate::compact_query::{CompactQuery, QueryParams, QueryTemplates};
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::queries_repository::{PreparedQuery, QueryType};
+use crate::query::QueryBuilder;
+use crate::run_metrics;
+use crate::scenario::Vendor;
+use crate::scheduler::Msg;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::info;
+
+/// The query name the self-test's queries are counted under.
+pub const SELF_TEST_QUERY: &str = "metrics_self_test";
+
+/// How late the self-test's queries are due, which their deadline offset has to show.
+const LATENESS: Duration = Duration::from_secs(1);
+
+const VENDORS: [Vendor; 3] = [Vendor::Falkor, Vendor::Neo4j, Vendor::Memgraph];
+
+/// Send the self-test's queries with `execute`, which reports whether a query succeeded, and
+/// fail when the metrics they moved aren't `vendor`'s alone.
+pub async fn verify(
+    vendor: Vendor,
+    mut execute: impl AsyncFnMut(&Msg<CompactQuery>) -> bool,
+) -> BenchmarkResult<()> {
+    let before = Samples::gather()?;
+    let succeeded = execute(&late_message(vendor, "RETURN 1")?).await;
+    let failed = !execute(&late_message(vendor, "RETURN metrics_self_test(")?).await;
+    let after = Samples::gather()?;
+    let mut problems = check(vendor, &before, &after);
+    if !succeeded {
+        problems.insert(0, "the query `RETURN 1` failed".to_string());
+    }
+    if !failed {
+        problems.insert(0, "a query that can't parse succeeded".to_string());
+    }
+    if !problems.is_empty() {
+        return Err(OtherError(format!(
+            "--verify-metrics found {} metric wiring problems: {}",
+            vendor,
+            problems.join("; ")
+        )));
+    }
+    info!("Verified the {} metric wiring", vendor);
+    // Leave the self-test out of metrics.prom.
+    run_metrics::begin()
+}
+
+fn late_message(
+    vendor: Vendor,
+    cypher: &str,
+) -> BenchmarkResult<Msg<CompactQuery>> {
+    let query = PreparedQuery::new(
+        0,
+        SELF_TEST_QUERY.to_string(),
+        QueryType::Read,
+        QueryBuilder::new().text(cypher).build(),
+    );
+    let now = Instant::now();
+    Ok(Msg {
+        start_time: now.checked_sub(LATENESS).unwrap_or(now),
+        offset: 0,
+        payload: QueryTemplates::new(vendor, QueryParams::Parameterized).compact(query)?,
+    })
+}
+
+/// What one success and one failure of the self-test query must have moved for `vendor`, and
+/// nothing for the other vendors; the problems found.
+fn check(
+    vendor: Vendor,
+    before: &Samples,
+    after: &Samples,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    for other in VENDORS {
+        let own = other == vendor;
+        let delta = |metric: &str, failure: Option<&str>| {
+            after.sum(metric, other, failure) - before.sum(metric, other, failure)
+        };
+        for (what, metric, failure, expected) in [
+            ("attempts", "operations_total", Some(""), 2.0),
+            ("errors", "operations_total", Some("error"), 1.0),
+            ("timeouts", "operations_total", Some("timeout"), 0.0),
+            ("result sets", "query_result_rows_count", None, 1.0),
+            ("result rows", "query_result_rows_sum", None, 1.0),
+        ] {
+            let expected = if own { expected } else { 0.0 };
+            let counted = delta(metric, failure);
+            if counted != expected {
+                problems.push(format!(
+                    "{} counted {} {} under {}, expected {}",
+                    metric,
+                    counted,
+                    what,
+                    other.metric_label(),
+                    expected
+                ));
+            }
+        }
+        let gauge = deadline_offset_gauge(other);
+        let (was, is) = (before.value(gauge), after.value(gauge));
+        if own && is.is_none_or(|offset| offset > -(LATENESS.as_millis() as f64)) {
+            problems.push(format!(
+                "{} is {:?}, not the self-test's offset of at most -{}ms",
+                gauge,
+                is,
+                LATENESS.as_millis()
+            ));
+        }
+        if !own && was != is {
+            problems.push(format!("{} moved from {:?} to {:?}", gauge, was, is));
+        }
+    }
+    problems
+}
+
+fn deadline_offset_gauge(vendor: Vendor) -> &'static str {
+    match vendor {
+        Vendor::Falkor => "falkordb_msg_deadline_offset",
+        Vendor::Neo4j => "neo4j_msg_deadline_offset",
+        Vendor::Memgraph => "memgraph_msg_deadline_offset",
+    }
+}
+
+type MetricLabels = BTreeMap<String, String>;
+
+/// The samples of the registered metrics, by name and labels.
+struct Samples(BTreeMap<String, Vec<(MetricLabels, f64)>>);
+
+impl Samples {
+    fn gather() -> BenchmarkResult<Self> {
+        Ok(Self::parse(&run_metrics::gather_text()?))
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut samples: BTreeMap<String, Vec<_>> = BTreeMap::new();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let Some((key, value)) = line.rsplit_once(' ') else {
+                continue;
+            };
+            let Ok(value) = value.parse::<f64>() else {
+                continue;
+            };
+            let (name, labels) = match key.split_once('{') {
+                Some((name, labels)) => (name, labels.trim_end_matches('}')),
+                None => (key, ""),
+            };
+            let labels = labels
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(label, value)| (label.to_string(), value.trim_matches('"').to_string()))
+                .collect();
+            samples
+                .entry(name.to_string())
+                .or_default()
+                .push((labels, value));
+        }
+        Samples(samples)
+    }
+
+    /// The self-test query's samples of `metric` for `vendor`, of the `failure` type when given.
+    fn sum(
+        &self,
+        metric: &str,
+        vendor: Vendor,
+        failure: Option<&str>,
+    ) -> f64 {
+        self.0
+            .get(metric)
+            .into_iter()
+            .flatten()
+            .filter(|(labels, _)| {
+                labels.get("vendor").map(String::as_str) == Some(vendor.metric_label())
+                    && labels.get("name").map(String::as_str) == Some(SELF_TEST_QUERY)
+                    && failure.is_none_or(|failure| {
+                        labels.get("type").map(String::as_str).unwrap_or("") == failure
+                    })
+            })
+            .map(|(_, value)| value)
+            .sum()
+    }
+
+    fn value(
+        &self,
+        metric: &str,
+    ) -> Option<f64> {
+        self.0.get(metric)?.first().map(|(_, value)| *value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(
+        ops: &[(&str, &str, u32)],
+        rows: &[(&str, u32)],
+        offsets: [i64; 3],
+    ) -> Samples {
+        let mut text = String::from("# TYPE operations_total counter\n");
+        for (vendor, failure, count) in ops {
+            text.push_str(&format!(
+                "operations_total{{dataset=\"\",dataset_size=\"\",name=\"metrics_self_test\",spawn_id=\"self-test\",type=\"{}\",vendor=\"{}\"}} {}\n",
+                failure, vendor, count
+            ));
+        }
+        for (vendor, count) in rows {
+            text.push_str(&format!(
+                "query_result_rows_count{{name=\"metrics_self_test\",vendor=\"{0}\"}} {1}\n\
+                 query_result_rows_sum{{name=\"metrics_self_test\",vendor=\"{0}\"}} {1}\n",
+                vendor, count
+            ));
+        }
+        for (vendor, offset) in VENDORS.iter().zip(offsets) {
+            text.push_str(&format!("{} {}\n", deadline_offset_gauge(*vendor), offset));
+        }
+        Samples::parse(&text)
+    }
+
+    #[test]
+    fn only_the_vendors_own_metrics_may_move() {
+        let before = samples(&[], &[], [0, 0, 0]);
+        let wired = samples(
+            &[("memgraph", "", 2), ("memgraph", "error", 1)],
+            &[("memgraph", 1)],
+            [0, 0, -1003],
+        );
+        assert_eq!(
+            check(Vendor::Memgraph, &before, &wired),
+            Vec::<String>::new()
+        );
+
+        // Neo4j's failures counted as FalkorDB's, and its deadline offset written to Memgraph's
+        // gauge.
+        let crossed = samples(
+            &[("neo4j", "", 2), ("falkor", "error", 1)],
+            &[("neo4j", 1)],
+            [0, 0, -1003],
+        );
+        let problems = check(Vendor::Neo4j, &before, &crossed);
+        assert_eq!(problems.len(), 4, "{:#?}", problems);
+        assert!(problems[0].contains("1 errors under falkor, expected 0"));
+        assert!(problems[1].contains("0 errors under neo4j, expected 1"));
+        assert!(problems[2].starts_with("neo4j_msg_deadline_offset is Some(0.0)"));
+        assert!(problems[3].starts_with("memgraph_msg_deadline_offset moved"));
+    }
+}
@@ -1,11 +1,17 @@
+use crate::backup_manifest::{check_before_restore, BackupManifest, MANIFEST_FILE};
+use crate::bolt_ready::BoltReadiness;
+use crate::database_config::DEFAULT_NEO4J_DATABASE;
 use crate::error::BenchmarkError::{FailedToSpawnProcessError, OtherError};
 use crate::error::BenchmarkResult;
 use crate::neo4j_client::Neo4jClient;
-use crate::scenario::Spec;
-use crate::utils::{create_directory_if_not_exists, spawn_command};
+use crate::neo4j_memory::Neo4jMemory;
+use crate::resource_limits::ServerLimits;
+use crate::scenario::{Spec, Vendor};
+use crate::server_output::ServerOutput;
+use crate::utils::{create_directory_if_not_exists, kill_process, spawn_command};
 use crate::{
     prometheus_metrics, CPU_USAGE_GAUGE, MEM_USAGE_GAUGE, NEO4J_CPU_USAGE_GAUGE,
-    NEO4J_MEM_USAGE_GAUGE,
+    NEO4J_MEM_USAGE_GAUGE, NEO4J_PROCESS_MEMORY_BYTES,
 };
 use std::env;
 use std::ffi::OsString;
@@ -25,6 +31,9 @@ pub struct Neo4j {
     neo4j_home: String,
     prom_shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
     prom_process_handle: Option<JoinHandle<()>>,
+    server_limits: ServerLimits,
+    memory: Neo4jMemory,
+    server_output: Option<ServerOutput>,
 }
 
 impl Default for Neo4j {
@@ -33,6 +42,11 @@ impl Default for Neo4j {
     }
 }
 
+/// `NEO4J_HOME`, else where `scripts/download-neo4j.sh` unpacks Neo4j.
+pub(crate) fn neo4j_home() -> String {
+    env::var("NEO4J_HOME").unwrap_or_else(|_| String::from("./downloads/neo4j_local"))
+}
+
 impl Neo4j {
     pub fn store_size_bytes(&self) -> u64 {
         let store_dir = Path::new(&self.neo4j_home).join("data/databases/neo4j");
@@ -40,8 +54,7 @@ impl Neo4j {
     }
 
     fn new() -> Neo4j {
-        let neo4j_home =
-            env::var("NEO4J_HOME").unwrap_or_else(|_| String::from("./downloads/neo4j_local"));
+        let neo4j_home = neo4j_home();
         let uri = env::var("NEO4J_URI").unwrap_or_else(|_| String::from("127.0.0.1:7687"));
         let user = env::var("NEO4J_USER").unwrap_or_else(|_| String::from("neo4j"));
         let password = env::var("NEO4J_PASSWORD").unwrap_or_else(|_| String::from("h6u4krd10"));
@@ -52,9 +65,45 @@ impl Neo4j {
             neo4j_home,
             prom_shutdown_tx: None,
             prom_process_handle: None,
+            server_limits: ServerLimits::default(),
+            memory: Neo4jMemory::default(),
+            server_output: None,
         }
     }
 
+    /// Launch the server under `server_limits` from now on (`--server-mem-limit`, `--server-cpus`).
+    pub fn set_server_limits(
+        &mut self,
+        server_limits: ServerLimits,
+    ) {
+        self.server_limits = server_limits;
+    }
+
+    /// Write `memory` into neo4j.conf whenever the server starts (`--neo4j-heap`,
+    /// `--neo4j-pagecache`).
+    pub fn set_memory(
+        &mut self,
+        memory: Neo4jMemory,
+    ) {
+        self.memory = memory;
+    }
+
+    /// Capture the server's stdout and stderr under `output` instead of the terminal.
+    pub fn set_server_output(
+        &mut self,
+        output: ServerOutput,
+    ) {
+        self.server_output = Some(output);
+    }
+
+    /// The memory settings neo4j.conf has in effect.
+    pub async fn memory_settings(&self) -> BenchmarkResult<Neo4jMemory> {
+        let conf = fs::read_to_string(self.neo4j_conf())
+            .await
+            .map_err(|e| OtherError(format!("Failed reading {}: {}", self.neo4j_conf(), e)))?;
+        Ok(Neo4jMemory::read(&conf))
+    }
+
     pub async fn restore_db(
         &self,
         spec: Spec<'_>,
@@ -67,7 +116,7 @@ impl Neo4j {
             self.uri.to_string(),
             self.user.to_string(),
             self.password.to_string(),
-            Some("neo4j".to_string()),
+            Some(DEFAULT_NEO4J_DATABASE.to_string()),
         )
         .await
     }
@@ -80,13 +129,19 @@ impl Neo4j {
         format!("{}/run/neo4j.pid", self.neo4j_home.clone())
     }
 
+    fn neo4j_conf(&self) -> String {
+        format!("{}/conf/neo4j.conf", self.neo4j_home)
+    }
+
     fn neo4j_admin(&self) -> String {
         format!("{}/bin/neo4j-admin", self.neo4j_home.clone())
     }
 
+    /// Dump the database into the spec's backup directory, with `manifest` next to it.
     pub async fn dump(
         &self,
         spec: Spec<'_>,
+        manifest: &BackupManifest,
     ) -> BenchmarkResult<Output> {
         if fs::metadata(&self.neo4j_pid()).await.is_ok() {
             return Err(OtherError(
@@ -107,7 +162,11 @@ impl Neo4j {
             backup_path.as_str(),
             "neo4j",
         ];
-        spawn_command(command.as_str(), &args).await
+        let out = spawn_command(command.as_str(), &args).await?;
+        manifest
+            .write(&Path::new(&backup_path).join(MANIFEST_FILE))
+            .await?;
+        Ok(out)
     }
 
     pub async fn restore(
@@ -122,6 +181,7 @@ impl Neo4j {
         }
         let command = self.neo4j_admin();
         let backup_path = spec.backup_path();
+        check_before_restore(&Path::new(&backup_path).join(MANIFEST_FILE), &spec).await?;
 
         let args = [
             "database",
@@ -170,27 +230,36 @@ impl Neo4j {
         if fs::metadata(&self.neo4j_pid()).await.is_ok() {
             self.stop(false).await?;
         }
+        if !self.memory.is_empty() {
+            let conf = fs::read_to_string(self.neo4j_conf())
+                .await
+                .map_err(|e| OtherError(format!("Failed reading {}: {}", self.neo4j_conf(), e)))?;
+            fs::write(self.neo4j_conf(), self.memory.apply(&conf))
+                .await
+                .map_err(|e| OtherError(format!("Failed writing {}: {}", self.neo4j_conf(), e)))?;
+        }
         info!("starting Neo4j process");
-        let child = Command::new(self.neo4j_binary())
-            .arg("console")
-            // .stdout(Stdio::null()) // Redirect stdout to null
-            .spawn()
-            .map_err(|e| {
-                FailedToSpawnProcessError(
-                    e,
-                    format!(
-                        "Failed to spawn Neo4j process, cmd: {} console",
-                        self.neo4j_binary()
-                    )
-                    .to_string(),
-                )
-            })?;
-
-        while !self.is_running().await? {
-            tokio::time::sleep(Duration::from_secs(1)).await;
+        let (program, prefix) = self.server_limits.command(&self.neo4j_binary());
+        let mut command = Command::new(program);
+        command.args(prefix).arg("console");
+        let mut child = match &self.server_output {
+            Some(output) => output.spawn("neo4j", &mut command),
+            None => command.spawn(),
         }
+        .map_err(|e| {
+            FailedToSpawnProcessError(
+                e,
+                format!(
+                    "Failed to spawn Neo4j process, cmd: {} console",
+                    self.neo4j_binary()
+                )
+                .to_string(),
+            )
+        })?;
 
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        BoltReadiness::new("Neo4j", &self.uri, &self.user, &self.password, None)
+            .wait(&mut child)
+            .await?;
         let pid: u32 = child.id();
 
         info!("Neo4j is running: {}", pid);
@@ -224,6 +293,17 @@ impl Neo4j {
         spawn_command(command.as_str(), &args).await
     }
 
+    /// SIGKILL the server (no clean shutdown) and start it again; used by `--chaos kill-server`.
+    pub async fn kill_and_restart(&mut self) -> BenchmarkResult<()> {
+        let pid = get_neo4j_server_pid()
+            .ok_or_else(|| OtherError("Neo4j server process not found".to_string()))?;
+        kill_process(pid).await?;
+        // A killed server leaves its pid file behind, which would make `start` run `neo4j stop`.
+        let _ = fs::remove_file(self.neo4j_pid()).await;
+        self.start().await?;
+        Ok(())
+    }
+
     pub async fn is_running(&self) -> BenchmarkResult<bool> {
         trace!("Starting Neo4j process: {} status", self.neo4j_binary());
 
@@ -264,6 +344,8 @@ async fn report_metrics(sys: std::sync::Arc<std::sync::Mutex<System>>) -> Benchm
             NEO4J_CPU_USAGE_GAUGE.set(cpu_usage);
             let mem_used = process.memory() as i64;
             NEO4J_MEM_USAGE_GAUGE.set(mem_used);
+            NEO4J_PROCESS_MEMORY_BYTES.set(mem_used);
+            prometheus_metrics::report_process_disk_io(Vendor::Neo4j, process);
         }
     }
     Ok(())
@@ -1,23 +1,56 @@
+use crate::cache_control::WARMUP_QUERIES;
+use crate::compact_query::CompactQuery;
 use crate::data_prep::bench_capacity;
-use crate::error::BenchmarkError::{Neo4rsError, OtherError};
+use crate::error::BenchmarkError::{Neo4rsError, OtherError, QueryTimeout};
 use crate::error::BenchmarkResult;
-use crate::queries_repository::PreparedQuery;
+use crate::index_parity::IndexDescriptor;
+use crate::index_wait::{IndexPopulation, IndexState};
+use crate::neo4j_gc::{GcDeltas, GcTotals};
+use crate::prometheus_metrics::EndpointMetricsPoller;
+use crate::queries_repository::{PreparedQuery, VectorWorkload};
+use crate::query_plans::{render_neo4j_plan, PlanMode};
+use crate::query_timeout::QueryTimeouts;
+use crate::result_size;
+use crate::scenario::Vendor;
 use crate::scheduler::Msg;
+use crate::simulate::SimulatedLatency;
+use crate::slow_queries::param_json;
 use crate::{NEO4J_MSG_DEADLINE_OFFSET_GAUGE, OPERATION_COUNTER};
 use futures::stream::TryStreamExt;
 use futures::{Stream, StreamExt};
 use histogram::Histogram;
-use neo4rs::{query, BoltList, BoltMap, BoltType, ConfigBuilder, Graph, Row};
+use neo4rs::{query, BoltList, BoltMap, BoltType, Config, ConfigBuilder, Graph, Row};
 use std::hint::black_box;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::io;
 use tokio::time::Instant;
 use tracing::{error, info, trace};
 
+/// Client-side timeout of a measured query unless `run --query-timeout` says otherwise.
+pub const DEFAULT_NEO4J_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Port of the HTTP API plans are read from unless `NEO4J_HTTP_URI` says otherwise.
+const NEO4J_HTTP_PORT: u16 = 7474;
+
 #[derive(Clone)]
 pub struct Neo4jClient {
     graph: Graph,
+    query_timeouts: Arc<QueryTimeouts>,
+    // The same target over a single connection, for `reconnect`.
+    single_connection: Config,
+    // Bolt drops the plan of an EXPLAIN/PROFILE; the HTTP API returns it.
+    http_api: Neo4jHttpApi,
+    // The GC totals of the previous `collect_gc_metrics`, shared by clones.
+    gc: Arc<Mutex<GcDeltas>>,
+}
+
+#[derive(Clone)]
+struct Neo4jHttpApi {
+    commit_url: String,
+    user: String,
+    password: String,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -45,36 +78,83 @@ impl Neo4jClient {
         password: String,
         database: Option<String>,
     ) -> BenchmarkResult<Neo4jClient> {
-        let config = ConfigBuilder::default()
-            .uri(&uri)
-            .user(&user)
-            .password(&password);
-
-        let config = if let Some(db) = database {
-            config.db(db)
-        } else {
-            config
+        let config = || {
+            let config = ConfigBuilder::default()
+                .uri(&uri)
+                .user(&user)
+                .password(&password);
+            if let Some(db) = database.clone() {
+                config.db(db)
+            } else {
+                config
+            }
         };
 
-        let graph = Graph::connect(config.build().map_err(Neo4rsError)?)
+        let graph = Graph::connect(config().build().map_err(Neo4rsError)?)
             .await
             .map_err(Neo4rsError)?;
-        Ok(Neo4jClient { graph })
+        let http_base = std::env::var("NEO4J_HTTP_URI").unwrap_or_else(|_| {
+            let host = uri.rsplit("://").next().unwrap_or(&uri);
+            let host = host.split([':', '/']).next().unwrap_or(host);
+            format!("http://{}:{}", host, NEO4J_HTTP_PORT)
+        });
+        Ok(Neo4jClient {
+            graph,
+            query_timeouts: Arc::new(QueryTimeouts::uniform(DEFAULT_NEO4J_QUERY_TIMEOUT)),
+            single_connection: config().max_connections(1).build().map_err(Neo4rsError)?,
+            http_api: Neo4jHttpApi {
+                commit_url: format!(
+                    "{}/db/{}/tx/commit",
+                    http_base.trim_end_matches('/'),
+                    database.as_deref().unwrap_or("neo4j")
+                ),
+                user: user.clone(),
+                password: password.clone(),
+            },
+            gc: Arc::new(Mutex::new(GcDeltas::default())),
+        })
+    }
+
+    /// Replace this client's connection pool with a fresh single connection, completing a round
+    /// trip on it so the Bolt handshake is done, for `run --reconnect-every`. Clones keep the old
+    /// pool.
+    pub async fn reconnect(&mut self) -> BenchmarkResult<()> {
+        let graph = Graph::connect(self.single_connection.clone())
+            .await
+            .map_err(Neo4rsError)?;
+        graph.run(query("RETURN 1")).await.map_err(Neo4rsError)?;
+        self.graph = graph;
+        Ok(())
+    }
+
+    pub fn default_query_timeout(&self) -> Duration {
+        self.query_timeouts.default_timeout()
     }
+
+    pub fn set_query_timeouts(
+        &mut self,
+        query_timeouts: QueryTimeouts,
+    ) {
+        self.query_timeouts = Arc::new(query_timeouts);
+    }
+
     pub async fn execute_prepared_query<S: AsRef<str>>(
         &mut self,
         worker_id: S,
-        msg: &Msg<PreparedQuery>,
-        simulate: &Option<usize>,
+        msg: &Msg<CompactQuery>,
+        simulate: &Option<SimulatedLatency>,
     ) -> BenchmarkResult<()> {
-        let Msg {
-            payload: PreparedQuery { bolt, q_name, .. },
-            ..
-        } = msg;
+        if msg.payload.is_transaction() {
+            return self
+                .execute_prepared_transaction(worker_id.as_ref(), msg, simulate)
+                .await;
+        }
+
+        let CompactQuery { q_name, q_id, .. } = &msg.payload;
 
         let worker_id = worker_id.as_ref();
-        let q_name = q_name.as_str();
-        let timeout = Duration::from_secs(60);
+        let q_name = &**q_name;
+        let timeout = self.query_timeouts.for_query(*q_id);
         let offset = msg.compute_offset_ms();
 
         NEO4J_MSG_DEADLINE_OFFSET_GAUGE.set(offset);
@@ -83,48 +163,142 @@ impl Neo4jClient {
             tokio::time::sleep(Duration::from_millis(offset as u64)).await;
         }
 
-        let bolt_query = bolt.query.as_str();
-        let bolt_params = bolt.clone().params;
-
-        let neo4j_result = self
-            .graph
-            .execute(neo4rs::query(bolt_query).params(bolt_params));
+        let neo4j_result = self.graph.execute(msg.payload.statement().bolt());
 
-        if let Some(delay) = simulate {
-            if *delay > 0 {
-                let delay: u64 = *delay as u64;
-                tokio::time::sleep(Duration::from_millis(delay)).await;
+        if let Some(latency) = simulate {
+            let delay = latency.sample(&mut rand::rng());
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
             }
             return Ok(());
         }
 
         let neo4j_result = tokio::time::timeout(timeout, neo4j_result).await;
         OPERATION_COUNTER
-            .with_label_values(&["neo4j", worker_id, "", q_name, "", ""])
+            .with_label_values(&[Vendor::Neo4j.metric_label(), worker_id, "", q_name, "", ""])
             .inc();
         match neo4j_result {
             Ok(Ok(mut stream)) => {
+                let mut rows = 0;
                 while let Ok(Some(row)) = stream.next().await {
                     trace!("Row: {:?}", row);
                     black_box(row);
+                    rows += 1;
                 }
+                result_size::observe(Vendor::Neo4j.metric_label(), q_name, rows);
             }
             Ok(Err(e)) => {
                 OPERATION_COUNTER
-                    .with_label_values(&["neo4j", worker_id, "error", q_name, "", ""])
+                    .with_label_values(&[
+                        Vendor::Neo4j.metric_label(),
+                        worker_id,
+                        "error",
+                        q_name,
+                        "",
+                        "",
+                    ])
                     .inc();
                 return Err(Neo4rsError(e));
             }
             Err(_) => {
                 OPERATION_COUNTER
-                    .with_label_values(&["neo4j", worker_id, "timeout", q_name, "", ""])
+                    .with_label_values(&[
+                        Vendor::Neo4j.metric_label(),
+                        worker_id,
+                        "timeout",
+                        q_name,
+                        "",
+                        "",
+                    ])
                     .inc();
-                return Err(OtherError("Timeout".to_string()));
+                return Err(QueryTimeout(timeout));
             }
         }
         Ok(())
     }
 
+    /// Execute a transaction entry: every statement runs inside one explicit Bolt transaction,
+    /// consuming its rows, and the transaction is committed at the end.
+    async fn execute_prepared_transaction(
+        &mut self,
+        worker_id: &str,
+        msg: &Msg<CompactQuery>,
+        simulate: &Option<SimulatedLatency>,
+    ) -> BenchmarkResult<()> {
+        let CompactQuery {
+            q_name,
+            q_id,
+            statements,
+            ..
+        } = &msg.payload;
+        let q_name = &**q_name;
+        let timeout = self.query_timeouts.for_query(*q_id);
+        let offset = msg.compute_offset_ms();
+
+        NEO4J_MSG_DEADLINE_OFFSET_GAUGE.set(offset);
+        if offset > 0 {
+            // sleep offset millis
+            tokio::time::sleep(Duration::from_millis(offset as u64)).await;
+        }
+
+        if let Some(latency) = simulate {
+            let delay = latency.sample(&mut rand::rng());
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            return Ok(());
+        }
+
+        let transaction = async {
+            let mut txn = self.graph.start_txn().await.map_err(Neo4rsError)?;
+            for statement in statements {
+                let mut stream = txn
+                    .execute(statement.bolt())
+                    .await
+                    .map_err(Neo4rsError)?;
+                while let Ok(Some(row)) = stream.next(txn.handle()).await {
+                    trace!("Row: {:?}", row);
+                    black_box(row);
+                }
+            }
+            txn.commit().await.map_err(Neo4rsError)
+        };
+
+        let result = tokio::time::timeout(timeout, transaction).await;
+        OPERATION_COUNTER
+            .with_label_values(&[Vendor::Neo4j.metric_label(), worker_id, "", q_name, "", ""])
+            .inc();
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => {
+                OPERATION_COUNTER
+                    .with_label_values(&[
+                        Vendor::Neo4j.metric_label(),
+                        worker_id,
+                        "error",
+                        q_name,
+                        "",
+                        "",
+                    ])
+                    .inc();
+                Err(e)
+            }
+            Err(_) => {
+                OPERATION_COUNTER
+                    .with_label_values(&[
+                        Vendor::Neo4j.metric_label(),
+                        worker_id,
+                        "timeout",
+                        q_name,
+                        "",
+                        "",
+                    ])
+                    .inc();
+                Err(QueryTimeout(timeout))
+            }
+        }
+    }
+
     /// Best-effort: estimate Neo4j store size (data + schema/native indexes) via JMX exposed through Cypher.
     ///
     /// This works for external endpoints (where we can't inspect the filesystem), but requires the
@@ -149,7 +323,8 @@ impl Neo4jClient {
     /// Best-effort: collect JVM memory usage via JMX exposed through Cypher.
     ///
     /// On external endpoints, this can be used as a proxy for "process" memory when RSS isn't accessible.
-    /// If successful, this also updates `neo4j_memory_usage` (bytes) to heap+nonheap used.
+    /// If successful, this also updates `neo4j_reported_memory_bytes` and `neo4j_memory_usage`
+    /// (bytes) to heap+nonheap used.
     pub async fn collect_jvm_memory_metrics(&self) {
         match self.jvm_memory_used_bytes_via_jmx().await {
             Ok((heap_used, nonheap_used)) => {
@@ -157,6 +332,7 @@ impl Neo4jClient {
                 crate::NEO4J_JVM_NONHEAP_USED_BYTES.set(nonheap_used.min(i64::MAX as u64) as i64);
 
                 let total = heap_used.saturating_add(nonheap_used);
+                crate::NEO4J_REPORTED_MEMORY_BYTES.set(total.min(i64::MAX as u64) as i64);
                 if total > 0 {
                     // For external endpoints we otherwise don't have a value for neo4j_memory_usage.
                     crate::NEO4J_MEM_USAGE_GAUGE.set(total.min(i64::MAX as u64) as i64);
@@ -169,6 +345,44 @@ impl Neo4jClient {
         }
     }
 
+    /// Best-effort: add the garbage collection since the previous call to `neo4j_gc_*` via JMX.
+    /// The first call only takes the baseline.
+    pub async fn collect_gc_metrics(&self) {
+        match self.gc_totals_via_jmx().await {
+            Ok(totals) => self.gc.lock().unwrap().export(&totals),
+            Err(e) => {
+                // Not fatal; just means JMX is blocked / not available.
+                error!("Failed to collect Neo4j GC metrics via JMX: {:?}", e);
+            }
+        }
+    }
+
+    /// Sample the garbage collectors every interval, local server or not, so GC pauses can be
+    /// lined up with the run's latency.
+    pub fn spawn_gc_poller(&self) -> EndpointMetricsPoller {
+        let client = self.clone();
+        EndpointMetricsPoller::spawn(move || {
+            let client = client.clone();
+            async move {
+                client.collect_gc_metrics().await;
+                Ok(())
+            }
+        })
+    }
+
+    /// For external endpoints: re-sample JVM memory and store size via JMX for the rest of the run.
+    pub fn spawn_endpoint_metrics_poller(&self) -> EndpointMetricsPoller {
+        let client = self.clone();
+        EndpointMetricsPoller::spawn(move || {
+            let client = client.clone();
+            async move {
+                client.collect_jvm_memory_metrics().await;
+                client.collect_store_size_metrics().await;
+                Ok(())
+            }
+        })
+    }
+
     pub async fn detect_algorithm_capabilities(
         &self
     ) -> BenchmarkResult<Neo4jAlgorithmCapabilities> {
@@ -540,6 +754,74 @@ RETURN graphName
         Ok(())
     }
 
+    /// Seed the embeddings of `vector` on every `User` still missing them, create its vector
+    /// index, and wait until it is online and a KNN probe finds neighbours. Embeddings already
+    /// seeded by an earlier run are kept.
+    pub async fn ensure_vector_workload_ready(
+        &self,
+        vector: VectorWorkload,
+    ) -> BenchmarkResult<()> {
+        const SEED_BATCH: usize = 10_000;
+        const INDEX_ONLINE_TIMEOUT_SECS: u64 = 600;
+
+        let property = vector.property();
+        let index_name = vector.index_name();
+        let seed_start = Instant::now();
+        let mut seeded = 0;
+        loop {
+            let batch = self
+                .query_single_u64(&format!(
+                    "MATCH (u:User) WHERE u.{property} IS NULL \
+                     WITH u LIMIT {SEED_BATCH} \
+                     SET u.{property} = {} \
+                     RETURN count(u) AS count",
+                    vector.embedding_expression("u")
+                ))
+                .await?;
+            if batch == 0 {
+                break;
+            }
+            seeded += batch;
+        }
+        info!(
+            "Neo4j seeded {} embeddings of {} dimensions in {:?}",
+            seeded,
+            vector.dimensions,
+            seed_start.elapsed()
+        );
+
+        self.run_query_no_results(&format!(
+            "CREATE VECTOR INDEX {} IF NOT EXISTS \
+             FOR (u:User) ON (u.{}) \
+             OPTIONS {{indexConfig: {{`vector.dimensions`: {}, `vector.similarity_function`: 'cosine'}}}}",
+            index_name, property, vector.dimensions
+        ))
+        .await?;
+        self.run_query_no_results(&format!(
+            "CALL db.awaitIndex('{}', {})",
+            index_name, INDEX_ONLINE_TIMEOUT_SECS
+        ))
+        .await?;
+
+        let hits = self
+            .query_single_u64(&format!(
+                "CALL db.index.vector.queryNodes('{}', 1, {}) \
+                 YIELD node \
+                 RETURN count(node) AS count",
+                index_name,
+                vector.probe_embedding()
+            ))
+            .await?;
+        if hits == 0 {
+            return Err(OtherError(format!(
+                "Neo4j vector index {} is online but returned no neighbours",
+                index_name
+            )));
+        }
+        info!("Neo4j vector index {} ready", index_name);
+        Ok(())
+    }
+
     async fn query_single_u64(
         &self,
         q: &str,
@@ -623,6 +905,34 @@ RETURN
         Ok((0, 0))
     }
 
+    async fn gc_totals_via_jmx(&self) -> BenchmarkResult<Vec<GcTotals>> {
+        // One bean per collector, e.g. `java.lang:type=GarbageCollector,name=G1 Young Generation`;
+        // CollectionTime is in milliseconds.
+        let q = r#"
+CALL dbms.queryJmx('java.lang:type=GarbageCollector,*') YIELD attributes
+RETURN
+  attributes['Name']['value'] AS collector,
+  attributes['CollectionCount']['value'] AS collections,
+  attributes['CollectionTime']['value'] AS time_ms
+"#;
+
+        let mut result = self.graph.execute(query(q)).await?;
+        let mut totals = Vec::new();
+        while let Ok(Some(row)) = result.next().await {
+            let count = |key: &str| {
+                row.get::<i64>(key)
+                    .map(|v| v.max(0) as u64)
+                    .or_else(|_| row.get::<u64>(key))
+            };
+            totals.push(GcTotals {
+                collector: row.get::<String>("collector")?,
+                collections: count("collections")?,
+                time_ms: count("time_ms")?,
+            });
+        }
+        Ok(totals)
+    }
+
     async fn store_size_bytes_via_jmx(&self) -> BenchmarkResult<u64> {
         // This query is a Cypher equivalent of the "Store file sizes" section in :sysinfo.
         // It returns multiple rows like (name, value). We sum all numeric values.
@@ -680,6 +990,112 @@ RETURN k AS name, attributes[k]['value'] AS value\n"
         Ok(0)
     }
 
+    /// Single-label property (range) indexes present on the server, for the pre-run parity check.
+    /// Every index with its `state`; Neo4j populates an index in the background after `CREATE
+    /// INDEX` returns.
+    pub async fn index_states(&self) -> BenchmarkResult<Vec<IndexState>> {
+        let q = "SHOW INDEXES YIELD name, state, populationPercent \
+                 RETURN name, state, populationPercent";
+        let mut result = self.graph.execute(query(q)).await.map_err(Neo4rsError)?;
+        let mut states = Vec::new();
+        while let Some(row) = result.next().await.map_err(Neo4rsError)? {
+            let name: String = row.get("name")?;
+            let state: String = row.get("state")?;
+            let state = match state.as_str() {
+                "ONLINE" => IndexPopulation::Online,
+                "FAILED" => IndexPopulation::Failed,
+                _ => IndexPopulation::Populating(row.get::<f64>("populationPercent").ok()),
+            };
+            states.push(IndexState { name, state });
+        }
+        Ok(states)
+    }
+
+    pub async fn list_indexes(&self) -> BenchmarkResult<Vec<IndexDescriptor>> {
+        let q = "SHOW INDEXES YIELD type, labelsOrTypes, properties \
+                 WHERE type IN ['RANGE', 'BTREE'] AND size(labelsOrTypes) = 1 \
+                 RETURN labelsOrTypes[0] AS label, properties";
+        let mut result = self.graph.execute(query(q)).await.map_err(Neo4rsError)?;
+        let mut indexes = Vec::new();
+        while let Some(row) = result.next().await.map_err(Neo4rsError)? {
+            let label: String = row.get("label")?;
+            let properties: Vec<String> = row.get("properties")?;
+            indexes.push(IndexDescriptor::new(label, properties.join(",")));
+        }
+        Ok(indexes)
+    }
+
+    /// `EXPLAIN` or `PROFILE` of `query` through the HTTP API (`NEO4J_HTTP_URI`, by default port
+    /// 7474 of the Bolt host), for `run --capture-plans`.
+    pub async fn query_plan(
+        &self,
+        query: &PreparedQuery,
+        mode: PlanMode,
+    ) -> BenchmarkResult<Vec<String>> {
+        let parameters: serde_json::Map<String, serde_json::Value> = query
+            .bolt
+            .params
+            .iter()
+            .map(|(name, value)| (name.clone(), param_json(value)))
+            .collect();
+        let body = serde_json::json!({
+            "statements": [{
+                "statement": format!("{} {}", mode.keyword(), query.bolt.query),
+                "parameters": parameters,
+            }]
+        });
+        let response = reqwest::Client::new()
+            .post(&self.http_api.commit_url)
+            .basic_auth(&self.http_api.user, Some(&self.http_api.password))
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await?
+            .error_for_status()?;
+        let response: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        if let Some(error) = response["errors"]
+            .as_array()
+            .and_then(|errors| errors.first())
+        {
+            return Err(OtherError(format!(
+                "Neo4j {} failed: {}",
+                mode.keyword(),
+                error["message"].as_str().unwrap_or_default()
+            )));
+        }
+        let result = &response["results"][0];
+        let root = match mode {
+            PlanMode::Explain => &result["plan"]["root"],
+            PlanMode::Profile if result["profile"]["root"].is_object() => {
+                &result["profile"]["root"]
+            }
+            PlanMode::Profile => &result["profile"],
+        };
+        render_neo4j_plan(root)
+    }
+
+    /// Rows of a query returning `key` and `value` columns, for `inspect`.
+    pub async fn key_counts(
+        &self,
+        q: &str,
+    ) -> BenchmarkResult<Vec<(String, i64)>> {
+        let mut result = self.graph.execute(query(q)).await.map_err(Neo4rsError)?;
+        let mut rows = Vec::new();
+        while let Some(row) = result.next().await.map_err(Neo4rsError)? {
+            rows.push((row.get("key")?, row.get("value")?));
+        }
+        Ok(rows)
+    }
+
+    /// Run the `--warm` scans before the measured phase, draining every row.
+    pub async fn warm_up(&self) -> BenchmarkResult<()> {
+        for q in WARMUP_QUERIES {
+            let mut result = self.graph.execute(query(q)).await.map_err(Neo4rsError)?;
+            while result.next().await.map_err(Neo4rsError)?.is_some() {}
+        }
+        Ok(())
+    }
+
     pub async fn graph_size(&self) -> BenchmarkResult<(u64, u64)> {
         let mut result = self
             .graph
@@ -1080,6 +1496,7 @@ impl Neo4jClient {
                     }
                 }
             }
+            crate::run_status::loaded(total_processed as u64);
         }
 
         // Final flush.
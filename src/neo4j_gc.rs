@@ -0,0 +1,106 @@
+//! Neo4j's garbage collection during a run. The JVM's `java.lang:type=GarbageCollector` beans
+//! only report totals since it started, so each poll exports what every collector did since the
+//! previous one as `neo4j_gc_collections_total` and `neo4j_gc_pause_seconds_total`. Latency
+//! spikes that line up with GC pauses are the JVM's, not the queries'.
+
+use crate::{NEO4J_GC_COLLECTIONS_TOTAL, NEO4J_GC_PAUSE_SECONDS_TOTAL};
+use std::collections::HashMap;
+
+/// One collector's totals since the JVM started, e.g. `G1 Young Generation`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GcTotals {
+    pub collector: String,
+    pub collections: u64,
+    pub time_ms: u64,
+}
+
+/// What a collector did between two polls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GcDelta {
+    pub collector: String,
+    pub collections: u64,
+    pub time_ms: u64,
+}
+
+/// The totals of the previous poll, per collector.
+#[derive(Debug, Default)]
+pub struct GcDeltas {
+    last: HashMap<String, (u64, u64)>,
+}
+
+impl GcDeltas {
+    /// The work done since the previous call. A collector's first totals are only the baseline,
+    /// since they include everything before the run; totals that went down mean Neo4j
+    /// restarted, and count from zero.
+    pub fn record(
+        &mut self,
+        totals: &[GcTotals],
+    ) -> Vec<GcDelta> {
+        let mut deltas = Vec::new();
+        for totals in totals {
+            let current = (totals.collections, totals.time_ms);
+            let Some(last) = self.last.insert(totals.collector.clone(), current) else {
+                continue;
+            };
+            let since = |current: u64, last: u64| current.checked_sub(last).unwrap_or(current);
+            let delta = GcDelta {
+                collector: totals.collector.clone(),
+                collections: since(current.0, last.0),
+                time_ms: since(current.1, last.1),
+            };
+            if delta.collections > 0 || delta.time_ms > 0 {
+                deltas.push(delta);
+            }
+        }
+        deltas
+    }
+
+    /// [`GcDeltas::record`], added to the `neo4j_gc_*` counters.
+    pub fn export(
+        &mut self,
+        totals: &[GcTotals],
+    ) {
+        for delta in self.record(totals) {
+            NEO4J_GC_COLLECTIONS_TOTAL
+                .with_label_values(&[delta.collector.as_str()])
+                .inc_by(delta.collections);
+            NEO4J_GC_PAUSE_SECONDS_TOTAL
+                .with_label_values(&[delta.collector.as_str()])
+                .inc_by(delta.time_ms as f64 / 1000.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(
+        collections: u64,
+        time_ms: u64,
+    ) -> Vec<GcTotals> {
+        vec![GcTotals {
+            collector: "G1 Young Generation".to_string(),
+            collections,
+            time_ms,
+        }]
+    }
+
+    #[test]
+    fn deltas_start_at_the_first_poll_and_survive_a_restart() {
+        let mut deltas = GcDeltas::default();
+        assert!(deltas.record(&totals(40, 900)).is_empty());
+        assert_eq!(
+            deltas.record(&totals(43, 960)),
+            [GcDelta {
+                collector: "G1 Young Generation".to_string(),
+                collections: 3,
+                time_ms: 60,
+            }]
+        );
+        assert!(deltas.record(&totals(43, 960)).is_empty());
+        // Restarted: the new JVM's totals are all new work.
+        assert_eq!(deltas.record(&totals(2, 15))[0].collections, 2);
+        assert_eq!(deltas.record(&totals(5, 45))[0].time_ms, 30);
+    }
+}
@@ -0,0 +1,125 @@
+//! `run --neo4j-heap`/`--neo4j-pagecache`: the locally managed Neo4j's memory settings. They are
+//! written into neo4j.conf before the server starts and read back from it into meta.json, so a
+//! result says how much heap and page cache Neo4j had even when Neo4j picked them itself.
+
+use crate::resource_limits::MemoryLimit;
+use serde::Serialize;
+
+const HEAP_MAX_SIZE: &str = "server.memory.heap.max_size";
+const PAGECACHE_SIZE: &str = "server.memory.pagecache.size";
+/// The Neo4j 4 names of the same settings; Neo4j 5 rejects them.
+const LEGACY_HEAP_MAX_SIZE: &str = "dbms.memory.heap.max_size";
+const LEGACY_PAGECACHE_SIZE: &str = "dbms.memory.pagecache.size";
+
+/// Neo4j's memory settings as neo4j.conf spells them; `None` leaves one to Neo4j's heuristics.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Neo4jMemory {
+    pub heap_max_size: Option<String>,
+    pub pagecache_size: Option<String>,
+}
+
+impl Neo4jMemory {
+    pub fn new(
+        heap: Option<MemoryLimit>,
+        pagecache: Option<MemoryLimit>,
+    ) -> Self {
+        // Neo4j reads `8g`, `512m` and so on.
+        let size = |limit: MemoryLimit| limit.to_string().to_lowercase();
+        Neo4jMemory {
+            heap_max_size: heap.map(size),
+            pagecache_size: pagecache.map(size),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap_max_size.is_none() && self.pagecache_size.is_none()
+    }
+
+    /// `conf` with the settings given here replacing any it already had.
+    pub fn apply(
+        &self,
+        conf: &str,
+    ) -> String {
+        let settings = [
+            (HEAP_MAX_SIZE, LEGACY_HEAP_MAX_SIZE, &self.heap_max_size),
+            (PAGECACHE_SIZE, LEGACY_PAGECACHE_SIZE, &self.pagecache_size),
+        ];
+        let mut out: String = conf
+            .lines()
+            .filter(|line| {
+                !settings.iter().any(|(key, legacy, value)| {
+                    value.is_some()
+                        && matches!(setting(line), Some((k, _)) if k == *key || k == *legacy)
+                })
+            })
+            .map(|line| format!("{}\n", line))
+            .collect();
+        for (key, _, value) in settings {
+            if let Some(value) = value {
+                out.push_str(&format!("{}={}\n", key, value));
+            }
+        }
+        out
+    }
+
+    /// The memory settings `conf` has in effect; the last one wins, as in Neo4j.
+    pub fn read(conf: &str) -> Self {
+        let mut memory = Neo4jMemory::default();
+        for (key, value) in conf.lines().filter_map(setting) {
+            let value = Some(value.to_string());
+            if key == HEAP_MAX_SIZE || key == LEGACY_HEAP_MAX_SIZE {
+                memory.heap_max_size = value;
+            } else if key == PAGECACHE_SIZE || key == LEGACY_PAGECACHE_SIZE {
+                memory.pagecache_size = value;
+            }
+        }
+        memory
+    }
+}
+
+/// A `key=value` line of neo4j.conf; `None` for comments and blank lines.
+fn setting(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return None;
+    }
+    line.split_once('=')
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_replaces_the_settings_and_read_gets_them_back() {
+        let conf = "#server.memory.heap.max_size=512m\n\
+                    dbms.memory.pagecache.size=1g\n\
+                    dbms.usage_report.enabled=false\n";
+        assert_eq!(
+            Neo4jMemory::read(conf),
+            Neo4jMemory {
+                heap_max_size: None,
+                pagecache_size: Some("1g".to_string()),
+            }
+        );
+
+        let memory = Neo4jMemory::new(Some("4G".parse().unwrap()), Some("2G".parse().unwrap()));
+        let applied = memory.apply(conf);
+        assert_eq!(
+            applied,
+            "#server.memory.heap.max_size=512m\n\
+             dbms.usage_report.enabled=false\n\
+             server.memory.heap.max_size=4g\n\
+             server.memory.pagecache.size=2g\n"
+        );
+        assert_eq!(Neo4jMemory::read(&applied), memory);
+
+        // Only the settings given are touched.
+        let heap_only = Neo4jMemory::new(Some("4G".parse().unwrap()), None);
+        assert_eq!(
+            Neo4jMemory::read(&heap_only.apply(conf)).pagecache_size,
+            Some("1g".to_string())
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Finding and stopping processes without `ps` or Unix-only APIs, so the client also builds and
+//! runs on Windows (e.g. against an `--endpoint`). Processes are listed with sysinfo; they are
+//! stopped with signals on Unix and with `TerminateProcess`, through sysinfo, elsewhere.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use std::path::Path;
+use sysinfo::{ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System, UpdateKind};
+
+/// What [`find_pid`] looks at in a process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub name: String,
+    pub cmd: Vec<String>,
+    pub zombie: bool,
+}
+
+impl ProcessEntry {
+    /// The executable's file name, from the first command-line word so a server that rewrites
+    /// its title (`redis-server *:6379`) still matches; the process name when the command line
+    /// can't be read.
+    fn executable(&self) -> &str {
+        let program = self
+            .cmd
+            .first()
+            .and_then(|arg| arg.split_whitespace().next())
+            .unwrap_or(&self.name);
+        Path::new(program)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(program)
+    }
+}
+
+/// The processes running now.
+pub fn process_table() -> Vec<ProcessEntry> {
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cmd(UpdateKind::OnlyIfNotSet),
+    );
+    system
+        .processes()
+        .iter()
+        .map(|(pid, process)| ProcessEntry {
+            pid: pid.as_u32(),
+            name: process.name().to_string_lossy().to_string(),
+            cmd: process
+                .cmd()
+                .iter()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect(),
+            zombie: process.status() == ProcessStatus::Zombie,
+        })
+        .collect()
+}
+
+/// The lowest PID running `executable`, e.g. `redis-server` (or `redis-server.exe`), leaving out
+/// zombies. Only the exact name matches: `redis-server-asan` or `redis-cli` don't.
+pub fn find_pid(executable: &str) -> Option<u32> {
+    pick_pid(&process_table(), executable)
+}
+
+fn pick_pid(
+    table: &[ProcessEntry],
+    executable: &str,
+) -> Option<u32> {
+    let windows_name = format!("{}.exe", executable);
+    table
+        .iter()
+        .filter(|entry| !entry.zombie)
+        .filter(|entry| entry.executable() == executable || entry.executable() == windows_name)
+        .map(|entry| entry.pid)
+        .min()
+}
+
+/// Stop `pid` at once, with no chance to clean up (SIGKILL).
+pub fn kill(pid: u32) -> BenchmarkResult<()> {
+    #[cfg(unix)]
+    return signal(pid, nix::sys::signal::Signal::SIGKILL);
+    #[cfg(not(unix))]
+    return terminate_process(pid);
+}
+
+/// Ask `pid` to shut down (SIGTERM). Windows has no such request for a console-less process, so
+/// there it is stopped like [`kill`].
+pub fn terminate(pid: u32) -> BenchmarkResult<()> {
+    #[cfg(unix)]
+    return signal(pid, nix::sys::signal::Signal::SIGTERM);
+    #[cfg(not(unix))]
+    return terminate_process(pid);
+}
+
+#[cfg(unix)]
+fn signal(
+    pid: u32,
+    signal: nix::sys::signal::Signal,
+) -> BenchmarkResult<()> {
+    match nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), signal) {
+        Ok(()) => Ok(()),
+        Err(nix::Error::ESRCH) => Err(OtherError(format!("No process with pid {} found", pid))),
+        Err(e) => Err(OtherError(format!(
+            "Failed to send {} to process {}: {}",
+            signal, pid, e
+        ))),
+    }
+}
+
+#[cfg(not(unix))]
+fn terminate_process(pid: u32) -> BenchmarkResult<()> {
+    let sys_pid = sysinfo::Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[sys_pid]), true);
+    match system.process(sys_pid) {
+        Some(process) if process.kill() => Ok(()),
+        Some(_) => Err(OtherError(format!("Failed to kill process {}", pid))),
+        None => Err(OtherError(format!("No process with pid {} found", pid))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        pid: u32,
+        name: &str,
+        cmd: &[&str],
+        zombie: bool,
+    ) -> ProcessEntry {
+        ProcessEntry {
+            pid,
+            name: name.to_string(),
+            cmd: cmd.iter().map(|arg| arg.to_string()).collect(),
+            zombie,
+        }
+    }
+
+    #[test]
+    fn picks_the_lowest_live_pid_with_exactly_that_executable() {
+        let table = [
+            entry(30, "redis-server", &["redis-server *:6379"], false),
+            entry(
+                10,
+                "redis-server",
+                &["/usr/bin/redis-server", "--port", "6380"],
+                true,
+            ),
+            entry(
+                20,
+                "redis-server-as",
+                &["/opt/redis-server-asan", "--port", "6379"],
+                false,
+            ),
+            entry(5, "redis-cli", &["redis-cli", "-p", "redis-server"], false),
+            entry(40, "bash", &["bash", "-c", "redis-server"], false),
+        ];
+        assert_eq!(pick_pid(&table, "redis-server"), Some(30));
+        assert_eq!(pick_pid(&table, "redis-server-asan"), Some(20));
+        assert_eq!(pick_pid(&table, "redis"), None);
+
+        // A process whose command line can't be read is matched on its name.
+        let table = [
+            entry(7, "memgraph", &[], false),
+            entry(8, "redis-server.exe", &[], false),
+        ];
+        assert_eq!(pick_pid(&table, "memgraph"), Some(7));
+        assert_eq!(pick_pid(&table, "redis-server"), Some(8));
+    }
+
+    #[test]
+    fn finds_this_process_and_not_a_made_up_one() {
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_string_lossy().to_string();
+        let pid = find_pid(&name).unwrap();
+        assert!(pid <= std::process::id());
+        assert_eq!(find_pid("no-such-program-c1f0e4"), None);
+    }
+}
@@ -0,0 +1,347 @@
+//! Server-side persistence during a run's measured phase: Redis `BGSAVE` and AOF rewrites (and
+//! the fork before them) for FalkorDB, snapshot creation for a locally managed Memgraph. Each
+//! event is timestamped against the run's clock and written to
+//! `<results-dir>/<vendor>/persistence_events.jsonl`; `persistence_in_progress{vendor,kind}` marks
+//! it on the dashboards, so a latency spike can be told apart from checkpoint activity.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::falkor::falkor_log::read_new_lines;
+use crate::scenario::Vendor;
+use crate::throughput::ThroughputTracker;
+use crate::{PERSISTENCE_EVENTS_TOTAL, PERSISTENCE_IN_PROGRESS};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+pub const PERSISTENCE_EVENTS_FILE: &str = "persistence_events.jsonl";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PersistenceKind {
+    /// An RDB snapshot written by a forked child, `INFO persistence`'s `rdb_bgsave_in_progress`.
+    Bgsave,
+    /// `aof_rewrite_in_progress`.
+    AofRewrite,
+    /// Memgraph's "Starting snapshot creation" up to its success or failure.
+    Snapshot,
+}
+
+impl PersistenceKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            PersistenceKind::Bgsave => "bgsave",
+            PersistenceKind::AofRewrite => "aof_rewrite",
+            PersistenceKind::Snapshot => "snapshot",
+        }
+    }
+}
+
+/// One line of `persistence_events.jsonl`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PersistenceEvent {
+    pub kind: PersistenceKind,
+    /// Into the measured phase, on the clock of `throughput.csv`.
+    pub start_ms: u64,
+    /// `None` when it was still going when the run ended.
+    pub end_ms: Option<u64>,
+    pub start_unix_ms: u64,
+    /// How long Redis took to fork for it, `latest_fork_usec`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fork_us: Option<u64>,
+}
+
+/// Turns observations of whether each kind is in progress into events.
+#[derive(Debug)]
+pub struct PersistenceEvents {
+    vendor: Vendor,
+    pub events: Vec<PersistenceEvent>,
+}
+
+impl PersistenceEvents {
+    pub fn new(vendor: Vendor) -> Self {
+        PersistenceEvents {
+            vendor,
+            events: Vec::new(),
+        }
+    }
+
+    /// `kind` is `active` at `at`; starts an event on the first observation it is, ends it on the
+    /// first it isn't.
+    pub fn observe(
+        &mut self,
+        kind: PersistenceKind,
+        active: bool,
+        at: Duration,
+    ) {
+        let open = self
+            .events
+            .iter_mut()
+            .rev()
+            .find(|event| event.kind == kind && event.end_ms.is_none());
+        let labels = [self.vendor.metric_label(), kind.as_str()];
+        match (open, active) {
+            (None, true) => {
+                info!("{} {} started", self.vendor, kind.as_str());
+                PERSISTENCE_EVENTS_TOTAL.with_label_values(&labels).inc();
+                PERSISTENCE_IN_PROGRESS.with_label_values(&labels).set(1);
+                let start_unix_ms = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |now| now.as_millis() as u64);
+                self.events.push(PersistenceEvent {
+                    kind,
+                    start_ms: at.as_millis() as u64,
+                    end_ms: None,
+                    start_unix_ms,
+                    fork_us: None,
+                });
+            }
+            (Some(event), false) => {
+                PERSISTENCE_IN_PROGRESS.with_label_values(&labels).set(0);
+                event.end_ms = Some(at.as_millis() as u64);
+            }
+            _ => {}
+        }
+    }
+
+    /// Redis' `INFO persistence`.
+    fn observe_redis(
+        &mut self,
+        info: &redis::InfoDict,
+        at: Duration,
+    ) {
+        for (kind, field) in [
+            (PersistenceKind::Bgsave, "rdb_bgsave_in_progress"),
+            (PersistenceKind::AofRewrite, "aof_rewrite_in_progress"),
+        ] {
+            if let Some(active) = info.get::<i64>(field) {
+                self.observe(kind, active == 1, at);
+            }
+        }
+        // A fork happens at the start of either; it is known by the first poll that sees it.
+        if let (Some(event), Some(fork_us)) = (self.events.last_mut(), info.get("latest_fork_usec"))
+        {
+            if event.end_ms.is_none() && event.fork_us.is_none() {
+                event.fork_us = Some(fork_us);
+            }
+        }
+    }
+
+    pub fn to_jsonl(&self) -> BenchmarkResult<String> {
+        let mut jsonl = String::new();
+        for event in &self.events {
+            jsonl.push_str(&serde_json::to_string(event)?);
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+
+    /// Writes [`PERSISTENCE_EVENTS_FILE`] to `run_dir`, empty when nothing was persisted.
+    pub async fn write(
+        &self,
+        run_dir: &Path,
+    ) -> BenchmarkResult<()> {
+        let path = run_dir.join(PERSISTENCE_EVENTS_FILE);
+        tokio::fs::write(&path, self.to_jsonl()?)
+            .await
+            .map_err(|e| OtherError(format!("Failed writing {}: {}", path.display(), e)))
+    }
+}
+
+/// Whether a Memgraph log line starts (`true`) or ends (`false`) a snapshot.
+pub fn memgraph_snapshot_line(line: &str) -> Option<bool> {
+    let lower = line.to_lowercase();
+    if lower.contains("starting snapshot creation") {
+        Some(true)
+    } else if lower.contains("snapshot creation successful")
+        || lower.contains("snapshot creation failed")
+    {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Where events are read from.
+enum Source {
+    /// `INFO persistence` of the Redis FalkorDB runs in.
+    Redis { redis_url: String },
+    /// A Memgraph log, from `offset` on.
+    MemgraphLog {
+        path: String,
+        offset: u64,
+        pending: String,
+    },
+}
+
+impl Source {
+    async fn poll(
+        &mut self,
+        events: &mut PersistenceEvents,
+        at: Duration,
+    ) -> BenchmarkResult<()> {
+        match self {
+            Source::Redis { redis_url } => {
+                let client = redis::Client::open(redis_url.as_str())?;
+                let mut con = client.get_multiplexed_async_connection().await?;
+                let info: redis::InfoDict = redis::cmd("INFO")
+                    .arg("persistence")
+                    .query_async(&mut con)
+                    .await?;
+                events.observe_redis(&info, at);
+            }
+            Source::MemgraphLog {
+                path,
+                offset,
+                pending,
+            } => {
+                for line in read_new_lines(path, offset, pending).await {
+                    if let Some(active) = memgraph_snapshot_line(&line) {
+                        events.observe(PersistenceKind::Snapshot, active, at);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Watches for persistence events until [`PersistenceWatch::stop`].
+pub struct PersistenceWatch {
+    vendor: Vendor,
+    handle: JoinHandle<PersistenceEvents>,
+    shutdown_tx: oneshot::Sender<()>,
+}
+
+impl PersistenceWatch {
+    /// Polls `INFO persistence` of the Redis at `redis_url`.
+    pub fn spawn_redis(
+        redis_url: String,
+        throughput: Arc<ThroughputTracker>,
+    ) -> Self {
+        Self::spawn(Vendor::Falkor, Source::Redis { redis_url }, throughput)
+    }
+
+    /// Reads the snapshot lines Memgraph appends to `path` from now on.
+    pub async fn spawn_memgraph_log(
+        path: String,
+        throughput: Arc<ThroughputTracker>,
+    ) -> Self {
+        let offset = tokio::fs::metadata(&path)
+            .await
+            .map(|meta| meta.len())
+            .unwrap_or(0);
+        let source = Source::MemgraphLog {
+            path,
+            offset,
+            pending: String::new(),
+        };
+        Self::spawn(Vendor::Memgraph, source, throughput)
+    }
+
+    fn spawn(
+        vendor: Vendor,
+        mut source: Source,
+        throughput: Arc<ThroughputTracker>,
+    ) -> Self {
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            let mut events = PersistenceEvents::new(vendor);
+            let mut ticker = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                let done = tokio::select! {
+                    _ = ticker.tick() => false,
+                    _ = &mut shutdown_rx => true,
+                };
+                if let Err(e) = source.poll(&mut events, throughput.elapsed()).await {
+                    debug!("Failed reading {}'s persistence activity: {}", vendor, e);
+                }
+                if done {
+                    break;
+                }
+            }
+            for event in events.events.iter().filter(|event| event.end_ms.is_none()) {
+                PERSISTENCE_IN_PROGRESS
+                    .with_label_values(&[vendor.metric_label(), event.kind.as_str()])
+                    .set(0);
+            }
+            events
+        });
+        PersistenceWatch {
+            vendor,
+            handle,
+            shutdown_tx,
+        }
+    }
+
+    /// Polls once more and returns the events found.
+    pub async fn stop(self) -> PersistenceEvents {
+        drop(self.shutdown_tx);
+        self.handle
+            .await
+            .unwrap_or_else(|_| PersistenceEvents::new(self.vendor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_open_and_close_on_what_the_server_reports() {
+        let mut events = PersistenceEvents::new(Vendor::Falkor);
+        let s = Duration::from_secs;
+        let info = |bgsave: i64, fork_us: i64| {
+            let text = format!(
+                "# Persistence\r\nrdb_bgsave_in_progress:{}\r\naof_rewrite_in_progress:0\r\nlatest_fork_usec:{}\r\n",
+                bgsave, fork_us
+            );
+            redis::InfoDict::new(&text)
+        };
+        events.observe_redis(&info(0, 0), s(1));
+        events.observe_redis(&info(1, 812), s(2));
+        events.observe_redis(&info(1, 812), s(3));
+        events.observe_redis(&info(0, 812), s(4));
+        events.observe_redis(&info(1, 640), s(9));
+        assert_eq!(
+            events.events,
+            [
+                PersistenceEvent {
+                    kind: PersistenceKind::Bgsave,
+                    start_ms: 2_000,
+                    end_ms: Some(4_000),
+                    start_unix_ms: events.events[0].start_unix_ms,
+                    fork_us: Some(812),
+                },
+                PersistenceEvent {
+                    kind: PersistenceKind::Bgsave,
+                    start_ms: 9_000,
+                    end_ms: None,
+                    start_unix_ms: events.events[1].start_unix_ms,
+                    fork_us: Some(640),
+                },
+            ]
+        );
+        assert_eq!(events.to_jsonl().unwrap().lines().count(), 2);
+
+        let line = memgraph_snapshot_line;
+        assert_eq!(
+            line("[2026-10-16 12:00:00.123] [memgraph_log] [info] Starting snapshot creation to \"/var/lib/memgraph/snapshots/2026\""),
+            Some(true)
+        );
+        assert_eq!(
+            line("[2026-10-16 12:00:04.001] [memgraph_log] [info] Snapshot creation successful!"),
+            Some(false)
+        );
+        assert_eq!(
+            line("[memgraph_log] [warning] Using default bolt port"),
+            None
+        );
+    }
+}
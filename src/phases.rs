@@ -0,0 +1,241 @@
+//! `run --phases "read-only:5m,mixed:10m,write-heavy:5m"`: one run that shifts its read/write mix
+//! at set points of the schedule. The scheduler draws each query from the file's reads or writes
+//! to match the phase it is scheduled in, records when each phase began in meta.json, and the
+//! latency of every phase is reported on its own.
+
+use crate::query_timeout::parse_duration;
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::warn;
+
+/// One stage of the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    /// The mix as given, e.g. `read-only` or `0.3`, with `#2`, `#3` and so on appended when it
+    /// recurs; the `phase` label of its metrics.
+    pub name: String,
+    pub write_ratio: f32,
+    pub duration: Duration,
+}
+
+/// The write ratio of a named mix.
+fn preset_write_ratio(name: &str) -> Option<f32> {
+    match name {
+        "read-only" => Some(0.0),
+        "read-heavy" => Some(0.1),
+        "mixed" => Some(0.5),
+        "write-heavy" => Some(0.9),
+        "write-only" => Some(1.0),
+        _ => None,
+    }
+}
+
+impl FromStr for Phase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, duration) = s
+            .trim()
+            .rsplit_once(':')
+            .ok_or_else(|| format!("invalid phase '{}', expected <mix>:<duration>", s))?;
+        let name = name.trim();
+        let write_ratio = match preset_write_ratio(name) {
+            Some(ratio) => ratio,
+            None => name
+                .parse::<f32>()
+                .ok()
+                .filter(|ratio| (0.0..=1.0).contains(ratio))
+                .ok_or_else(|| {
+                    format!(
+                        "unknown mix '{}', expected read-only, read-heavy, mixed, write-heavy, write-only or a write ratio (0.0 - 1.0)",
+                        name
+                    )
+                })?,
+        };
+        let duration = parse_duration(duration)?;
+        if duration.is_zero() {
+            return Err(format!("phase '{}' must last longer than 0s", s));
+        }
+        Ok(Phase {
+            name: name.to_string(),
+            write_ratio,
+            duration,
+        })
+    }
+}
+
+/// The run's phases in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phases(Vec<Phase>);
+
+impl FromStr for Phases {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut phases = s
+            .split(',')
+            .map(Phase::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        for i in 1..phases.len() {
+            let earlier = phases[..i]
+                .iter()
+                .filter(|phase| phase.name.split('#').next() == Some(phases[i].name.as_str()))
+                .count();
+            if earlier > 0 {
+                phases[i].name = format!("{}#{}", phases[i].name, earlier + 1);
+            }
+        }
+        Ok(Phases(phases))
+    }
+}
+
+impl Phases {
+    pub fn phases(&self) -> &[Phase] {
+        &self.0
+    }
+
+    /// How long the phases take together; the run's `--duration`.
+    pub fn total(&self) -> Duration {
+        self.0.iter().map(|phase| phase.duration).sum()
+    }
+
+    /// The phase a query scheduled `at` into the run belongs to; `None` past the last one.
+    pub fn index_at(
+        &self,
+        at: Duration,
+    ) -> Option<usize> {
+        let mut end = Duration::ZERO;
+        self.0.iter().position(|phase| {
+            end += phase.duration;
+            at < end
+        })
+    }
+}
+
+/// When a phase began; recorded in meta.json as `phases`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseBoundary {
+    pub phase: String,
+    pub write_ratio: f32,
+    /// Into the schedule.
+    pub offset_ms: u64,
+    /// When the phase's first query was scheduled to start.
+    pub at_epoch_ms: u64,
+}
+
+/// The queries of a run, split into reads and writes and drawn to match each phase's mix.
+#[derive(Debug)]
+pub struct PhasedRequests<Payload> {
+    phases: Phases,
+    reads: VecDeque<Payload>,
+    writes: VecDeque<Payload>,
+    rng: StdRng,
+    ran_short: bool,
+}
+
+impl<Payload> PhasedRequests<Payload> {
+    pub fn new(
+        phases: Phases,
+        requests: Vec<Payload>,
+        is_write: impl Fn(&Payload) -> bool,
+    ) -> Self {
+        let (writes, reads): (VecDeque<_>, VecDeque<_>) = requests.into_iter().partition(is_write);
+        PhasedRequests {
+            phases,
+            reads,
+            writes,
+            rng: StdRng::seed_from_u64(0),
+            ran_short: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.reads.len() + self.writes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The query to schedule `at` into the run. When the file has no more of the kind the
+    /// phase asks for, the other kind is sent instead.
+    pub fn next(
+        &mut self,
+        at: Duration,
+    ) -> Option<Payload> {
+        let phases = self.phases.phases();
+        let phase = &phases[self.phases.index_at(at).unwrap_or(phases.len() - 1)];
+        let write = self
+            .rng
+            .random_bool(f64::from(phase.write_ratio).clamp(0.0, 1.0));
+        let (wanted, other) = if write {
+            (&mut self.writes, &mut self.reads)
+        } else {
+            (&mut self.reads, &mut self.writes)
+        };
+        if let Some(payload) = wanted.pop_front() {
+            return Some(payload);
+        }
+        if !self.ran_short && !other.is_empty() {
+            self.ran_short = true;
+            warn!(
+                "The queries file ran out of {} during phase {}; sending {} instead",
+                if write { "writes" } else { "reads" },
+                phase.name,
+                if write { "reads" } else { "writes" }
+            );
+        }
+        other.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_phases_and_draws_each_ones_mix() {
+        let phases: Phases = "read-only:2s,0.25:1m,write-only:500ms".parse().unwrap();
+        assert_eq!(
+            phases
+                .phases()
+                .iter()
+                .map(|phase| (phase.name.as_str(), phase.write_ratio))
+                .collect::<Vec<_>>(),
+            [("read-only", 0.0), ("0.25", 0.25), ("write-only", 1.0)]
+        );
+        assert_eq!(phases.total(), Duration::from_millis(62_500));
+        assert_eq!(phases.index_at(Duration::from_millis(1_999)), Some(0));
+        assert_eq!(phases.index_at(Duration::from_secs(2)), Some(1));
+        assert_eq!(phases.index_at(Duration::from_secs(62)), Some(2));
+        assert_eq!(phases.index_at(Duration::from_millis(62_500)), None);
+        let names: Vec<String> = "mixed:1m,read-only:1m,mixed:1m,mixed:1m"
+            .parse::<Phases>()
+            .unwrap()
+            .phases()
+            .iter()
+            .map(|phase| phase.name.clone())
+            .collect();
+        assert_eq!(names, ["mixed", "read-only", "mixed#2", "mixed#3"]);
+        assert!("read-only".parse::<Phases>().is_err());
+        assert!("sideways:5m".parse::<Phases>().is_err());
+        assert!("1.5:5m".parse::<Phases>().is_err());
+        assert!("mixed:0s".parse::<Phases>().is_err());
+
+        // 'r' for reads, 'w' for writes.
+        let phases: Phases = "read-only:1s,write-only:1s".parse().unwrap();
+        let mut requests = PhasedRequests::new(phases, "rwrwrrww".chars().collect(), |q| *q == 'w');
+        let ms = Duration::from_millis;
+        let drawn: String = [0, 10, 20, 1000, 1010, 1020, 1030, 1040]
+            .into_iter()
+            .map_while(|at| requests.next(ms(at)))
+            .collect();
+        assert_eq!(drawn, "rrrwwwwr");
+        assert!(requests.is_empty());
+        assert_eq!(requests.next(ms(0)), None);
+    }
+}
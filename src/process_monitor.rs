@@ -1,17 +1,83 @@
+use crate::error::BenchmarkError::OtherError;
 use crate::error::BenchmarkResult;
+use crate::os_process;
 use prometheus::core::{AtomicU64, GenericCounter};
+use prometheus::IntGauge;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use sysinfo::{Pid, ProcessStatus, ProcessesToUpdate, System};
 use tokio::process::{Child, Command};
 use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
 use tokio::time::{sleep, timeout, Duration};
 use tracing::{error, info, warn};
 
+/// How often an attached server is sampled (`run --attach-pid`, `--attach-port`).
+pub const ATTACH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When the monitored process exited and was respawned, in ms since the epoch. Shared with the
+/// owner of the monitor so runs can report restarts that happened while they were measuring.
+#[derive(Debug, Clone, Default)]
+pub struct RestartLog(Arc<Mutex<Vec<u64>>>);
+
+impl RestartLog {
+    fn record(
+        &self,
+        at: SystemTime,
+    ) {
+        if let Ok(mut restarts) = self.0.lock() {
+            restarts.push(epoch_ms(at));
+        }
+    }
+
+    /// Restarts within `[from, to]`.
+    pub fn between(
+        &self,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> ServerRestarts {
+        let (from, to) = (epoch_ms(from), epoch_ms(to));
+        let at_epoch_ms: Vec<u64> = self
+            .0
+            .lock()
+            .map(|restarts| {
+                restarts
+                    .iter()
+                    .copied()
+                    .filter(|at| (from..=to).contains(at))
+                    .collect()
+            })
+            .unwrap_or_default();
+        ServerRestarts {
+            count: at_epoch_ms.len(),
+            at_epoch_ms,
+        }
+    }
+}
+
+/// Server restarts observed during the measured phase; recorded in meta.json.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerRestarts {
+    pub count: usize,
+    pub at_epoch_ms: Vec<u64>,
+}
+
+fn epoch_ms(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 pub struct ProcessMonitor {
     command: String,
     args: Vec<String>,
     env_vars: HashMap<String, String>,
     shutdown_signal: oneshot::Receiver<()>,
     grace_period: Duration,
+    graceful_stop: bool,
 }
 
 impl ProcessMonitor {
@@ -29,14 +95,23 @@ impl ProcessMonitor {
             env_vars,
             shutdown_signal: shutdown_rx,
             grace_period,
+            graceful_stop: false,
         };
 
         (monitor, shutdown_tx)
     }
 
+    /// Stop the process with SIGTERM and wait up to the grace period before killing it, so it
+    /// can run its exit handlers (LeakSanitizer's leak check, say). Otherwise it is killed.
+    pub fn with_graceful_stop(mut self) -> Self {
+        self.graceful_stop = true;
+        self
+    }
+
     pub async fn run(
         &mut self,
         restarts_counter: GenericCounter<AtomicU64>,
+        restart_log: RestartLog,
     ) -> BenchmarkResult<()> {
         restarts_counter.reset();
         loop {
@@ -49,6 +124,7 @@ impl ProcessMonitor {
                         Ok(status) => {
                             warn!("Process exited with status: {:?}", status);
                             restarts_counter.inc();
+                            restart_log.record(SystemTime::now());
                             sleep(Duration::from_secs(1)).await;
                         }
                         Err(e) => {
@@ -89,9 +165,14 @@ impl ProcessMonitor {
         &self,
         child: &mut Child,
     ) {
-        // Send SIGTERM
-        if let Err(e) = child.start_kill() {
-            error!("Failed to send SIGTERM: {:?}", e);
+        let sent = match child.id().filter(|_| self.graceful_stop) {
+            Some(pid) => os_process::terminate(pid).map_err(|e| e.to_string()),
+            None => child
+                .start_kill()
+                .map_err(|e| format!("Failed to kill process: {:?}", e)),
+        };
+        if let Err(e) = sent {
+            error!("{}", e);
             return;
         }
 
@@ -109,3 +190,365 @@ impl ProcessMonitor {
         }
     }
 }
+
+/// A server this process didn't start, watched instead of supervised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachTarget {
+    /// Only this process; once it exits the server stays down.
+    Pid(u32),
+    /// Whichever local process listens on this TCP port, so a restarted server is picked up again.
+    Port(u16),
+}
+
+impl Display for AttachTarget {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            AttachTarget::Pid(pid) => write!(f, "pid {}", pid),
+            AttachTarget::Port(port) => write!(f, "port {}", port),
+        }
+    }
+}
+
+/// A stretch of time the attached server wasn't running; `to_epoch_ms` is `None` when it was
+/// still down at the end.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DowntimeWindow {
+    pub from_epoch_ms: u64,
+    pub to_epoch_ms: Option<u64>,
+}
+
+/// What watching an attached server saw during the measured phase; recorded in meta.json.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AttachedServerReport {
+    pub target: AttachTarget,
+    /// Every PID the server ran as, in order.
+    pub pids: Vec<u32>,
+    pub downtime: Vec<DowntimeWindow>,
+    pub downtime_ms: u64,
+    pub peak_memory_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct AttachState {
+    pid: Option<u32>,
+    pids: Vec<u32>,
+    downtime: Vec<DowntimeWindow>,
+    peak_memory_bytes: u64,
+}
+
+impl AttachState {
+    /// Record that the server runs as `pid` (`None`: not running) at `at`; returns whether it
+    /// came back as a new process.
+    fn observe(
+        &mut self,
+        pid: Option<u32>,
+        at: SystemTime,
+    ) -> bool {
+        let at = epoch_ms(at);
+        let open = self
+            .downtime
+            .last_mut()
+            .filter(|window| window.to_epoch_ms.is_none());
+        match (pid, open) {
+            (Some(pid), open) => {
+                if let Some(window) = open {
+                    window.to_epoch_ms = Some(at);
+                }
+                let restarted = self.pid.is_some_and(|previous| previous != pid)
+                    || (self.pid.is_none() && !self.pids.is_empty());
+                if !self.pids.contains(&pid) {
+                    self.pids.push(pid);
+                }
+                self.pid = Some(pid);
+                restarted
+            }
+            (None, None) => {
+                self.pid = None;
+                self.downtime.push(DowntimeWindow {
+                    from_epoch_ms: at,
+                    to_epoch_ms: None,
+                });
+                false
+            }
+            (None, Some(_)) => false,
+        }
+    }
+
+    /// The downtime within `[from, to]`, clipped to it.
+    fn downtime_between(
+        &self,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Vec<DowntimeWindow> {
+        let (from, to) = (epoch_ms(from), epoch_ms(to));
+        self.downtime
+            .iter()
+            .filter(|window| {
+                window.from_epoch_ms <= to && window.to_epoch_ms.unwrap_or(u64::MAX) >= from
+            })
+            .map(|window| DowntimeWindow {
+                from_epoch_ms: window.from_epoch_ms.max(from),
+                to_epoch_ms: window.to_epoch_ms.filter(|end| *end <= to),
+            })
+            .collect()
+    }
+}
+
+/// Watches an externally started server for CPU, memory and liveness, setting the same gauges
+/// and keeping the same [`RestartLog`] a [`ProcessMonitor`] would for a managed one.
+#[derive(Debug)]
+pub struct AttachedProcess {
+    target: AttachTarget,
+    state: Arc<Mutex<AttachState>>,
+    restart_log: RestartLog,
+    handle: JoinHandle<()>,
+    shutdown_tx: oneshot::Sender<()>,
+}
+
+impl AttachedProcess {
+    /// Start watching `target`; fails when it isn't running right now.
+    pub fn spawn(
+        target: AttachTarget,
+        cpu_gauge: IntGauge,
+        memory_gauges: Vec<IntGauge>,
+        restarts_counter: Option<GenericCounter<AtomicU64>>,
+    ) -> BenchmarkResult<Self> {
+        let mut system = System::new_all();
+        let pid = resolve_pid(target, &mut system)
+            .ok_or_else(|| OtherError(format!("No running server found for {}", target)))?;
+        info!("Attached to the server at {} (PID {})", target, pid);
+
+        let state = Arc::new(Mutex::new(AttachState::default()));
+        let restart_log = RestartLog::default();
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+        let handle = tokio::spawn({
+            let state = state.clone();
+            let restart_log = restart_log.clone();
+            async move {
+                let logical_cpus = system.cpus().len().max(1) as i64;
+                loop {
+                    let now = SystemTime::now();
+                    let pid = resolve_pid(target, &mut system);
+                    let sample = pid.and_then(|pid| system.process(Pid::from_u32(pid)));
+                    if let Some(process) = sample {
+                        cpu_gauge.set(process.cpu_usage() as i64 / logical_cpus);
+                        for gauge in &memory_gauges {
+                            gauge.set(process.memory() as i64);
+                        }
+                    }
+                    if let Ok(mut state) = state.lock() {
+                        let was_up = state.pid.is_some();
+                        if state.observe(pid, now) {
+                            warn!("The server at {} restarted as PID {:?}", target, pid);
+                            restart_log.record(now);
+                            if let Some(counter) = &restarts_counter {
+                                counter.inc();
+                            }
+                        } else if was_up && pid.is_none() {
+                            warn!("The server at {} is down", target);
+                        }
+                        let memory = sample.map_or(0, |process| process.memory());
+                        state.peak_memory_bytes = state.peak_memory_bytes.max(memory);
+                    }
+                    tokio::select! {
+                        _ = sleep(ATTACH_POLL_INTERVAL) => {}
+                        _ = &mut shutdown_rx => return,
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            target,
+            state,
+            restart_log,
+            handle,
+            shutdown_tx,
+        })
+    }
+
+    /// Restarts observed so far.
+    pub fn restart_log(&self) -> &RestartLog {
+        &self.restart_log
+    }
+
+    /// Stop watching and report what happened within `[from, to]`.
+    pub async fn stop(
+        self,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> AttachedServerReport {
+        let _ = self.shutdown_tx.send(());
+        let _ = self.handle.await;
+        let Ok(state) = self.state.lock() else {
+            return AttachedServerReport {
+                target: self.target,
+                pids: Vec::new(),
+                downtime: Vec::new(),
+                downtime_ms: 0,
+                peak_memory_bytes: 0,
+            };
+        };
+        let downtime = state.downtime_between(from, to);
+        let to_ms = epoch_ms(to);
+        AttachedServerReport {
+            target: self.target,
+            pids: state.pids.clone(),
+            downtime_ms: downtime
+                .iter()
+                .map(|window| window.to_epoch_ms.unwrap_or(to_ms) - window.from_epoch_ms)
+                .sum(),
+            downtime,
+            peak_memory_bytes: state.peak_memory_bytes,
+        }
+    }
+}
+
+/// The PID `target` currently runs as, if it's running.
+fn resolve_pid(
+    target: AttachTarget,
+    system: &mut System,
+) -> Option<u32> {
+    let pid = match target {
+        AttachTarget::Pid(pid) => pid,
+        AttachTarget::Port(port) => port_owner(port)?,
+    };
+    system.refresh_processes(ProcessesToUpdate::Some(&[Pid::from_u32(pid)]), true);
+    system
+        .process(Pid::from_u32(pid))
+        .filter(|process| {
+            !matches!(
+                process.status(),
+                ProcessStatus::Zombie | ProcessStatus::Dead
+            )
+        })
+        .map(|_| pid)
+}
+
+/// The process listening on TCP `port`, from `/proc/net/tcp{,6}` and the socket links under
+/// `/proc/<pid>/fd`. Other users' processes are only visible to root.
+#[cfg(target_os = "linux")]
+fn port_owner(port: u16) -> Option<u32> {
+    let inodes: Vec<u64> = ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .filter_map(|table| std::fs::read_to_string(table).ok())
+        .flat_map(|table| listening_inodes(&table, port))
+        .collect();
+    if inodes.is_empty() {
+        return None;
+    }
+    let sockets: Vec<String> = inodes
+        .iter()
+        .map(|inode| format!("socket:[{}]", inode))
+        .collect();
+    std::fs::read_dir("/proc")
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .find(|pid| {
+            std::fs::read_dir(format!("/proc/{}/fd", pid))
+                .into_iter()
+                .flatten()
+                .filter_map(|fd| std::fs::read_link(fd.ok()?.path()).ok())
+                .any(|link| sockets.iter().any(|socket| link.to_str() == Some(socket)))
+        })
+}
+
+/// The process listening on TCP `port` where there is no `/proc`, e.g. macOS, as `lsof` reports
+/// it. Other users' processes are only visible to root.
+#[cfg(not(target_os = "linux"))]
+fn port_owner(port: u16) -> Option<u32> {
+    let output = std::process::Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-t"])
+        .output()
+        .ok()?;
+    first_pid(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The first PID of `lsof -t` output, one PID per line.
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn first_pid(lsof: &str) -> Option<u32> {
+    lsof.lines().find_map(|line| line.trim().parse().ok())
+}
+
+/// Inodes of the sockets listening on `port` in a `/proc/net/tcp` table.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn listening_inodes(
+    table: &str,
+    port: u16,
+) -> Vec<u64> {
+    const TCP_LISTEN: &str = "0A";
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local_port = fields.get(1)?.rsplit_once(':')?.1;
+            (u16::from_str_radix(local_port, 16).ok()? == port && *fields.get(3)? == TCP_LISTEN)
+                .then(|| fields.get(9)?.parse().ok())?
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn restart_log_reports_only_restarts_inside_the_window() {
+        let log = RestartLog::default();
+        let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for offset_secs in [5, 20, 40, 90] {
+            log.record(t0 + Duration::from_secs(offset_secs));
+        }
+
+        let restarts = log.between(t0 + Duration::from_secs(10), t0 + Duration::from_secs(40));
+        assert_eq!(
+            restarts,
+            ServerRestarts {
+                count: 2,
+                at_epoch_ms: vec![1_700_000_020_000, 1_700_000_040_000],
+            }
+        );
+        assert_eq!(log.between(t0, t0).count, 0);
+    }
+
+    #[test]
+    fn attached_server_restarts_and_downtime() {
+        let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        let mut state = AttachState::default();
+        assert!(!state.observe(Some(100), at(0)));
+        assert!(!state.observe(None, at(10)));
+        assert!(!state.observe(None, at(11)));
+        assert!(state.observe(Some(200), at(15)));
+        // A restart between two polls has no downtime but a new PID.
+        assert!(state.observe(Some(300), at(20)));
+        assert!(!state.observe(None, at(30)));
+        assert_eq!(state.pids, vec![100, 200, 300]);
+        assert_eq!(
+            state.downtime_between(at(12), at(40)),
+            vec![
+                DowntimeWindow {
+                    from_epoch_ms: 1_700_000_012_000,
+                    to_epoch_ms: Some(1_700_000_015_000),
+                },
+                DowntimeWindow {
+                    from_epoch_ms: 1_700_000_030_000,
+                    to_epoch_ms: None,
+                },
+            ]
+        );
+
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:18EB 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4242 1 0 100 0 0 10 0
+   1: 0100007F:18EB 0100007F:9C40 01 00000000:00000000 00:00000000 00000000  1000        0 4343 1 0 20 4 30 10 -1";
+        assert_eq!(listening_inodes(table, 6379), vec![4242]);
+        assert!(listening_inodes(table, 7687).is_empty());
+        assert_eq!(first_pid("4242\n4243\n"), Some(4242));
+        assert_eq!(first_pid(""), None);
+    }
+}
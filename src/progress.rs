@@ -0,0 +1,133 @@
+//! A progress bar on stderr for `load` and `run`, redrawn from [`run_status`] (the counters
+//! behind the periodic progress log lines and `/status`): items loaded out of the dataset's
+//! estimate, then queries completed out of the file's with an ETA.
+//!
+//! It's only drawn when stderr is a terminal; `--no-progress` turns it off there too.
+
+use crate::run_status::{self, Phase, RunStatus};
+use crate::utils::format_number;
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+const BAR_WIDTH: usize = 30;
+
+/// Clears its line when dropped.
+pub struct ProgressBar {
+    handle: JoinHandle<()>,
+}
+
+impl ProgressBar {
+    /// Start redrawing unless `disabled` or stderr isn't a terminal.
+    pub fn spawn(disabled: bool) -> Option<ProgressBar> {
+        if disabled || !std::io::stderr().is_terminal() {
+            return None;
+        }
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REDRAW_INTERVAL);
+            loop {
+                interval.tick().await;
+                let line = render(&run_status::snapshot()).unwrap_or_default();
+                let mut stderr = std::io::stderr().lock();
+                let _ = write!(stderr, "\r\x1b[2K{}", line);
+                let _ = stderr.flush();
+            }
+        });
+        Some(ProgressBar { handle })
+    }
+}
+
+impl Drop for ProgressBar {
+    fn drop(&mut self) {
+        self.handle.abort();
+        let _ = write!(std::io::stderr(), "\r\x1b[2K");
+    }
+}
+
+/// The bar for `status`; `None` outside loading and the measured phase.
+pub fn render(status: &RunStatus) -> Option<String> {
+    let (action, unit) = match status.phase {
+        Phase::Loading => ("load", "items"),
+        Phase::Running | Phase::Draining => ("run", "queries"),
+        _ => return None,
+    };
+    let vendor = status.vendor.as_deref().unwrap_or_default();
+    let rate = if status.elapsed_secs > 0 {
+        status.completed as f64 / status.elapsed_secs as f64
+    } else {
+        0.0
+    };
+    if status.total == 0 {
+        return Some(format!(
+            "{} {} {} {} {:.0}/s",
+            action,
+            vendor,
+            format_number(status.completed),
+            unit,
+            rate
+        ));
+    }
+    let fraction = (status.completed as f64 / status.total as f64).min(1.0);
+    let filled = (fraction * BAR_WIDTH as f64) as usize;
+    let eta = if rate > 0.0 {
+        format_eta(status.total.saturating_sub(status.completed) as f64 / rate)
+    } else {
+        "?".to_string()
+    };
+    Some(format!(
+        "{} {} [{}{}] {}/{} {} {:.0}% {:.0}/s ETA {}",
+        action,
+        vendor,
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        format_number(status.completed),
+        format_number(status.total),
+        unit,
+        fraction * 100.0,
+        rate,
+        eta
+    ))
+}
+
+fn format_eta(secs: f64) -> String {
+    let secs = secs.ceil() as u64;
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, _) => format!("{}h{:02}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bars_for_loading_and_running() {
+        let running = RunStatus {
+            phase: Phase::Running,
+            vendor: Some("falkor".to_string()),
+            elapsed_secs: 10,
+            total: 4000,
+            completed: 1000,
+            ..RunStatus::default()
+        };
+        assert_eq!(
+            render(&running).unwrap(),
+            "run falkor [#######-----------------------] 1,000/4,000 queries 25% 100/s ETA 30s"
+        );
+
+        let loading = RunStatus {
+            phase: Phase::Loading,
+            vendor: Some("neo4j".to_string()),
+            elapsed_secs: 0,
+            total: 131_716,
+            ..RunStatus::default()
+        };
+        assert!(render(&loading).unwrap().ends_with("items 0% 0/s ETA ?"));
+        assert!(render(&RunStatus::default()).is_none());
+        assert_eq!(format_eta(3725.0), "1h02m");
+        assert_eq!(format_eta(61.2), "1m02s");
+    }
+}
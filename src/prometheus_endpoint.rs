@@ -1,48 +1,215 @@
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::run_control;
+use crate::run_status;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::Args;
+use hyper::server::accept::Accept;
+use hyper::server::Builder;
 use hyper::service::{make_service_fn, service_fn};
-use hyper::{Body, Request, Response, Server};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
 use prometheus::{Encoder, TextEncoder};
-use std::net::SocketAddr;
-use tokio::sync::oneshot::Sender;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+use tokio::sync::oneshot::{Receiver, Sender};
 use tokio::task;
 use tokio::task::JoinHandle;
-use tracing::{error, info, trace};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{crypto, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, info, trace, warn};
 
-pub struct PrometheusEndpoint {
-    shutdown_tx: Option<Sender<()>>,
-    server_thread: Option<JoinHandle<()>>,
+/// Port tried when `--metrics-port` isn't given; the one the bundled Prometheus scrapes.
+pub const DEFAULT_METRICS_PORT: u16 = 8080;
+
+/// How long a client gets to finish the TLS handshake before it's dropped.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the metrics endpoint listens and who may use it. It binds to localhost unless
+/// `--metrics-expose` allows another address, since on a shared network anyone could otherwise
+/// read the metrics and, with `run --control-api`, drive the run.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct MetricsEndpointConfig {
+    #[arg(
+        long,
+        required = false,
+        help = "port of the Prometheus metrics endpoint (0 for an ephemeral one); by default 8080, or an ephemeral port when 8080 is taken"
+    )]
+    pub metrics_port: Option<u16>,
+    #[arg(
+        long,
+        required = false,
+        default_value = "127.0.0.1",
+        help = "address the Prometheus metrics endpoint binds to; any but a loopback address also needs --metrics-expose"
+    )]
+    pub metrics_bind_addr: IpAddr,
+    #[arg(
+        long,
+        required = false,
+        default_value_t = false,
+        help = "allow --metrics-bind-addr to be reachable from other machines (e.g. 0.0.0.0); consider --metrics-basic-auth and --metrics-tls-cert with it"
+    )]
+    pub metrics_expose: bool,
+    #[arg(
+        long,
+        env = "BENCHMARK_METRICS_BASIC_AUTH",
+        required = false,
+        help = "require HTTP basic auth as <user>:<password> on every path of the metrics endpoint but /health"
+    )]
+    pub metrics_basic_auth: Option<BasicAuth>,
+    #[arg(
+        long,
+        required = false,
+        requires = "metrics_tls_key",
+        help = "serve the metrics endpoint over HTTPS with this PEM certificate (chain)"
+    )]
+    pub metrics_tls_cert: Option<PathBuf>,
+    #[arg(
+        long,
+        required = false,
+        requires = "metrics_tls_cert",
+        help = "PEM private key of --metrics-tls-cert"
+    )]
+    pub metrics_tls_key: Option<PathBuf>,
 }
 
-impl Default for PrometheusEndpoint {
+impl Default for MetricsEndpointConfig {
     fn default() -> Self {
-        Self::new()
+        MetricsEndpointConfig {
+            metrics_port: None,
+            metrics_bind_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            metrics_expose: false,
+            metrics_basic_auth: None,
+            metrics_tls_cert: None,
+            metrics_tls_key: None,
+        }
     }
 }
 
-impl PrometheusEndpoint {
-    fn new() -> Self {
-        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+/// `--metrics-basic-auth <user>:<password>`; the password stays out of `Debug`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BasicAuth {
+    user: String,
+    password: String,
+}
 
-        let server_thread = task::spawn(async {
-            let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+impl BasicAuth {
+    /// The `Authorization` header a request must carry.
+    fn header_value(&self) -> String {
+        format!(
+            "Basic {}",
+            STANDARD.encode(format!("{}:{}", self.user, self.password))
+        )
+    }
+}
 
-            let make_svc = make_service_fn(|_conn| async {
-                Ok::<_, hyper::Error>(service_fn(metrics_handler))
-            });
+impl FromStr for BasicAuth {
+    type Err = String;
 
-            let server = Server::bind(&addr).serve(make_svc);
-            let graceful = server.with_graceful_shutdown(async {
-                shutdown_rx.await.ok();
-            });
-            info!("Listening on http://{}", addr);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((user, password)) if !user.is_empty() && !password.is_empty() => Ok(BasicAuth {
+                user: user.to_string(),
+                password: password.to_string(),
+            }),
+            _ => Err("expected <user>:<password> with both set".to_string()),
+        }
+    }
+}
 
-            if let Err(e) = graceful.await {
-                error!("server error: {}", e);
+impl fmt::Debug for BasicAuth {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "BasicAuth({}:***)", self.user)
+    }
+}
+
+pub struct PrometheusEndpoint {
+    local_addr: SocketAddr,
+    shutdown_tx: Option<Sender<()>>,
+    server_thread: Option<JoinHandle<()>>,
+}
+
+impl PrometheusEndpoint {
+    /// Serve `/metrics` as `config` says, failing if the port is taken; port 0 picks an
+    /// ephemeral one. Without a port, [`DEFAULT_METRICS_PORT`] is tried first and an ephemeral
+    /// port is used when it's taken, so concurrent benchmark processes can each expose their
+    /// metrics.
+    pub fn bind(config: &MetricsEndpointConfig) -> BenchmarkResult<Self> {
+        let ip = config.metrics_bind_addr;
+        if !ip.is_loopback() && !config.metrics_expose {
+            return Err(OtherError(format!(
+                "--metrics-bind-addr {} makes the metrics endpoint reachable from other machines; pass --metrics-expose to allow it",
+                ip
+            )));
+        }
+        let listener = bind_listener(ip, config.metrics_port)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let auth = config
+            .metrics_basic_auth
+            .as_ref()
+            .map(BasicAuth::header_value);
+
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let server_thread = match (&config.metrics_tls_cert, &config.metrics_tls_key) {
+            (Some(cert), Some(key)) => {
+                let acceptor = tls_acceptor(cert, key)?;
+                let listener = tokio::net::TcpListener::from_std(listener)?;
+                let (connections_tx, connections_rx) = mpsc::channel(16);
+                task::spawn(accept_tls(listener, acceptor, connections_tx));
+                info!("Listening on https://{}", local_addr);
+                serve(
+                    Server::builder(hyper::server::accept::from_stream(ReceiverStream::new(
+                        connections_rx,
+                    ))),
+                    auth,
+                    shutdown_rx,
+                )
             }
-        });
+            _ => {
+                let builder = Server::from_tcp(listener).map_err(|e| {
+                    OtherError(format!("Failed to serve the metrics endpoint: {}", e))
+                })?;
+                info!("Listening on http://{}", local_addr);
+                serve(builder, auth, shutdown_rx)
+            }
+        };
 
-        PrometheusEndpoint {
+        Ok(PrometheusEndpoint {
+            local_addr,
             shutdown_tx: Some(shutdown_tx),
             server_thread: Some(server_thread),
+        })
+    }
+
+    /// The address actually bound, with the ephemeral port resolved.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop serving and wait until the port is released, so the next run in this process can
+    /// bind it again.
+    pub async fn shutdown(mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(());
+            info!("Prometheus endpoint shutdown");
+        }
+        if let Some(thread) = self.server_thread.take() {
+            let _ = thread.await;
         }
     }
 }
@@ -59,14 +226,278 @@ impl Drop for PrometheusEndpoint {
     }
 }
 
-async fn metrics_handler(_req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+fn bind_listener(
+    ip: IpAddr,
+    port: Option<u16>,
+) -> BenchmarkResult<TcpListener> {
+    match port {
+        Some(port) => TcpListener::bind(SocketAddr::new(ip, port)).map_err(|e| {
+            OtherError(format!(
+                "Failed to bind the metrics endpoint to {}: {}",
+                SocketAddr::new(ip, port),
+                e
+            ))
+        }),
+        None => match TcpListener::bind(SocketAddr::new(ip, DEFAULT_METRICS_PORT)) {
+            Ok(listener) => Ok(listener),
+            Err(e) => {
+                warn!(
+                    "Metrics port {} is unavailable ({}), using an ephemeral port",
+                    DEFAULT_METRICS_PORT, e
+                );
+                TcpListener::bind(SocketAddr::new(ip, 0))
+                    .map_err(|e| OtherError(format!("Failed to bind the metrics endpoint: {}", e)))
+            }
+        },
+    }
+}
+
+/// Run the endpoint on `builder`'s connections until `shutdown` fires.
+fn serve<I>(
+    builder: Builder<I>,
+    auth: Option<String>,
+    shutdown: Receiver<()>,
+) -> JoinHandle<()>
+where
+    I: Accept + Send + 'static,
+    I::Conn: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    I::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let auth = Arc::new(auth);
+    let make_svc = make_service_fn(move |_conn| {
+        let auth = auth.clone();
+        async move { Ok::<_, hyper::Error>(service_fn(move |req| metrics_handler(req, auth.clone()))) }
+    });
+    let server = builder.serve(make_svc);
+    task::spawn(async move {
+        let graceful = server.with_graceful_shutdown(async {
+            shutdown.await.ok();
+        });
+        if let Err(e) = graceful.await {
+            error!("server error: {}", e);
+        }
+    })
+}
+
+fn tls_acceptor(
+    cert: &Path,
+    key: &Path,
+) -> BenchmarkResult<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            OtherError(format!(
+                "could not read the certificate '{}': {}",
+                cert.display(),
+                e
+            ))
+        })?;
+    let key = PrivateKeyDer::from_pem_file(key).map_err(|e| {
+        OtherError(format!(
+            "could not read the private key '{}': {}",
+            key.display(),
+            e
+        ))
+    })?;
+    // Explicit, as other dependencies enable a second crypto provider.
+    let config = ServerConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| OtherError(format!("invalid metrics TLS certificate: {}", e)))?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Accept connections and hand them to the server once their handshake completes, so a slow
+/// client doesn't hold up the others. Stops when the server is gone.
+async fn accept_tls(
+    listener: tokio::net::TcpListener,
+    acceptor: TlsAcceptor,
+    connections: mpsc::Sender<std::io::Result<TlsStream<tokio::net::TcpStream>>>,
+) {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("metrics endpoint failed to accept a connection: {}", e);
+                    continue;
+                }
+            },
+            _ = connections.closed() => return,
+        };
+        let (acceptor, connections) = (acceptor.clone(), connections.clone());
+        task::spawn(async move {
+            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = connections.send(Ok(stream)).await;
+                }
+                Ok(Err(e)) => debug!("metrics TLS handshake failed: {}", e),
+                Err(_) => debug!("metrics TLS handshake timed out"),
+            }
+        });
+    }
+}
+
+/// `/health` answers while the process is up, `/status` is the [`run_status`] snapshot as
+/// JSON, `/control/*` drives a `run --control-api` (see [`control_response`]), and every
+/// other path serves the metrics. With `--metrics-basic-auth`, all but `/health` need it.
+async fn metrics_handler(
+    req: Request<Body>,
+    auth: Arc<Option<String>>,
+) -> Result<Response<Body>, hyper::Error> {
+    if let Some(expected) = auth.as_deref() {
+        let authorized = req
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .is_some_and(|value| value.as_bytes() == expected.as_bytes());
+        if !authorized && req.uri().path() != "/health" {
+            return Ok(Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .header(hyper::header::WWW_AUTHENTICATE, "Basic realm=\"benchmark\"")
+                .body(Body::empty())
+                .unwrap());
+        }
+    }
+    Ok(match req.uri().path() {
+        "/health" => json_response(serde_json::json!({ "status": "ok" })),
+        "/status" => json_response(run_status::snapshot()),
+        path if path.starts_with("/control/") => control_response(req).await?,
+        _ => metrics_response(),
+    })
+}
+
+#[derive(Deserialize)]
+struct MpsRequest {
+    mps: usize,
+}
+
+/// `POST /control/start`, `POST /control/stop` and `POST /control/mps` with `{"mps": <n>}`.
+async fn control_response(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+    let error = |status: StatusCode, message: &str| {
+        let mut response = json_response(serde_json::json!({ "error": message }));
+        *response.status_mut() = status;
+        response
+    };
+    let Some(control) = run_control::current() else {
+        return Ok(error(
+            StatusCode::NOT_FOUND,
+            "the control API is only served by `run --control-api`",
+        ));
+    };
+    if req.method() != Method::POST {
+        return Ok(error(StatusCode::METHOD_NOT_ALLOWED, "use POST"));
+    }
+    let path = req.uri().path().to_string();
+    Ok(match path.as_str() {
+        "/control/start" => {
+            control.start();
+            json_response(run_status::snapshot())
+        }
+        "/control/stop" => {
+            control.stop();
+            json_response(run_status::snapshot())
+        }
+        "/control/mps" => {
+            let body = hyper::body::to_bytes(req.into_body()).await?;
+            match serde_json::from_slice::<MpsRequest>(&body) {
+                Ok(MpsRequest { mps }) if mps > 0 => {
+                    control.set_mps(mps);
+                    json_response(run_status::snapshot())
+                }
+                _ => error(
+                    StatusCode::BAD_REQUEST,
+                    "expected a body like {\"mps\": 1000} with a positive rate",
+                ),
+            }
+        }
+        _ => error(StatusCode::NOT_FOUND, "unknown control action"),
+    })
+}
+
+fn metrics_response() -> Response<Body> {
     let encoder = TextEncoder::new();
     let metric_families = prometheus::gather();
     let mut buffer = Vec::new();
     encoder.encode(&metric_families, &mut buffer).unwrap();
     trace!("Metrics request received");
-    Ok(Response::builder()
+    Response::builder()
         .header(hyper::header::CONTENT_TYPE, encoder.format_type())
         .body(Body::from(buffer))
-        .unwrap())
+        .unwrap()
+}
+
+fn json_response(body: impl Serialize) -> Response<Body> {
+    Response::builder()
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn an_explicit_port_in_use_is_an_error() {
+        let first = PrometheusEndpoint::bind(&MetricsEndpointConfig {
+            metrics_port: Some(0),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_ne!(first.local_addr().port(), 0);
+        assert!(PrometheusEndpoint::bind(&MetricsEndpointConfig {
+            metrics_port: Some(first.local_addr().port()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn external_addresses_need_opt_in_and_basic_auth_guards_all_but_health() {
+        let external = MetricsEndpointConfig {
+            metrics_port: Some(0),
+            metrics_bind_addr: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            ..Default::default()
+        };
+        assert!(PrometheusEndpoint::bind(&external).is_err());
+        assert!(PrometheusEndpoint::bind(&MetricsEndpointConfig {
+            metrics_expose: true,
+            ..external
+        })
+        .is_ok());
+        assert!("user".parse::<BasicAuth>().is_err());
+        assert!("user:".parse::<BasicAuth>().is_err());
+
+        let endpoint = PrometheusEndpoint::bind(&MetricsEndpointConfig {
+            metrics_port: Some(0),
+            metrics_basic_auth: Some("scraper:s3cret:x".parse().unwrap()),
+            ..Default::default()
+        })
+        .unwrap();
+        let url = |path: &str| format!("http://{}{}", endpoint.local_addr(), path);
+        let client = reqwest::Client::new();
+        let status = |request: reqwest::RequestBuilder| async move {
+            request.send().await.unwrap().status().as_u16()
+        };
+        assert_eq!(status(client.get(url("/metrics"))).await, 401);
+        assert_eq!(
+            status(
+                client
+                    .get(url("/metrics"))
+                    .basic_auth("scraper", Some("wrong"))
+            )
+            .await,
+            401
+        );
+        assert_eq!(
+            status(
+                client
+                    .get(url("/metrics"))
+                    .basic_auth("scraper", Some("s3cret:x"))
+            )
+            .await,
+            200
+        );
+        assert_eq!(status(client.get(url("/health"))).await, 200);
+    }
 }
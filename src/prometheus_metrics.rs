@@ -1,31 +1,79 @@
 use crate::error::BenchmarkResult;
+use crate::scenario::Vendor;
+use crate::{
+    NETWORK_RECEIVED_BYTES_TOTAL, NETWORK_TRANSMITTED_BYTES_TOTAL, PROCESS_DISK_READ_BYTES_TOTAL,
+    PROCESS_DISK_WRITTEN_BYTES_TOTAL,
+};
 use std::future::Future;
 use std::sync::{Arc, Mutex};
-use sysinfo::System;
+use std::time::Duration;
+use sysinfo::{Networks, Process, System};
 use tokio::task::JoinHandle;
 use tracing::info;
 
+/// How often metrics are refreshed, both for locally managed processes and external endpoints.
+pub const METRICS_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
 pub(crate) fn run_metrics_reporter<FN, FUTURE>(
     measure: FN
 ) -> (JoinHandle<()>, tokio::sync::oneshot::Sender<()>)
 where
     FN: Fn(Arc<Mutex<System>>) -> FUTURE + Send + Sync + 'static,
     FUTURE: Future<Output = BenchmarkResult<()>> + Send + 'static,
+{
+    let system = Arc::new(Mutex::new(System::new_all()));
+    let networks = Mutex::new(Networks::new_with_refreshed_list());
+    run_periodic_reporter(move || {
+        report_network_io(&networks);
+        measure(system.clone())
+    })
+}
+
+/// Add what the host's interfaces, loopback included, received and transmitted since the
+/// previous refresh to the `network_*_bytes_total` counters.
+fn report_network_io(networks: &Mutex<Networks>) {
+    let mut networks = networks.lock().unwrap();
+    networks.refresh(true);
+    for data in networks.list().values() {
+        NETWORK_RECEIVED_BYTES_TOTAL.inc_by(data.received());
+        NETWORK_TRANSMITTED_BYTES_TOTAL.inc_by(data.transmitted());
+    }
+}
+
+/// Add what the server process read from and wrote to disk since the previous refresh of
+/// `process` to the `process_disk_*_bytes_total` counters. A process seen for the first time,
+/// e.g. after a restart, counts everything since it started.
+pub(crate) fn report_process_disk_io(
+    vendor: Vendor,
+    process: &Process,
+) {
+    let usage = process.disk_usage();
+    PROCESS_DISK_READ_BYTES_TOTAL
+        .with_label_values(&[vendor.metric_label()])
+        .inc_by(usage.read_bytes);
+    PROCESS_DISK_WRITTEN_BYTES_TOTAL
+        .with_label_values(&[vendor.metric_label()])
+        .inc_by(usage.written_bytes);
+}
+
+fn run_periodic_reporter<FN, FUTURE>(
+    measure: FN
+) -> (JoinHandle<()>, tokio::sync::oneshot::Sender<()>)
+where
+    FN: Fn() -> FUTURE + Send + Sync + 'static,
+    FUTURE: Future<Output = BenchmarkResult<()>> + Send + 'static,
 {
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel();
 
     let handle = tokio::spawn(async move {
-        let system = Arc::new(Mutex::new(System::new_all()));
-        let sys = system.clone();
-        if let Err(e) = measure(sys).await {
+        if let Err(e) = measure().await {
             info!("Error reporting metrics: {:?}", e);
         }
 
         loop {
-            let sys = system.clone();
             tokio::select! {
-                _ = tokio::time::sleep(std::time::Duration::from_secs(5)) => {
-                    if let Err(e) = measure(sys).await {
+                _ = tokio::time::sleep(METRICS_REPORT_INTERVAL) => {
+                    if let Err(e) = measure().await {
                         info!("Error reporting metrics: {:?}", e);
                     }
                 }
@@ -39,3 +87,60 @@ where
 
     (handle, shutdown_tx)
 }
+
+/// Background task polling a vendor's query-interface metrics (e.g. `GRAPH.MEMORY`,
+/// `SHOW STORAGE INFO`, `dbms.queryJmx`) every [`METRICS_REPORT_INTERVAL`].
+///
+/// External endpoints have no local process to monitor, so this is what keeps their gauges
+/// moving during the run instead of holding a single before/after sample.
+pub struct EndpointMetricsPoller {
+    handle: JoinHandle<()>,
+    shutdown_tx: tokio::sync::oneshot::Sender<()>,
+}
+
+impl EndpointMetricsPoller {
+    /// Poll once immediately, then every interval until [`EndpointMetricsPoller::stop`].
+    pub fn spawn<FN, FUTURE>(poll: FN) -> Self
+    where
+        FN: Fn() -> FUTURE + Send + Sync + 'static,
+        FUTURE: Future<Output = BenchmarkResult<()>> + Send + 'static,
+    {
+        let (handle, shutdown_tx) = run_periodic_reporter(poll);
+        Self {
+            handle,
+            shutdown_tx,
+        }
+    }
+
+    pub async fn stop(self) {
+        drop(self.shutdown_tx);
+        let _ = self.handle.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn endpoint_poller_polls_until_stopped() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let counter = polls.clone();
+        let poller = EndpointMetricsPoller::spawn(move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+
+        tokio::time::sleep(METRICS_REPORT_INTERVAL * 2 + Duration::from_millis(1)).await;
+        poller.stop().await;
+        let after_stop = polls.load(Ordering::SeqCst);
+        assert_eq!(after_stop, 3);
+
+        tokio::time::sleep(METRICS_REPORT_INTERVAL * 2).await;
+        assert_eq!(polls.load(Ordering::SeqCst), after_stop);
+    }
+}
@@ -1,23 +1,57 @@
-use crate::query::{Bolt, Query, QueryBuilder};
+use crate::bounded_writes::{
+    is_compensation_query_name, MutationTracker, DROP_CREATED_USER_QUERY_NAME,
+    DROP_CREATED_USER_TEXT, RESTORE_USER_QUERY_NAME, RESTORE_USER_TEXT,
+};
+use crate::dataset_graph::DatasetGraph;
+use crate::query::{Bolt, Query, QueryBuilder, QueryParam};
+use crate::scenario::Vendor;
 use clap::ValueEnum;
 use rand::prelude::IndexedRandom;
-use rand::random;
-use rand::{Rng, RngExt};
+use rand::rngs::StdRng;
+use rand::{Rng, RngExt, SeedableRng};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
 pub enum QueryType {
     Read,
     Write,
 }
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+
+impl QueryType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QueryType::Read => "read",
+            QueryType::Write => "write",
+        }
+    }
+}
+/// Cypher dialect a query is rendered for. Serialized with the vendor names so the per-flavour
+/// renderings in a queries file are keyed by vendor.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Flavour {
+    #[serde(rename = "falkor")]
     FalkorDB,
     Neo4j,
     Memgraph,
 }
 
+impl Flavour {
+    pub const ALL: [Flavour; 3] = [Flavour::FalkorDB, Flavour::Neo4j, Flavour::Memgraph];
+}
+
+impl From<Vendor> for Flavour {
+    fn from(vendor: Vendor) -> Self {
+        match vendor {
+            Vendor::Falkor => Flavour::FalkorDB,
+            Vendor::Neo4j => Flavour::Neo4j,
+            Vendor::Memgraph => Flavour::Memgraph,
+        }
+    }
+}
+
 pub const NEO4J_ALGORITHM_GRAPH_NAME: &str = "benchmark_algo_graph";
 const ALGORITHM_QUERY_TARGET_RATIO_PER_QUERY: f32 = 0.01;
 const ALGORITHM_QUERY_NAMES: [&str; 4] = [
@@ -81,6 +115,315 @@ impl QueryCoverageProfile {
     }
 }
 
+/// How generated queries pick the node ids they touch.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ValueEnum, Default)]
+#[serde(rename_all = "kebab-case")]
+#[value(rename_all = "kebab-case")]
+pub enum AccessDistribution {
+    /// Every vertex is equally likely (the historical behaviour).
+    #[default]
+    Uniform,
+    /// Zipf-distributed ranks; low ids are the hottest.
+    Zipfian,
+    /// Zipf-distributed ranks counted from the highest id, favouring recently inserted vertices.
+    Latest,
+    /// A fixed fraction of the id space receives a fixed fraction of the accesses.
+    Hotspot,
+}
+
+pub const DEFAULT_ZIPF_EXPONENT: f64 = 0.99;
+pub const DEFAULT_HOT_SET_FRACTION: f64 = 0.2;
+pub const DEFAULT_HOT_ACCESS_FRACTION: f64 = 0.8;
+
+/// Node-id access pattern used while generating queries; stored in the queries file metadata.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AccessPattern {
+    pub distribution: AccessDistribution,
+    /// Skew of the `zipfian` and `latest` distributions (must be > 0).
+    pub zipf_exponent: f64,
+    /// Share of the id space that is hot for the `hotspot` distribution.
+    pub hot_set_fraction: f64,
+    /// Share of accesses that land in the hot set for the `hotspot` distribution.
+    pub hot_access_fraction: f64,
+}
+
+impl Default for AccessPattern {
+    fn default() -> Self {
+        Self {
+            distribution: AccessDistribution::Uniform,
+            zipf_exponent: DEFAULT_ZIPF_EXPONENT,
+            hot_set_fraction: DEFAULT_HOT_SET_FRACTION,
+            hot_access_fraction: DEFAULT_HOT_ACCESS_FRACTION,
+        }
+    }
+}
+
+/// Draws vertex ids in `1..=vertices` according to an [`AccessPattern`].
+#[derive(Debug, Clone, Copy)]
+enum VertexSampler {
+    Uniform,
+    Zipfian {
+        zipf: ZipfSampler,
+        reversed: bool,
+    },
+    Hotspot {
+        hot_vertices: i32,
+        hot_access_fraction: f64,
+    },
+}
+
+impl VertexSampler {
+    fn new(
+        access_pattern: AccessPattern,
+        vertices: i32,
+    ) -> Self {
+        let vertices = vertices.max(1);
+        match access_pattern.distribution {
+            AccessDistribution::Uniform => VertexSampler::Uniform,
+            AccessDistribution::Zipfian | AccessDistribution::Latest => VertexSampler::Zipfian {
+                zipf: ZipfSampler::new(vertices, access_pattern.zipf_exponent),
+                reversed: access_pattern.distribution == AccessDistribution::Latest,
+            },
+            AccessDistribution::Hotspot => {
+                let hot_vertices = (vertices as f64
+                    * access_pattern.hot_set_fraction.clamp(0.0, 1.0))
+                .round()
+                .clamp(1.0, vertices as f64) as i32;
+                VertexSampler::Hotspot {
+                    hot_vertices,
+                    hot_access_fraction: access_pattern.hot_access_fraction.clamp(0.0, 1.0),
+                }
+            }
+        }
+    }
+
+    fn sample(
+        &self,
+        rng: &mut dyn Rng,
+        vertices: i32,
+    ) -> i32 {
+        match *self {
+            VertexSampler::Uniform => rng.random_range(1..=vertices),
+            VertexSampler::Zipfian { zipf, reversed } => {
+                let rank = zipf.sample(rng);
+                if reversed {
+                    vertices - rank + 1
+                } else {
+                    rank
+                }
+            }
+            VertexSampler::Hotspot {
+                hot_vertices,
+                hot_access_fraction,
+            } => {
+                if hot_vertices >= vertices || rng.random::<f64>() < hot_access_fraction {
+                    rng.random_range(1..=hot_vertices)
+                } else {
+                    rng.random_range(hot_vertices + 1..=vertices)
+                }
+            }
+        }
+    }
+}
+
+/// Zipf sampler over `1..=n` using rejection-inversion (Hörmann & Derflinger), so no per-n
+/// normalisation table is needed even for the large datasets.
+#[derive(Debug, Clone, Copy)]
+struct ZipfSampler {
+    n: i32,
+    exponent: f64,
+    h_integral_x1: f64,
+    h_integral_n: f64,
+    s: f64,
+}
+
+impl ZipfSampler {
+    fn new(
+        n: i32,
+        exponent: f64,
+    ) -> Self {
+        let exponent = if exponent.is_finite() && exponent > 0.0 {
+            exponent
+        } else {
+            DEFAULT_ZIPF_EXPONENT
+        };
+        let mut sampler = ZipfSampler {
+            n: n.max(1),
+            exponent,
+            h_integral_x1: 0.0,
+            h_integral_n: 0.0,
+            s: 0.0,
+        };
+        sampler.h_integral_x1 = sampler.h_integral(1.5) - 1.0;
+        sampler.h_integral_n = sampler.h_integral(sampler.n as f64 + 0.5);
+        sampler.s = 2.0 - sampler.h_integral_inverse(sampler.h_integral(2.5) - sampler.h(2.0));
+        sampler
+    }
+
+    fn sample(
+        &self,
+        rng: &mut dyn Rng,
+    ) -> i32 {
+        loop {
+            let u =
+                self.h_integral_n + rng.random::<f64>() * (self.h_integral_x1 - self.h_integral_n);
+            let x = self.h_integral_inverse(u);
+            let k = ((x + 0.5) as i64).clamp(1, self.n as i64);
+            let kf = k as f64;
+            if kf - x <= self.s || u >= self.h_integral(kf + 0.5) - self.h(kf) {
+                return k as i32;
+            }
+        }
+    }
+
+    fn h(
+        &self,
+        x: f64,
+    ) -> f64 {
+        (-self.exponent * x.ln()).exp()
+    }
+
+    fn h_integral(
+        &self,
+        x: f64,
+    ) -> f64 {
+        let log_x = x.ln();
+        zipf_helper2((1.0 - self.exponent) * log_x) * log_x
+    }
+
+    fn h_integral_inverse(
+        &self,
+        x: f64,
+    ) -> f64 {
+        let t = (x * (1.0 - self.exponent)).max(-1.0);
+        (zipf_helper1(t) * x).exp()
+    }
+}
+
+/// `ln(1 + x) / x`, stable around zero.
+fn zipf_helper1(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.ln_1p() / x
+    } else {
+        1.0 - x * (0.5 - x * (1.0 / 3.0 - 0.25 * x))
+    }
+}
+
+/// `(exp(x) - 1) / x`, stable around zero.
+fn zipf_helper2(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.exp_m1() / x
+    } else {
+        1.0 + x * 0.5 * (1.0 + x / 3.0 * (1.0 + 0.25 * x))
+    }
+}
+
+
+/// KNN read added by `generate-queries --vector-dimensions`.
+pub const VECTOR_KNN_QUERY_NAME: &str = "vector_knn";
+pub const DEFAULT_VECTOR_K: u16 = 10;
+pub const DEFAULT_VECTOR_SHARE: f64 = 0.5;
+
+/// KNN workload over a vector index on `:User(embedding_<dimensions>)`; stored in the queries
+/// file metadata so `run` can seed the embeddings and create the index first.
+///
+/// Each property is named after its dimension, so workloads of different sizes (and the
+/// 3-dimensional `embedding` fixture of the fixture-dependent profile) can share a dataset.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct VectorWorkload {
+    pub dimensions: u16,
+    /// Neighbours returned per query.
+    pub k: u16,
+    /// Share of the read queries that are KNN reads.
+    pub share: f64,
+}
+
+impl VectorWorkload {
+    pub fn property(&self) -> String {
+        format!("embedding_{}", self.dimensions)
+    }
+
+    /// Neo4j names its indexes; FalkorDB addresses them by label and property.
+    pub fn index_name(&self) -> String {
+        format!("bench_user_embedding_{}_idx", self.dimensions)
+    }
+
+    /// Cypher list expression of node `var`'s embedding, derived from its id so every vendor
+    /// stores the same vectors. Components are spread over [-1, 1] like the query vectors.
+    pub fn embedding_expression(
+        &self,
+        var: &str,
+    ) -> String {
+        format!(
+            "[i IN range(0, {}) | toFloat((({var}.id + 1) * 2654435761 + (i + 1) * 40503 * ({var}.id % 101 + 1)) % 1000003) / 500001.5 - 1.0]",
+            self.dimensions - 1
+        )
+    }
+
+    /// A fixed query vector for readiness probes.
+    pub fn probe_embedding(&self) -> String {
+        format!("[{}]", vec!["1.0"; self.dimensions as usize].join(", "))
+    }
+}
+
+/// Whole-graph reads added by `generate-queries --query-mix analytics|mixed`.
+const ANALYTICS_QUERY_NAMES: [&str; 4] = [
+    "analytics_count_by_age_bucket",
+    "analytics_degree_distribution",
+    "analytics_top_connected_users",
+    "analytics_neighbourhood_triangles",
+];
+
+/// The kind of workload a query belongs to. Results are reported per group as well as per query.
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueryGroup {
+    /// Point reads, short traversals and writes.
+    #[default]
+    Oltp,
+    /// Aggregations over the whole graph or a neighbourhood of it.
+    Analytics,
+    /// The `--enable-algo-*` graph algorithms.
+    Algorithm,
+}
+
+impl QueryGroup {
+    /// The group of query `name`; derived from the name so results of older runs can be grouped.
+    pub fn of(name: &str) -> Self {
+        if is_algorithm_query_name(name) {
+            QueryGroup::Algorithm
+        } else if ANALYTICS_QUERY_NAMES.contains(&name) {
+            QueryGroup::Analytics
+        } else {
+            QueryGroup::Oltp
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QueryGroup::Oltp => "oltp",
+            QueryGroup::Analytics => "analytics",
+            QueryGroup::Algorithm => "algorithm",
+        }
+    }
+}
+
+/// Which non-algorithm reads a generated workload draws from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ValueEnum, Default)]
+#[serde(rename_all = "kebab-case")]
+#[value(rename_all = "kebab-case")]
+pub enum QueryMix {
+    /// Only the OLTP reads (the historical behaviour).
+    #[default]
+    Oltp,
+    /// Only the analytics reads.
+    Analytics,
+    /// Both, every read equally likely.
+    Mixed,
+}
 
 fn is_algorithm_query_name(name: &str) -> bool {
     ALGORITHM_QUERY_NAMES.contains(&name)
@@ -122,15 +465,57 @@ impl QueryGenerator {
     }
 }
 
+/// Generates the statements of a multi-statement transaction, executed together in one explicit
+/// transaction per vendor.
+pub struct TransactionGenerator {
+    query_type: QueryType,
+    generator: TransactionFn,
+}
+
+impl TransactionGenerator {
+    pub fn new<F>(
+        query_type: QueryType,
+        generator: F,
+    ) -> Self
+    where
+        F: Fn(&mut dyn Rng) -> Vec<Query> + Send + Sync + 'static,
+    {
+        TransactionGenerator {
+            query_type,
+            generator: Box::new(generator),
+        }
+    }
+
+    pub fn generate(&self) -> Vec<Query> {
+        let mut rng = rand::rng();
+        (self.generator)(&mut rng)
+    }
+
+    pub fn generate_with_rng(
+        &self,
+        rng: &mut dyn Rng,
+    ) -> Vec<Query> {
+        (self.generator)(rng)
+    }
+}
+
 // Define a type alias for the function type
 type QueryFn = Box<dyn Fn(&mut dyn Rng) -> Query + Send + Sync>;
+type TransactionFn = Box<dyn Fn(&mut dyn Rng) -> Vec<Query> + Send + Sync>;
+
+enum EntryGenerator {
+    Query(QueryFn),
+    Transaction(TransactionFn),
+}
 
 // Define a type alias for the tuple
-type QueryEntry = (String, QueryType, QueryFn);
+type QueryEntry = (String, QueryType, EntryGenerator);
 
 pub struct QueriesRepositoryBuilder<U: Send> {
     vertices: i32,
     edges: i32,
+    access_pattern: AccessPattern,
+    graph: Option<Arc<DatasetGraph>>,
     queries: Vec<QueryEntry>,
     flavour: U,
 }
@@ -143,10 +528,26 @@ impl QueriesRepositoryBuilder<Empty> {
         QueriesRepositoryBuilder {
             vertices,
             edges,
+            access_pattern: AccessPattern::default(),
+            graph: None,
             queries: Vec::new(),
             flavour: Empty,
         }
     }
+    pub fn access_pattern(
+        mut self,
+        access_pattern: AccessPattern,
+    ) -> QueriesRepositoryBuilder<Empty> {
+        self.access_pattern = access_pattern;
+        self
+    }
+    pub fn dataset_graph(
+        mut self,
+        graph: Option<Arc<DatasetGraph>>,
+    ) -> QueriesRepositoryBuilder<Empty> {
+        self.graph = graph;
+        self
+    }
     pub fn flavour(
         self,
         flavour: Flavour,
@@ -154,6 +555,8 @@ impl QueriesRepositoryBuilder<Empty> {
         QueriesRepositoryBuilder {
             vertices: self.vertices,
             edges: self.edges,
+            access_pattern: self.access_pattern,
+            graph: self.graph,
             queries: self.queries,
             flavour,
         }
@@ -172,17 +575,52 @@ impl QueriesRepositoryBuilder<Flavour> {
         let vertices = self.vertices;
         let edges = self.edges;
         let flavour = self.flavour;
+        let sampler = VertexSampler::new(self.access_pattern, vertices);
+        let graph = self.graph.clone();
         self.queries.push((
             name.into(),
             query_type,
-            Box::new(move |rng: &mut dyn Rng| {
+            EntryGenerator::Query(Box::new(move |rng: &mut dyn Rng| {
                 let mut random = RandomUtil {
                     rng,
                     vertices,
                     _edges: edges,
+                    sampler,
+                    graph: graph.as_deref(),
                 };
                 generator(&mut random, flavour)
-            }),
+            })),
+        ));
+        self
+    }
+
+    fn add_transaction<F>(
+        mut self,
+        name: impl Into<String>,
+        query_type: QueryType,
+        generator: F,
+    ) -> Self
+    where
+        F: Fn(&mut RandomUtil<'_>, Flavour) -> Vec<Query> + Send + Sync + 'static,
+    {
+        let vertices = self.vertices;
+        let edges = self.edges;
+        let flavour = self.flavour;
+        let sampler = VertexSampler::new(self.access_pattern, vertices);
+        let graph = self.graph.clone();
+        self.queries.push((
+            name.into(),
+            query_type,
+            EntryGenerator::Transaction(Box::new(move |rng: &mut dyn Rng| {
+                let mut random = RandomUtil {
+                    rng,
+                    vertices,
+                    _edges: edges,
+                    sampler,
+                    graph: graph.as_deref(),
+                };
+                generator(&mut random, flavour)
+            })),
         ));
         self
     }
@@ -193,7 +631,14 @@ impl QueriesRepositoryBuilder<Flavour> {
         for (idx, (name, query_type, generator)) in self.queries.into_iter().enumerate() {
             // Stable query ids are assigned in definition order.
             let id = idx as u16;
-            queries_repository.add_with_id(id, name, query_type, generator);
+            match generator {
+                EntryGenerator::Query(generator) => {
+                    queries_repository.add_with_id(id, name, query_type, generator)
+                }
+                EntryGenerator::Transaction(generator) => {
+                    queries_repository.add_transaction_with_id(id, name, query_type, generator)
+                }
+            }
         }
         queries_repository
     }
@@ -204,15 +649,132 @@ pub struct QueryCatalogEntry {
     pub id: u16,
     pub name: String,
     pub q_type: QueryType,
+    #[serde(default)]
+    pub group: QueryGroup,
+    /// Per-query timeout replacing `run`'s default (see [`crate::query_timeout`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Absent in files generated before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shape: Option<QueryShape>,
+}
+
+/// Type of a query parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    List,
+    DateTime,
+}
+
+impl From<&QueryParam> for ParamType {
+    fn from(param: &QueryParam) -> Self {
+        match param {
+            QueryParam::String(_) => ParamType::String,
+            QueryParam::Integer(_) => ParamType::Integer,
+            QueryParam::Float(_) => ParamType::Float,
+            QueryParam::Boolean(_) => ParamType::Boolean,
+            QueryParam::List(_) => ParamType::List,
+            QueryParam::DateTime(_) => ParamType::DateTime,
+        }
+    }
+}
+
+/// How many rows a query returns, inferred from its template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Cardinality {
+    /// No `RETURN`.
+    None,
+    /// A `LIMIT`ed result.
+    Bounded,
+    /// Aggregated rows (`count`, `sum`, `avg`, `min`, `max`, `collect`).
+    Aggregate,
+    /// One node looked up by id, without a traversal.
+    Single,
+    /// As many rows as the pattern matches.
+    Many,
+}
+
+impl Cardinality {
+    pub fn infer(template: &str) -> Self {
+        let upper = template.to_uppercase();
+        let Some(at) = upper.rfind("RETURN") else {
+            return Cardinality::None;
+        };
+        let returned = &upper[at..];
+        let compact: String = upper.chars().filter(|c| !c.is_whitespace()).collect();
+        if returned.contains("LIMIT") {
+            Cardinality::Bounded
+        } else if ["COUNT(", "SUM(", "AVG(", "MIN(", "MAX(", "COLLECT("]
+            .iter()
+            .any(|aggregate| returned.contains(aggregate))
+        {
+            Cardinality::Aggregate
+        } else if compact.contains("{ID:$")
+            && !["-[", "-->", "<--", ")-("]
+                .iter()
+                .any(|edge| compact.contains(edge))
+        {
+            Cardinality::Single
+        } else {
+            Cardinality::Many
+        }
+    }
+}
+
+/// What a catalog query looks like, from one instance rendered with a fixed seed, so latency
+/// stats can be joined to query semantics without reading the generators.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryShape {
+    /// The Cypher text with `$parameters`; a transaction's statements one per line.
+    pub template: String,
+    /// The instance with its parameters inlined.
+    pub example: String,
+    pub params: BTreeMap<String, ParamType>,
+    pub cardinality: Cardinality,
+}
+
+impl QueryShape {
+    /// The shape of a query or transaction rendered as `statements`.
+    pub fn of(statements: &[Query]) -> Self {
+        let template = statements
+            .iter()
+            .map(|statement| statement.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        QueryShape {
+            example: statements
+                .iter()
+                .map(Query::to_cypher)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            params: statements
+                .iter()
+                .flat_map(|statement| statement.params.iter())
+                .map(|(name, param)| (name.clone(), ParamType::from(param)))
+                .collect(),
+            cardinality: Cardinality::infer(&template),
+            template,
+        }
+    }
 }
 
 pub struct QueriesRepository {
     read_queries: HashMap<String, QueryGenerator>,
     write_queries: HashMap<String, QueryGenerator>,
+    transactions: HashMap<String, TransactionGenerator>,
     read_query_names: Vec<String>,
     write_query_names: Vec<String>,
     algorithm_read_query_names: Vec<String>,
     non_algorithm_read_query_names: Vec<String>,
+    // KNN reads, picked at the workload's own share rather than from the general read pool.
+    vector_read_query_names: Vec<String>,
+    analytics_read_query_names: Vec<String>,
     name_to_id: HashMap<String, u16>,
     catalog: Vec<QueryCatalogEntry>,
 }
@@ -222,10 +784,13 @@ impl QueriesRepository {
         QueriesRepository {
             read_queries: HashMap::new(),
             write_queries: HashMap::new(),
+            transactions: HashMap::new(),
             read_query_names: Vec::new(),
             write_query_names: Vec::new(),
             algorithm_read_query_names: Vec::new(),
             non_algorithm_read_query_names: Vec::new(),
+            vector_read_query_names: Vec::new(),
+            analytics_read_query_names: Vec::new(),
             name_to_id: HashMap::new(),
             catalog: Vec::new(),
         }
@@ -242,10 +807,14 @@ impl QueriesRepository {
     {
         let name = name.into();
         self.name_to_id.insert(name.clone(), id);
+        let example = generator(&mut StdRng::seed_from_u64(id as u64));
         self.catalog.push(QueryCatalogEntry {
             id,
             name: name.clone(),
             q_type: query_type,
+            group: QueryGroup::of(&name),
+            timeout_ms: None,
+            shape: Some(QueryShape::of(&[example])),
         });
 
         match query_type {
@@ -253,6 +822,10 @@ impl QueriesRepository {
                 self.read_query_names.push(name.clone());
                 if is_algorithm_query_name(name.as_str()) {
                     self.algorithm_read_query_names.push(name.clone());
+                } else if name == VECTOR_KNN_QUERY_NAME {
+                    self.vector_read_query_names.push(name.clone());
+                } else if QueryGroup::of(&name) == QueryGroup::Analytics {
+                    self.analytics_read_query_names.push(name.clone());
                 } else {
                     self.non_algorithm_read_query_names.push(name.clone());
                 }
@@ -260,17 +833,63 @@ impl QueriesRepository {
                     .insert(name, QueryGenerator::new(query_type, generator));
             }
             QueryType::Write => {
-                self.write_query_names.push(name.clone());
+                // Compensating writes are emitted by the generator, never drawn.
+                if !is_compensation_query_name(&name) {
+                    self.write_query_names.push(name.clone());
+                }
                 self.write_queries
                     .insert(name, QueryGenerator::new(query_type, generator));
             }
         }
     }
 
+    /// Register a transaction under `id`. It joins the read or write pool of `query_type` so the
+    /// configured write ratio covers it like any other query.
+    fn add_transaction_with_id<F>(
+        &mut self,
+        id: u16,
+        name: impl Into<String>,
+        query_type: QueryType,
+        generator: F,
+    ) where
+        F: Fn(&mut dyn Rng) -> Vec<Query> + Send + Sync + 'static,
+    {
+        let name = name.into();
+        self.name_to_id.insert(name.clone(), id);
+        let example = generator(&mut StdRng::seed_from_u64(id as u64));
+        self.catalog.push(QueryCatalogEntry {
+            id,
+            name: name.clone(),
+            q_type: query_type,
+            group: QueryGroup::of(&name),
+            timeout_ms: None,
+            shape: Some(QueryShape::of(&example)),
+        });
+
+        match query_type {
+            QueryType::Read => {
+                self.read_query_names.push(name.clone());
+                self.non_algorithm_read_query_names.push(name.clone());
+            }
+            QueryType::Write => {
+                self.write_query_names.push(name.clone());
+            }
+        }
+        self.transactions
+            .insert(name, TransactionGenerator::new(query_type, generator));
+    }
+
     pub fn catalog(&self) -> Vec<QueryCatalogEntry> {
         self.catalog.clone()
     }
 
+    fn id_of(
+        &self,
+        name: &str,
+    ) -> Option<u16> {
+        self.name_to_id.get(name).copied()
+    }
+
     /// The non-algorithm read shape names, in definition order — the baseline read shapes the
     /// synthetic check records (design §3.4). Excludes algorithm reads (opt-in, capability-gated)
     /// and writes.
@@ -297,40 +916,107 @@ impl QueriesRepository {
         ))
     }
 
-    fn random_query_from_pool(
+    /// Render the query or transaction `name` from a caller-supplied RNG, without preparing it.
+    fn render_flavour_with_rng(
+        &self,
+        name: &str,
+        rng: &mut dyn Rng,
+    ) -> Option<(QueryType, FlavourQuery)> {
+        if let Some(transaction) = self.transactions.get(name) {
+            let statements = transaction.generate_with_rng(rng);
+            return Some((
+                transaction.query_type,
+                FlavourQuery::transaction(statements),
+            ));
+        }
+        let generator = self
+            .read_queries
+            .get(name)
+            .or_else(|| self.write_queries.get(name))?;
+        Some((
+            generator.query_type,
+            FlavourQuery::single(generator.generate_with_rng(rng)),
+        ))
+    }
+
+    /// Pick an entry from `query_names` with `rng` and render it from a fresh per-query seed. The
+    /// seed is returned so the same logical query can be rendered again for other flavours.
+    fn random_seeded_query_from_pool(
         &self,
-        queries: &HashMap<String, QueryGenerator>,
         query_names: &[String],
-    ) -> Option<PreparedQuery> {
-        let mut rng = rand::rng();
-        let key = query_names.choose(&mut rng)?;
-        let generator = queries.get(key)?;
+        rng: &mut dyn Rng,
+    ) -> Option<(PreparedQuery, u64)> {
+        let key = query_names.choose(rng)?;
+        let seed = rng.random::<u64>();
         let q_id = *self.name_to_id.get(key).unwrap_or(&0);
-        Some(PreparedQuery::new(
-            q_id,
-            key.clone(),
-            generator.query_type,
-            generator.generate(),
+        let (query_type, rendered) =
+            self.render_flavour_with_rng(key, &mut StdRng::seed_from_u64(seed))?;
+        Some((
+            PreparedQuery::from_flavour(q_id, key.clone(), query_type, rendered),
+            seed,
         ))
     }
 
+    fn random_seeded_query(
+        &self,
+        query_type: QueryType,
+        rng: &mut dyn Rng,
+    ) -> Option<(PreparedQuery, u64)> {
+        let query_names = match query_type {
+            QueryType::Read => &self.read_query_names,
+            QueryType::Write => &self.write_query_names,
+        };
+        self.random_seeded_query_from_pool(query_names, rng)
+    }
+
     pub fn random_query(
         &self,
         query_type: QueryType,
     ) -> Option<PreparedQuery> {
-        let (queries, query_names) = match query_type {
-            QueryType::Read => (&self.read_queries, &self.read_query_names),
-            QueryType::Write => (&self.write_queries, &self.write_query_names),
-        };
-        self.random_query_from_pool(queries, query_names)
+        self.random_seeded_query(query_type, &mut rand::rng())
+            .map(|(query, _)| query)
     }
 
-    fn random_algorithm_read_query(&self) -> Option<PreparedQuery> {
-        self.random_query_from_pool(&self.read_queries, &self.algorithm_read_query_names)
+    fn random_algorithm_read_query(
+        &self,
+        rng: &mut dyn Rng,
+    ) -> Option<(PreparedQuery, u64)> {
+        self.random_seeded_query_from_pool(&self.algorithm_read_query_names, rng)
+    }
+
+    fn random_non_algorithm_read_query(
+        &self,
+        rng: &mut dyn Rng,
+    ) -> Option<(PreparedQuery, u64)> {
+        self.random_seeded_query_from_pool(&self.non_algorithm_read_query_names, rng)
     }
 
-    fn random_non_algorithm_read_query(&self) -> Option<PreparedQuery> {
-        self.random_query_from_pool(&self.read_queries, &self.non_algorithm_read_query_names)
+    fn random_vector_read_query(
+        &self,
+        rng: &mut dyn Rng,
+    ) -> Option<(PreparedQuery, u64)> {
+        self.random_seeded_query_from_pool(&self.vector_read_query_names, rng)
+    }
+
+    fn random_analytics_read_query(
+        &self,
+        rng: &mut dyn Rng,
+    ) -> Option<(PreparedQuery, u64)> {
+        self.random_seeded_query_from_pool(&self.analytics_read_query_names, rng)
+    }
+
+    /// A read drawn uniformly from the non-algorithm and analytics reads together.
+    fn random_mixed_read_query(
+        &self,
+        rng: &mut dyn Rng,
+    ) -> Option<(PreparedQuery, u64)> {
+        let oltp = self.non_algorithm_read_query_names.len();
+        let total = oltp + self.analytics_read_query_names.len();
+        if total > 0 && rng.random_range(0..total) >= oltp {
+            self.random_analytics_read_query(rng)
+        } else {
+            self.random_non_algorithm_read_query(rng)
+        }
     }
 
     fn algorithm_read_query_count(&self) -> usize {
@@ -342,11 +1028,16 @@ struct RandomUtil<'a> {
     rng: &'a mut dyn Rng,
     vertices: i32,
     _edges: i32,
+    sampler: VertexSampler,
+    graph: Option<&'a DatasetGraph>,
 }
 
+/// Sources [`RandomUtil::connected_path`] tries before settling for an unconnected pair.
+const CONNECTED_SOURCE_ATTEMPTS: usize = 16;
+
 impl RandomUtil<'_> {
     fn random_vertex(&mut self) -> i32 {
-        self.rng.random_range(1..=self.vertices)
+        self.sampler.sample(self.rng, self.vertices)
     }
     #[allow(dead_code)]
     fn random_path(&mut self) -> (i32, i32) {
@@ -359,9 +1050,81 @@ impl RandomUtil<'_> {
         }
         (start, end)
     }
+
+    /// Like [`Self::random_path`], but with the dataset graph known the target is where a walk of
+    /// at most `max_hops` edges from the source ends, so the pair is connected.
+    fn connected_path(
+        &mut self,
+        max_hops: u8,
+    ) -> (i32, i32) {
+        let Some(graph) = self.graph else {
+            return self.random_path();
+        };
+        for _ in 0..CONNECTED_SOURCE_ATTEMPTS {
+            let from = self.random_vertex();
+            if let Some(to) = graph.walk(self.rng, from, max_hops) {
+                return (from, to);
+            }
+        }
+        self.random_path()
+    }
+
+    /// A vertex and an age threshold at most the age of one of its friends, or `default` for
+    /// the vertices that have none with an age.
+    fn vertex_with_friend_age(
+        &mut self,
+        default: i32,
+    ) -> (i32, i32) {
+        let mut id = self.random_vertex();
+        if let Some(graph) = self.graph {
+            for _ in 0..CONNECTED_SOURCE_ATTEMPTS {
+                if let Some(age) = graph.friend_age(self.rng, id) {
+                    return (id, age);
+                }
+                id = self.random_vertex();
+            }
+        }
+        (id, default)
+    }
+
+    /// Epoch milliseconds uniform over 2020 through 2025.
+    fn random_instant(&mut self) -> i64 {
+        self.rng.random_range(1_577_836_800_000..1_767_225_600_000)
+    }
+
+    /// A Cypher list literal of `dimensions` components uniform in [-1, 1].
+    fn random_embedding(
+        &mut self,
+        dimensions: u16,
+    ) -> String {
+        let components: Vec<String> = (0..dimensions)
+            .map(|_| format!("{:.4}", self.rng.random_range(-1.0..=1.0f64)))
+            .collect();
+        format!("[{}]", components.join(", "))
+    }
+}
+/// Inputs the users query set is built from, kept so the same set can be rebuilt per flavour.
+#[derive(Debug, Clone)]
+struct UsersQueriesSettings {
+    vertices: i32,
+    edges: i32,
+    algorithm_selection: AlgorithmQuerySelection,
+    query_coverage_profile: QueryCoverageProfile,
+    access_pattern: AccessPattern,
+    include_transactions: bool,
+    vector: Option<VectorWorkload>,
+    query_mix: QueryMix,
+    bounded_writes: Option<u32>,
+    typed_params: bool,
+    graph: Option<Arc<DatasetGraph>>,
 }
+
 pub struct UsersQueriesRepository {
     queries_repository: QueriesRepository,
+    flavour: Flavour,
+    settings: UsersQueriesSettings,
+    /// The same query set built for the other flavours; see [`Self::with_all_flavours`].
+    flavour_variants: Vec<(Flavour, QueriesRepository)>,
 }
 
 impl UsersQueriesRepository {
@@ -392,16 +1155,135 @@ impl UsersQueriesRepository {
     ) -> Box<dyn Iterator<Item = PreparedQuery> + Send + Sync> {
         Box::new((0..count).filter_map(move |_| self.random_query(write_ratio)))
     }
+    /// Add the `vector_knn` read of `vector`. Call before [`Self::with_all_flavours`]. Memgraph
+    /// gets no rendering.
+    pub fn with_vector_workload(
+        mut self,
+        vector: Option<VectorWorkload>,
+    ) -> Self {
+        self.settings.vector = vector;
+        self.queries_repository = Self::build(&self.settings, self.flavour);
+        self
+    }
+
+    /// Draw the non-algorithm reads from `query_mix`, adding the analytics reads unless it is
+    /// [`QueryMix::Oltp`]. Call before [`Self::with_all_flavours`].
+    pub fn with_query_mix(
+        mut self,
+        query_mix: QueryMix,
+    ) -> Self {
+        self.settings.query_mix = query_mix;
+        self.queries_repository = Self::build(&self.settings, self.flavour);
+        self
+    }
+
+    /// Add the compensating writes of `generate-queries --bounded-writes`, emitted after every
+    /// `every` user mutations (see [`crate::bounded_writes`]). Call before
+    /// [`Self::with_all_flavours`].
+    pub fn with_bounded_writes(
+        mut self,
+        every: Option<u32>,
+    ) -> Self {
+        self.settings.bounded_writes = every;
+        self.queries_repository = Self::build(&self.settings, self.flavour);
+        self
+    }
+
+    /// Add the reads of `generate-queries --enable-typed-params`, looking users up by string,
+    /// float, list and datetime parameters. Call before [`Self::with_all_flavours`]. Memgraph gets
+    /// no rendering of the datetime read.
+    pub fn with_typed_params(
+        mut self,
+        typed_params: bool,
+    ) -> Self {
+        self.settings.typed_params = typed_params;
+        self.queries_repository = Self::build(&self.settings, self.flavour);
+        self
+    }
+
+    /// Draw correlated parameters from `graph` (see [`crate::dataset_graph`]): path queries get
+    /// connected pairs, and the `aggregate_expansion_1_with_age_threshold` read is added. Call
+    /// before [`Self::with_all_flavours`].
+    pub fn with_dataset_graph(
+        mut self,
+        graph: Option<Arc<DatasetGraph>>,
+    ) -> Self {
+        self.settings.graph = graph;
+        self.queries_repository = Self::build(&self.settings, self.flavour);
+        self
+    }
+
+    /// A tracker for one chunk of generated queries, unless writes aren't bounded.
+    pub fn mutation_tracker(&self) -> Option<MutationTracker> {
+        self.settings
+            .bounded_writes
+            .map(|every| MutationTracker::new(every, self.settings.vertices))
+    }
+
+    /// The compensating write `name` as `query`, rendered the same for every flavour.
+    pub fn prepare_compensation(
+        &self,
+        name: &str,
+        query: Query,
+    ) -> Option<PreparedQuery> {
+        let q_id = self.queries_repository.id_of(name)?;
+        let mut prepared = PreparedQuery::new(q_id, name.to_string(), QueryType::Write, query);
+        for (flavour, _) in &self.flavour_variants {
+            prepared
+                .flavours
+                .insert(*flavour, FlavourQuery::single(prepared.query.clone()));
+        }
+        Some(prepared)
+    }
+
+    /// Also render every generated query for the other flavours, with the same random
+    /// parameters, into [`PreparedQuery::flavours`].
+    pub fn with_all_flavours(mut self) -> Self {
+        self.flavour_variants = Flavour::ALL
+            .into_iter()
+            .filter(|flavour| *flavour != self.flavour)
+            .map(|flavour| (flavour, Self::build(&self.settings, flavour)))
+            .collect();
+        self
+    }
+
     pub fn random_query(
         &self,
         write_ratio: f32,
     ) -> Option<PreparedQuery> {
+        self.random_query_with_rng(write_ratio, &mut rand::rng())
+    }
+
+    /// [`Self::random_query`] drawing every choice from `rng`, so a seeded RNG yields the same
+    /// queries; the parallel generator gives each chunk its own.
+    pub fn random_query_with_rng(
+        &self,
+        write_ratio: f32,
+        rng: &mut dyn Rng,
+    ) -> Option<PreparedQuery> {
+        let (mut query, seed) = self.random_seeded_query(write_ratio, rng)?;
+        for (flavour, repository) in &self.flavour_variants {
+            // Queries a flavour does not support are left without a rendering for it.
+            if let Some((_, rendered)) =
+                repository.render_flavour_with_rng(&query.q_name, &mut StdRng::seed_from_u64(seed))
+            {
+                query.flavours.insert(*flavour, rendered);
+            }
+        }
+        Some(query)
+    }
+
+    fn random_seeded_query(
+        &self,
+        write_ratio: f32,
+        rng: &mut dyn Rng,
+    ) -> Option<(PreparedQuery, u64)> {
         let algorithm_share = (self.queries_repository.algorithm_read_query_count() as f32
             * ALGORITHM_QUERY_TARGET_RATIO_PER_QUERY)
             .clamp(0.0, 1.0);
 
-        if random::<f32>() < algorithm_share {
-            if let Some(query) = self.queries_repository.random_algorithm_read_query() {
+        if rng.random::<f32>() < algorithm_share {
+            if let Some(query) = self.queries_repository.random_algorithm_read_query(rng) {
                 return Some(query);
             }
         }
@@ -415,18 +1297,38 @@ impl UsersQueriesRepository {
             0.0
         };
 
-        if random::<f32>() < write_probability_within_remaining {
+        if rng.random::<f32>() < write_probability_within_remaining {
             return self
                 .queries_repository
-                .random_query(QueryType::Write)
-                .or_else(|| self.queries_repository.random_non_algorithm_read_query())
-                .or_else(|| self.queries_repository.random_query(QueryType::Read));
+                .random_seeded_query(QueryType::Write, rng)
+                .or_else(|| self.queries_repository.random_non_algorithm_read_query(rng))
+                .or_else(|| {
+                    self.queries_repository
+                        .random_seeded_query(QueryType::Read, rng)
+                });
+        }
+
+        if let Some(vector) = self.settings.vector {
+            if rng.random::<f64>() < vector.share {
+                if let Some(query) = self.queries_repository.random_vector_read_query(rng) {
+                    return Some(query);
+                }
+            }
         }
 
-        self.queries_repository
-            .random_non_algorithm_read_query()
-            .or_else(|| self.queries_repository.random_query(QueryType::Read))
-            .or_else(|| self.queries_repository.random_query(QueryType::Write))
+        let read = match self.settings.query_mix {
+            QueryMix::Oltp => self.queries_repository.random_non_algorithm_read_query(rng),
+            QueryMix::Analytics => self.queries_repository.random_analytics_read_query(rng),
+            QueryMix::Mixed => self.queries_repository.random_mixed_read_query(rng),
+        };
+        read.or_else(|| {
+            self.queries_repository
+                .random_seeded_query(QueryType::Read, rng)
+        })
+        .or_else(|| {
+            self.queries_repository
+                .random_seeded_query(QueryType::Write, rng)
+        })
     }
     pub fn new(
         vertices: i32,
@@ -434,8 +1336,50 @@ impl UsersQueriesRepository {
         flavour: Flavour,
         algorithm_selection: AlgorithmQuerySelection,
         query_coverage_profile: QueryCoverageProfile,
+        access_pattern: AccessPattern,
+        include_transactions: bool,
     ) -> UsersQueriesRepository {
+        let settings = UsersQueriesSettings {
+            vertices,
+            edges,
+            algorithm_selection,
+            query_coverage_profile,
+            access_pattern,
+            include_transactions,
+            vector: None,
+            query_mix: QueryMix::default(),
+            bounded_writes: None,
+            typed_params: false,
+            graph: None,
+        };
+        UsersQueriesRepository {
+            queries_repository: Self::build(&settings, flavour),
+            flavour,
+            settings,
+            flavour_variants: Vec::new(),
+        }
+    }
+
+    fn build(
+        settings: &UsersQueriesSettings,
+        flavour: Flavour,
+    ) -> QueriesRepository {
+        let UsersQueriesSettings {
+            vertices,
+            edges,
+            algorithm_selection,
+            query_coverage_profile,
+            access_pattern,
+            include_transactions,
+            vector,
+            query_mix,
+            bounded_writes,
+            typed_params,
+            ref graph,
+        } = *settings;
         let mut queries_builder = QueriesRepositoryBuilder::new(vertices, edges)
+            .access_pattern(access_pattern)
+            .dataset_graph(graph.clone())
             .flavour(flavour)
             .add_query("single_vertex_read", QueryType::Read, |random, _flavour| {
                 QueryBuilder::new()
@@ -609,7 +1553,7 @@ impl UsersQueriesRepository {
             )
             // Shortest-path style queries
             .add_query("shortest_path", QueryType::Read, |random, flavour| {
-                let (from, to) = random.random_path();
+                let (from, to) = random.connected_path(6);
                 let text = match flavour {
                     Flavour::FalkorDB => "MATCH (s:User {id: $from}), (t:User {id: $to}) WITH shortestPath((s)-[*]->(t)) AS p RETURN length(p)",
                     Flavour::Neo4j => "MATCH (s:User {id: $from}), (t:User {id: $to}) MATCH p = shortestPath((s)-[*]->(t)) RETURN length(p)",
@@ -622,7 +1566,7 @@ impl UsersQueriesRepository {
                     .build()
             })
             .add_query("shortest_path_with_filter", QueryType::Read, |random, flavour| {
-                let (from, to) = random.random_path();
+                let (from, to) = random.connected_path(6);
                 let text = match flavour {
                     Flavour::FalkorDB => "MATCH (s:User {id: $from}), (t:User {id: $to}) WITH shortestPath((s)-[*]->(t)) AS p WHERE length(p) > 0 RETURN length(p)",
                     Flavour::Neo4j => "MATCH (s:User {id: $from}), (t:User {id: $to}) MATCH p = shortestPath((s)-[*]->(t)) WHERE length(p) > 0 RETURN length(p)",
@@ -662,9 +1606,15 @@ impl UsersQueriesRepository {
             .add_query(
                 "vertex_on_label_property_index",
                 QueryType::Read,
-                |random, _flavour| {
+                |random, flavour| {
+                    // Force the id index where the planner accepts a hint; FalkorDB has no hints.
+                    let text = match flavour {
+                        Flavour::FalkorDB => "MATCH (n:User {id: $id}) RETURN n",
+                        Flavour::Neo4j => "MATCH (n:User {id: $id}) USING INDEX n:User(id) RETURN n",
+                        Flavour::Memgraph => "USING INDEX :User(id) MATCH (n:User {id: $id}) RETURN n",
+                    };
                     QueryBuilder::new()
-                        .text("MATCH (n:User {id: $id}) RETURN n")
+                        .text(text)
                         .param("id", random.random_vertex())
                         .build()
                 },
@@ -773,7 +1723,7 @@ impl UsersQueriesRepository {
                 "algo_max_flow_single_pair",
                 QueryType::Read,
                 |random, flavour| {
-                    let (source_id, target_id) = random.random_path();
+                    let (source_id, target_id) = random.connected_path(4);
                     let text = match flavour {
                         Flavour::FalkorDB => {
                             "MATCH (s:User {id: $source_id}), (t:User {id: $target_id}) \
@@ -953,7 +1903,7 @@ impl UsersQueriesRepository {
                     .build()
             })
             .add_query("all_shortest_paths_len", QueryType::Read, |random, flavour| {
-                let (from, to) = random.random_path();
+                let (from, to) = random.connected_path(4);
                 let text = match flavour {
                     Flavour::Memgraph => {
                         "MATCH p = (:User {id: $from})-[*BFS]->(:User {id: $to}) RETURN length(p)"
@@ -1034,18 +1984,46 @@ impl UsersQueriesRepository {
                 let id3 = random.random_vertex();
                 let id4 = random.random_vertex();
                 QueryBuilder::new()
-                    .text("MATCH (u:User) WHERE u.id IN [$id1, $id2, $id3, $id4] RETURN u.id")
-                    .param("id1", id1)
-                    .param("id2", id2)
-                    .param("id3", id3)
-                    .param("id4", id4)
+                    .text("MATCH (u:User) WHERE u.id IN [$id1, $id2, $id3, $id4] RETURN u.id")
+                    .param("id1", id1)
+                    .param("id2", id2)
+                    .param("id3", id3)
+                    .param("id4", id4)
+                    .build()
+            })
+            .add_query("entity_path_introspection", QueryType::Read, |random, _flavour| {
+                QueryBuilder::new()
+                    .text("MATCH p=(a:User {id: $id})-[r:Friend]->(b:User) RETURN labels(a), type(r), properties(a), nodes(p), relationships(p), length(p) LIMIT 1")
+                    .param("id", random.random_vertex())
+                    .build()
+            })
+            // Path finding over :Friend, bounded so every engine explores the same search space
+            .add_query("shortest_path_bounded", QueryType::Read, |random, flavour| {
+                let (from, to) = random.connected_path(6);
+                let text = match flavour {
+                    Flavour::FalkorDB => "MATCH (s:User {id: $from}), (t:User {id: $to}) WITH shortestPath((s)-[:Friend*1..6]->(t)) AS p RETURN coalesce(length(p), -1) AS len",
+                    Flavour::Neo4j => "MATCH (s:User {id: $from}), (t:User {id: $to}) OPTIONAL MATCH p = shortestPath((s)-[:Friend*1..6]->(t)) RETURN coalesce(length(p), -1) AS len",
+                    Flavour::Memgraph => "MATCH (s:User {id: $from}), (t:User {id: $to}) OPTIONAL MATCH p = (s)-[:Friend *BFS ..6]->(t) RETURN coalesce(length(p), -1) AS len",
+                };
+                QueryBuilder::new()
+                    .text(text)
+                    .param("from", from)
+                    .param("to", to)
                     .build()
             })
-            .add_query("entity_path_introspection", QueryType::Read, |random, _flavour| {
+            .add_query("var_len_friends_1_3", QueryType::Read, |random, _flavour| {
                 QueryBuilder::new()
-                    .text("MATCH p=(a:User {id: $id})-[r:Friend]->(b:User) RETURN labels(a), type(r), properties(a), nodes(p), relationships(p), length(p) LIMIT 1")
+                    .text("MATCH (a:User {id: $id})-[:Friend*1..3]->(b:User) RETURN DISTINCT b.id")
                     .param("id", random.random_vertex())
                     .build()
+            })
+            .add_query("all_paths_with_limit", QueryType::Read, |random, _flavour| {
+                let (from, to) = random.connected_path(4);
+                QueryBuilder::new()
+                    .text("MATCH p = (s:User {id: $from})-[:Friend*1..4]->(t:User {id: $to}) RETURN length(p) AS len LIMIT 10")
+                    .param("from", from)
+                    .param("to", to)
+                    .build()
             });
         if query_coverage_profile.includes_extended_core() && !matches!(flavour, Flavour::Memgraph)
         {
@@ -1154,9 +2132,177 @@ impl UsersQueriesRepository {
                 );
         }
 
-        let queries_repository = queries_builder.build();
+        // Multi-statement transactions (opt-in), appended last so existing query ids stay stable.
+        if include_transactions {
+            queries_builder = queries_builder
+                .add_transaction(
+                    "tx_transfer_social_credit",
+                    QueryType::Write,
+                    |random, _flavour| {
+                        let (from, to) = random.random_path();
+                        let amount = random.random_vertex();
+                        vec![
+                            QueryBuilder::new()
+                                .text("MATCH (n:User {id: $id}) SET n.rpc_social_credit = coalesce(n.rpc_social_credit, 0) - $amount RETURN n.rpc_social_credit")
+                                .param("id", from)
+                                .param("amount", amount)
+                                .build(),
+                            QueryBuilder::new()
+                                .text("MATCH (n:User {id: $id}) SET n.rpc_social_credit = coalesce(n.rpc_social_credit, 0) + $amount RETURN n.rpc_social_credit")
+                                .param("id", to)
+                                .param("amount", amount)
+                                .build(),
+                        ]
+                    },
+                )
+                .add_transaction("tx_befriend_and_touch", QueryType::Write, |random, _flavour| {
+                    let (from, to) = random.random_path();
+                    vec![
+                        QueryBuilder::new()
+                            .text("MATCH (n:User {id: $from}), (m:User {id: $to}) MERGE (n)-[e:Friend]->(m) ON CREATE SET e.bench_capacity = 1 + ((n.id * 31 + m.id * 17) % 20) RETURN e")
+                            .param("from", from)
+                            .param("to", to)
+                            .build(),
+                        QueryBuilder::new()
+                            .text("MATCH (n:User) WHERE n.id IN [$from, $to] SET n.touch = $touch RETURN count(n)")
+                            .param("from", from)
+                            .param("to", to)
+                            .param("touch", random.random_vertex())
+                            .build(),
+                    ]
+                });
+        }
+
+        // Appended after the transactions for the same reason. The query vector is part of the
+        // text, since parameters can't carry lists.
+        if let Some(vector) = vector.filter(|_| !matches!(flavour, Flavour::Memgraph)) {
+            let property = vector.property();
+            let index_name = vector.index_name();
+            queries_builder = queries_builder.add_query(
+                VECTOR_KNN_QUERY_NAME,
+                QueryType::Read,
+                move |random, flavour| {
+                    let embedding = random.random_embedding(vector.dimensions);
+                    let text = match flavour {
+                        Flavour::FalkorDB => format!(
+                            "CALL db.idx.vector.queryNodes('User', '{}', {}, vecf32({})) \
+                             YIELD node, score \
+                             RETURN node.id AS id, score",
+                            property, vector.k, embedding
+                        ),
+                        Flavour::Neo4j | Flavour::Memgraph => format!(
+                            "CALL db.index.vector.queryNodes('{}', {}, {}) \
+                             YIELD node, score \
+                             RETURN node.id AS id, score",
+                            index_name, vector.k, embedding
+                        ),
+                    };
+                    QueryBuilder::new().text(text).build()
+                },
+            );
+        }
+
+        // Analytics reads, appended last for the same reason.
+        if query_mix != QueryMix::Oltp {
+            queries_builder = queries_builder
+                .add_query("analytics_count_by_age_bucket", QueryType::Read, |_random, _flavour| {
+                    QueryBuilder::new()
+                        .text("MATCH (u:User) RETURN (u.age / 10) * 10 AS bucket, count(u) AS users ORDER BY bucket")
+                        .build()
+                })
+                .add_query("analytics_degree_distribution", QueryType::Read, |_random, _flavour| {
+                    QueryBuilder::new()
+                        .text("MATCH (u:User) OPTIONAL MATCH (u)-[f:Friend]->() WITH u, count(f) AS degree RETURN degree, count(u) AS users ORDER BY degree")
+                        .build()
+                })
+                .add_query("analytics_top_connected_users", QueryType::Read, |_random, _flavour| {
+                    QueryBuilder::new()
+                        .text("MATCH (u:User)-[:Friend]->() RETURN u.id AS id, count(*) AS degree ORDER BY degree DESC, id LIMIT 10")
+                        .build()
+                })
+                // Triangles through one user, each counted once.
+                .add_query("analytics_neighbourhood_triangles", QueryType::Read, |random, _flavour| {
+                    QueryBuilder::new()
+                        .text("MATCH (s:User {id: $id})-[:Friend]-(a:User)-[:Friend]-(b:User)-[:Friend]-(s) WHERE id(a) < id(b) RETURN count(*) AS triangles")
+                        .param("id", random.random_vertex())
+                        .build()
+                });
+        }
+
+        // Compensating writes, appended last for the same reason.
+        if bounded_writes.is_some() {
+            queries_builder = queries_builder
+                .add_query(DROP_CREATED_USER_QUERY_NAME, QueryType::Write, |random, _flavour| {
+                    QueryBuilder::new()
+                        .text(DROP_CREATED_USER_TEXT)
+                        .param("id", random.random_vertex())
+                        .build()
+                })
+                .add_query(RESTORE_USER_QUERY_NAME, QueryType::Write, |random, _flavour| {
+                    let (id, source) = random.random_path();
+                    QueryBuilder::new()
+                        .text(RESTORE_USER_TEXT)
+                        .param("id", id)
+                        .param("source", source)
+                        .build()
+                });
+        }
+
+        // Lookups by string, float, list and datetime parameters, appended last for the same
+        // reason. FalkorDB compares the datetime as the `timestamp()` milliseconds `created_at`
+        // holds; Memgraph has no `epochMillis`, like it has no spatial distance function above.
+        if typed_params {
+            queries_builder = queries_builder.add_query(
+                "typed_param_filter",
+                QueryType::Read,
+                |random, _flavour| {
+                    let ids: Vec<i32> = (0..4).map(|_| random.random_vertex()).collect();
+                    let gender = if random.rng.random_bool(0.5) { "male" } else { "female" };
+                    QueryBuilder::new()
+                        .text("MATCH (u:User) WHERE u.id IN $ids AND u.gender = $gender AND u.completion_percentage >= $min_completion RETURN u.id")
+                        .param("ids", ids)
+                        .param("gender", gender)
+                        .param("min_completion", random.rng.random_range(0.0..100.0f32))
+                        .build()
+                },
+            );
+            if !matches!(flavour, Flavour::Memgraph) {
+                queries_builder = queries_builder.add_query(
+                    "created_before_datetime",
+                    QueryType::Read,
+                    |random, flavour| {
+                        let ids: Vec<i32> = (0..4).map(|_| random.random_vertex()).collect();
+                        let text = match flavour {
+                            Flavour::FalkorDB => "MATCH (u:User) WHERE u.id IN $ids AND coalesce(u.created_at, 0) <= $as_of RETURN u.id",
+                            _ => "MATCH (u:User) WHERE u.id IN $ids AND coalesce(u.created_at, 0) <= $as_of.epochMillis RETURN u.id",
+                        };
+                        QueryBuilder::new()
+                            .text(text)
+                            .param("ids", ids)
+                            .param("as_of", QueryParam::DateTime(random.random_instant()))
+                            .build()
+                    },
+                );
+            }
+        }
+
+        // Appended last for the same reason, and only with the dataset graph to draw from.
+        if graph.is_some() {
+            queries_builder = queries_builder.add_query(
+                "aggregate_expansion_1_with_age_threshold",
+                QueryType::Read,
+                |random, _flavour| {
+                    let (id, min_age) = random.vertex_with_friend_age(18);
+                    QueryBuilder::new()
+                        .text("MATCH (s:User {id: $id})-->(n:User) WHERE n.age >= $min_age RETURN n.id")
+                        .param("id", id)
+                        .param("min_age", min_age)
+                        .build()
+                },
+            );
+        }
 
-        UsersQueriesRepository { queries_repository }
+        queries_builder.build()
     }
 }
 
@@ -1169,6 +2315,46 @@ pub struct PreparedQuery {
     pub query: Query,
     pub cypher: String,
     pub bolt: Bolt,
+    /// Statements of a multi-statement transaction, in execution order. Empty for single-statement
+    /// queries; when present, `query`/`cypher`/`bolt` mirror the first statement.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statements: Vec<PreparedStatement>,
+    /// Renderings of the same logical query, with the same parameters, for the other flavours.
+    /// Empty unless the queries file was generated with every flavour; the runner swaps in its own
+    /// vendor's rendering with [`PreparedQuery::select_flavour`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub flavours: BTreeMap<Flavour, FlavourQuery>,
+}
+
+/// One flavour's rendering of a query or transaction, before it's prepared.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlavourQuery {
+    pub query: Query,
+    /// All statements of a transaction (the first is mirrored in `query`); empty otherwise.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statements: Vec<Query>,
+}
+
+impl FlavourQuery {
+    fn single(query: Query) -> Self {
+        Self {
+            query,
+            statements: Vec::new(),
+        }
+    }
+
+    fn transaction(statements: Vec<Query>) -> Self {
+        Self {
+            query: statements.first().cloned().unwrap_or_default(),
+            statements,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreparedStatement {
+    pub cypher: String,
+    pub bolt: Bolt,
 }
 
 impl PreparedQuery {
@@ -1187,8 +2373,61 @@ impl PreparedQuery {
             query,
             cypher,
             bolt,
+            statements: Vec::new(),
+            flavours: BTreeMap::new(),
+        }
+    }
+
+    fn from_flavour(
+        q_id: u16,
+        q_name: String,
+        q_type: QueryType,
+        rendered: FlavourQuery,
+    ) -> Self {
+        if rendered.statements.is_empty() {
+            Self::new(q_id, q_name, q_type, rendered.query)
+        } else {
+            Self::new_transaction(q_id, q_name, q_type, rendered.statements)
+        }
+    }
+
+    pub fn new_transaction(
+        q_id: u16,
+        q_name: String,
+        q_type: QueryType,
+        statements: Vec<Query>,
+    ) -> Self {
+        let first = statements.first().cloned().unwrap_or_default();
+        let statements = statements
+            .iter()
+            .map(|query| PreparedStatement {
+                cypher: query.to_cypher(),
+                bolt: query.to_bolt_struct(),
+            })
+            .collect();
+        Self {
+            statements,
+            ..Self::new(q_id, q_name, q_type, first)
         }
     }
+
+    pub fn is_transaction(&self) -> bool {
+        !self.statements.is_empty()
+    }
+
+    /// Switch to the stored rendering for `flavour`, if there is one, and drop the others.
+    /// Returns whether a stored rendering was used.
+    pub fn select_flavour(
+        &mut self,
+        flavour: Flavour,
+    ) -> bool {
+        let Some(rendered) = std::mem::take(&mut self.flavours).remove(&flavour) else {
+            return false;
+        };
+        let q_name = std::mem::take(&mut self.q_name);
+        *self = Self::from_flavour(self.q_id, q_name, self.q_type, rendered);
+        true
+    }
 }
 
 #[cfg(test)]
@@ -1333,6 +2572,169 @@ mod tests {
         }
     }
 
+    fn sample_vertices(
+        access_pattern: AccessPattern,
+        vertices: i32,
+        draws: usize,
+    ) -> Vec<i32> {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let sampler = VertexSampler::new(access_pattern, vertices);
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..draws)
+            .map(|_| sampler.sample(&mut rng, vertices))
+            .collect()
+    }
+
+    #[test]
+    fn skewed_access_patterns_stay_in_range_and_favour_hot_ids() {
+        let vertices = 10_000;
+        let draws = 20_000;
+        let zipfian = AccessPattern {
+            distribution: AccessDistribution::Zipfian,
+            ..AccessPattern::default()
+        };
+        let latest = AccessPattern {
+            distribution: AccessDistribution::Latest,
+            ..AccessPattern::default()
+        };
+
+        let zipf_samples = sample_vertices(zipfian, vertices, draws);
+        assert!(zipf_samples.iter().all(|v| (1..=vertices).contains(v)));
+        // With exponent 0.99 over 10k ids, the first 1% of ids receives roughly half the draws;
+        // a uniform draw would give them ~1%.
+        let low = zipf_samples
+            .iter()
+            .filter(|v| **v <= vertices / 100)
+            .count();
+        assert!(
+            low > draws / 3,
+            "zipfian should favour low ids, got {low}/{draws}"
+        );
+
+        let latest_samples = sample_vertices(latest, vertices, draws);
+        assert!(latest_samples.iter().all(|v| (1..=vertices).contains(v)));
+        let high = latest_samples
+            .iter()
+            .filter(|v| **v > vertices - vertices / 100)
+            .count();
+        assert!(
+            high > draws / 3,
+            "latest should favour high ids, got {high}/{draws}"
+        );
+    }
+
+    #[test]
+    fn hotspot_access_pattern_respects_configured_fractions() {
+        let vertices = 1_000;
+        let draws = 20_000;
+        let hotspot = AccessPattern {
+            distribution: AccessDistribution::Hotspot,
+            hot_set_fraction: 0.1,
+            hot_access_fraction: 0.9,
+            ..AccessPattern::default()
+        };
+
+        let samples = sample_vertices(hotspot, vertices, draws);
+        assert!(samples.iter().all(|v| (1..=vertices).contains(v)));
+        let hot = samples.iter().filter(|v| **v <= 100).count() as f64 / draws as f64;
+        assert!(
+            (hot - 0.9).abs() < 0.02,
+            "expected ~90% hot accesses, got {hot}"
+        );
+
+        // A hot set covering everything degenerates to uniform without panicking.
+        let everything_hot = AccessPattern {
+            hot_set_fraction: 1.0,
+            ..hotspot
+        };
+        assert!(sample_vertices(everything_hot, 10, 100)
+            .iter()
+            .all(|v| (1..=10).contains(v)));
+    }
+
+    #[test]
+    fn access_pattern_metadata_defaults_to_uniform() {
+        let parsed: AccessPattern = serde_json::from_str("{}").unwrap();
+        assert_eq!(parsed, AccessPattern::default());
+        let parsed: AccessPattern =
+            serde_json::from_str(r#"{"distribution":"hotspot","hot_set_fraction":0.05}"#).unwrap();
+        assert_eq!(parsed.distribution, AccessDistribution::Hotspot);
+        assert_eq!(parsed.hot_set_fraction, 0.05);
+        assert_eq!(parsed.hot_access_fraction, DEFAULT_HOT_ACCESS_FRACTION);
+    }
+
+    #[test]
+    fn transactions_are_opt_in_and_appended_after_existing_queries() {
+        let build = |include_transactions| {
+            UsersQueriesRepository::new(
+                100,
+                1000,
+                Flavour::FalkorDB,
+                AlgorithmQuerySelection::default(),
+                QueryCoverageProfile::Baseline,
+                AccessPattern::default(),
+                include_transactions,
+            )
+        };
+        let without = build(false);
+        let with = build(true);
+
+        assert!(without.queries_repository.transactions.is_empty());
+        let without_catalog = without.catalog();
+        let with_catalog = with.catalog();
+        assert_eq!(with_catalog.len(), without_catalog.len() + 2);
+        // Existing ids stay stable; transactions take the next ids.
+        for (a, b) in without_catalog.iter().zip(with_catalog.iter()) {
+            assert_eq!((a.id, &a.name), (b.id, &b.name));
+        }
+
+        let (transaction, _) = with
+            .queries_repository
+            .random_seeded_query_from_pool(
+                &["tx_transfer_social_credit".to_string()],
+                &mut rand::rng(),
+            )
+            .expect("transaction renders");
+        assert!(transaction.is_transaction());
+        assert_eq!(transaction.q_type, QueryType::Write);
+        assert_eq!(transaction.statements.len(), 2);
+        assert_eq!(transaction.cypher, transaction.statements[0].cypher);
+        assert!(transaction
+            .statements
+            .iter()
+            .all(|statement| statement.cypher.starts_with("CYPHER ")));
+    }
+
+    #[test]
+    fn prepared_query_statements_are_omitted_for_single_statements() {
+        let single = PreparedQuery::new(
+            0,
+            "q".to_string(),
+            QueryType::Read,
+            QueryBuilder::new().text("RETURN 1").build(),
+        );
+        let json = serde_json::to_string(&single).unwrap();
+        assert!(!json.contains("statements"));
+        let parsed: PreparedQuery = serde_json::from_str(&json).unwrap();
+        assert!(!parsed.is_transaction());
+
+        let transaction = PreparedQuery::new_transaction(
+            1,
+            "tx".to_string(),
+            QueryType::Write,
+            vec![
+                QueryBuilder::new().text("RETURN 1").build(),
+                QueryBuilder::new().text("RETURN 2").build(),
+            ],
+        );
+        let parsed: PreparedQuery =
+            serde_json::from_str(&serde_json::to_string(&transaction).unwrap()).unwrap();
+        assert!(parsed.is_transaction());
+        assert_eq!(parsed.statements[1].bolt.query, "RETURN 2");
+    }
+
     #[test]
     fn test_algorithm_queries_are_tracked() {
         let repository = UsersQueriesRepository::new(
@@ -1341,6 +2743,8 @@ mod tests {
             Flavour::FalkorDB,
             AlgorithmQuerySelection::default(),
             QueryCoverageProfile::Baseline,
+            AccessPattern::default(),
+            false,
         );
         assert_eq!(
             repository.queries_repository.algorithm_read_query_count(),
@@ -1348,6 +2752,132 @@ mod tests {
         );
     }
 
+    #[test]
+    fn catalog_entries_describe_their_query() {
+        let repository = UsersQueriesRepository::new(
+            100,
+            1000,
+            Flavour::FalkorDB,
+            AlgorithmQuerySelection::default(),
+            QueryCoverageProfile::Baseline,
+            AccessPattern::default(),
+            true,
+        );
+        let catalog = repository.catalog();
+        assert!(catalog.iter().all(|entry| entry.shape.is_some()));
+        let shape = |name: &str| {
+            catalog
+                .iter()
+                .find(|entry| entry.name == name)
+                .and_then(|entry| entry.shape.clone())
+                .unwrap()
+        };
+        let read = shape("single_vertex_read");
+        assert_eq!(read.template, "MATCH (n:User {id : $id}) RETURN n");
+        assert_eq!(read.params.get("id"), Some(&ParamType::Integer));
+        assert!(read.example.starts_with("CYPHER id = "));
+        assert_eq!(read.cardinality, Cardinality::Single);
+        assert_eq!(
+            shape("aggregate_expansion_1").cardinality,
+            Cardinality::Many
+        );
+        assert_eq!(
+            Cardinality::infer("MATCH (n) RETURN count(n)"),
+            Cardinality::Aggregate
+        );
+        assert_eq!(Cardinality::infer("CREATE (n:User)"), Cardinality::None);
+    }
+
+    #[test]
+    fn a_dataset_graph_correlates_path_ids_and_age_thresholds() {
+        // A chain 1 -> 2 -> ... -> 50, each user as old as its id.
+        let nodes: Vec<String> = (1..=50)
+            .map(|id| format!("CREATE (:User {{id: {}, age: {}}});", id, id))
+            .collect();
+        let edges: Vec<String> = (1..50)
+            .map(|id| {
+                format!(
+                    "MATCH (n:User {{id: {}}}), (m:User {{id: {}}}) CREATE (n)-[e: Friend]->(m);",
+                    id,
+                    id + 1
+                )
+            })
+            .collect();
+        let graph = DatasetGraph::from_lines(nodes.iter().chain(&edges).map(String::as_str));
+        let repository = UsersQueriesRepository::new(
+            50,
+            49,
+            Flavour::FalkorDB,
+            AlgorithmQuerySelection::default(),
+            QueryCoverageProfile::Baseline,
+            AccessPattern::default(),
+            false,
+        )
+        .with_dataset_graph(Some(Arc::new(graph)));
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..50 {
+            let query = repository
+                .render_read_with_rng("shortest_path", &mut rng)
+                .unwrap()
+                .query;
+            let id = |name: &str| match query.params.get(name) {
+                Some(QueryParam::Integer(id)) => *id,
+                other => panic!("{:?}", other),
+            };
+            assert!((1..=6).contains(&(id("to") - id("from"))), "{:?}", query);
+
+            let query = repository
+                .render_read_with_rng("aggregate_expansion_1_with_age_threshold", &mut rng)
+                .unwrap()
+                .query;
+            assert_eq!(
+                query.params.get("min_age").cloned(),
+                Some(match query.params.get("id") {
+                    Some(QueryParam::Integer(50)) => QueryParam::Integer(18),
+                    Some(QueryParam::Integer(id)) => QueryParam::Integer(id + 1),
+                    other => panic!("{:?}", other),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn typed_params_add_string_float_list_and_datetime_lookups() {
+        let repository = |flavour| {
+            UsersQueriesRepository::new(
+                100,
+                1000,
+                flavour,
+                AlgorithmQuerySelection::default(),
+                QueryCoverageProfile::Baseline,
+                AccessPattern::default(),
+                false,
+            )
+            .with_typed_params(true)
+        };
+        let catalog = repository(Flavour::Neo4j).catalog();
+        let params = |name: &str| {
+            catalog
+                .iter()
+                .find(|entry| entry.name == name)
+                .and_then(|entry| entry.shape.clone())
+                .unwrap()
+                .params
+        };
+        let filter = params("typed_param_filter");
+        assert_eq!(filter.get("ids"), Some(&ParamType::List));
+        assert_eq!(filter.get("gender"), Some(&ParamType::String));
+        assert_eq!(filter.get("min_completion"), Some(&ParamType::Float));
+        assert_eq!(
+            params("created_before_datetime").get("as_of"),
+            Some(&ParamType::DateTime)
+        );
+        assert!(!repository(Flavour::Memgraph)
+            .catalog()
+            .iter()
+            .any(|entry| entry.name == "created_before_datetime"));
+    }
+
     #[test]
     fn test_algorithm_selection_can_limit_queries() {
         let repository = UsersQueriesRepository::new(
@@ -1361,6 +2891,8 @@ mod tests {
                 harmonic: false,
             },
             QueryCoverageProfile::Baseline,
+            AccessPattern::default(),
+            false,
         );
 
         assert_eq!(
@@ -1377,6 +2909,8 @@ mod tests {
             Flavour::FalkorDB,
             AlgorithmQuerySelection::default(),
             QueryCoverageProfile::Baseline,
+            AccessPattern::default(),
+            false,
         );
         let names: Vec<&str> = repo.non_algorithm_read_names().iter().map(String::as_str).collect();
 
@@ -1413,6 +2947,8 @@ mod tests {
             Flavour::FalkorDB,
             AlgorithmQuerySelection::default(),
             QueryCoverageProfile::Baseline,
+            AccessPattern::default(),
+            false,
         );
 
         // Render a whole corpus (many draws) for a randomised shape from a fixed seed, twice: the
@@ -1441,9 +2977,191 @@ mod tests {
             Flavour::FalkorDB,
             AlgorithmQuerySelection::default(),
             QueryCoverageProfile::Baseline,
+            AccessPattern::default(),
+            false,
         );
         assert!(repo.render_read_with_rng("no_such_shape", &mut rand::rng()).is_none());
         // A write shape is not a read and must not render through the read seam.
         assert!(repo.render_read_with_rng("single_vertex_write", &mut rand::rng()).is_none());
     }
+
+    #[test]
+    fn all_flavours_share_parameters_and_select_by_vendor() {
+        let repo = UsersQueriesRepository::new(
+            1_000,
+            10_000,
+            Flavour::FalkorDB,
+            AlgorithmQuerySelection::default(),
+            QueryCoverageProfile::ExtendedCore,
+            AccessPattern::default(),
+            true,
+        )
+        .with_all_flavours();
+
+        let mut seen_memgraph_gap = false;
+        for _ in 0..2_000 {
+            let query = repo.random_query(0.3).expect("query");
+            assert!(!query.flavours.contains_key(&Flavour::FalkorDB));
+            let neo4j = query
+                .flavours
+                .get(&Flavour::Neo4j)
+                .expect("neo4j rendering");
+            assert_eq!(neo4j.query.params, query.query.params);
+            assert_eq!(neo4j.statements.len(), query.statements.len());
+            // Memgraph has no spatial distance function, so that query has no Memgraph text.
+            match query.flavours.get(&Flavour::Memgraph) {
+                Some(memgraph) => assert_eq!(memgraph.query.params, query.query.params),
+                None => {
+                    assert_eq!(query.q_name, "temporal_spatial_roundtrip");
+                    seen_memgraph_gap = true;
+                }
+            }
+        }
+        assert!(seen_memgraph_gap);
+
+        let mut query = loop {
+            let query = repo.random_query(0.0).expect("query");
+            if query.q_name == "shortest_path" {
+                break query;
+            }
+        };
+        let line = serde_json::to_string(&query).unwrap();
+        assert!(line.contains("\"flavours\":{\"neo4j\":"));
+        assert!(line.contains("\"memgraph\":{\"query\":"));
+        let falkor_text = query.query.text.clone();
+        let params = query.query.params.clone();
+
+        let mut from_file: PreparedQuery = serde_json::from_str(&line).unwrap();
+        assert!(from_file.select_flavour(Flavour::Memgraph));
+        assert!(from_file.query.text.contains("[*BFS]"));
+        assert!(from_file.cypher.contains("[*BFS]"));
+        assert_eq!(from_file.query.params, params);
+        assert_eq!(from_file.q_name, "shortest_path");
+        assert!(from_file.flavours.is_empty());
+
+        // The generated flavour has no stored copy; the top-level text is already its rendering.
+        assert!(!query.select_flavour(Flavour::FalkorDB));
+        assert_eq!(query.query.text, falkor_text);
+    }
+
+    #[test]
+    fn single_flavour_queries_carry_no_renderings() {
+        let repo = UsersQueriesRepository::new(
+            100,
+            1000,
+            Flavour::Neo4j,
+            AlgorithmQuerySelection::default(),
+            QueryCoverageProfile::Baseline,
+            AccessPattern::default(),
+            false,
+        );
+        let query = repo.random_query(0.5).expect("query");
+        assert!(query.flavours.is_empty());
+        assert!(!serde_json::to_string(&query).unwrap().contains("flavours"));
+        assert_eq!(
+            serde_json::to_string(&Flavour::FalkorDB).unwrap(),
+            "\"falkor\""
+        );
+        assert_eq!(Flavour::from(Vendor::Memgraph), Flavour::Memgraph);
+    }
+
+    #[test]
+    fn vector_workload_renders_knn_reads_at_its_share() {
+        let vector = VectorWorkload {
+            dimensions: 8,
+            k: 5,
+            share: 1.0,
+        };
+        let repo = UsersQueriesRepository::new(
+            1_000,
+            10_000,
+            Flavour::FalkorDB,
+            AlgorithmQuerySelection {
+                pagerank: false,
+                max_flow: false,
+                msf: false,
+                harmonic: false,
+            },
+            QueryCoverageProfile::Baseline,
+            AccessPattern::default(),
+            false,
+        )
+        .with_vector_workload(Some(vector))
+        .with_all_flavours();
+        assert!(repo
+            .catalog()
+            .iter()
+            .any(|entry| entry.name == VECTOR_KNN_QUERY_NAME));
+
+        for _ in 0..50 {
+            let query = repo.random_query(0.0).expect("query");
+            assert_eq!(query.q_name, VECTOR_KNN_QUERY_NAME);
+            assert!(query
+                .query
+                .text
+                .contains("'User', 'embedding_8', 5, vecf32(["));
+            let neo4j = &query.flavours[&Flavour::Neo4j].query.text;
+            assert!(neo4j.contains("'bench_user_embedding_8_idx', 5, ["));
+            // Both vendors search with the same vector.
+            let vector_of =
+                |text: &str| text[text.find("[").unwrap()..text.find("]").unwrap()].to_string();
+            assert_eq!(vector_of(&query.query.text), vector_of(neo4j));
+            assert_eq!(vector_of(neo4j).matches(", ").count(), 7);
+            assert!(!query.flavours.contains_key(&Flavour::Memgraph));
+        }
+        assert!(vector
+            .embedding_expression("u")
+            .starts_with("[i IN range(0, 7) | "));
+    }
+
+    #[test]
+    fn query_mix_selects_the_reads_by_group() {
+        let build = |query_mix| {
+            UsersQueriesRepository::new(
+                1_000,
+                10_000,
+                Flavour::Memgraph,
+                AlgorithmQuerySelection {
+                    pagerank: false,
+                    max_flow: false,
+                    msf: false,
+                    harmonic: false,
+                },
+                QueryCoverageProfile::Baseline,
+                AccessPattern::default(),
+                false,
+            )
+            .with_query_mix(query_mix)
+        };
+        let groups_drawn = |repo: &UsersQueriesRepository| {
+            (0..200)
+                .map(|_| QueryGroup::of(&repo.random_query(0.0).expect("query").q_name))
+                .collect::<std::collections::BTreeSet<_>>()
+        };
+
+        let oltp = build(QueryMix::Oltp);
+        assert!(oltp
+            .catalog()
+            .iter()
+            .all(|entry| entry.group == QueryGroup::Oltp));
+        assert_eq!(groups_drawn(&oltp), [QueryGroup::Oltp].into());
+
+        let analytics = build(QueryMix::Analytics);
+        let catalog = analytics.catalog();
+        // Appended after the OLTP queries, whose ids stay the same.
+        assert_eq!(
+            catalog.len(),
+            oltp.catalog().len() + ANALYTICS_QUERY_NAMES.len()
+        );
+        assert!(catalog[oltp.catalog().len()..]
+            .iter()
+            .all(|entry| entry.group == QueryGroup::Analytics));
+        assert_eq!(groups_drawn(&analytics), [QueryGroup::Analytics].into());
+
+        assert_eq!(
+            groups_drawn(&build(QueryMix::Mixed)),
+            [QueryGroup::Oltp, QueryGroup::Analytics].into()
+        );
+        assert_eq!(QueryGroup::of("algo_msf_summary"), QueryGroup::Algorithm);
+    }
 }
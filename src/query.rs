@@ -1,12 +1,12 @@
 // CYPHER name_param = "Niccolò Machiavelli" birth_year_param = 1469 MATCH (p:Person {name: $name_param, birth_year: $birth_year_param}) RETURN p
 use neo4rs::BoltType;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Query {
     pub text: String,
-    pub params: HashMap<String, QueryParam>,
+    pub params: BTreeMap<String, QueryParam>,
 }
 
 impl Query {
@@ -49,6 +49,11 @@ pub enum QueryParam {
     Integer(i32),
     Float(f32),
     Boolean(bool),
+    /// For `IN` clauses; the elements may be of any type, lists included.
+    List(Vec<QueryParam>),
+    /// Milliseconds since the Unix epoch, UTC. Bolt carries it as a `DateTime`; FalkorDB, whose
+    /// `CYPHER` preamble takes literals only, as the integer `timestamp()` would return.
+    DateTime(i64),
 }
 
 impl From<QueryParam> for BoltType {
@@ -58,17 +63,66 @@ impl From<QueryParam> for BoltType {
             QueryParam::Integer(i) => i.into(),
             QueryParam::Float(f) => f.into(),
             QueryParam::Boolean(b) => b.into(),
+            QueryParam::List(items) => items.into(),
+            QueryParam::DateTime(millis) => match QueryParam::utc(millis) {
+                Some(datetime) => datetime.fixed_offset().into(),
+                None => millis.into(),
+            },
         }
     }
 }
 
 impl QueryParam {
+    /// `millis` as a UTC datetime; `None` when chrono can't represent it.
+    pub fn utc(millis: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::from_timestamp_millis(millis)
+    }
+
     pub fn to_cypher_string(&self) -> String {
+        let mut cypher = String::new();
+        self.write_cypher(&mut cypher);
+        cypher
+    }
+
+    /// Append [`Self::to_cypher_string`] to `out`.
+    pub fn write_cypher(
+        &self,
+        out: &mut String,
+    ) {
+        use std::fmt::Write;
         match self {
-            QueryParam::String(s) => format!("\"{}\"", s.replace("\"", "\\\"")),
-            QueryParam::Integer(i) => i.to_string(),
-            QueryParam::Float(f) => f.to_string(),
-            QueryParam::Boolean(b) => b.to_string(),
+            QueryParam::String(s) => {
+                out.push('"');
+                for c in s.chars() {
+                    if c == '"' || c == '\\' {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+                out.push('"');
+            }
+            QueryParam::Integer(i) => {
+                let _ = write!(out, "{}", i);
+            }
+            QueryParam::Float(f) => {
+                let _ = write!(out, "{}", f);
+            }
+            QueryParam::Boolean(b) => {
+                let _ = write!(out, "{}", b);
+            }
+            QueryParam::List(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    item.write_cypher(out);
+                }
+                out.push(']');
+            }
+            QueryParam::DateTime(millis) => {
+                let _ = write!(out, "{}", millis);
+            }
         }
     }
 }
@@ -82,6 +136,8 @@ impl PartialEq for QueryParam {
             (QueryParam::Integer(a), QueryParam::Integer(b)) => a == b,
             (QueryParam::Float(a), QueryParam::Float(b)) => a.to_bits() == b.to_bits(),
             (QueryParam::Boolean(a), QueryParam::Boolean(b)) => a == b,
+            (QueryParam::List(a), QueryParam::List(b)) => a == b,
+            (QueryParam::DateTime(a), QueryParam::DateTime(b)) => a == b,
             _ => false,
         }
     }
@@ -149,6 +205,22 @@ impl From<bool> for QueryParam {
         QueryParam::Boolean(value)
     }
 }
+
+impl<T: Into<QueryParam>> From<Vec<T>> for QueryParam {
+    fn from(value: Vec<T>) -> Self {
+        QueryParam::List(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<std::time::SystemTime> for QueryParam {
+    fn from(value: std::time::SystemTime) -> Self {
+        let millis = match value.duration_since(std::time::UNIX_EPOCH) {
+            Ok(since) => since.as_millis() as i64,
+            Err(before) => -(before.duration().as_millis() as i64),
+        };
+        QueryParam::DateTime(millis)
+    }
+}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,6 +272,32 @@ mod tests {
         assert_eq!(QueryParam::Integer(42).to_cypher_string(), "42");
         assert_eq!(QueryParam::Float(3.16).to_cypher_string(), "3.16");
         assert_eq!(QueryParam::Boolean(true).to_cypher_string(), "true");
+        assert_eq!(
+            QueryParam::from("a \\ \"b\"").to_cypher_string(),
+            "\"a \\\\ \\\"b\\\"\""
+        );
+        assert_eq!(
+            QueryParam::from(vec![QueryParam::from(1), "x".into(), 2.5.into()]).to_cypher_string(),
+            "[1, \"x\", 2.5]"
+        );
+        assert_eq!(
+            QueryParam::DateTime(1_700_000_000_000).to_cypher_string(),
+            "1700000000000"
+        );
+    }
+
+    #[test]
+    fn list_and_datetime_params_are_typed_for_bolt() {
+        let ids: BoltType = QueryParam::from(vec![1, 2, 3]).into();
+        assert_eq!(ids, BoltType::from(vec![1, 2, 3]));
+        let as_of: BoltType = QueryParam::DateTime(1_700_000_000_000).into();
+        assert!(matches!(as_of, BoltType::DateTime(_)), "{:?}", as_of);
+        let moment = std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_500);
+        assert_eq!(QueryParam::from(moment), QueryParam::DateTime(1_500));
+
+        let list = QueryParam::from(vec!["a", "b"]);
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(serde_json::from_str::<QueryParam>(&json).unwrap(), list);
     }
 
     #[test]
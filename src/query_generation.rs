@@ -0,0 +1,214 @@
+//! Parallel `generate-queries`. The file is cut into chunks of a fixed size; each chunk is
+//! generated and serialized on a blocking thread from its own RNG, seeded from the run's seed
+//! and the chunk's index, so a `--seed` reproduces the same file whatever the number of
+//! workers. Chunks are written in order while the following ones are still being generated.
+//! `run --inline-queries` takes the same chunks in memory, so it runs the queries a file
+//! generated with the same seed would hold.
+
+use crate::bounded_writes::MutationTracker;
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::queries_repository::{PreparedQuery, UsersQueriesRepository};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::task::JoinHandle;
+
+pub const CHUNK_SIZE: usize = 10_000;
+
+/// The seed of chunk `index`; neighbouring chunks get unrelated streams.
+fn chunk_seed(
+    seed: u64,
+    index: usize,
+) -> u64 {
+    seed.wrapping_add((index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// `count` queries drawn from an RNG seeded with `seed`. With bounded writes, the compensating
+/// writes follow the queries they compensate, and the chunk ends with those still pending.
+pub fn generate_chunk(
+    repository: &UsersQueriesRepository,
+    count: usize,
+    write_ratio: f32,
+    seed: u64,
+) -> Vec<PreparedQuery> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut queries = Vec::with_capacity(count);
+    let mut tracker = repository.mutation_tracker();
+    for _ in 0..count {
+        if let Some(query) = repository.random_query_with_rng(write_ratio, &mut rng) {
+            let recorded = tracker
+                .as_mut()
+                .is_some_and(|tracker| tracker.record(&query));
+            queries.push(query);
+            if let Some(tracker) = tracker.as_mut().filter(|_| recorded) {
+                push_compensations(repository, tracker, &mut rng, &mut queries);
+            }
+        }
+    }
+    if let Some(tracker) = tracker.as_mut() {
+        push_compensations(repository, tracker, &mut rng, &mut queries);
+    }
+    queries
+}
+
+/// [`generate_chunk`] as JSON lines.
+fn serialize_chunk(queries: &[PreparedQuery]) -> BenchmarkResult<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(queries.len() * 256);
+    for query in queries {
+        serde_json::to_writer(&mut buffer, query)?;
+        buffer.push(b'\n');
+    }
+    Ok(buffer)
+}
+
+fn push_compensations(
+    repository: &UsersQueriesRepository,
+    tracker: &mut MutationTracker,
+    rng: &mut StdRng,
+    queries: &mut Vec<PreparedQuery>,
+) {
+    queries.extend(
+        tracker
+            .drain(rng)
+            .into_iter()
+            .filter_map(|(name, query)| repository.prepare_compensation(name, query)),
+    );
+}
+
+/// Generate `count` queries in chunks of `chunk_size` on up to `workers` threads and write them
+/// to `writer` in chunk order.
+pub async fn write_queries<W>(
+    repository: Arc<UsersQueriesRepository>,
+    count: usize,
+    write_ratio: f32,
+    seed: u64,
+    workers: usize,
+    chunk_size: usize,
+    writer: &mut W,
+) -> BenchmarkResult<()>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    generate_chunks(
+        repository,
+        count,
+        write_ratio,
+        seed,
+        workers,
+        chunk_size,
+        |queries| serialize_chunk(&queries),
+        async |chunk: Vec<u8>| Ok(writer.write_all(&chunk).await?),
+    )
+    .await
+}
+
+/// Like [`write_queries`], but keep the queries in memory.
+pub async fn generate_queries(
+    repository: Arc<UsersQueriesRepository>,
+    count: usize,
+    write_ratio: f32,
+    seed: u64,
+    workers: usize,
+    chunk_size: usize,
+) -> BenchmarkResult<Vec<PreparedQuery>> {
+    let mut queries = Vec::with_capacity(count);
+    generate_chunks(
+        repository,
+        count,
+        write_ratio,
+        seed,
+        workers,
+        chunk_size,
+        Ok,
+        async |chunk: Vec<PreparedQuery>| {
+            queries.extend(chunk);
+            Ok(())
+        },
+    )
+    .await?;
+    Ok(queries)
+}
+
+/// Generate the chunks on blocking threads, turn each into `T` there with `finish`, and hand them
+/// to `sink` in chunk order.
+#[allow(clippy::too_many_arguments)]
+async fn generate_chunks<T: Send + 'static>(
+    repository: Arc<UsersQueriesRepository>,
+    count: usize,
+    write_ratio: f32,
+    seed: u64,
+    workers: usize,
+    chunk_size: usize,
+    finish: fn(Vec<PreparedQuery>) -> BenchmarkResult<T>,
+    mut sink: impl AsyncFnMut(T) -> BenchmarkResult<()>,
+) -> BenchmarkResult<()> {
+    let chunk_size = chunk_size.max(1);
+    let chunks = count.div_ceil(chunk_size);
+    let mut pending: VecDeque<JoinHandle<BenchmarkResult<T>>> = VecDeque::new();
+    let mut next = 0;
+    loop {
+        while next < chunks && pending.len() < workers.max(1) {
+            let repository = repository.clone();
+            let len = chunk_size.min(count - next * chunk_size);
+            let seed = chunk_seed(seed, next);
+            pending.push_back(tokio::task::spawn_blocking(move || {
+                finish(generate_chunk(&repository, len, write_ratio, seed))
+            }));
+            next += 1;
+        }
+        let Some(handle) = pending.pop_front() else {
+            break;
+        };
+        let chunk = handle
+            .await
+            .map_err(|e| OtherError(format!("Query generation failed: {}", e)))??;
+        sink(chunk).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queries_repository::{
+        AccessPattern, AlgorithmQuerySelection, Flavour, QueryCoverageProfile,
+    };
+
+    fn repository() -> Arc<UsersQueriesRepository> {
+        let repository = UsersQueriesRepository::new(
+            1_000,
+            10_000,
+            Flavour::FalkorDB,
+            AlgorithmQuerySelection::default(),
+            QueryCoverageProfile::default(),
+            AccessPattern::default(),
+            false,
+        )
+        .with_all_flavours();
+        Arc::new(repository)
+    }
+
+    async fn generate(workers: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_queries(repository(), 250, 0.2, 42, workers, 64, &mut out)
+            .await
+            .unwrap();
+        out
+    }
+
+    #[tokio::test]
+    async fn a_seed_yields_the_same_file_for_any_worker_count() {
+        let sequential = generate(1).await;
+        assert_eq!(sequential.iter().filter(|b| **b == b'\n').count(), 250);
+        assert_eq!(generate(4).await, sequential);
+        assert_ne!(chunk_seed(42, 0), chunk_seed(42, 1));
+
+        let inline = generate_queries(repository(), 250, 0.2, 42, 3, 64)
+            .await
+            .unwrap();
+        assert_eq!(serialize_chunk(&inline).unwrap(), sequential);
+    }
+}
@@ -0,0 +1,232 @@
+//! `run --capture-plans explain|profile`: before the measured phase, the plan the server picks
+//! for one instance of every catalog query, written to `<results-dir>/<vendor>/plans.json`.
+//!
+//! Writes are always only `EXPLAIN`ed so the graph the run measures stays untouched; `profile`
+//! executes the reads once, which is a small extra warm-up. A query whose plan can't be captured
+//! keeps its error in the file and doesn't fail the run. Transactions are planned by their
+//! first statement.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::queries_repository::{PreparedQuery, QueryCatalogEntry, QueryType};
+use crate::scenario::Vendor;
+use crate::utils::{create_directory_if_not_exists, write_to_file};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+pub const PLANS_FILE: &str = "plans.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanMode {
+    Explain,
+    Profile,
+}
+
+impl PlanMode {
+    /// The mode `query_type` is planned with: writes are never profiled.
+    pub fn for_query(
+        self,
+        query_type: QueryType,
+    ) -> Self {
+        match query_type {
+            QueryType::Write => PlanMode::Explain,
+            QueryType::Read => self,
+        }
+    }
+
+    /// The Cypher prefix asking for this plan.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            PlanMode::Explain => "EXPLAIN",
+            PlanMode::Profile => "PROFILE",
+        }
+    }
+}
+
+/// One entry of `plans.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryPlan {
+    pub name: String,
+    pub mode: PlanMode,
+    /// The instance that was planned, with its parameters inlined.
+    pub query: String,
+    /// The plan as the server prints it, one operator per line.
+    pub plan: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Written to `plans.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryPlans {
+    pub vendor: String,
+    pub plans: Vec<QueryPlan>,
+}
+
+impl QueryPlans {
+    pub fn new(vendor: Vendor) -> Self {
+        QueryPlans {
+            vendor: vendor.to_string(),
+            plans: Vec::new(),
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        query: &PreparedQuery,
+        mode: PlanMode,
+        plan: BenchmarkResult<Vec<String>>,
+    ) {
+        let (plan, error) = match plan {
+            Ok(plan) => (plan, None),
+            Err(e) => {
+                warn!("Failed capturing the plan of {}: {}", query.q_name, e);
+                (Vec::new(), Some(e.to_string()))
+            }
+        };
+        self.plans.push(QueryPlan {
+            name: query.q_name.clone(),
+            mode,
+            query: query.cypher.clone(),
+            plan,
+            error,
+        });
+    }
+
+    pub async fn write(
+        &self,
+        run_dir: &Path,
+    ) -> BenchmarkResult<()> {
+        create_directory_if_not_exists(&run_dir.to_string_lossy()).await?;
+        let path = run_dir.join(PLANS_FILE);
+        write_to_file(
+            &path.to_string_lossy(),
+            &serde_json::to_string_pretty(self)?,
+        )
+        .await?;
+        info!(
+            "Captured {} query plans into {}",
+            self.plans
+                .iter()
+                .filter(|plan| plan.error.is_none())
+                .count(),
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+/// The first instance of every catalog query present in `queries`, in catalog order.
+pub fn plan_samples<'a>(
+    queries: &'a [PreparedQuery],
+    catalog: &[QueryCatalogEntry],
+) -> Vec<&'a PreparedQuery> {
+    let mut first: HashMap<&str, &PreparedQuery> = HashMap::new();
+    for query in queries {
+        first.entry(query.q_name.as_str()).or_insert(query);
+    }
+    catalog
+        .iter()
+        .filter_map(|entry| first.get(entry.name.as_str()).copied())
+        .collect()
+}
+
+/// Render a Neo4j plan tree (`plan.root` or `profile` of the HTTP API) as indented lines.
+pub fn render_neo4j_plan(root: &serde_json::Value) -> BenchmarkResult<Vec<String>> {
+    if root["operatorType"].as_str().is_none() {
+        return Err(OtherError(format!("Unexpected Neo4j plan: {}", root)));
+    }
+    let mut lines = Vec::new();
+    render_neo4j_operator(root, 0, &mut lines);
+    Ok(lines)
+}
+
+fn render_neo4j_operator(
+    operator: &serde_json::Value,
+    depth: usize,
+    lines: &mut Vec<String>,
+) {
+    let mut line = format!(
+        "{}{}",
+        "    ".repeat(depth),
+        operator["operatorType"].as_str().unwrap_or("?")
+    );
+    let details = operator["arguments"]["Details"]
+        .as_str()
+        .or_else(|| operator["Details"].as_str());
+    if let Some(details) = details {
+        line.push_str(&format!(" | {}", details));
+    }
+    for (key, label) in [("rows", "rows"), ("dbHits", "db hits")] {
+        if let Some(value) = operator[key].as_u64() {
+            line.push_str(&format!(" | {} {}", label, value));
+        }
+    }
+    lines.push(line);
+    for child in operator["children"].as_array().into_iter().flatten() {
+        render_neo4j_operator(child, depth + 1, lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queries_repository::QueryGroup;
+    use crate::query::Query;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn one_sample_per_catalog_entry_and_rendered_neo4j_plans() {
+        let query = |name: &str, text: &str| {
+            PreparedQuery::new(
+                0,
+                name.to_string(),
+                QueryType::Read,
+                Query {
+                    text: text.to_string(),
+                    params: BTreeMap::new(),
+                },
+            )
+        };
+        let entry = |name: &str| QueryCatalogEntry {
+            id: 0,
+            name: name.to_string(),
+            q_type: QueryType::Read,
+            group: QueryGroup::default(),
+            timeout_ms: None,
+            shape: None,
+        };
+        let queries = vec![
+            query("b", "RETURN 1"),
+            query("a", "RETURN 2"),
+            query("b", "RETURN 3"),
+        ];
+        let samples = plan_samples(&queries, &[entry("a"), entry("b"), entry("missing")]);
+        let texts: Vec<&str> = samples.iter().map(|q| q.query.text.as_str()).collect();
+        assert_eq!(texts, vec!["RETURN 2", "RETURN 1"]);
+        assert_eq!(
+            PlanMode::Profile.for_query(QueryType::Write),
+            PlanMode::Explain
+        );
+
+        let plan = serde_json::json!({
+            "operatorType": "ProduceResults@neo4j",
+            "arguments": {"Details": "n"},
+            "rows": 1,
+            "dbHits": 0,
+            "children": [{"operatorType": "NodeIndexSeek@neo4j", "arguments": {}, "children": []}]
+        });
+        assert_eq!(
+            render_neo4j_plan(&plan).unwrap(),
+            vec![
+                "ProduceResults@neo4j | n | rows 1 | db hits 0",
+                "    NodeIndexSeek@neo4j"
+            ]
+        );
+        assert!(render_neo4j_plan(&serde_json::json!({})).is_err());
+    }
+}
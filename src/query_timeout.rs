@@ -0,0 +1,250 @@
+//! Per-query timeouts for the measured phase of `run`.
+//!
+//! `run --query-timeout` sets the timeout for every query; without it each vendor keeps its own
+//! default (see [`QueryTimeouts::uniform`] callers). Individual queries can be given a different
+//! timeout at generation time with `generate-queries --query-timeout-override <query>=<timeout>`,
+//! which is stored in the queries file's catalog so every vendor replaying the file uses it.
+
+use crate::queries_repository::QueryCatalogEntry;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A timeout parsed from `500ms`, `60s` or `5m`; a bare number is seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryTimeout(pub Duration);
+
+/// Parse `500ms`, `60s` or `5m`; a bare number is seconds.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(idx) => s.split_at(idx),
+        None => (s, "s"),
+    };
+    let value: u64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid duration '{}', expected e.g. 60s or 500ms", s))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "s" => Ok(Duration::from_secs(value)),
+        "m" => Ok(Duration::from_secs(value * 60)),
+        other => Err(format!(
+            "unknown duration unit '{}', expected ms, s or m",
+            other
+        )),
+    }
+}
+
+impl FromStr for QueryTimeout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let timeout = parse_duration(s)?;
+        if timeout.is_zero() {
+            return Err("query timeout must be positive".to_string());
+        }
+        Ok(QueryTimeout(timeout))
+    }
+}
+
+impl fmt::Display for QueryTimeout {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "{}ms", self.0.as_millis())
+    }
+}
+
+/// `generate-queries --query-timeout-override <query>=<timeout>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTimeoutOverride {
+    pub query: String,
+    pub timeout: QueryTimeout,
+}
+
+impl FromStr for QueryTimeoutOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (query, timeout) = s.split_once('=').ok_or_else(|| {
+            format!(
+                "invalid query timeout override '{}', expected e.g. algo_pagerank_summary=5m",
+                s
+            )
+        })?;
+        Ok(QueryTimeoutOverride {
+            query: query.trim().to_string(),
+            timeout: timeout.parse()?,
+        })
+    }
+}
+
+/// The timeout of every query of a run, indexed by query id like the catalog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryTimeouts {
+    default: Duration,
+    overrides: Vec<Option<Duration>>,
+}
+
+impl QueryTimeouts {
+    /// `default` for every query.
+    pub fn uniform(default: Duration) -> Self {
+        QueryTimeouts {
+            default,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// `default`, except for the catalog entries that carry their own timeout.
+    pub fn new(
+        default: Duration,
+        catalog: &[QueryCatalogEntry],
+    ) -> Self {
+        let mut overrides = Vec::new();
+        for entry in catalog {
+            let idx = entry.id as usize;
+            if overrides.len() <= idx {
+                overrides.resize(idx + 1, None);
+            }
+            overrides[idx] = entry.timeout_ms.map(Duration::from_millis);
+        }
+        QueryTimeouts { default, overrides }
+    }
+
+    pub fn default_timeout(&self) -> Duration {
+        self.default
+    }
+
+    pub fn for_query(
+        &self,
+        q_id: u16,
+    ) -> Duration {
+        self.overrides
+            .get(q_id as usize)
+            .copied()
+            .flatten()
+            .unwrap_or(self.default)
+    }
+}
+
+/// The timeouts a run used and how many queries hit them; recorded in meta.json.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueryTimeoutReport {
+    pub default_ms: u64,
+    /// Queries whose catalog entry overrides the default.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides_ms: BTreeMap<String, u64>,
+    /// Timed-out executions per query name; queries that never timed out are left out.
+    pub timeouts: BTreeMap<String, u64>,
+}
+
+impl QueryTimeoutReport {
+    pub fn new(
+        timeouts: &QueryTimeouts,
+        catalog: &[QueryCatalogEntry],
+        counts: impl IntoIterator<Item = (u16, u64)>,
+    ) -> Self {
+        let name_of = |q_id: u16| {
+            catalog
+                .iter()
+                .find(|entry| entry.id == q_id)
+                .map(|entry| entry.name.clone())
+        };
+        QueryTimeoutReport {
+            default_ms: timeouts.default.as_millis() as u64,
+            overrides_ms: catalog
+                .iter()
+                .filter_map(|entry| Some((entry.name.clone(), entry.timeout_ms?)))
+                .collect(),
+            timeouts: counts
+                .into_iter()
+                .filter(|(_, count)| *count > 0)
+                .filter_map(|(q_id, count)| Some((name_of(q_id)?, count)))
+                .collect(),
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.timeouts.values().sum()
+    }
+}
+
+/// Apply `overrides` to `catalog`, rejecting names that aren't in it.
+pub fn apply_timeout_overrides(
+    catalog: &mut [QueryCatalogEntry],
+    overrides: &[QueryTimeoutOverride],
+) -> Result<(), String> {
+    for timeout_override in overrides {
+        let entry = catalog
+            .iter_mut()
+            .find(|entry| entry.name == timeout_override.query)
+            .ok_or_else(|| {
+                format!(
+                    "--query-timeout-override: unknown query '{}'",
+                    timeout_override.query
+                )
+            })?;
+        entry.timeout_ms = Some(timeout_override.timeout.0.as_millis() as u64);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queries_repository::{QueryGroup, QueryType};
+
+    fn entry(
+        id: u16,
+        name: &str,
+    ) -> QueryCatalogEntry {
+        QueryCatalogEntry {
+            id,
+            name: name.to_string(),
+            q_type: QueryType::Read,
+            group: QueryGroup::of(name),
+            timeout_ms: None,
+            shape: None,
+        }
+    }
+
+    #[test]
+    fn catalog_overrides_replace_the_default_timeout() {
+        assert_eq!(
+            "500ms".parse::<QueryTimeout>().unwrap().0,
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            "90".parse::<QueryTimeout>().unwrap().0,
+            Duration::from_secs(90)
+        );
+        assert!("0s".parse::<QueryTimeout>().is_err());
+        assert!("pagerank:5m".parse::<QueryTimeoutOverride>().is_err());
+
+        let mut catalog = vec![entry(0, "single_vertex_read"), entry(1, "pagerank")];
+        let overrides = ["pagerank=5m".parse::<QueryTimeoutOverride>().unwrap()];
+        apply_timeout_overrides(&mut catalog, &overrides).unwrap();
+        assert!(apply_timeout_overrides(
+            &mut catalog,
+            &["nope=1s".parse::<QueryTimeoutOverride>().unwrap()]
+        )
+        .is_err());
+
+        let timeouts = QueryTimeouts::new(Duration::from_secs(60), &catalog);
+        assert_eq!(timeouts.for_query(0), Duration::from_secs(60));
+        assert_eq!(timeouts.for_query(1), Duration::from_secs(300));
+        assert_eq!(timeouts.for_query(7), Duration::from_secs(60));
+
+        let report = QueryTimeoutReport::new(&timeouts, &catalog, [(0, 0), (1, 3)]);
+        assert_eq!(report.default_ms, 60_000);
+        assert_eq!(report.overrides_ms.get("pagerank"), Some(&300_000));
+        assert_eq!(
+            report.timeouts.into_iter().collect::<Vec<_>>(),
+            vec![("pagerank".to_string(), 3)]
+        );
+    }
+}
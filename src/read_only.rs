@@ -0,0 +1,91 @@
+//! `--read-only`: a guard for runs against shared or production-adjacent endpoints. `load` is
+//! refused outright, a `run` refuses a queries file holding any write before it connects, and
+//! every worker refuses a write again before sending it, so neither a mixed file picked by
+//! mistake nor a slip in the file's handling reaches the server.
+
+use crate::compact_query::CompactQuery;
+use crate::error::BenchmarkError::{self, OtherError};
+use crate::error::BenchmarkResult;
+use crate::queries_repository::{PreparedQuery, QueryType};
+use std::collections::BTreeMap;
+
+/// Refuse a queries file with writes, naming the write queries and how often each occurs.
+pub fn check_queries(queries: &[PreparedQuery]) -> BenchmarkResult<()> {
+    let mut writes = BTreeMap::<&str, usize>::new();
+    for query in queries.iter().filter(|q| q.q_type == QueryType::Write) {
+        *writes.entry(query.q_name.as_str()).or_default() += 1;
+    }
+    if writes.is_empty() {
+        return Ok(());
+    }
+    let names = writes
+        .iter()
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(OtherError(format!(
+        "--read-only refuses a queries file with {} writes: {}; generate it with --write-ratio 0",
+        writes.values().sum::<usize>(),
+        names
+    )))
+}
+
+/// Refuse a setup step that writes to the graph, `what` naming the queries that need it.
+pub fn refuse_setup(
+    needed: bool,
+    what: &str,
+) -> BenchmarkResult<()> {
+    if needed {
+        return Err(OtherError(format!(
+            "the {} queries need setup that writes to the graph and can't run with --read-only",
+            what
+        )));
+    }
+    Ok(())
+}
+
+/// The dispatch-time check: `Some` error in place of sending `query` when it writes.
+pub fn refused(query: &CompactQuery) -> Option<BenchmarkError> {
+    query.is_write().then(|| {
+        OtherError(format!(
+            "--read-only refused to send write query '{}'",
+            query.q_name
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::QueryBuilder;
+
+    fn query(
+        name: &str,
+        q_type: QueryType,
+    ) -> PreparedQuery {
+        PreparedQuery::new(
+            0,
+            name.to_string(),
+            q_type,
+            QueryBuilder::new().text("RETURN 1").build(),
+        )
+    }
+
+    #[test]
+    fn a_file_with_writes_is_refused_naming_them() {
+        assert!(check_queries(&[query("single_vertex_read", QueryType::Read)]).is_ok());
+        let err = check_queries(&[
+            query("single_vertex_read", QueryType::Read),
+            query("single_vertex_write", QueryType::Write),
+            query("single_vertex_write", QueryType::Write),
+            query("single_edge_update", QueryType::Write),
+        ])
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("3 writes: single_edge_update (1), single_vertex_write (2)"),
+            "{}",
+            err
+        );
+    }
+}
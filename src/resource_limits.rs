@@ -0,0 +1,399 @@
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::utils::spawn_command;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::fs;
+use tracing::{info, warn};
+
+/// Mount point of the cgroup v2 hierarchy; override with `BENCHMARK_CGROUP_ROOT` to use a
+/// delegated subtree (e.g. a systemd user slice) when not running as root.
+const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Joins the cgroup whose `cgroup.procs` is passed as `$0`, then execs the server in place, so
+/// the server keeps the spawned pid and is inside the cgroup before it allocates anything.
+const JOIN_CGROUP_SCRIPT: &str = r#"echo $$ > "$0" && exec "$@""#;
+
+/// `--server-mem-limit`, e.g. `8G`: a memory ceiling in bytes. `K`, `M`, `G` and `T` are
+/// binary units (an optional trailing `B`/`iB` is accepted); a bare number is bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimit(pub u64);
+
+impl FromStr for MemoryLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(idx) => s.split_at(idx),
+            None => (s, ""),
+        };
+        let value: u64 = number
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid memory limit '{}', expected e.g. 8G or 512M", s))?;
+        let shift = match unit
+            .to_ascii_uppercase()
+            .trim_end_matches("IB")
+            .trim_end_matches('B')
+        {
+            "" => 0,
+            "K" => 10,
+            "M" => 20,
+            "G" => 30,
+            "T" => 40,
+            _ => {
+                return Err(format!(
+                    "unknown memory limit unit '{}', expected K, M, G or T",
+                    unit
+                ))
+            }
+        };
+        let bytes = value
+            .checked_mul(1 << shift)
+            .ok_or_else(|| format!("memory limit '{}' is too large", s))?;
+        if bytes == 0 {
+            return Err("memory limit must be positive".to_string());
+        }
+        Ok(MemoryLimit(bytes))
+    }
+}
+
+impl fmt::Display for MemoryLimit {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        for (shift, unit) in [(40, "T"), (30, "G"), (20, "M"), (10, "K")] {
+            if self.0.is_multiple_of(1 << shift) {
+                return write!(f, "{}{}", self.0 >> shift, unit);
+            }
+        }
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A cgroup v2 group with `memory.max` set, holding a locally managed server so it is
+/// OOM-killed at the limit instead of growing into swap or starving the benchmark client.
+#[derive(Debug, Clone)]
+pub struct ServerCgroup {
+    path: PathBuf,
+    limit: MemoryLimit,
+    /// `oom_kill` already counted when the group was (re)used, so reports only cover this run.
+    oom_kills_before: u64,
+}
+
+impl ServerCgroup {
+    /// Create (or reuse) `<cgroup root>/falkordb-benchmark-<vendor>` with the given limit. Swap
+    /// is disabled for the group so the ceiling is a hard one.
+    pub async fn create(
+        vendor: impl fmt::Display,
+        limit: MemoryLimit,
+    ) -> BenchmarkResult<Self> {
+        let root =
+            env::var("BENCHMARK_CGROUP_ROOT").unwrap_or_else(|_| DEFAULT_CGROUP_ROOT.to_string());
+        let root = Path::new(&root);
+        if fs::metadata(root.join("cgroup.controllers")).await.is_err() {
+            return Err(OtherError(format!(
+                "--server-mem-limit needs a cgroup v2 hierarchy at {} (set BENCHMARK_CGROUP_ROOT)",
+                root.display()
+            )));
+        }
+        // Usually already enabled; only matters for a freshly delegated subtree.
+        let _ = fs::write(root.join("cgroup.subtree_control"), "+memory").await;
+
+        let path = root.join(format!("falkordb-benchmark-{}", vendor));
+        fs::create_dir_all(&path).await.map_err(|e| {
+            OtherError(format!(
+                "failed to create cgroup {}: {} (needs root or a delegated cgroup)",
+                path.display(),
+                e
+            ))
+        })?;
+        fs::write(path.join("memory.max"), limit.0.to_string())
+            .await
+            .map_err(|e| {
+                OtherError(format!(
+                    "failed to set memory.max on {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        if let Err(e) = fs::write(path.join("memory.swap.max"), "0").await {
+            warn!("could not disable swap for {}: {}", path.display(), e);
+        }
+
+        let mut cgroup = ServerCgroup {
+            path,
+            limit,
+            oom_kills_before: 0,
+        };
+        cgroup.oom_kills_before = cgroup.oom_kills().await.unwrap_or_default();
+        info!(
+            "servers will run in cgroup {} with memory.max={}",
+            cgroup.path.display(),
+            limit
+        );
+        Ok(cgroup)
+    }
+
+    /// Launch `command` (program and leading arguments) inside this cgroup.
+    fn wrap(
+        &self,
+        (program, args): (String, Vec<String>),
+    ) -> (String, Vec<String>) {
+        let mut wrapped = vec![
+            "-c".to_string(),
+            JOIN_CGROUP_SCRIPT.to_string(),
+            self.path.join("cgroup.procs").display().to_string(),
+            program,
+        ];
+        wrapped.extend(args);
+        ("sh".to_string(), wrapped)
+    }
+
+    async fn oom_kills(&self) -> BenchmarkResult<u64> {
+        let events = fs::read_to_string(self.path.join("memory.events")).await?;
+        Ok(parse_oom_kills(&events))
+    }
+
+    /// Limit, OOM kills since [`ServerCgroup::create`] and peak usage (if the kernel reports it).
+    pub async fn report(&self) -> ServerMemoryReport {
+        let oom_kills = match self.oom_kills().await {
+            Ok(total) => total.saturating_sub(self.oom_kills_before),
+            Err(e) => {
+                warn!(
+                    "failed to read OOM kills for {}: {}",
+                    self.path.display(),
+                    e
+                );
+                0
+            }
+        };
+        let peak_bytes = fs::read_to_string(self.path.join("memory.peak"))
+            .await
+            .ok()
+            .and_then(|peak| peak.trim().parse().ok());
+        ServerMemoryReport {
+            limit: self.limit.to_string(),
+            limit_bytes: self.limit.0,
+            cgroup: self.path.display().to_string(),
+            oom_kills,
+            peak_bytes,
+        }
+    }
+}
+
+/// `--cpus`-style core list, e.g. `0-7` or `0,2,4-6`, as accepted by `taskset -c`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuSet(Vec<usize>);
+
+impl CpuSet {
+    pub fn overlaps(
+        &self,
+        other: &CpuSet,
+    ) -> bool {
+        self.0.iter().any(|cpu| other.0.contains(cpu))
+    }
+}
+
+impl FromStr for CpuSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid cpu list '{}', expected e.g. 0-7 or 0,2,4-6", s);
+        let mut cpus = Vec::new();
+        for part in s.split(',').map(str::trim) {
+            let (first, last) = part.split_once('-').unwrap_or((part, part));
+            let first: usize = first.trim().parse().map_err(|_| invalid())?;
+            let last: usize = last.trim().parse().map_err(|_| invalid())?;
+            if first > last {
+                return Err(invalid());
+            }
+            cpus.extend(first..=last);
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Ok(CpuSet(cpus))
+    }
+}
+
+impl fmt::Display for CpuSet {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for &cpu in &self.0 {
+            match ranges.last_mut() {
+                Some((_, last)) if *last + 1 == cpu => *last = cpu,
+                _ => ranges.push((cpu, cpu)),
+            }
+        }
+        let ranges: Vec<String> = ranges
+            .into_iter()
+            .map(|(first, last)| {
+                if first == last {
+                    first.to_string()
+                } else {
+                    format!("{}-{}", first, last)
+                }
+            })
+            .collect();
+        write!(f, "{}", ranges.join(","))
+    }
+}
+
+/// Pin every thread of the benchmark process itself to `cpus` (`--client-cpus`). Threads
+/// started later inherit the affinity.
+pub async fn pin_benchmark_process(cpus: &CpuSet) -> BenchmarkResult<()> {
+    let cpus = cpus.to_string();
+    let pid = std::process::id().to_string();
+    spawn_command("taskset", &["-a", "-p", "-c", cpus.as_str(), pid.as_str()]).await?;
+    info!("benchmark client pinned to cpus {}", cpus);
+    Ok(())
+}
+
+/// How locally managed servers are launched: inside a memory-limited cgroup
+/// (`--server-mem-limit`) and/or pinned to a core set (`--server-cpus`).
+#[derive(Debug, Clone, Default)]
+pub struct ServerLimits {
+    pub cgroup: Option<ServerCgroup>,
+    pub cpus: Option<CpuSet>,
+}
+
+impl ServerLimits {
+    /// Program and leading arguments for starting `program` under these limits; append the
+    /// server's own arguments after them.
+    pub fn command(
+        &self,
+        program: &str,
+    ) -> (String, Vec<String>) {
+        let mut command = (program.to_string(), Vec::new());
+        if let Some(cpus) = &self.cpus {
+            command = (
+                "taskset".to_string(),
+                vec!["-c".to_string(), cpus.to_string(), program.to_string()],
+            );
+        }
+        match &self.cgroup {
+            Some(cgroup) => cgroup.wrap(command),
+            None => command,
+        }
+    }
+
+    pub async fn memory_report(&self) -> Option<ServerMemoryReport> {
+        Some(self.cgroup.as_ref()?.report().await)
+    }
+}
+
+/// `--server-cpus`/`--client-cpus`; recorded in meta.json.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuLayout {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_cpus: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cpus: Option<String>,
+}
+
+impl CpuLayout {
+    /// `None` when neither side is pinned.
+    pub fn new(
+        server_cpus: Option<&CpuSet>,
+        client_cpus: Option<&CpuSet>,
+    ) -> Option<Self> {
+        if server_cpus.is_none() && client_cpus.is_none() {
+            return None;
+        }
+        Some(CpuLayout {
+            server_cpus: server_cpus.map(CpuSet::to_string),
+            client_cpus: client_cpus.map(CpuSet::to_string),
+        })
+    }
+}
+
+/// `--server-mem-limit` outcome; recorded in meta.json.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerMemoryReport {
+    pub limit: String,
+    pub limit_bytes: u64,
+    pub cgroup: String,
+    pub oom_kills: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_bytes: Option<u64>,
+}
+
+fn parse_oom_kills(memory_events: &str) -> u64 {
+    memory_events
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .find(|(key, _)| *key == "oom_kill")
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_memory_limit() {
+        assert_eq!("8G".parse::<MemoryLimit>().unwrap().0, 8 << 30);
+        assert_eq!("512MiB".parse::<MemoryLimit>().unwrap().0, 512 << 20);
+        assert_eq!("64kb".parse::<MemoryLimit>().unwrap().0, 64 << 10);
+        assert_eq!("4096".parse::<MemoryLimit>().unwrap().0, 4096);
+        assert!("0G".parse::<MemoryLimit>().is_err());
+        assert!("8X".parse::<MemoryLimit>().is_err());
+        assert!("G".parse::<MemoryLimit>().is_err());
+
+        assert_eq!(MemoryLimit(8 << 30).to_string(), "8G");
+        assert_eq!(MemoryLimit(1536 << 20).to_string(), "1536M");
+        assert_eq!(MemoryLimit(1000).to_string(), "1000");
+    }
+
+    #[test]
+    fn parses_cpu_lists_and_wraps_server_command() {
+        let server: CpuSet = "0-3, 6,5".parse().unwrap();
+        assert_eq!(server.to_string(), "0-3,5-6");
+        let client: CpuSet = "8-15".parse().unwrap();
+        assert!(!server.overlaps(&client));
+        assert!(server.overlaps(&"3".parse().unwrap()));
+        assert!("3-1".parse::<CpuSet>().is_err());
+        assert!("a".parse::<CpuSet>().is_err());
+
+        let limits = ServerLimits {
+            cgroup: Some(ServerCgroup {
+                path: PathBuf::from("/sys/fs/cgroup/test"),
+                limit: MemoryLimit(1 << 30),
+                oom_kills_before: 0,
+            }),
+            cpus: Some(server),
+        };
+        let (program, args) = limits.command("redis-server");
+        assert_eq!(program, "sh");
+        assert_eq!(
+            args[2..],
+            [
+                "/sys/fs/cgroup/test/cgroup.procs",
+                "taskset",
+                "-c",
+                "0-3,5-6",
+                "redis-server"
+            ]
+        );
+        assert_eq!(
+            ServerLimits::default().command("redis-server"),
+            ("redis-server".to_string(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn reads_oom_kill_count_from_memory_events() {
+        let events = "low 0\nhigh 0\nmax 12\noom 3\noom_kill 2\noom_group_kill 0\n";
+        assert_eq!(parse_oom_kills(events), 2);
+        assert_eq!(parse_oom_kills("low 0\n"), 0);
+    }
+}
@@ -0,0 +1,378 @@
+//! `resources.csv`: CPU, memory and disk I/O of the vendor's server and CPU, memory and network
+//! throughput of the whole machine, sampled every [`METRICS_REPORT_INTERVAL`] of the measured
+//! phase. The Prometheus gauges only keep the last sample, so this is what shows how utilization
+//! moved during the run; `aggregate` turns it into the average and maximum of each column.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::prometheus_metrics::METRICS_REPORT_INTERVAL;
+use crate::scenario::Vendor;
+use crate::{
+    FALKOR_CPU_USAGE_GAUGE, FALKOR_PROCESS_MEMORY_BYTES, FALKOR_REPORTED_MEMORY_BYTES,
+    MEMGRAPH_CPU_USAGE_GAUGE, MEMGRAPH_PROCESS_MEMORY_BYTES, MEMGRAPH_REPORTED_MEMORY_BYTES,
+    NEO4J_CPU_USAGE_GAUGE, NEO4J_PROCESS_MEMORY_BYTES, NEO4J_REPORTED_MEMORY_BYTES,
+    NETWORK_RECEIVED_BYTES_TOTAL, NETWORK_TRANSMITTED_BYTES_TOTAL, PROCESS_DISK_READ_BYTES_TOTAL,
+    PROCESS_DISK_WRITTEN_BYTES_TOTAL,
+};
+use prometheus::IntGauge;
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use sysinfo::System;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+
+pub const RESOURCES_FILE: &str = "resources.csv";
+
+const HEADER: &str = "offset_ms,server_cpu_pct,server_memory_bytes,system_cpu_pct,\
+system_memory_bytes,server_disk_read_bytes_per_sec,server_disk_written_bytes_per_sec,\
+network_received_bytes_per_sec,network_transmitted_bytes_per_sec";
+
+/// One row of `resources.csv`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceSample {
+    /// Into the measured phase.
+    pub offset_ms: u64,
+    /// The server process, in percent of all cores; 0 for an external endpoint, which has no
+    /// local process.
+    pub server_cpu_pct: f64,
+    /// What the server reports through its query interface, else its RSS.
+    pub server_memory_bytes: u64,
+    pub system_cpu_pct: f64,
+    pub system_memory_bytes: u64,
+    /// Since the previous sample, like the network columns. Only a managed server process is
+    /// measured; 0 for external endpoints and attached servers.
+    pub server_disk_read_bytes_per_sec: u64,
+    pub server_disk_written_bytes_per_sec: u64,
+    /// All interfaces of the machine, loopback included.
+    pub network_received_bytes_per_sec: u64,
+    pub network_transmitted_bytes_per_sec: u64,
+}
+
+/// The samples of one run, in order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceSeries(Vec<ResourceSample>);
+
+impl ResourceSeries {
+    pub fn samples(&self) -> &[ResourceSample] {
+        &self.0
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", HEADER);
+        for sample in &self.0 {
+            let _ = writeln!(
+                csv,
+                "{},{:.1},{},{:.1},{},{},{},{},{}",
+                sample.offset_ms,
+                sample.server_cpu_pct,
+                sample.server_memory_bytes,
+                sample.system_cpu_pct,
+                sample.system_memory_bytes,
+                sample.server_disk_read_bytes_per_sec,
+                sample.server_disk_written_bytes_per_sec,
+                sample.network_received_bytes_per_sec,
+                sample.network_transmitted_bytes_per_sec
+            );
+        }
+        csv
+    }
+
+    pub fn from_csv(text: &str) -> BenchmarkResult<Self> {
+        let mut samples = Vec::new();
+        for (i, line) in text.lines().enumerate().skip(1) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = || {
+                OtherError(format!(
+                    "invalid {} line {}: '{}'",
+                    RESOURCES_FILE,
+                    i + 1,
+                    line
+                ))
+            };
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            // Files written before the I/O columns were added end after system_memory_bytes.
+            if fields.len() != 5 && fields.len() != 9 {
+                return Err(invalid());
+            }
+            let field = |i: usize| fields.get(i).copied().unwrap_or("0");
+            samples.push(ResourceSample {
+                offset_ms: field(0).parse().map_err(|_| invalid())?,
+                server_cpu_pct: field(1).parse().map_err(|_| invalid())?,
+                server_memory_bytes: field(2).parse().map_err(|_| invalid())?,
+                system_cpu_pct: field(3).parse().map_err(|_| invalid())?,
+                system_memory_bytes: field(4).parse().map_err(|_| invalid())?,
+                server_disk_read_bytes_per_sec: field(5).parse().map_err(|_| invalid())?,
+                server_disk_written_bytes_per_sec: field(6).parse().map_err(|_| invalid())?,
+                network_received_bytes_per_sec: field(7).parse().map_err(|_| invalid())?,
+                network_transmitted_bytes_per_sec: field(8).parse().map_err(|_| invalid())?,
+            });
+        }
+        Ok(ResourceSeries(samples))
+    }
+
+    /// `<run_dir>/resources.csv`; `None` for results written before it was kept.
+    pub fn read(run_dir: &Path) -> BenchmarkResult<Option<Self>> {
+        let path = run_dir.join(RESOURCES_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| OtherError(format!("Failed reading {}: {}", path.display(), e)))?;
+        ResourceSeries::from_csv(&text).map(Some)
+    }
+
+    /// Average and maximum of every column; `None` without samples, e.g. for a run shorter
+    /// than one interval.
+    pub fn summary(&self) -> Option<ResourceSummary> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let usage = |value: fn(&ResourceSample) -> f64| ResourceUsage {
+            avg: self.0.iter().map(value).sum::<f64>() / self.0.len() as f64,
+            max: self.0.iter().map(value).fold(0.0, f64::max),
+        };
+        Some(ResourceSummary {
+            server_cpu_pct: usage(|s| s.server_cpu_pct),
+            server_memory_bytes: usage(|s| s.server_memory_bytes as f64),
+            system_cpu_pct: usage(|s| s.system_cpu_pct),
+            system_memory_bytes: usage(|s| s.system_memory_bytes as f64),
+            server_disk_read_bytes_per_sec: usage(|s| s.server_disk_read_bytes_per_sec as f64),
+            server_disk_written_bytes_per_sec: usage(|s| {
+                s.server_disk_written_bytes_per_sec as f64
+            }),
+            network_received_bytes_per_sec: usage(|s| s.network_received_bytes_per_sec as f64),
+            network_transmitted_bytes_per_sec: usage(|s| {
+                s.network_transmitted_bytes_per_sec as f64
+            }),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ResourceUsage {
+    pub avg: f64,
+    pub max: f64,
+}
+
+/// Utilization over the run window, in the UI summaries as `resources`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ResourceSummary {
+    pub server_cpu_pct: ResourceUsage,
+    pub server_memory_bytes: ResourceUsage,
+    pub system_cpu_pct: ResourceUsage,
+    pub system_memory_bytes: ResourceUsage,
+    pub server_disk_read_bytes_per_sec: ResourceUsage,
+    pub server_disk_written_bytes_per_sec: ResourceUsage,
+    pub network_received_bytes_per_sec: ResourceUsage,
+    pub network_transmitted_bytes_per_sec: ResourceUsage,
+}
+
+/// The gauges the vendor's process monitor, attached-server watcher or endpoint poller keep
+/// current: CPU, reported memory and process memory.
+fn server_gauges(vendor: Vendor) -> [&'static IntGauge; 3] {
+    match vendor {
+        Vendor::Falkor => [
+            &FALKOR_CPU_USAGE_GAUGE,
+            &FALKOR_REPORTED_MEMORY_BYTES,
+            &FALKOR_PROCESS_MEMORY_BYTES,
+        ],
+        Vendor::Neo4j => [
+            &NEO4J_CPU_USAGE_GAUGE,
+            &NEO4J_REPORTED_MEMORY_BYTES,
+            &NEO4J_PROCESS_MEMORY_BYTES,
+        ],
+        Vendor::Memgraph => [
+            &MEMGRAPH_CPU_USAGE_GAUGE,
+            &MEMGRAPH_REPORTED_MEMORY_BYTES,
+            &MEMGRAPH_PROCESS_MEMORY_BYTES,
+        ],
+    }
+}
+
+/// The I/O counters the process metrics reporters advance, read at one instant.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct IoTotals {
+    disk_read: u64,
+    disk_written: u64,
+    network_received: u64,
+    network_transmitted: u64,
+}
+
+impl IoTotals {
+    fn read(vendor: Vendor) -> Self {
+        let label = [vendor.metric_label()];
+        IoTotals {
+            disk_read: PROCESS_DISK_READ_BYTES_TOTAL
+                .with_label_values(&label)
+                .get(),
+            disk_written: PROCESS_DISK_WRITTEN_BYTES_TOTAL
+                .with_label_values(&label)
+                .get(),
+            network_received: NETWORK_RECEIVED_BYTES_TOTAL.get(),
+            network_transmitted: NETWORK_TRANSMITTED_BYTES_TOTAL.get(),
+        }
+    }
+
+    /// What each counter moved per second since `earlier`.
+    fn per_sec(
+        &self,
+        earlier: &IoTotals,
+        elapsed: Duration,
+    ) -> IoTotals {
+        let secs = elapsed.as_secs_f64();
+        let rate = |now: u64, then: u64| {
+            if secs > 0.0 {
+                (now.saturating_sub(then) as f64 / secs).round() as u64
+            } else {
+                0
+            }
+        };
+        IoTotals {
+            disk_read: rate(self.disk_read, earlier.disk_read),
+            disk_written: rate(self.disk_written, earlier.disk_written),
+            network_received: rate(self.network_received, earlier.network_received),
+            network_transmitted: rate(self.network_transmitted, earlier.network_transmitted),
+        }
+    }
+}
+
+/// Samples the machine and the vendor's gauges and I/O counters until [`ResourceSampler::stop`].
+pub struct ResourceSampler {
+    samples: Arc<Mutex<Vec<ResourceSample>>>,
+    handle: JoinHandle<()>,
+    shutdown_tx: oneshot::Sender<()>,
+}
+
+impl ResourceSampler {
+    pub fn spawn(vendor: Vendor) -> Self {
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+        let handle = tokio::spawn({
+            let samples = samples.clone();
+            async move {
+                let start = Instant::now();
+                let [cpu, reported_memory, process_memory] = server_gauges(vendor);
+                let mut system = System::new();
+                let mut last_io = (start, IoTotals::read(vendor));
+                // CPU usage is measured between two refreshes.
+                system.refresh_cpu_usage();
+                let mut ticker = tokio::time::interval(METRICS_REPORT_INTERVAL);
+                ticker.tick().await;
+                loop {
+                    let stopping = tokio::select! {
+                        _ = ticker.tick() => false,
+                        _ = &mut shutdown_rx => true,
+                    };
+                    system.refresh_cpu_usage();
+                    system.refresh_memory();
+                    let server_memory = match reported_memory.get() {
+                        0 => process_memory.get(),
+                        reported => reported,
+                    };
+                    let now = Instant::now();
+                    let io = IoTotals::read(vendor);
+                    let io_rates = io.per_sec(&last_io.1, now - last_io.0);
+                    last_io = (now, io);
+                    samples.lock().unwrap().push(ResourceSample {
+                        offset_ms: start.elapsed().as_millis() as u64,
+                        server_cpu_pct: cpu.get().max(0) as f64,
+                        server_memory_bytes: server_memory.max(0) as u64,
+                        system_cpu_pct: f64::from(system.global_cpu_usage()),
+                        system_memory_bytes: system.used_memory(),
+                        server_disk_read_bytes_per_sec: io_rates.disk_read,
+                        server_disk_written_bytes_per_sec: io_rates.disk_written,
+                        network_received_bytes_per_sec: io_rates.network_received,
+                        network_transmitted_bytes_per_sec: io_rates.network_transmitted,
+                    });
+                    if stopping {
+                        return;
+                    }
+                }
+            }
+        });
+        ResourceSampler {
+            samples,
+            handle,
+            shutdown_tx,
+        }
+    }
+
+    /// Take a last sample at the end of the run and return them all.
+    pub async fn stop(self) -> ResourceSeries {
+        drop(self.shutdown_tx);
+        let _ = self.handle.await;
+        let samples = std::mem::take(&mut *self.samples.lock().unwrap());
+        ResourceSeries(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_the_csv_and_summarizes_the_window() {
+        let sample = |offset_ms, server_cpu_pct, system_memory_bytes| ResourceSample {
+            offset_ms,
+            server_cpu_pct,
+            server_memory_bytes: 1 << 30,
+            system_cpu_pct: 50.0,
+            system_memory_bytes,
+            server_disk_read_bytes_per_sec: system_memory_bytes / 2,
+            server_disk_written_bytes_per_sec: 0,
+            network_received_bytes_per_sec: 1_000,
+            network_transmitted_bytes_per_sec: 2_000,
+        };
+        let series = ResourceSeries(vec![
+            sample(5_000, 20.0, 4_000),
+            sample(10_000, 60.0, 8_000),
+            sample(12_300, 40.0, 6_000),
+        ]);
+        let csv = series.to_csv();
+        assert!(csv.starts_with("offset_ms,server_cpu_pct,"));
+        assert_eq!(ResourceSeries::from_csv(&csv).unwrap(), series);
+        assert!(ResourceSeries::from_csv("header\n1,2,3\n").is_err());
+
+        let summary = series.summary().unwrap();
+        assert_eq!(
+            summary.server_cpu_pct,
+            ResourceUsage {
+                avg: 40.0,
+                max: 60.0
+            }
+        );
+        assert_eq!(summary.system_memory_bytes.max, 8_000.0);
+        assert_eq!(summary.server_memory_bytes.avg, (1u64 << 30) as f64);
+        assert_eq!(summary.server_disk_read_bytes_per_sec.max, 4_000.0);
+        assert_eq!(ResourceSeries::default().summary(), None);
+    }
+
+    #[test]
+    fn io_counters_turn_into_rates_and_older_files_still_read() {
+        let totals = |disk_read, network_received| IoTotals {
+            disk_read,
+            network_received,
+            ..IoTotals::default()
+        };
+        let rates = totals(30_000, 500).per_sec(&totals(10_000, 0), Duration::from_secs(4));
+        assert_eq!(rates, totals(5_000, 125));
+        // A restarted server's counters start over; no negative rates.
+        assert_eq!(
+            totals(100, 0).per_sec(&totals(900, 0), Duration::from_secs(1)),
+            IoTotals::default()
+        );
+
+        let old =
+            "offset_ms,server_cpu_pct,server_memory_bytes,system_cpu_pct,system_memory_bytes\n\
+                   5000,12.5,1024,30.0,2048\n";
+        let series = ResourceSeries::from_csv(old).unwrap();
+        assert_eq!(series.samples()[0].system_memory_bytes, 2048);
+        assert_eq!(series.samples()[0].network_received_bytes_per_sec, 0);
+    }
+}
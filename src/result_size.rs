@@ -0,0 +1,75 @@
+//! Latency by how many rows a query returns. An empty result and a thousand-row result are
+//! different work: a percentile over both hides where engines differ, an engine that gives up
+//! quickly on empty paths looking fast next to one that streams large results. The drivers
+//! count the rows of every successful query into `query_result_rows`, and the aggregator sorts
+//! each query into a bin by its mean row count over the run and merges the latency histograms of
+//! the queries in each bin.
+
+use crate::QUERY_RESULT_ROWS_HISTOGRAM;
+use std::fmt;
+
+/// The upper bounds of the `query_result_rows` buckets, which are the bin boundaries.
+pub const ROW_BUCKETS: [f64; 3] = [0.0, 10.0, 1000.0];
+
+/// A bin of result sizes, by a query's mean rows per call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResultSize {
+    Empty,
+    Few,
+    Many,
+    Large,
+}
+
+impl ResultSize {
+    pub fn of_mean(rows: f64) -> Self {
+        match rows {
+            rows if rows <= ROW_BUCKETS[0] => ResultSize::Empty,
+            rows if rows <= ROW_BUCKETS[1] => ResultSize::Few,
+            rows if rows <= ROW_BUCKETS[2] => ResultSize::Many,
+            _ => ResultSize::Large,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ResultSize::Empty => "0",
+            ResultSize::Few => "1-10",
+            ResultSize::Many => "11-1000",
+            ResultSize::Large => ">1000",
+        }
+    }
+}
+
+impl fmt::Display for ResultSize {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Count the `rows` a successful call of `q_name` returned.
+pub fn observe(
+    vendor: &str,
+    q_name: &str,
+    rows: u64,
+) {
+    QUERY_RESULT_ROWS_HISTOGRAM
+        .with_label_values(&[vendor, q_name])
+        .observe(rows as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_rows_fall_into_the_bins() {
+        let bins = [0.0, 0.2, 10.0, 10.5, 1000.0, 4000.0].map(ResultSize::of_mean);
+        assert_eq!(
+            bins.map(ResultSize::as_str),
+            ["0", "1-10", "1-10", "11-1000", "11-1000", ">1000"]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Where `run` writes its results: `<results-dir>/<results-template>`. The template defaults to
+//! `{vendor}`, the historical per-vendor layout, and can name the run directory after the
+//! vendor, dataset, rate, parallelism, timestamp and `--run-id` instead. Without
+//! `--results-dir`, the results go to `Results-<run-id>`, and the run id defaults to a UTC
+//! timestamp like `261017-091500`: no colons, which some filesystems and tools reject. The
+//! location is resolved once, so the child processes of `--repeat`, `--sweep` and `--slo-p99`
+//! write where their parent does. A template with tokens must include `{vendor}`, and a run
+//! refuses a directory whose meta.json is another vendor's. The aggregator finds each vendor's runs by their meta.json,
+//! whatever the layout.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::scenario::{Size, Vendor};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+pub const DEFAULT_RESULTS_TEMPLATE: &str = "{vendor}";
+
+const TOKENS: [&str; 6] = [
+    "vendor",
+    "dataset",
+    "mps",
+    "parallel",
+    "timestamp",
+    "run_id",
+];
+
+/// Now in UTC as `YYMMDD-HHMMSS`, for default directory names.
+pub fn timestamp() -> String {
+    use time::macros::format_description;
+
+    let fmt = format_description!(
+        "[year repr:last_two][month padding:zero][day padding:zero]-[hour padding:zero][minute padding:zero][second padding:zero]"
+    );
+    time::OffsetDateTime::now_utc()
+        .format(&fmt)
+        .unwrap_or_else(|_| "000000-000000".to_string())
+}
+
+/// `--run-id`: letters, digits, `-`, `_` and `.`, so it can name a directory anywhere.
+pub fn parse_run_id(s: &str) -> Result<String, String> {
+    if s.is_empty()
+        || s.starts_with('.')
+        || !s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(format!(
+            "invalid run id '{}', expected letters, digits, '-', '_' or '.'",
+            s
+        ));
+    }
+    Ok(s.to_string())
+}
+
+/// `--results-template`: a path relative to `--results-dir` with `{token}` placeholders.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultsTemplate(String);
+
+impl FromStr for ResultsTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = Path::new(s);
+        if s.is_empty()
+            || path
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return Err(format!(
+                "results template '{}' must be a relative path inside --results-dir",
+                s
+            ));
+        }
+        let mut rest = s;
+        while let Some(open) = rest.find('{') {
+            let Some(close) = rest[open..].find('}') else {
+                return Err(format!("unclosed '{{' in results template '{}'", s));
+            };
+            let token = &rest[open + 1..open + close];
+            if !TOKENS.contains(&token) {
+                return Err(format!(
+                    "unknown token {{{}}} in results template '{}', expected one of {{{}}}",
+                    token,
+                    s,
+                    TOKENS.join("}, {")
+                ));
+            }
+            rest = &rest[open + close + 1..];
+        }
+        if rest.contains('}') {
+            return Err(format!("unmatched '}}' in results template '{}'", s));
+        }
+        // Without it, every vendor's run would write to the same directory. A template with no
+        // tokens at all is one a parent `run` already expanded for its children.
+        if s.contains('{') && !s.contains("{vendor}") {
+            return Err(format!(
+                "results template '{}' must contain {{vendor}} so vendors don't overwrite each other",
+                s
+            ));
+        }
+        Ok(ResultsTemplate(s.to_string()))
+    }
+}
+
+impl fmt::Display for ResultsTemplate {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Refuse to write `vendor`'s results into `dir` when it holds another vendor's meta.json.
+pub fn check_run_dir(
+    dir: &Path,
+    vendor: Vendor,
+) -> BenchmarkResult<()> {
+    let Ok(text) = std::fs::read_to_string(dir.join("meta.json")) else {
+        return Ok(());
+    };
+    let found = serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|meta| meta.get("vendor")?.as_str().map(str::to_string));
+    match found {
+        Some(found) if found != vendor.to_string() => Err(OtherError(format!(
+            "{} already holds {} results; pick a --results-template with {{vendor}} or another --results-dir",
+            dir.display(),
+            found
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// What a run's directory can be named after, besides its id and timestamp.
+#[derive(Debug, Clone, Copy)]
+pub struct RunNaming {
+    pub vendor: Vendor,
+    pub dataset: Size,
+    pub mps: usize,
+    pub parallel: usize,
+}
+
+/// Where one `run` writes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunLocation {
+    pub results_dir: String,
+    pub run_id: String,
+    /// The expanded template.
+    pub run_path: String,
+}
+
+impl RunLocation {
+    pub fn resolve(
+        results_dir: Option<String>,
+        run_id: Option<String>,
+        template: &ResultsTemplate,
+        naming: RunNaming,
+    ) -> Self {
+        let timestamp = timestamp();
+        let run_id = run_id.unwrap_or_else(|| timestamp.clone());
+        let mut run_path = template.0.clone();
+        for (token, value) in [
+            ("vendor", naming.vendor.to_string()),
+            ("dataset", naming.dataset.to_string()),
+            ("mps", naming.mps.to_string()),
+            ("parallel", naming.parallel.to_string()),
+            ("timestamp", timestamp),
+            ("run_id", run_id.clone()),
+        ] {
+            run_path = run_path.replace(&format!("{{{}}}", token), &value);
+        }
+        RunLocation {
+            results_dir: results_dir.unwrap_or_else(|| format!("Results-{}", run_id)),
+            run_id,
+            run_path,
+        }
+    }
+
+    /// `<results-dir>/<expanded template>`: the vendor directory of the default layout, which
+    /// repetitions, clients and probes go under.
+    pub fn dir(&self) -> PathBuf {
+        PathBuf::from(&self.results_dir).join(&self.run_path)
+    }
+
+    /// The expanded template as a template of its own; a child `run` given it with this
+    /// results directory and run id writes here.
+    pub fn child_template(&self) -> ResultsTemplate {
+        ResultsTemplate(self.run_path.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_expand_their_tokens_under_the_results_dir() {
+        let naming = RunNaming {
+            vendor: Vendor::Falkor,
+            dataset: Size::Small,
+            mps: 4000,
+            parallel: 40,
+        };
+        let template: ResultsTemplate = "{dataset}/{vendor}-{mps}mps-p{parallel}-{run_id}"
+            .parse()
+            .unwrap();
+        let location = RunLocation::resolve(None, Some("nightly".to_string()), &template, naming);
+        assert_eq!(
+            location.dir(),
+            PathBuf::from("Results-nightly/small/falkor-4000mps-p40-nightly")
+        );
+        assert_eq!(
+            RunLocation::resolve(
+                Some(location.results_dir.clone()),
+                None,
+                &location.child_template(),
+                naming
+            )
+            .dir(),
+            location.dir()
+        );
+
+        let default: ResultsTemplate = DEFAULT_RESULTS_TEMPLATE.parse().unwrap();
+        let location = RunLocation::resolve(None, None, &default, naming);
+        assert!(!location.results_dir.contains(':'));
+        assert_eq!(location.dir().file_name().unwrap(), "falkor");
+
+        let shared: Result<ResultsTemplate, _> = "{dataset}-{mps}".parse();
+        assert!(shared.unwrap_err().contains("{vendor}"));
+        // The expanded template children get has no tokens left.
+        assert!("small/run-4000".parse::<ResultsTemplate>().is_ok());
+
+        let dir = std::env::temp_dir().join(format!("results-layout-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(check_run_dir(&dir, Vendor::Neo4j).is_ok());
+        std::fs::write(dir.join("meta.json"), r#"{"vendor":"falkor"}"#).unwrap();
+        assert!(check_run_dir(&dir, Vendor::Falkor).is_ok());
+        assert!(check_run_dir(&dir, Vendor::Neo4j).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        for bad in [
+            "{host}",
+            "/tmp/{vendor}",
+            "../{vendor}",
+            "{vendor",
+            "vendor}",
+            "",
+        ] {
+            assert!(bad.parse::<ResultsTemplate>().is_err(), "{}", bad);
+        }
+        assert!(parse_run_id("2026-10-17_a").is_ok());
+        assert!(parse_run_id("a:b").is_err());
+    }
+}
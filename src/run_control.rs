@@ -0,0 +1,377 @@
+//! Remote control of a `run` (`--control-api`): a coordinator starts the measured phase,
+//! changes the target rate live and stops the run early through the metrics endpoint, so
+//! several client machines can be driven as one distributed load test.
+
+use crate::circuit_breaker::{CircuitBreaker, CircuitState, ErrorBackoff};
+use crate::error_budget::{ErrorBudget, RunAbort};
+use crate::phases::{PhaseBoundary, Phases};
+use crate::scheduler::{Dispatch, OverloadPolicy, OverloadReport};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+/// A control request that reached the run, recorded in meta.json.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ControlEvent {
+    pub at_epoch_ms: u64,
+    /// `start`, `stop`, `abort` or `mps=<n>`.
+    pub action: String,
+}
+
+#[derive(Debug)]
+pub struct RunControl {
+    mps: AtomicUsize,
+    duration: Option<Duration>,
+    error_budget: Option<ErrorBudget>,
+    error_backoff: ErrorBackoff,
+    circuit_breaker: Option<CircuitBreaker>,
+    circuit: CircuitState,
+    aborted: Mutex<Option<RunAbort>>,
+    overload_policy: OverloadPolicy,
+    dropped: AtomicU64,
+    dispatch: Dispatch,
+    phases: Option<Phases>,
+    read_only: bool,
+    verify_metrics: bool,
+    phase_boundaries: Mutex<Vec<PhaseBoundary>>,
+    started: AtomicBool,
+    stopped: AtomicBool,
+    notify: Notify,
+    events: Mutex<Vec<ControlEvent>>,
+}
+
+impl RunControl {
+    pub fn new(mps: usize) -> Self {
+        RunControl {
+            mps: AtomicUsize::new(mps),
+            duration: None,
+            error_budget: None,
+            error_backoff: ErrorBackoff::default(),
+            circuit_breaker: None,
+            circuit: CircuitState::default(),
+            aborted: Mutex::new(None),
+            overload_policy: OverloadPolicy::default(),
+            dropped: AtomicU64::new(0),
+            dispatch: Dispatch::default(),
+            phases: None,
+            read_only: false,
+            verify_metrics: false,
+            phase_boundaries: Mutex::new(Vec::new()),
+            started: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            notify: Notify::new(),
+            events: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// `run --duration`: stop scheduling once the schedule reaches `duration`.
+    pub fn with_duration(
+        mut self,
+        duration: Option<Duration>,
+    ) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// `run --abort-on-error-rate`: abort the run once its error rate exceeds `budget`.
+    pub fn with_error_budget(
+        mut self,
+        budget: Option<ErrorBudget>,
+    ) -> Self {
+        self.error_budget = budget;
+        self
+    }
+
+    pub fn error_budget(&self) -> Option<ErrorBudget> {
+        self.error_budget
+    }
+
+    /// `run --error-backoff`: how long a worker waits after failed queries in a row.
+    pub fn with_error_backoff(
+        mut self,
+        backoff: ErrorBackoff,
+    ) -> Self {
+        self.error_backoff = backoff;
+        self
+    }
+
+    pub fn error_backoff(&self) -> ErrorBackoff {
+        self.error_backoff
+    }
+
+    /// `run --circuit-breaker`: pause dispatch while the error rate spikes.
+    pub fn with_circuit_breaker(
+        mut self,
+        breaker: Option<CircuitBreaker>,
+    ) -> Self {
+        self.circuit_breaker = breaker;
+        self
+    }
+
+    pub fn circuit_breaker(&self) -> Option<CircuitBreaker> {
+        self.circuit_breaker
+    }
+
+    pub fn circuit(&self) -> &CircuitState {
+        &self.circuit
+    }
+
+    /// `run --overload-policy`: what the scheduler does when the workers fall behind.
+    pub fn with_overload_policy(
+        mut self,
+        policy: OverloadPolicy,
+    ) -> Self {
+        self.overload_policy = policy;
+        self
+    }
+
+    pub fn overload_policy(&self) -> OverloadPolicy {
+        self.overload_policy
+    }
+
+    /// The scheduler dropped a message under [`OverloadPolicy::Drop`].
+    pub fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn overload(&self) -> OverloadReport {
+        OverloadReport {
+            policy: self.overload_policy,
+            dropped: self.dropped(),
+        }
+    }
+
+    /// `run --dispatch`: how the scheduled messages reach the workers.
+    pub fn with_dispatch(
+        mut self,
+        dispatch: Dispatch,
+    ) -> Self {
+        self.dispatch = dispatch;
+        self
+    }
+
+    pub fn dispatch(&self) -> Dispatch {
+        self.dispatch
+    }
+
+    /// `run --phases`: shift the read/write mix at set points; the run lasts as long as the
+    /// phases together.
+    pub fn with_phases(
+        mut self,
+        phases: Option<Phases>,
+    ) -> Self {
+        if let Some(phases) = &phases {
+            self.duration = Some(phases.total());
+        }
+        self.phases = phases;
+        self
+    }
+
+    pub fn phases(&self) -> Option<&Phases> {
+        self.phases.as_ref()
+    }
+
+    /// `run --read-only`: refuse writes, in the queries file and again at dispatch.
+    pub fn with_read_only(
+        mut self,
+        read_only: bool,
+    ) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// `run --verify-metrics`: check the vendor's metric wiring before the workload.
+    pub fn with_verify_metrics(
+        mut self,
+        verify_metrics: bool,
+    ) -> Self {
+        self.verify_metrics = verify_metrics;
+        self
+    }
+
+    pub fn verify_metrics(&self) -> bool {
+        self.verify_metrics
+    }
+
+    /// The scheduler reached phase `index`, `offset` into the schedule; its first query is due
+    /// at `at`.
+    pub fn enter_phase(
+        &self,
+        index: usize,
+        offset: Duration,
+        at: SystemTime,
+    ) {
+        let Some(phase) = self
+            .phases
+            .as_ref()
+            .and_then(|phases| phases.phases().get(index))
+        else {
+            return;
+        };
+        info!(
+            "Entering phase {} (write ratio {})",
+            phase.name, phase.write_ratio
+        );
+        if let Ok(mut boundaries) = self.phase_boundaries.lock() {
+            boundaries.push(PhaseBoundary {
+                phase: phase.name.clone(),
+                write_ratio: phase.write_ratio,
+                offset_ms: offset.as_millis() as u64,
+                at_epoch_ms: at
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_millis() as u64),
+            });
+        }
+    }
+
+    pub fn phase_boundaries(&self) -> Vec<PhaseBoundary> {
+        self.phase_boundaries
+            .lock()
+            .map(|boundaries| boundaries.clone())
+            .unwrap_or_default()
+    }
+
+    /// The target rate the scheduler paces the next query at.
+    pub fn mps(&self) -> usize {
+        self.mps.load(Ordering::Relaxed)
+    }
+
+    pub fn set_mps(
+        &self,
+        mps: usize,
+    ) {
+        self.mps.store(mps, Ordering::Relaxed);
+        info!("Target rate changed to {} messages per second", mps);
+        self.record(format!("mps={}", mps));
+    }
+
+    pub fn start(&self) {
+        if !self.started.swap(true, Ordering::Relaxed) {
+            self.record("start".to_string());
+        }
+        self.notify.notify_waiters();
+    }
+
+    /// Stop scheduling queries; the ones already queued still run.
+    pub fn stop(&self) {
+        if !self.stopped.swap(true, Ordering::Relaxed) {
+            info!("Stop requested, no more queries will be scheduled");
+            self.record("stop".to_string());
+        }
+        self.notify.notify_waiters();
+    }
+
+    /// Stop the run early for `abort`'s reason; only the first abort is kept.
+    pub fn abort(
+        &self,
+        abort: RunAbort,
+    ) {
+        if let Ok(mut aborted) = self.aborted.lock() {
+            if aborted.is_some() {
+                return;
+            }
+            warn!("Aborting the run: {}", abort.reason);
+            *aborted = Some(abort);
+        }
+        self.record("abort".to_string());
+        self.stop();
+    }
+
+    pub fn aborted(&self) -> Option<RunAbort> {
+        self.aborted.lock().ok().and_then(|aborted| aborted.clone())
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Wait for [`RunControl::start`]; a stop ends the wait as well.
+    pub async fn wait_for_start(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.started.load(Ordering::Relaxed) || self.is_stopped() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    pub fn events(&self) -> Vec<ControlEvent> {
+        self.events
+            .lock()
+            .map(|events| events.clone())
+            .unwrap_or_default()
+    }
+
+    fn record(
+        &self,
+        action: String,
+    ) {
+        let at_epoch_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        if let Ok(mut events) = self.events.lock() {
+            events.push(ControlEvent {
+                at_epoch_ms,
+                action,
+            });
+        }
+    }
+}
+
+static CURRENT: Mutex<Option<Arc<RunControl>>> = Mutex::new(None);
+
+/// Make `control` reachable from the metrics endpoint's control routes.
+pub fn install(control: Arc<RunControl>) {
+    if let Ok(mut current) = CURRENT.lock() {
+        *current = Some(control);
+    }
+}
+
+/// The installed control; `None` unless the run was started with `--control-api`.
+pub fn current() -> Option<Arc<RunControl>> {
+    CURRENT.lock().ok().and_then(|current| current.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stop_ends_the_wait_for_start_and_is_recorded_once() {
+        let control = Arc::new(RunControl::new(100));
+        let waiter = tokio::spawn({
+            let control = control.clone();
+            async move { control.wait_for_start().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiter.is_finished());
+
+        control.set_mps(250);
+        control.stop();
+        control.stop();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(control.mps(), 250);
+        assert!(control.is_stopped());
+        let actions: Vec<String> = control.events().into_iter().map(|e| e.action).collect();
+        assert_eq!(actions, vec!["mps=250", "stop"]);
+    }
+}
@@ -0,0 +1,143 @@
+//! The Prometheus metrics of one run. The metrics are process-wide statics, so a `run` after a
+//! `load` or after another run in the same process would otherwise write the earlier work into
+//! its `metrics.prom`. [`begin`] takes a snapshot as a run starts, and [`since_begin`] renders
+//! the metrics with every counter and histogram reduced by it. Gauges hold current values and are
+//! written as they are. The live metrics endpoint keeps the process-wide totals, as Prometheus
+//! expects of counters.
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use prometheus::{Encoder, TextEncoder};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+static BASELINE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Every registered metric in the text exposition format.
+pub fn gather_text() -> BenchmarkResult<String> {
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|e| OtherError(format!("Failed to encode prometheus metrics: {}", e)))?;
+    Ok(String::from_utf8_lossy(&buffer).to_string())
+}
+
+/// Start counting a new run from the metrics as they are now.
+pub fn begin() -> BenchmarkResult<()> {
+    let baseline = gather_text()?;
+    if let Ok(mut current) = BASELINE.lock() {
+        *current = Some(baseline);
+    }
+    Ok(())
+}
+
+/// The metrics of the run since [`begin`]; all of them when no run began.
+pub fn since_begin() -> BenchmarkResult<String> {
+    let current = gather_text()?;
+    let baseline = BASELINE.lock().ok().and_then(|baseline| baseline.clone());
+    Ok(match baseline {
+        Some(baseline) => subtract(&current, &baseline),
+        None => current,
+    })
+}
+
+/// `current` with the counter and histogram samples of `baseline` subtracted. A sample below its
+/// baseline was reset since and is kept as it is.
+fn subtract(
+    current: &str,
+    baseline: &str,
+) -> String {
+    let before: HashMap<&str, f64> = cumulative_samples(baseline).collect();
+    let mut run = String::with_capacity(current.len());
+    let mut cumulative = false;
+    for line in current.lines() {
+        if let Some(kind) = line.strip_prefix("# TYPE ") {
+            cumulative = matches!(kind.rsplit(' ').next(), Some("counter" | "histogram"));
+        }
+        let sample = line.rsplit_once(' ').and_then(|(key, value)| {
+            let value = value.parse::<f64>().ok()?;
+            let since = value - before.get(key)?;
+            Some((key, since))
+        });
+        match sample {
+            Some((key, since)) if cumulative && !line.starts_with('#') && since >= 0.0 => {
+                run.push_str(&format!("{} {}", key, since));
+            }
+            _ => run.push_str(line),
+        }
+        run.push('\n');
+    }
+    run
+}
+
+/// The samples of the counter and histogram families in `text`, by name and labels.
+fn cumulative_samples(text: &str) -> impl Iterator<Item = (&str, f64)> {
+    let mut cumulative = false;
+    text.lines().filter_map(move |line| {
+        if let Some(kind) = line.strip_prefix("# TYPE ") {
+            cumulative = matches!(kind.rsplit(' ').next(), Some("counter" | "histogram"));
+            return None;
+        }
+        if !cumulative || line.starts_with('#') {
+            return None;
+        }
+        let (key, value) = line.rsplit_once(' ')?;
+        Some((key, value.parse().ok()?))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{register_histogram, register_int_counter, register_int_gauge};
+
+    #[test]
+    fn a_run_writes_only_what_happened_since_it_began() {
+        let counter =
+            register_int_counter!("run_metrics_test_total", "run_metrics test counter").unwrap();
+        let gauge =
+            register_int_gauge!("run_metrics_test_gauge", "run_metrics test gauge").unwrap();
+        let histogram = register_histogram!(
+            "run_metrics_test_seconds",
+            "run_metrics test histogram",
+            vec![1.0, 10.0]
+        )
+        .unwrap();
+        // An earlier load or run in the same process.
+        counter.inc_by(5);
+        gauge.set(7);
+        histogram.observe(0.5);
+        histogram.observe(5.0);
+
+        begin().unwrap();
+        counter.inc_by(2);
+        gauge.set(3);
+        histogram.observe(20.0);
+
+        let run = since_begin().unwrap();
+        for sample in [
+            "run_metrics_test_total 2",
+            "run_metrics_test_gauge 3",
+            "run_metrics_test_seconds_bucket{le=\"1\"} 0",
+            "run_metrics_test_seconds_bucket{le=\"10\"} 0",
+            "run_metrics_test_seconds_bucket{le=\"+Inf\"} 1",
+            "run_metrics_test_seconds_sum 20",
+            "run_metrics_test_seconds_count 1",
+        ] {
+            assert!(
+                run.lines().any(|line| line == sample),
+                "{}\n{}",
+                sample,
+                run
+            );
+        }
+        assert!(run.contains("# TYPE run_metrics_test_total counter"));
+
+        // A counter reset during the run counts from zero.
+        let reset = subtract(
+            "# TYPE c counter\nc{a=\"x\"} 1\n",
+            "# TYPE c counter\nc{a=\"x\"} 4\n",
+        );
+        assert_eq!(reset, "# TYPE c counter\nc{a=\"x\"} 1\n");
+    }
+}
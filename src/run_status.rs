@@ -0,0 +1,189 @@
+//! What this benchmark process is doing, served as JSON at `/status` next to the Prometheus
+//! metrics so CI jobs and orchestrators can follow a run without parsing its logs.
+
+use crate::run_control;
+use crate::scenario::Vendor;
+use crate::throughput::{ThroughputTracker, THROUGHPUT_WINDOWS_SECS};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    #[default]
+    Idle,
+    Loading,
+    /// Prepared and waiting for `/control/start` (`--wait-for-start`).
+    Ready,
+    /// The scheduler is still handing out queries.
+    Running,
+    /// Every query is scheduled; the workers are finishing the queued ones.
+    Draining,
+    /// The workload is over; results are being written.
+    Done,
+}
+
+/// Body of `/status`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunStatus {
+    pub phase: Phase,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<String>,
+    /// Since loading or the measured phase started.
+    pub elapsed_secs: u64,
+    /// Queries in the measured phase, or while loading the dataset's estimated nodes and edges
+    /// with `completed` counting the items loaded so far.
+    pub total: u64,
+    pub completed: u64,
+    pub errors: u64,
+    /// Completions per second over the last full second.
+    pub qps: f64,
+    /// The live target rate, when the run is under `--control-api`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_mps: Option<usize>,
+}
+
+struct State {
+    phase: Phase,
+    vendor: Option<Vendor>,
+    started: Option<Instant>,
+    total: u64,
+    loaded: u64,
+    throughput: Option<Arc<ThroughputTracker>>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    phase: Phase::Idle,
+    vendor: None,
+    started: None,
+    total: 0,
+    loaded: 0,
+    throughput: None,
+});
+
+fn update(f: impl FnOnce(&mut State)) {
+    if let Ok(mut state) = STATE.lock() {
+        f(&mut state);
+    }
+}
+
+/// Loading a dataset of about `total` nodes and edges started.
+pub fn loading(
+    vendor: Vendor,
+    total: u64,
+) {
+    update(|state| {
+        *state = State {
+            phase: Phase::Loading,
+            vendor: Some(vendor),
+            started: Some(Instant::now()),
+            total,
+            loaded: 0,
+            throughput: None,
+        }
+    });
+}
+
+/// The loader has sent `items` nodes and edges so far.
+pub fn loaded(items: u64) {
+    update(|state| {
+        if state.phase == Phase::Loading {
+            state.loaded = items;
+        }
+    });
+}
+
+/// The measured phase of `total` queries started; progress is read from `throughput`.
+pub fn running(
+    vendor: Vendor,
+    total: usize,
+    throughput: Arc<ThroughputTracker>,
+) {
+    update(|state| {
+        *state = State {
+            phase: Phase::Running,
+            vendor: Some(vendor),
+            started: Some(Instant::now()),
+            total: total as u64,
+            loaded: 0,
+            throughput: Some(throughput),
+        }
+    });
+}
+
+pub fn ready() {
+    update(|state| state.phase = Phase::Ready);
+}
+
+pub fn draining() {
+    update(|state| state.phase = Phase::Draining);
+}
+
+pub fn done() {
+    update(|state| state.phase = Phase::Done);
+}
+
+pub fn snapshot() -> RunStatus {
+    let Ok(state) = STATE.lock() else {
+        return RunStatus::default();
+    };
+    let samples = state
+        .throughput
+        .as_ref()
+        .map(|throughput| throughput.samples())
+        .unwrap_or_default();
+    RunStatus {
+        phase: state.phase,
+        vendor: state.vendor.map(|vendor| vendor.to_string()),
+        elapsed_secs: state
+            .started
+            .map_or(0, |started| started.elapsed().as_secs()),
+        total: state.total,
+        completed: if state.throughput.is_some() {
+            samples.iter().map(|sample| sample.completed).sum()
+        } else {
+            state.loaded
+        },
+        errors: samples.iter().map(|sample| sample.errors).sum(),
+        qps: state
+            .throughput
+            .as_ref()
+            .map_or(0.0, |throughput| throughput.qps(THROUGHPUT_WINDOWS_SECS[0])),
+        target_mps: run_control::current().map(|control| control.mps()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_follows_the_phases_and_the_tracker() {
+        loading(Vendor::Neo4j, 100);
+        let status = snapshot();
+        assert_eq!(status.phase, Phase::Loading);
+        assert_eq!(status.vendor.as_deref(), Some("neo4j"));
+        assert_eq!(status.completed, 0);
+        loaded(40);
+        assert_eq!((snapshot().total, snapshot().completed), (100, 40));
+
+        let throughput = Arc::new(ThroughputTracker::new("status-test"));
+        running(Vendor::Falkor, 10, throughput.clone());
+        throughput.record(true);
+        throughput.record(false);
+        let status = snapshot();
+        assert_eq!(
+            (status.phase, status.total, status.completed, status.errors),
+            (Phase::Running, 10, 2, 1)
+        );
+
+        draining();
+        assert_eq!(snapshot().phase, Phase::Draining);
+        done();
+        assert_eq!(
+            serde_json::to_value(snapshot()).unwrap()["phase"],
+            serde_json::json!("done")
+        );
+    }
+}
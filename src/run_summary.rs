@@ -0,0 +1,242 @@
+//! `run --output json` and `load --output json`: one line of JSON on stdout when the command
+//! ends, and an exit code that tells apart how it ended, so CI pipelines can act on a run
+//! without parsing its logs (which go to stderr instead).
+
+use crate::error::BenchmarkResult;
+use crate::runner::RunResults;
+use crate::scenario::Vendor;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How `run` and `load` report their outcome.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Logs only, to stdout.
+    #[default]
+    Text,
+    /// Logs to stderr and a [`CommandSummary`] line to stdout.
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SummaryStatus {
+    Ok,
+    /// The command failed; `message` says why.
+    Failed,
+    /// `--slo-p99` found no rate that kept P99 within the SLO.
+    SloFailed,
+    /// `--abort-on-error-rate` stopped the run; its results are still written.
+    Aborted,
+}
+
+impl SummaryStatus {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            SummaryStatus::Ok => 0,
+            SummaryStatus::Failed => 1,
+            SummaryStatus::SloFailed => 2,
+            SummaryStatus::Aborted => 3,
+        }
+    }
+}
+
+/// The line `--output json` prints. Latency and throughput are left out when the command
+/// didn't measure them, e.g. for `load` or `run --repeat`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandSummary {
+    pub command: &'static str,
+    pub status: SummaryStatus,
+    pub vendor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p50_us: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p95_us: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p99_us: Option<u64>,
+    /// Successful queries per second over the measured phase.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub achieved_qps: Option<f64>,
+    /// The run's directory, `slo.json` for `--slo-p99`, or the results dir the child runs of
+    /// `--repeat` and `--sweep` wrote to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results_path: Option<String>,
+    /// The error, or why the run was aborted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl CommandSummary {
+    fn new(
+        command: &'static str,
+        vendor: Vendor,
+    ) -> Self {
+        CommandSummary {
+            command,
+            status: SummaryStatus::Ok,
+            vendor: vendor.to_string(),
+            errors: None,
+            p50_us: None,
+            p95_us: None,
+            p99_us: None,
+            achieved_qps: None,
+            results_path: None,
+            message: None,
+        }
+    }
+
+    fn failed(
+        mut self,
+        error: &impl ToString,
+    ) -> Self {
+        self.status = SummaryStatus::Failed;
+        self.message = Some(error.to_string());
+        self
+    }
+
+    pub fn for_load(
+        vendor: Vendor,
+        result: &BenchmarkResult<()>,
+    ) -> Self {
+        let summary = CommandSummary::new("load", vendor);
+        match result {
+            Ok(()) => summary,
+            Err(e) => summary.failed(e),
+        }
+    }
+
+    pub fn for_run(
+        vendor: Vendor,
+        result: &BenchmarkResult<RunResults>,
+    ) -> Self {
+        let mut summary = CommandSummary::new("run", vendor);
+        match result {
+            Err(e) => return summary.failed(e),
+            Ok(RunResults::Run(outcome)) => {
+                let latency = &outcome.report.latency;
+                summary.errors = Some(outcome.report.queries.iter().map(|q| q.errors).sum());
+                summary.p50_us = Some(latency.p50_us);
+                summary.p95_us = Some(latency.p95_us);
+                summary.p99_us = Some(latency.p99_us);
+                let elapsed = outcome.elapsed.as_secs_f64();
+                summary.achieved_qps = Some(if elapsed > 0.0 {
+                    latency.count as f64 / elapsed
+                } else {
+                    0.0
+                });
+                summary.results_path = outcome
+                    .run_dir
+                    .as_ref()
+                    .map(|dir| dir.display().to_string());
+                if let Some(aborted) = &outcome.aborted {
+                    summary.status = SummaryStatus::Aborted;
+                    summary.message = Some(aborted.reason.clone());
+                }
+            }
+            Ok(RunResults::SloSearch { result, path, .. }) => {
+                let best = result
+                    .max_mps
+                    .and_then(|mps| result.probes.iter().find(|probe| probe.mps == mps));
+                summary.p99_us = best.map(|probe| probe.p99_us);
+                summary.achieved_qps = result.achieved_qps;
+                summary.results_path = Some(path.display().to_string());
+                if result.max_mps.is_none() {
+                    summary.status = SummaryStatus::SloFailed;
+                    summary.message = Some(format!(
+                        "P99 stayed over {}ms at every probed rate",
+                        result.slo_p99_ms
+                    ));
+                }
+            }
+            Ok(RunResults::Repetitions { results_dir, .. }) => {
+                summary.results_path = Some(results_dir.display().to_string());
+            }
+            Ok(RunResults::Sweep { path, .. }) => {
+                summary.results_path = Some(path.display().to_string());
+            }
+        }
+        summary
+    }
+
+    pub fn to_json_line(&self) -> BenchmarkResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::BenchmarkError::OtherError;
+    use crate::error_budget::RunAbort;
+    use crate::metrics_collector::MetricsCollector;
+    use crate::runner::RunOutcome;
+    use crate::scheduler::{OverloadPolicy, OverloadReport};
+    use crate::slo_search::{SloProbe, SloResult};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn each_outcome_maps_to_its_status_and_exit_code() {
+        let metrics = MetricsCollector::new(Vec::new()).unwrap();
+        let outcome = RunOutcome {
+            vendor: Vendor::Falkor,
+            run_dir: Some(PathBuf::from("results/falkor")),
+            report: metrics.report(Vendor::Falkor),
+            queries_count: 0,
+            elapsed: Duration::from_secs(2),
+            aborted: Some(RunAbort {
+                reason: "error rate 60% over the last 10s".to_string(),
+                at_second: 12,
+                errors: 60,
+                completed: 100,
+            }),
+            overload: OverloadReport {
+                policy: OverloadPolicy::Block,
+                dropped: 0,
+            },
+        };
+        let summary =
+            CommandSummary::for_run(Vendor::Falkor, &Ok(RunResults::Run(Box::new(outcome))));
+        assert_eq!(summary.status.exit_code(), 3);
+        let line = summary.to_json_line().unwrap();
+        assert!(!line.contains('\n'));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["status"], "aborted");
+        assert_eq!(json["vendor"], "falkor");
+        assert_eq!(json["errors"], 0);
+        assert_eq!(json["achieved_qps"], 0.0);
+        assert_eq!(json["results_path"], "results/falkor");
+
+        let search = |max_mps| RunResults::SloSearch {
+            result: SloResult {
+                vendor: "neo4j".to_string(),
+                slo_p99_ms: 50,
+                max_mps,
+                achieved_qps: max_mps.map(|_| 95.0),
+                converged: true,
+                probes: vec![SloProbe {
+                    mps: 100,
+                    p99_us: 40_000,
+                    achieved_qps: 95.0,
+                    passed: max_mps.is_some(),
+                }],
+            },
+            path: PathBuf::from("results/neo4j/slo.json"),
+            probes: Vec::new(),
+        };
+        let passed = CommandSummary::for_run(Vendor::Neo4j, &Ok(search(Some(100))));
+        assert_eq!(
+            (passed.status, passed.p99_us),
+            (SummaryStatus::Ok, Some(40_000))
+        );
+        let failed = CommandSummary::for_run(Vendor::Neo4j, &Ok(search(None)));
+        assert_eq!(failed.status.exit_code(), 2);
+
+        let error = CommandSummary::for_load(Vendor::Memgraph, &Err(OtherError("boom".into())));
+        assert_eq!(error.status, SummaryStatus::Failed);
+        assert_eq!(error.status.exit_code(), 1);
+        assert_eq!(error.message.as_deref(), Some("Other error: boom"));
+    }
+}
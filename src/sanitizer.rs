@@ -0,0 +1,222 @@
+//! `run --sanitizer asan|msan`: a FalkorDB correctness run against sanitizer builds of
+//! redis-server and the module. The target rate is lowered to what the slower build can serve,
+//! the sanitizer's reports are collected from its log files once the server has stopped, and a
+//! run that produced any fails with the reports recorded in meta.json.
+
+use crate::error::BenchmarkResult;
+use crate::utils::get_falkor_log_path;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Sanitizer {
+    /// AddressSanitizer, with LeakSanitizer's check when the server exits.
+    Asan,
+    /// MemorySanitizer.
+    Msan,
+}
+
+impl Sanitizer {
+    pub fn name(self) -> &'static str {
+        match self {
+            Sanitizer::Asan => "asan",
+            Sanitizer::Msan => "msan",
+        }
+    }
+
+    /// How many times slower than a release build the sanitizer build is taken to be.
+    fn slowdown(self) -> usize {
+        match self {
+            Sanitizer::Asan => 4,
+            Sanitizer::Msan => 8,
+        }
+    }
+
+    /// `--mps` scaled down to what the sanitizer build can keep up with.
+    pub fn lowered_mps(
+        self,
+        mps: usize,
+    ) -> usize {
+        (mps / self.slowdown()).max(1)
+    }
+
+    /// The sanitizer build of redis-server: `FALKOR_SANITIZER_REDIS_SERVER`, else
+    /// `redis-server-asan` or `redis-server-msan` from the PATH. The module is still taken
+    /// from `FALKOR_PATH` and has to be built with the same sanitizer.
+    pub fn redis_server(self) -> String {
+        env::var("FALKOR_SANITIZER_REDIS_SERVER")
+            .unwrap_or_else(|_| format!("redis-server-{}", self.name()))
+    }
+
+    /// The server's environment: reports go to `<log_prefix>.<pid>` instead of stderr.
+    pub fn env_vars(
+        self,
+        log_prefix: &str,
+    ) -> HashMap<String, String> {
+        let (name, options) = match self {
+            Sanitizer::Asan => (
+                "ASAN_OPTIONS",
+                format!("log_path={}:detect_leaks=1", log_prefix),
+            ),
+            Sanitizer::Msan => ("MSAN_OPTIONS", format!("log_path={}", log_prefix)),
+        };
+        HashMap::from([(name.to_string(), options)])
+    }
+}
+
+/// Where the sanitizer's reports go, next to FalkorDB's log.
+pub fn log_prefix() -> BenchmarkResult<String> {
+    Ok(format!("{}.sanitizer", get_falkor_log_path()?))
+}
+
+/// One problem the sanitizer reported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SanitizerReport {
+    /// e.g. `heap-buffer-overflow`, `detected memory leaks`, `use-of-uninitialized-value`.
+    pub kind: String,
+    pub summary: Option<String>,
+    pub text: String,
+}
+
+/// `--sanitizer` and what it found; recorded in meta.json as `sanitizer`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SanitizerRun {
+    pub sanitizer: Sanitizer,
+    /// `--mps` before it was lowered.
+    pub requested_mps: usize,
+    pub reports: Vec<SanitizerReport>,
+}
+
+/// The reports in a sanitizer log: each from its `ERROR:`/`WARNING:` line to its `SUMMARY:`.
+pub fn parse_reports(log: &str) -> Vec<SanitizerReport> {
+    let mut reports = Vec::new();
+    let mut current: Option<SanitizerReport> = None;
+    for line in log.lines() {
+        if let Some(kind) = report_kind(line) {
+            reports.extend(current.take());
+            current = Some(SanitizerReport {
+                kind,
+                summary: None,
+                text: String::new(),
+            });
+        }
+        let Some(report) = current.as_mut() else {
+            continue;
+        };
+        report.text.push_str(line);
+        report.text.push('\n');
+        if let Some(summary) = line.strip_prefix("SUMMARY: ") {
+            report.summary = Some(summary.to_string());
+            reports.extend(current.take());
+        }
+    }
+    reports.extend(current);
+    reports
+}
+
+/// The kind of report a line starts, e.g. `==12==ERROR: AddressSanitizer: heap-buffer-overflow
+/// on address ...`.
+fn report_kind(line: &str) -> Option<String> {
+    let (_, rest) = line
+        .split_once("ERROR: ")
+        .or_else(|| line.split_once("WARNING: "))?;
+    let (tool, kind) = rest.split_once(": ")?;
+    if !tool.ends_with("Sanitizer") {
+        return None;
+    }
+    let kind = kind.split(" on ").next().unwrap_or(kind);
+    Some(kind.trim().to_string())
+}
+
+/// The log files `log_prefix` has collected, one per server process.
+async fn log_files(log_prefix: &str) -> Vec<String> {
+    let prefix = Path::new(log_prefix);
+    let (Some(dir), Some(name)) = (prefix.parent(), prefix.file_name()) else {
+        return Vec::new();
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let name = format!("{}.", name.to_string_lossy());
+    let mut files = Vec::new();
+    if let Ok(mut entries) = tokio::fs::read_dir(dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.file_name().to_string_lossy().starts_with(&name) {
+                files.push(entry.path().to_string_lossy().to_string());
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Remove the logs of an earlier run.
+pub async fn clear_reports(log_prefix: &str) {
+    for file in log_files(log_prefix).await {
+        if let Err(e) = tokio::fs::remove_file(&file).await {
+            warn!("Failed removing old sanitizer log {}: {}", file, e);
+        }
+    }
+}
+
+/// Every report in the logs under `log_prefix`.
+pub async fn collect_reports(log_prefix: &str) -> Vec<SanitizerReport> {
+    let mut reports = Vec::new();
+    for file in log_files(log_prefix).await {
+        match tokio::fs::read_to_string(&file).await {
+            Ok(log) => reports.extend(parse_reports(&log)),
+            Err(e) => warn!("Failed reading sanitizer log {}: {}", file, e),
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_report_up_to_its_summary() {
+        let log = "\
+some startup noise
+==4711==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000014 at pc 0x1
+    #0 0x55 in Graph_GetNode graph.c:120
+SUMMARY: AddressSanitizer: heap-buffer-overflow graph.c:120 in Graph_GetNode
+==4711==ABORTING
+==4712==ERROR: LeakSanitizer: detected memory leaks
+Direct leak of 64 byte(s) in 1 object(s) allocated from:
+SUMMARY: AddressSanitizer: 64 byte(s) leaked in 1 allocation(s).
+==4713==WARNING: MemorySanitizer: use-of-uninitialized-value
+    #0 0x66 in AR_EXP_Evaluate
+";
+        let reports = parse_reports(log);
+        let kinds: Vec<&str> = reports.iter().map(|r| r.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "heap-buffer-overflow",
+                "detected memory leaks",
+                "use-of-uninitialized-value"
+            ]
+        );
+        assert_eq!(
+            reports[1].summary.as_deref(),
+            Some("AddressSanitizer: 64 byte(s) leaked in 1 allocation(s).")
+        );
+        assert!(reports[0].text.contains("Graph_GetNode graph.c:120"));
+        assert!(!reports[0].text.contains("ABORTING"));
+        // A report cut off by the process dying is kept without a summary.
+        assert_eq!(reports[2].summary, None);
+
+        assert!(parse_reports("ERROR: something else: entirely").is_empty());
+        assert_eq!(Sanitizer::Asan.lowered_mps(4000), 1000);
+        assert_eq!(Sanitizer::Msan.lowered_mps(4), 1);
+    }
+}
@@ -1,15 +1,13 @@
 #![allow(dead_code)]
 
 use crate::error::BenchmarkResult;
+use crate::index_parity::IndexDescriptor;
 use crate::utils::{create_directory_if_not_exists, download_file, read_lines, url_file_name};
 use clap::ValueEnum;
-use flate2::read::GzDecoder;
 use futures::Stream;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io;
-use std::io::Read;
-use std::path::Path;
 use std::pin::Pin;
 use strum_macros::Display;
 use tracing::info;
@@ -38,6 +36,20 @@ pub enum Vendor {
     Memgraph,
 }
 
+impl Vendor {
+    pub const ALL: [Vendor; 3] = [Vendor::Neo4j, Vendor::Falkor, Vendor::Memgraph];
+
+    /// The `vendor` label on every exported metric, and what the aggregator filters a metrics
+    /// dump on. Not the results UI's vendor id, which calls FalkorDB `falkordb`.
+    pub fn metric_label(self) -> &'static str {
+        match self {
+            Vendor::Neo4j => "neo4j",
+            Vendor::Falkor => "falkor",
+            Vendor::Memgraph => "memgraph",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Spec<'a> {
     pub name: Name,
@@ -92,6 +104,17 @@ impl Spec<'_> {
         }
     }
 
+    /// Property indexes every vendor is expected to have for this dataset; the pre-run parity
+    /// check compares them with what the target reports.
+    pub fn expected_indexes(&self) -> Vec<IndexDescriptor> {
+        match self.name {
+            Name::Users => vec![
+                IndexDescriptor::new("User", "id"),
+                IndexDescriptor::new("User", "age"),
+            ],
+        }
+    }
+
     pub fn backup_path(&self) -> String {
         format!("./backups/{}/{}/{}", self.vendor, self.name, self.size)
     }
@@ -128,29 +151,8 @@ impl Spec<'_> {
             download_file(url, cache_file.as_str()).await?;
         }
 
-        // If the cached file is gzip-compressed, transparently decompress it once and
-        // return the path to the decompressed file. This avoids having to teach every
-        // consumer how to handle .gz files.
-        if Path::new(&cache_file)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            == Some("gz")
-        {
-            let decompressed_path = cache_file.trim_end_matches(".gz").to_string();
-            if fs::metadata(&decompressed_path).is_err() {
-                info!(
-                    "Decompressing gzip cache {} to {}",
-                    cache_file, decompressed_path
-                );
-                let compressed = fs::read(&cache_file)?;
-                let mut decoder = GzDecoder::new(&compressed[..]);
-                let mut out = Vec::new();
-                decoder.read_to_end(&mut out)?;
-                fs::write(&decompressed_path, &out)?;
-            }
-            Ok(decompressed_path)
-        } else {
-            Ok(cache_file)
-        }
+        // A .gz or .zst cache file is kept compressed; read_lines decompresses it while
+        // streaming.
+        Ok(cache_file)
     }
 }
@@ -1,9 +1,23 @@
+use crate::phases::PhasedRequests;
+use crate::run_control::RunControl;
+use crate::{
+    RUN_PHASE, SCHEDULER_DROPPED_MESSAGES, SCHEDULER_QUEUE_DEPTH, WORKER_BUSY_SECONDS,
+    WORKER_IDLE_SECONDS,
+};
+use clap::ValueEnum;
+use rand::RngExt;
+use serde::Serialize;
+use std::fmt;
 use std::ops::Add;
-use std::time::Duration;
-use tokio::sync::mpsc::Sender;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tokio::time::Instant;
-use tracing::info;
+use tracing::{info, warn};
 
 #[derive(Debug)]
 pub struct Msg<Payload: Send + Sync> {
@@ -16,22 +30,7 @@ impl<Payload: Send + Sync> Msg<Payload> {
     /// Compute the offset in milliseconds from the current time to the target time
     #[inline]
     pub fn compute_offset_ms(&self) -> i64 {
-        let current_time = Instant::now();
-        let target_time = self.start_time + Duration::from_millis(self.offset);
-
-        let duration = if current_time >= target_time {
-            current_time - target_time
-        } else {
-            target_time - current_time
-        };
-
-        let offset_ms = duration.as_millis() as i64;
-
-        if current_time >= target_time {
-            -offset_ms
-        } else {
-            offset_ms
-        }
+        offset_ms(self.intended_start(), Instant::now())
     }
 
     /// The instant this message was scheduled to start executing.
@@ -44,37 +43,449 @@ impl<Payload: Send + Sync> Msg<Payload> {
         self.start_time + Duration::from_millis(self.offset)
     }
 }
-/// schedule at a rate of msg_per_sec messages per second to sender for number_of_messages
+/// Milliseconds from `now` until `deadline`: positive while the message is early, negative
+/// once it is late, truncated toward zero.
+pub fn offset_ms(
+    deadline: Instant,
+    now: Instant,
+) -> i64 {
+    if now >= deadline {
+        -((now - deadline).as_millis() as i64)
+    } else {
+        (deadline - now).as_millis() as i64
+    }
+}
+
+/// Where each message falls in the schedule: `1/mps` after the one before. Offsets are computed
+/// from the messages sent since the rate last changed rather than summed interval by interval,
+/// so rounding doesn't accumulate over a long run.
+#[derive(Debug, Default)]
+pub struct Pacer {
+    base: Duration,
+    mps: u64,
+    sent: u64,
+}
+
+impl Pacer {
+    /// The next message's offset from the start of the schedule.
+    pub fn at(&self) -> Duration {
+        if self.mps == 0 {
+            return self.base;
+        }
+        let nanos = u128::from(self.sent) * 1_000_000_000 / u128::from(self.mps);
+        self.base + Duration::from_nanos(nanos as u64)
+    }
+
+    /// Move past a message sent at `mps`.
+    pub fn advance(
+        &mut self,
+        mps: usize,
+    ) {
+        let mps = mps.max(1) as u64;
+        if mps != self.mps {
+            self.base = self.at();
+            self.mps = mps;
+            self.sent = 0;
+        }
+        self.sent += 1;
+    }
+}
+
+/// What the scheduler does when the workers fall behind the target rate and the channel fills.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverloadPolicy {
+    /// Wait for room in the channel; the backlog stays in the recorded latency.
+    #[default]
+    Block,
+    /// Hand each message over at its scheduled start and drop it when the channel is full.
+    Drop,
+    /// Queue the whole schedule up front, so the scheduler never waits.
+    QueueUnbounded,
+}
+
+impl OverloadPolicy {
+    /// Size of the channel between the scheduler and `parallel` workers running `queries` queries.
+    pub fn channel_capacity(
+        self,
+        parallel: usize,
+        queries: usize,
+    ) -> usize {
+        match self {
+            OverloadPolicy::Block | OverloadPolicy::Drop => 20 * parallel,
+            OverloadPolicy::QueueUnbounded => queries.max(1),
+        }
+    }
+}
+
+/// `--overload-policy` and what it cost; recorded in meta.json as `overload`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OverloadReport {
+    pub policy: OverloadPolicy,
+    pub dropped: u64,
+}
+
+/// How the scheduled messages reach the workers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Dispatch {
+    /// All workers take from one shared queue; whichever gets the lock first runs the next query.
+    #[default]
+    Shared,
+    /// Each worker has its own queue and the messages are dealt out in turn, so every worker
+    /// runs the same share of the queries. A slow worker holds the others back once its queue
+    /// is full.
+    RoundRobin,
+}
+
+/// Messages a worker's own queue holds under [`Dispatch::RoundRobin`].
+const WORKER_QUEUE_CAPACITY: usize = 20;
+
+/// One queue per worker for the messages of `receiver`: the shared queue itself, or under
+/// [`Dispatch::RoundRobin`] a queue of its own that a dispatcher task fills in turn.
+pub fn worker_queues<Payload: Send + Sync + 'static>(
+    dispatch: Dispatch,
+    receiver: Receiver<Msg<Payload>>,
+    workers: usize,
+) -> Vec<Arc<Mutex<Receiver<Msg<Payload>>>>> {
+    match dispatch {
+        Dispatch::Shared => {
+            let shared = Arc::new(Mutex::new(receiver));
+            (0..workers).map(|_| shared.clone()).collect()
+        }
+        Dispatch::RoundRobin => {
+            let (senders, receivers): (Vec<_>, Vec<_>) = (0..workers.max(1))
+                .map(|_| tokio::sync::mpsc::channel(WORKER_QUEUE_CAPACITY))
+                .unzip();
+            tokio::spawn(async move {
+                let mut receiver = receiver;
+                for sender in senders.iter().cycle() {
+                    let Some(msg) = receiver.recv().await else {
+                        return;
+                    };
+                    if sender.send(msg).await.is_err() {
+                        return;
+                    }
+                }
+            });
+            receivers
+                .into_iter()
+                .map(|receiver| Arc::new(Mutex::new(receiver)))
+                .collect()
+        }
+    }
+}
+
+/// A worker's side of the schedule: the channel's depth as it takes a message, and its time split
+/// into busy (from a query's scheduled start, or its send when late, to the reply) and idle.
+pub struct WorkerClock {
+    vendor: &'static str,
+    last: Instant,
+    /// The worker's last think pause, until the next query takes it.
+    pause: Option<(Instant, Instant)>,
+}
+
+impl WorkerClock {
+    pub fn new(vendor: &'static str) -> Self {
+        WorkerClock {
+            vendor,
+            last: Instant::now(),
+            pause: None,
+        }
+    }
+
+    /// Where the latency of the query taken after the worker's last think pause is measured
+    /// from: its scheduled start, moved later by the part of the pause that came after it. Time
+    /// it spent waiting behind the worker's previous query still counts.
+    pub fn measured_from(
+        &mut self,
+        intended_start: Instant,
+    ) -> Instant {
+        match self.pause.take() {
+            Some((_, end)) if intended_start >= end => intended_start,
+            Some((start, end)) if intended_start >= start => end,
+            Some((start, end)) => intended_start + (end - start),
+            None => intended_start,
+        }
+    }
+
+    /// Take the client's think time, if any, before the next query.
+    pub async fn think(
+        &mut self,
+        think_time: Option<ThinkTime>,
+    ) {
+        if let Some(think_time) = think_time {
+            let pause = think_time.sample(&mut rand::rng());
+            let start = Instant::now();
+            tokio::time::sleep(pause).await;
+            self.pause = Some((start, Instant::now()));
+        }
+    }
+
+    /// The next message, recording how many are left waiting.
+    pub async fn next<Payload: Send + Sync>(
+        &self,
+        receiver: &Mutex<Receiver<Msg<Payload>>>,
+    ) -> Option<Msg<Payload>> {
+        let mut receiver = receiver.lock().await;
+        let msg = receiver.recv().await;
+        SCHEDULER_QUEUE_DEPTH
+            .with_label_values(&[self.vendor])
+            .set(receiver.len() as i64);
+        msg
+    }
+
+    /// A query sent at `sent` for `intended_start` was answered; the time since the previous
+    /// answer is busy from the later of the two and idle before it.
+    pub fn answered(
+        &mut self,
+        sent: Instant,
+        intended_start: Instant,
+    ) {
+        let now = Instant::now();
+        let total = now.saturating_duration_since(self.last);
+        let busy = now
+            .saturating_duration_since(sent.max(intended_start))
+            .min(total);
+        WORKER_BUSY_SECONDS
+            .with_label_values(&[self.vendor])
+            .inc_by(busy.as_secs_f64());
+        WORKER_IDLE_SECONDS
+            .with_label_values(&[self.vendor])
+            .inc_by((total - busy).as_secs_f64());
+        self.last = now;
+    }
+}
+
+/// Per-query client think time: a pause a worker takes after finishing a query and
+/// before picking up the next one, modelling clients that don't fire back-to-back.
+///
+/// Parsed from `<base>[±<jitter>]` (e.g. `5ms±2ms`, `5ms+-2ms`, `500us`, `1s`);
+/// a bare number is taken as milliseconds. Each pause is drawn uniformly from
+/// `[base - jitter, base + jitter]`, clamped at zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ThinkTime {
+    #[serde(rename = "base_us", serialize_with = "serialize_duration_us")]
+    pub base: Duration,
+    #[serde(rename = "jitter_us", serialize_with = "serialize_duration_us")]
+    pub jitter: Duration,
+}
+
+impl ThinkTime {
+    /// Draw the next pause.
+    pub fn sample<R: rand::Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Duration {
+        if self.jitter.is_zero() {
+            return self.base;
+        }
+        let base = self.base.as_micros() as i64;
+        let jitter = self.jitter.as_micros() as i64;
+        let us = rng.random_range(base - jitter..=base + jitter).max(0);
+        Duration::from_micros(us as u64)
+    }
+}
+
+impl FromStr for ThinkTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (base, jitter) = match s.split_once('±').or_else(|| s.split_once("+-")) {
+            Some((base, jitter)) => (base, Some(jitter)),
+            None => (s, None),
+        };
+        let base = parse_think_duration(base)?;
+        let jitter = match jitter {
+            Some(jitter) => parse_think_duration(jitter)?,
+            None => Duration::ZERO,
+        };
+        Ok(ThinkTime { base, jitter })
+    }
+}
+
+impl fmt::Display for ThinkTime {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "{}us", self.base.as_micros())?;
+        if !self.jitter.is_zero() {
+            write!(f, "±{}us", self.jitter.as_micros())?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_think_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(idx) => s.split_at(idx),
+        None => (s, "ms"),
+    };
+    let value: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid think time '{}', expected e.g. 5ms or 5ms±2ms", s))?;
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!("think time must be non-negative, got '{}'", s));
+    }
+    let micros = match unit.trim() {
+        "us" => value,
+        "ms" => value * 1_000.0,
+        "s" => value * 1_000_000.0,
+        other => {
+            return Err(format!(
+                "unknown think time unit '{}', expected us, ms or s",
+                other
+            ))
+        }
+    };
+    Ok(Duration::from_micros(micros.round() as u64))
+}
+
+fn serialize_duration_us<S: serde::Serializer>(
+    d: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(d.as_micros() as u64)
+}
+
+/// The order the scheduler sends the queries in.
+pub enum Requests<Payload> {
+    /// As they are in the queries file.
+    InOrder(std::vec::IntoIter<Payload>),
+    /// `run --phases`: drawn to match the mix of the phase they're scheduled in.
+    Phased(Box<PhasedRequests<Payload>>),
+}
+
+impl<Payload> From<Vec<Payload>> for Requests<Payload> {
+    fn from(requests: Vec<Payload>) -> Self {
+        Requests::InOrder(requests.into_iter())
+    }
+}
+
+impl<Payload> Requests<Payload> {
+    /// The run's queries: in file order, or drawn per phase when `control` has phases.
+    pub fn for_run(
+        control: &RunControl,
+        requests: Vec<Payload>,
+        is_write: impl Fn(&Payload) -> bool,
+    ) -> Self {
+        match control.phases() {
+            Some(phases) => Requests::Phased(Box::new(PhasedRequests::new(
+                phases.clone(),
+                requests,
+                is_write,
+            ))),
+            None => requests.into(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Requests::InOrder(requests) => requests.len(),
+            Requests::Phased(requests) => requests.len(),
+        }
+    }
+
+    fn next(
+        &mut self,
+        at: Duration,
+    ) -> Option<Payload> {
+        match self {
+            Requests::InOrder(requests) => requests.next(),
+            Requests::Phased(requests) => requests.next(at),
+        }
+    }
+}
+
+/// schedule at the rate of `control` to sender, one message per request
 /// returns a handle to the spawned task
 /// The actual send should be done as fast as possible,
 /// but each message contain an offset from the start time which should
 /// server as a deadline for the message to be processed or delay depending on the system at test speed
+/// The rate is read again before every message, so a live change applies once the queued
+/// messages are consumed; a stop ends the schedule early.
 pub fn spawn_scheduler<Payload: Send + Sync + 'static>(
-    msg_per_sec: usize,
+    control: Arc<RunControl>,
     sender: Sender<Msg<Payload>>,
-    requests: Vec<Payload>,
+    requests: impl Into<Requests<Payload>>,
 ) -> JoinHandle<()> {
+    let mut requests = requests.into();
     tokio::spawn(async move {
-        let interval_in_nanos = (1_000_000_000.0 / msg_per_sec as f64) as u64;
         // anchor the start time to 200 ms from now
         let start_time = Instant::now().add(Duration::from_millis(200));
-        for (count, payload) in requests.into_iter().enumerate() {
-            // compute offset in millis from an interval in nonos
-            let offset = (count as u64 * interval_in_nanos) / 1_000_000;
-            match sender
-                .send(Msg {
-                    start_time,
-                    offset,
-                    payload,
-                })
-                .await
-            {
-                Ok(_) => {}
-                Err(e) => {
-                    info!("Error sending message: {}, exiting", e);
-                    return;
+        let mut pacer = Pacer::default();
+        let total = requests.len();
+        let mut phase = None;
+        for count in 0usize.. {
+            if control.is_stopped() {
+                info!("Stopped after {} of {} messages", count, total);
+                return;
+            }
+            let at = pacer.at();
+            if control.duration().is_some_and(|duration| at >= duration) {
+                info!("Reached --duration after {} of {} messages", count, total);
+                return;
+            }
+            let Some(payload) = requests.next(at) else {
+                break;
+            };
+            let offset = at.as_millis() as u64;
+            if let Some(phases) = control.phases() {
+                let index = phases.index_at(at);
+                if index != phase {
+                    phase = index;
+                    let due = start_time + at;
+                    let wall = SystemTime::now() + due.saturating_duration_since(Instant::now());
+                    for p in phases.phases() {
+                        RUN_PHASE.with_label_values(&[p.name.as_str()]).set(0);
+                    }
+                    if let Some(index) = index {
+                        control.enter_phase(index, at, wall);
+                        RUN_PHASE
+                            .with_label_values(&[phases.phases()[index].name.as_str()])
+                            .set(1);
+                    }
                 }
             }
+            let msg = Msg {
+                start_time,
+                offset,
+                payload,
+            };
+            if control.overload_policy() == OverloadPolicy::Drop {
+                tokio::time::sleep_until(msg.intended_start()).await;
+                match sender.try_send(msg) {
+                    Ok(_) => {}
+                    Err(TrySendError::Full(_)) => {
+                        SCHEDULER_DROPPED_MESSAGES.inc();
+                        control.record_dropped();
+                    }
+                    Err(TrySendError::Closed(_)) => {
+                        info!("Channel closed, exiting");
+                        return;
+                    }
+                }
+            } else if let Err(e) = sender.send(msg).await {
+                info!("Error sending message: {}, exiting", e);
+                return;
+            }
+            pacer.advance(control.mps());
+        }
+        if control.dropped() > 0 {
+            warn!(
+                "Dropped {} messages the workers had no room for",
+                control.dropped()
+            );
+        }
+        if let Some(duration) = control.duration() {
+            warn!(
+                "The queries ran out before --duration {:?}; the run was shorter",
+                duration
+            );
         }
         info!("All messages sent");
     })
@@ -83,6 +494,7 @@ pub fn spawn_scheduler<Payload: Send + Sync + 'static>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use prometheus::CounterVec;
 
     #[test]
     fn intended_start_anchors_at_scheduled_time() {
@@ -111,4 +523,320 @@ mod tests {
         };
         assert_eq!(msg.intended_start(), start_time);
     }
+
+    #[test]
+    fn offset_ms_is_positive_while_early_and_negative_once_late() {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let ms = Duration::from_millis;
+        assert_eq!(offset_ms(deadline, deadline - ms(250)), 250);
+        assert_eq!(offset_ms(deadline, deadline), 0);
+        assert_eq!(offset_ms(deadline, deadline + ms(40)), -40);
+        // Less than a millisecond either way counts as on time.
+        assert_eq!(
+            offset_ms(deadline, deadline - Duration::from_micros(900)),
+            0
+        );
+        assert_eq!(
+            offset_ms(deadline, deadline + Duration::from_micros(900)),
+            0
+        );
+    }
+
+    #[test]
+    fn pacer_keeps_exact_offsets_across_rate_changes() {
+        let mut pacer = Pacer::default();
+        assert_eq!(pacer.at(), Duration::ZERO);
+        // 1/3s doesn't divide into nanoseconds, yet a million messages land exactly on time.
+        for _ in 0..1_000_000 {
+            pacer.advance(3);
+        }
+        assert_eq!(
+            pacer.at(),
+            Duration::from_secs(1_000_000 / 3) + Duration::from_nanos(333_333_333)
+        );
+        let mut pacer = Pacer::default();
+        for _ in 0..3 {
+            pacer.advance(3);
+        }
+        assert_eq!(pacer.at(), Duration::from_secs(1));
+        pacer.advance(1_000);
+        pacer.advance(1_000);
+        assert_eq!(pacer.at(), Duration::from_millis(1_002));
+        // A zero rate is treated as one message per second rather than stalling.
+        pacer.advance(0);
+        assert_eq!(pacer.at(), Duration::from_millis(2_002));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drop_policy_releases_each_message_at_its_deadline() {
+        let control = Arc::new(RunControl::new(3).with_overload_policy(OverloadPolicy::Drop));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        let scheduler = spawn_scheduler(control.clone(), tx, vec![(); 4]);
+        let mut offsets = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            assert_eq!(Instant::now(), msg.intended_start());
+            offsets.push(msg.offset);
+        }
+        scheduler.await.unwrap();
+        assert_eq!(offsets, vec![0, 333, 666, 1_000]);
+        assert_eq!(control.dropped(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn block_policy_holds_the_schedule_until_workers_make_room() {
+        let control = Arc::new(RunControl::new(1_000));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let scheduler = spawn_scheduler(control.clone(), tx, vec![(); 3]);
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        // One message fills the channel; the rest wait for room however late they get.
+        assert!(!scheduler.is_finished());
+        for offset in 0..3 {
+            assert_eq!(rx.recv().await.map(|msg| msg.offset), Some(offset));
+        }
+        scheduler.await.unwrap();
+        assert_eq!(control.dropped(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scheduler_ends_when_the_queries_run_out_or_nobody_listens() {
+        let control = Arc::new(RunControl::new(1_000));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        spawn_scheduler(control.clone(), tx, Vec::<()>::new())
+            .await
+            .unwrap();
+        assert!(rx.recv().await.is_none());
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        drop(rx);
+        spawn_scheduler(control.clone(), tx, vec![(); 3])
+            .await
+            .unwrap();
+        let control = Arc::new(RunControl::new(1_000).with_overload_policy(OverloadPolicy::Drop));
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        drop(rx);
+        spawn_scheduler(control.clone(), tx, vec![(); 3])
+            .await
+            .unwrap();
+        assert_eq!(control.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn scheduler_paces_at_the_control_rate_until_stopped() {
+        let control = Arc::new(RunControl::new(1_000));
+        control.set_mps(500);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        spawn_scheduler(control.clone(), tx, vec![(); 3])
+            .await
+            .unwrap();
+        let mut offsets = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            offsets.push(msg.offset);
+        }
+        assert_eq!(offsets, vec![0, 2, 4]);
+
+        control.stop();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        spawn_scheduler(control, tx, vec![(); 3]).await.unwrap();
+        assert!(rx.recv().await.is_none());
+
+        // --duration ends the schedule at 3ms, well before the 10 queries run out.
+        let control =
+            Arc::new(RunControl::new(1_000).with_duration(Some(Duration::from_millis(3))));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        spawn_scheduler(control, tx, vec![(); 10]).await.unwrap();
+        let mut offsets = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            offsets.push(msg.offset);
+        }
+        assert_eq!(offsets, vec![0, 1, 2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drop_policy_counts_messages_the_channel_has_no_room_for() {
+        let control = Arc::new(RunControl::new(1_000).with_overload_policy(OverloadPolicy::Drop));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        spawn_scheduler(control.clone(), tx, vec![(); 3])
+            .await
+            .unwrap();
+        // Nobody consumed the first message, so the two after it found the channel full.
+        assert_eq!(rx.recv().await.map(|msg| msg.offset), Some(0));
+        assert!(rx.recv().await.is_none());
+        assert_eq!(
+            control.overload(),
+            OverloadReport {
+                policy: OverloadPolicy::Drop,
+                dropped: 2
+            }
+        );
+        assert_eq!(
+            OverloadPolicy::QueueUnbounded.channel_capacity(4, 1_000),
+            1_000
+        );
+        assert_eq!(OverloadPolicy::Block.channel_capacity(4, 1_000), 80);
+    }
+
+    #[tokio::test]
+    async fn round_robin_dispatch_deals_messages_out_in_turn() {
+        let (tx, rx) = tokio::sync::mpsc::channel(10);
+        let queues = worker_queues(Dispatch::RoundRobin, rx, 3);
+        let start_time = Instant::now();
+        for offset in 0..7 {
+            tx.send(Msg {
+                start_time,
+                offset,
+                payload: (),
+            })
+            .await
+            .unwrap();
+        }
+        drop(tx);
+        let mut dealt = Vec::new();
+        for queue in &queues {
+            let mut offsets = Vec::new();
+            while let Some(msg) = queue.lock().await.recv().await {
+                offsets.push(msg.offset);
+            }
+            dealt.push(offsets);
+        }
+        assert_eq!(dealt, vec![vec![0, 3, 6], vec![1, 4], vec![2, 5]]);
+
+        let (_tx, rx) = tokio::sync::mpsc::channel::<Msg<()>>(10);
+        let queues = worker_queues(Dispatch::Shared, rx, 3);
+        assert!(Arc::ptr_eq(&queues[0], &queues[2]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn worker_clock_splits_busy_from_idle_time() {
+        let vendor = "worker-clock-test";
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        let start_time = Instant::now() + Duration::from_millis(20);
+        for offset in [0, 5] {
+            tx.send(Msg {
+                start_time,
+                offset,
+                payload: (),
+            })
+            .await
+            .unwrap();
+        }
+        let rx = Mutex::new(rx);
+        let mut clock = WorkerClock::new(vendor);
+
+        let msg = clock.next(&rx).await.unwrap();
+        assert_eq!(SCHEDULER_QUEUE_DEPTH.with_label_values(&[vendor]).get(), 1);
+        // Sent 10ms early: the 10ms until the scheduled start are idle like the first 10ms.
+        tokio::time::advance(Duration::from_millis(10)).await;
+        let sent = Instant::now();
+        tokio::time::advance(Duration::from_millis(30)).await;
+        clock.answered(sent, msg.intended_start());
+
+        let seconds = |counter: &CounterVec| counter.with_label_values(&[vendor]).get();
+        assert!((seconds(&WORKER_BUSY_SECONDS) - 0.020).abs() < 1e-6);
+        assert!((seconds(&WORKER_IDLE_SECONDS) - 0.020).abs() < 1e-6);
+    }
+
+    #[test]
+    fn think_time_parses_base_and_jitter() {
+        let t: ThinkTime = "5ms±2ms".parse().unwrap();
+        assert_eq!(t.base, Duration::from_millis(5));
+        assert_eq!(t.jitter, Duration::from_millis(2));
+        assert_eq!("5ms+-2ms".parse::<ThinkTime>().unwrap(), t);
+
+        let t: ThinkTime = "500us".parse().unwrap();
+        assert_eq!(t.base, Duration::from_micros(500));
+        assert!(t.jitter.is_zero());
+        assert_eq!(
+            "3".parse::<ThinkTime>().unwrap().base,
+            Duration::from_millis(3)
+        );
+        assert_eq!(
+            "1s".parse::<ThinkTime>().unwrap().base,
+            Duration::from_secs(1)
+        );
+
+        assert!("".parse::<ThinkTime>().is_err());
+        assert!("5h".parse::<ThinkTime>().is_err());
+        assert!("-5ms".parse::<ThinkTime>().is_err());
+        assert!("5ms±x".parse::<ThinkTime>().is_err());
+    }
+
+    #[test]
+    fn think_time_samples_within_jitter_bounds() {
+        let mut rng = rand::rng();
+        let t: ThinkTime = "5ms±2ms".parse().unwrap();
+        for _ in 0..1_000 {
+            let d = t.sample(&mut rng);
+            assert!(d >= Duration::from_millis(3) && d <= Duration::from_millis(7));
+        }
+        // jitter larger than base clamps at zero rather than underflowing
+        let t: ThinkTime = "1ms±5ms".parse().unwrap();
+        for _ in 0..1_000 {
+            assert!(t.sample(&mut rng) <= Duration::from_millis(6));
+        }
+        let fixed: ThinkTime = "2ms".parse().unwrap();
+        assert_eq!(fixed.sample(&mut rng), Duration::from_millis(2));
+    }
+
+    #[test]
+    fn think_time_serializes_as_micros() {
+        let t: ThinkTime = "5ms±2ms".parse().unwrap();
+        let v = serde_json::to_value(t).unwrap();
+        assert_eq!(v, serde_json::json!({"base_us": 5000, "jitter_us": 2000}));
+    }
+
+    /// One worker at 100 queries/s answering in 2ms: the p99 it records stays the database's
+    /// 2ms whatever think time the rate leaves room for, even when a pause runs past the next
+    /// scheduled start.
+    #[tokio::test(start_paused = true)]
+    async fn think_time_stays_out_of_measured_latency() {
+        async fn p99(think_time: Option<ThinkTime>) -> Duration {
+            let mut clock = WorkerClock::new("test");
+            let start = Instant::now();
+            let mut latencies = Vec::new();
+            for i in 0..200 {
+                let scheduled = start + Duration::from_millis(10 * i);
+                tokio::time::sleep_until(scheduled).await;
+                let from = clock.measured_from(scheduled);
+                tokio::time::sleep(Duration::from_millis(2)).await;
+                latencies.push(Instant::now() - from);
+                clock.think(think_time).await;
+            }
+            latencies.sort();
+            latencies[latencies.len() * 99 / 100]
+        }
+
+        let without = p99(None).await;
+        assert_eq!(without, Duration::from_millis(2));
+        for think_time in ["5ms", "7ms±1ms", "8ms"] {
+            assert_eq!(p99(Some(think_time.parse().unwrap())).await, without);
+        }
+    }
+
+    /// Queries that queue behind the worker's own previous query count the wait, but not the
+    /// think pause after it, and only the query taken right after a pause skips it.
+    #[tokio::test(start_paused = true)]
+    async fn queueing_behind_a_think_pause_still_counts() {
+        let ms = Duration::from_millis;
+        let think_time: ThinkTime = "15ms".parse().unwrap();
+        let mut clock = WorkerClock::new("test");
+        let start = Instant::now();
+
+        // A query at 0ms answered at 2ms, then a 15ms pause to 17ms.
+        clock.measured_from(start);
+        tokio::time::sleep(ms(2)).await;
+        clock.think(Some(think_time)).await;
+        // Scheduled at 1ms, while the worker was busy: 1ms queued plus 2ms answering.
+        let from = clock.measured_from(start + ms(1));
+        tokio::time::sleep(ms(2)).await;
+        assert_eq!(Instant::now() - from, ms(3));
+        // Scheduled at 10ms, during the pause, and taken without one: the backlog from 10ms to
+        // 19ms counts.
+        let from = clock.measured_from(start + ms(10));
+        tokio::time::sleep(ms(2)).await;
+        assert_eq!(Instant::now() - from, ms(11));
+
+        // Scheduled during a pause: measured from its end.
+        clock.think(Some(think_time)).await;
+        assert_eq!(clock.measured_from(start + ms(30)), start + ms(36));
+    }
 }
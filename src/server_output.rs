@@ -0,0 +1,182 @@
+//! The stdout and stderr of a locally managed Neo4j or Memgraph during `run`, written to
+//! `<results-dir>/<vendor>/server/<name>.stdout.log` and `.stderr.log` so a failed run's server
+//! output sits next to its metrics. Each file is rotated at [`MAX_FILE_BYTES`]; the
+//! [`KEPT_FILES`] most recent rotations are kept as `.1`, `.2` and so on.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use tracing::warn;
+
+pub const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+pub const KEPT_FILES: usize = 5;
+
+/// A log file that moves to `<path>.1` once it would grow past `max_bytes`.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    /// Appends to `path` if it already exists, e.g. after a server restart.
+    pub fn open(
+        path: PathBuf,
+        max_bytes: u64,
+        keep: usize,
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            max_bytes,
+            keep,
+            file,
+            written,
+        })
+    }
+
+    fn rotated(
+        &self,
+        n: usize,
+    ) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated(self.keep));
+            for n in (1..self.keep).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    fs::rename(&from, self.rotated(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+            self.file = File::create(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(
+        &mut self,
+        buf: &[u8],
+    ) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Where a managed server's output goes.
+#[derive(Debug, Clone)]
+pub struct ServerOutput {
+    dir: PathBuf,
+}
+
+impl ServerOutput {
+    pub fn new(dir: PathBuf) -> Self {
+        ServerOutput { dir }
+    }
+
+    /// Spawn `command` with its stdout and stderr copied into `<name>.stdout.log` and
+    /// `<name>.stderr.log`.
+    pub fn spawn(
+        &self,
+        name: &str,
+        command: &mut Command,
+    ) -> io::Result<Child> {
+        fs::create_dir_all(&self.dir)?;
+        let stdout = self.file(name, "stdout")?;
+        let stderr = self.file(name, "stderr")?;
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(out) = child.stdout.take() {
+            copy_in_background(out, stdout);
+        }
+        if let Some(err) = child.stderr.take() {
+            copy_in_background(err, stderr);
+        }
+        Ok(child)
+    }
+
+    /// Where `spawn(name, ..)` writes `stream` (`stdout` or `stderr`).
+    pub fn log_path(
+        &self,
+        name: &str,
+        stream: &str,
+    ) -> PathBuf {
+        self.dir.join(format!("{}.{}.log", name, stream))
+    }
+
+    fn file(
+        &self,
+        name: &str,
+        stream: &str,
+    ) -> io::Result<RotatingFile> {
+        RotatingFile::open(
+            self.log_path(name, stream),
+            MAX_FILE_BYTES,
+            KEPT_FILES,
+        )
+    }
+}
+
+/// Copies until the server closes the stream, i.e. exits.
+fn copy_in_background(
+    mut from: impl Read + Send + 'static,
+    mut to: RotatingFile,
+) {
+    thread::spawn(move || {
+        if let Err(e) = io::copy(&mut from, &mut to) {
+            warn!(
+                "Failed capturing server output to {}: {}",
+                to.path.display(),
+                e
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_past_the_size_limit_and_keeps_the_newest() {
+        let dir = std::env::temp_dir().join(format!("server-output-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("neo4j.stdout.log");
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+        for line in ["aaaaaaa\n", "bbbbbbb\n", "ccccccc\n", "ddddddd\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        let read = |p: PathBuf| fs::read_to_string(p).unwrap_or_default();
+        assert_eq!(read(path.clone()), "ddddddd\n");
+        assert_eq!(read(file.rotated(1)), "ccccccc\n");
+        assert_eq!(read(file.rotated(2)), "bbbbbbb\n");
+        assert!(!file.rotated(3).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+//! Whether a latency difference between two vendors' repeated runs is more than noise.
+//!
+//! Each vendor's per-repetition values (e.g. the P99 of every `run-<n>`) are bootstrapped: resampled
+//! with replacement, the median taken, [`BOOTSTRAP_RESAMPLES`] times. The 2.5th and 97.5th
+//! percentiles of those medians are the vendor's 95% confidence interval, and of their pairwise
+//! differences the interval for the difference. A comparison whose per-vendor intervals overlap is
+//! inconclusive. The resampling is seeded, so aggregating the same results twice gives the same
+//! intervals.
+
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use serde::Serialize;
+
+pub const BOOTSTRAP_RESAMPLES: usize = 10_000;
+
+const BOOTSTRAP_SEED: u64 = 0x5eed_b007;
+
+/// A 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Interval {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Interval {
+    fn overlaps(
+        &self,
+        other: &Interval,
+    ) -> bool {
+        self.low <= other.high && other.low <= self.high
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+    /// The baseline's values are lower (for latency: faster).
+    BaselineLower,
+    BaselineHigher,
+    Inconclusive,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Comparison {
+    pub baseline: Interval,
+    pub other: Interval,
+    /// `other - baseline`.
+    pub difference: Interval,
+    pub verdict: Verdict,
+}
+
+/// Compare `baseline` with `other`; `None` unless both have at least two repetitions.
+pub fn compare(
+    baseline: &[f64],
+    other: &[f64],
+) -> Option<Comparison> {
+    if baseline.len() < 2 || other.len() < 2 {
+        return None;
+    }
+    let mut rng = StdRng::seed_from_u64(BOOTSTRAP_SEED);
+    let mut baseline_medians = Vec::with_capacity(BOOTSTRAP_RESAMPLES);
+    let mut other_medians = Vec::with_capacity(BOOTSTRAP_RESAMPLES);
+    let mut differences = Vec::with_capacity(BOOTSTRAP_RESAMPLES);
+    for _ in 0..BOOTSTRAP_RESAMPLES {
+        let b = resampled_median(baseline, &mut rng);
+        let o = resampled_median(other, &mut rng);
+        baseline_medians.push(b);
+        other_medians.push(o);
+        differences.push(o - b);
+    }
+    let baseline = interval(baseline_medians);
+    let other = interval(other_medians);
+    let verdict = if baseline.overlaps(&other) {
+        Verdict::Inconclusive
+    } else if baseline.high < other.low {
+        Verdict::BaselineLower
+    } else {
+        Verdict::BaselineHigher
+    };
+    Some(Comparison {
+        baseline,
+        other,
+        difference: interval(differences),
+        verdict,
+    })
+}
+
+fn resampled_median(
+    values: &[f64],
+    rng: &mut StdRng,
+) -> f64 {
+    let resample: Vec<f64> = (0..values.len())
+        .map(|_| values[rng.random_range(0..values.len())])
+        .collect();
+    median(resample)
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let n = values.len();
+    if n.is_multiple_of(2) {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    } else {
+        values[n / 2]
+    }
+}
+
+fn interval(mut values: Vec<f64>) -> Interval {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let at = |q: f64| values[((values.len() - 1) as f64 * q).round() as usize];
+    Interval {
+        low: at(0.025),
+        high: at(0.975),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separates_clear_differences_from_noise() {
+        let falkor = [1.0, 1.1, 0.9, 1.05, 0.95];
+        let neo4j = [3.0, 3.2, 2.9, 3.1, 3.05];
+        let clear = compare(&falkor, &neo4j).unwrap();
+        assert_eq!(clear.verdict, Verdict::BaselineLower);
+        assert!(clear.difference.low > 1.5 && clear.difference.high < 2.5);
+        assert_eq!(
+            compare(&neo4j, &falkor).unwrap().verdict,
+            Verdict::BaselineHigher
+        );
+
+        let noisy = [1.0, 2.0, 1.5, 0.8, 2.2];
+        assert_eq!(
+            compare(&falkor, &noisy).unwrap().verdict,
+            Verdict::Inconclusive
+        );
+
+        // Deterministic, and needs repetitions on both sides.
+        assert_eq!(
+            compare(&falkor, &neo4j).unwrap().difference,
+            clear.difference
+        );
+        assert!(compare(&[1.0], &neo4j).is_none());
+    }
+}
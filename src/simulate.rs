@@ -0,0 +1,227 @@
+//! `run --simulate`: skip the server and have every query take a made-up time instead, to test
+//! the harness itself. A fixed delay gives every query the same latency, which makes for
+//! degenerate histograms; a distribution gives the aggregator and dashboards a realistic shape
+//! to work with.
+
+use rand::RngExt;
+use serde::{Serialize, Serializer};
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How long a simulated query takes.
+///
+/// Parsed from `<delay>` (a bare number is milliseconds, as before distributions were
+/// supported), `uniform:<min>-<max>` (e.g. `uniform:2-10ms`) or `lognormal:<median>,<sigma>`
+/// (e.g. `lognormal:5ms,1.5`, where sigma is the spread of the underlying normal).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimulatedLatency {
+    Fixed(Duration),
+    Uniform { min: Duration, max: Duration },
+    LogNormal { median: Duration, sigma: f64 },
+}
+
+impl SimulatedLatency {
+    /// Draw the next query's latency.
+    pub fn sample<R: rand::Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Duration {
+        match *self {
+            SimulatedLatency::Fixed(delay) => delay,
+            SimulatedLatency::Uniform { min, max } => {
+                Duration::from_micros(rng.random_range(min.as_micros()..=max.as_micros()) as u64)
+            }
+            SimulatedLatency::LogNormal { median, sigma } => {
+                // Box-Muller; 1 - u keeps the logarithm's argument above zero.
+                let u1: f64 = 1.0 - rng.random::<f64>();
+                let u2: f64 = rng.random();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+                Duration::from_secs_f64((median.as_secs_f64() * (sigma * z).exp()).min(3600.0))
+            }
+        }
+    }
+
+    /// The delay of a fixed `--simulate`, recorded in meta.json as `simulate_ms` like before
+    /// distributions were supported.
+    pub fn fixed_ms(&self) -> Option<usize> {
+        match self {
+            SimulatedLatency::Fixed(delay) => Some(delay.as_millis() as usize),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for SimulatedLatency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let Some((kind, spec)) = s.split_once(':') else {
+            return Ok(SimulatedLatency::Fixed(parse_delay(s, "ms")?));
+        };
+        match kind.trim() {
+            "uniform" => {
+                let (min, max) = spec.split_once('-').ok_or_else(|| {
+                    format!(
+                        "invalid uniform latency '{}', expected e.g. uniform:2-10ms",
+                        s
+                    )
+                })?;
+                let max_unit = max.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                let max = parse_delay(max, "ms")?;
+                let min = parse_delay(min, max_unit.trim())?;
+                if min > max {
+                    return Err(format!(
+                        "uniform latency '{}' has its minimum above its maximum",
+                        s
+                    ));
+                }
+                Ok(SimulatedLatency::Uniform { min, max })
+            }
+            "lognormal" => {
+                let (median, sigma) = spec.split_once(',').ok_or_else(|| {
+                    format!(
+                        "invalid lognormal latency '{}', expected e.g. lognormal:5ms,1.5",
+                        s
+                    )
+                })?;
+                let sigma: f64 = sigma
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|sigma: &f64| sigma.is_finite() && *sigma > 0.0)
+                    .ok_or_else(|| format!("lognormal sigma must be positive, got '{}'", sigma))?;
+                Ok(SimulatedLatency::LogNormal {
+                    median: parse_delay(median, "ms")?,
+                    sigma,
+                })
+            }
+            other => Err(format!(
+                "unknown latency distribution '{}', expected uniform or lognormal",
+                other
+            )),
+        }
+    }
+}
+
+/// `5ms`, `500us` or `1.5s`; a bare number is in `default_unit`.
+fn parse_delay(
+    s: &str,
+    default_unit: &str,
+) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(idx) => s.split_at(idx),
+        None => (s, default_unit),
+    };
+    let value: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid simulated latency '{}', expected e.g. 5ms", s))?;
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!(
+            "simulated latency must be non-negative, got '{}'",
+            s
+        ));
+    }
+    let micros = match unit.trim() {
+        "us" => value,
+        "ms" | "" => value * 1_000.0,
+        "s" => value * 1_000_000.0,
+        other => {
+            return Err(format!(
+                "unknown simulated latency unit '{}', expected us, ms or s",
+                other
+            ))
+        }
+    };
+    Ok(Duration::from_micros(micros.round() as u64))
+}
+
+impl fmt::Display for SimulatedLatency {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            SimulatedLatency::Fixed(delay) => write!(f, "{}us", delay.as_micros()),
+            SimulatedLatency::Uniform { min, max } => {
+                write!(f, "uniform:{}us-{}us", min.as_micros(), max.as_micros())
+            }
+            SimulatedLatency::LogNormal { median, sigma } => {
+                write!(f, "lognormal:{}us,{}", median.as_micros(), sigma)
+            }
+        }
+    }
+}
+
+impl Serialize for SimulatedLatency {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn parses_and_samples_each_distribution() {
+        let parse = |s: &str| s.parse::<SimulatedLatency>();
+        let ms = Duration::from_millis;
+        assert_eq!(parse("5"), Ok(SimulatedLatency::Fixed(ms(5))));
+        assert_eq!(parse("5").unwrap().fixed_ms(), Some(5));
+        assert_eq!(
+            parse("uniform:2-10ms"),
+            Ok(SimulatedLatency::Uniform {
+                min: ms(2),
+                max: ms(10)
+            })
+        );
+        assert_eq!(
+            parse("uniform:500us-1s"),
+            Ok(SimulatedLatency::Uniform {
+                min: Duration::from_micros(500),
+                max: ms(1000)
+            })
+        );
+        let lognormal = parse("lognormal:5ms,1.5").unwrap();
+        assert_eq!(lognormal.fixed_ms(), None);
+        // Display round-trips.
+        for latency in [
+            parse("2.5ms").unwrap(),
+            parse("uniform:2-10ms").unwrap(),
+            lognormal,
+        ] {
+            assert_eq!(parse(&latency.to_string()), Ok(latency));
+        }
+        for bad in [
+            "uniform:10-2ms",
+            "uniform:5ms",
+            "lognormal:5ms,0",
+            "normal:5ms,1",
+            "-1",
+        ] {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let uniform = parse("uniform:2-10ms").unwrap();
+        assert!((0..1000)
+            .map(|_| uniform.sample(&mut rng))
+            .all(|d| (ms(2)..=ms(10)).contains(&d)));
+        let mut samples: Vec<Duration> = (0..10_001).map(|_| lognormal.sample(&mut rng)).collect();
+        samples.sort();
+        let median = samples[5_000].as_secs_f64() * 1000.0;
+        assert!((4.5..5.5).contains(&median), "median {}ms", median);
+        // A long right tail rather than a spike.
+        assert!(samples[9_900] > ms(100));
+    }
+}
@@ -0,0 +1,197 @@
+//! `run --slo-p99 50ms`: find the highest rate a vendor sustains while its P99 stays under the
+//! SLO.
+//!
+//! Every probe is a full `run` of the queries file at one `--mps`, in its own process, into
+//! `<results-dir>/<vendor>/slo/mps-<n>/`. The search doubles the rate from `--mps` until a probe
+//! fails, then bisects between the fastest passing and the slowest failing rate until they are
+//! within `--slo-resolution` of each other. A probe passes when its P99 is within the SLO and it
+//! completed at least [`MIN_ACHIEVED_FRACTION`] of the offered rate, so a client that can't keep
+//! up doesn't count as headroom. The outcome goes to `<results-dir>/<vendor>/slo.json`.
+
+use crate::query_timeout::parse_duration;
+use serde::Serialize;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A probe must complete this share of the offered rate to pass.
+pub const MIN_ACHIEVED_FRACTION: f64 = 0.95;
+
+/// Default for `--slo-resolution`.
+pub const DEFAULT_SLO_RESOLUTION: f64 = 0.05;
+
+/// The search stops after this many probes even when it hasn't converged.
+pub const MAX_SLO_PROBES: usize = 16;
+
+/// `--slo-p99`, e.g. `50ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SloP99(pub Duration);
+
+impl FromStr for SloP99 {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_duration(s).map(SloP99)
+    }
+}
+
+/// One probe of the search.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SloProbe {
+    pub mps: usize,
+    pub p99_us: u64,
+    pub achieved_qps: f64,
+    pub passed: bool,
+}
+
+/// Written to `slo.json`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SloResult {
+    pub vendor: String,
+    pub slo_p99_ms: u64,
+    /// The highest passing rate; `None` when even the lowest probe broke the SLO.
+    pub max_mps: Option<usize>,
+    /// Completed queries per second of that probe.
+    pub achieved_qps: Option<f64>,
+    pub converged: bool,
+    pub probes: Vec<SloProbe>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SloSearch {
+    slo: Duration,
+    start_mps: usize,
+    resolution: f64,
+    probes: Vec<SloProbe>,
+}
+
+impl SloSearch {
+    pub fn new(
+        slo: Duration,
+        start_mps: usize,
+        resolution: f64,
+    ) -> Self {
+        SloSearch {
+            slo,
+            start_mps: start_mps.max(1),
+            resolution,
+            probes: Vec::new(),
+        }
+    }
+
+    fn fastest_pass(&self) -> Option<&SloProbe> {
+        self.probes
+            .iter()
+            .filter(|probe| probe.passed)
+            .max_by_key(|probe| probe.mps)
+    }
+
+    fn slowest_fail(&self) -> Option<usize> {
+        self.probes
+            .iter()
+            .filter(|probe| !probe.passed)
+            .map(|probe| probe.mps)
+            .min()
+    }
+
+    /// The rate to probe next; `None` once the search is over.
+    pub fn next_mps(&self) -> Option<usize> {
+        if self.probes.len() >= MAX_SLO_PROBES {
+            return None;
+        }
+        self.next_rate()
+    }
+
+    fn next_rate(&self) -> Option<usize> {
+        let pass = self.fastest_pass().map(|probe| probe.mps);
+        match (pass, self.slowest_fail()) {
+            (None, None) => Some(self.start_mps),
+            (Some(pass), None) => Some(pass.saturating_mul(2)),
+            (None, Some(fail)) => Some(fail / 2).filter(|mps| *mps >= 1),
+            (Some(pass), Some(fail)) => {
+                let step = ((pass as f64 * self.resolution) as usize).max(1);
+                Some(pass + (fail - pass) / 2).filter(|_| fail - pass > step)
+            }
+        }
+    }
+
+    /// Record how a probe at `mps` went.
+    pub fn record(
+        &mut self,
+        mps: usize,
+        p99_us: u64,
+        achieved_qps: f64,
+    ) -> &SloProbe {
+        let passed = p99_us as u128 <= self.slo.as_micros()
+            && achieved_qps >= mps as f64 * MIN_ACHIEVED_FRACTION;
+        self.probes.push(SloProbe {
+            mps,
+            p99_us,
+            achieved_qps,
+            passed,
+        });
+        &self.probes[self.probes.len() - 1]
+    }
+
+    pub fn result(
+        &self,
+        vendor: &str,
+    ) -> SloResult {
+        let fastest = self.fastest_pass();
+        SloResult {
+            vendor: vendor.to_string(),
+            slo_p99_ms: self.slo.as_millis() as u64,
+            max_mps: fastest.map(|probe| probe.mps),
+            achieved_qps: fastest.map(|probe| probe.achieved_qps),
+            converged: self.next_rate().is_none(),
+            probes: self.probes.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A server whose P99 stays at 10ms up to `capacity` qps and then climbs steeply.
+    fn search(
+        capacity: usize,
+        start_mps: usize,
+    ) -> SloResult {
+        let mut search = SloSearch::new(Duration::from_millis(50), start_mps, 0.05);
+        while let Some(mps) = search.next_mps() {
+            let p99_us = if mps <= capacity { 10_000 } else { 200_000 };
+            search.record(mps, p99_us, mps as f64);
+        }
+        search.result("falkor")
+    }
+
+    #[test]
+    fn finds_the_highest_rate_within_the_slo() {
+        let result = search(3_000, 500);
+        let max_mps = result.max_mps.unwrap();
+        assert!(result.converged);
+        assert!(max_mps <= 3_000 && max_mps as f64 >= 3_000.0 * 0.95);
+        let rates: Vec<usize> = result.probes.iter().map(|probe| probe.mps).collect();
+        assert_eq!(&rates[..4], &[500, 1_000, 2_000, 4_000]);
+
+        // Already over capacity at the start: halve until a rate passes.
+        let result = search(300, 1_000);
+        assert_eq!(result.probes[1].mps, 500);
+        assert!(result.max_mps.unwrap() <= 300);
+
+        assert_eq!(search(0, 4).max_mps, None);
+    }
+
+    #[test]
+    fn a_client_that_falls_behind_fails_the_probe() {
+        let mut search = SloSearch::new(Duration::from_millis(50), 1_000, 0.05);
+        assert!(search.record(1_000, 10_000, 980.0).passed);
+        assert!(!search.record(2_000, 10_000, 1_500.0).passed);
+        assert!(!search.record(1_500, 60_000, 1_500.0).passed);
+        assert_eq!(search.next_mps(), Some(1_250));
+        assert_eq!(
+            "50ms".parse::<SloP99>(),
+            Ok(SloP99(Duration::from_millis(50)))
+        );
+    }
+}
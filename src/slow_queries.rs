@@ -0,0 +1,232 @@
+//! `run --slow-query-threshold 500ms`: measured queries slower than the threshold, with the
+//! parameters they ran with, written to `<results-dir>/<vendor>/slow_queries.jsonl`.
+//!
+//! At most `--slow-query-limit` entries are kept; once full, a slower query replaces the fastest
+//! one kept, so the file always holds the worst offenders. meta.json records how many queries
+//! crossed the threshold in total.
+
+use crate::compact_query::CompactQuery;
+use crate::error::BenchmarkResult;
+use crate::query::QueryParam;
+use crate::query_timeout::parse_duration;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Entries kept unless `--slow-query-limit` is given.
+pub const DEFAULT_SLOW_QUERY_LIMIT: usize = 1000;
+
+/// `--slow-query-threshold`, e.g. `500ms` or `2s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlowQueryThreshold(pub Duration);
+
+impl FromStr for SlowQueryThreshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_duration(s).map(SlowQueryThreshold)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlowQueryOutcome {
+    Success,
+    Timeout,
+    Error,
+}
+
+/// One line of `slow_queries.jsonl`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQuery {
+    pub name: String,
+    pub params: BTreeMap<String, serde_json::Value>,
+    /// Measured like the run's latency, from the query's scheduled start.
+    pub duration_us: u64,
+    pub timestamp_epoch_ms: u64,
+    pub worker: String,
+    pub outcome: SlowQueryOutcome,
+}
+
+/// `--slow-query-threshold` outcome; recorded in meta.json.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQueryReport {
+    pub threshold_ms: u64,
+    pub limit: usize,
+    /// Queries over the threshold, including the ones that didn't fit under the limit.
+    pub total: u64,
+    pub recorded: usize,
+}
+
+#[derive(Debug, Default)]
+struct SlowQueries {
+    entries: Vec<SlowQuery>,
+    total: u64,
+}
+
+/// Shared by the run's workers.
+#[derive(Debug)]
+pub struct SlowQueryLog {
+    threshold: Duration,
+    limit: usize,
+    slow: Mutex<SlowQueries>,
+}
+
+impl SlowQueryLog {
+    pub fn new(
+        threshold: Duration,
+        limit: usize,
+    ) -> Self {
+        SlowQueryLog {
+            threshold,
+            limit,
+            slow: Mutex::new(SlowQueries::default()),
+        }
+    }
+
+    /// Record `query` if it took at least the threshold.
+    pub fn record(
+        &self,
+        worker: &str,
+        query: &CompactQuery,
+        duration: Duration,
+        outcome: SlowQueryOutcome,
+    ) {
+        if duration < self.threshold {
+            return;
+        }
+        let entry = SlowQuery {
+            name: query.q_name.to_string(),
+            params: query
+                .statement()
+                .params()
+                .map(|(name, value)| (name.to_string(), param_json(value)))
+                .collect(),
+            duration_us: duration.as_micros() as u64,
+            timestamp_epoch_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            worker: worker.to_string(),
+            outcome,
+        };
+        let Ok(mut slow) = self.slow.lock() else {
+            return;
+        };
+        slow.total += 1;
+        if slow.entries.len() < self.limit {
+            slow.entries.push(entry);
+        } else if let Some(fastest) = slow
+            .entries
+            .iter_mut()
+            .min_by_key(|kept| kept.duration_us)
+            .filter(|kept| kept.duration_us < entry.duration_us)
+        {
+            *fastest = entry;
+        }
+    }
+
+    pub fn report(&self) -> SlowQueryReport {
+        let (total, recorded) = self
+            .slow
+            .lock()
+            .map(|slow| (slow.total, slow.entries.len()))
+            .unwrap_or_default();
+        SlowQueryReport {
+            threshold_ms: self.threshold.as_millis() as u64,
+            limit: self.limit,
+            total,
+            recorded,
+        }
+    }
+
+    /// The kept entries in the order they completed, one JSON object per line.
+    pub fn to_jsonl(&self) -> BenchmarkResult<String> {
+        let mut entries = self
+            .slow
+            .lock()
+            .map(|slow| slow.entries.clone())
+            .unwrap_or_default();
+        entries.sort_by_key(|entry| entry.timestamp_epoch_ms);
+        let mut jsonl = String::new();
+        for entry in &entries {
+            jsonl.push_str(&serde_json::to_string(entry)?);
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+}
+
+pub(crate) fn param_json(value: &QueryParam) -> serde_json::Value {
+    match value {
+        QueryParam::String(s) => s.clone().into(),
+        QueryParam::Integer(i) => (*i).into(),
+        QueryParam::Float(f) => (*f as f64).into(),
+        QueryParam::Boolean(b) => (*b).into(),
+        QueryParam::List(items) => items.iter().map(param_json).collect(),
+        QueryParam::DateTime(millis) => match QueryParam::utc(*millis) {
+            Some(datetime) => datetime
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+                .into(),
+            None => (*millis).into(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_query::{QueryParams, QueryTemplates};
+    use crate::queries_repository::{PreparedQuery, QueryType};
+    use crate::query::Query;
+    use std::collections::BTreeMap;
+
+    fn query(id: i32) -> CompactQuery {
+        QueryTemplates::new(crate::scenario::Vendor::Neo4j, QueryParams::Parameterized)
+            .compact(PreparedQuery::new(
+                0,
+                "single_vertex_read".to_string(),
+                QueryType::Read,
+                Query {
+                    text: "MATCH (n:User {id: $id}) RETURN n".to_string(),
+                    params: BTreeMap::from([("id".to_string(), QueryParam::Integer(id))]),
+                },
+            ))
+            .unwrap()
+    }
+
+    #[test]
+    fn keeps_the_slowest_queries_over_the_threshold() {
+        assert_eq!(
+            "500ms".parse::<SlowQueryThreshold>().unwrap().0,
+            Duration::from_millis(500)
+        );
+
+        let log = SlowQueryLog::new(Duration::from_millis(500), 2);
+        let ms = Duration::from_millis;
+        log.record("0", &query(1), ms(100), SlowQueryOutcome::Success);
+        log.record("0", &query(2), ms(600), SlowQueryOutcome::Success);
+        log.record("1", &query(3), ms(900), SlowQueryOutcome::Timeout);
+        log.record("1", &query(4), ms(700), SlowQueryOutcome::Error);
+        log.record("0", &query(5), ms(550), SlowQueryOutcome::Success);
+
+        let report = log.report();
+        assert_eq!((report.total, report.recorded), (4, 2));
+
+        let jsonl = log.to_jsonl().unwrap();
+        let lines: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let mut ids: Vec<i64> = lines
+            .iter()
+            .map(|line| line["params"]["id"].as_i64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        assert_eq!(ids, [3, 4]);
+        assert_eq!(lines[0]["name"], "single_vertex_read");
+        assert!(lines.iter().any(|line| line["outcome"] == "timeout"));
+    }
+}
@@ -0,0 +1,341 @@
+//! `benchmark suite --file suite.yaml`: a matrix of runs in one invocation.
+//!
+//! The file is YAML when it ends in `.yaml` or `.yml` and TOML otherwise, with the same keys
+//! either way. It lists vendors, datasets, write ratios, parallelism levels and rates. The suite
+//! generates one queries file per dataset × write ratio, shared by every vendor so they all see
+//! the same workload, and runs each vendor × parallel × mps combination against it. Results go
+//! into one tree per combination, so each can be aggregated on its own:
+//!
+//! ```text
+//! <root>/queries/<dataset>-w<ratio>.jsonl
+//! <root>/runs/<dataset>/w<ratio>/p<parallel>-mps<mps>/<vendor>/{meta.json,...}
+//! <root>/summaries/<dataset>/w<ratio>/p<parallel>-mps<mps>/<vendor>_vs_falkordb.json
+//! <root>/suite.json
+//! ```
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use crate::scenario::{Size, Vendor};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Queries generated per dataset × write ratio unless `queries` is set.
+pub const DEFAULT_SUITE_QUERIES: usize = 100_000;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteEndpoints {
+    pub falkor: Option<String>,
+    pub neo4j: Option<String>,
+    pub memgraph: Option<String>,
+}
+
+impl SuiteEndpoints {
+    fn for_vendor(
+        &self,
+        vendor: Vendor,
+    ) -> Option<&String> {
+        match vendor {
+            Vendor::Falkor => self.falkor.as_ref(),
+            Vendor::Neo4j => self.neo4j.as_ref(),
+            Vendor::Memgraph => self.memgraph.as_ref(),
+        }
+    }
+}
+
+/// Parsed suite file. Unknown keys are rejected so a typo fails before hours of runs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteSpec {
+    /// Root of the results tree; `--results-dir` overrides it.
+    pub results_dir: Option<String>,
+    pub vendors: Vec<String>,
+    pub datasets: Vec<String>,
+    pub parallel: Vec<usize>,
+    pub mps: Vec<usize>,
+    #[serde(default = "default_write_ratios")]
+    pub write_ratio: Vec<f32>,
+    #[serde(default = "default_queries")]
+    pub queries: usize,
+    /// Run `load` for every vendor × dataset first.
+    #[serde(default)]
+    pub load: bool,
+    /// Extra `run` arguments for every run, e.g. `["--warm"]`.
+    #[serde(default)]
+    pub run_args: Vec<String>,
+    #[serde(default)]
+    pub endpoints: SuiteEndpoints,
+}
+
+fn default_write_ratios() -> Vec<f32> {
+    vec![0.0]
+}
+
+fn default_queries() -> usize {
+    DEFAULT_SUITE_QUERIES
+}
+
+/// One `benchmark` invocation of the suite.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SuiteStep {
+    pub label: String,
+    pub args: Vec<String>,
+}
+
+/// Steps in execution order, then the run directories to aggregate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuitePlan {
+    pub steps: Vec<SuiteStep>,
+    /// `(results_dir, out_dir)` pairs for the aggregator; empty unless FalkorDB (the baseline)
+    /// and at least one other vendor are in the suite.
+    pub aggregations: Vec<(String, String)>,
+}
+
+impl SuiteSpec {
+    pub fn from_toml(text: &str) -> BenchmarkResult<SuiteSpec> {
+        toml::from_str(text).map_err(|e| OtherError(format!("invalid suite file: {}", e)))
+    }
+
+    pub fn from_yaml(text: &str) -> BenchmarkResult<SuiteSpec> {
+        serde_norway::from_str(text).map_err(|e| OtherError(format!("invalid suite file: {}", e)))
+    }
+
+    /// Read `path` as YAML or TOML by its extension.
+    pub fn load(path: &str) -> BenchmarkResult<SuiteSpec> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| OtherError(format!("could not read suite file '{}': {}", path, e)))?;
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => SuiteSpec::from_yaml(&text),
+            _ => SuiteSpec::from_toml(&text),
+        }
+    }
+
+    fn vendors(&self) -> BenchmarkResult<Vec<Vendor>> {
+        parse_values(&self.vendors, "vendor")
+    }
+
+    fn datasets(&self) -> BenchmarkResult<Vec<Size>> {
+        parse_values(&self.datasets, "dataset")
+    }
+
+    /// Expand the matrix into steps under `root`.
+    pub fn plan(
+        &self,
+        root: &str,
+    ) -> BenchmarkResult<SuitePlan> {
+        let vendors = self.vendors()?;
+        let datasets = self.datasets()?;
+        for (name, empty) in [
+            ("vendors", vendors.is_empty()),
+            ("datasets", datasets.is_empty()),
+            ("parallel", self.parallel.is_empty()),
+            ("mps", self.mps.is_empty()),
+            ("write_ratio", self.write_ratio.is_empty()),
+        ] {
+            if empty {
+                return Err(OtherError(format!("suite needs at least one {}", name)));
+            }
+        }
+        if let Some(ratio) = self.write_ratio.iter().find(|r| !(0.0..=1.0).contains(*r)) {
+            return Err(OtherError(format!(
+                "suite write_ratio {} is outside 0.0 - 1.0",
+                ratio
+            )));
+        }
+
+        let root = Path::new(root);
+        let path = |p: &Path| p.to_string_lossy().to_string();
+        let aggregate = vendors.contains(&Vendor::Falkor) && vendors.len() > 1;
+        let mut steps = Vec::new();
+        let mut aggregations = Vec::new();
+
+        for &dataset in &datasets {
+            if self.load {
+                for &vendor in &vendors {
+                    let mut args = vec![
+                        "load".to_string(),
+                        format!("--vendor={}", vendor),
+                        format!("--size={}", dataset),
+                    ];
+                    self.push_endpoint(&mut args, vendor);
+                    steps.push(SuiteStep {
+                        label: format!("load {} {}", vendor, dataset),
+                        args,
+                    });
+                }
+            }
+            for &write_ratio in &self.write_ratio {
+                let ratio_dir = format!("w{}", write_ratio);
+                let queries_file = path(
+                    &root
+                        .join("queries")
+                        .join(format!("{}-{}.jsonl", dataset, ratio_dir)),
+                );
+                steps.push(SuiteStep {
+                    label: format!("generate-queries {} {}", dataset, ratio_dir),
+                    args: vec![
+                        "generate-queries".to_string(),
+                        "--vendor=falkor".to_string(),
+                        format!("--size={}", self.queries),
+                        format!("--dataset={}", dataset),
+                        format!("--name={}", queries_file),
+                        format!("--write-ratio={}", write_ratio),
+                    ],
+                });
+                for &parallel in &self.parallel {
+                    for &mps in &self.mps {
+                        let combination = Path::new(&dataset.to_string())
+                            .join(&ratio_dir)
+                            .join(format!("p{}-mps{}", parallel, mps));
+                        let results_dir = path(&root.join("runs").join(&combination));
+                        for &vendor in &vendors {
+                            let mut args = vec![
+                                "run".to_string(),
+                                format!("--vendor={}", vendor),
+                                format!("--name={}", queries_file),
+                                format!("--parallel={}", parallel),
+                                format!("--mps={}", mps),
+                                format!("--results-dir={}", results_dir),
+                            ];
+                            self.push_endpoint(&mut args, vendor);
+                            args.extend(self.run_args.iter().cloned());
+                            steps.push(SuiteStep {
+                                label: format!(
+                                    "run {} {} {} p{} mps{}",
+                                    vendor, dataset, ratio_dir, parallel, mps
+                                ),
+                                args,
+                            });
+                        }
+                        if aggregate {
+                            aggregations.push((
+                                results_dir,
+                                path(&root.join("summaries").join(&combination)),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(SuitePlan {
+            steps,
+            aggregations,
+        })
+    }
+
+    fn push_endpoint(
+        &self,
+        args: &mut Vec<String>,
+        vendor: Vendor,
+    ) {
+        if let Some(endpoint) = self.endpoints.for_vendor(vendor) {
+            args.push(format!("--endpoint={}", endpoint));
+        }
+    }
+}
+
+fn parse_values<T: ValueEnum>(
+    values: &[String],
+    what: &str,
+) -> BenchmarkResult<Vec<T>> {
+    values
+        .iter()
+        .map(|value| {
+            T::from_str(value, true)
+                .map_err(|_| OtherError(format!("unknown {} '{}' in suite", what, value)))
+        })
+        .collect()
+}
+
+/// Outcome of one step; the list is written to `<root>/suite.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SuiteStepResult {
+    #[serde(flatten)]
+    pub step: SuiteStep,
+    pub success: bool,
+    pub elapsed_ms: u128,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_matrix_into_shared_queries_and_per_combination_runs() {
+        let spec = SuiteSpec::from_toml(
+            r#"
+vendors = ["falkor", "neo4j"]
+datasets = ["small"]
+parallel = [10, 40]
+mps = [1000]
+write_ratio = [0.0, 0.1]
+queries = 5000
+run_args = ["--warm"]
+
+[endpoints]
+neo4j = "neo4j://db:7687"
+"#,
+        )
+        .unwrap();
+        let plan = spec.plan("suite").unwrap();
+
+        // Per write ratio: 1 generate + 2 parallel × 2 vendors.
+        assert_eq!(plan.steps.len(), 2 * (1 + 2 * 2));
+        assert_eq!(
+            plan.steps[0].args,
+            [
+                "generate-queries",
+                "--vendor=falkor",
+                "--size=5000",
+                "--dataset=small",
+                "--name=suite/queries/small-w0.jsonl",
+                "--write-ratio=0",
+            ]
+        );
+        assert_eq!(
+            plan.steps[2].args,
+            [
+                "run",
+                "--vendor=neo4j",
+                "--name=suite/queries/small-w0.jsonl",
+                "--parallel=10",
+                "--mps=1000",
+                "--results-dir=suite/runs/small/w0/p10-mps1000",
+                "--endpoint=neo4j://db:7687",
+                "--warm",
+            ]
+        );
+        assert_eq!(plan.aggregations.len(), 4);
+        assert_eq!(
+            plan.aggregations[3],
+            (
+                "suite/runs/small/w0.1/p40-mps1000".to_string(),
+                "suite/summaries/small/w0.1/p40-mps1000".to_string()
+            )
+        );
+
+        let typo = SuiteSpec::from_toml("vendors = [\"falkor\"]\ndatasets = [\"small\"]\nparallel = [1]\nmps = [1]\nwrite_ratios = [0.5]\n");
+        assert!(typo.is_err());
+
+        let yaml = SuiteSpec::from_yaml(
+            r#"
+vendors: [falkor, neo4j]
+datasets: [small]
+parallel: [10, 40]
+mps: [1000]
+write_ratio: [0.0, 0.1]
+queries: 5000
+run_args: ["--warm"]
+endpoints:
+  neo4j: neo4j://db:7687
+"#,
+        )
+        .unwrap();
+        assert_eq!(yaml.plan("suite").unwrap(), plan);
+        assert!(SuiteSpec::from_yaml("vendors: [falkor]\nwrite_ratios: [0.5]\n").is_err());
+        let mut unknown = spec.clone();
+        unknown.vendors.push("arangodb".to_string());
+        assert!(unknown.plan("suite").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! `run --sweep <max-mps> --duration 60s`: a latency-vs-throughput curve in one command.
+//!
+//! Each point runs the queries file at a fixed share of the maximum rate for `--duration`, in its
+//! own process, into `<results-dir>/<vendor>/sweep/mps-<n>/`. The curve goes to
+//! `<results-dir>/<vendor>/sweep.csv` and `sweep.json`. `--slo-p99` probes rates the same way
+//! (see [`crate::slo_search`]); both read a finished probe back with [`ProbeOutcome::read`].
+
+use crate::error::BenchmarkError::OtherError;
+use crate::error::BenchmarkResult;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Default `--sweep-points`, in percent of the maximum rate.
+pub const DEFAULT_SWEEP_POINTS: [f64; 6] = [10.0, 25.0, 50.0, 75.0, 90.0, 100.0];
+
+/// Which search a `run` is a probe of (the hidden `run --probe`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Probe {
+    Slo,
+    Sweep,
+}
+
+impl Probe {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Probe::Slo => "slo",
+            Probe::Sweep => "sweep",
+        }
+    }
+
+    /// `<vendor-dir>/<slo|sweep>/mps-<n>/`, the vendor directory being `<results-dir>/<vendor>`
+    /// in the default layout.
+    pub fn dir(
+        self,
+        vendor_dir: &Path,
+        mps: usize,
+    ) -> PathBuf {
+        vendor_dir.join(self.as_str()).join(format!("mps-{}", mps))
+    }
+}
+
+/// What a finished probe achieved, from its report.json and meta.json.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProbeOutcome {
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub p99_us: u64,
+    /// Successful queries per second over the measured phase.
+    pub achieved_qps: f64,
+    pub errors: u64,
+}
+
+impl ProbeOutcome {
+    pub fn read(dir: &Path) -> BenchmarkResult<Self> {
+        let read = |name: &str| -> BenchmarkResult<serde_json::Value> {
+            let path = dir.join(name);
+            let raw = std::fs::read_to_string(&path)
+                .map_err(|e| OtherError(format!("Failed reading {}: {}", path.display(), e)))?;
+            Ok(serde_json::from_str(&raw)?)
+        };
+        Ok(Self::from_results(
+            &read("report.json")?,
+            &read("meta.json")?,
+        ))
+    }
+
+    fn from_results(
+        report: &serde_json::Value,
+        meta: &serde_json::Value,
+    ) -> Self {
+        let latency = |field: &str| report["latency"][field].as_u64().unwrap_or(0);
+        let elapsed_ms = meta["elapsed_ms"].as_u64().unwrap_or(0);
+        ProbeOutcome {
+            p50_us: latency("p50_us"),
+            p95_us: latency("p95_us"),
+            p99_us: latency("p99_us"),
+            achieved_qps: if elapsed_ms > 0 {
+                latency("count") as f64 * 1000.0 / elapsed_ms as f64
+            } else {
+                0.0
+            },
+            errors: report["queries"].as_array().map_or(0, |queries| {
+                queries.iter().filter_map(|q| q["errors"].as_u64()).sum()
+            }),
+        }
+    }
+}
+
+/// The rates of `--sweep-points` (percent of `max_mps`), ascending and without duplicates.
+pub fn sweep_rates(
+    max_mps: usize,
+    percents: &[f64],
+) -> Vec<usize> {
+    let mut rates: Vec<usize> = percents
+        .iter()
+        .map(|pct| ((max_mps as f64 * pct / 100.0).round() as usize).max(1))
+        .collect();
+    rates.sort_unstable();
+    rates.dedup();
+    rates
+}
+
+/// One point of the curve.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SweepPoint {
+    pub target_mps: usize,
+    pub achieved_qps: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub errors: u64,
+    /// The probe process failed; the latencies are missing.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub failed: bool,
+}
+
+impl SweepPoint {
+    pub fn new(
+        target_mps: usize,
+        outcome: Option<ProbeOutcome>,
+    ) -> Self {
+        let measured = outcome.unwrap_or_default();
+        let ms = |us: u64| us as f64 / 1000.0;
+        SweepPoint {
+            target_mps,
+            achieved_qps: measured.achieved_qps,
+            p50_ms: ms(measured.p50_us),
+            p95_ms: ms(measured.p95_us),
+            p99_ms: ms(measured.p99_us),
+            errors: measured.errors,
+            failed: outcome.is_none(),
+        }
+    }
+}
+
+/// Written to `sweep.json`; [`SweepCurve::to_csv`] gives `sweep.csv`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SweepCurve {
+    pub vendor: String,
+    pub max_mps: usize,
+    pub duration_secs: u64,
+    pub points: Vec<SweepPoint>,
+}
+
+impl SweepCurve {
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("target_mps,achieved_qps,p50_ms,p95_ms,p99_ms,errors\n");
+        for point in self.points.iter().filter(|point| !point.failed) {
+            let _ = writeln!(
+                csv,
+                "{},{:.1},{:.3},{:.3},{:.3},{}",
+                point.target_mps,
+                point.achieved_qps,
+                point.p50_ms,
+                point.p95_ms,
+                point.p99_ms,
+                point.errors
+            );
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep_points_and_curve() {
+        assert_eq!(
+            sweep_rates(1_000, &DEFAULT_SWEEP_POINTS),
+            vec![100, 250, 500, 750, 900, 1_000]
+        );
+        assert_eq!(sweep_rates(4, &[10.0, 25.0, 100.0]), vec![1, 4]);
+
+        let outcome = ProbeOutcome::from_results(
+            &serde_json::json!({
+                "latency": {"count": 900, "p50_us": 1500, "p95_us": 4000, "p99_us": 9000},
+                "queries": [{"errors": 3}, {"errors": 2}]
+            }),
+            &serde_json::json!({"elapsed_ms": 10000}),
+        );
+        assert_eq!((outcome.p99_us, outcome.errors), (9_000, 5));
+        assert_eq!(outcome.achieved_qps, 90.0);
+
+        let curve = SweepCurve {
+            vendor: "falkor".to_string(),
+            max_mps: 1_000,
+            duration_secs: 10,
+            points: vec![
+                SweepPoint::new(100, Some(outcome)),
+                SweepPoint::new(1_000, None),
+            ],
+        };
+        assert_eq!(
+            curve.to_csv(),
+            "target_mps,achieved_qps,p50_ms,p95_ms,p99_ms,errors\n100,90.0,1.500,4.000,9.000,5\n"
+        );
+        assert_eq!(
+            Probe::Sweep.dir(Path::new("out/neo4j"), 250),
+            PathBuf::from("out/neo4j/sweep/mps-250")
+        );
+    }
+}
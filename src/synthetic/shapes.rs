@@ -36,7 +36,8 @@
 use crate::error::BenchmarkError::OtherError;
 use crate::error::BenchmarkResult;
 use crate::queries_repository::{
-    AlgorithmQuerySelection, Flavour, QueryCoverageProfile, QueryType, UsersQueriesRepository,
+    AccessPattern, AlgorithmQuerySelection, Flavour, QueryCoverageProfile, QueryType,
+    UsersQueriesRepository,
 };
 use crate::synthetic::catalog::CORPUS_SIZE;
 use crate::synthetic::recording::RecordedOp;
@@ -106,7 +107,7 @@ pub struct ShapeSpec {
     pub capability: Option<ShapeCapability>,
 }
 
-/// The curated annotation for the **46 baseline non-algorithm read shapes** (design §3.4).
+/// The curated annotation for the **49 baseline non-algorithm read shapes** (design §3.4).
 ///
 /// The op *set* is auto-discovered from [`queries_repository`] — the drift-guard test asserts this
 /// table's names are **exactly** [`UsersQueriesRepository::non_algorithm_read_names`] for the
@@ -186,6 +187,13 @@ pub fn baseline_read_shapes() -> Vec<ShapeSpec> {
             Full,
             NotApplicable("LIMIT without ORDER BY returns an unordered subset"),
         ),
+        s("shortest_path_bounded", Full, Gated),
+        s("var_len_friends_1_3", Full, Gated),
+        s(
+            "all_paths_with_limit",
+            Full,
+            NotApplicable("LIMIT without ORDER BY returns an unordered subset"),
+        ),
     ]
 }
 
@@ -304,7 +312,15 @@ fn read_shapes_repository(
     vertices: i32,
     edges: i32,
 ) -> UsersQueriesRepository {
-    UsersQueriesRepository::new(vertices, edges, Flavour::FalkorDB, no_algorithms(), profile)
+    UsersQueriesRepository::new(
+        vertices,
+        edges,
+        Flavour::FalkorDB,
+        no_algorithms(),
+        profile,
+        AccessPattern::default(),
+        false,
+    )
 }
 
 /// Render the selected `tier`'s repo read shapes into [`RecordedOp`]s, ready for
@@ -480,22 +496,22 @@ mod tests {
     }
 
     #[test]
-    fn there_are_forty_six_baseline_reads_with_a_nonempty_core_subset() {
+    fn there_are_forty_nine_baseline_reads_with_a_nonempty_core_subset() {
         let shapes = baseline_read_shapes();
-        assert_eq!(shapes.len(), 46, "expected the 46 baseline reads (design §3.4)");
+        assert_eq!(shapes.len(), 49, "expected the 49 baseline reads (design §3.4)");
         // Names are unique.
-        assert_eq!(annotated_names().len(), 46, "shape names must be unique");
+        assert_eq!(annotated_names().len(), 49, "shape names must be unique");
         let core = shapes.iter().filter(|s| s.tier == Tier::Core).count();
         assert!(core > 0 && core < shapes.len(), "core is a small non-empty subset, got {core}");
     }
 
     #[test]
-    fn repo_read_shapes_are_fifty_across_the_three_profiles() {
-        // Baseline (46) + ExtendedCore (1) + FixtureDependent (3) = 50 unique reads across profiles.
+    fn repo_read_shapes_are_fifty_three_across_the_three_profiles() {
+        // Baseline (49) + ExtendedCore (1) + FixtureDependent (3) = 53 unique reads across profiles.
         let shapes = repo_read_shapes();
-        assert_eq!(shapes.len(), 50, "46 baseline + 1 extended-core + 3 fixture-dependent reads");
+        assert_eq!(shapes.len(), 53, "49 baseline + 1 extended-core + 3 fixture-dependent reads");
         let names: BTreeSet<&str> = shapes.iter().map(|s| s.name).collect();
-        assert_eq!(names.len(), 50, "shape names must be unique across profiles");
+        assert_eq!(names.len(), 53, "shape names must be unique across profiles");
         assert_eq!(
             shapes.iter().filter(|s| s.profile == QueryCoverageProfile::ExtendedCore).count(),
             1,
@@ -528,8 +544,8 @@ mod tests {
 
     #[test]
     fn only_the_top_k_and_limit_shapes_are_result_na() {
-        // The result-N/A reads are exactly `entity_path_introspection` (LIMIT without ORDER BY) and
-        // the three fulltext/vector top-k reads; every other read is result-gated (Decision 4).
+        // The result-N/A reads are exactly the two LIMIT-without-ORDER-BY shapes and the three
+        // fulltext/vector top-k reads; every other read is result-gated (Decision 4).
         let na: BTreeSet<&str> = repo_read_shapes()
             .iter()
             .filter(|s| !s.result_policy.is_gated())
@@ -539,6 +555,7 @@ mod tests {
             na,
             BTreeSet::from([
                 "entity_path_introspection",
+                "all_paths_with_limit",
                 "vector_query_nodes_smoke",
                 "fulltext_query_nodes_smoke",
                 "fulltext_query_relationships_smoke",
@@ -571,7 +588,7 @@ mod tests {
         // The ExtendedCore shape is recorded and result-gated…
         let ts = ops.iter().find(|o| o.key.name() == "temporal_spatial_roundtrip").unwrap();
         assert!(ts.result_gated, "temporal_spatial_roundtrip is result-gated");
-        // …and the result-N/A reads are recorded but not gated: the LIMIT-without-ORDER shape plus
+        // …and the result-N/A reads are recorded but not gated: the LIMIT-without-ORDER shapes plus
         // the three fulltext/vector top-k reads.
         let na: BTreeSet<&str> =
             ops.iter().filter(|o| !o.result_gated).map(|o| o.key.name()).collect();
@@ -579,6 +596,7 @@ mod tests {
             na,
             BTreeSet::from([
                 "entity_path_introspection",
+                "all_paths_with_limit",
                 "vector_query_nodes_smoke",
                 "fulltext_query_nodes_smoke",
                 "fulltext_query_relationships_smoke",
@@ -0,0 +1,220 @@
+use crate::chaos::{ChaosEvent, RecoveryTracker};
+use crate::THROUGHPUT_QPS;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Sliding windows exported as `throughput_qps{window=...}`, in whole seconds.
+pub const THROUGHPUT_WINDOWS_SECS: [u64; 2] = [1, 10];
+
+/// Completions observed during one wall-clock second of the measured phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SecondSample {
+    /// Successful and failed queries alike.
+    pub completed: u64,
+    pub errors: u64,
+}
+
+/// Per-second completion counts for a run, kept in-process so achieved throughput is visible
+/// both live (as gauges) and afterwards (as `throughput.csv`), independent of Prometheus `rate()`.
+pub struct ThroughputTracker {
+    vendor: String,
+    start: Instant,
+    seconds: Mutex<Vec<SecondSample>>,
+    recovery: Option<RecoveryTracker>,
+}
+
+impl ThroughputTracker {
+    pub fn new(vendor: impl ToString) -> Self {
+        let vendor = vendor.to_string();
+        // Avoid stale values when multiple runs happen in a single process.
+        for window in THROUGHPUT_WINDOWS_SECS {
+            THROUGHPUT_QPS
+                .with_label_values(&[vendor.as_str(), window_label(window).as_str()])
+                .set(0.0);
+        }
+        Self {
+            vendor,
+            start: Instant::now(),
+            seconds: Mutex::new(Vec::new()),
+            recovery: None,
+        }
+    }
+
+    /// Also match completions against `--chaos` failures (see [`RecoveryTracker`]).
+    pub fn with_recovery_tracking(
+        mut self,
+        enabled: bool,
+    ) -> Self {
+        self.recovery = enabled.then(RecoveryTracker::default);
+        self
+    }
+
+    pub fn mark_failure_injected(&self) {
+        if let Some(recovery) = &self.recovery {
+            recovery.mark_failure_injected(self.start.elapsed());
+        }
+    }
+
+    pub fn chaos_events(&self) -> Vec<ChaosEvent> {
+        self.recovery
+            .as_ref()
+            .map(RecoveryTracker::events)
+            .unwrap_or_default()
+    }
+
+    pub fn record(
+        &self,
+        success: bool,
+    ) {
+        let elapsed = self.start.elapsed();
+        self.record_at(elapsed, success);
+        if let Some(recovery) = &self.recovery {
+            recovery.record(elapsed, success);
+        }
+    }
+
+    fn record_at(
+        &self,
+        elapsed: Duration,
+        success: bool,
+    ) {
+        let second = elapsed.as_secs() as usize;
+        let Ok(mut seconds) = self.seconds.lock() else {
+            return;
+        };
+        if seconds.len() <= second {
+            seconds.resize(second + 1, SecondSample::default());
+        }
+        seconds[second].completed += 1;
+        if !success {
+            seconds[second].errors += 1;
+        }
+    }
+
+    /// Completions per second over the last `window` full seconds before `elapsed`; the
+    /// in-progress second is left out so the rate doesn't dip at every tick.
+    fn rate_at(
+        &self,
+        elapsed: Duration,
+        window: u64,
+    ) -> f64 {
+        let now = elapsed.as_secs() as usize;
+        let window = (window as usize).min(now);
+        if window == 0 {
+            return 0.0;
+        }
+        let Ok(seconds) = self.seconds.lock() else {
+            return 0.0;
+        };
+        let completed: u64 = (now - window..now)
+            .filter_map(|second| seconds.get(second))
+            .map(|sample| sample.completed)
+            .sum();
+        completed as f64 / window as f64
+    }
+
+    /// Completions per second over the last `window` full seconds.
+    pub fn qps(
+        &self,
+        window: u64,
+    ) -> f64 {
+        self.rate_at(self.start.elapsed(), window)
+    }
+
+    pub fn export_to_prometheus(&self) {
+        let elapsed = self.start.elapsed();
+        for window in THROUGHPUT_WINDOWS_SECS {
+            THROUGHPUT_QPS
+                .with_label_values(&[self.vendor.as_str(), window_label(window).as_str()])
+                .set(self.rate_at(elapsed, window));
+        }
+    }
+
+    /// Time since the measured phase started.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    pub fn samples(&self) -> Vec<SecondSample> {
+        self.seconds
+            .lock()
+            .map(|seconds| seconds.clone())
+            .unwrap_or_default()
+    }
+
+    /// One row per second since the measured phase started: `second,completed,errors`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("second,completed,errors\n");
+        for (second, sample) in self.samples().iter().enumerate() {
+            let _ = writeln!(csv, "{},{},{}", second, sample.completed, sample.errors);
+        }
+        csv
+    }
+
+    /// Refresh the gauges every second until [`ThroughputReporter::stop`].
+    pub fn spawn_reporter(self: &Arc<Self>) -> ThroughputReporter {
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel();
+        let tracker = Arc::clone(self);
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => tracker.export_to_prometheus(),
+                    _ = &mut shutdown_rx => {
+                        tracker.export_to_prometheus();
+                        return;
+                    }
+                }
+            }
+        });
+        ThroughputReporter {
+            handle,
+            shutdown_tx,
+        }
+    }
+}
+
+pub struct ThroughputReporter {
+    handle: JoinHandle<()>,
+    shutdown_tx: tokio::sync::oneshot::Sender<()>,
+}
+
+impl ThroughputReporter {
+    pub async fn stop(self) {
+        drop(self.shutdown_tx);
+        let _ = self.handle.await;
+    }
+}
+
+fn window_label(window: u64) -> String {
+    format!("{}s", window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sliding_windows_and_csv_use_full_seconds() {
+        let tracker = ThroughputTracker::new("test");
+        for ms in [100, 200, 900, 1_500, 2_100, 2_200, 2_300, 2_400] {
+            tracker.record_at(Duration::from_millis(ms), true);
+        }
+        tracker.record_at(Duration::from_millis(2_500), false);
+
+        // Nothing has fully elapsed yet.
+        assert_eq!(tracker.rate_at(Duration::from_millis(999), 1), 0.0);
+        // Second 2 (5 completions) is the last full second at 3.2s.
+        assert_eq!(tracker.rate_at(Duration::from_millis(3_200), 1), 5.0);
+        // The 10s window only spans the 3 seconds that exist so far: (3 + 1 + 5) / 3.
+        assert_eq!(tracker.rate_at(Duration::from_millis(3_200), 10), 3.0);
+
+        assert_eq!(
+            tracker.to_csv(),
+            "second,completed,errors\n0,3,0\n1,1,0\n2,5,1\n"
+        );
+    }
+}
@@ -2,9 +2,8 @@ use crate::error::BenchmarkError::{
     FailedToDownloadFileError, FailedToSpawnProcessError, OtherError, ProcessNofFoundError,
 };
 use crate::error::{BenchmarkError, BenchmarkResult};
+use crate::os_process;
 use futures::stream::Stream;
-use nix::sys::signal::{kill, Signal};
-use nix::unistd::Pid;
 use std::env;
 use std::path::Path;
 use std::process::Output;
@@ -12,12 +11,13 @@ use std::str;
 use std::time::Duration;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::AsyncBufReadExt;
 use tokio::process::Command;
 use tokio::time::sleep;
 use tokio::{fs, io};
 use tokio_stream::StreamExt;
 use tracing::{error, info, trace};
+use url::Url;
 
 pub async fn spawn_command(
     command: &str,
@@ -58,6 +58,13 @@ pub fn falkor_shared_lib_path() -> BenchmarkResult<String> {
         Err(OtherError("Failed to get current directory".to_string()))
     }
 }
+/// `FALKOR_PATH`, else `falkordb.so` in the working directory.
+pub fn falkor_module_path() -> BenchmarkResult<String> {
+    match env::var("FALKOR_PATH") {
+        Ok(path) => Ok(path),
+        Err(_) => falkor_shared_lib_path(),
+    }
+}
 pub fn falkor_logs_path() -> BenchmarkResult<String> {
     if let Ok(path) = env::current_dir() {
         Ok(format!("{}/falkordb.log", path.display()))
@@ -122,11 +129,8 @@ pub async fn read_lines<P>(
 where
     P: AsRef<Path>,
 {
-    // Open the file asynchronously
-    let file = File::open(filename).await?;
-
-    // Create a buffered reader
-    let reader = BufReader::new(file);
+    // Open the file asynchronously, decompressing .gz and .zst files
+    let reader = crate::compression::open(filename).await?;
 
     let stream = tokio_stream::wrappers::LinesStream::new(reader.lines()).filter_map(|res| {
         match res {
@@ -147,56 +151,13 @@ where
 }
 
 pub async fn kill_process(pid: u32) -> BenchmarkResult<()> {
-    let pid = Pid::from_raw(pid as i32);
-    match kill(pid, Signal::SIGKILL) {
-        Ok(_) => Ok(()),
-        Err(nix::Error::ESRCH) => Err(OtherError(format!("No process with pid {} found", pid))),
-        Err(e) => Err(OtherError(format!("Failed to kill process {}: {}", pid, e))),
-    }
+    os_process::kill(pid)
 }
 
+/// The PID of the running process whose executable is exactly `cmd`, e.g. `redis-server`.
 pub async fn get_command_pid(cmd: impl AsRef<str>) -> BenchmarkResult<u32> {
     let cmd = cmd.as_ref();
-    let output = Command::new("ps")
-        .args(["-eo", "pid,command,stat"])
-        .output()
-        .await
-        .map_err(BenchmarkError::IoError)?;
-
-    if output.status.success() {
-        let stdout = str::from_utf8(&output.stdout)
-            .map_err(|e| OtherError(format!("UTF-8 conversion error: {}", e)))?;
-
-        for (index, line) in stdout.lines().enumerate() {
-            if index == 0 || line.contains("grep") {
-                continue;
-            }
-            if line.contains(cmd) {
-                if let [pid, command, stat, ..] =
-                    line.split_whitespace().collect::<Vec<_>>().as_slice()
-                {
-                    if command.contains(cmd) {
-                        if stat.starts_with("Z") || stat.contains("<defunct>") {
-                            continue;
-                        }
-                        return pid
-                            .parse::<u32>()
-                            .map_err(|e| OtherError(format!("Failed to parse PID: {}", e)));
-                    }
-                }
-            }
-        }
-        Err(ProcessNofFoundError(cmd.to_string()))
-    } else {
-        error!(
-            "ps command failed with exit code: {:?}",
-            output.status.code()
-        );
-        Err(OtherError(format!(
-            "ps command failed with exit code: {:?}",
-            output.status.code()
-        )))
-    }
+    os_process::find_pid(cmd).ok_or_else(|| ProcessNofFoundError(cmd.to_string()))
 }
 
 pub async fn ping_redis() -> BenchmarkResult<()> {
@@ -456,3 +417,14 @@ where
     );
     Ok(total_processed)
 }
+
+/// Endpoint URL with the password stripped, for logs and results.
+pub fn redact_endpoint(endpoint: &str) -> String {
+    // Best-effort: if this isn't a valid URL, just return a placeholder.
+    if let Ok(mut url) = Url::parse(endpoint) {
+        // Strip password if present; keep username to help identify which creds are being used.
+        let _ = url.set_password(None);
+        return url.to_string();
+    }
+    "<invalid-endpoint>".to_string()
+}